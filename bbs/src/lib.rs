@@ -0,0 +1,176 @@
+//! BBS+ signatures over BLS12-381: one short signature on a list of
+//! messages, whose holder can later show any of the messages to a verifier
+//! along with a zero-knowledge proof that they are signed, without showing
+//! the others or the signature. The building block of anonymous
+//! credentials, the messages being the attributes of a credential.
+//!
+//! The key is `x` with the public key `W = x * G2`, and the messages
+//! `m_1, ..., m_L` are scalars, see [`message`] to hash bytes to one. With
+//! the generators `H_0, H_1, ..., H_L` of G1 hashed to the curve, and
+//! random e and s, the signature is `(A, e, s)` for
+//!
+//! B = G1 + s * H_0 + ∑ m_i * H_i
+//! A = B / (x + e)
+//!
+//! and it is checked with
+//!
+//! e(A, W + e * G2) == e(B, G2)
+//!
+//! See [`proof`] for the selective disclosure proofs.
+pub mod proof;
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use bls_shamir::secret::SecretKey;
+use group::ff::Field;
+use group::Curve;
+use pairing::pairing_product_is_one;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use std::fmt;
+
+const DST: &[u8] = b"ZK_LAB_BBS_XMD:SHA-256_SSWU_RO_";
+const MESSAGE_TAG: &[u8] = b"zk-lab/bbs/message";
+
+/// `H_0` for the blinding s, and `H_i` for the message `m_i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generators {
+    pub blinding: G1Projective,
+    pub messages: Vec<G1Projective>,
+}
+
+/// `(A, e, s)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub a: G1Affine,
+    pub e: Scalar,
+    pub s: Scalar,
+}
+
+impl Generators {
+    /// The generators for signatures on `count` messages. The first ones
+    /// don't depend on the count, so a key signs lists of any length.
+    pub fn new(count: usize) -> Self {
+        let hash = |label: &[u8]| {
+            <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(label, DST)
+        };
+        Generators {
+            blinding: hash(b"H_0"),
+            messages: (1..=count)
+                .map(|i| hash(format!("H_{}", i).as_bytes()))
+                .collect(),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// `B = G1 + s * H_0 + ∑ m_i * H_i`
+    fn commit(&self, messages: &[Scalar], s: Scalar) -> Result<G1Projective, BbsError> {
+        if messages.len() != self.count() {
+            return Err(BbsError::Messages {
+                expected: self.count(),
+                got: messages.len(),
+            });
+        }
+        Ok(G1Projective::generator()
+            + self.blinding * s
+            + self
+                .messages
+                .iter()
+                .zip(messages)
+                .map(|(h, m)| h * m)
+                .sum::<G1Projective>())
+    }
+}
+
+/// Hashes bytes to a message.
+pub fn message(bytes: &[u8]) -> Scalar {
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&Sha512::new().chain(MESSAGE_TAG).chain(bytes).finalize());
+    Scalar::from_bytes_wide(&wide)
+}
+
+/// `W = x * G2`
+pub fn public_key(sk: &SecretKey) -> G2Affine {
+    (G2Affine::generator() * sk.as_scalar()).to_affine()
+}
+
+/// Signs the messages, one for each of the generators.
+pub fn sign<R: RngCore + CryptoRng>(
+    sk: &SecretKey,
+    generators: &Generators,
+    messages: &[Scalar],
+    rng: &mut R,
+) -> Result<Signature, BbsError> {
+    let s = Scalar::random(&mut *rng);
+    let b = generators.commit(messages, s)?;
+    // x + e is only zero with a negligible probability, draw again if so.
+    loop {
+        let e = Scalar::random(&mut *rng);
+        if let Some(inverse) = Option::<Scalar>::from((sk.as_scalar() + e).invert()) {
+            return Ok(Signature {
+                a: (b * inverse).to_affine(),
+                e,
+                s,
+            });
+        }
+    }
+}
+
+/// Checks the signature on the messages.
+pub fn verify(
+    pk: &G2Affine,
+    generators: &Generators,
+    messages: &[Scalar],
+    signature: &Signature,
+) -> Result<(), BbsError> {
+    let b = generators.commit(messages, signature.s)?;
+    // e(A, W + e * G2) * e(-B, G2) == 1
+    let shifted = (pk + G2Projective::generator() * signature.e).to_affine();
+    let valid = bool::from(!signature.a.is_identity())
+        && pairing_product_is_one(&[
+            (signature.a, shifted),
+            (-b.to_affine(), G2Affine::generator()),
+        ]);
+    if !valid {
+        return Err(BbsError::Invalid);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BbsError {
+    /// Not as many messages as there are generators.
+    Messages {
+        expected: usize,
+        got: usize,
+    },
+    /// A disclosed message that isn't in the list.
+    Index {
+        index: usize,
+        count: usize,
+    },
+    Invalid,
+    Proof,
+}
+
+impl fmt::Display for BbsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BbsError::Messages { expected, got } => {
+                write!(f, "expected {} messages, got {}", expected, got)
+            }
+            BbsError::Index { index, count } => write!(
+                f,
+                "message {} doesn't exist, there are {} messages",
+                index, count
+            ),
+            BbsError::Invalid => write!(f, "invalid signature"),
+            BbsError::Proof => write!(f, "invalid proof"),
+        }
+    }
+}
+
+impl std::error::Error for BbsError {}
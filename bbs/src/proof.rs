@@ -0,0 +1,225 @@
+//! Selective disclosure: a proof of knowledge of a signature on messages
+//! of which only some are disclosed, following Camenisch, Drijvers and
+//! Lehmann.
+//!
+//! Showing `(A, e, s)` itself would link every presentation of the same
+//! signature, so the prover randomizes it with random `r1` and `r2`, and
+//! `r3 = 1 / r1`:
+//!
+//! A' = r1 * A
+//! Ā  = r1 * B - e * A'          (= x * A')
+//! D  = r1 * B - r2 * H_0
+//! s' = s - r2 * r3
+//!
+//! The verifier checks `e(A', W) == e(Ā, G2)`, that Ā is A' times the key,
+//! and the prover shows with a sigma protocol that it knows the exponents
+//! of the two equations
+//!
+//! Ā - D = -e * A' + r2 * H_0
+//! G1 + ∑_disclosed m_i * H_i = r3 * D - s' * H_0 - ∑_hidden m_j * H_j
+//!
+//! the second of which only holds for D built from a B on the disclosed
+//! messages. A' is uniformly random and the rest is determined by it and
+//! the zero-knowledge responses, so presentations can't be linked. The
+//! challenge binds the proof to a header, for the verifier's nonce.
+use crate::{BbsError, Generators, Signature};
+use bls12_381::{G1Affine, G1Projective, G2Affine, Scalar};
+use group::ff::Field;
+use pairing::pairing_product_is_one;
+use rand_core::{CryptoRng, RngCore};
+use sigma::transcript::Transcript;
+use std::collections::BTreeMap;
+
+const DOMAIN: &[u8] = b"zk-lab/bbs/proof";
+
+/// A presentation of a signature, with the disclosed messages given
+/// separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub a_prime: G1Affine,
+    pub a_bar: G1Affine,
+    pub d: G1Affine,
+    pub challenge: Scalar,
+    /// For `-e` and `r2`.
+    pub signature_responses: [Scalar; 2],
+    /// For `r3` and `-s'`.
+    pub blinding_responses: [Scalar; 2],
+    /// For the `-m_j` of the hidden messages, in order.
+    pub message_responses: Vec<Scalar>,
+}
+
+/// Proves knowledge of a signature on the messages, disclosing the ones at
+/// the indices of `disclosed`.
+pub fn prove<R: RngCore + CryptoRng>(
+    pk: &G2Affine,
+    generators: &Generators,
+    signature: &Signature,
+    messages: &[Scalar],
+    disclosed: &[usize],
+    header: &[u8],
+    rng: &mut R,
+) -> Result<Proof, BbsError> {
+    let b = generators.commit(messages, signature.s)?;
+    let mut revealed = BTreeMap::new();
+    for i in disclosed {
+        let m = messages.get(*i).ok_or(BbsError::Index {
+            index: *i,
+            count: messages.len(),
+        })?;
+        revealed.insert(*i, *m);
+    }
+    let hidden = (0..messages.len())
+        .filter(|i| !revealed.contains_key(i))
+        .collect::<Vec<_>>();
+
+    let r1 = loop {
+        let r1 = Scalar::random(&mut *rng);
+        if r1 != Scalar::zero() {
+            break r1;
+        }
+    };
+    let r2 = Scalar::random(&mut *rng);
+    let r3 = r1.invert().unwrap();
+    let a_prime = signature.a * r1;
+    let a_bar = b * r1 - a_prime * signature.e;
+    let d = b * r1 - generators.blinding * r2;
+    let s_prime = signature.s - r2 * r3;
+
+    let mut random = || Scalar::random(&mut *rng);
+    let signature_nonces = [random(), random()];
+    let blinding_nonces = [random(), random()];
+    let message_nonces = hidden.iter().map(|_| random()).collect::<Vec<_>>();
+    let t1 = a_prime * signature_nonces[0] + generators.blinding * signature_nonces[1];
+    let t2 = d * blinding_nonces[0]
+        + generators.blinding * blinding_nonces[1]
+        + hidden
+            .iter()
+            .zip(&message_nonces)
+            .map(|(j, k)| generators.messages[*j] * k)
+            .sum::<G1Projective>();
+
+    let [a_prime, a_bar, d] = affine([a_prime, a_bar, d]);
+    let challenge = challenge(
+        pk,
+        generators,
+        &revealed,
+        header,
+        [a_prime, a_bar, d],
+        affine([t1, t2]),
+    );
+    let respond = |k: Scalar, secret: Scalar| k + challenge * secret;
+    Ok(Proof {
+        a_prime,
+        a_bar,
+        d,
+        challenge,
+        signature_responses: [
+            respond(signature_nonces[0], -signature.e),
+            respond(signature_nonces[1], r2),
+        ],
+        blinding_responses: [
+            respond(blinding_nonces[0], r3),
+            respond(blinding_nonces[1], -s_prime),
+        ],
+        message_responses: hidden
+            .iter()
+            .zip(&message_nonces)
+            .map(|(j, k)| respond(*k, -messages[*j]))
+            .collect(),
+    })
+}
+
+/// Checks a proof of knowledge of a signature on messages including the
+/// `disclosed` ones, given with their indices.
+pub fn verify(
+    pk: &G2Affine,
+    generators: &Generators,
+    disclosed: &[(usize, Scalar)],
+    header: &[u8],
+    proof: &Proof,
+) -> Result<(), BbsError> {
+    let count = generators.count();
+    if let Some((index, _)) = disclosed.iter().find(|(i, _)| *i >= count) {
+        return Err(BbsError::Index {
+            index: *index,
+            count,
+        });
+    }
+    let disclosed = disclosed.iter().copied().collect::<BTreeMap<_, _>>();
+    let hidden = (0..count)
+        .filter(|i| !disclosed.contains_key(i))
+        .collect::<Vec<_>>();
+    if proof.message_responses.len() != hidden.len() || bool::from(proof.a_prime.is_identity()) {
+        return Err(BbsError::Proof);
+    }
+
+    let c = proof.challenge;
+    let [z_e, z_r2] = proof.signature_responses;
+    let [z_r3, z_s] = proof.blinding_responses;
+    let (a_prime, a_bar, d) = (
+        G1Projective::from(proof.a_prime),
+        G1Projective::from(proof.a_bar),
+        G1Projective::from(proof.d),
+    );
+    let t1 = a_prime * z_e + generators.blinding * z_r2 - (a_bar - d) * c;
+    let revealed = G1Projective::generator()
+        + disclosed
+            .iter()
+            .map(|(i, m)| generators.messages[*i] * m)
+            .sum::<G1Projective>();
+    let t2 = d * z_r3
+        + generators.blinding * z_s
+        + hidden
+            .iter()
+            .zip(&proof.message_responses)
+            .map(|(j, z)| generators.messages[*j] * z)
+            .sum::<G1Projective>()
+        - revealed * c;
+
+    let expected = challenge(
+        pk,
+        generators,
+        &disclosed,
+        header,
+        [proof.a_prime, proof.a_bar, proof.d],
+        affine([t1, t2]),
+    );
+    // e(A', W) * e(-Ā, G2) == 1
+    let valid = expected == c
+        && pairing_product_is_one(&[(proof.a_prime, *pk), (-proof.a_bar, G2Affine::generator())]);
+    if !valid {
+        return Err(BbsError::Proof);
+    }
+    Ok(())
+}
+
+fn challenge(
+    pk: &G2Affine,
+    generators: &Generators,
+    disclosed: &BTreeMap<usize, Scalar>,
+    header: &[u8],
+    points: [G1Affine; 3],
+    commitments: [G1Affine; 2],
+) -> Scalar {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_point(b"public key", pk);
+    transcript.append_u64(b"messages", generators.count() as u64);
+    transcript.append_message(b"header", header);
+    for (i, m) in disclosed {
+        transcript.append_u64(b"disclosed index", *i as u64);
+        transcript.append_scalar(b"disclosed message", m);
+    }
+    for point in &points {
+        transcript.append_point(b"point", point);
+    }
+    for commitment in &commitments {
+        transcript.append_point(b"commitment", commitment);
+    }
+    transcript.challenge_scalar(b"challenge")
+}
+
+fn affine<const N: usize>(points: [G1Projective; N]) -> [G1Affine; N] {
+    let mut affine = [G1Affine::identity(); N];
+    G1Projective::batch_normalize(&points, &mut affine);
+    affine
+}
@@ -0,0 +1,166 @@
+use bbs::proof;
+use bbs::{message, public_key, sign, verify, BbsError, Generators};
+use bls12_381::Scalar;
+use bls_shamir::secret::SecretKey;
+
+fn attributes() -> Vec<Scalar> {
+    ["alice", "1990-01-01", "NL", "driver's license B"]
+        .iter()
+        .map(|attribute| message(attribute.as_bytes()))
+        .collect()
+}
+
+#[test]
+fn signs_lists_of_messages() {
+    let mut rng = rand::thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = public_key(&sk);
+    let generators = Generators::new(4);
+    let messages = attributes();
+
+    let signature = sign(&sk, &generators, &messages, &mut rng).unwrap();
+    assert_eq!(verify(&pk, &generators, &messages, &signature), Ok(()));
+
+    let mut changed = messages.clone();
+    changed[2] = message(b"BE");
+    assert_eq!(
+        verify(&pk, &generators, &changed, &signature),
+        Err(BbsError::Invalid)
+    );
+    let other = public_key(&SecretKey::random(&mut rng));
+    assert_eq!(
+        verify(&other, &generators, &messages, &signature),
+        Err(BbsError::Invalid)
+    );
+    assert_eq!(
+        verify(&pk, &generators, &messages[..3], &signature),
+        Err(BbsError::Messages {
+            expected: 4,
+            got: 3
+        })
+    );
+    assert_eq!(
+        sign(&sk, &Generators::new(5), &messages, &mut rng),
+        Err(BbsError::Messages {
+            expected: 5,
+            got: 4
+        })
+    );
+}
+
+#[test]
+fn discloses_only_chosen_messages() {
+    let mut rng = rand::thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = public_key(&sk);
+    let generators = Generators::new(4);
+    let messages = attributes();
+    let signature = sign(&sk, &generators, &messages, &mut rng).unwrap();
+
+    for disclosed in [vec![], vec![2], vec![0, 3], vec![0, 1, 2, 3]] {
+        let proof = proof::prove(
+            &pk,
+            &generators,
+            &signature,
+            &messages,
+            &disclosed,
+            b"nonce",
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(proof.message_responses.len(), 4 - disclosed.len());
+        let revealed = disclosed
+            .iter()
+            .map(|i| (*i, messages[*i]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            proof::verify(&pk, &generators, &revealed, b"nonce", &proof),
+            Ok(())
+        );
+    }
+}
+
+#[test]
+fn presentations_are_unlinkable() {
+    let mut rng = rand::thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = public_key(&sk);
+    let generators = Generators::new(4);
+    let messages = attributes();
+    let signature = sign(&sk, &generators, &messages, &mut rng).unwrap();
+
+    let first = proof::prove(&pk, &generators, &signature, &messages, &[2], b"", &mut rng).unwrap();
+    let second =
+        proof::prove(&pk, &generators, &signature, &messages, &[2], b"", &mut rng).unwrap();
+    assert_ne!(first.a_prime, second.a_prime);
+    assert_ne!(first.a_prime, signature.a);
+}
+
+#[test]
+fn wrong_presentations_are_rejected() {
+    let mut rng = rand::thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = public_key(&sk);
+    let generators = Generators::new(4);
+    let messages = attributes();
+    let signature = sign(&sk, &generators, &messages, &mut rng).unwrap();
+    let proof = proof::prove(
+        &pk,
+        &generators,
+        &signature,
+        &messages,
+        &[2],
+        b"nonce",
+        &mut rng,
+    )
+    .unwrap();
+    let revealed = [(2, messages[2])];
+
+    // Another disclosed value, index, header or key.
+    assert_eq!(
+        proof::verify(&pk, &generators, &[(2, message(b"BE"))], b"nonce", &proof),
+        Err(BbsError::Proof)
+    );
+    assert_eq!(
+        proof::verify(&pk, &generators, &[(1, messages[2])], b"nonce", &proof),
+        Err(BbsError::Proof)
+    );
+    assert_eq!(
+        proof::verify(&pk, &generators, &revealed, b"other nonce", &proof),
+        Err(BbsError::Proof)
+    );
+    let other = public_key(&SecretKey::random(&mut rng));
+    assert_eq!(
+        proof::verify(&other, &generators, &revealed, b"nonce", &proof),
+        Err(BbsError::Proof)
+    );
+    assert_eq!(
+        proof::verify(&pk, &generators, &[(4, messages[2])], b"nonce", &proof),
+        Err(BbsError::Index { index: 4, count: 4 })
+    );
+
+    let mut tampered = proof.clone();
+    tampered.message_responses[0] += Scalar::one();
+    assert_eq!(
+        proof::verify(&pk, &generators, &revealed, b"nonce", &tampered),
+        Err(BbsError::Proof)
+    );
+
+    // A presentation of a signature that doesn't hold.
+    let mut forged = signature;
+    forged.s += Scalar::one();
+    let proof = proof::prove(
+        &pk,
+        &generators,
+        &forged,
+        &messages,
+        &[2],
+        b"nonce",
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(
+        proof::verify(&pk, &generators, &revealed, b"nonce", &proof),
+        Err(BbsError::Proof)
+    );
+}
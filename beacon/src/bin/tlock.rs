@@ -0,0 +1,95 @@
+//! Encrypts messages to a future round of an unchained beacon, and decrypts
+//! them with the beacons a node stored once the round is there:
+//!
+//! ```text
+//! tlock encrypt --public-key <hex> --round 1200 < message > locked
+//! tlock decrypt --public-key <hex> --chain beacon-lab-1.chain < locked
+//! ```
+use beacon::tlock::{self, Timelocked};
+use beacon::{Chain, Scheme};
+use bls12_381::G1Affine;
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "tlock",
+    about = "Timelock encryption to the rounds of an unchained beacon"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Encrypts stdin to a round, writing the timelocked message to stdout.
+    Encrypt {
+        /// Hex of the group's public key, as in its group file.
+        #[arg(long, value_name = "HEX", value_parser = parse_public_key)]
+        public_key: G1Affine,
+
+        /// The round the message can be decrypted from.
+        #[arg(long)]
+        round: u64,
+    },
+    /// Decrypts the timelocked message on stdin with its round's beacon,
+    /// writing the message to stdout.
+    Decrypt {
+        /// Hex of the group's public key, as in its group file.
+        #[arg(long, value_name = "HEX", value_parser = parse_public_key)]
+        public_key: G1Affine,
+
+        /// File the beacons are kept in by `p2p beacon --unchained`.
+        #[arg(long, value_name = "PATH")]
+        chain: PathBuf,
+    },
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("tlock: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+
+    match cli.command {
+        Command::Encrypt { public_key, round } => {
+            let timelocked = tlock::encrypt(&public_key, round, &input, &mut rand::rngs::OsRng);
+            println!("{}", timelocked);
+        }
+        Command::Decrypt { public_key, chain } => {
+            let timelocked: Timelocked = String::from_utf8(input)?.parse()?;
+            if !chain.exists() {
+                return Err(format!("{}: no such chain file", chain.display()).into());
+            }
+            let chain = Chain::open(&chain, public_key, Scheme::Unchained)?;
+            let beacon = chain.get(timelocked.round).ok_or_else(|| {
+                format!(
+                    "round {} isn't in the chain yet, the next round is {}",
+                    timelocked.round,
+                    chain.next_round()
+                )
+            })?;
+            let message = tlock::decrypt(beacon, &timelocked)
+                .ok_or("the message wasn't encrypted to this group, or was tampered with")?;
+            io::stdout().write_all(&message)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_public_key(hex: &str) -> Result<G1Affine, String> {
+    let bytes: [u8; 48] = hex::decode(hex.trim())
+        .map_err(|e| format!("{}", e))?
+        .try_into()
+        .map_err(|_| "a public key is 48 bytes")?;
+    Option::from(G1Affine::from_compressed(&bytes)).ok_or_else(|| "not a public key".into())
+}
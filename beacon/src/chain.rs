@@ -0,0 +1,126 @@
+use crate::{genesis_seed, hash, Beacon, BeaconError, Scheme};
+use bls12_381::{G1Affine, G2Affine};
+use bls_shamir::signature;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// The verified beacons of a group, from round 1 on without gaps, optionally
+/// backed by a flat file with one `<round> <hex signature>` line per round.
+#[derive(Debug)]
+pub struct Chain {
+    public_key: G1Affine,
+    scheme: Scheme,
+    seed: [u8; 32],
+    /// Round `i + 1` at index `i`.
+    beacons: Vec<Beacon>,
+    file: Option<File>,
+}
+
+impl Chain {
+    /// An empty chain kept in memory only.
+    pub fn new(public_key: G1Affine, scheme: Scheme) -> Self {
+        Chain {
+            public_key,
+            scheme,
+            seed: genesis_seed(&public_key),
+            beacons: Vec::new(),
+            file: None,
+        }
+    }
+
+    /// Opens the chain file, creating an empty one if it doesn't exist, and
+    /// verifies every beacon in it.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        public_key: G1Affine,
+        scheme: Scheme,
+    ) -> Result<Self, BeaconError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        let mut chain = Chain::new(public_key, scheme);
+        for (i, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line?;
+            let corrupt = BeaconError::Corrupt { line: i + 1 };
+            let beacon = parse_record(&line).ok_or(corrupt)?;
+            chain
+                .append(beacon)
+                .map_err(|_| BeaconError::Corrupt { line: i + 1 })?;
+        }
+        chain.file = Some(file);
+
+        Ok(chain)
+    }
+
+    pub fn public_key(&self) -> &G1Affine {
+        &self.public_key
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    pub fn latest(&self) -> Option<&Beacon> {
+        self.beacons.last()
+    }
+
+    pub fn get(&self, round: u64) -> Option<&Beacon> {
+        let index = usize::try_from(round.checked_sub(1)?).ok()?;
+        self.beacons.get(index)
+    }
+
+    /// The round to be added next.
+    pub fn next_round(&self) -> u64 {
+        self.beacons.len() as u64 + 1
+    }
+
+    /// The hashed message of the next round.
+    pub fn next_hash(&self) -> G2Affine {
+        let previous = match self.latest() {
+            Some(latest) => latest.signature.to_compressed().to_vec(),
+            None => self.seed.to_vec(),
+        };
+        hash(self.scheme, self.next_round(), &previous)
+    }
+
+    /// Verifies the beacon of the next round and durably adds it.
+    pub fn append(&mut self, beacon: Beacon) -> Result<(), BeaconError> {
+        let next = self.next_round();
+        if beacon.round != next {
+            return Err(BeaconError::NotNext {
+                round: beacon.round,
+                next,
+            });
+        }
+        if !signature::verify_hashed(&self.public_key, &self.next_hash(), &beacon.signature) {
+            return Err(BeaconError::InvalidSignature {
+                round: beacon.round,
+            });
+        }
+
+        if let Some(file) = &mut self.file {
+            writeln!(
+                file,
+                "{} {}",
+                beacon.round,
+                hex::encode(beacon.signature.to_compressed())
+            )?;
+            file.sync_data()?;
+        }
+        self.beacons.push(beacon);
+        Ok(())
+    }
+}
+
+fn parse_record(line: &str) -> Option<Beacon> {
+    let (round, signature) = line.split_once(' ')?;
+    let bytes: [u8; 96] = hex::decode(signature).ok()?.try_into().ok()?;
+    Some(Beacon {
+        round: round.parse().ok()?,
+        signature: Option::from(G2Affine::from_compressed(&bytes))?,
+    })
+}
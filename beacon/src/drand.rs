@@ -0,0 +1,227 @@
+//! Verification of the beacons of drand networks, such as the League of
+//! Entropy's mainnet, from the JSON of their HTTP API: the chain info of
+//! `/<chain hash>/info` and the beacons of `/<chain hash>/public/<round>`.
+//! Fetching them is left to the caller.
+//!
+//! The schemes differ in what is signed and on which curve:
+//!
+//! - `pedersen-bls-chained`: G2 signatures on
+//!   `SHA-256(previous signature || round)`, the mainnet's scheme.
+//! - `pedersen-bls-unchained`: G2 signatures on `SHA-256(round)`.
+//! - `bls-unchained-g1-rfc9380`: G1 signatures on `SHA-256(round)`, the
+//!   public key being in G2.
+//!
+//! The round is big-endian, signatures are checked under the basic scheme
+//! DST of their curve and the randomness is the SHA-256 of the signature,
+//! so a beacon that verifies here shows our hash-to-curve matches drand's.
+use bls12_381::{G1Affine, G2Affine};
+use bls_shamir::{min_sig, signature};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::Duration;
+
+/// The drand schemes we can verify.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Chained,
+    Unchained,
+    UnchainedOnG1,
+}
+
+impl Scheme {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Scheme::Chained => "pedersen-bls-chained",
+            Scheme::Unchained => "pedersen-bls-unchained",
+            Scheme::UnchainedOnG1 => "bls-unchained-g1-rfc9380",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        [Scheme::Chained, Scheme::Unchained, Scheme::UnchainedOnG1]
+            .into_iter()
+            .find(|scheme| scheme.id() == id)
+    }
+}
+
+/// The public key of a network, on the curve opposite its signatures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PublicKey {
+    G1(G1Affine),
+    G2(G2Affine),
+}
+
+/// What a network publishes about itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainInfo {
+    pub public_key: PublicKey,
+    pub period: Duration,
+    /// Unix time, in seconds, round 1 was due at.
+    pub genesis_time: u64,
+    pub hash: Vec<u8>,
+    pub scheme: Scheme,
+}
+
+/// One round of a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrandBeacon {
+    pub round: u64,
+    pub randomness: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// Only in the beacons of chained networks.
+    pub previous_signature: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct InfoJson {
+    public_key: String,
+    period: u64,
+    genesis_time: u64,
+    hash: String,
+    /// Missing from the info of networks older than the scheme ids, which
+    /// are all chained.
+    #[serde(rename = "schemeID")]
+    scheme_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BeaconJson {
+    round: u64,
+    randomness: String,
+    signature: String,
+    previous_signature: Option<String>,
+}
+
+impl ChainInfo {
+    pub fn from_json(json: &str) -> Result<Self, DrandError> {
+        let info: InfoJson = serde_json::from_str(json)?;
+        let scheme = match &info.scheme_id {
+            Some(id) => Scheme::from_id(id).ok_or_else(|| DrandError::UnknownScheme(id.clone()))?,
+            None => Scheme::Chained,
+        };
+        let public_key = decode("public_key", &info.public_key)?;
+        let public_key = match scheme {
+            Scheme::Chained | Scheme::Unchained => PublicKey::G1(g1(&public_key)?),
+            Scheme::UnchainedOnG1 => PublicKey::G2(g2(&public_key)?),
+        };
+
+        Ok(ChainInfo {
+            public_key,
+            period: Duration::from_secs(info.period),
+            genesis_time: info.genesis_time,
+            hash: decode("hash", &info.hash)?,
+            scheme,
+        })
+    }
+
+    /// Checks the beacon's signature and randomness.
+    pub fn verify(&self, beacon: &DrandBeacon) -> Result<(), DrandError> {
+        let round = beacon.round.to_be_bytes();
+        let message: [u8; 32] = match self.scheme {
+            Scheme::Chained => {
+                let previous = beacon
+                    .previous_signature
+                    .as_ref()
+                    .ok_or(DrandError::MissingPreviousSignature)?;
+                Sha256::new().chain(previous).chain(round).finalize().into()
+            }
+            Scheme::Unchained | Scheme::UnchainedOnG1 => Sha256::digest(&round).into(),
+        };
+
+        let valid = match self.public_key {
+            PublicKey::G1(pk) => signature::verify(&pk, &message, &g2(&beacon.signature)?),
+            PublicKey::G2(pk) => min_sig::verify(&pk, &message, &g1(&beacon.signature)?),
+        };
+        if !valid {
+            return Err(DrandError::InvalidSignature {
+                round: beacon.round,
+            });
+        }
+        if Sha256::digest(&beacon.signature)[..] != beacon.randomness[..] {
+            return Err(DrandError::Randomness {
+                round: beacon.round,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl DrandBeacon {
+    pub fn from_json(json: &str) -> Result<Self, DrandError> {
+        let beacon: BeaconJson = serde_json::from_str(json)?;
+        Ok(DrandBeacon {
+            round: beacon.round,
+            randomness: decode("randomness", &beacon.randomness)?,
+            signature: decode("signature", &beacon.signature)?,
+            previous_signature: beacon
+                .previous_signature
+                .map(|previous| decode("previous_signature", &previous))
+                .transpose()?,
+        })
+    }
+}
+
+fn decode(field: &'static str, hex: &str) -> Result<Vec<u8>, DrandError> {
+    hex::decode(hex).map_err(|_| DrandError::Malformed(field))
+}
+
+fn g1(bytes: &[u8]) -> Result<G1Affine, DrandError> {
+    let bytes: [u8; 48] = bytes
+        .try_into()
+        .map_err(|_| DrandError::Malformed("point"))?;
+    Option::from(G1Affine::from_compressed(&bytes)).ok_or(DrandError::Malformed("point"))
+}
+
+fn g2(bytes: &[u8]) -> Result<G2Affine, DrandError> {
+    let bytes: [u8; 96] = bytes
+        .try_into()
+        .map_err(|_| DrandError::Malformed("point"))?;
+    Option::from(G2Affine::from_compressed(&bytes)).ok_or(DrandError::Malformed("point"))
+}
+
+#[derive(Debug)]
+pub enum DrandError {
+    Json(serde_json::Error),
+    /// The field isn't hex, or the point isn't on the curve.
+    Malformed(&'static str),
+    UnknownScheme(String),
+    /// Chained beacons are signed over the previous signature.
+    MissingPreviousSignature,
+    InvalidSignature {
+        round: u64,
+    },
+    /// The randomness isn't the hash of the signature.
+    Randomness {
+        round: u64,
+    },
+}
+
+impl fmt::Display for DrandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrandError::Json(e) => write!(f, "invalid drand JSON: {}", e),
+            DrandError::Malformed(field) => write!(f, "malformed {}", field),
+            DrandError::UnknownScheme(id) => write!(f, "unknown drand scheme {}", id),
+            DrandError::MissingPreviousSignature => {
+                write!(f, "a chained beacon needs its previous signature")
+            }
+            DrandError::InvalidSignature { round } => {
+                write!(f, "invalid signature for round {}", round)
+            }
+            DrandError::Randomness { round } => write!(
+                f,
+                "the randomness of round {} isn't the hash of its signature",
+                round
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DrandError {}
+
+impl From<serde_json::Error> for DrandError {
+    fn from(e: serde_json::Error) -> Self {
+        DrandError::Json(e)
+    }
+}
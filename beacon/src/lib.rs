@@ -0,0 +1,153 @@
+//! A drand-style randomness beacon, run by the group that came out of a DKG.
+//!
+//! Every period from a genesis time the group threshold-signs
+//! `SHA-256(round || previous signature)`, chaining each round to the one
+//! before it. Round 1 chains to the [`genesis_seed`] of the group instead.
+//! BLS signatures are unique: whichever `threshold + 1` members sign a round,
+//! it has exactly one valid signature, which nobody can know before that many
+//! members revealed their partial signature and anyone holding the group's
+//! public key can check. The randomness of a round is the SHA-256 of its
+//! signature.
+//!
+//! A beacon can also be run [`Scheme::Unchained`], signing each round number
+//! alone as an identity of the group's Boneh-Franklin IBE. Anyone can then
+//! encrypt a message to a future round, which can only be decrypted once the
+//! round's signature exists, see [`tlock`]. A round's randomness can also
+//! seed the toy [`vdf`], for values nobody can know until some time after
+//! the round.
+//!
+//! [`Chain`] keeps the verified beacons, [`Schedule`] says which round is due
+//! when, and [`Node`] runs the protocol for a member, or for a follower that
+//! only verifies. None of them does any networking: a node is handed the
+//! partial signatures and beacons that arrive and returns the ones to
+//! publish.
+use bls12_381::{G1Affine, G2Affine};
+use bls_shamir::ibe;
+use bls_shamir::signature::{self, DST};
+use group::Curve;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+
+mod chain;
+pub mod drand;
+mod node;
+mod schedule;
+pub mod tlock;
+pub mod vdf;
+
+pub use chain::Chain;
+pub use node::{Node, Step};
+pub use schedule::Schedule;
+
+/// What the group signs for each round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scheme {
+    /// `SHA-256(round || previous signature)`, so no round can be signed
+    /// before the one preceding it.
+    #[default]
+    Chained,
+    /// The round number alone, so that messages can be timelocked to it.
+    Unchained,
+}
+
+/// The group's signature of one round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beacon {
+    pub round: u64,
+    pub signature: G2Affine,
+}
+
+impl Beacon {
+    /// The round's random value.
+    pub fn randomness(&self) -> [u8; 32] {
+        Sha256::digest(&self.signature.to_compressed()).into()
+    }
+}
+
+/// What round 1 chains to in place of a previous signature, so that two
+/// groups never sign the same rounds.
+pub fn genesis_seed(public_key: &G1Affine) -> [u8; 32] {
+    Sha256::digest(&public_key.to_compressed()).into()
+}
+
+/// The message signed for `round`, `previous` being the compressed signature
+/// of the round before, or the genesis seed for round 1.
+pub fn message(round: u64, previous: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(round.to_be_bytes());
+    hasher.update(previous);
+    hasher.finalize().into()
+}
+
+/// What is signed for `round` under `scheme`, hashed to the curve.
+/// `previous` is only used by the chained scheme.
+pub fn hash(scheme: Scheme, round: u64, previous: &[u8]) -> G2Affine {
+    match scheme {
+        Scheme::Chained => signature::hash_to_g2(&message(round, previous), DST).to_affine(),
+        Scheme::Unchained => ibe::hash_identity(&tlock::identity(round)),
+    }
+}
+
+#[derive(Debug)]
+pub enum BeaconError {
+    /// Only the round after the end of the chain can be added to it.
+    NotNext {
+        round: u64,
+        next: u64,
+    },
+    /// The signature isn't the group's on the round's message.
+    InvalidSignature {
+        round: u64,
+    },
+    /// The partial signature doesn't match the member's public share.
+    InvalidPartial {
+        round: u64,
+        index: u64,
+    },
+    /// No member of the group has this index.
+    UnknownMember(u64),
+    /// The share doesn't match the member's public share.
+    WrongShare,
+    /// The chain file contains a line we can't parse or verify.
+    Corrupt {
+        line: usize,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for BeaconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BeaconError::NotNext { round, next } => write!(
+                f,
+                "round {} doesn't follow the chain, the next round is {}",
+                round, next
+            ),
+            BeaconError::InvalidSignature { round } => {
+                write!(f, "invalid signature for round {}", round)
+            }
+            BeaconError::InvalidPartial { round, index } => write!(
+                f,
+                "invalid partial signature of member {} for round {}",
+                index, round
+            ),
+            BeaconError::UnknownMember(index) => write!(f, "no member has index {}", index),
+            BeaconError::WrongShare => {
+                write!(f, "the share doesn't match the member's public share")
+            }
+            BeaconError::Corrupt { line } => {
+                write!(f, "beacon chain file is corrupt at line {}", line)
+            }
+            BeaconError::Io(e) => write!(f, "beacon chain io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BeaconError {}
+
+impl From<io::Error> for BeaconError {
+    fn from(e: io::Error) -> Self {
+        BeaconError::Io(e)
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{Beacon, BeaconError, Chain};
+use bls12_381::G1Affine;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::threshold::{self, PartialSignature};
+use std::collections::BTreeMap;
+
+/// How many rounds past the end of the chain partial signatures are kept,
+/// for the members a round or two ahead of us.
+const LOOKAHEAD: u64 = 2;
+
+/// How many beacons past the end of the chain are kept, until the rounds
+/// before them arrive.
+const MAX_EARLY: usize = 1024;
+
+/// What a node has to do after a tick or a message.
+#[derive(Debug, Default)]
+pub struct Step {
+    /// Our partial signature of a round, to be published.
+    pub partial: Option<(u64, PartialSignature)>,
+    /// The beacons we aggregated, to be published.
+    pub aggregated: Vec<Beacon>,
+    /// Every beacon added to the chain, in order.
+    pub stored: Vec<Beacon>,
+}
+
+/// A member of the group taking part in the beacon, or a follower.
+#[derive(Debug)]
+pub struct Node {
+    chain: Chain,
+    /// Any `threshold + 1` members can sign a round.
+    threshold: usize,
+    public_shares: BTreeMap<u64, G1Affine>,
+    share: Option<(u64, SecretKey)>,
+    /// The last round we signed.
+    signed: u64,
+    /// The partial signatures of the next round, checked, and of the rounds
+    /// after it, which can't be checked yet.
+    partials: BTreeMap<u64, BTreeMap<u64, PartialSignature>>,
+    /// The beacons past the end of the chain.
+    early: BTreeMap<u64, Beacon>,
+}
+
+impl Node {
+    /// A follower of the beacon of the group with the given public shares,
+    /// by member index. It verifies and stores the beacons, and aggregates
+    /// the partial signatures it sees, but doesn't sign.
+    pub fn new(
+        chain: Chain,
+        threshold: usize,
+        public_shares: impl IntoIterator<Item = (u64, G1Affine)>,
+    ) -> Self {
+        Node {
+            chain,
+            threshold,
+            public_shares: public_shares.into_iter().collect(),
+            share: None,
+            signed: 0,
+            partials: BTreeMap::new(),
+            early: BTreeMap::new(),
+        }
+    }
+
+    /// Takes part in the beacon as member `index`.
+    pub fn with_share(mut self, index: u64, share: SecretKey) -> Result<Self, BeaconError> {
+        let public_share = self
+            .public_shares
+            .get(&index)
+            .ok_or(BeaconError::UnknownMember(index))?;
+        if *public_share != share.public_key() {
+            return Err(BeaconError::WrongShare);
+        }
+        self.share = Some((index, share));
+        Ok(self)
+    }
+
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+
+    pub fn is_member(&self) -> bool {
+        self.share.is_some()
+    }
+
+    /// The rounds missing between the end of the chain and the first beacon
+    /// we got past it.
+    pub fn missing(&self) -> Option<(u64, u64)> {
+        let first = self.early.keys().next()?;
+        Some((self.chain.next_round(), first - 1))
+    }
+
+    /// Signs the next round once it is due, `due` being the latest round
+    /// the schedule says is.
+    pub fn tick(&mut self, due: u64) -> Step {
+        let mut step = Step::default();
+        let next = self.chain.next_round();
+        let (index, share) = match &self.share {
+            Some((index, share)) if next <= due && self.signed < next => (*index, share),
+            _ => return step,
+        };
+
+        let partial = threshold::sign_share(
+            index,
+            share,
+            &self.chain.next_hash(),
+            &mut rand::rngs::OsRng,
+        );
+        self.signed = next;
+        self.partials
+            .entry(next)
+            .or_default()
+            .insert(index, partial);
+        step.partial = Some((next, partial));
+        self.settle(&mut step);
+        step
+    }
+
+    /// Takes in a member's partial signature of `round`.
+    pub fn handle_partial(
+        &mut self,
+        round: u64,
+        partial: PartialSignature,
+    ) -> Result<Step, BeaconError> {
+        let mut step = Step::default();
+        let public_share = self
+            .public_shares
+            .get(&partial.index)
+            .ok_or(BeaconError::UnknownMember(partial.index))?;
+        let next = self.chain.next_round();
+        if round < next {
+            // Too late to matter.
+            return Ok(step);
+        }
+        if round > next + LOOKAHEAD {
+            return Err(BeaconError::NotNext { round, next });
+        }
+        if round == next
+            && !threshold::verify_share(public_share, &self.chain.next_hash(), &partial)
+        {
+            return Err(BeaconError::InvalidPartial {
+                round,
+                index: partial.index,
+            });
+        }
+
+        self.partials
+            .entry(round)
+            .or_default()
+            .insert(partial.index, partial);
+        self.settle(&mut step);
+        Ok(step)
+    }
+
+    /// Takes in the beacon of a round, which only gets checked once the
+    /// chain reaches it.
+    pub fn handle_beacon(&mut self, beacon: Beacon) -> Result<Step, BeaconError> {
+        let mut step = Step::default();
+        let next = self.chain.next_round();
+        if beacon.round < next {
+            // Signatures are unique, a different one can't be valid.
+            return match self.chain.get(beacon.round) {
+                Some(stored) if *stored == beacon => Ok(step),
+                _ => Err(BeaconError::InvalidSignature {
+                    round: beacon.round,
+                }),
+            };
+        }
+        if beacon.round > next {
+            if self.early.len() < MAX_EARLY {
+                self.early.insert(beacon.round, beacon);
+            }
+            return Ok(step);
+        }
+
+        self.chain.append(beacon)?;
+        step.stored.push(beacon);
+        self.settle(&mut step);
+        Ok(step)
+    }
+
+    /// Extends the chain with the early beacons and the partial signatures
+    /// we have, for as long as they reach the next round.
+    fn settle(&mut self, step: &mut Step) {
+        loop {
+            let next = self.chain.next_round();
+            self.partials = self.partials.split_off(&next);
+            self.early = self.early.split_off(&next);
+
+            if let Some(beacon) = self.early.remove(&next) {
+                if self.chain.append(beacon).is_ok() {
+                    step.stored.push(beacon);
+                    self.check_partials();
+                    continue;
+                }
+            }
+            match self.aggregate(next) {
+                Some(beacon) if self.chain.append(beacon).is_ok() => {
+                    step.aggregated.push(beacon);
+                    step.stored.push(beacon);
+                    self.check_partials();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// The beacon of `round` once `threshold + 1` members signed it.
+    fn aggregate(&self, round: u64) -> Option<Beacon> {
+        let partials = self.partials.get(&round)?;
+        if partials.len() <= self.threshold {
+            return None;
+        }
+        let partials = partials.values().copied().collect::<Vec<_>>();
+        Some(Beacon {
+            round,
+            signature: threshold::aggregate_shares(&partials[..=self.threshold]).ok()?,
+        })
+    }
+
+    /// Drops the partial signatures of the new next round that don't verify,
+    /// now that its message is known.
+    fn check_partials(&mut self) {
+        let hm = self.chain.next_hash();
+        let public_shares = &self.public_shares;
+        if let Some(partials) = self.partials.get_mut(&self.chain.next_round()) {
+            partials.retain(|index, partial| {
+                public_shares
+                    .get(index)
+                    .is_some_and(|public_share| threshold::verify_share(public_share, &hm, partial))
+            });
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::{Duration, SystemTime};
+
+/// When each round is due: round 1 at the genesis time, then one more every
+/// period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    genesis: SystemTime,
+    period: Duration,
+}
+
+impl Schedule {
+    /// Panics if `period` is zero.
+    pub fn new(genesis: SystemTime, period: Duration) -> Self {
+        assert!(!period.is_zero(), "The beacon period can't be zero");
+        Schedule { genesis, period }
+    }
+
+    pub fn genesis(&self) -> SystemTime {
+        self.genesis
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// The latest round due at `time`, 0 before the genesis.
+    pub fn round_at(&self, time: SystemTime) -> u64 {
+        match time.duration_since(self.genesis) {
+            Ok(elapsed) => (elapsed.as_nanos() / self.period.as_nanos()) as u64 + 1,
+            Err(_) => 0,
+        }
+    }
+
+    /// When `round` is due.
+    pub fn time_of(&self, round: u64) -> SystemTime {
+        let elapsed = self.period.as_nanos() * u128::from(round.saturating_sub(1));
+        self.genesis + Duration::from_nanos(elapsed as u64)
+    }
+}
@@ -0,0 +1,98 @@
+//! Timelock encryption to a round of an unchained beacon.
+//!
+//! The signature of round `r` of an unchained beacon is `s * H(r)`, which is
+//! also the private key of the identity `r` in the Boneh-Franklin IBE under
+//! the group's public key. A message encrypted to that identity can be read
+//! by anyone once the group signed the round, and by no one before, short of
+//! `threshold + 1` members colluding.
+//!
+//! A timelocked message is written as one `<round> <hex>` line, the hex
+//! being `U || V || W` of the IBE ciphertext.
+use crate::Beacon;
+use bls12_381::G1Affine;
+use bls_shamir::ibe::{self, Ciphertext, IdentityKey};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use std::str::FromStr;
+
+/// The IBE identity of `round`.
+pub fn identity(round: u64) -> [u8; 8] {
+    round.to_be_bytes()
+}
+
+/// A message only the signature of `round` decrypts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timelocked {
+    pub round: u64,
+    pub ciphertext: Ciphertext,
+}
+
+/// Encrypts `msg` to `round` of the unchained beacon of the group with the
+/// given public key.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    public_key: &G1Affine,
+    round: u64,
+    msg: &[u8],
+    rng: &mut R,
+) -> Timelocked {
+    Timelocked {
+        round,
+        ciphertext: ibe::encrypt(public_key, &identity(round), msg, rng),
+    }
+}
+
+/// Decrypts with the beacon of the message's round, `None` if the beacon is
+/// of another round or the message was tampered with.
+pub fn decrypt(beacon: &Beacon, timelocked: &Timelocked) -> Option<Vec<u8>> {
+    if beacon.round != timelocked.round {
+        return None;
+    }
+    ibe::decrypt(&IdentityKey(beacon.signature), &timelocked.ciphertext)
+}
+
+impl fmt::Display for Timelocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ciphertext = &self.ciphertext;
+        let mut bytes = ciphertext.u.to_compressed().to_vec();
+        bytes.extend_from_slice(&ciphertext.v);
+        bytes.extend_from_slice(&ciphertext.w);
+        write!(f, "{} {}", self.round, hex::encode(bytes))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError;
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not a timelocked message")
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Timelocked {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (round, bytes) = s.trim().split_once(' ').ok_or(ParseError)?;
+        let round = round.parse().map_err(|_| ParseError)?;
+        let bytes = hex::decode(bytes).map_err(|_| ParseError)?;
+        if bytes.len() < 48 + 32 {
+            return Err(ParseError);
+        }
+        let (u, rest) = bytes.split_at(48);
+        let (v, w) = rest.split_at(32);
+        let u: [u8; 48] = u.try_into().expect("Split at 48 bytes");
+        let u = Option::from(G1Affine::from_compressed(&u)).ok_or(ParseError)?;
+
+        Ok(Timelocked {
+            round,
+            ciphertext: Ciphertext {
+                u,
+                v: v.try_into().expect("Split at 32 bytes"),
+                w: w.to_vec(),
+            },
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! A toy verifiable delay function, Wesolowski's repeated squaring in an
+//! RSA group, to mix into the beacon: a value derived from a round's
+//! randomness that takes T sequential squarings to compute but is checked
+//! in about `2 * log2(l)` multiplications. Whoever contributes last to a
+//! commit-reveal scheme seeded with it can't see the outcome in time to
+//! withhold their part.
+//!
+//! The input is `x = H(seed) mod N` and the output is
+//!
+//! y = x^(2^T) mod N
+//!
+//! For a prime l hashed from x, y and T, the proof is `π = x^⌊2^T / l⌋`,
+//! computed along with the squarings, and with `r = 2^T mod l`
+//!
+//! π^l * x^r == y
+//!
+//! as `2^T = ⌊2^T / l⌋ * l + r`. Anyone who knows the order of the group
+//! can skip the squarings and forge proofs, so N must have unknown factors.
+//! The default [`TOY_MODULUS`] is the product of two 32-bit primes that
+//! anyone factors in no time, and l has 64 bits instead of 128: this shows
+//! the protocol over machine integers, it is not a delay.
+use sha2::{Digest, Sha256};
+
+/// `(2^32 - 5) * (2^32 - 17)`
+pub const TOY_MODULUS: u64 = 18446743979220271189;
+
+const INPUT_TAG: &[u8] = b"zk-lab/beacon/vdf/input";
+const PRIME_TAG: &[u8] = b"zk-lab/beacon/vdf/prime";
+const OUTPUT_TAG: &[u8] = b"zk-lab/beacon/vdf/output";
+
+/// The VDF with T squarings in the group of units modulo N.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vdf {
+    modulus: u64,
+    iterations: u64,
+}
+
+/// `(y, π)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Evaluation {
+    pub output: u64,
+    pub proof: u64,
+}
+
+impl Vdf {
+    /// T squarings modulo the [`TOY_MODULUS`].
+    pub fn new(iterations: u64) -> Self {
+        Vdf::with_modulus(TOY_MODULUS, iterations)
+    }
+
+    /// T squarings modulo an RSA modulus of unknown factorization.
+    pub fn with_modulus(modulus: u64, iterations: u64) -> Self {
+        assert!(modulus > 3, "The modulus must leave room for an input");
+        Vdf {
+            modulus,
+            iterations,
+        }
+    }
+
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// `x = H(seed) mod N`, kept away from 0 and 1.
+    pub fn input(&self, seed: &[u8]) -> u64 {
+        let digest = Sha256::new().chain(INPUT_TAG).chain(seed).finalize();
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        (u128::from_le_bytes(bytes) % (self.modulus as u128 - 2)) as u64 + 2
+    }
+
+    /// Computes y and its proof with T squarings, sequential by design.
+    pub fn evaluate(&self, seed: &[u8]) -> Evaluation {
+        let x = self.input(seed);
+        let mut output = x;
+        for _ in 0..self.iterations {
+            output = self.mul(output, output);
+        }
+        let l = hash_to_prime(x, output, self.iterations);
+        // The long division of 2^T by l, one bit of the quotient per step.
+        let (mut proof, mut remainder) = (1, 1u128);
+        for _ in 0..self.iterations {
+            remainder *= 2;
+            proof = self.mul(proof, proof);
+            if remainder >= l as u128 {
+                remainder -= l as u128;
+                proof = self.mul(proof, x);
+            }
+        }
+        Evaluation { output, proof }
+    }
+
+    /// Checks `π^l * x^r == y` for the input of the seed.
+    pub fn verify(&self, seed: &[u8], evaluation: &Evaluation) -> bool {
+        let x = self.input(seed);
+        let Evaluation { output, proof } = *evaluation;
+        if output == 0 || output >= self.modulus || proof == 0 || proof >= self.modulus {
+            return false;
+        }
+        let l = hash_to_prime(x, output, self.iterations);
+        let r = pow_mod(2, self.iterations, l);
+        self.mul(self.pow(proof, l), self.pow(x, r)) == output
+    }
+
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        (a as u128 * b as u128 % self.modulus as u128) as u64
+    }
+
+    fn pow(&self, base: u64, exponent: u64) -> u64 {
+        pow_mod(base, exponent, self.modulus)
+    }
+}
+
+impl Evaluation {
+    /// The random value the output stands for.
+    pub fn randomness(&self) -> [u8; 32] {
+        Sha256::new()
+            .chain(OUTPUT_TAG)
+            .chain(self.output.to_le_bytes())
+            .finalize()
+            .into()
+    }
+}
+
+/// The first prime of 64 bits from SHA-256 of the statement and a counter.
+fn hash_to_prime(x: u64, y: u64, iterations: u64) -> u64 {
+    (0u64..)
+        .map(|counter| {
+            let digest = Sha256::new()
+                .chain(PRIME_TAG)
+                .chain(x.to_le_bytes())
+                .chain(y.to_le_bytes())
+                .chain(iterations.to_le_bytes())
+                .chain(counter.to_le_bytes())
+                .finalize();
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&digest[..8]);
+            u64::from_le_bytes(bytes) | 1 << 63 | 1
+        })
+        .find(|candidate| is_prime(*candidate))
+        .expect("Primes are dense enough")
+}
+
+/// Miller-Rabin with the first twelve primes as bases, deterministic below
+/// 2^64.
+fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(base) = BASES.iter().find(|base| n.is_multiple_of(**base)) {
+        return n == *base;
+    }
+    let shift = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> shift;
+    BASES.iter().all(|base| {
+        let mut x = pow_mod(*base, odd, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..shift {
+            x = (x as u128 * x as u128 % n as u128) as u64;
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+fn pow_mod(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    let (mut base, mut result) = (base as u128 % modulus, 1 % modulus);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result as u64
+}
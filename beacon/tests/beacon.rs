@@ -0,0 +1,176 @@
+use beacon::tlock::{self, Timelocked};
+use beacon::{Beacon, BeaconError, Chain, Node, Schedule, Scheme};
+use bls_shamir::secret::SecretPolynomial;
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// A group of `n` members of which any `threshold + 1` can sign, every
+/// member's node already holding its share.
+fn group(n: u64, threshold: usize) -> Vec<Node> {
+    group_with(n, threshold, Scheme::Chained)
+}
+
+fn group_with(n: u64, threshold: usize, scheme: Scheme) -> Vec<Node> {
+    let f = SecretPolynomial::random(threshold, &mut rand::thread_rng());
+    let public_key = f.secret().public_key();
+    let public_shares = (1..=n)
+        .map(|i| (i, f.evaluate(i).public_key()))
+        .collect::<Vec<_>>();
+    (1..=n)
+        .map(|i| {
+            Node::new(
+                Chain::new(public_key, scheme),
+                threshold,
+                public_shares.clone(),
+            )
+            .with_share(i, f.evaluate(i))
+            .unwrap()
+        })
+        .collect()
+}
+
+/// Has the first `signers` nodes sign the next round and everyone take in
+/// their partial signatures.
+fn run_round(nodes: &mut [Node], due: u64, signers: usize) {
+    let partials = nodes[..signers]
+        .iter_mut()
+        .filter_map(|node| node.tick(due).partial)
+        .collect::<Vec<_>>();
+    for (i, node) in nodes.iter_mut().enumerate() {
+        for (round, partial) in &partials {
+            if partial.index != i as u64 + 1 {
+                node.handle_partial(*round, *partial).unwrap();
+            }
+        }
+    }
+}
+
+#[test]
+fn members_chain_rounds() {
+    let mut nodes = group(3, 1);
+    for round in 1..=3 {
+        run_round(&mut nodes, round, 2);
+    }
+
+    let latest = nodes[0].chain().latest().copied().unwrap();
+    assert_eq!(latest.round, 3);
+    for node in &nodes {
+        assert_eq!(node.chain().latest(), Some(&latest));
+    }
+}
+
+#[test]
+fn rounds_wait_for_the_threshold() {
+    let mut nodes = group(4, 2);
+    run_round(&mut nodes, 1, 2);
+    assert!(nodes.iter().all(|node| node.chain().latest().is_none()));
+
+    run_round(&mut nodes, 1, 3);
+    assert!(nodes.iter().all(|node| node.chain().next_round() == 2));
+}
+
+#[test]
+fn followers_verify_beacons() {
+    let mut nodes = group(3, 1);
+    run_round(&mut nodes, 1, 2);
+    run_round(&mut nodes, 2, 2);
+    let public_key = *nodes[0].chain().public_key();
+    let beacons = (1..=2)
+        .map(|round| *nodes[0].chain().get(round).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut follower = Node::new(Chain::new(public_key, Scheme::Chained), 1, Vec::new());
+    assert!(!follower.is_member());
+    let forged = Beacon {
+        round: 1,
+        signature: beacons[1].signature,
+    };
+    assert!(matches!(
+        follower.handle_beacon(forged),
+        Err(BeaconError::InvalidSignature { round: 1 })
+    ));
+
+    // Round 2 waits for round 1 to arrive.
+    let step = follower.handle_beacon(beacons[1]).unwrap();
+    assert!(step.stored.is_empty());
+    assert_eq!(follower.missing(), Some((1, 1)));
+
+    let step = follower.handle_beacon(beacons[0]).unwrap();
+    assert_eq!(step.stored, beacons);
+    assert!(step.aggregated.is_empty());
+    assert_eq!(follower.missing(), None);
+}
+
+#[test]
+fn rejects_invalid_partials() {
+    let mut nodes = group(3, 1);
+    let (round, mut partial) = nodes[0].tick(1).partial.unwrap();
+    partial.index = 2;
+    assert!(matches!(
+        nodes[2].handle_partial(round, partial),
+        Err(BeaconError::InvalidPartial { round: 1, index: 2 })
+    ));
+    partial.index = 9;
+    assert!(matches!(
+        nodes[2].handle_partial(round, partial),
+        Err(BeaconError::UnknownMember(9))
+    ));
+}
+
+#[test]
+fn chain_file_survives_restarts() {
+    let path = std::env::temp_dir().join(format!("beacon-{}.chain", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut nodes = group(3, 1);
+    run_round(&mut nodes, 1, 2);
+    run_round(&mut nodes, 2, 2);
+    let public_key = *nodes[0].chain().public_key();
+
+    let mut chain = Chain::open(&path, public_key, Scheme::Chained).unwrap();
+    for round in 1..=2 {
+        chain.append(*nodes[0].chain().get(round).unwrap()).unwrap();
+    }
+    drop(chain);
+
+    let chain = Chain::open(&path, public_key, Scheme::Chained).unwrap();
+    assert_eq!(chain.latest(), nodes[0].chain().latest());
+
+    // A chain of another group doesn't verify.
+    let other = *group(3, 1)[0].chain().public_key();
+    assert!(matches!(
+        Chain::open(&path, other, Scheme::Chained),
+        Err(BeaconError::Corrupt { line: 1 })
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn schedule_rounds() {
+    let genesis = UNIX_EPOCH + Duration::from_secs(1_000);
+    let schedule = Schedule::new(genesis, Duration::from_secs(30));
+    assert_eq!(schedule.round_at(genesis - Duration::from_secs(1)), 0);
+    assert_eq!(schedule.round_at(genesis), 1);
+    assert_eq!(schedule.round_at(genesis + Duration::from_secs(29)), 1);
+    assert_eq!(schedule.round_at(genesis + Duration::from_secs(30)), 2);
+    assert_eq!(schedule.time_of(3), genesis + Duration::from_secs(60));
+}
+
+#[test]
+fn timelocked_messages_open_with_their_round() {
+    let mut nodes = group_with(3, 1, Scheme::Unchained);
+    let public_key = *nodes[0].chain().public_key();
+    let timelocked = tlock::encrypt(&public_key, 2, b"sealed bid", &mut rand::thread_rng());
+    let timelocked: Timelocked = timelocked.to_string().parse().unwrap();
+
+    run_round(&mut nodes, 1, 2);
+    let first = *nodes[0].chain().get(1).unwrap();
+    assert_eq!(tlock::decrypt(&first, &timelocked), None);
+
+    run_round(&mut nodes, 2, 2);
+    let second = nodes[0].chain().get(2).unwrap();
+    assert_eq!(
+        tlock::decrypt(second, &timelocked),
+        Some(b"sealed bid".to_vec())
+    );
+}
@@ -0,0 +1,159 @@
+//! drand beacons signed with a key of our own, in the JSON of its HTTP API,
+//! and beacons of the League of Entropy's networks as their API served them.
+use beacon::drand::{ChainInfo, DrandBeacon, DrandError, PublicKey, Scheme};
+use bls12_381::Scalar;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::{min_sig, signature};
+use sha2::{Digest, Sha256};
+
+/// `https://api.drand.sh/info`
+const MAINNET_INFO: &str = r#"{"public_key":"868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31","period":30,"genesis_time":1595431050,"hash":"8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce","groupHash":"176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"}"#;
+
+/// `https://api.drand.sh/public/72785`
+const MAINNET_BEACON: &str = r#"{"round":72785,"randomness":"8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9","signature":"82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42","previous_signature":"a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747"}"#;
+
+/// `https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971/info`
+const QUICKNET_INFO: &str = r#"{"public_key":"83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a","period":3,"genesis_time":1692803367,"hash":"52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971","groupHash":"f477d5c89f21a17c863a7f937c6a6d15859414d2be09cd448d4279af331c5d3e","schemeID":"bls-unchained-g1-rfc9380"}"#;
+
+/// `https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971/public/1000`
+const QUICKNET_BEACON: &str = r#"{"round":1000,"randomness":"fe290beca10872ef2fb164d2aa4442de4566183ec51c56ff3cd603d930e54fdd","signature":"b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125e342b73a8dd2bacbe47e4b6b63ed5e39"}"#;
+
+fn key() -> SecretKey {
+    SecretKey::new(Scalar::from(0x1234_5678))
+}
+
+fn info(scheme: Option<&str>) -> String {
+    let sk = key();
+    let public_key = match scheme {
+        Some("bls-unchained-g1-rfc9380") => hex::encode(min_sig::public_key(&sk).to_compressed()),
+        _ => hex::encode(signature::public_key(&sk).to_compressed()),
+    };
+    let scheme = scheme.map_or(String::new(), |id| format!(r#","schemeID":"{}""#, id));
+    format!(
+        r#"{{"public_key":"{}","period":30,"genesis_time":1595431050,"hash":"8990e7a9","groupHash":"176f93498eac9ca3"{}}}"#,
+        public_key, scheme
+    )
+}
+
+/// The beacon of `round` as drand signs it, chained to `previous` if given.
+fn beacon(scheme: Scheme, round: u64, previous: Option<&[u8]>) -> String {
+    let sk = key();
+    let message: [u8; 32] = match previous {
+        Some(previous) => Sha256::new()
+            .chain(previous)
+            .chain(round.to_be_bytes())
+            .finalize()
+            .into(),
+        None => Sha256::digest(&round.to_be_bytes()).into(),
+    };
+    let signature = match scheme {
+        Scheme::UnchainedOnG1 => min_sig::sign(&sk, &message).to_compressed().to_vec(),
+        _ => signature::sign(&sk, &message).to_compressed().to_vec(),
+    };
+    let randomness = Sha256::digest(&signature);
+    let previous = previous.map_or(String::new(), |previous| {
+        format!(r#","previous_signature":"{}""#, hex::encode(previous))
+    });
+    format!(
+        r#"{{"round":{},"randomness":"{}","signature":"{}"{}}}"#,
+        round,
+        hex::encode(randomness),
+        hex::encode(signature),
+        previous
+    )
+}
+
+#[test]
+fn verifies_chained_beacons() {
+    // The mainnet's info predates the scheme ids.
+    let info = ChainInfo::from_json(&info(None)).unwrap();
+    assert_eq!(info.scheme, Scheme::Chained);
+    assert!(matches!(info.public_key, PublicKey::G1(_)));
+
+    let first = DrandBeacon::from_json(&beacon(Scheme::Chained, 1, Some(&[7; 96][..]))).unwrap();
+    info.verify(&first).unwrap();
+    let second =
+        DrandBeacon::from_json(&beacon(Scheme::Chained, 2, Some(&first.signature[..]))).unwrap();
+    info.verify(&second).unwrap();
+
+    let mut unchained = second.clone();
+    unchained.previous_signature = None;
+    assert!(matches!(
+        info.verify(&unchained),
+        Err(DrandError::MissingPreviousSignature)
+    ));
+    let mut rechained = second;
+    rechained.previous_signature = Some(vec![7; 96]);
+    assert!(matches!(
+        info.verify(&rechained),
+        Err(DrandError::InvalidSignature { round: 2 })
+    ));
+}
+
+#[test]
+fn verifies_unchained_beacons() {
+    for scheme in [Scheme::Unchained, Scheme::UnchainedOnG1] {
+        let info = ChainInfo::from_json(&info(Some(scheme.id()))).unwrap();
+        assert_eq!(info.scheme, scheme);
+
+        let mut beacon = DrandBeacon::from_json(&beacon(scheme, 1000, None)).unwrap();
+        info.verify(&beacon).unwrap();
+
+        beacon.round = 1001;
+        assert!(matches!(
+            info.verify(&beacon),
+            Err(DrandError::InvalidSignature { round: 1001 })
+        ));
+    }
+}
+
+#[test]
+fn randomness_is_the_hash_of_the_signature() {
+    let info = ChainInfo::from_json(&info(Some(Scheme::Unchained.id()))).unwrap();
+    let mut beacon = DrandBeacon::from_json(&beacon(Scheme::Unchained, 3, None)).unwrap();
+    beacon.randomness[0] ^= 1;
+    assert!(matches!(
+        info.verify(&beacon),
+        Err(DrandError::Randomness { round: 3 })
+    ));
+}
+
+#[test]
+fn verifies_the_mainnet() {
+    let info = ChainInfo::from_json(MAINNET_INFO).unwrap();
+    assert_eq!(info.scheme, Scheme::Chained);
+    assert!(matches!(info.public_key, PublicKey::G1(_)));
+
+    let mut beacon = DrandBeacon::from_json(MAINNET_BEACON).unwrap();
+    info.verify(&beacon).unwrap();
+
+    beacon.round += 1;
+    assert!(matches!(
+        info.verify(&beacon),
+        Err(DrandError::InvalidSignature { round: 72786 })
+    ));
+}
+
+#[test]
+fn verifies_quicknet() {
+    let info = ChainInfo::from_json(QUICKNET_INFO).unwrap();
+    assert_eq!(info.scheme, Scheme::UnchainedOnG1);
+    assert!(matches!(info.public_key, PublicKey::G2(_)));
+
+    let mut beacon = DrandBeacon::from_json(QUICKNET_BEACON).unwrap();
+    info.verify(&beacon).unwrap();
+
+    beacon.round += 1;
+    assert!(matches!(
+        info.verify(&beacon),
+        Err(DrandError::InvalidSignature { round: 1001 })
+    ));
+}
+
+#[test]
+fn rejects_unknown_schemes() {
+    assert!(matches!(
+        ChainInfo::from_json(&info(Some("bls-bn254-unchained-on-g1"))),
+        Err(DrandError::UnknownScheme(_))
+    ));
+}
@@ -0,0 +1,46 @@
+use beacon::vdf::{Evaluation, Vdf, TOY_MODULUS};
+
+#[test]
+fn evaluates_and_verifies() {
+    let vdf = Vdf::new(10_000);
+    let evaluation = vdf.evaluate(b"round 7");
+    assert!(vdf.verify(b"round 7", &evaluation));
+    assert!(!vdf.verify(b"round 8", &evaluation));
+    assert!(!Vdf::new(10_001).verify(b"round 7", &evaluation));
+    assert_ne!(
+        vdf.evaluate(b"round 8").randomness(),
+        evaluation.randomness()
+    );
+}
+
+#[test]
+fn the_output_is_repeated_squaring() {
+    let vdf = Vdf::new(100);
+    let x = vdf.input(b"seed") as u128;
+    let output = (0..100).fold(x, |y, _| y * y % TOY_MODULUS as u128);
+    assert_eq!(vdf.evaluate(b"seed").output as u128, output);
+}
+
+#[test]
+fn rejects_a_wrong_output_or_proof() {
+    let vdf = Vdf::new(1_000);
+    let evaluation = vdf.evaluate(b"seed");
+    let Evaluation { output, proof } = evaluation;
+    for forged in [
+        Evaluation {
+            output: output ^ 1,
+            proof,
+        },
+        Evaluation {
+            output,
+            proof: proof ^ 1,
+        },
+        Evaluation { output, proof: 0 },
+        Evaluation {
+            output: TOY_MODULUS,
+            proof,
+        },
+    ] {
+        assert!(!vdf.verify(b"seed", &forged));
+    }
+}
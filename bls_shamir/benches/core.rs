@@ -0,0 +1,135 @@
+//! The building blocks everything else is made of: pairings, hashing to G2,
+//! checking and aggregating signature shares, and multi-scalar multiplication.
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use bls_shamir::backend;
+use bls_shamir::secret::SecretPolynomial;
+use bls_shamir::signature::{hash_to_g2, DST};
+use bls_shamir::threshold::{aggregate_shares, sign_share, verify_share, PartialSignature};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use group::ff::Field;
+use group::Curve;
+
+/// `(n, t)` pairs, any `t + 1` of the `n` shares recover the signature.
+const THRESHOLDS: [(u64, usize); 4] = [(4, 2), (16, 10), (64, 42), (256, 170)];
+
+const MSM_SIZES: [usize; 6] = [4, 16, 64, 256, 1024, 4096];
+
+fn pairings(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let p = (G1Affine::generator() * Scalar::random(&mut rng)).to_affine();
+    let q = (G2Affine::generator() * Scalar::random(&mut rng)).to_affine();
+    let pq = (G1Affine::generator() * Scalar::random(&mut rng)).to_affine();
+
+    let mut group = c.benchmark_group("pairing");
+
+    group.bench_function("single", |b| b.iter(|| pairing(&p, &q)));
+
+    // e(A, B) == e(C, D), once as two full pairings and once as a single
+    // product check.
+    group.bench_function("compare_two", |b| {
+        b.iter(|| pairing(&p, &q) == pairing(&pq, &G2Affine::generator()))
+    });
+    group.bench_function("product_of_two", |b| {
+        b.iter(|| ::pairing::pairing_product_is_one(&[(p, q), (-pq, G2Affine::generator())]))
+    });
+
+    group.finish();
+}
+
+fn hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_to_g2");
+
+    for len in [32usize, 1024] {
+        let msg = vec![0x5a; len];
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &msg, |b, msg| {
+            b.iter(|| hash_to_g2(msg, DST))
+        });
+    }
+
+    group.finish();
+}
+
+fn shares(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let hm = hash_to_g2(b"Hello world", DST).to_affine();
+
+    let share = SecretPolynomial::random(1, &mut rng).evaluate(1);
+    let public_share = share.public_key();
+    let partial = sign_share(1, &share, &hm, &mut rng);
+    c.bench_function("verify_share", |b| {
+        b.iter(|| verify_share(&public_share, &hm, &partial))
+    });
+
+    let mut group = c.benchmark_group("aggregate_shares");
+    group.sample_size(20);
+
+    for (n, t) in THRESHOLDS {
+        let f = SecretPolynomial::random(t, &mut rng);
+        // Any t + 1 shares will do, take the largest indices so the
+        // Lagrange coefficients aren't computed over 1..=t+1.
+        let partials = (1..=n)
+            .rev()
+            .take(t + 1)
+            .map(|x| sign_share(x, &f.evaluate(x), &hm, &mut rng))
+            .collect::<Vec<PartialSignature>>();
+
+        group.throughput(Throughput::Elements(partials.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("n_t", format!("{}_{}", n, t)),
+            &partials,
+            |b, partials| b.iter(|| aggregate_shares(partials)),
+        );
+    }
+
+    group.finish();
+}
+
+fn msm(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let mut group = c.benchmark_group("msm");
+    group.sample_size(10);
+
+    for size in MSM_SIZES {
+        let scalars = (0..size)
+            .map(|_| Scalar::random(&mut rng))
+            .collect::<Vec<_>>();
+        let g1 = scalars
+            .iter()
+            .map(|s| (G1Affine::generator() * s).to_affine())
+            .collect::<Vec<_>>();
+        let g2 = scalars
+            .iter()
+            .map(|s| (G2Affine::generator() * s).to_affine())
+            .collect::<Vec<_>>();
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("g1", size), &size, |b, _| {
+            b.iter(|| backend::g1_msm(&g1, &scalars))
+        });
+        group.bench_with_input(BenchmarkId::new("g1_naive", size), &size, |b, _| {
+            b.iter(|| {
+                g1.iter()
+                    .zip(&scalars)
+                    .map(|(p, s)| p * s)
+                    .sum::<G1Projective>()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("g2", size), &size, |b, _| {
+            b.iter(|| backend::g2_msm(&g2, &scalars))
+        });
+        group.bench_with_input(BenchmarkId::new("g2_naive", size), &size, |b, _| {
+            b.iter(|| {
+                g2.iter()
+                    .zip(&scalars)
+                    .map(|(p, s)| p * s)
+                    .sum::<G2Projective>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, pairings, hashing, shares, msm);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Verifying a run of beacon signatures under a single group key, with and
+//! without reusing the prepared Miller loop lines of the key.
+use bls_shamir::min_sig::{self, PreparedPublicKey};
+use bls_shamir::secret::SecretKey;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use sha2::{Digest, Sha256};
+
+const ROUNDS: u64 = 32;
+
+fn beacon_signatures(c: &mut Criterion) {
+    let sk = SecretKey::random(&mut rand::thread_rng());
+    let pk = min_sig::public_key(&sk);
+
+    // Like drand's unchained beacons, round `r` signs `sha256(r)`.
+    let rounds = (1..=ROUNDS)
+        .map(|round| {
+            let msg = Sha256::digest(&round.to_be_bytes()).to_vec();
+            let sig = min_sig::sign(&sk, &msg);
+            (msg, sig)
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("beacon_verify");
+    group.throughput(Throughput::Elements(ROUNDS));
+    group.sample_size(10);
+
+    group.bench_function("unprepared", |b| {
+        b.iter(|| {
+            for (msg, sig) in &rounds {
+                assert!(min_sig::verify(&pk, msg, sig));
+            }
+        })
+    });
+
+    group.bench_function("prepared", |b| {
+        b.iter_batched(
+            || PreparedPublicKey::new(&pk),
+            |prepared| {
+                for (msg, sig) in &rounds {
+                    assert!(prepared.verify(msg, sig));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, beacon_signatures);
+criterion_main!(benches);
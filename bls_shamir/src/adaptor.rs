@@ -0,0 +1,71 @@
+//! Adaptor signatures on [`schnorr`](crate::schnorr) signatures: a
+//! pre-signature that only becomes a signature with the discrete log t of an
+//! adaptor point `T = t * G`, and that gives t away once the signature is
+//! published. The scriptless script behind atomic swaps: Alice pre-signs a
+//! payment to Bob under Bob's T, Bob completes it to get paid, and Alice
+//! reads t off the signature to unlock her side.
+//!
+//! With the nonce k, the pre-signature is
+//!
+//! R = k * G
+//! c = H(R + T, P, m)
+//! ŝ = k + c * x
+//!
+//! checked with `ŝ * G == R + c * P`. Then `(R + T, ŝ + t)` is a Schnorr
+//! signature, and `t = s - ŝ` for any such signature s.
+use crate::schnorr::{self, Signature};
+use crate::secret::SecretKey;
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+/// `(R, ŝ)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreSignature {
+    pub r: G1Affine,
+    pub s: Scalar,
+}
+
+/// Pre-signs `msg` under the adaptor point.
+pub fn pre_sign<R: RngCore + CryptoRng>(
+    sk: &SecretKey,
+    msg: &[u8],
+    adaptor: &G1Affine,
+    rng: &mut R,
+) -> PreSignature {
+    let k = Zeroizing::new(Scalar::random(&mut *rng));
+    let r = G1Projective::generator() * *k;
+    let c = schnorr::challenge(&(r + adaptor).to_affine(), &sk.public_key(), msg);
+    PreSignature {
+        r: r.to_affine(),
+        s: *k + c * sk.as_scalar(),
+    }
+}
+
+/// Checks that the pre-signature completes to a signature on `msg` with
+/// the discrete log of the adaptor point.
+pub fn pre_verify(pk: &G1Affine, msg: &[u8], adaptor: &G1Affine, pre: &PreSignature) -> bool {
+    if bool::from(pk.is_identity()) {
+        return false;
+    }
+    let c = schnorr::challenge(&(G1Projective::from(pre.r) + adaptor).to_affine(), pk, msg);
+    G1Projective::generator() * pre.s == G1Projective::from(pre.r) + pk * c
+}
+
+/// Completes the pre-signature with t, the discrete log of its adaptor
+/// point.
+pub fn adapt(pre: &PreSignature, secret: &SecretKey) -> Signature {
+    Signature {
+        r: (G1Projective::from(pre.r) + G1Affine::generator() * secret.as_scalar()).to_affine(),
+        z: pre.s + secret.as_scalar(),
+    }
+}
+
+/// Recovers t from the pre-signature and its completion, if it is the
+/// discrete log of the adaptor point.
+pub fn extract(pre: &PreSignature, signature: &Signature, adaptor: &G1Affine) -> Option<SecretKey> {
+    let secret = SecretKey::new(signature.z - pre.s);
+    (secret.public_key() == *adaptor).then_some(secret)
+}
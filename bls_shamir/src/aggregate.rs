@@ -0,0 +1,202 @@
+//! Attestation style aggregation: many committee members sign the same
+//! message, and the aggregate carries a bitfield recording who contributed.
+//!
+//! sig = ∑ sig_i
+//! apk = ∑ pk_i, for every i whose bit is set
+//!
+//! e(apk, H(m)) == e(G, sig)
+//!
+//! Summing keys is only safe when every committee key came with a proof of
+//! possession, otherwise use [`crate::multisig`] which weights the keys.
+use crate::signature;
+use bls12_381::*;
+use group::Curve;
+use std::fmt;
+
+/// A fixed length bitvector indexed by committee position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitfield {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl Bitfield {
+    pub fn new(len: usize) -> Self {
+        Bitfield {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        index < self.len && self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Sets the bit at `index`, returns false if it is out of range.
+    pub fn set(&mut self, index: usize) -> bool {
+        if index >= self.len {
+            return false;
+        }
+        self.words[index / 64] |= 1 << (index % 64);
+        true
+    }
+
+    /// Number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_disjoint(&self, other: &Bitfield) -> bool {
+        self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+    }
+
+    pub fn union(&self, other: &Bitfield) -> Bitfield {
+        Bitfield {
+            len: self.len,
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a | b)
+                .collect(),
+        }
+    }
+
+    /// Iterates over the indices of the set bits in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(move |i| self.get(*i))
+    }
+
+    /// Packs the bits little-endian into bytes, as in SSZ bitvectors.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.len.div_ceil(8)];
+        for i in self.ones() {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(len: usize, bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != len.div_ceil(8) {
+            return None;
+        }
+
+        let mut bits = Bitfield::new(len);
+        for i in 0..bytes.len() * 8 {
+            if bytes[i / 8] & (1 << (i % 8)) != 0 && !bits.set(i) {
+                // A padding bit past `len` is set.
+                return None;
+            }
+        }
+        Some(bits)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateError {
+    /// The committee position is past the end of the bitfield.
+    IndexOutOfRange,
+    /// The member already contributed, or both aggregates share a member.
+    Overlap,
+    /// The aggregates are for committees of different sizes.
+    SizeMismatch,
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateError::IndexOutOfRange => write!(f, "committee index out of range"),
+            AggregateError::Overlap => write!(f, "aggregates have overlapping participants"),
+            AggregateError::SizeMismatch => write!(f, "aggregates have different committee sizes"),
+        }
+    }
+}
+
+impl std::error::Error for AggregateError {}
+
+/// An aggregated signature and the committee members that contributed to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateWithBitfield {
+    pub bits: Bitfield,
+    pub signature: G2Affine,
+}
+
+impl AggregateWithBitfield {
+    /// An empty aggregate for a committee of the given size.
+    pub fn new(committee_size: usize) -> Self {
+        AggregateWithBitfield {
+            bits: Bitfield::new(committee_size),
+            signature: G2Affine::identity(),
+        }
+    }
+
+    /// The aggregate holding a single member's signature.
+    pub fn from_signature(
+        committee_size: usize,
+        index: usize,
+        signature: &G2Affine,
+    ) -> Result<Self, AggregateError> {
+        let mut aggregate = Self::new(committee_size);
+        aggregate.add(index, signature)?;
+        Ok(aggregate)
+    }
+
+    /// Adds the signature of the member at `index`.
+    pub fn add(&mut self, index: usize, signature: &G2Affine) -> Result<(), AggregateError> {
+        if index >= self.bits.len() {
+            return Err(AggregateError::IndexOutOfRange);
+        }
+        if self.bits.get(index) {
+            return Err(AggregateError::Overlap);
+        }
+
+        self.bits.set(index);
+        self.signature = (G2Projective::from(self.signature) + signature).to_affine();
+        Ok(())
+    }
+
+    /// Combines two aggregates with disjoint participants.
+    pub fn merge(&self, other: &AggregateWithBitfield) -> Result<Self, AggregateError> {
+        if self.bits.len() != other.bits.len() {
+            return Err(AggregateError::SizeMismatch);
+        }
+        if !self.bits.is_disjoint(&other.bits) {
+            return Err(AggregateError::Overlap);
+        }
+
+        Ok(AggregateWithBitfield {
+            bits: self.bits.union(&other.bits),
+            signature: (G2Projective::from(self.signature) + other.signature).to_affine(),
+        })
+    }
+
+    /// Number of contributing members.
+    pub fn participants(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    /// Verifies the aggregate on `msg` against the public keys of the whole
+    /// committee, in committee order.
+    pub fn verify(&self, committee: &[G1Affine], msg: &[u8]) -> bool {
+        if committee.len() != self.bits.len() || self.participants() == 0 {
+            return false;
+        }
+
+        let apk = self
+            .bits
+            .ones()
+            .map(|i| G1Projective::from(committee[i]))
+            .sum::<G1Projective>()
+            .to_affine();
+
+        signature::verify(&apk, msg, &self.signature)
+    }
+}
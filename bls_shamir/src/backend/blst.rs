@@ -0,0 +1,107 @@
+//! The blst backend. Points cross the boundary in their uncompressed encoding,
+//! the results are produced by blst so they skip the subgroup checks on the
+//! way back.
+use ::blst::*;
+use bls12_381::*;
+
+fn p1_affine(p: &G1Affine) -> blst_p1_affine {
+    let mut out = blst_p1_affine::default();
+    let err = unsafe { blst_p1_deserialize(&mut out, p.to_uncompressed().as_ptr()) };
+    debug_assert_eq!(err, BLST_ERROR::BLST_SUCCESS);
+    out
+}
+
+fn p2_affine(p: &G2Affine) -> blst_p2_affine {
+    let mut out = blst_p2_affine::default();
+    let err = unsafe { blst_p2_deserialize(&mut out, p.to_uncompressed().as_ptr()) };
+    debug_assert_eq!(err, BLST_ERROR::BLST_SUCCESS);
+    out
+}
+
+fn from_p1(p: &blst_p1) -> G1Projective {
+    let mut bytes = [0u8; 96];
+    unsafe { blst_p1_serialize(bytes.as_mut_ptr(), p) };
+    G1Affine::from_uncompressed_unchecked(&bytes)
+        .unwrap()
+        .into()
+}
+
+fn from_p2(p: &blst_p2) -> G2Projective {
+    let mut bytes = [0u8; 192];
+    unsafe { blst_p2_serialize(bytes.as_mut_ptr(), p) };
+    G2Affine::from_uncompressed_unchecked(&bytes)
+        .unwrap()
+        .into()
+}
+
+/// Scalars are passed to blst as 32 little-endian bytes each.
+fn scalar_bytes(scalars: &[Scalar]) -> Vec<u8> {
+    scalars.iter().flat_map(|s| s.to_bytes()).collect()
+}
+
+/// Hashes a message to a point in G2 under the given domain separation tag.
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Projective {
+    let mut out = blst_p2::default();
+    unsafe {
+        blst_hash_to_g2(
+            &mut out,
+            msg.as_ptr(),
+            msg.len(),
+            dst.as_ptr(),
+            dst.len(),
+            std::ptr::null(),
+            0,
+        )
+    };
+    from_p2(&out)
+}
+
+/// Returns `s * p`.
+pub fn g2_mul(p: &G2Affine, s: &Scalar) -> G2Affine {
+    let mut point = blst_p2::default();
+    let mut out = blst_p2::default();
+    unsafe {
+        blst_p2_from_affine(&mut point, &p2_affine(p));
+        blst_p2_mult(&mut out, &point, s.to_bytes().as_ptr(), 255);
+    }
+    from_p2(&out).into()
+}
+
+/// Checks `e(pk, hm) == e(G, sig)` as `e(pk, hm) * e(G, -sig) == 1` with a
+/// single final exponentiation.
+pub fn pairing_check(pk: &G1Affine, hm: &G2Affine, sig: &G2Affine) -> bool {
+    let mut left = blst_fp12::default();
+    let mut right = blst_fp12::default();
+    let mut product = blst_fp12::default();
+    let mut result = blst_fp12::default();
+
+    unsafe {
+        blst_miller_loop(&mut left, &p2_affine(hm), &p1_affine(pk));
+        blst_miller_loop(&mut right, &p2_affine(&-sig), blst_p1_affine_generator());
+        blst_fp12_mul(&mut product, &left, &right);
+        blst_final_exp(&mut result, &product);
+        blst_fp12_is_one(&result)
+    }
+}
+
+/// Computes `∑ s_i * p_i` with blst's Pippenger implementation.
+pub fn g1_msm(points: &[G1Affine], scalars: &[Scalar]) -> G1Projective {
+    let n = points.len().min(scalars.len());
+    if n == 0 {
+        return G1Projective::identity();
+    }
+
+    let points = points[..n].iter().map(p1_affine).collect::<Vec<_>>();
+    from_p1(&points.mult(&scalar_bytes(&scalars[..n]), 255))
+}
+
+/// Computes `∑ s_i * p_i` with blst's Pippenger implementation.
+pub fn g2_msm(points: &[G2Affine], scalars: &[Scalar]) -> G2Projective {
+    let n = points.len().min(scalars.len());
+    if n == 0 {
+        return G2Projective::identity();
+    }
+
+    let points = points[..n].iter().map(p2_affine).collect::<Vec<_>>();
+    from_p2(&points.mult(&scalar_bytes(&scalars[..n]), 255))
+}
@@ -0,0 +1,19 @@
+//! The curve arithmetic on the hot paths (signing, verification, aggregation
+//! and multi-scalar multiplication).
+//!
+//! By default everything runs on the pure Rust `bls12_381` crate. With the
+//! `backend-blst` feature the same functions are computed by the blst
+//! bindings instead, converting to and from `bls12_381` types at the
+//! boundary so the public API stays the same. Both backends are always
+//! reachable by name so they can be tested against each other.
+
+pub mod pure;
+
+#[cfg(feature = "backend-blst")]
+pub mod blst;
+
+#[cfg(not(feature = "backend-blst"))]
+pub use self::pure::*;
+
+#[cfg(feature = "backend-blst")]
+pub use self::blst::*;
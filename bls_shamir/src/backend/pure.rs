@@ -0,0 +1,99 @@
+//! The pure Rust backend built on the `bls12_381` crate.
+use bls12_381::hash_to_curve::*;
+use bls12_381::*;
+use group::Curve;
+
+/// Hashes a message to a point in G2 under the given domain separation tag.
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Projective {
+    <G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst)
+}
+
+/// Returns `s * p`.
+pub fn g2_mul(p: &G2Affine, s: &Scalar) -> G2Affine {
+    (p * s).to_affine()
+}
+
+/// Checks `e(pk, hm) == e(G, sig)` as `e(pk, hm) * e(-G, sig) == 1`.
+pub fn pairing_check(pk: &G1Affine, hm: &G2Affine, sig: &G2Affine) -> bool {
+    ::pairing::pairing_product_is_one(&[(*pk, *hm), (-G1Affine::generator(), *sig)])
+}
+
+/// Computes `∑ s_i * p_i` with Pippenger's bucket method.
+pub fn g1_msm(points: &[G1Affine], scalars: &[Scalar]) -> G1Projective {
+    pippenger(points, scalars)
+}
+
+/// Computes `∑ s_i * p_i` with Pippenger's bucket method.
+pub fn g2_msm(points: &[G2Affine], scalars: &[Scalar]) -> G2Projective {
+    pippenger(points, scalars)
+}
+
+/// Below this many terms the scalar multiplications are cheaper than
+/// filling and summing the buckets.
+const PIPPENGER_THRESHOLD: usize = 16;
+
+/// Pippenger's bucket method. The scalars are cut into windows of c bits
+/// and, from the top window down, every point is added to the bucket of its
+/// scalar's digit in the window. The buckets `B_j` are summed as
+/// `∑ j * B_j` with a running sum, and the result is shifted by c bits
+/// before the next window.
+///
+/// That is about `255 / c * (n + 2^(c + 1))` additions, instead of the ~255
+/// doublings and ~128 additions of each of the n scalar multiplications,
+/// and a c growing with `log n` keeps the buckets from dominating.
+///
+/// Unlike the scalar multiplications, the running time depends on the
+/// scalars.
+fn pippenger<G: Curve<Scalar = Scalar>>(points: &[G::AffineRepr], scalars: &[Scalar]) -> G {
+    let n = points.len().min(scalars.len());
+    if n < PIPPENGER_THRESHOLD {
+        return points
+            .iter()
+            .zip(scalars)
+            .fold(G::identity(), |mut acc, (p, s)| {
+                let mut term = G::identity();
+                term += p;
+                acc += term * s;
+                acc
+            });
+    }
+
+    let c = window(n);
+    let scalars = scalars[..n]
+        .iter()
+        .map(Scalar::to_bytes)
+        .collect::<Vec<_>>();
+    let mut acc = G::identity();
+    for window in (0..255usize.div_ceil(c)).rev() {
+        for _ in 0..c {
+            acc = acc.double();
+        }
+
+        let mut buckets = vec![G::identity(); (1 << c) - 1];
+        for (p, s) in points.iter().zip(&scalars) {
+            let digit = digit(s, window * c, c);
+            if digit != 0 {
+                buckets[digit - 1] += p;
+            }
+        }
+        let mut running = G::identity();
+        for bucket in buckets.iter().rev() {
+            running += bucket;
+            acc += running;
+        }
+    }
+    acc
+}
+
+/// The window size for n terms, about `ln n + 2` bits.
+fn window(n: usize) -> usize {
+    let log = (usize::BITS - n.leading_zeros()) as usize;
+    (log * 69 / 100 + 2).clamp(3, 16)
+}
+
+/// The `width` bits of the little-endian scalar from bit `start`.
+fn digit(bytes: &[u8; 32], start: usize, width: usize) -> usize {
+    (start..(start + width).min(256)).rev().fold(0, |acc, bit| {
+        (acc << 1) | ((bytes[bit / 8] >> (bit % 8)) & 1) as usize
+    })
+}
@@ -0,0 +1,105 @@
+//! Blind BLS signatures.
+//!
+//! The requester hides the message from the signer by multiplying its hash
+//! with a random scalar r:
+//!
+//! Blinded message   = r * H(m)
+//! Blinded signature = a * r * H(m)
+//! Signature         = r^-1 * a * r * H(m) = a * H(m)
+//!
+//! The result is an ordinary signature on m, and since r is uniformly random
+//! the signer learns nothing about which message it signed.
+use crate::backend;
+use crate::secret::SecretKey;
+use crate::signature::{hash_to_g2, verify_hashed, DST};
+use crate::threshold::{self, InterpolationError, PartialSignature};
+use bls12_381::*;
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+/// The secret r kept by the requester between blinding and unblinding, knowing
+/// it links the blinded message to the signature.
+pub struct BlindingFactor(Scalar);
+
+impl Zeroize for BlindingFactor {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for BlindingFactor {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// `r * H(m)`, the only thing the signer gets to see.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlindedMessage(pub G2Affine);
+
+/// `a * r * H(m)`, returned by the signer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlindSignature(pub G2Affine);
+
+/// Hashes and blinds the message, returning the factor needed to unblind.
+pub fn blind<R: RngCore + CryptoRng>(msg: &[u8], rng: &mut R) -> (BlindingFactor, BlindedMessage) {
+    let r = loop {
+        let r = Scalar::random(&mut *rng);
+        if r != Scalar::zero() {
+            break r;
+        }
+    };
+
+    let blinded = (hash_to_g2(msg, DST) * r).to_affine();
+    (BlindingFactor(r), BlindedMessage(blinded))
+}
+
+/// Signs a blinded message with the secret key.
+pub fn sign_blinded(sk: &SecretKey, msg: &BlindedMessage) -> BlindSignature {
+    BlindSignature(backend::g2_mul(&msg.0, sk.as_scalar()))
+}
+
+/// Lets the requester check the signer didn't cheat before unblinding.
+pub fn verify_blinded(pk: &G1Affine, msg: &BlindedMessage, sig: &BlindSignature) -> bool {
+    verify_hashed(pk, &msg.0, &sig.0)
+}
+
+/// Removes the blinding factor, producing a signature that verifies with
+/// [`crate::signature::verify`].
+pub fn unblind(factor: &BlindingFactor, sig: &BlindSignature) -> G2Affine {
+    // r is never zero, see `blind`.
+    let mut r_inv = factor.0.invert().unwrap();
+    let sig = backend::g2_mul(&sig.0, &r_inv);
+    r_inv.zeroize();
+    sig
+}
+
+/// Threshold variant: each share holder signs the blinded message with its
+/// share `f(x)`.
+pub fn sign_blinded_share<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    msg: &BlindedMessage,
+    rng: &mut R,
+) -> PartialSignature {
+    threshold::sign_share(index, share, &msg.0, rng)
+}
+
+/// Checks a blinded partial signature against the public share `f(x) * G`.
+pub fn verify_blinded_share(
+    public_share: &G1Affine,
+    msg: &BlindedMessage,
+    partial: &PartialSignature,
+) -> bool {
+    threshold::verify_share(public_share, &msg.0, partial)
+}
+
+/// Interpolates `t + 1` blinded partial signatures into `f(0) * r * H(m)`,
+/// which is unblinded like a single signer's signature.
+pub fn combine_blinded_shares(
+    partials: &[PartialSignature],
+) -> Result<BlindSignature, InterpolationError> {
+    threshold::aggregate_shares(partials).map(BlindSignature)
+}
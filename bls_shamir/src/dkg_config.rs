@@ -0,0 +1,160 @@
+//! The sizes a DKG runs with, checked once so the protocol doesn't have to.
+//!
+//! Two numbers that are easy to conflate set what a committee of n members
+//! withstands. The threshold t is the degree of the shared polynomial: any
+//! `t + 1` shares sign, and t colluding members learn nothing of the secret.
+//! The faults f are how many members may misbehave, crash or lie, while the
+//! ceremony still ends with a key the others can use. With a low threshold,
+//! `3t + 1 <= n`, the two are the same and f is t. A high threshold, say
+//! `t = 2n/3`, keeps more members from signing alone than misbehaving
+//! members can be tolerated, so f is smaller than t.
+//!
+//! A configuration is valid when
+//!
+//! 1 <= t               a single share doesn't give away the secret
+//! f <= t               the faulty members can't sign on their own
+//! 3f + 1 <= n          the honest members reach a quorum without the others
+//! t + f + 1 <= n       the honest members can sign without the faulty ones
+//!
+//! Agreeing on a dealing then takes `2f + 1` members, which the `n - f`
+//! honest ones are on their own, and any two such quorums share an honest
+//! member. `f + 1` qualified dealers make sure one of them is honest.
+//! Running without rounds, over reliable broadcast, also takes
+//! `t + 2f + 1 <= n`: a dealing is only settled on once `f + t + 1` members
+//! vouched for their share of it, `t + 1` honest ones that can give any
+//! member its share, and the honest members alone have to be that many.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DkgConfig {
+    members: usize,
+    threshold: usize,
+    faults: usize,
+}
+
+impl DkgConfig {
+    pub fn new(members: usize, threshold: usize, faults: usize) -> Result<Self, ConfigError> {
+        if threshold == 0 {
+            return Err(ConfigError::ZeroThreshold);
+        }
+        if faults > threshold {
+            return Err(ConfigError::FaultsAboveThreshold { faults, threshold });
+        }
+        if 3 * faults + 1 > members {
+            return Err(ConfigError::TooManyFaults { faults, members });
+        }
+        if threshold + faults + 1 > members {
+            return Err(ConfigError::Unreachable {
+                threshold,
+                faults,
+                members,
+            });
+        }
+        Ok(DkgConfig {
+            members,
+            threshold,
+            faults,
+        })
+    }
+
+    /// Tolerates as many faults as `threshold` allows, `threshold` itself
+    /// when it is low.
+    pub fn with_threshold(members: usize, threshold: usize) -> Result<Self, ConfigError> {
+        let faults = threshold
+            .min(members.saturating_sub(1) / 3)
+            .min(members.saturating_sub(threshold + 1));
+        DkgConfig::new(members, threshold, faults)
+    }
+
+    pub fn members(&self) -> usize {
+        self.members
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn faults(&self) -> usize {
+        self.faults
+    }
+
+    /// Whether it takes more than half of the members to sign, `2t + 1 > n`.
+    pub fn is_high_threshold(&self) -> bool {
+        2 * self.threshold + 1 > self.members
+    }
+
+    /// The members that have to vouch for a dealing, `2f + 1`, no more than
+    /// the honest ones.
+    pub fn quorum(&self) -> usize {
+        2 * self.faults + 1
+    }
+
+    /// The fewest dealers whose secrets make up the key, `f + 1`, so that at
+    /// least one is honest.
+    pub fn min_qualified(&self) -> usize {
+        self.faults + 1
+    }
+
+    /// The members that have to vouch for their share of a dealing before
+    /// it is settled on without rounds, `f + t + 1`.
+    pub fn recovery_quorum(&self) -> usize {
+        self.faults + self.threshold + 1
+    }
+
+    /// Whether running without rounds outlasts the faults, `t + 2f + 1 <= n`.
+    pub fn allows_asynchrony(&self) -> bool {
+        self.recovery_quorum() + self.faults <= self.members
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Every member would hold the secret.
+    ZeroThreshold,
+    /// The faulty members could sign on their own.
+    FaultsAboveThreshold { faults: usize, threshold: usize },
+    /// The honest members are too few to reach a quorum on their own.
+    TooManyFaults { faults: usize, members: usize },
+    /// The honest members are too few to sign.
+    Unreachable {
+        threshold: usize,
+        faults: usize,
+        members: usize,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroThreshold => {
+                write!(f, "a threshold of 0 gives every member the secret")
+            }
+            ConfigError::FaultsAboveThreshold { faults, threshold } => write!(
+                f,
+                "{} faulty members could sign with a threshold of {}",
+                faults, threshold
+            ),
+            ConfigError::TooManyFaults { faults, members } => write!(
+                f,
+                "tolerating {} faults takes {} members, the committee has {}",
+                faults,
+                3 * faults + 1,
+                members
+            ),
+            ConfigError::Unreachable {
+                threshold,
+                faults,
+                members,
+            } => write!(
+                f,
+                "a threshold of {} with {} faults takes {} members, the committee has {}",
+                threshold,
+                faults,
+                threshold + faults + 1,
+                members
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,285 @@
+//! Aggregatable DKG transcripts, after Gurkan, Jovanovic, Maller,
+//! Meiklejohn, Stern and Tomescu: each dealer publishes a PVSS transcript of
+//! its secret, and any two transcripts add up into one of the same size for
+//! the sum of their secrets. The transcript of a whole DKG is checked once,
+//! at a cost that hardly grows with the number of dealers, where checking
+//! every dealing on its own takes a loop over the dealers.
+//!
+//! Member i has the encryption key `ek_i = dk_i * Ĝ` in G2. For a polynomial
+//! p of degree t, a transcript holds
+//!
+//! A_i = p(i) * G       for i from 0 to n
+//! Y_i = p(i) * ek_i    for i from 1 to n
+//!
+//! and, for each dealer j, its commitment `C_j = s_j * G` with a proof of
+//! knowledge of s_j bound to j, and the weight `w_j` it was added with.
+//! Aggregating adds up the A_i and Y_i and merges the contributions. A
+//! transcript is valid when
+//!
+//! ∑ λ_i * f(i) * A_i == 0    the A_i lie on a polynomial of degree t
+//! e(A_i, ek_i) == e(G, Y_i)  each Y_i encrypts the p(i) of A_i
+//! A_0 == ∑ w_j * C_j         the secret is the dealers' secrets added up
+//!
+//! with `λ_i = ∏_{j != i} 1 / (i - j)` over the points 0 to n and f of
+//! degree `n - t - 1` hashed from the transcript, as in `pvss`. The pairing
+//! equations are checked all at once with random weights, in one
+//! multi-pairing of `n + 1` terms. Only the last equation and the proofs
+//! depend on the dealers, an addition and a Schnorr proof each, and the
+//! proofs keep a dealer from picking its `C_j` to cancel the others'.
+//!
+//! Member i decrypts its share `dk_i^-1 * Y_i = p(i) * Ĝ`, which anyone
+//! checks with `e(A_i, Ĝ) == e(G, p(i) * Ĝ)`, and the group key is A_0. The
+//! shares are points of G2 rather than scalars, so they don't sign like
+//! the shares of the network DKG: they fit schemes that only pair with the
+//! secret, such as the VUF of the paper.
+use crate::secret::{SecretKey, SecretPolynomial};
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sigma::schnorr;
+use sigma::transcript::Transcript;
+use sigma::Proof;
+
+const DOMAIN: &[u8] = b"zk-lab/bls_shamir/dkg_transcript";
+const PROOF_TAG: &[u8] = b"zk-lab/bls_shamir/dkg_transcript/contribution";
+
+/// A dealer's part in a transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contribution {
+    pub dealer: u64,
+    /// How many times the dealer's transcript was added in.
+    pub weight: u64,
+    /// `s_j * G`
+    pub commitment: G1Affine,
+    /// Of knowledge of `s_j`, bound to the dealer.
+    pub proof: Proof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkgTranscript {
+    /// `A_0` to `A_n`, the group key then the public shares.
+    pub commitments: Vec<G1Affine>,
+    /// `Y_1` to `Y_n`.
+    pub encrypted: Vec<G2Affine>,
+    /// Sorted by dealer.
+    pub contributions: Vec<Contribution>,
+}
+
+/// `dk * Ĝ`, what shares are encrypted to.
+pub fn encryption_key(dk: &SecretKey) -> G2Affine {
+    (G2Affine::generator() * dk.as_scalar()).to_affine()
+}
+
+/// The transcript of dealer `dealer` for a random secret of degree
+/// `threshold`, member `i + 1` holding `keys[i]`.
+pub fn deal<R: RngCore + CryptoRng>(
+    dealer: u64,
+    threshold: usize,
+    keys: &[G2Affine],
+    context: &[u8],
+    rng: &mut R,
+) -> DkgTranscript {
+    let polynomial = SecretPolynomial::random(threshold, rng);
+    let commitments = (0..=keys.len() as u64)
+        .map(|x| polynomial.evaluate(x).public_key())
+        .collect::<Vec<_>>();
+    let encrypted = keys
+        .iter()
+        .zip(1..)
+        .map(|(key, x)| (key * polynomial.evaluate(x).as_scalar()).to_affine())
+        .collect();
+    let proof = schnorr::prove::<G1Projective, _>(
+        polynomial.secret().as_scalar(),
+        &proof_context(context, dealer),
+        rng,
+    );
+    DkgTranscript {
+        contributions: vec![Contribution {
+            dealer,
+            weight: 1,
+            commitment: commitments[0],
+            proof,
+        }],
+        commitments,
+        encrypted,
+    }
+}
+
+/// Adds up two transcripts for the same members into one, `None` if they
+/// are for different members or give one dealer two commitments.
+pub fn aggregate(a: &DkgTranscript, b: &DkgTranscript) -> Option<DkgTranscript> {
+    if a.commitments.len() != b.commitments.len() || a.encrypted.len() != b.encrypted.len() {
+        return None;
+    }
+    let commitments = a
+        .commitments
+        .iter()
+        .zip(&b.commitments)
+        .map(|(x, y)| (G1Projective::from(x) + y).to_affine())
+        .collect();
+    let encrypted = a
+        .encrypted
+        .iter()
+        .zip(&b.encrypted)
+        .map(|(x, y)| (G2Projective::from(x) + y).to_affine())
+        .collect();
+
+    let mut contributions = a.contributions.clone();
+    for contribution in &b.contributions {
+        match contributions
+            .iter_mut()
+            .find(|known| known.dealer == contribution.dealer)
+        {
+            Some(known) if known.commitment == contribution.commitment => {
+                known.weight = known.weight.checked_add(contribution.weight)?;
+            }
+            Some(_) => return None,
+            None => contributions.push(*contribution),
+        }
+    }
+    contributions.sort_by_key(|contribution| contribution.dealer);
+    Some(DkgTranscript {
+        commitments,
+        encrypted,
+        contributions,
+    })
+}
+
+/// Checks that the transcript shares, between the holders of `keys`, a
+/// secret of degree `threshold` made of the secrets of its dealers.
+pub fn verify(
+    threshold: usize,
+    keys: &[G2Affine],
+    transcript: &DkgTranscript,
+    context: &[u8],
+) -> bool {
+    let n = keys.len();
+    let dealers_sorted = transcript
+        .contributions
+        .windows(2)
+        .all(|pair| pair[0].dealer < pair[1].dealer);
+    if n < threshold + 1
+        || transcript.commitments.len() != n + 1
+        || transcript.encrypted.len() != n
+        || transcript.contributions.is_empty()
+        || !dealers_sorted
+        || keys.iter().any(|key| bool::from(key.is_identity()))
+    {
+        return false;
+    }
+
+    // The dealers, one addition and one proof each.
+    let mut secret = G1Projective::identity();
+    for contribution in &transcript.contributions {
+        let proven = schnorr::verify(
+            &G1Projective::from(contribution.commitment),
+            &proof_context(context, contribution.dealer),
+            &contribution.proof,
+        );
+        if contribution.weight == 0 || !proven {
+            return false;
+        }
+        secret += contribution.commitment * Scalar::from(contribution.weight);
+    }
+    if secret.to_affine() != transcript.commitments[0] {
+        return false;
+    }
+
+    let mut hash = Transcript::new(DOMAIN);
+    hash.append_message(b"context", context);
+    hash.append_u64(b"t", threshold as u64);
+    for key in keys {
+        hash.append_point(b"ek", key);
+    }
+    for a in &transcript.commitments {
+        hash.append_point(b"A", a);
+    }
+    for y in &transcript.encrypted {
+        hash.append_point(b"Y", y);
+    }
+
+    // The degree, over the points 0 to n.
+    let f = (0..n - threshold)
+        .map(|_| hash.challenge_scalar(b"f"))
+        .collect::<Vec<_>>();
+    let sum = transcript
+        .commitments
+        .iter()
+        .zip(dual_weights(n))
+        .zip(0..)
+        .map(|((a, lambda), x)| a * (lambda * evaluate(&f, x)))
+        .sum::<G1Projective>();
+    if !bool::from(sum.is_identity()) {
+        return false;
+    }
+
+    // ∏ e(r_i * A_i, ek_i) * e(-G, ∑ r_i * Y_i) == 1
+    let r = (0..n)
+        .map(|_| hash.challenge_scalar(b"r"))
+        .collect::<Vec<_>>();
+    let scaled = transcript.commitments[1..]
+        .iter()
+        .zip(&r)
+        .map(|(a, r)| (a * r).to_affine());
+    let combined = transcript
+        .encrypted
+        .iter()
+        .zip(&r)
+        .map(|(y, r)| y * r)
+        .sum::<G2Projective>()
+        .to_affine();
+    let generator = -G1Affine::generator();
+    let terms = scaled
+        .into_iter()
+        .zip(keys.iter().copied())
+        .chain(Some((generator, combined)))
+        .collect::<Vec<_>>();
+    ::pairing::pairing_product_is_one(&terms)
+}
+
+/// Member `index` decrypts its share `p(index) * Ĝ`, `None` if the
+/// transcript has no share for it.
+pub fn decrypt(dk: &SecretKey, index: u64, transcript: &DkgTranscript) -> Option<G2Affine> {
+    let encrypted = transcript.encrypted.get(index.checked_sub(1)? as usize)?;
+    let dk_inv = Option::<Scalar>::from(dk.as_scalar().invert())?;
+    Some((encrypted * dk_inv).to_affine())
+}
+
+/// Checks a decrypted share against the public share `A_index`.
+pub fn verify_share(transcript: &DkgTranscript, index: u64, share: &G2Affine) -> bool {
+    match transcript.commitments.get(index as usize) {
+        Some(public) if index > 0 => {
+            pairing(public, &G2Affine::generator()) == pairing(&G1Affine::generator(), share)
+        }
+        _ => false,
+    }
+}
+
+/// What a dealer's proof is bound to.
+fn proof_context(context: &[u8], dealer: u64) -> Vec<u8> {
+    [PROOF_TAG, context, &dealer.to_be_bytes()].concat()
+}
+
+/// `λ_i = ∏_{j != i} 1 / (i - j)` for i and j from 0 to n.
+fn dual_weights(n: usize) -> Vec<Scalar> {
+    (0..=n as u64)
+        .map(|i| {
+            let product = (0..=n as u64)
+                .filter(|j| *j != i)
+                .fold(Scalar::one(), |acc, j| {
+                    acc * (Scalar::from(i) - Scalar::from(j))
+                });
+            // The points are distinct, so the product is never zero.
+            product.invert().unwrap()
+        })
+        .collect()
+}
+
+fn evaluate(coefficients: &[Scalar], x: u64) -> Scalar {
+    let x = Scalar::from(x);
+    // Horner's method.
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |acc, a| acc * x + a)
+}
@@ -0,0 +1,116 @@
+//! Hierarchical key derivation for BLS12-381 secret keys, as specified by
+//! EIP-2333.
+//!
+//! The tree is rooted at a master key derived from a seed, and every node has
+//! 2^32 children. A child key is derived from its parent by turning the parent
+//! into a Lamport public key (a hash-only construction), so knowing a child
+//! key reveals nothing about its parent or its siblings.
+use crate::secret::SecretKey;
+use bls12_381::Scalar;
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+/// The minimum seed length accepted by [`derive_master_sk`].
+pub const MIN_SEED_LEN: usize = 32;
+
+/// `ceil((3 * ceil(log2(r))) / 16)`, the number of bytes expanded by HKDF
+/// before reducing modulo `r`.
+const HKDF_MOD_R_L: usize = 48;
+
+/// Number of 32-byte chunks in each half of the Lamport secret key.
+const LAMPORT_CHUNKS: usize = 255;
+
+/// Derives the master secret key from a seed of at least 32 bytes.
+///
+/// Returns `None` if the seed is too short.
+pub fn derive_master_sk(seed: &[u8]) -> Option<SecretKey> {
+    if seed.len() < MIN_SEED_LEN {
+        return None;
+    }
+
+    Some(hkdf_mod_r(seed, b""))
+}
+
+/// Derives the child secret key at `index` from its parent secret key.
+pub fn derive_child_sk(parent: &SecretKey, index: u32) -> SecretKey {
+    let compressed_lamport_pk = parent_sk_to_lamport_pk(parent, index);
+    hkdf_mod_r(&compressed_lamport_pk, b"")
+}
+
+/// Walks `path` starting at the master key of `seed`, e.g. the EIP-2334
+/// signing key path `m/12381/3600/i/0` is `[12381, 3600, i, 0]`.
+pub fn derive_sk_from_path(seed: &[u8], path: &[u32]) -> Option<SecretKey> {
+    let master = derive_master_sk(seed)?;
+    Some(
+        path.iter()
+            .fold(master, |sk, index| derive_child_sk(&sk, *index)),
+    )
+}
+
+/// Hashes the input keying material into a non-zero scalar.
+fn hkdf_mod_r(ikm: &[u8], key_info: &[u8]) -> SecretKey {
+    let mut salt = b"BLS-SIG-KEYGEN-SALT-".to_vec();
+
+    loop {
+        salt = Sha256::digest(&salt).to_vec();
+
+        // PRK = HKDF-Extract(salt, IKM || I2OSP(0, 1))
+        let mut ikm = Zeroizing::new(ikm.to_vec());
+        ikm.push(0);
+        let hk = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+
+        // OKM = HKDF-Expand(PRK, key_info || I2OSP(L, 2), L)
+        let mut okm = Zeroizing::new([0u8; HKDF_MOD_R_L]);
+        hk.expand_multi_info(
+            &[key_info, &(HKDF_MOD_R_L as u16).to_be_bytes()],
+            &mut okm[..],
+        )
+        .expect("48 bytes is a valid HKDF output length");
+
+        // SK = OS2IP(OKM) mod r, the OKM is big-endian while `from_bytes_wide`
+        // expects a little-endian integer.
+        let mut wide = Zeroizing::new([0u8; 64]);
+        for (dst, src) in wide.iter_mut().zip(okm.iter().rev()) {
+            *dst = *src;
+        }
+        let sk = SecretKey::new(Scalar::from_bytes_wide(&wide));
+
+        if sk.as_scalar() != &Scalar::zero() {
+            return sk;
+        }
+    }
+}
+
+/// Expands the input keying material into the 255 chunks of a Lamport
+/// secret key.
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Zeroizing<Vec<u8>> {
+    let hk = Hkdf::<Sha256>::new(Some(salt), ikm);
+    let mut okm = Zeroizing::new(vec![0u8; 32 * LAMPORT_CHUNKS]);
+    hk.expand(b"", &mut okm)
+        .expect("8160 bytes is a valid HKDF output length");
+    okm
+}
+
+/// Computes the compressed Lamport public key of `parent` for the given
+/// child index.
+fn parent_sk_to_lamport_pk(parent: &SecretKey, index: u32) -> [u8; 32] {
+    let salt = index.to_be_bytes();
+
+    // I2OSP(parent_SK, 32), `to_bytes` is little-endian.
+    let mut ikm = parent.as_scalar().to_bytes();
+    ikm.reverse();
+    let mut not_ikm = ikm.map(|b| !b);
+
+    let lamport_0 = ikm_to_lamport_sk(&ikm, &salt);
+    let lamport_1 = ikm_to_lamport_sk(&not_ikm, &salt);
+    ikm.zeroize();
+    not_ikm.zeroize();
+
+    let mut hasher = Sha256::new();
+    for chunk in lamport_0.chunks_exact(32).chain(lamport_1.chunks_exact(32)) {
+        hasher.update(Sha256::digest(chunk));
+    }
+
+    hasher.finalize().into()
+}
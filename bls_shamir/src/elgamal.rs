@@ -0,0 +1,160 @@
+//! Hashed ElGamal encryption under the group key, decrypted by the share
+//! holders together.
+//!
+//! To encrypt M under `P = s * G`, pick a random r:
+//!
+//! U   = r * G
+//! C   = M ⊕ H1(r * P)
+//! Tag = H2(r * P, U, C)
+//!
+//! Whoever knows s computes `s * U = r * P` and unmasks M, after checking the
+//! tag so that a tampered ciphertext is rejected instead of decrypting to
+//! garbage.
+//!
+//! In the threshold variant no one knows s. The holder of share `s_i`
+//! publishes the decryption share `D_i = s_i * U` with a Chaum-Pedersen proof
+//! that `log_G(s_i * G) = log_U(D_i)`, so a wrong share is caught before it
+//! is used. Any `t + 1` valid shares are combined with Lagrange interpolation
+//! into `s * U`, exactly like partial signatures.
+use crate::secret::SecretKey;
+use crate::threshold;
+use bls12_381::*;
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use sigma::dleq;
+use zeroize::Zeroizing;
+
+const H1_TAG: &[u8] = b"ZK_LAB_ELGAMAL_H1_POINT_TO_MASK";
+const H2_TAG: &[u8] = b"ZK_LAB_ELGAMAL_H2_TAG";
+const DLEQ_CONTEXT: &[u8] = b"ZK_LAB_ELGAMAL_DECRYPTION_SHARE";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ciphertext {
+    pub u: G1Affine,
+    pub c: Vec<u8>,
+    pub tag: [u8; 32],
+}
+
+/// A proof that two points are multiples of two bases by the same scalar.
+pub use sigma::Proof as DleqProof;
+
+/// `s_i * U`, produced by the holder of share `index`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecryptionShare {
+    pub index: u64,
+    pub point: G1Affine,
+    pub proof: DleqProof,
+}
+
+/// Encrypts `msg` to the holder, or the holders, of the key of `pk`.
+pub fn encrypt<R: RngCore + CryptoRng>(pk: &G1Affine, msg: &[u8], rng: &mut R) -> Ciphertext {
+    let r = Zeroizing::new(Scalar::random(&mut *rng));
+    let u = (G1Affine::generator() * *r).to_affine();
+    let shared = (pk * *r).to_affine();
+
+    let mut c = h1(&shared, msg.len());
+    xor(&mut c, msg);
+    let tag = h2(&shared, &u, &c);
+
+    Ciphertext { u, c, tag }
+}
+
+/// Decrypts with the whole key, returns `None` if the ciphertext wasn't
+/// encrypted to it or was tampered with.
+pub fn decrypt(sk: &SecretKey, ct: &Ciphertext) -> Option<Vec<u8>> {
+    open(&(ct.u * sk.as_scalar()).to_affine(), ct)
+}
+
+/// The share holder's decryption share of the ciphertext, with its proof.
+pub fn decryption_share<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    ct: &Ciphertext,
+    rng: &mut R,
+) -> DecryptionShare {
+    DecryptionShare {
+        index,
+        point: (ct.u * share.as_scalar()).to_affine(),
+        proof: dleq::prove(
+            share.as_scalar(),
+            &G1Projective::generator(),
+            &G1Projective::from(ct.u),
+            DLEQ_CONTEXT,
+            rng,
+        ),
+    }
+}
+
+/// Checks a decryption share against the public share `s_i * G` of its
+/// holder.
+pub fn verify_share(public_share: &G1Affine, ct: &Ciphertext, share: &DecryptionShare) -> bool {
+    dleq::verify(
+        &G1Projective::generator(),
+        &G1Projective::from(public_share),
+        &G1Projective::from(ct.u),
+        &G1Projective::from(share.point),
+        DLEQ_CONTEXT,
+        &share.proof,
+    )
+}
+
+/// Combines `t + 1` valid decryption shares and decrypts, returns `None` if
+/// the ciphertext was tampered with or two shares have the same index.
+pub fn combine(shares: &[DecryptionShare], ct: &Ciphertext) -> Option<Vec<u8>> {
+    let points = shares
+        .iter()
+        .map(|share| (share.index, G1Projective::from(share.point)))
+        .collect::<Vec<_>>();
+    open(
+        &threshold::interpolate_at_zero(&points).ok()?.to_affine(),
+        ct,
+    )
+}
+
+/// Unmasks the message with `r * P`, once the tag checks out.
+fn open(shared: &G1Affine, ct: &Ciphertext) -> Option<Vec<u8>> {
+    if h2(shared, &ct.u, &ct.c) != ct.tag {
+        return None;
+    }
+    let mut msg = h1(shared, ct.c.len());
+    xor(&mut msg, &ct.c);
+    Some(msg)
+}
+
+/// H1: G1 -> {0, 1}^len, SHA-256 in counter mode.
+fn h1(shared: &G1Affine, len: usize) -> Vec<u8> {
+    let shared = shared.to_compressed();
+    let mut out = Vec::with_capacity(len + 32);
+    let mut counter = 0u64;
+    while out.len() < len {
+        out.extend_from_slice(
+            &Sha256::new()
+                .chain(H1_TAG)
+                .chain(counter.to_be_bytes())
+                .chain(shared)
+                .finalize(),
+        );
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+/// H2: G1 x G1 x {0, 1}^* -> {0, 1}^256
+fn h2(shared: &G1Affine, u: &G1Affine, c: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain(H2_TAG)
+        .chain(shared.to_compressed())
+        .chain(u.to_compressed())
+        .chain(c)
+        .finalize()
+        .into()
+}
+
+fn xor(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
@@ -0,0 +1,265 @@
+//! Verifiable encryption of a discrete log, to escrow a share: a ciphertext
+//! under an escrow agent's key `Y = y * G` that anyone can check holds the
+//! x of a public `X = x * G`, without learning x.
+//!
+//! ElGamal in the exponent hides x but only decrypts to `x * G`, so x is
+//! split into [`CHUNKS`] chunks of [`CHUNK_BITS`] bits, `x = ∑ 2^(16 * j) * x_j`,
+//! each encrypted on its own
+//!
+//! U_j = r_j * G
+//! V_j = x_j * G + r_j * Y
+//!
+//! and the agent recovers each `x_j` from `V_j - y * U_j` with a baby-step
+//! giant-step search over 2^16 values. The proof is a Schnorr proof of
+//! knowledge of the `x_j` and `r_j` behind every `(U_j, V_j)` with
+//! `∑ 2^(16 * j) * x_j * G = X`, one challenge for all of them, and a
+//! Bulletproofs range proof that every `V_j`, a Pedersen commitment under
+//! `(G, Y)`, is to a value below 2^16. Without the range proof a chunk
+//! could be anything and the agent couldn't find it.
+use crate::secret::SecretKey;
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sigma::pedersen::{Commitment, Generators};
+use sigma::range::{RangeGenerators, RangeProof};
+use sigma::transcript::Transcript;
+use std::collections::HashMap;
+use zeroize::Zeroizing;
+
+/// The bits of a chunk.
+pub const CHUNK_BITS: usize = 16;
+/// The chunks of a scalar.
+pub const CHUNKS: usize = 16;
+
+const DOMAIN: &[u8] = b"zk-lab/bls_shamir/escrow";
+
+/// `(U_j, V_j)` for each chunk, least significant first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub chunks: Vec<(G1Affine, G1Affine)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub challenge: Scalar,
+    /// For `x_j` and `r_j`, chunk by chunk.
+    pub responses: Vec<(Scalar, Scalar)>,
+    pub range: RangeProof,
+}
+
+/// Encrypts the secret to the escrow key, with the proof that it is the
+/// discrete log of `secret.public_key()`.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    escrow: &G1Affine,
+    secret: &SecretKey,
+    context: &[u8],
+    rng: &mut R,
+) -> (Ciphertext, Proof) {
+    let bytes = Zeroizing::new(secret.as_scalar().to_bytes());
+    let values = Zeroizing::new(
+        bytes
+            .chunks(CHUNK_BITS / 8)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]) as u64)
+            .collect::<Vec<_>>(),
+    );
+    let openings = Zeroizing::new(
+        values
+            .iter()
+            .map(|value| (*value, Scalar::random(&mut *rng)))
+            .collect::<Vec<_>>(),
+    );
+    let (range, commitments) = RangeProof::prove(
+        &range_generators(escrow),
+        &openings,
+        CHUNK_BITS,
+        context,
+        rng,
+    )
+    .expect("The chunks are in range and fit the generators");
+    let ciphertext = Ciphertext {
+        chunks: openings
+            .iter()
+            .zip(&commitments)
+            .map(|((_, r), v)| ((G1Affine::generator() * r).to_affine(), v.0.to_affine()))
+            .collect(),
+    };
+
+    let nonces = Zeroizing::new(
+        (0..CHUNKS)
+            .map(|_| (Scalar::random(&mut *rng), Scalar::random(&mut *rng)))
+            .collect::<Vec<_>>(),
+    );
+    let commitments = nonces
+        .iter()
+        .map(|(k_x, k_r)| {
+            (
+                G1Projective::generator() * k_r,
+                G1Projective::generator() * k_x + escrow * k_r,
+            )
+        })
+        .collect::<Vec<_>>();
+    let k = G1Projective::generator() * recombine(nonces.iter().map(|(k_x, _)| *k_x));
+    let challenge = challenge(
+        escrow,
+        &secret.public_key(),
+        &ciphertext,
+        &commitments,
+        &k,
+        context,
+    );
+    let responses = nonces
+        .iter()
+        .zip(openings.iter())
+        .map(|((k_x, k_r), (x, r))| (k_x - challenge * Scalar::from(*x), k_r - challenge * r))
+        .collect();
+    (
+        ciphertext,
+        Proof {
+            challenge,
+            responses,
+            range,
+        },
+    )
+}
+
+/// Checks that the ciphertext encrypts the discrete log of `public` to the
+/// escrow key.
+pub fn verify(
+    escrow: &G1Affine,
+    public: &G1Affine,
+    ciphertext: &Ciphertext,
+    context: &[u8],
+    proof: &Proof,
+) -> bool {
+    if bool::from(escrow.is_identity())
+        || ciphertext.chunks.len() != CHUNKS
+        || proof.responses.len() != CHUNKS
+    {
+        return false;
+    }
+    let c = proof.challenge;
+    // A_j = z_r * G + c * U_j, B_j = z_x * G + z_r * Y + c * V_j
+    let commitments = ciphertext
+        .chunks
+        .iter()
+        .zip(&proof.responses)
+        .map(|((u, v), (z_x, z_r))| {
+            (
+                G1Projective::generator() * z_r + u * c,
+                G1Projective::generator() * z_x + escrow * z_r + v * c,
+            )
+        })
+        .collect::<Vec<_>>();
+    // K = (∑ 2^(16 * j) * z_x) * G + c * X
+    let k = G1Projective::generator() * recombine(proof.responses.iter().map(|(z_x, _)| *z_x))
+        + public * c;
+    if challenge(escrow, public, ciphertext, &commitments, &k, context) != c {
+        return false;
+    }
+    let values = ciphertext
+        .chunks
+        .iter()
+        .map(|(_, v)| Commitment(v.into()))
+        .collect::<Vec<_>>();
+    proof
+        .range
+        .verify(&range_generators(escrow), &values, CHUNK_BITS, context)
+        .is_ok()
+}
+
+/// The escrow agent's decryption, `None` unless it gives the discrete log
+/// of `public`.
+pub fn decrypt(
+    escrow: &SecretKey,
+    public: &G1Affine,
+    ciphertext: &Ciphertext,
+) -> Option<SecretKey> {
+    if ciphertext.chunks.len() != CHUNKS {
+        return None;
+    }
+    let search = Search::new();
+    let chunks = ciphertext
+        .chunks
+        .iter()
+        .map(|(u, v)| search.find(G1Projective::from(v) - u * escrow.as_scalar()))
+        .collect::<Option<Vec<_>>>()?;
+    let secret = SecretKey::new(recombine(chunks.into_iter().map(Scalar::from)));
+    (secret.public_key() == *public).then_some(secret)
+}
+
+/// `∑ 2^(16 * j) * a_j`
+fn recombine(chunks: impl DoubleEndedIterator<Item = Scalar>) -> Scalar {
+    let shift = Scalar::from(1 << CHUNK_BITS);
+    chunks
+        .rev()
+        .fold(Scalar::zero(), |sum, chunk| sum * shift + chunk)
+}
+
+fn range_generators(escrow: &G1Affine) -> RangeGenerators {
+    let pedersen = Generators {
+        g: G1Projective::generator(),
+        h: escrow.into(),
+    };
+    RangeGenerators::new(pedersen, CHUNK_BITS * CHUNKS)
+}
+
+fn challenge(
+    escrow: &G1Affine,
+    public: &G1Affine,
+    ciphertext: &Ciphertext,
+    commitments: &[(G1Projective, G1Projective)],
+    k: &G1Projective,
+    context: &[u8],
+) -> Scalar {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_message(b"context", context);
+    transcript.append_point(b"Y", escrow);
+    transcript.append_point(b"X", public);
+    for (u, v) in &ciphertext.chunks {
+        transcript.append_point(b"U", u);
+        transcript.append_point(b"V", v);
+    }
+    for (a, b) in commitments {
+        transcript.append_point(b"A", a);
+        transcript.append_point(b"B", b);
+    }
+    transcript.append_point(b"K", k);
+    transcript.challenge_scalar(b"c")
+}
+
+/// Baby-step giant-step for discrete logs below `2^CHUNK_BITS`.
+struct Search {
+    /// `i * G` to i, for i below `2^(CHUNK_BITS / 2)`.
+    baby_steps: HashMap<[u8; 48], u64>,
+    /// `2^(CHUNK_BITS / 2) * G`
+    giant_step: G1Projective,
+}
+
+impl Search {
+    fn new() -> Self {
+        let steps = 1u64 << (CHUNK_BITS / 2);
+        let mut baby_steps = HashMap::with_capacity(steps as usize);
+        let mut point = G1Projective::identity();
+        for i in 0..steps {
+            baby_steps.insert(point.to_affine().to_compressed(), i);
+            point += G1Projective::generator();
+        }
+        Search {
+            baby_steps,
+            giant_step: point,
+        }
+    }
+
+    /// The `a < 2^CHUNK_BITS` with `a * G = point`.
+    fn find(&self, mut point: G1Projective) -> Option<u64> {
+        let steps = 1u64 << (CHUNK_BITS / 2);
+        for j in 0..steps {
+            if let Some(i) = self.baby_steps.get(&point.to_affine().to_compressed()) {
+                return Some(j * steps + i);
+            }
+            point -= self.giant_step;
+        }
+        None
+    }
+}
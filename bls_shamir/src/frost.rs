@@ -0,0 +1,279 @@
+//! FROST, threshold Schnorr signatures in two rounds, after Komlo and
+//! Goldberg, on the shares of a DKG: any `t + 1` holders of `(i, s_i)`
+//! produce a [`schnorr`](crate::schnorr) signature under the group key.
+//!
+//! In the first round, which can be run ahead of time, each signer draws
+//! two nonces `d_i, e_i` and publishes `D_i = d_i * G` and `E_i = e_i * G`.
+//! Once the message m and the set S of signers are known, everyone
+//! computes the binding factors and the group commitment
+//!
+//! ρ_i = H(i, P, m, (j, D_j, E_j) for j in S)
+//! R   = ∑ D_j + ρ_j * E_j
+//! c   = H(R, P, m)
+//!
+//! and in the second round signer i sends
+//!
+//! z_i = d_i + ρ_i * e_i + λ_i * s_i * c
+//!
+//! with λ_i its Lagrange coefficient at zero in S. A share is checked with
+//! `z_i * G == D_i + ρ_i * E_i + c * λ_i * Y_i` against the public share
+//! `Y_i = s_i * G`, and `(R, ∑ z_i)` is a plain Schnorr signature. The
+//! binding factors tie every nonce to the message and the whole set of
+//! commitments, which is what stops a signer from choosing its own after
+//! seeing the others' and forging with concurrent sessions. Nonces must
+//! never be used twice, [`SigningNonces`] is consumed by [`sign`].
+use crate::schnorr::{self, Signature};
+use crate::secret::SecretKey;
+use crate::threshold::{lagrange_at_zero, InterpolationError};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use std::fmt;
+use zeroize::Zeroizing;
+
+const BINDING_TAG: &[u8] = b"zk-lab/bls_shamir/frost/binding";
+
+/// A signer's `d_i` and `e_i`, for one signature only.
+pub struct SigningNonces {
+    hiding: Zeroizing<Scalar>,
+    binding: Zeroizing<Scalar>,
+    commitment: SigningCommitment,
+}
+
+/// `(i, D_i, E_i)`, published in the first round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningCommitment {
+    pub index: u64,
+    pub hiding: G1Affine,
+    pub binding: G1Affine,
+}
+
+/// `(i, z_i)`, sent in the second round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureShare {
+    pub index: u64,
+    pub z: Scalar,
+}
+
+impl SigningNonces {
+    pub fn commitment(&self) -> SigningCommitment {
+        self.commitment
+    }
+}
+
+impl fmt::Debug for SigningNonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SigningNonces({:?})", self.commitment.index)
+    }
+}
+
+/// The first round: draws the nonces of the holder of share `index`.
+pub fn commit<R: RngCore + CryptoRng>(index: u64, rng: &mut R) -> SigningNonces {
+    let hiding = Zeroizing::new(Scalar::random(&mut *rng));
+    let binding = Zeroizing::new(Scalar::random(&mut *rng));
+    let commitment = SigningCommitment {
+        index,
+        hiding: (G1Affine::generator() * *hiding).to_affine(),
+        binding: (G1Affine::generator() * *binding).to_affine(),
+    };
+    SigningNonces {
+        hiding,
+        binding,
+        commitment,
+    }
+}
+
+/// The second round: signs `msg` under the group key `pk` with the share
+/// `s_i`, the commitments of the signers being in `commitments`.
+pub fn sign(
+    share: &SecretKey,
+    nonces: SigningNonces,
+    pk: &G1Affine,
+    msg: &[u8],
+    commitments: &[SigningCommitment],
+) -> Result<SignatureShare, FrostError> {
+    let index = nonces.commitment.index;
+    let session = Session::new(pk, msg, commitments)?;
+    let position = session.position(index)?;
+    if commitments[position] != nonces.commitment {
+        return Err(FrostError::Nonces { index });
+    }
+    Ok(SignatureShare {
+        index,
+        z: *nonces.hiding
+            + session.binding_factors[position] * *nonces.binding
+            + session.lagrange[position] * share.as_scalar() * session.challenge,
+    })
+}
+
+/// Checks the share of a signer against its public share `s_i * G`.
+pub fn verify_share(
+    public_share: &G1Affine,
+    pk: &G1Affine,
+    msg: &[u8],
+    commitments: &[SigningCommitment],
+    share: &SignatureShare,
+) -> bool {
+    let Ok(session) = Session::new(pk, msg, commitments) else {
+        return false;
+    };
+    let Ok(position) = session.position(share.index) else {
+        return false;
+    };
+    // z_i * G == D_i + ρ_i * E_i + c * λ_i * Y_i
+    G1Affine::generator() * share.z
+        == session.commitment_share(position)
+            + public_share * (session.challenge * session.lagrange[position])
+}
+
+/// Adds up the shares of every signer of `commitments` into the signature.
+/// Check the shares with [`verify_share`] to find the culprit when the
+/// result doesn't verify.
+pub fn aggregate(
+    pk: &G1Affine,
+    msg: &[u8],
+    commitments: &[SigningCommitment],
+    shares: &[SignatureShare],
+) -> Result<Signature, FrostError> {
+    let session = Session::new(pk, msg, commitments)?;
+    let mut z = Scalar::zero();
+    for commitment in commitments {
+        let mut matching = shares.iter().filter(|s| s.index == commitment.index);
+        match (matching.next(), matching.next()) {
+            (Some(share), None) => z += share.z,
+            (None, _) => {
+                return Err(FrostError::Missing {
+                    index: commitment.index,
+                })
+            }
+            (Some(_), Some(_)) => {
+                return Err(FrostError::Index {
+                    index: commitment.index,
+                })
+            }
+        }
+    }
+    if let Some(share) = shares.iter().find(|s| session.position(s.index).is_err()) {
+        return Err(FrostError::Unknown { index: share.index });
+    }
+    Ok(Signature {
+        r: session.group_commitment,
+        z,
+    })
+}
+
+/// What everyone derives from the message and the commitments.
+struct Session<'a> {
+    commitments: &'a [SigningCommitment],
+    binding_factors: Vec<Scalar>,
+    lagrange: Vec<Scalar>,
+    group_commitment: G1Affine,
+    challenge: Scalar,
+}
+
+impl<'a> Session<'a> {
+    fn new(
+        pk: &G1Affine,
+        msg: &[u8],
+        commitments: &'a [SigningCommitment],
+    ) -> Result<Self, FrostError> {
+        let lagrange = lagrange_at_zero(
+            &commitments
+                .iter()
+                .map(|commitment| commitment.index)
+                .collect::<Vec<_>>(),
+        )
+        .map_err(|e| match e {
+            InterpolationError::ZeroIndex => FrostError::Index { index: 0 },
+            InterpolationError::RepeatedIndex { index } => FrostError::Index { index },
+        })?;
+        let binding_factors = binding_factors(pk, msg, commitments);
+        let mut session = Session {
+            commitments,
+            binding_factors,
+            lagrange,
+            group_commitment: G1Affine::identity(),
+            challenge: Scalar::zero(),
+        };
+        session.group_commitment = (0..commitments.len())
+            .map(|position| session.commitment_share(position))
+            .sum::<G1Projective>()
+            .to_affine();
+        session.challenge = schnorr::challenge(&session.group_commitment, pk, msg);
+        Ok(session)
+    }
+
+    fn position(&self, index: u64) -> Result<usize, FrostError> {
+        self.commitments
+            .iter()
+            .position(|commitment| commitment.index == index)
+            .ok_or(FrostError::Unknown { index })
+    }
+
+    /// `D_i + ρ_i * E_i`
+    fn commitment_share(&self, position: usize) -> G1Projective {
+        let commitment = &self.commitments[position];
+        G1Projective::from(commitment.hiding) + commitment.binding * self.binding_factors[position]
+    }
+}
+
+/// `ρ_i` for each signer, SHA-512 reduced into the field.
+fn binding_factors(pk: &G1Affine, msg: &[u8], commitments: &[SigningCommitment]) -> Vec<Scalar> {
+    let mut prefix = Sha512::new()
+        .chain(BINDING_TAG)
+        .chain(pk.to_compressed())
+        .chain((commitments.len() as u64).to_le_bytes());
+    for commitment in commitments {
+        prefix = prefix
+            .chain(commitment.index.to_le_bytes())
+            .chain(commitment.hiding.to_compressed())
+            .chain(commitment.binding.to_compressed());
+    }
+    let prefix = prefix.chain(msg).finalize();
+    commitments
+        .iter()
+        .map(|commitment| {
+            let mut wide = [0u8; 64];
+            wide.copy_from_slice(
+                &Sha512::new()
+                    .chain(prefix)
+                    .chain(commitment.index.to_le_bytes())
+                    .finalize(),
+            );
+            Scalar::from_bytes_wide(&wide)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrostError {
+    /// A signer index that is 0 or appears twice.
+    Index { index: u64 },
+    /// A signer without a commitment among the signers'.
+    Unknown { index: u64 },
+    /// A signer of the commitments without a share.
+    Missing { index: u64 },
+    /// Nonces that don't match the signer's commitment.
+    Nonces { index: u64 },
+}
+
+impl fmt::Display for FrostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrostError::Index { index } => write!(f, "invalid or repeated signer {}", index),
+            FrostError::Unknown { index } => {
+                write!(f, "signer {} has no commitment in the set", index)
+            }
+            FrostError::Missing { index } => write!(f, "missing the share of signer {}", index),
+            FrostError::Nonces { index } => write!(
+                f,
+                "the nonces of signer {} don't match its commitment",
+                index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrostError {}
@@ -0,0 +1,198 @@
+//! Boneh-Franklin identity based encryption (the FullIdent variant).
+//!
+//! The key generator (PKG) holds a master secret s with the public key
+//! `P_pub = s * G`. The private key of an identity is `d_id = s * H(id)`,
+//! which is nothing but a BLS signature on the identity, so anyone can check
+//! it against `P_pub`.
+//!
+//! To encrypt M to an identity, pick a random σ and derive `r = H3(σ, M)`:
+//!
+//! U = r * G
+//! V = σ ⊕ H2(e(P_pub, H(id))^r)
+//! W = M ⊕ H4(σ)
+//!
+//! The holder of `d_id` computes `e(U, d_id) = e(rG, sH(id)) = e(P_pub, H(id))^r`,
+//! unmasks σ and then M, and finally checks that U was computed from them,
+//! which rejects any tampered ciphertext.
+//!
+//! In the threshold variant the master secret is the output of a DKG. Each
+//! key generator only returns `s_i * H(id)`, and any `t + 1` of them are
+//! combined with Lagrange interpolation exactly like partial signatures.
+use crate::secret::SecretKey;
+use crate::signature::{hash_to_g2, verify_hashed};
+use crate::threshold::{self, InterpolationError, PartialSignature};
+use ::pairing::gt::GtElement;
+use bls12_381::*;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Domain separation tag for hashing identities to G2.
+pub const DST: &[u8] = b"BF_IBE_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+const H2_TAG: &[u8] = b"BF_IBE_H2_GT_TO_BYTES";
+const H3_TAG: &[u8] = b"BF_IBE_H3_SIGMA_MSG_TO_SCALAR";
+const H4_TAG: &[u8] = b"BF_IBE_H4_SIGMA_TO_MASK";
+
+/// The private key `s * H(id)` of an identity.
+#[derive(Clone, PartialEq)]
+pub struct IdentityKey(pub G2Affine);
+
+impl Zeroize for IdentityKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for IdentityKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl std::fmt::Debug for IdentityKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IdentityKey(..)")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ciphertext {
+    pub u: G1Affine,
+    pub v: [u8; 32],
+    pub w: Vec<u8>,
+}
+
+/// Hashes an identity to the point `H(id)` in G2.
+pub fn hash_identity(id: &[u8]) -> G2Affine {
+    hash_to_g2(id, DST).to_affine()
+}
+
+/// Derives the private key of `id` from the master secret.
+pub fn extract(master: &SecretKey, id: &[u8]) -> IdentityKey {
+    IdentityKey((hash_identity(id) * master.as_scalar()).to_affine())
+}
+
+/// Checks `e(P_pub, H(id)) == e(G, d_id)`.
+pub fn verify_identity_key(master_pk: &G1Affine, id: &[u8], key: &IdentityKey) -> bool {
+    verify_hashed(master_pk, &hash_identity(id), &key.0)
+}
+
+/// Encrypts `msg` so that only the holder of the private key of `id` can
+/// read it.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    master_pk: &G1Affine,
+    id: &[u8],
+    msg: &[u8],
+    rng: &mut R,
+) -> Ciphertext {
+    let mut sigma = Zeroizing::new([0u8; 32]);
+    rng.fill_bytes(&mut sigma[..]);
+
+    let r = h3(&sigma, msg);
+    let u = (G1Affine::generator() * r).to_affine();
+    let g_id = GtElement::pairing(master_pk, &hash_identity(id)).pow(&r);
+
+    let mut v = h2(&g_id);
+    xor(&mut v, &sigma[..]);
+
+    let mut w = h4(&sigma, msg.len());
+    xor(&mut w, msg);
+
+    Ciphertext { u, v, w }
+}
+
+/// Decrypts the ciphertext, returns `None` if it was not encrypted to the
+/// identity of `key` or was tampered with.
+pub fn decrypt(key: &IdentityKey, ct: &Ciphertext) -> Option<Vec<u8>> {
+    let mut sigma = Zeroizing::new(h2(&GtElement::pairing(&ct.u, &key.0)));
+    xor(&mut sigma[..], &ct.v);
+
+    let mut msg = h4(&sigma, ct.w.len());
+    xor(&mut msg, &ct.w);
+
+    let r = h3(&sigma, &msg);
+    if (G1Affine::generator() * r).to_affine() != ct.u {
+        msg.zeroize();
+        return None;
+    }
+
+    Some(msg)
+}
+
+/// Threshold variant: a key generator's share of the private key of `id`.
+pub fn extract_share<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    id: &[u8],
+    rng: &mut R,
+) -> PartialSignature {
+    threshold::sign_share(index, share, &hash_identity(id), rng)
+}
+
+/// Checks a key generator's share against its public share `s_i * G`.
+pub fn verify_extract_share(
+    public_share: &G1Affine,
+    id: &[u8],
+    partial: &PartialSignature,
+) -> bool {
+    threshold::verify_share(public_share, &hash_identity(id), partial)
+}
+
+/// Combines `t + 1` valid shares into the private key of the identity.
+pub fn combine_extract_shares(
+    partials: &[PartialSignature],
+) -> Result<IdentityKey, InterpolationError> {
+    threshold::aggregate_shares(partials).map(IdentityKey)
+}
+
+/// H2: Gt -> {0, 1}^256
+fn h2(g: &GtElement) -> [u8; 32] {
+    Sha256::new()
+        .chain(H2_TAG)
+        .chain(g.to_bytes())
+        .finalize()
+        .into()
+}
+
+/// H3: {0, 1}^256 x {0, 1}^* -> Scalar
+fn h3(sigma: &[u8; 32], msg: &[u8]) -> Scalar {
+    let digest = |i: u8| {
+        Sha256::new()
+            .chain(H3_TAG)
+            .chain([i])
+            .chain(sigma)
+            .chain(msg)
+            .finalize()
+    };
+
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(&digest(0));
+    wide[32..].copy_from_slice(&digest(1));
+    Scalar::from_bytes_wide(&wide)
+}
+
+/// H4: {0, 1}^256 -> {0, 1}^len, SHA-256 in counter mode.
+fn h4(sigma: &[u8; 32], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + 32);
+    let mut counter = 0u64;
+    while out.len() < len {
+        out.extend_from_slice(
+            &Sha256::new()
+                .chain(H4_TAG)
+                .chain(counter.to_be_bytes())
+                .chain(sigma)
+                .finalize(),
+        );
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn xor(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
@@ -0,0 +1,371 @@
+//! EIP-2335 JSON keystores, so BLS secret keys and DKG output shares can be
+//! exchanged with Ethereum tooling.
+//!
+//! A keystore holds a 32-byte secret encrypted with AES-128-CTR under a key
+//! derived from a password by either scrypt or PBKDF2. A SHA-256 checksum over
+//! the second half of the derived key and the ciphertext lets us tell a wrong
+//! password apart from a corrupted secret.
+use crate::secret::SecretKey;
+use aes::cipher::{NewCipher, StreamCipher};
+use aes::Aes128Ctr;
+use bls12_381::Scalar;
+use hmac::Hmac;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+/// The only keystore version defined by EIP-2335.
+pub const VERSION: u32 = 4;
+
+/// A keystore as it appears on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub crypto: Crypto,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Hex encoded compressed G1 public key of the secret, may be empty.
+    #[serde(default)]
+    pub pubkey: String,
+    /// The EIP-2334 derivation path of the secret, empty if not derived.
+    pub path: String,
+    pub uuid: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Crypto {
+    pub kdf: KdfModule,
+    pub checksum: ChecksumModule,
+    pub cipher: CipherModule,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KdfModule {
+    #[serde(flatten)]
+    pub kdf: Kdf,
+    #[serde(with = "hex_bytes")]
+    pub message: Vec<u8>,
+}
+
+/// The password based key derivation function and its parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "function", content = "params", rename_all = "lowercase")]
+pub enum Kdf {
+    Scrypt {
+        dklen: u32,
+        n: u32,
+        r: u32,
+        p: u32,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+    Pbkdf2 {
+        dklen: u32,
+        c: u32,
+        prf: String,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecksumModule {
+    pub function: String,
+    pub params: EmptyParams,
+    #[serde(with = "hex_bytes")]
+    pub message: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CipherModule {
+    pub function: String,
+    pub params: CipherParams,
+    #[serde(with = "hex_bytes")]
+    pub message: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CipherParams {
+    #[serde(with = "hex_bytes")]
+    pub iv: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EmptyParams {}
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// The checksum did not match, almost always a wrong password.
+    InvalidPassword,
+    /// The keystore uses a version, function or parameter we don't support.
+    Unsupported(String),
+    /// The decrypted bytes are not a canonical scalar.
+    InvalidSecret,
+    Json(serde_json::Error),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::InvalidPassword => write!(f, "invalid password"),
+            KeystoreError::Unsupported(what) => write!(f, "unsupported keystore: {}", what),
+            KeystoreError::InvalidSecret => write!(f, "keystore secret is not a valid scalar"),
+            KeystoreError::Json(e) => write!(f, "malformed keystore: {}", e),
+            KeystoreError::Io(e) => write!(f, "keystore io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<serde_json::Error> for KeystoreError {
+    fn from(e: serde_json::Error) -> Self {
+        KeystoreError::Json(e)
+    }
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+impl Kdf {
+    /// Scrypt with the work factor recommended by EIP-2335 and a random salt.
+    pub fn scrypt<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Kdf::Scrypt {
+            dklen: 32,
+            n: 262144,
+            r: 8,
+            p: 1,
+            salt: random_bytes(rng, 32),
+        }
+    }
+
+    /// PBKDF2-HMAC-SHA256 with the iteration count recommended by EIP-2335 and
+    /// a random salt.
+    pub fn pbkdf2<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Kdf::Pbkdf2 {
+            dklen: 32,
+            c: 262144,
+            prf: "hmac-sha256".into(),
+            salt: random_bytes(rng, 32),
+        }
+    }
+
+    /// Derives the 32 byte decryption key from the processed password.
+    fn derive_key(&self, password: &[u8]) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+        let mut key = Zeroizing::new([0u8; 32]);
+
+        match self {
+            Kdf::Scrypt {
+                dklen,
+                n,
+                r,
+                p,
+                salt,
+            } => {
+                if *dklen != 32 || !n.is_power_of_two() {
+                    return Err(KeystoreError::Unsupported("scrypt parameters".into()));
+                }
+                let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p)
+                    .map_err(|_| KeystoreError::Unsupported("scrypt parameters".into()))?;
+                scrypt::scrypt(password, salt, &params, &mut key[..])
+                    .map_err(|_| KeystoreError::Unsupported("scrypt parameters".into()))?;
+            }
+            Kdf::Pbkdf2 {
+                dklen,
+                c,
+                prf,
+                salt,
+            } => {
+                if *dklen != 32 || prf != "hmac-sha256" {
+                    return Err(KeystoreError::Unsupported("pbkdf2 parameters".into()));
+                }
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(password, salt, *c, &mut key[..]);
+            }
+        }
+
+        Ok(key)
+    }
+}
+
+impl Keystore {
+    /// Encrypts `secret` under `password`, `path` is the derivation path of the
+    /// secret or an empty string.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        secret: &SecretKey,
+        password: &str,
+        path: &str,
+        kdf: Kdf,
+        rng: &mut R,
+    ) -> Result<Self, KeystoreError> {
+        let decryption_key = kdf.derive_key(&process_password(password))?;
+        let iv = random_bytes(rng, 16);
+
+        let mut message = secret_to_bytes(secret).to_vec();
+        Aes128Ctr::new(decryption_key[..16].into(), iv[..].into()).apply_keystream(&mut message);
+
+        let pubkey = secret.public_key().to_compressed();
+
+        Ok(Keystore {
+            crypto: Crypto {
+                kdf: KdfModule {
+                    kdf,
+                    message: Vec::new(),
+                },
+                checksum: ChecksumModule {
+                    function: "sha256".into(),
+                    params: EmptyParams {},
+                    message: checksum(&decryption_key, &message).to_vec(),
+                },
+                cipher: CipherModule {
+                    function: "aes-128-ctr".into(),
+                    params: CipherParams { iv },
+                    message,
+                },
+            },
+            description: String::new(),
+            pubkey: hex::encode(pubkey),
+            path: path.into(),
+            uuid: uuid_v4(rng),
+            version: VERSION,
+        })
+    }
+
+    /// Recovers the secret, failing with [`KeystoreError::InvalidPassword`] if
+    /// the checksum does not match.
+    pub fn decrypt(&self, password: &str) -> Result<SecretKey, KeystoreError> {
+        if self.version != VERSION {
+            return Err(KeystoreError::Unsupported(format!(
+                "version {}",
+                self.version
+            )));
+        }
+        if self.crypto.checksum.function != "sha256" {
+            return Err(KeystoreError::Unsupported(
+                self.crypto.checksum.function.clone(),
+            ));
+        }
+        if self.crypto.cipher.function != "aes-128-ctr" || self.crypto.cipher.params.iv.len() != 16
+        {
+            return Err(KeystoreError::Unsupported(
+                self.crypto.cipher.function.clone(),
+            ));
+        }
+
+        let decryption_key = self
+            .crypto
+            .kdf
+            .kdf
+            .derive_key(&process_password(password))?;
+
+        let ciphertext = &self.crypto.cipher.message;
+        if checksum(&decryption_key, ciphertext)[..] != self.crypto.checksum.message[..] {
+            return Err(KeystoreError::InvalidPassword);
+        }
+
+        let mut secret = Zeroizing::new(ciphertext.clone());
+        Aes128Ctr::new(
+            decryption_key[..16].into(),
+            self.crypto.cipher.params.iv[..].into(),
+        )
+        .apply_keystream(&mut secret);
+
+        secret_from_bytes(&secret).ok_or(KeystoreError::InvalidSecret)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, KeystoreError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Keystore to be serializable")
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, KeystoreError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), KeystoreError> {
+        Ok(std::fs::write(path, self.to_json())?)
+    }
+}
+
+/// NFKD normalizes the password and strips the C0, C1 and Delete control
+/// codes, as required by EIP-2335.
+fn process_password(password: &str) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(
+        password
+            .nfkd()
+            .filter(|c| !matches!(*c as u32, 0x00..=0x1f | 0x7f..=0x9f))
+            .collect::<String>()
+            .into_bytes(),
+    )
+}
+
+/// `SHA256(decryption_key[16..32] || cipher_message)`
+fn checksum(decryption_key: &[u8; 32], cipher_message: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&decryption_key[16..]);
+    hasher.update(cipher_message);
+    hasher.finalize().into()
+}
+
+/// The secret is stored as a 32 byte big-endian integer.
+fn secret_to_bytes(secret: &SecretKey) -> Zeroizing<[u8; 32]> {
+    let mut bytes = Zeroizing::new(secret.as_scalar().to_bytes());
+    bytes.reverse();
+    bytes
+}
+
+fn secret_from_bytes(bytes: &[u8]) -> Option<SecretKey> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    let mut le = Zeroizing::new([0u8; 32]);
+    for (dst, src) in le.iter_mut().zip(bytes.iter().rev()) {
+        *dst = *src;
+    }
+    Option::<Scalar>::from(Scalar::from_bytes(&le)).map(SecretKey::new)
+}
+
+fn random_bytes<R: RngCore>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn uuid_v4<R: RngCore>(rng: &mut R) -> String {
+    let mut b = [0u8; 16];
+    rng.fill_bytes(&mut b);
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let h = hex::encode(b);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[0..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..32]
+    )
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(d)?;
+        hex::decode(s.trim_start_matches("0x")).map_err(serde::de::Error::custom)
+    }
+}
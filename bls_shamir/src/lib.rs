@@ -0,0 +1,27 @@
+//! Building blocks for BLS signatures over BLS12-381, shared by the threshold
+//! signing examples in this workspace.
+
+pub mod adaptor;
+pub mod aggregate;
+pub mod backend;
+pub mod blind;
+pub mod dkg_config;
+pub mod dkg_transcript;
+pub mod eip2333;
+pub mod elgamal;
+pub mod escrow;
+pub mod frost;
+pub mod ibe;
+pub mod keystore;
+pub mod min_sig;
+pub mod multisig;
+pub mod oprf;
+pub mod pvss;
+pub mod reshare;
+pub mod schnorr;
+pub mod secret;
+pub mod shuffle;
+pub mod signature;
+pub mod slashing;
+pub mod threshold;
+pub mod vrf;
@@ -1,42 +1,61 @@
 use bls12_381::hash_to_curve::*;
 use bls12_381::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::signature::verify_hashed;
+use bls_shamir::threshold::{interpolate_at_zero, lagrange_at_zero};
 use group::Curve;
+use std::process;
+use tracing::{debug, info};
 
 /// A threshold sign using a secret polynomial f(x), using f(0) as the private
 /// key.
 #[allow(non_snake_case)]
 fn main() {
+    let (level, format) = logging::parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\nUsage: bls_shamir {}", e, logging::USAGE);
+        process::exit(2);
+    });
+    logging::init(level, format).expect("No subscriber installed yet");
+
     let G = G1Affine::generator();
 
     // Each node computes a random point and holds it as their secret share.
     // this values were hand chosen from the simple `F(x) = 5x + 3` polynomial.
-    let secret_points = vec![(8u64, 43u64), (16, 83)];
+    // The shares are kept in `SecretKey`s so they're wiped once we're done.
+    let f = SecretPolynomial::new(vec![Scalar::from(3), Scalar::from(5)]);
+    let secret_points = [8u64, 16]
+        .iter()
+        .map(|x| (*x, f.evaluate(*x)))
+        .collect::<Vec<_>>();
+    drop(f);
 
     // Now each node emits public points (x, yG).
     let public_points = secret_points
         .iter()
-        .cloned()
-        .map(|(x, y)| (x, G * Scalar::from(y)))
+        .map(|(x, y)| (*x, G * y.as_scalar()))
         .collect::<Vec<_>>();
 
     // Compute f(0) using secret points, this is used for demo.
-    let private_key = mul_zero(&secret_points)
-        .into_iter()
-        .map(|(m, y)| m * (*y as i64))
-        .sum::<i64>() as u64;
+    let xs = secret_points.iter().map(|(x, _)| *x).collect::<Vec<_>>();
+    let private_key = SecretKey::new(
+        lagrange_at_zero(&xs)
+            .expect("The indices are distinct and non-zero")
+            .into_iter()
+            .zip(&secret_points)
+            .map(|(l, (_, y))| l * y.as_scalar())
+            .sum(),
+    );
 
     // We should be able to compute `f(0) * G` using the public points.
-    let public_key = mul_zero(&public_points)
-        .into_iter()
-        .map(projective_mul)
-        .sum::<G1Projective>()
+    let public_key = interpolate_at_zero(&public_points)
+        .expect("The indices are distinct and non-zero")
         .to_affine();
 
     // Show that we indeed have the right `f(0) * G`.
-    let t = (G * Scalar::from(private_key)).to_affine();
-    println!("Private key={:#?}", private_key);
-    println!("Public key(1)={:#?}", t);
-    println!("Public key(2)={:#?}", public_key);
+    let t = private_key.public_key();
+    debug!("Private key = {:?}", private_key);
+    info!("Public key from f(0) = {:?}", t);
+    info!("Public key from the public points = {:?}", public_key);
     assert_eq!(t, public_key);
 
     // Now we want to sign a message. From BLS we remember that:
@@ -65,59 +84,20 @@ fn main() {
     // Now each of the nodes will send their share (x, yM).
     let sign_points = secret_points
         .iter()
-        .map(|(x, y)| (*x, M * Scalar::from(*y)))
+        .map(|(x, y)| (*x, M * y.as_scalar()))
         .collect::<Vec<_>>();
 
     // Now having all of the (x, yM) points, we can compute `f(0) * M`.
-    let sign = mul_zero(&sign_points)
-        .into_iter()
-        .map(projective_mul)
-        .sum::<G2Projective>()
+    let sign = interpolate_at_zero(&sign_points)
+        .expect("The indices are distinct and non-zero")
         .to_affine();
 
-    println!("Sign={:#?}", sign);
-
-    // Now we want to validate this sign.
-    let left = pairing(&public_key, &M);
-    let right = pairing(&G, &sign);
-
-    println!("L={:#?}", left);
-    println!("R={:#?}", right);
-    assert_eq!(left, right);
-
-    println!("Signature validated.")
-}
-
-fn mul_zero<T: std::fmt::Debug>(points: &Vec<(u64, T)>) -> Vec<(i64, &T)> {
-    points
-        .iter()
-        .map(|(x, y)| {
-            let xj = *x as f64;
-            let mut r = 1.0;
+    info!("Sign = {:?}", sign);
 
-            for (xm, _) in points {
-                if xm != x {
-                    let xm = *xm as f64;
-                    r *= xm / (xm - xj);
-                }
-            }
-
-            // assert_eq!(r as i64 as f64, r);
-
-            (r as i64, y)
-        })
-        .collect::<Vec<_>>()
-}
+    // Now we want to validate this sign, `e(Public key, M) == e(G, Signature)`
+    // is checked as `e(Public key, M) * e(-G, Signature) == 1` so that we only
+    // pay for one final exponentiation.
+    assert!(verify_hashed(&public_key, &M, &sign));
 
-fn projective_mul<'a, T: 'a + std::ops::Neg<Output = T>>((m, y): (i64, &'a T)) -> T
-where
-    &'a T: std::ops::Mul<Scalar, Output = T>,
-{
-    if m < 0 {
-        let m = (-m) as u64;
-        -(y * Scalar::from(m))
-    } else {
-        let m = m as u64;
-        y * Scalar::from(m)
-    }
+    info!("Signature validated");
 }
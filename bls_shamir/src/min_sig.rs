@@ -0,0 +1,80 @@
+//! BLS signatures with the groups swapped: public keys in G2 and signatures in
+//! G1. Signatures are half the size, which is why randomness beacons such as
+//! drand's unchained network publish them this way.
+//!
+//! Public key = a * G2
+//! Signature  = a * H(m), H(m) in G1
+//!
+//! e(H(m), Public key) == e(Signature, G2)
+//!
+//! Both G2 points in that equation are fixed for a given signer, so their
+//! Miller loop lines can be computed once with [`PreparedPublicKey`] and
+//! reused for every signature.
+use crate::secret::SecretKey;
+use bls12_381::hash_to_curve::*;
+use bls12_381::*;
+use group::Curve;
+
+/// The domain separation tag of the basic scheme with signatures in G1.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Hashes a message to a point in G1 under the given domain separation tag.
+pub fn hash_to_g1(msg: &[u8], dst: &[u8]) -> G1Projective {
+    <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst)
+}
+
+/// Returns `sk * G2`.
+pub fn public_key(sk: &SecretKey) -> G2Affine {
+    (G2Affine::generator() * sk.as_scalar()).to_affine()
+}
+
+/// Returns `sk * H(msg)`.
+pub fn sign(sk: &SecretKey, msg: &[u8]) -> G1Affine {
+    (hash_to_g1(msg, DST) * sk.as_scalar()).to_affine()
+}
+
+/// Verifies a signature, preparing the public key on every call. Use
+/// [`PreparedPublicKey`] when checking many signatures from the same key.
+pub fn verify(pk: &G2Affine, msg: &[u8], sig: &G1Affine) -> bool {
+    PreparedPublicKey::new(pk).verify(msg, sig)
+}
+
+/// A public key together with the precomputed Miller loop lines of both the
+/// key and the G2 generator.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey {
+    pk: G2Affine,
+    prepared: G2Prepared,
+    generator: G2Prepared,
+}
+
+impl PreparedPublicKey {
+    pub fn new(pk: &G2Affine) -> Self {
+        PreparedPublicKey {
+            pk: *pk,
+            prepared: G2Prepared::from(*pk),
+            generator: G2Prepared::from(G2Affine::generator()),
+        }
+    }
+
+    pub fn public_key(&self) -> &G2Affine {
+        &self.pk
+    }
+
+    pub fn verify(&self, msg: &[u8], sig: &G1Affine) -> bool {
+        self.verify_hashed(&hash_to_g1(msg, DST).to_affine(), sig)
+    }
+
+    /// Checks `e(hm, pk) * e(-sig, G2) == 1` for a message already hashed to
+    /// the curve, under whatever DST the caller used.
+    pub fn verify_hashed(&self, hm: &G1Affine, sig: &G1Affine) -> bool {
+        let neg_sig = -sig;
+        ::pairing::prepared_product_is_one(&[(hm, &self.prepared), (&neg_sig, &self.generator)])
+    }
+}
+
+impl From<G2Affine> for PreparedPublicKey {
+    fn from(pk: G2Affine) -> Self {
+        PreparedPublicKey::new(&pk)
+    }
+}
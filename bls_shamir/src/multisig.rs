@@ -0,0 +1,100 @@
+//! Multi-signatures with aggregated public keys, following Boneh, Drijvers
+//! and Neven.
+//!
+//! Naively summing public keys is open to rogue key attacks, where a signer
+//! picks `pk' = x * G - pk_honest` and then signs alone on behalf of both. To
+//! prevent that without proofs of possession, every key is weighted by a
+//! coefficient derived from hashing the whole key set:
+//!
+//! a_i = H(pk_i, {pk_1, ..., pk_n})
+//! apk = ∑ a_i * pk_i
+//! sig = ∑ a_i * sig_i
+//!
+//! Since a rogue key changes every coefficient, it can't be chosen to cancel
+//! out the honest keys.
+use crate::backend;
+use crate::secret::SecretKey;
+use crate::signature::{self, DST};
+use bls12_381::*;
+use group::Curve;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Domain separation for the key coefficient hash.
+const COEFFICIENT_DST: &[u8] = b"BLS_MULTISIG_BDN_COEFFICIENT_";
+
+/// Computes the coefficient `a_i` of every key in `pks`. The key set is
+/// treated as a set, so the order of `pks` only decides the order of the
+/// returned coefficients.
+pub fn key_coefficients(pks: &[G1Affine]) -> Vec<Scalar> {
+    let mut set = pks.iter().map(|pk| pk.to_compressed()).collect::<Vec<_>>();
+    set.sort_unstable();
+
+    let mut set_hasher = Sha256::new();
+    set_hasher.update(COEFFICIENT_DST);
+    for pk in &set {
+        set_hasher.update(pk);
+    }
+
+    pks.iter()
+        .map(|pk| {
+            let digest = set_hasher.clone().chain(pk.to_compressed()).finalize();
+
+            // 128-bit coefficients are enough for the security argument and
+            // keep the scalar multiplications cheap.
+            let mut bytes = [0u8; 32];
+            bytes[..16].copy_from_slice(&digest[..16]);
+            Scalar::from_bytes(&bytes).unwrap()
+        })
+        .collect()
+}
+
+/// Computes `apk = ∑ a_i * pk_i`.
+pub fn aggregate_public_keys(pks: &[G1Affine]) -> G1Affine {
+    backend::g1_msm(pks, &key_coefficients(pks)).to_affine()
+}
+
+/// Computes `∑ a_i * sig_i`, where `sigs[i]` is the plain BLS signature by the
+/// owner of `pks[i]`.
+pub fn aggregate_signatures(
+    pks: &[G1Affine],
+    sigs: &[G2Affine],
+) -> Result<G2Affine, MultisigError> {
+    if pks.len() != sigs.len() {
+        return Err(MultisigError::Length {
+            keys: pks.len(),
+            signatures: sigs.len(),
+        });
+    }
+
+    Ok(backend::g2_msm(sigs, &key_coefficients(pks)).to_affine())
+}
+
+/// Each signer produces an ordinary BLS signature, the weighting happens
+/// during aggregation.
+pub fn sign(sk: &SecretKey, msg: &[u8]) -> G2Affine {
+    signature::sign(sk, msg)
+}
+
+/// Verifies an aggregated signature against an aggregated public key.
+pub fn verify(apk: &G1Affine, msg: &[u8], sig: &G2Affine) -> bool {
+    signature::verify_hashed(apk, &signature::hash_to_g2(msg, DST).to_affine(), sig)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultisigError {
+    /// There must be one signature per public key.
+    Length { keys: usize, signatures: usize },
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultisigError::Length { keys, signatures } => {
+                write!(f, "{} signatures for {} public keys", signatures, keys)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MultisigError {}
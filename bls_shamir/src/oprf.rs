@@ -0,0 +1,190 @@
+//! An oblivious pseudorandom function with BLS keys: the client gets
+//! `F_k(x) = H2(x, k * H1(x))` for the server's key k without the server
+//! learning x, and without the client learning k.
+//!
+//! Blinded input   = r * H1(x)
+//! Evaluation      = k * r * H1(x)
+//! Output          = H2(x, r^-1 * k * r * H1(x))
+//!
+//! as for blind signatures, but with its own domain separation tag, so an
+//! evaluation is never a signature, and a hash on top, so the output is
+//! uniform bytes. In the verifiable variant the server proves with a DLEQ
+//! that `log_G(K) = log_B(E)` for its public key `K = k * G`, the blinded
+//! input B and the evaluation E, so it can't answer one client with another
+//! key to tag it. The key can also be the group key of a DKG: each holder
+//! of a share evaluates with it and proves it against its public share, and
+//! any `t + 1` evaluations interpolate into `k * B`.
+use crate::secret::SecretKey;
+use crate::signature::hash_to_g2;
+use crate::threshold::{self, InterpolationError};
+use bls12_381::*;
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use sigma::dleq;
+use zeroize::Zeroize;
+
+/// Domain separation tag for hashing inputs to G2.
+pub const DST: &[u8] = b"ZK_LAB_OPRF_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+const OUTPUT_TAG: &[u8] = b"ZK_LAB_OPRF_OUTPUT";
+const DLEQ_CONTEXT: &[u8] = b"ZK_LAB_OPRF_EVALUATION";
+
+pub use sigma::Proof as DleqProof;
+
+/// The r kept by the client between blinding and finalizing.
+pub struct Blind(Scalar);
+
+impl Zeroize for Blind {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for Blind {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// `r * H1(x)`, sent to the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlindedElement(pub G2Affine);
+
+/// `k * r * H1(x)`, returned by the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvaluatedElement(pub G2Affine);
+
+/// `s_i * r * H1(x)` with its proof, from the holder of share `index`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvaluationShare {
+    pub index: u64,
+    pub point: G2Affine,
+    pub proof: DleqProof,
+}
+
+/// Hashes and blinds the input.
+pub fn blind<R: RngCore + CryptoRng>(input: &[u8], rng: &mut R) -> (Blind, BlindedElement) {
+    let r = loop {
+        let r = Scalar::random(&mut *rng);
+        if r != Scalar::zero() {
+            break r;
+        }
+    };
+    let blinded = (hash_to_g2(input, DST) * r).to_affine();
+    (Blind(r), BlindedElement(blinded))
+}
+
+/// The server's evaluation of a blinded input.
+pub fn evaluate(sk: &SecretKey, blinded: &BlindedElement) -> EvaluatedElement {
+    EvaluatedElement((blinded.0 * sk.as_scalar()).to_affine())
+}
+
+/// The server's evaluation with the proof that it used the key of
+/// `sk.public_key()`.
+pub fn evaluate_verifiable<R: RngCore + CryptoRng>(
+    sk: &SecretKey,
+    blinded: &BlindedElement,
+    rng: &mut R,
+) -> (EvaluatedElement, DleqProof) {
+    let proof = dleq::prove(
+        sk.as_scalar(),
+        &G1Projective::generator(),
+        &G2Projective::from(blinded.0),
+        DLEQ_CONTEXT,
+        rng,
+    );
+    (evaluate(sk, blinded), proof)
+}
+
+/// Checks an evaluation against the public key `k * G` of the server, or
+/// against the public share of a share holder.
+pub fn verify_evaluation(
+    pk: &G1Affine,
+    blinded: &BlindedElement,
+    evaluated: &EvaluatedElement,
+    proof: &DleqProof,
+) -> bool {
+    dleq::verify(
+        &G1Projective::generator(),
+        &G1Projective::from(pk),
+        &G2Projective::from(blinded.0),
+        &G2Projective::from(evaluated.0),
+        DLEQ_CONTEXT,
+        proof,
+    )
+}
+
+/// Unblinds the evaluation into the output for the input.
+pub fn finalize(input: &[u8], blind: &Blind, evaluated: &EvaluatedElement) -> [u8; 32] {
+    // r is never zero, see `blind`.
+    let mut r_inv = blind.0.invert().unwrap();
+    let unblinded = (evaluated.0 * r_inv).to_affine();
+    r_inv.zeroize();
+    output(input, &unblinded)
+}
+
+/// The output for the input computed with the key directly, by the server
+/// itself.
+pub fn evaluate_input(sk: &SecretKey, input: &[u8]) -> [u8; 32] {
+    output(
+        input,
+        &(hash_to_g2(input, DST) * sk.as_scalar()).to_affine(),
+    )
+}
+
+/// The evaluation by the holder of share `index`, proven against its public
+/// share.
+pub fn evaluate_share<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    blinded: &BlindedElement,
+    rng: &mut R,
+) -> EvaluationShare {
+    let (evaluated, proof) = evaluate_verifiable(share, blinded, rng);
+    EvaluationShare {
+        index,
+        point: evaluated.0,
+        proof,
+    }
+}
+
+/// Checks an evaluation share against the public share `s_i * G` of its
+/// holder.
+pub fn verify_share(
+    public_share: &G1Affine,
+    blinded: &BlindedElement,
+    share: &EvaluationShare,
+) -> bool {
+    verify_evaluation(
+        public_share,
+        blinded,
+        &EvaluatedElement(share.point),
+        &share.proof,
+    )
+}
+
+/// Interpolates `t + 1` valid evaluation shares into the evaluation under
+/// the group key.
+pub fn combine(shares: &[EvaluationShare]) -> Result<EvaluatedElement, InterpolationError> {
+    let points = shares
+        .iter()
+        .map(|share| (share.index, G2Projective::from(share.point)))
+        .collect::<Vec<_>>();
+    Ok(EvaluatedElement(
+        threshold::interpolate_at_zero(&points)?.to_affine(),
+    ))
+}
+
+/// `H2(x, k * H1(x))`, with the length of x so that the two can't be
+/// confused.
+fn output(input: &[u8], point: &G2Affine) -> [u8; 32] {
+    Sha256::new()
+        .chain(OUTPUT_TAG)
+        .chain((input.len() as u64).to_be_bytes())
+        .chain(input)
+        .chain(point.to_compressed())
+        .finalize()
+        .into()
+}
@@ -0,0 +1,249 @@
+//! Publicly verifiable secret sharing in the style of SCRAPE: a dealer
+//! shares a random secret between the n holders of BLS keys
+//! `PK_i = sk_i * G`, and anyone, not only the holders, can check that every
+//! share is right, so there is no complaint round.
+//!
+//! For a random polynomial p of degree t the dealer publishes, for each
+//! member i,
+//!
+//! V_i = p(i) * H
+//! E_i = p(i) * PK_i
+//!
+//! with a DLEQ proof that `log_H(V_i) = log_PK_i(E_i)`, H being a second
+//! generator hashed to G1 so that no one knows its log to G. Member i
+//! decrypts `S_i = sk_i^-1 * E_i = p(i) * G`, and any `t + 1` of those
+//! interpolate to the secret `p(0) * G`.
+//!
+//! The proofs tie each E_i to its V_i, it remains to check that the V_i lie
+//! on a polynomial of degree t. They are then a Reed-Solomon codeword, which
+//! is orthogonal to the dual code: for any f of degree `n - t - 2`, with
+//! `λ_i = ∏_{j != i} 1 / (i - j)`,
+//!
+//! ∑ λ_i * f(i) * V_i == 0
+//!
+//! and with f hashed from the dealing this only holds by chance otherwise.
+//! That is n multiplications, where checking against Feldman commitments
+//! takes `n * (t + 1)`. Members publish their S_i with a DLEQ proof that
+//! `log_G(PK_i) = log_S_i(E_i)`, so the secret is recovered without trusting
+//! them either.
+use crate::min_sig::hash_to_g1;
+use crate::secret::{SecretKey, SecretPolynomial};
+use crate::threshold::{self, InterpolationError};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sigma::dleq;
+use sigma::transcript::Transcript;
+use sigma::Proof;
+
+/// Domain separation tag for hashing the commitment generator H to G1.
+pub const DST: &[u8] = b"ZK_LAB_PVSS_BLS12381G1_XMD:SHA-256_SSWU_RO_";
+
+const DOMAIN: &[u8] = b"zk-lab/bls_shamir/pvss";
+const SHARE_TAG: &[u8] = b"zk-lab/bls_shamir/pvss/share";
+const DECRYPTION_TAG: &[u8] = b"zk-lab/bls_shamir/pvss/decryption";
+
+/// The commitments, encrypted shares and proofs, member by member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dealing {
+    pub commitments: Vec<G1Affine>,
+    pub encrypted: Vec<G1Affine>,
+    pub proofs: Vec<Proof>,
+}
+
+/// `p(i) * G`, decrypted by member `index`, with its proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecryptedShare {
+    pub index: u64,
+    pub point: G1Affine,
+    pub proof: Proof,
+}
+
+/// The generator H the commitments are made with.
+pub fn commitment_base() -> G1Projective {
+    hash_to_g1(b"commitments", DST)
+}
+
+/// Shares a random secret of degree `threshold` between the holders of
+/// `keys`, member `i + 1` holding `keys[i]`.
+pub fn deal<R: RngCore + CryptoRng>(
+    threshold: usize,
+    keys: &[G1Affine],
+    context: &[u8],
+    rng: &mut R,
+) -> Dealing {
+    let polynomial = SecretPolynomial::random(threshold, rng);
+    let h = commitment_base();
+    let share_context = [SHARE_TAG, context].concat();
+    let mut dealing = Dealing {
+        commitments: Vec::with_capacity(keys.len()),
+        encrypted: Vec::with_capacity(keys.len()),
+        proofs: Vec::with_capacity(keys.len()),
+    };
+    for (key, x) in keys.iter().zip(1..) {
+        let share = polynomial.evaluate(x);
+        dealing
+            .commitments
+            .push((h * share.as_scalar()).to_affine());
+        dealing
+            .encrypted
+            .push((key * share.as_scalar()).to_affine());
+        dealing.proofs.push(dleq::prove(
+            share.as_scalar(),
+            &h,
+            &G1Projective::from(key),
+            &share_context,
+            rng,
+        ));
+    }
+    dealing
+}
+
+/// Checks that the dealing shares a secret of degree `threshold` between
+/// the holders of `keys`, each share encrypted to its holder.
+pub fn verify(threshold: usize, keys: &[G1Affine], dealing: &Dealing, context: &[u8]) -> bool {
+    let n = keys.len();
+    if n < threshold + 2
+        || dealing.commitments.len() != n
+        || dealing.encrypted.len() != n
+        || dealing.proofs.len() != n
+        || keys.iter().any(|key| bool::from(key.is_identity()))
+    {
+        return false;
+    }
+    let h = commitment_base();
+    let share_context = [SHARE_TAG, context].concat();
+    let proven = (0..n).all(|i| {
+        dleq::verify(
+            &h,
+            &G1Projective::from(dealing.commitments[i]),
+            &G1Projective::from(keys[i]),
+            &G1Projective::from(dealing.encrypted[i]),
+            &share_context,
+            &dealing.proofs[i],
+        )
+    });
+    if !proven {
+        return false;
+    }
+
+    let f = dual_polynomial(threshold, keys, dealing, context);
+    let lambdas = dual_weights(n);
+    let sum = dealing
+        .commitments
+        .iter()
+        .zip(&lambdas)
+        .zip(1..)
+        .map(|((v, lambda), x)| v * (lambda * evaluate(&f, x)))
+        .sum::<G1Projective>();
+    bool::from(sum.is_identity())
+}
+
+/// Member `index` decrypts its share of a verified dealing, `None` if the
+/// dealing has no share for it.
+pub fn decrypt<R: RngCore + CryptoRng>(
+    sk: &SecretKey,
+    index: u64,
+    dealing: &Dealing,
+    context: &[u8],
+    rng: &mut R,
+) -> Option<DecryptedShare> {
+    let encrypted = dealing.encrypted.get(index.checked_sub(1)? as usize)?;
+    let sk_inv = Option::<Scalar>::from(sk.as_scalar().invert())?;
+    let point = (encrypted * sk_inv).to_affine();
+    let proof = dleq::prove(
+        sk.as_scalar(),
+        &G1Projective::generator(),
+        &G1Projective::from(point),
+        &[DECRYPTION_TAG, context].concat(),
+        rng,
+    );
+    Some(DecryptedShare {
+        index,
+        point,
+        proof,
+    })
+}
+
+/// Checks a decrypted share against the key of its member.
+pub fn verify_share(
+    key: &G1Affine,
+    dealing: &Dealing,
+    share: &DecryptedShare,
+    context: &[u8],
+) -> bool {
+    let encrypted = match share
+        .index
+        .checked_sub(1)
+        .and_then(|i| dealing.encrypted.get(i as usize))
+    {
+        Some(encrypted) => encrypted,
+        None => return false,
+    };
+    dleq::verify(
+        &G1Projective::generator(),
+        &G1Projective::from(key),
+        &G1Projective::from(share.point),
+        &G1Projective::from(encrypted),
+        &[DECRYPTION_TAG, context].concat(),
+        &share.proof,
+    )
+}
+
+/// Interpolates `threshold + 1` valid shares of distinct members into the
+/// secret `p(0) * G`.
+pub fn reconstruct(shares: &[DecryptedShare]) -> Result<G1Affine, InterpolationError> {
+    let points = shares
+        .iter()
+        .map(|share| (share.index, G1Projective::from(share.point)))
+        .collect::<Vec<_>>();
+    Ok(threshold::interpolate_at_zero(&points)?.to_affine())
+}
+
+/// The coefficients of the f of degree `n - t - 2`, hashed from the dealing.
+fn dual_polynomial(
+    threshold: usize,
+    keys: &[G1Affine],
+    dealing: &Dealing,
+    context: &[u8],
+) -> Vec<Scalar> {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_message(b"context", context);
+    transcript.append_u64(b"t", threshold as u64);
+    for ((key, v), e) in keys
+        .iter()
+        .zip(&dealing.commitments)
+        .zip(&dealing.encrypted)
+    {
+        transcript.append_point(b"PK", key);
+        transcript.append_point(b"V", v);
+        transcript.append_point(b"E", e);
+    }
+    (0..keys.len() - threshold - 1)
+        .map(|_| transcript.challenge_scalar(b"f"))
+        .collect()
+}
+
+/// `λ_i = ∏_{j != i} 1 / (i - j)` for i and j from 1 to n.
+fn dual_weights(n: usize) -> Vec<Scalar> {
+    (1..=n as u64)
+        .map(|i| {
+            let product = (1..=n as u64)
+                .filter(|j| *j != i)
+                .fold(Scalar::one(), |acc, j| {
+                    acc * (Scalar::from(i) - Scalar::from(j))
+                });
+            // The points are distinct, so the product is never zero.
+            product.invert().unwrap()
+        })
+        .collect()
+}
+
+fn evaluate(coefficients: &[Scalar], x: u64) -> Scalar {
+    let x = Scalar::from(x);
+    // Horner's method.
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |acc, a| acc * x + a)
+}
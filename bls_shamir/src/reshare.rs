@@ -0,0 +1,100 @@
+//! Handing a threshold key over to another committee without ever putting
+//! it together, after Desmedt and Jajodia: the holders of the old shares
+//! share their shares in turn, and the new committee interpolates the
+//! sub-shares back into shares of the same secret.
+//!
+//! Old member i, holding `s_i = f(i)`, deals a random polynomial g_i of the
+//! new degree t' with `g_i(0) = s_i`, publishing its commitments. They check
+//! out when
+//!
+//! g_i(0) * G == f(i) * G
+//!
+//! the old public share of i, and new member j checks its sub-share `g_i(j)`
+//! against them as in a DKG. For any set S of `t + 1` old members with
+//! valid dealings, with λ_i their Lagrange coefficients at zero,
+//!
+//! s'_j = ∑_{i in S} λ_i * g_i(j)
+//!
+//! lies on the polynomial `∑ λ_i * g_i` of degree t', whose constant term is
+//! `∑ λ_i * f(i) = f(0)`. The group key stays the same while every share
+//! changes, so shares of the old committee don't combine with new ones, and
+//! the new committee may have another size and threshold. Everyone must use
+//! the same S.
+use crate::secret::{SecretKey, SecretPolynomial};
+use crate::threshold::{self, InterpolationError};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+
+/// The polynomial old member `share` deals to a committee of degree
+/// `threshold`, sub-share `j` going to new member j.
+pub fn deal<R: RngCore + CryptoRng>(
+    share: &SecretKey,
+    threshold: usize,
+    rng: &mut R,
+) -> SecretPolynomial {
+    SecretPolynomial::with_secret(share, threshold, rng)
+}
+
+/// Checks that the commitments of a dealing are of degree `threshold` and
+/// share the dealer's old share, whose public key is `public_share`.
+pub fn verify_dealing(
+    public_share: &G1Affine,
+    commitments: &[G1Projective],
+    threshold: usize,
+) -> bool {
+    commitments.len() == threshold + 1 && commitments[0].to_affine() == *public_share
+}
+
+/// Checks sub-share `index` against its dealing.
+pub fn verify_subshare(commitments: &[G1Projective], index: u64, subshare: &SecretKey) -> bool {
+    subshare.public_key() == evaluate(commitments, index).to_affine()
+}
+
+/// A new member's share from its sub-shares `(i, g_i(j))` of the old
+/// members in S.
+pub fn combine_shares(subshares: &[(u64, SecretKey)]) -> Result<SecretKey, InterpolationError> {
+    let xs = subshares.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    let share = subshares
+        .iter()
+        .zip(threshold::lagrange_at_zero(&xs)?)
+        .map(|((_, subshare), lambda)| subshare.as_scalar() * lambda)
+        .sum::<Scalar>();
+    Ok(SecretKey::new(share))
+}
+
+/// The public coefficients of the new committee from the commitments
+/// `(i, [g_i coefficients * G])` of the old members in S. The first one is
+/// the group key.
+pub fn combine_commitments(
+    dealings: &[(u64, Vec<G1Projective>)],
+) -> Result<Vec<G1Projective>, InterpolationError> {
+    let xs = dealings.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    let lambdas = threshold::lagrange_at_zero(&xs)?;
+    let degree = dealings
+        .first()
+        .map_or(0, |(_, commitments)| commitments.len());
+    Ok((0..degree)
+        .map(|k| {
+            dealings
+                .iter()
+                .zip(&lambdas)
+                .map(|((_, commitments), lambda)| commitments[k] * lambda)
+                .sum::<G1Projective>()
+        })
+        .collect())
+}
+
+/// `∑ C_k * x^k`, the public key of share `x` of the committed polynomial.
+pub fn public_share(coefficients: &[G1Projective], x: u64) -> G1Affine {
+    evaluate(coefficients, x).to_affine()
+}
+
+fn evaluate(coefficients: &[G1Projective], x: u64) -> G1Projective {
+    let x = Scalar::from(x);
+    // Horner's method.
+    coefficients
+        .iter()
+        .rev()
+        .fold(G1Projective::identity(), |acc, c| acc * x + c)
+}
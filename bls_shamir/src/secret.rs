@@ -0,0 +1,140 @@
+//! Containers for secret material that wipe themselves from memory when they
+//! are dropped.
+//!
+//! Neither type is `Copy` and their `Debug` output is redacted, so a secret can
+//! only leave the container through an explicit call to `as_scalar`.
+use bls12_381::*;
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// A secret scalar, used for private keys and for shares of a secret
+/// polynomial.
+#[derive(Clone, PartialEq)]
+pub struct SecretKey(Scalar);
+
+impl SecretKey {
+    pub fn new(scalar: Scalar) -> Self {
+        SecretKey(scalar)
+    }
+
+    /// Samples a uniformly random non-zero key.
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        loop {
+            let scalar = Scalar::random(&mut *rng);
+            if scalar != Scalar::zero() {
+                return SecretKey(scalar);
+            }
+        }
+    }
+
+    pub fn as_scalar(&self) -> &Scalar {
+        &self.0
+    }
+
+    /// Returns `sk * G`.
+    pub fn public_key(&self) -> G1Affine {
+        (G1Affine::generator() * self.0).to_affine()
+    }
+}
+
+impl From<Scalar> for SecretKey {
+    fn from(scalar: Scalar) -> Self {
+        SecretKey(scalar)
+    }
+}
+
+impl Zeroize for SecretKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(..)")
+    }
+}
+
+/// The coefficients `[a_i]` of a dealer's secret polynomial
+/// `f(x) = ∑ a_i * x^i`, where `f(0)` is the secret being shared.
+#[derive(Clone)]
+pub struct SecretPolynomial(Vec<Scalar>);
+
+impl SecretPolynomial {
+    pub fn new(coefficients: Vec<Scalar>) -> Self {
+        SecretPolynomial(coefficients)
+    }
+
+    /// Samples a random polynomial of the given degree, any `degree + 1`
+    /// shares are needed to recover `f(0)`.
+    pub fn random<R: RngCore + CryptoRng>(degree: usize, rng: &mut R) -> Self {
+        SecretPolynomial((0..=degree).map(|_| Scalar::random(&mut *rng)).collect())
+    }
+
+    /// Samples a random polynomial of the given degree with `f(0) = secret`,
+    /// to share a secret that already exists.
+    pub fn with_secret<R: RngCore + CryptoRng>(
+        secret: &SecretKey,
+        degree: usize,
+        rng: &mut R,
+    ) -> Self {
+        let mut coefficients = Vec::with_capacity(degree + 1);
+        coefficients.push(secret.0);
+        coefficients.extend((0..degree).map(|_| Scalar::random(&mut *rng)));
+        SecretPolynomial(coefficients)
+    }
+
+    pub fn degree(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+
+    /// Computes the share `f(x)`.
+    pub fn evaluate(&self, x: u64) -> SecretKey {
+        let x = Scalar::from(x);
+        // Horner's method.
+        let y = self
+            .0
+            .iter()
+            .rev()
+            .fold(Scalar::zero(), |acc, a| acc * x + a);
+        SecretKey(y)
+    }
+
+    /// The shared secret `f(0)`.
+    pub fn secret(&self) -> SecretKey {
+        SecretKey(self.0.first().copied().unwrap_or_else(Scalar::zero))
+    }
+
+    /// The public coefficients `[a_i * G]`, which let anyone check a share
+    /// against `f(x) * G = ∑ (a_i * G) * x^i`.
+    pub fn commitments(&self) -> Vec<G1Projective> {
+        self.0.iter().map(|a| G1Affine::generator() * a).collect()
+    }
+}
+
+impl Zeroize for SecretPolynomial {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretPolynomial {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretPolynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretPolynomial(degree={})", self.degree())
+    }
+}
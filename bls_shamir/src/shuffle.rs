@@ -0,0 +1,476 @@
+//! Verifiable shuffles of ElGamal ciphertexts, for a toy mixnet.
+//!
+//! Unlike the hashed ElGamal of [`crate::elgamal`], the messages here are
+//! points of G1 and the ciphertexts are homomorphic:
+//!
+//! Enc(M; r) = (r * G, M + r * P)
+//!
+//! so anyone can re-encrypt a ciphertext by adding an encryption of zero,
+//! and the result can't be linked to the original without the key. A mix
+//! server re-encrypts the list it is given and permutes it,
+//! `C'_i = C_π(i) + Enc(0; ρ_i)`, then proves that it did so without
+//! revealing π. After a chain of servers, the holders of the key decrypt
+//! with decryption shares, as for hashed ElGamal.
+//!
+//! The proof follows the structure of Bayer and Groth's shuffle argument,
+//! with linear-size arguments in place of their sublinear ones. With
+//! Pedersen commitments, the prover commits to `a_i = π(i)`, gets the
+//! challenge x, and commits to `b_i = x^π(i)`. After the challenges y and
+//! z a product argument shows
+//!
+//! ∏ (y a_i + b_i - z) = ∏ (y i + x^i - z)
+//!
+//! which, as polynomials in y and z, only holds when the pairs `(a_i, b_i)`
+//! are the `(i, x^i)` in some order, so b is a permutation of the powers of
+//! x. It commits to the partial products and proves each multiplication.
+//! A multi-exponentiation argument then shows, for the committed b and some
+//! ρ,
+//!
+//! ∑ b_i C'_i - Enc(0; ρ) = ∑ x^i C_i
+//!
+//! which for a random x means that the outputs are re-encryptions of the
+//! inputs in the order of π. Both arguments are sigma protocols sharing a
+//! single Fiat-Shamir challenge.
+use crate::elgamal::DecryptionShare;
+use crate::secret::SecretKey;
+use crate::threshold::{self, InterpolationError};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sigma::dleq;
+use sigma::pedersen::Generators;
+use sigma::transcript::Transcript;
+use std::ops::{Add, Mul};
+
+const DOMAIN: &[u8] = b"zk-lab/bls_shamir/shuffle";
+const DLEQ_CONTEXT: &[u8] = b"ZK_LAB_SHUFFLE_DECRYPTION_SHARE";
+
+/// `(r * G, M + r * P)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub u: G1Projective,
+    pub v: G1Projective,
+}
+
+impl Ciphertext {
+    /// `Enc(M; r)`
+    pub fn new(pk: &G1Affine, message: &G1Projective, r: Scalar) -> Self {
+        Ciphertext {
+            u: G1Projective::generator() * r,
+            v: message + pk * r,
+        }
+    }
+
+    /// The same message, re-encrypted with the extra randomness `r`.
+    pub fn reencrypt(&self, pk: &G1Affine, r: Scalar) -> Self {
+        *self + Ciphertext::new(pk, &G1Projective::identity(), r)
+    }
+
+    /// `Enc(O; 0)`, the empty sum of ciphertexts.
+    fn zero() -> Self {
+        Ciphertext {
+            u: G1Projective::identity(),
+            v: G1Projective::identity(),
+        }
+    }
+}
+
+impl Add for Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, other: Ciphertext) -> Ciphertext {
+        Ciphertext {
+            u: self.u + other.u,
+            v: self.v + other.v,
+        }
+    }
+}
+
+impl Mul<Scalar> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn mul(self, s: Scalar) -> Ciphertext {
+        Ciphertext {
+            u: self.u * s,
+            v: self.v * s,
+        }
+    }
+}
+
+/// The commitments of a shuffle proof and the responses to its challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShuffleProof {
+    /// Commitments to `π(i)`.
+    pub permutation: Vec<G1Projective>,
+    /// Commitments to `x^π(i)`.
+    pub powers: Vec<G1Projective>,
+    /// Commitments to the partial products but the first and the last,
+    /// which are public.
+    pub partial_products: Vec<G1Projective>,
+    pub multiplications: Vec<MultiplicationProof>,
+    pub exponentiation: ExponentiationProof,
+}
+
+/// That `E_k` commits to the product of the values of `E_{k-1}` and `D_k`:
+/// `E_k = e_{k-1} * D_k + δ * H` for the `e_{k-1}` in `E_{k-1}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiplicationProof {
+    pub commitments: [G1Projective; 2],
+    /// For `e_{k-1}`, its blinding and δ.
+    pub responses: [Scalar; 3],
+}
+
+/// That `∑ b_i C'_i - Enc(0; ρ)` is the public combination of the inputs,
+/// for the b of the commitments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExponentiationProof {
+    pub commitments: Vec<G1Projective>,
+    pub ciphertext: Ciphertext,
+    pub values: Vec<Scalar>,
+    pub blindings: Vec<Scalar>,
+    pub randomness: Scalar,
+}
+
+/// Encrypts the point `message` to the holder, or the holders, of the key
+/// of `pk`.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    pk: &G1Affine,
+    message: &G1Projective,
+    rng: &mut R,
+) -> Ciphertext {
+    Ciphertext::new(pk, message, Scalar::random(rng))
+}
+
+/// Decrypts with the whole key.
+pub fn decrypt(sk: &SecretKey, ct: &Ciphertext) -> G1Projective {
+    ct.v - ct.u * sk.as_scalar()
+}
+
+/// The share holder's decryption share of the ciphertext, `s_i * U` with
+/// its proof.
+pub fn decryption_share<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    ct: &Ciphertext,
+    rng: &mut R,
+) -> DecryptionShare {
+    DecryptionShare {
+        index,
+        point: (ct.u * share.as_scalar()).to_affine(),
+        proof: dleq::prove(
+            share.as_scalar(),
+            &G1Projective::generator(),
+            &ct.u,
+            DLEQ_CONTEXT,
+            rng,
+        ),
+    }
+}
+
+/// Checks a decryption share against the public share `s_i * G` of its
+/// holder.
+pub fn verify_share(public_share: &G1Affine, ct: &Ciphertext, share: &DecryptionShare) -> bool {
+    dleq::verify(
+        &G1Projective::generator(),
+        &G1Projective::from(public_share),
+        &ct.u,
+        &G1Projective::from(share.point),
+        DLEQ_CONTEXT,
+        &share.proof,
+    )
+}
+
+/// Combines `t + 1` valid decryption shares into the message.
+pub fn combine(
+    shares: &[DecryptionShare],
+    ct: &Ciphertext,
+) -> Result<G1Projective, InterpolationError> {
+    let points = shares
+        .iter()
+        .map(|share| (share.index, G1Projective::from(share.point)))
+        .collect::<Vec<_>>();
+    Ok(ct.v - threshold::interpolate_at_zero(&points)?)
+}
+
+/// Re-encrypts and permutes the ciphertexts at random, returns them with the
+/// proof.
+pub fn shuffle<R: RngCore + CryptoRng>(
+    pk: &G1Affine,
+    inputs: &[Ciphertext],
+    rng: &mut R,
+) -> (Vec<Ciphertext>, ShuffleProof) {
+    let n = inputs.len();
+    // Fisher-Yates.
+    let mut permutation: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        permutation.swap(i, j);
+    }
+    let randomness: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut *rng)).collect();
+    let outputs: Vec<Ciphertext> = permutation
+        .iter()
+        .zip(&randomness)
+        .map(|(i, r)| inputs[*i].reencrypt(pk, *r))
+        .collect();
+    let proof = prove(pk, inputs, &outputs, &permutation, &randomness, rng);
+    (outputs, proof)
+}
+
+/// Proves that `outputs[i]` is `inputs[permutation[i]]` re-encrypted with
+/// `randomness[i]`.
+pub fn prove<R: RngCore + CryptoRng>(
+    pk: &G1Affine,
+    inputs: &[Ciphertext],
+    outputs: &[Ciphertext],
+    permutation: &[usize],
+    randomness: &[Scalar],
+    rng: &mut R,
+) -> ShuffleProof {
+    let n = inputs.len();
+    assert!(
+        outputs.len() == n && permutation.len() == n && randomness.len() == n,
+        "The outputs, the permutation and the randomness are of the inputs' length"
+    );
+    let generators = Generators::new(DOMAIN);
+    let mut random = || Scalar::random(&mut *rng);
+    let commit = |value: Scalar, blinding: Scalar| generators.g * value + generators.h * blinding;
+    let mut transcript = transcript(pk, inputs, outputs);
+
+    let a: Vec<Scalar> = permutation
+        .iter()
+        .map(|i| Scalar::from(*i as u64))
+        .collect();
+    let a_blindings: Vec<Scalar> = (0..n).map(|_| random()).collect();
+    let permutation_commitments: Vec<G1Projective> = a
+        .iter()
+        .zip(&a_blindings)
+        .map(|(a, r)| commit(*a, *r))
+        .collect();
+    for commitment in &permutation_commitments {
+        transcript.append_point(b"permutation", commitment);
+    }
+    let x = transcript.challenge_scalar(b"x");
+
+    let powers = powers(x, n);
+    let b: Vec<Scalar> = permutation.iter().map(|i| powers[*i]).collect();
+    let b_blindings: Vec<Scalar> = (0..n).map(|_| random()).collect();
+    let power_commitments: Vec<G1Projective> = b
+        .iter()
+        .zip(&b_blindings)
+        .map(|(b, s)| commit(*b, *s))
+        .collect();
+    for commitment in &power_commitments {
+        transcript.append_point(b"power", commitment);
+    }
+    let y = transcript.challenge_scalar(b"y");
+    let z = transcript.challenge_scalar(b"z");
+
+    // The product argument: d_i = y a_i + b_i - z, and the partial products
+    // e_k = d_1 ⋯ d_k committed in E_k, from E_0 = G to the public E_n.
+    let d: Vec<Scalar> = a.iter().zip(&b).map(|(a, b)| y * a + b - z).collect();
+    let d_blindings: Vec<Scalar> = a_blindings
+        .iter()
+        .zip(&b_blindings)
+        .map(|(r, s)| y * r + s)
+        .collect();
+    let d_commitments: Vec<G1Projective> = d
+        .iter()
+        .zip(&d_blindings)
+        .map(|(d, r)| commit(*d, *r))
+        .collect();
+    let mut e = vec![Scalar::one()];
+    let mut e_blindings = vec![Scalar::zero()];
+    for (k, d) in d.iter().enumerate() {
+        e.push(e[k] * d);
+        e_blindings.push(if k + 1 == n { Scalar::zero() } else { random() });
+    }
+    let partial_products: Vec<G1Projective> =
+        (1..n).map(|k| commit(e[k], e_blindings[k])).collect();
+    for commitment in &partial_products {
+        transcript.append_point(b"partial product", commitment);
+    }
+
+    // E_k = e_{k-1} D_k + δ_k H, for δ_k the blinding of E_k less e_{k-1}
+    // times that of D_k.
+    let deltas: Vec<Scalar> = (0..n)
+        .map(|k| e_blindings[k + 1] - e[k] * d_blindings[k])
+        .collect();
+    let nonces: Vec<[Scalar; 3]> = (0..n).map(|_| [random(), random(), random()]).collect();
+    let multiplication_commitments: Vec<[G1Projective; 2]> = nonces
+        .iter()
+        .zip(&d_commitments)
+        .map(|([k_e, k_r, k_delta], d)| [commit(*k_e, *k_r), d * k_e + generators.h * k_delta])
+        .collect();
+    for [t1, t2] in &multiplication_commitments {
+        transcript.append_point(b"multiplication", t1);
+        transcript.append_point(b"multiplication", t2);
+    }
+
+    // The multi-exponentiation argument, ρ = ∑ b_i ρ_i.
+    let rho: Scalar = b.iter().zip(randomness).map(|(b, r)| b * r).sum();
+    let value_nonces: Vec<Scalar> = (0..n).map(|_| random()).collect();
+    let blinding_nonces: Vec<Scalar> = (0..n).map(|_| random()).collect();
+    let rho_nonce = random();
+    let exponentiation_commitments: Vec<G1Projective> = value_nonces
+        .iter()
+        .zip(&blinding_nonces)
+        .map(|(k, t)| commit(*k, *t))
+        .collect();
+    let ciphertext = linear_combination(outputs, &value_nonces)
+        + Ciphertext::new(pk, &G1Projective::identity(), -rho_nonce);
+    for commitment in &exponentiation_commitments {
+        transcript.append_point(b"exponentiation", commitment);
+    }
+    transcript.append_point(b"exponentiation u", &ciphertext.u);
+    transcript.append_point(b"exponentiation v", &ciphertext.v);
+    let c = transcript.challenge_scalar(b"c");
+
+    let multiplications = multiplication_commitments
+        .into_iter()
+        .zip(&nonces)
+        .enumerate()
+        .map(
+            |(k, (commitments, [k_e, k_r, k_delta]))| MultiplicationProof {
+                commitments,
+                responses: [
+                    k_e + c * e[k],
+                    k_r + c * e_blindings[k],
+                    k_delta + c * deltas[k],
+                ],
+            },
+        )
+        .collect();
+    let response = |nonces: &[Scalar], secrets: &[Scalar]| {
+        nonces
+            .iter()
+            .zip(secrets)
+            .map(|(k, s)| k + c * s)
+            .collect::<Vec<_>>()
+    };
+    ShuffleProof {
+        permutation: permutation_commitments,
+        powers: power_commitments,
+        partial_products,
+        multiplications,
+        exponentiation: ExponentiationProof {
+            commitments: exponentiation_commitments,
+            ciphertext,
+            values: response(&value_nonces, &b),
+            blindings: response(&blinding_nonces, &b_blindings),
+            randomness: rho_nonce + c * rho,
+        },
+    }
+}
+
+/// Checks that the outputs are a re-encryption of a permutation of the
+/// inputs.
+pub fn verify(
+    pk: &G1Affine,
+    inputs: &[Ciphertext],
+    outputs: &[Ciphertext],
+    proof: &ShuffleProof,
+) -> bool {
+    let n = inputs.len();
+    let exponentiation = &proof.exponentiation;
+    if outputs.len() != n
+        || proof.permutation.len() != n
+        || proof.powers.len() != n
+        || proof.partial_products.len() != n.saturating_sub(1)
+        || proof.multiplications.len() != n
+        || exponentiation.commitments.len() != n
+        || exponentiation.values.len() != n
+        || exponentiation.blindings.len() != n
+    {
+        return false;
+    }
+    let generators = Generators::new(DOMAIN);
+    let commit = |value: Scalar, blinding: Scalar| generators.g * value + generators.h * blinding;
+    let mut transcript = transcript(pk, inputs, outputs);
+
+    for commitment in &proof.permutation {
+        transcript.append_point(b"permutation", commitment);
+    }
+    let x = transcript.challenge_scalar(b"x");
+    for commitment in &proof.powers {
+        transcript.append_point(b"power", commitment);
+    }
+    let y = transcript.challenge_scalar(b"y");
+    let z = transcript.challenge_scalar(b"z");
+    for commitment in &proof.partial_products {
+        transcript.append_point(b"partial product", commitment);
+    }
+    for [t1, t2] in proof.multiplications.iter().map(|m| &m.commitments) {
+        transcript.append_point(b"multiplication", t1);
+        transcript.append_point(b"multiplication", t2);
+    }
+    for commitment in &exponentiation.commitments {
+        transcript.append_point(b"exponentiation", commitment);
+    }
+    transcript.append_point(b"exponentiation u", &exponentiation.ciphertext.u);
+    transcript.append_point(b"exponentiation v", &exponentiation.ciphertext.v);
+    let c = transcript.challenge_scalar(b"c");
+
+    // The product argument, from E_0 = G to E_n = ∏ (y i + x^i - z) G.
+    let powers = powers(x, n);
+    let target: Scalar = powers
+        .iter()
+        .enumerate()
+        .map(|(i, power)| y * Scalar::from(i as u64) + power - z)
+        .fold(Scalar::one(), |acc, v| acc * v);
+    let partial_products: Vec<G1Projective> = std::iter::once(generators.g)
+        .chain(proof.partial_products.iter().copied())
+        .chain(std::iter::once(generators.g * target))
+        .collect();
+    let multiplications_hold = proof.multiplications.iter().enumerate().all(|(k, m)| {
+        let d = proof.permutation[k] * y + proof.powers[k] - generators.g * z;
+        let [t1, t2] = m.commitments;
+        let [s_e, s_r, s_delta] = m.responses;
+        commit(s_e, s_r) == t1 + partial_products[k] * c
+            && d * s_e + generators.h * s_delta == t2 + partial_products[k + 1] * c
+    });
+
+    // The multi-exponentiation argument.
+    let openings_hold = exponentiation
+        .commitments
+        .iter()
+        .zip(&proof.powers)
+        .zip(exponentiation.values.iter().zip(&exponentiation.blindings))
+        .all(|((t, b), (v, w))| commit(*v, *w) == t + b * c);
+    let left = linear_combination(outputs, &exponentiation.values)
+        + Ciphertext::new(pk, &G1Projective::identity(), -exponentiation.randomness);
+    let right = exponentiation.ciphertext + linear_combination(inputs, &powers) * c;
+
+    multiplications_hold && openings_hold && left == right
+}
+
+/// `x^i` for i in `0..n`.
+fn powers(x: Scalar, n: usize) -> Vec<Scalar> {
+    std::iter::successors(Some(Scalar::one()), |power| Some(power * x))
+        .take(n)
+        .collect()
+}
+
+/// `∑ s_i C_i`
+fn linear_combination(ciphertexts: &[Ciphertext], scalars: &[Scalar]) -> Ciphertext {
+    ciphertexts
+        .iter()
+        .zip(scalars)
+        .fold(Ciphertext::zero(), |sum, (ct, s)| sum + *ct * *s)
+}
+
+/// The transcript, bound to the key and to both lists of ciphertexts.
+fn transcript(pk: &G1Affine, inputs: &[Ciphertext], outputs: &[Ciphertext]) -> Transcript {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_point(b"public key", pk);
+    transcript.append_u64(b"length", inputs.len() as u64);
+    for ct in inputs {
+        transcript.append_point(b"input", &ct.u);
+        transcript.append_point(b"input", &ct.v);
+    }
+    for ct in outputs {
+        transcript.append_point(b"output", &ct.u);
+        transcript.append_point(b"output", &ct.v);
+    }
+    transcript
+}
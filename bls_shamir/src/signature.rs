@@ -0,0 +1,86 @@
+//! BLS signatures with public keys in G1 and signatures in G2.
+//!
+//! Private key = a
+//! Public key  = a * G
+//! Signature   = a * H(m)
+//!
+//! e(Public key, H(m)) = e(aG, H(m)) = e(G, aH(m)) = e(G, Signature)
+use crate::backend;
+use crate::secret::SecretKey;
+use bls12_381::*;
+use group::Curve;
+
+/// The domain separation tag of the basic scheme ciphersuite.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// The domain separation tag of the message augmentation ciphersuite.
+pub const AUG_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
+
+/// The signature schemes from the IETF BLS signature draft that we support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Ciphersuite {
+    /// Signs `H(m)`. Aggregating signatures of different signers on the same
+    /// message is only safe with distinct messages or proofs of possession.
+    #[default]
+    Basic,
+    /// Signs `H(pk || m)`, so every signer signs a distinct message even when
+    /// the plaintext is shared, which rules out rogue key attacks on
+    /// aggregation.
+    MessageAugmentation,
+}
+
+impl Ciphersuite {
+    pub fn dst(&self) -> &'static [u8] {
+        match self {
+            Ciphersuite::Basic => DST,
+            Ciphersuite::MessageAugmentation => AUG_DST,
+        }
+    }
+
+    /// Hashes the message as signed by the owner of `pk`.
+    pub fn hash_message(&self, pk: &G1Affine, msg: &[u8]) -> G2Affine {
+        match self {
+            Ciphersuite::Basic => hash_to_g2(msg, self.dst()).to_affine(),
+            Ciphersuite::MessageAugmentation => {
+                let mut augmented = pk.to_compressed().to_vec();
+                augmented.extend_from_slice(msg);
+                hash_to_g2(&augmented, self.dst()).to_affine()
+            }
+        }
+    }
+
+    pub fn sign(&self, sk: &SecretKey, msg: &[u8]) -> G2Affine {
+        let hm = self.hash_message(&sk.public_key(), msg);
+        backend::g2_mul(&hm, sk.as_scalar())
+    }
+
+    pub fn verify(&self, pk: &G1Affine, msg: &[u8], sig: &G2Affine) -> bool {
+        verify_hashed(pk, &self.hash_message(pk, msg), sig)
+    }
+}
+
+/// Hashes a message to a point in G2 under the given domain separation tag.
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Projective {
+    backend::hash_to_g2(msg, dst)
+}
+
+/// Returns `sk * G`.
+pub fn public_key(sk: &SecretKey) -> G1Affine {
+    sk.public_key()
+}
+
+/// Signs `msg` under the basic scheme.
+pub fn sign(sk: &SecretKey, msg: &[u8]) -> G2Affine {
+    Ciphersuite::Basic.sign(sk, msg)
+}
+
+/// Verifies a basic scheme signature.
+pub fn verify(pk: &G1Affine, msg: &[u8], sig: &G2Affine) -> bool {
+    Ciphersuite::Basic.verify(pk, msg, sig)
+}
+
+/// Checks `e(pk, H(m)) == e(G, sig)` for a message that is already hashed to
+/// the curve.
+pub fn verify_hashed(pk: &G1Affine, hm: &G2Affine, sig: &G2Affine) -> bool {
+    backend::pairing_check(pk, hm, sig)
+}
@@ -0,0 +1,157 @@
+//! Slashing protection for share holders.
+//!
+//! A share holder that signs two different messages for the same epoch helps
+//! the committee produce two conflicting group signatures, which is exactly
+//! what validator-style deployments get punished for. Before producing a
+//! partial signature we record `(epoch, SHA256(message))` in an append-only
+//! file and refuse anything that conflicts with what was already signed:
+//!
+//! 1. Signing the same message again for an epoch is fine.
+//! 2. Signing a different message for an already signed epoch is refused.
+//! 3. Signing for an epoch older than the newest signed one is refused, so a
+//!    lost or rolled back record can't be exploited with old epochs.
+use crate::secret::SecretKey;
+use crate::signature::{hash_to_g2, DST};
+use crate::threshold::{self, PartialSignature};
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum SlashingError {
+    /// A different message was already signed for this epoch.
+    DoubleSign {
+        epoch: u64,
+    },
+    /// The epoch is older than the newest epoch we signed for.
+    Stale {
+        epoch: u64,
+        newest: u64,
+    },
+    /// The database file contains a line we can't parse.
+    Corrupt {
+        line: usize,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for SlashingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlashingError::DoubleSign { epoch } => {
+                write!(
+                    f,
+                    "refusing to sign a conflicting message for epoch {}",
+                    epoch
+                )
+            }
+            SlashingError::Stale { epoch, newest } => write!(
+                f,
+                "refusing to sign for epoch {}, already signed epoch {}",
+                epoch, newest
+            ),
+            SlashingError::Corrupt { line } => {
+                write!(
+                    f,
+                    "slashing protection database is corrupt at line {}",
+                    line
+                )
+            }
+            SlashingError::Io(e) => write!(f, "slashing protection io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SlashingError {}
+
+impl From<io::Error> for SlashingError {
+    fn from(e: io::Error) -> Self {
+        SlashingError::Io(e)
+    }
+}
+
+/// The signing history of one share holder, backed by a flat file with one
+/// `<epoch> <hex sha256 of message>` line per signature.
+#[derive(Debug)]
+pub struct SlashingProtection {
+    file: File,
+    signed: BTreeMap<u64, [u8; 32]>,
+}
+
+impl SlashingProtection {
+    /// Opens the database, creating an empty one if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SlashingError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        let mut signed = BTreeMap::new();
+        for (i, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line?;
+            let (epoch, root) =
+                parse_record(&line).ok_or(SlashingError::Corrupt { line: i + 1 })?;
+            signed.insert(epoch, root);
+        }
+
+        Ok(SlashingProtection { file, signed })
+    }
+
+    /// The newest epoch we have signed for, if any.
+    pub fn newest_epoch(&self) -> Option<u64> {
+        self.signed.keys().next_back().copied()
+    }
+
+    /// Checks that signing `msg` for `epoch` doesn't conflict with the history
+    /// and durably records it. Only sign once this returns `Ok`.
+    pub fn check_and_record(&mut self, epoch: u64, msg: &[u8]) -> Result<(), SlashingError> {
+        let root: [u8; 32] = Sha256::digest(msg).into();
+
+        if let Some(previous) = self.signed.get(&epoch) {
+            return if *previous == root {
+                Ok(())
+            } else {
+                Err(SlashingError::DoubleSign { epoch })
+            };
+        }
+
+        if let Some(newest) = self.newest_epoch() {
+            if epoch < newest {
+                return Err(SlashingError::Stale { epoch, newest });
+            }
+        }
+
+        writeln!(self.file, "{} {}", epoch, hex::encode(root))?;
+        self.file.sync_data()?;
+        self.signed.insert(epoch, root);
+        Ok(())
+    }
+
+    /// Produces the holder's partial signature on `msg` for `epoch`, if it is
+    /// safe to do so.
+    pub fn sign_share<R: RngCore + CryptoRng>(
+        &mut self,
+        epoch: u64,
+        index: u64,
+        share: &SecretKey,
+        msg: &[u8],
+        rng: &mut R,
+    ) -> Result<PartialSignature, SlashingError> {
+        self.check_and_record(epoch, msg)?;
+        let hm = hash_to_g2(msg, DST).to_affine();
+        Ok(threshold::sign_share(index, share, &hm, rng))
+    }
+}
+
+fn parse_record(line: &str) -> Option<(u64, [u8; 32])> {
+    let (epoch, root) = line.split_once(' ')?;
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(root.trim(), &mut bytes).ok()?;
+    Some((epoch.parse().ok()?, bytes))
+}
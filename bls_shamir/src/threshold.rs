@@ -0,0 +1,135 @@
+//! Shamir shares of a secret polynomial `f(x)` and Lagrange interpolation at
+//! zero, which lets any `t + 1` holders of `(x, f(x) * M)` recover `f(0) * M`.
+use crate::backend;
+use crate::secret::SecretKey;
+use bls12_381::*;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sigma::{dleq, Proof};
+use std::fmt;
+use std::ops::Mul;
+
+const DLEQ_CONTEXT: &[u8] = b"zk-lab/threshold/partial-signature";
+
+/// A signature share `(x, f(x) * M)` produced by the holder of share `x`,
+/// with a proof that it matches the public share `f(x) * G`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PartialSignature {
+    pub index: u64,
+    pub point: G2Affine,
+    pub proof: Proof,
+}
+
+/// Computes the Lagrange coefficients `λ_j = ∏ x_m / (x_m - x_j)` for the
+/// given set of distinct, non-zero x coordinates, such that
+/// `f(0) = ∑ λ_j * f(x_j)`.
+pub fn lagrange_at_zero(xs: &[u64]) -> Result<Vec<Scalar>, InterpolationError> {
+    for (k, x) in xs.iter().enumerate() {
+        if *x == 0 {
+            return Err(InterpolationError::ZeroIndex);
+        }
+        if xs[..k].contains(x) {
+            return Err(InterpolationError::RepeatedIndex { index: *x });
+        }
+    }
+
+    Ok(xs
+        .iter()
+        .map(|xj| {
+            let (num, den) = xs.iter().filter(|xm| *xm != xj).fold(
+                (Scalar::one(), Scalar::one()),
+                |(num, den), xm| {
+                    let xm_s = Scalar::from(*xm);
+                    (num * xm_s, den * (xm_s - Scalar::from(*xj)))
+                },
+            );
+
+            // The indices are distinct, and below the order of the field.
+            num * den.invert().unwrap()
+        })
+        .collect())
+}
+
+/// Given the points `(x, y)` on `f(x) * T` for a group (or field) element T,
+/// computes `f(0) * T`.
+pub fn interpolate_at_zero<T>(points: &[(u64, T)]) -> Result<T, InterpolationError>
+where
+    T: Copy + Mul<Scalar, Output = T> + std::iter::Sum,
+{
+    let xs = points.iter().map(|(x, _)| *x).collect::<Vec<_>>();
+
+    Ok(lagrange_at_zero(&xs)?
+        .into_iter()
+        .zip(points)
+        .map(|(l, (_, y))| *y * l)
+        .sum())
+}
+
+/// Produces the share holder's partial signature on an already hashed message.
+pub fn sign_share<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    hm: &G2Affine,
+    rng: &mut R,
+) -> PartialSignature {
+    PartialSignature {
+        index,
+        point: backend::g2_mul(hm, share.as_scalar()),
+        proof: dleq::prove(
+            share.as_scalar(),
+            &G1Projective::generator(),
+            &G2Projective::from(hm),
+            &context(index),
+            rng,
+        ),
+    }
+}
+
+/// Checks a partial signature against the public share `f(x) * G` with its
+/// DLEQ proof, a few multiplications instead of the two pairings of checking
+/// it as a signature.
+pub fn verify_share(public_share: &G1Affine, hm: &G2Affine, partial: &PartialSignature) -> bool {
+    dleq::verify(
+        &G1Projective::generator(),
+        &G1Projective::from(public_share),
+        &G2Projective::from(hm),
+        &G2Projective::from(partial.point),
+        &context(partial.index),
+        &partial.proof,
+    )
+}
+
+/// Binds a proof to the index it was made for, so it can't be replayed under
+/// another holder's index.
+fn context(index: u64) -> Vec<u8> {
+    [DLEQ_CONTEXT, &index.to_be_bytes()].concat()
+}
+
+/// Combines `t + 1` valid partial signatures into `f(0) * M`.
+pub fn aggregate_shares(partials: &[PartialSignature]) -> Result<G2Affine, InterpolationError> {
+    let xs = partials.iter().map(|p| p.index).collect::<Vec<_>>();
+    let points = partials.iter().map(|p| p.point).collect::<Vec<_>>();
+
+    Ok(backend::g2_msm(&points, &lagrange_at_zero(&xs)?).to_affine())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationError {
+    /// Index zero is the secret, not a share.
+    ZeroIndex,
+    /// Two points share an index.
+    RepeatedIndex { index: u64 },
+}
+
+impl fmt::Display for InterpolationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpolationError::ZeroIndex => write!(f, "share index 0 is the secret"),
+            InterpolationError::RepeatedIndex { index } => {
+                write!(f, "share index {} is repeated", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InterpolationError {}
@@ -0,0 +1,83 @@
+//! A verifiable random function on the threshold key.
+//!
+//! The proof for an input is the BLS signature `s * H(input)`, with its own
+//! domain separation tag so a proof is never a valid signature, and the
+//! output is the hash of the proof. BLS signatures are unique, so there is
+//! exactly one output per key and input, nobody without `t + 1` shares can
+//! predict it, and anyone can check it against the group public key.
+//!
+//! Unlike the beacon, which signs one chained round after the other on a
+//! schedule, the VRF is evaluated on demand on any input, e.g. to draw a
+//! lottery for a named epoch.
+use crate::secret::SecretKey;
+use crate::signature::{hash_to_g2, verify_hashed};
+use crate::threshold::{self, InterpolationError, PartialSignature};
+use bls12_381::*;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Domain separation tag for hashing inputs to G2.
+pub const DST: &[u8] = b"ZK_LAB_VRF_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+const OUTPUT_TAG: &[u8] = b"ZK_LAB_VRF_PROOF_TO_OUTPUT";
+
+/// The proof `s * H(input)` of an evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Proof(pub G2Affine);
+
+impl Proof {
+    /// The output the proof attests to, only meaningful once the proof is
+    /// verified.
+    pub fn output(&self) -> [u8; 32] {
+        Sha256::new()
+            .chain(OUTPUT_TAG)
+            .chain(self.0.to_compressed())
+            .finalize()
+            .into()
+    }
+}
+
+/// Hashes an input to the point `H(input)` in G2.
+pub fn hash_input(input: &[u8]) -> G2Affine {
+    hash_to_g2(input, DST).to_affine()
+}
+
+/// Evaluates the VRF on `input` with a whole key.
+pub fn vrf_eval(sk: &SecretKey, input: &[u8]) -> Proof {
+    Proof((hash_input(input) * sk.as_scalar()).to_affine())
+}
+
+/// A share holder's part of the proof for `input`.
+pub fn vrf_partial_eval<R: RngCore + CryptoRng>(
+    index: u64,
+    share: &SecretKey,
+    input: &[u8],
+    rng: &mut R,
+) -> PartialSignature {
+    threshold::sign_share(index, share, &hash_input(input), rng)
+}
+
+/// Checks a share holder's part against its public share `s_i * G`.
+pub fn vrf_verify_partial(
+    public_share: &G1Affine,
+    input: &[u8],
+    partial: &PartialSignature,
+) -> bool {
+    threshold::verify_share(public_share, &hash_input(input), partial)
+}
+
+/// Combines `t + 1` valid parts into the proof.
+pub fn vrf_aggregate(partials: &[PartialSignature]) -> Result<Proof, InterpolationError> {
+    threshold::aggregate_shares(partials).map(Proof)
+}
+
+/// Checks `e(pk, H(input)) == e(G, proof)`, returning the output if the
+/// proof is valid.
+pub fn vrf_verify(pk: &G1Affine, input: &[u8], proof: &Proof) -> Option<[u8; 32]> {
+    if verify_hashed(pk, &hash_input(input), &proof.0) {
+        Some(proof.output())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,46 @@
+use bls12_381::{G1Affine, Scalar};
+use bls_shamir::adaptor::*;
+use bls_shamir::schnorr;
+use bls_shamir::secret::SecretKey;
+use rand::thread_rng;
+
+#[test]
+fn completing_a_pre_signature_reveals_the_secret() {
+    let mut rng = thread_rng();
+    let alice = SecretKey::random(&mut rng);
+    let pk = alice.public_key();
+    let t = SecretKey::random(&mut rng);
+    let adaptor = t.public_key();
+
+    let pre = pre_sign(&alice, b"pay bob", &adaptor, &mut rng);
+    assert!(pre_verify(&pk, b"pay bob", &adaptor, &pre));
+    // Not a signature by itself.
+    let unadapted = schnorr::Signature { r: pre.r, z: pre.s };
+    assert!(!schnorr::verify(&pk, b"pay bob", &unadapted));
+
+    let signature = adapt(&pre, &t);
+    assert!(schnorr::verify(&pk, b"pay bob", &signature));
+    assert_eq!(extract(&pre, &signature, &adaptor), Some(t));
+}
+
+#[test]
+fn rejects_mismatched_pre_signatures() {
+    let mut rng = thread_rng();
+    let alice = SecretKey::random(&mut rng);
+    let pk = alice.public_key();
+    let t = SecretKey::random(&mut rng);
+    let adaptor = t.public_key();
+    let pre = pre_sign(&alice, b"pay bob", &adaptor, &mut rng);
+
+    assert!(!pre_verify(&pk, b"pay carol", &adaptor, &pre));
+    assert!(!pre_verify(&pk, b"pay bob", &G1Affine::generator(), &pre));
+    let mut tampered = pre;
+    tampered.s += Scalar::one();
+    assert!(!pre_verify(&pk, b"pay bob", &adaptor, &tampered));
+
+    // The wrong secret completes to an invalid signature, from which
+    // nothing is extracted.
+    let wrong = adapt(&pre, &SecretKey::random(&mut rng));
+    assert!(!schnorr::verify(&pk, b"pay bob", &wrong));
+    assert_eq!(extract(&pre, &wrong, &adaptor), None);
+}
@@ -0,0 +1,109 @@
+use bls12_381::G1Affine;
+use bls_shamir::aggregate::*;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature;
+use rand::thread_rng;
+
+fn committee(n: usize) -> (Vec<SecretKey>, Vec<G1Affine>) {
+    let sks = (0..n)
+        .map(|_| SecretKey::random(&mut thread_rng()))
+        .collect::<Vec<_>>();
+    let pks = sks.iter().map(SecretKey::public_key).collect();
+    (sks, pks)
+}
+
+#[test]
+fn bitfields_round_trip_through_bytes() {
+    let mut bits = Bitfield::new(11);
+    for i in [0, 3, 8, 10] {
+        assert!(bits.set(i));
+    }
+    assert!(!bits.set(11));
+    let bytes = bits.to_bytes();
+    assert_eq!(bytes, [0b0000_1001, 0b0000_0101]);
+    assert_eq!(Bitfield::from_bytes(11, &bytes), Some(bits.clone()));
+    assert_eq!(bits.ones().collect::<Vec<_>>(), [0, 3, 8, 10]);
+    assert_eq!(bits.count_ones(), 4);
+
+    // A whole number of bytes has no padding.
+    let full = Bitfield::from_bytes(16, &[0xff, 0xff]).unwrap();
+    assert_eq!(full.count_ones(), 16);
+}
+
+#[test]
+fn bitfields_reject_padding_bits_and_lengths() {
+    // Bits 11 to 15 are padding for a length of 11.
+    for padding in 11..16 {
+        let mut bytes = [0u8, 0];
+        bytes[padding / 8] |= 1 << (padding % 8);
+        assert_eq!(Bitfield::from_bytes(11, &bytes), None);
+    }
+    assert_eq!(Bitfield::from_bytes(11, &[0]), None);
+    assert_eq!(Bitfield::from_bytes(11, &[0, 0, 0]), None);
+    assert_eq!(Bitfield::from_bytes(0, &[]), Some(Bitfield::new(0)));
+}
+
+#[test]
+fn aggregates_verify_against_the_participants() {
+    let (sks, pks) = committee(5);
+    let msg = b"attestation";
+    let mut aggregate = AggregateWithBitfield::new(5);
+    assert!(!aggregate.verify(&pks, msg));
+    for i in [0, 2, 4] {
+        aggregate.add(i, &signature::sign(&sks[i], msg)).unwrap();
+    }
+    assert_eq!(aggregate.participants(), 3);
+    assert!(aggregate.verify(&pks, msg));
+    assert!(!aggregate.verify(&pks, b"another"));
+    assert!(!aggregate.verify(&pks[..4], msg));
+
+    // Claiming a member that didn't sign breaks it.
+    let mut claimed = aggregate.clone();
+    claimed.bits.set(1);
+    assert!(!claimed.verify(&pks, msg));
+
+    assert_eq!(
+        aggregate.add(2, &signature::sign(&sks[2], msg)),
+        Err(AggregateError::Overlap)
+    );
+    assert_eq!(
+        aggregate.add(5, &signature::sign(&sks[2], msg)),
+        Err(AggregateError::IndexOutOfRange)
+    );
+}
+
+#[test]
+fn merges_disjoint_aggregates_only() {
+    let (sks, pks) = committee(4);
+    let msg = b"attestation";
+    let single = |i: usize| {
+        AggregateWithBitfield::from_signature(4, i, &signature::sign(&sks[i], msg)).unwrap()
+    };
+
+    let left = single(0).merge(&single(1)).unwrap();
+    let right = single(2).merge(&single(3)).unwrap();
+    let all = left.merge(&right).unwrap();
+    assert_eq!(all.participants(), 4);
+    assert!(all.verify(&pks, msg));
+    assert_eq!(all, right.merge(&left).unwrap());
+
+    // Overlapping in a single member is enough to be refused.
+    let overlapping = single(1).merge(&single(2)).unwrap();
+    assert_eq!(left.merge(&overlapping), Err(AggregateError::Overlap));
+    assert_eq!(all.merge(&single(3)), Err(AggregateError::Overlap));
+    assert_eq!(left.merge(&left), Err(AggregateError::Overlap));
+}
+
+#[test]
+fn refuses_to_merge_committees_of_other_sizes() {
+    let (sks, _) = committee(2);
+    let signature = signature::sign(&sks[0], b"attestation");
+    let small = AggregateWithBitfield::from_signature(2, 0, &signature).unwrap();
+    let large = AggregateWithBitfield::from_signature(3, 1, &signature).unwrap();
+    assert_eq!(small.merge(&large), Err(AggregateError::SizeMismatch));
+    assert_eq!(large.merge(&small), Err(AggregateError::SizeMismatch));
+    assert_eq!(
+        AggregateWithBitfield::from_signature(2, 2, &signature),
+        Err(AggregateError::IndexOutOfRange)
+    );
+}
@@ -0,0 +1,123 @@
+//! The backends against the reference computations of the `bls12_381`
+//! crate. The pure backend is checked on every run, the blst one is checked
+//! against it too with `cargo test --features backend-blst`.
+use bls12_381::*;
+#[cfg(feature = "backend-blst")]
+use bls_shamir::backend::blst;
+use bls_shamir::backend::pure;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature::{Ciphersuite, AUG_DST, DST};
+use group::ff::Field;
+use group::{Curve, Group};
+use rand::thread_rng;
+
+fn random_g1(n: usize) -> Vec<G1Affine> {
+    (0..n)
+        .map(|_| G1Projective::random(thread_rng()).to_affine())
+        .collect()
+}
+
+fn random_g2(n: usize) -> Vec<G2Affine> {
+    (0..n)
+        .map(|_| G2Projective::random(thread_rng()).to_affine())
+        .collect()
+}
+
+fn random_scalars(n: usize) -> Vec<Scalar> {
+    (0..n).map(|_| Scalar::random(thread_rng())).collect()
+}
+
+#[test]
+fn hash_to_g2_matches() {
+    for msg in [&b""[..], b"abc", b"Hello world", &[0xffu8; 300]] {
+        for dst in [DST, AUG_DST] {
+            let hm = pure::hash_to_g2(msg, dst);
+            assert!(bool::from(hm.to_affine().is_torsion_free()));
+            assert_ne!(hm, pure::hash_to_g2(b"another message", dst));
+            #[cfg(feature = "backend-blst")]
+            assert_eq!(hm, blst::hash_to_g2(msg, dst));
+        }
+    }
+}
+
+#[test]
+fn g2_mul_matches() {
+    for (p, s) in random_g2(8).iter().zip(random_scalars(8)) {
+        assert_eq!(pure::g2_mul(p, &s), (p * s).to_affine());
+        #[cfg(feature = "backend-blst")]
+        assert_eq!(pure::g2_mul(p, &s), blst::g2_mul(p, &s));
+    }
+
+    let p = G2Affine::generator();
+    assert_eq!(pure::g2_mul(&p, &Scalar::zero()), G2Affine::identity());
+    assert_eq!(
+        pure::g2_mul(&G2Affine::identity(), &Scalar::one()),
+        G2Affine::identity()
+    );
+    #[cfg(feature = "backend-blst")]
+    {
+        assert_eq!(
+            pure::g2_mul(&p, &Scalar::zero()),
+            blst::g2_mul(&p, &Scalar::zero())
+        );
+        assert_eq!(
+            pure::g2_mul(&G2Affine::identity(), &Scalar::one()),
+            blst::g2_mul(&G2Affine::identity(), &Scalar::one())
+        );
+    }
+}
+
+#[test]
+fn pairing_check_matches() {
+    let sk = Scalar::random(thread_rng());
+    let pk = (G1Affine::generator() * sk).to_affine();
+    let hm = random_g2(1)[0];
+    let sig = (hm * sk).to_affine();
+    let bad = (hm * (sk + Scalar::one())).to_affine();
+
+    assert!(pure::pairing_check(&pk, &hm, &sig));
+    assert!(!pure::pairing_check(&pk, &hm, &bad));
+    #[cfg(feature = "backend-blst")]
+    {
+        assert!(blst::pairing_check(&pk, &hm, &sig));
+        assert!(!blst::pairing_check(&pk, &hm, &bad));
+    }
+}
+
+#[test]
+fn msm_matches() {
+    for n in [0, 1, 2, 7, 33, 100] {
+        let scalars = random_scalars(n);
+
+        let g1 = random_g1(n);
+        let naive = g1
+            .iter()
+            .zip(&scalars)
+            .fold(G1Projective::identity(), |acc, (p, s)| acc + p * s);
+        assert_eq!(pure::g1_msm(&g1, &scalars), naive);
+        #[cfg(feature = "backend-blst")]
+        assert_eq!(pure::g1_msm(&g1, &scalars), blst::g1_msm(&g1, &scalars));
+
+        let g2 = random_g2(n);
+        let naive = g2
+            .iter()
+            .zip(&scalars)
+            .fold(G2Projective::identity(), |acc, (p, s)| acc + p * s);
+        assert_eq!(pure::g2_msm(&g2, &scalars), naive);
+        #[cfg(feature = "backend-blst")]
+        assert_eq!(pure::g2_msm(&g2, &scalars), blst::g2_msm(&g2, &scalars));
+    }
+}
+
+#[test]
+fn signatures_match_the_pure_backend() {
+    let sk = SecretKey::random(&mut thread_rng());
+    let pk = sk.public_key();
+
+    for suite in [Ciphersuite::Basic, Ciphersuite::MessageAugmentation] {
+        let sig = suite.sign(&sk, b"Hello world");
+        let hm = suite.hash_message(&pk, b"Hello world");
+        assert_eq!(sig, pure::g2_mul(&hm, sk.as_scalar()));
+        assert!(suite.verify(&pk, b"Hello world", &sig));
+    }
+}
@@ -0,0 +1,82 @@
+use bls_shamir::blind::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::signature;
+use bls_shamir::threshold::InterpolationError;
+use rand::thread_rng;
+
+#[test]
+fn unblinds_into_a_plain_signature() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = sk.public_key();
+
+    let (factor, blinded) = blind(b"hello", &mut rng);
+    // The signer sees neither the message nor its hash.
+    assert_ne!(
+        blinded.0,
+        signature::hash_to_g2(b"hello", signature::DST).into()
+    );
+    let blind_signature = sign_blinded(&sk, &blinded);
+    assert!(verify_blinded(&pk, &blinded, &blind_signature));
+
+    let sig = unblind(&factor, &blind_signature);
+    assert!(signature::verify(&pk, b"hello", &sig));
+    assert!(!signature::verify(&pk, b"goodbye", &sig));
+    assert_eq!(sig, signature::sign(&sk, b"hello"));
+
+    // Blinding the same message twice gives unlinkable requests.
+    let (_, again) = blind(b"hello", &mut rng);
+    assert_ne!(again, blinded);
+}
+
+#[test]
+fn rejects_a_blind_signature_of_another_key() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let other = SecretKey::random(&mut rng);
+
+    let (factor, blinded) = blind(b"hello", &mut rng);
+    let blind_signature = sign_blinded(&other, &blinded);
+    assert!(!verify_blinded(
+        &sk.public_key(),
+        &blinded,
+        &blind_signature
+    ));
+    let sig = unblind(&factor, &blind_signature);
+    assert!(!signature::verify(&sk.public_key(), b"hello", &sig));
+}
+
+#[test]
+fn threshold_shares_combine_into_the_group_signature() {
+    let mut rng = thread_rng();
+    let f = SecretPolynomial::random(1, &mut rng);
+    let pk = f.secret().public_key();
+
+    let (factor, blinded) = blind(b"hello", &mut rng);
+    let partials = (1..=3)
+        .map(|i| sign_blinded_share(i, &f.evaluate(i), &blinded, &mut rng))
+        .collect::<Vec<_>>();
+    for partial in &partials {
+        let public_share = f.evaluate(partial.index).public_key();
+        assert!(verify_blinded_share(&public_share, &blinded, partial));
+    }
+    assert!(!verify_blinded_share(
+        &f.evaluate(2).public_key(),
+        &blinded,
+        &partials[0]
+    ));
+
+    let first = combine_blinded_shares(&partials[..2]).unwrap();
+    assert_eq!(first, combine_blinded_shares(&partials[1..]).unwrap());
+    assert!(verify_blinded(&pk, &blinded, &first));
+    let sig = unblind(&factor, &first);
+    assert!(signature::verify(&pk, b"hello", &sig));
+
+    // A single share isn't enough, and one can't stand in for two.
+    let too_few = combine_blinded_shares(&partials[..1]).unwrap();
+    assert!(!verify_blinded(&pk, &blinded, &too_few));
+    assert_eq!(
+        combine_blinded_shares(&[partials[0], partials[0]]),
+        Err(InterpolationError::RepeatedIndex { index: 1 })
+    );
+}
@@ -0,0 +1,61 @@
+use bls12_381::Scalar;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature::*;
+
+const SUITES: [Ciphersuite; 2] = [Ciphersuite::Basic, Ciphersuite::MessageAugmentation];
+
+fn keys() -> (SecretKey, SecretKey) {
+    (
+        SecretKey::new(Scalar::from(0x1234_5678)),
+        SecretKey::new(Scalar::from(0x8765_4321)),
+    )
+}
+
+#[test]
+fn each_scheme_verifies_its_own_signatures() {
+    let (sk, _) = keys();
+    let pk = public_key(&sk);
+
+    for suite in SUITES {
+        let sig = suite.sign(&sk, b"Hello world");
+        assert!(suite.verify(&pk, b"Hello world", &sig));
+        assert!(!suite.verify(&pk, b"Hello world!", &sig));
+    }
+}
+
+#[test]
+fn signatures_do_not_verify_across_schemes() {
+    let (sk, _) = keys();
+    let pk = public_key(&sk);
+
+    let basic = Ciphersuite::Basic.sign(&sk, b"Hello world");
+    let aug = Ciphersuite::MessageAugmentation.sign(&sk, b"Hello world");
+    assert_ne!(basic, aug);
+
+    assert!(!Ciphersuite::MessageAugmentation.verify(&pk, b"Hello world", &basic));
+    assert!(!Ciphersuite::Basic.verify(&pk, b"Hello world", &aug));
+}
+
+#[test]
+fn augmented_message_is_bound_to_the_signer() {
+    let (sk1, sk2) = keys();
+    let (pk1, pk2) = (public_key(&sk1), public_key(&sk2));
+    let suite = Ciphersuite::MessageAugmentation;
+
+    let sig1 = suite.sign(&sk1, b"Hello world");
+    let sig2 = suite.sign(&sk2, b"Hello world");
+
+    assert!(!suite.verify(&pk2, b"Hello world", &sig1));
+    assert!(!suite.verify(&pk1, b"Hello world", &sig2));
+    assert_ne!(
+        suite.hash_message(&pk1, b"Hello world"),
+        suite.hash_message(&pk2, b"Hello world")
+    );
+}
+
+#[test]
+fn basic_scheme_is_the_default() {
+    let (sk, _) = keys();
+    assert_eq!(Ciphersuite::default(), Ciphersuite::Basic);
+    assert_eq!(sign(&sk, b"m"), Ciphersuite::Basic.sign(&sk, b"m"));
+}
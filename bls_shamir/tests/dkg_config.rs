@@ -0,0 +1,109 @@
+use bls12_381::Scalar;
+use bls_shamir::dkg_config::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::signature::{self, DST};
+use bls_shamir::threshold;
+use group::Curve;
+use rand::thread_rng;
+
+#[test]
+fn checks_the_sizes() {
+    assert!(DkgConfig::new(10, 3, 3).is_ok());
+    assert!(DkgConfig::new(6, 4, 1).is_ok());
+    assert!(DkgConfig::new(4, 3, 0).is_ok());
+    assert_eq!(DkgConfig::new(4, 0, 0), Err(ConfigError::ZeroThreshold));
+    assert_eq!(
+        DkgConfig::new(7, 2, 3),
+        Err(ConfigError::FaultsAboveThreshold {
+            faults: 3,
+            threshold: 2
+        })
+    );
+    assert_eq!(
+        DkgConfig::new(6, 3, 2),
+        Err(ConfigError::TooManyFaults {
+            faults: 2,
+            members: 6
+        })
+    );
+    assert_eq!(
+        DkgConfig::new(6, 5, 1),
+        Err(ConfigError::Unreachable {
+            threshold: 5,
+            faults: 1,
+            members: 6
+        })
+    );
+    assert!(DkgConfig::new(4, 4, 0).is_err());
+}
+
+#[test]
+fn tolerates_the_threshold_when_it_is_low() {
+    let low = DkgConfig::with_threshold(7, 2).unwrap();
+    assert_eq!(low.faults(), 2);
+    assert_eq!(low.quorum(), 5);
+    assert!(!low.is_high_threshold());
+    assert!(low.allows_asynchrony());
+
+    // Of 5 members 2 could crash, but then the other 3 would be no quorum.
+    let five = DkgConfig::with_threshold(5, 2).unwrap();
+    assert_eq!(five.faults(), 1);
+    assert_eq!(five.quorum(), 3);
+    assert!(five.quorum() + five.faults() <= five.members());
+
+    let high = DkgConfig::with_threshold(6, 4).unwrap();
+    assert!(high.is_high_threshold());
+    assert_eq!(high.faults(), 1);
+    assert_eq!(high.min_qualified(), 2);
+    // Vouching for a dealing would take all 6, the faulty one included.
+    assert_eq!(high.recovery_quorum(), 6);
+    assert!(!high.allows_asynchrony());
+    assert!(DkgConfig::new(7, 4, 1).unwrap().allows_asynchrony());
+
+    // Everyone has to sign, so no one may fail.
+    assert_eq!(DkgConfig::with_threshold(4, 3).unwrap().faults(), 0);
+    assert!(DkgConfig::with_threshold(4, 4).is_err());
+}
+
+#[test]
+fn a_high_threshold_key_takes_two_thirds_to_sign() {
+    let mut rng = thread_rng();
+    let config = DkgConfig::with_threshold(6, 4).unwrap();
+    let n = config.members() as u64;
+    let dealers = (0..n)
+        .map(|_| SecretPolynomial::random(config.threshold(), &mut rng))
+        .collect::<Vec<_>>();
+
+    // Member 6 is the faulty one, its dealing is left out and the others
+    // still qualify.
+    let qualified = &dealers[..5];
+    assert!(qualified.len() >= config.min_qualified());
+    let shares = (1..=n)
+        .map(|x| {
+            let share = qualified
+                .iter()
+                .map(|polynomial| *polynomial.evaluate(x).as_scalar())
+                .sum::<Scalar>();
+            SecretKey::new(share)
+        })
+        .collect::<Vec<_>>();
+    let group_key = qualified
+        .iter()
+        .map(|polynomial| *polynomial.secret().as_scalar())
+        .sum::<Scalar>();
+    let public_key = SecretKey::new(group_key).public_key();
+
+    // The honest members sign without the faulty one.
+    let hm = signature::hash_to_g2(b"hello", DST).to_affine();
+    let partials = shares[..5]
+        .iter()
+        .zip(1..)
+        .map(|(share, x)| threshold::sign_share(x, share, &hm, &mut rng))
+        .collect::<Vec<_>>();
+    let signature = threshold::aggregate_shares(&partials).unwrap();
+    assert!(signature::verify(&public_key, b"hello", &signature));
+
+    // A half of the committee is not enough.
+    let too_few = threshold::aggregate_shares(&partials[..4]).unwrap();
+    assert!(!signature::verify(&public_key, b"hello", &too_few));
+}
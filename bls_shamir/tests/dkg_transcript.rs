@@ -0,0 +1,109 @@
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective};
+use bls_shamir::dkg_transcript::*;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::threshold;
+use group::Curve;
+use rand::thread_rng;
+
+fn members(n: usize) -> (Vec<SecretKey>, Vec<G2Affine>) {
+    let mut rng = thread_rng();
+    let dks = (0..n)
+        .map(|_| SecretKey::random(&mut rng))
+        .collect::<Vec<_>>();
+    let keys = dks.iter().map(encryption_key).collect();
+    (dks, keys)
+}
+
+#[test]
+fn aggregated_transcripts_share_the_sum_of_the_secrets() {
+    let mut rng = thread_rng();
+    let (dks, keys) = members(4);
+    let transcripts = (1..=4)
+        .map(|dealer| deal(dealer, 1, &keys, b"dkg", &mut rng))
+        .collect::<Vec<_>>();
+    for transcript in &transcripts {
+        assert!(verify(1, &keys, transcript, b"dkg"));
+    }
+    let aggregated = transcripts[1..]
+        .iter()
+        .try_fold(transcripts[0].clone(), |sum, transcript| {
+            aggregate(&sum, transcript)
+        })
+        .unwrap();
+    assert!(verify(1, &keys, &aggregated, b"dkg"));
+    assert_eq!(aggregated.contributions.len(), 4);
+    let group_key = transcripts
+        .iter()
+        .map(|transcript| G1Projective::from(transcript.commitments[0]))
+        .sum::<G1Projective>();
+    assert_eq!(aggregated.commitments[0], group_key.to_affine());
+
+    let shares = dks
+        .iter()
+        .zip(1..)
+        .map(|(dk, index)| {
+            let share = decrypt(dk, index, &aggregated).unwrap();
+            assert!(verify_share(&aggregated, index, &share));
+            (index, G2Projective::from(share))
+        })
+        .collect::<Vec<_>>();
+    // The secret behind the group key, in G2.
+    let secret = threshold::interpolate_at_zero(&shares[..2]).unwrap();
+    assert_eq!(
+        secret,
+        threshold::interpolate_at_zero(&shares[2..]).unwrap()
+    );
+    assert_eq!(
+        bls12_381::pairing(&group_key.to_affine(), &G2Affine::generator()),
+        bls12_381::pairing(&G1Affine::generator(), &secret.to_affine())
+    );
+}
+
+#[test]
+fn adding_a_transcript_twice_weighs_it_twice() {
+    let mut rng = thread_rng();
+    let (_, keys) = members(3);
+    let a = deal(1, 1, &keys, b"dkg", &mut rng);
+    let b = deal(2, 1, &keys, b"dkg", &mut rng);
+    let twice = aggregate(&aggregate(&a, &b).unwrap(), &a).unwrap();
+    assert_eq!(twice.contributions[0].weight, 2);
+    assert_eq!(twice.contributions[1].weight, 1);
+    assert!(verify(1, &keys, &twice, b"dkg"));
+
+    // A dealer can't take part with two secrets.
+    let again = deal(1, 1, &keys, b"dkg", &mut rng);
+    assert_eq!(aggregate(&a, &again), None);
+}
+
+#[test]
+fn rejects_invalid_transcripts() {
+    let mut rng = thread_rng();
+    let (dks, keys) = members(4);
+    let transcript = deal(1, 1, &keys, b"dkg", &mut rng);
+    assert!(!verify(1, &keys, &transcript, b"another dkg"));
+    // Of degree 2, not 1.
+    assert!(!verify(
+        1,
+        &keys,
+        &deal(1, 2, &keys, b"dkg", &mut rng),
+        b"dkg"
+    ));
+
+    let mut encrypted = transcript.clone();
+    encrypted.encrypted.swap(0, 1);
+    assert!(!verify(1, &keys, &encrypted, b"dkg"));
+
+    // Claiming another dealer's contribution.
+    let mut stolen = transcript.clone();
+    stolen.contributions[0].dealer = 2;
+    assert!(!verify(1, &keys, &stolen, b"dkg"));
+
+    let mut unweighted = transcript.clone();
+    unweighted.contributions[0].weight = 2;
+    assert!(!verify(1, &keys, &unweighted, b"dkg"));
+
+    let share = decrypt(&dks[0], 1, &transcript).unwrap();
+    assert!(!verify_share(&transcript, 2, &share));
+    assert!(!verify_share(&transcript, 0, &share));
+    assert_eq!(decrypt(&dks[0], 5, &transcript), None);
+}
@@ -0,0 +1,70 @@
+use bls12_381::Scalar;
+use bls_shamir::eip2333::*;
+
+/// Parses a decimal integer smaller than the group order into a scalar.
+fn scalar(decimal: &str) -> Scalar {
+    decimal.bytes().fold(Scalar::zero(), |acc, digit| {
+        acc * Scalar::from(10) + Scalar::from((digit - b'0') as u64)
+    })
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// The test vectors from the EIP-2333 specification.
+const VECTORS: &[(&str, &str, u32, &str)] = &[
+    (
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        "6083874454709270928345386274498605044986640685124978867557563392430687146096",
+        0,
+        "20397789859736650942317412262472558107875392172444076792671091975210932703118",
+    ),
+    (
+        "3141592653589793238462643383279502884197169399375105820974944592",
+        "29757020647961307431480504535336562678282505419141012933316116377660817309383",
+        3141592653,
+        "25457201688850691947727629385191704516744796114925897962676248250929345014287",
+    ),
+    (
+        "0099ff991111002299dd7744ee3355bbdd8844115566cc55663355668888cc00",
+        "27580842291869792442942448775674722299803720648445448686099262467207037398656",
+        4294967295,
+        "29358610794459428860402234341874281240803786294062035874021252734817515685787",
+    ),
+    (
+        "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+        "19022158461524446591288038168518313374041767046816487870552872741050760015818",
+        42,
+        "31372231650479070279774297061823572166496564838472787488249775572789064611981",
+    ),
+];
+
+#[test]
+fn master_and_child_keys_match_spec() {
+    for (seed, master, index, child) in VECTORS {
+        let master_sk = derive_master_sk(&hex(seed)).unwrap();
+        assert_eq!(*master_sk.as_scalar(), scalar(master));
+
+        let child_sk = derive_child_sk(&master_sk, *index);
+        assert_eq!(*child_sk.as_scalar(), scalar(child));
+    }
+}
+
+#[test]
+fn path_derivation_walks_the_tree() {
+    let seed = hex(VECTORS[0].0);
+    let child = derive_sk_from_path(&seed, &[0]).unwrap();
+    assert_eq!(*child.as_scalar(), scalar(VECTORS[0].3));
+
+    let nested = derive_sk_from_path(&seed, &[12381, 3600, 0, 0]).unwrap();
+    assert_ne!(nested, child);
+}
+
+#[test]
+fn short_seed_is_rejected() {
+    assert!(derive_master_sk(&[0u8; MIN_SEED_LEN - 1]).is_none());
+}
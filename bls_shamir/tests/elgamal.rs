@@ -0,0 +1,57 @@
+use bls12_381::Scalar;
+use bls_shamir::elgamal::*;
+use bls_shamir::secret::SecretPolynomial;
+
+fn polynomial() -> SecretPolynomial {
+    SecretPolynomial::new(vec![Scalar::from(0x1234_5678), Scalar::from(0x8765_4321)])
+}
+
+#[test]
+fn any_threshold_of_shares_decrypts() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let ct = encrypt(&f.secret().public_key(), b"Hello world", &mut rng);
+    assert_eq!(decrypt(&f.secret(), &ct), Some(b"Hello world".to_vec()));
+
+    let shares = (1..=3)
+        .map(|i| decryption_share(i, &f.evaluate(i), &ct, &mut rng))
+        .collect::<Vec<_>>();
+    for share in &shares {
+        let public_share = f.evaluate(share.index).public_key();
+        assert!(verify_share(&public_share, &ct, share));
+    }
+    assert_eq!(combine(&shares[..2], &ct), Some(b"Hello world".to_vec()));
+    assert_eq!(combine(&shares[1..], &ct), Some(b"Hello world".to_vec()));
+}
+
+#[test]
+fn wrong_shares_are_caught() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let ct = encrypt(&f.secret().public_key(), b"Hello world", &mut rng);
+
+    let share = decryption_share(1, &f.evaluate(1), &ct, &mut rng);
+    // Checked against another holder's public share.
+    assert!(!verify_share(&f.evaluate(2).public_key(), &ct, &share));
+
+    // A share of another ciphertext.
+    let other = encrypt(&f.secret().public_key(), b"Hello world", &mut rng);
+    let mut forged = decryption_share(1, &f.evaluate(1), &other, &mut rng);
+    assert!(!verify_share(&f.evaluate(1).public_key(), &ct, &forged));
+    forged.point = share.point;
+    assert!(!verify_share(&f.evaluate(1).public_key(), &ct, &forged));
+}
+
+#[test]
+fn tampered_ciphertexts_are_rejected() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let mut ct = encrypt(&f.secret().public_key(), b"Hello world", &mut rng);
+    ct.c[0] ^= 1;
+    assert_eq!(decrypt(&f.secret(), &ct), None);
+
+    let shares = (1..=2)
+        .map(|i| decryption_share(i, &f.evaluate(i), &ct, &mut rng))
+        .collect::<Vec<_>>();
+    assert_eq!(combine(&shares, &ct), None);
+}
@@ -0,0 +1,78 @@
+use bls12_381::{G1Affine, Scalar};
+use bls_shamir::escrow::*;
+use bls_shamir::secret::SecretKey;
+use rand::thread_rng;
+
+#[test]
+fn the_agent_recovers_a_verified_share() {
+    let mut rng = thread_rng();
+    let agent = SecretKey::random(&mut rng);
+    let share = SecretKey::random(&mut rng);
+    let public = share.public_key();
+    let (ciphertext, proof) = encrypt(&agent.public_key(), &share, b"backup", &mut rng);
+    assert_eq!(ciphertext.chunks.len(), CHUNKS);
+    assert!(verify(
+        &agent.public_key(),
+        &public,
+        &ciphertext,
+        b"backup",
+        &proof
+    ));
+    assert_eq!(decrypt(&agent, &public, &ciphertext), Some(share));
+
+    let stranger = SecretKey::random(&mut rng);
+    assert_eq!(decrypt(&stranger, &public, &ciphertext), None);
+}
+
+#[test]
+fn rejects_a_ciphertext_of_another_secret() {
+    let mut rng = thread_rng();
+    let agent = SecretKey::random(&mut rng).public_key();
+    let share = SecretKey::random(&mut rng);
+    let (ciphertext, proof) = encrypt(&agent, &share, b"backup", &mut rng);
+
+    let other = SecretKey::random(&mut rng).public_key();
+    assert!(!verify(&agent, &other, &ciphertext, b"backup", &proof));
+    assert!(!verify(
+        &SecretKey::random(&mut rng).public_key(),
+        &share.public_key(),
+        &ciphertext,
+        b"backup",
+        &proof
+    ));
+    assert!(!verify(
+        &agent,
+        &share.public_key(),
+        &ciphertext,
+        b"restore",
+        &proof
+    ));
+
+    let mut swapped = ciphertext.clone();
+    swapped.chunks.swap(0, 1);
+    assert!(!verify(
+        &agent,
+        &share.public_key(),
+        &swapped,
+        b"backup",
+        &proof
+    ));
+    let mut tampered = proof.clone();
+    tampered.responses[3].0 += Scalar::one();
+    assert!(!verify(
+        &agent,
+        &share.public_key(),
+        &ciphertext,
+        b"backup",
+        &tampered
+    ));
+    let mut shifted = ciphertext;
+    shifted.chunks[2].1 = G1Affine::generator();
+    assert!(!verify(
+        &agent,
+        &share.public_key(),
+        &shifted,
+        b"backup",
+        &proof
+    ));
+}
@@ -0,0 +1,133 @@
+use bls12_381::Scalar;
+use bls_shamir::frost::*;
+use bls_shamir::schnorr;
+use bls_shamir::secret::SecretPolynomial;
+use rand::thread_rng;
+
+/// Runs both rounds with the holders of the shares at `signers`.
+fn run(
+    f: &SecretPolynomial,
+    signers: &[u64],
+    msg: &[u8],
+) -> (Vec<SigningCommitment>, Vec<SignatureShare>) {
+    let mut rng = thread_rng();
+    let pk = f.secret().public_key();
+    let nonces = signers
+        .iter()
+        .map(|i| commit(*i, &mut rng))
+        .collect::<Vec<_>>();
+    let commitments = nonces.iter().map(|n| n.commitment()).collect::<Vec<_>>();
+    let shares = signers
+        .iter()
+        .zip(nonces)
+        .map(|(i, nonces)| sign(&f.evaluate(*i), nonces, &pk, msg, &commitments).unwrap())
+        .collect();
+    (commitments, shares)
+}
+
+#[test]
+fn any_threshold_of_signers_signs_for_the_group() {
+    let f = SecretPolynomial::random(2, &mut thread_rng());
+    let pk = f.secret().public_key();
+    for signers in [[1, 2, 3], [2, 4, 5], [5, 1, 3]] {
+        let (commitments, shares) = run(&f, &signers, b"hello");
+        for share in &shares {
+            let public_share = f.evaluate(share.index).public_key();
+            assert!(verify_share(
+                &public_share,
+                &pk,
+                b"hello",
+                &commitments,
+                share
+            ));
+        }
+        let signature = aggregate(&pk, b"hello", &commitments, &shares).unwrap();
+        assert!(schnorr::verify(&pk, b"hello", &signature));
+        assert!(!schnorr::verify(&pk, b"hullo", &signature));
+    }
+}
+
+#[test]
+fn too_few_signers_dont_make_a_signature() {
+    let f = SecretPolynomial::random(2, &mut thread_rng());
+    let pk = f.secret().public_key();
+    let (commitments, shares) = run(&f, &[1, 2], b"hello");
+    let signature = aggregate(&pk, b"hello", &commitments, &shares).unwrap();
+    assert!(!schnorr::verify(&pk, b"hello", &signature));
+}
+
+#[test]
+fn finds_an_invalid_share() {
+    let f = SecretPolynomial::random(1, &mut thread_rng());
+    let pk = f.secret().public_key();
+    let (commitments, mut shares) = run(&f, &[1, 3], b"hello");
+    shares[1].z += Scalar::one();
+    let signature = aggregate(&pk, b"hello", &commitments, &shares).unwrap();
+    assert!(!schnorr::verify(&pk, b"hello", &signature));
+
+    let valid = shares
+        .iter()
+        .map(|share| {
+            let public_share = f.evaluate(share.index).public_key();
+            verify_share(&public_share, &pk, b"hello", &commitments, share)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(valid, [true, false]);
+    // A share is only valid with the commitments it was made for.
+    let (other, _) = run(&f, &[1, 3], b"hello");
+    let public_share = f.evaluate(1).public_key();
+    assert!(!verify_share(
+        &public_share,
+        &pk,
+        b"hello",
+        &other,
+        &shares[0]
+    ));
+}
+
+#[test]
+fn rejects_inconsistent_signing_sets() {
+    let mut rng = thread_rng();
+    let f = SecretPolynomial::random(1, &mut rng);
+    let pk = f.secret().public_key();
+    let (commitments, shares) = run(&f, &[1, 2], b"hello");
+
+    assert_eq!(
+        aggregate(&pk, b"hello", &commitments, &shares[..1]),
+        Err(FrostError::Missing { index: 2 })
+    );
+    assert_eq!(
+        aggregate(
+            &pk,
+            b"hello",
+            &commitments,
+            &[shares[0], shares[1], shares[1]]
+        ),
+        Err(FrostError::Index { index: 2 })
+    );
+    let mut stranger = shares.clone();
+    stranger.push(SignatureShare {
+        index: 3,
+        z: Scalar::one(),
+    });
+    assert_eq!(
+        aggregate(&pk, b"hello", &commitments, &stranger),
+        Err(FrostError::Unknown { index: 3 })
+    );
+    assert_eq!(
+        aggregate(&pk, b"hello", &[commitments[0], commitments[0]], &shares),
+        Err(FrostError::Index { index: 1 })
+    );
+
+    // Nonces for another commitment than the one published.
+    let nonces = commit(1, &mut rng);
+    assert_eq!(
+        sign(&f.evaluate(1), nonces, &pk, b"hello", &commitments),
+        Err(FrostError::Nonces { index: 1 })
+    );
+    let nonces = commit(3, &mut rng);
+    assert_eq!(
+        sign(&f.evaluate(3), nonces, &pk, b"hello", &commitments),
+        Err(FrostError::Unknown { index: 3 })
+    );
+}
@@ -0,0 +1,117 @@
+use bls12_381::Scalar;
+use bls_shamir::ibe::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::threshold::InterpolationError;
+
+fn master() -> SecretKey {
+    SecretKey::new(Scalar::from(0x1234_5678))
+}
+
+#[test]
+fn round_trip() {
+    let master = master();
+    let key = extract(&master, b"alice@example.com");
+    assert!(verify_identity_key(
+        &master.public_key(),
+        b"alice@example.com",
+        &key
+    ));
+
+    for msg in [&b""[..], b"Hello world", &[0xab; 100]] {
+        let ct = encrypt(
+            &master.public_key(),
+            b"alice@example.com",
+            msg,
+            &mut rand::thread_rng(),
+        );
+        assert_eq!(ct.w.len(), msg.len());
+        assert_eq!(decrypt(&key, &ct).as_deref(), Some(msg));
+    }
+}
+
+#[test]
+fn other_identities_cannot_decrypt() {
+    let master = master();
+    let ct = encrypt(
+        &master.public_key(),
+        b"alice",
+        b"Hello world",
+        &mut rand::thread_rng(),
+    );
+    let bob = extract(&master, b"bob");
+    assert!(!verify_identity_key(&master.public_key(), b"alice", &bob));
+    assert_eq!(decrypt(&bob, &ct), None);
+}
+
+#[test]
+fn tampered_ciphertext_is_rejected() {
+    let master = master();
+    let key = extract(&master, b"alice");
+    let ct = encrypt(
+        &master.public_key(),
+        b"alice",
+        b"Hello world",
+        &mut rand::thread_rng(),
+    );
+
+    let mut v = ct.clone();
+    v.v[0] ^= 1;
+    assert_eq!(decrypt(&key, &v), None);
+
+    let mut w = ct.clone();
+    w.w[3] ^= 1;
+    assert_eq!(decrypt(&key, &w), None);
+
+    let mut truncated = ct.clone();
+    truncated.w.pop();
+    assert_eq!(decrypt(&key, &truncated), None);
+
+    let other = encrypt(
+        &master.public_key(),
+        b"alice",
+        b"Hello world",
+        &mut rand::thread_rng(),
+    );
+    let mut u = ct;
+    u.u = other.u;
+    assert_eq!(decrypt(&key, &u), None);
+}
+
+#[test]
+fn threshold_extraction() {
+    let f = SecretPolynomial::new(vec![Scalar::from(0x1234_5678), Scalar::from(0x8765_4321)]);
+    let pk = f.secret().public_key();
+    let shares = (1..=3)
+        .map(|i| extract_share(i, &f.evaluate(i), b"alice", &mut rand::thread_rng()))
+        .collect::<Vec<_>>();
+    for (i, share) in (1..).zip(&shares) {
+        assert!(verify_extract_share(
+            &f.evaluate(i).public_key(),
+            b"alice",
+            share
+        ));
+        assert!(!verify_extract_share(
+            &f.evaluate(i).public_key(),
+            b"bob",
+            share
+        ));
+    }
+
+    let key = combine_extract_shares(&shares[..2]).unwrap();
+    assert_eq!(combine_extract_shares(&shares[1..]).unwrap(), key);
+    assert_eq!(key, extract(&f.secret(), b"alice"));
+    assert!(verify_identity_key(&pk, b"alice", &key));
+
+    let ct = encrypt(&pk, b"alice", b"Hello world", &mut rand::thread_rng());
+    assert_eq!(decrypt(&key, &ct).as_deref(), Some(&b"Hello world"[..]));
+
+    // A single share is below the threshold and gives the wrong key.
+    let short = combine_extract_shares(&shares[..1]).unwrap();
+    assert_eq!(decrypt(&short, &ct), None);
+
+    let repeated = [shares[0], shares[0]];
+    assert_eq!(
+        combine_extract_shares(&repeated).unwrap_err(),
+        InterpolationError::RepeatedIndex { index: 1 }
+    );
+}
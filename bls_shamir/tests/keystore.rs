@@ -0,0 +1,125 @@
+use bls12_381::Scalar;
+use bls_shamir::keystore::*;
+use bls_shamir::secret::SecretKey;
+
+/// The password used by the EIP-2335 test vectors, it exercises the NFKD
+/// normalization of the password.
+const PASSWORD: &str = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+
+const SECRET: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+const SCRYPT: &str = r#"{
+    "crypto": {
+        "kdf": {
+            "function": "scrypt",
+            "params": {
+                "dklen": 32,
+                "n": 262144,
+                "p": 1,
+                "r": 8,
+                "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+            },
+            "message": ""
+        },
+        "checksum": {
+            "function": "sha256",
+            "params": {},
+            "message": "d2217fe5f3e9a1e34581ef8a78f7c9928e436d36dacc5e846690a5581e8ea484"
+        },
+        "cipher": {
+            "function": "aes-128-ctr",
+            "params": {
+                "iv": "264daa3f303d7259501c93d997d84fe6"
+            },
+            "message": "06ae90d55fe0a6e9c5c3bc5b170827b2e5cce3929ed3f116c2811e6366dfe20f"
+        }
+    },
+    "description": "This is a test keystore that uses scrypt to secure the secret.",
+    "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+    "path": "m/12381/60/3141592653/589793238",
+    "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
+    "version": 4
+}"#;
+
+const PBKDF2: &str = r#"{
+    "crypto": {
+        "kdf": {
+            "function": "pbkdf2",
+            "params": {
+                "dklen": 32,
+                "c": 262144,
+                "prf": "hmac-sha256",
+                "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+            },
+            "message": ""
+        },
+        "checksum": {
+            "function": "sha256",
+            "params": {},
+            "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+        },
+        "cipher": {
+            "function": "aes-128-ctr",
+            "params": {
+                "iv": "264daa3f303d7259501c93d997d84fe6"
+            },
+            "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+        }
+    },
+    "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+    "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+    "path": "m/12381/60/0/0",
+    "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+    "version": 4
+}"#;
+
+fn secret() -> SecretKey {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(SECRET, &mut bytes).unwrap();
+    bytes.reverse();
+    SecretKey::new(Scalar::from_bytes(&bytes).unwrap())
+}
+
+fn pubkey(secret: &SecretKey) -> String {
+    hex::encode(secret.public_key().to_compressed())
+}
+
+#[test]
+fn decrypts_spec_vectors() {
+    for json in [SCRYPT, PBKDF2] {
+        let keystore = Keystore::from_json(json).unwrap();
+        let sk = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(sk, secret());
+        assert_eq!(pubkey(&sk), keystore.pubkey);
+    }
+}
+
+#[test]
+fn wrong_password_is_detected() {
+    let keystore = Keystore::from_json(PBKDF2).unwrap();
+    assert!(matches!(
+        keystore.decrypt("testpassword"),
+        Err(KeystoreError::InvalidPassword)
+    ));
+}
+
+#[test]
+fn json_round_trip() {
+    let keystore = Keystore::from_json(SCRYPT).unwrap();
+    assert_eq!(Keystore::from_json(&keystore.to_json()).unwrap(), keystore);
+}
+
+#[test]
+fn encrypt_then_decrypt() {
+    let mut rng = rand::thread_rng();
+    let sk = secret();
+
+    for kdf in [Kdf::scrypt(&mut rng), Kdf::pbkdf2(&mut rng)] {
+        let keystore = Keystore::encrypt(&sk, PASSWORD, "", kdf, &mut rng).unwrap();
+        assert_eq!(keystore.pubkey, pubkey(&sk));
+        assert_eq!(keystore.version, VERSION);
+
+        let parsed = Keystore::from_json(&keystore.to_json()).unwrap();
+        assert_eq!(parsed.decrypt(PASSWORD).unwrap(), sk);
+    }
+}
@@ -0,0 +1,63 @@
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine};
+use bls_shamir::min_sig::*;
+use bls_shamir::secret::SecretKey;
+use group::Curve;
+use rand::thread_rng;
+
+/// `e(H(m), pk) == e(sig, G2)`, with two full pairings.
+fn unprepared_verify(pk: &G2Affine, msg: &[u8], sig: &G1Affine) -> bool {
+    let hm = hash_to_g1(msg, DST).to_affine();
+    pairing(&hm, pk) == pairing(sig, &G2Affine::generator())
+}
+
+#[test]
+fn prepared_keys_accept_what_pairings_accept() {
+    let sk = SecretKey::random(&mut thread_rng());
+    let pk = public_key(&sk);
+    let prepared = PreparedPublicKey::new(&pk);
+    assert_eq!(*prepared.public_key(), pk);
+
+    for msg in [&b""[..], b"hello", &[0xffu8; 200]] {
+        let sig = sign(&sk, msg);
+        assert!(unprepared_verify(&pk, msg, &sig));
+        assert!(prepared.verify(msg, &sig));
+        assert!(verify(&pk, msg, &sig));
+        assert!(PreparedPublicKey::from(pk).verify(msg, &sig));
+    }
+}
+
+#[test]
+fn prepared_keys_reject_what_pairings_reject() {
+    let sk = SecretKey::random(&mut thread_rng());
+    let other = SecretKey::random(&mut thread_rng());
+    let pk = public_key(&sk);
+    let prepared = PreparedPublicKey::new(&pk);
+    let sig = sign(&sk, b"hello");
+
+    let rejected = [
+        (&b"goodbye"[..], sig),
+        (b"hello", sign(&other, b"hello")),
+        (b"hello", (G1Projective::from(sig).double()).to_affine()),
+        (b"hello", G1Affine::identity()),
+        (b"hello", G1Affine::generator()),
+    ];
+    for (msg, sig) in rejected {
+        assert!(!unprepared_verify(&pk, msg, &sig));
+        assert!(!prepared.verify(msg, &sig));
+        assert!(!verify(&pk, msg, &sig));
+    }
+
+    // Another key, prepared or not.
+    assert!(!PreparedPublicKey::new(&public_key(&other)).verify(b"hello", &sig));
+    assert!(!unprepared_verify(&public_key(&other), b"hello", &sig));
+}
+
+#[test]
+fn verify_hashed_takes_any_dst() {
+    let sk = SecretKey::random(&mut thread_rng());
+    let prepared = PreparedPublicKey::new(&public_key(&sk));
+    let hm = hash_to_g1(b"hello", b"ANOTHER_DST").to_affine();
+    let sig = (G1Projective::from(hm) * sk.as_scalar()).to_affine();
+    assert!(prepared.verify_hashed(&hm, &sig));
+    assert!(!prepared.verify(b"hello", &sig));
+}
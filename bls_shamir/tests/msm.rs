@@ -0,0 +1,65 @@
+//! The pure backend's Pippenger against the sum of scalar multiplications.
+use bls12_381::*;
+use bls_shamir::backend::pure;
+use group::ff::Field;
+use group::{Curve, Group};
+use rand::thread_rng;
+
+fn naive_g1(points: &[G1Affine], scalars: &[Scalar]) -> G1Projective {
+    points
+        .iter()
+        .zip(scalars)
+        .fold(G1Projective::identity(), |acc, (p, s)| acc + p * s)
+}
+
+fn naive_g2(points: &[G2Affine], scalars: &[Scalar]) -> G2Projective {
+    points
+        .iter()
+        .zip(scalars)
+        .fold(G2Projective::identity(), |acc, (p, s)| acc + p * s)
+}
+
+#[test]
+fn matches_the_naive_sum() {
+    // Either side of the threshold, and windows of several widths.
+    for n in [0, 1, 15, 16, 17, 100, 600] {
+        let scalars = (0..n)
+            .map(|_| Scalar::random(thread_rng()))
+            .collect::<Vec<_>>();
+        let g1 = (0..n)
+            .map(|_| G1Projective::random(thread_rng()).to_affine())
+            .collect::<Vec<_>>();
+        assert_eq!(pure::g1_msm(&g1, &scalars), naive_g1(&g1, &scalars));
+
+        let g2 = (0..n.min(40))
+            .map(|_| G2Projective::random(thread_rng()).to_affine())
+            .collect::<Vec<_>>();
+        assert_eq!(pure::g2_msm(&g2, &scalars), naive_g2(&g2, &scalars));
+    }
+}
+
+#[test]
+fn handles_extreme_scalars_and_points() {
+    let mut scalars = vec![Scalar::zero(), Scalar::one(), -Scalar::one()];
+    scalars.extend((0..29).map(|i| Scalar::from(1 << i)));
+    let mut points = vec![G1Affine::identity(); 3];
+    points.extend((0..29).map(|_| G1Projective::random(thread_rng()).to_affine()));
+    assert_eq!(pure::g1_msm(&points, &scalars), naive_g1(&points, &scalars));
+
+    points[3] = G1Affine::generator();
+    scalars[3] = -Scalar::one();
+    points[4] = G1Affine::generator();
+    scalars[4] = Scalar::one();
+    assert_eq!(pure::g1_msm(&points, &scalars), naive_g1(&points, &scalars));
+}
+
+#[test]
+fn stops_at_the_shorter_input() {
+    let points = (0..20)
+        .map(|_| G1Projective::random(thread_rng()).to_affine())
+        .collect::<Vec<_>>();
+    let scalars = (0..18)
+        .map(|_| Scalar::random(thread_rng()))
+        .collect::<Vec<_>>();
+    assert_eq!(pure::g1_msm(&points, &scalars), naive_g1(&points, &scalars));
+}
@@ -0,0 +1,75 @@
+use bls12_381::{G1Affine, G1Projective, G2Affine};
+use bls_shamir::multisig::*;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature;
+use group::Curve;
+use rand::thread_rng;
+
+fn keys(n: usize) -> (Vec<SecretKey>, Vec<G1Affine>) {
+    let sks = (0..n)
+        .map(|_| SecretKey::random(&mut thread_rng()))
+        .collect::<Vec<_>>();
+    let pks = sks.iter().map(SecretKey::public_key).collect();
+    (sks, pks)
+}
+
+#[test]
+fn aggregates_signatures_on_a_message() {
+    let (sks, pks) = keys(4);
+    let sigs = sks.iter().map(|sk| sign(sk, b"hello")).collect::<Vec<_>>();
+
+    let apk = aggregate_public_keys(&pks);
+    let sig = aggregate_signatures(&pks, &sigs).unwrap();
+    assert!(verify(&apk, b"hello", &sig));
+    assert!(!verify(&apk, b"goodbye", &sig));
+
+    // The key set is a set, the order doesn't matter.
+    let mut reversed = pks.clone();
+    reversed.reverse();
+    assert_eq!(aggregate_public_keys(&reversed), apk);
+
+    // Without one of the signers it no longer verifies.
+    let sig = aggregate_signatures(&pks[..3], &sigs[..3]).unwrap();
+    assert!(!verify(&apk, b"hello", &sig));
+    assert!(verify(&aggregate_public_keys(&pks[..3]), b"hello", &sig));
+}
+
+#[test]
+fn rejects_a_signature_per_key_mismatch() {
+    let (sks, pks) = keys(3);
+    let sigs = sks.iter().map(|sk| sign(sk, b"hello")).collect::<Vec<_>>();
+    assert_eq!(
+        aggregate_signatures(&pks, &sigs[..2]),
+        Err(MultisigError::Length {
+            keys: 3,
+            signatures: 2
+        })
+    );
+}
+
+#[test]
+fn rogue_keys_dont_cancel_the_honest_one() {
+    let (sks, pks) = keys(1);
+    let honest = pks[0];
+
+    // The attacker knows x and announces `x * G - pk_honest`, so that the
+    // plain sum of the two keys is `x * G`.
+    let x = SecretKey::random(&mut thread_rng());
+    let rogue = (G1Projective::from(x.public_key()) - honest).to_affine();
+    let forged = signature::sign(&x, b"hello");
+    let naive = (G1Projective::from(honest) + rogue).to_affine();
+    assert!(signature::verify(&naive, b"hello", &forged));
+
+    // The weighted aggregate isn't fooled, whichever of its two signatures
+    // the attacker claims.
+    let pks = [honest, rogue];
+    let apk = aggregate_public_keys(&pks);
+    assert!(!verify(&apk, b"hello", &forged));
+    let sig = aggregate_signatures(&pks, &[G2Affine::identity(), forged]).unwrap();
+    assert!(!verify(&apk, b"hello", &sig));
+
+    // While the honest signer's real signature still goes through with its
+    // own.
+    let sig = aggregate_signatures(&[honest], &[sign(&sks[0], b"hello")]).unwrap();
+    assert!(verify(&aggregate_public_keys(&[honest]), b"hello", &sig));
+}
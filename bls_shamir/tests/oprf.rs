@@ -0,0 +1,101 @@
+use bls_shamir::oprf::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::signature;
+use group::Curve;
+use rand::thread_rng;
+
+#[test]
+fn the_client_gets_the_servers_output() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let (factor, blinded) = blind(b"alice@example.com", &mut rng);
+    let evaluated = evaluate(&sk, &blinded);
+    let output = finalize(b"alice@example.com", &factor, &evaluated);
+    assert_eq!(output, evaluate_input(&sk, b"alice@example.com"));
+
+    // Another blinding of the same input looks unrelated but gives the
+    // same output.
+    let (other_blind, other_blinded) = blind(b"alice@example.com", &mut rng);
+    assert_ne!(other_blinded, blinded);
+    let other = finalize(
+        b"alice@example.com",
+        &other_blind,
+        &evaluate(&sk, &other_blinded),
+    );
+    assert_eq!(other, output);
+
+    assert_ne!(evaluate_input(&sk, b"bob@example.com"), output);
+    assert_ne!(
+        evaluate_input(&SecretKey::random(&mut rng), b"alice@example.com"),
+        output
+    );
+}
+
+#[test]
+fn an_evaluation_is_not_a_signature() {
+    let sk = SecretKey::random(&mut thread_rng());
+    let unblinded = BlindedElement(signature::hash_to_g2(b"hello", DST).to_affine());
+    let evaluated = evaluate(&sk, &unblinded);
+    assert!(!signature::verify(&sk.public_key(), b"hello", &evaluated.0));
+    assert!(signature::verify(
+        &sk.public_key(),
+        b"hello",
+        &signature::sign(&sk, b"hello")
+    ));
+}
+
+#[test]
+fn rejects_an_evaluation_with_another_key() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let other = SecretKey::random(&mut rng);
+    let (_, blinded) = blind(b"hello", &mut rng);
+    let (evaluated, proof) = evaluate_verifiable(&other, &blinded, &mut rng);
+    assert!(verify_evaluation(
+        &other.public_key(),
+        &blinded,
+        &evaluated,
+        &proof
+    ));
+    assert!(!verify_evaluation(
+        &sk.public_key(),
+        &blinded,
+        &evaluated,
+        &proof
+    ));
+    let (_, blinded_again) = blind(b"hello", &mut rng);
+    assert!(!verify_evaluation(
+        &other.public_key(),
+        &blinded_again,
+        &evaluated,
+        &proof
+    ));
+}
+
+#[test]
+fn any_threshold_of_servers_evaluates_under_the_group_key() {
+    let mut rng = thread_rng();
+    let f = SecretPolynomial::random(1, &mut rng);
+    let (factor, blinded) = blind(b"hello", &mut rng);
+    let shares = (1..=3)
+        .map(|i| evaluate_share(i, &f.evaluate(i), &blinded, &mut rng))
+        .collect::<Vec<_>>();
+    for share in &shares {
+        let public_share = f.evaluate(share.index).public_key();
+        assert!(verify_share(&public_share, &blinded, share));
+    }
+    let first = combine(&shares[..2]).unwrap();
+    assert_eq!(first, combine(&shares[1..]).unwrap());
+    assert_eq!(
+        finalize(b"hello", &factor, &first),
+        evaluate_input(&f.secret(), b"hello")
+    );
+
+    let mut forged = shares[0];
+    forged.point = shares[1].point;
+    assert!(!verify_share(
+        &f.evaluate(1).public_key(),
+        &blinded,
+        &forged
+    ));
+}
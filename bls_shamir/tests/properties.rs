@@ -0,0 +1,105 @@
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use bls_shamir::secret::SecretPolynomial;
+use bls_shamir::threshold::{interpolate_at_zero, lagrange_at_zero, InterpolationError};
+use proptest::collection::{btree_set, vec};
+use proptest::prelude::*;
+
+fn scalar() -> impl Strategy<Value = Scalar> {
+    any::<[u8; 32]>().prop_map(|bytes| {
+        let mut wide = [0u8; 64];
+        wide[..32].copy_from_slice(&bytes);
+        Scalar::from_bytes_wide(&wide)
+    })
+}
+
+/// A random polynomial of degree at most 7 together with `degree + 1`
+/// distinct non-zero indices.
+fn polynomial_and_indices() -> impl Strategy<Value = (Vec<Scalar>, Vec<u64>)> {
+    vec(scalar(), 1..=8).prop_flat_map(|coefficients| {
+        let n = coefficients.len();
+        (
+            Just(coefficients),
+            btree_set(1..u64::MAX, n).prop_map(|xs| xs.into_iter().collect()),
+        )
+    })
+}
+
+/// `∑ C_i * x^i`, which for Feldman commitments `C_i = a_i * G` is `f(x) * G`.
+fn evaluate_commitments(commitments: &[G1Projective], x: u64) -> G1Projective {
+    let x = Scalar::from(x);
+    commitments
+        .iter()
+        .rev()
+        .fold(G1Projective::identity(), |acc, c| acc * x + c)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn interpolation_recovers_the_secret((coefficients, xs) in polynomial_and_indices()) {
+        let f = SecretPolynomial::new(coefficients);
+        let shares = xs.iter().map(|x| f.evaluate(*x)).collect::<Vec<_>>();
+        let expected = *f.secret().as_scalar();
+
+        let secret: Scalar = lagrange_at_zero(&xs)
+            .unwrap()
+            .into_iter()
+            .zip(&shares)
+            .map(|(l, y)| l * y.as_scalar())
+            .sum();
+        prop_assert_eq!(secret, expected);
+
+        let points = xs
+            .iter()
+            .zip(&shares)
+            .map(|(x, y)| (*x, *y.as_scalar()))
+            .collect::<Vec<_>>();
+        prop_assert_eq!(interpolate_at_zero(&points).unwrap(), expected);
+    }
+
+    #[test]
+    fn interpolation_in_the_exponent((coefficients, xs) in polynomial_and_indices()) {
+        let f = SecretPolynomial::new(coefficients);
+        let g = G1Affine::generator();
+
+        let points = xs
+            .iter()
+            .map(|x| (*x, g * f.evaluate(*x).as_scalar()))
+            .collect::<Vec<_>>();
+        prop_assert_eq!(interpolate_at_zero(&points).unwrap(), g * f.secret().as_scalar());
+    }
+
+    #[test]
+    fn feldman_commitments_verify((coefficients, xs) in polynomial_and_indices(), tamper in scalar()) {
+        let f = SecretPolynomial::new(coefficients);
+        let commitments = f.commitments();
+        let g = G1Affine::generator();
+
+        for x in xs {
+            let share = f.evaluate(x);
+            let expected = evaluate_commitments(&commitments, x);
+            prop_assert_eq!(g * share.as_scalar(), expected);
+            if tamper != Scalar::zero() {
+                prop_assert_ne!(g * (share.as_scalar() + tamper), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn interpolation_rejects_zero_and_repeated_indices() {
+    assert_eq!(
+        lagrange_at_zero(&[1, 0, 2]),
+        Err(InterpolationError::ZeroIndex)
+    );
+    assert_eq!(
+        lagrange_at_zero(&[3, 1, 3]),
+        Err(InterpolationError::RepeatedIndex { index: 3 })
+    );
+    assert_eq!(
+        interpolate_at_zero(&[(2, Scalar::one()), (2, Scalar::one())]),
+        Err(InterpolationError::RepeatedIndex { index: 2 })
+    );
+    assert!(lagrange_at_zero(&[]).unwrap().is_empty());
+}
@@ -0,0 +1,80 @@
+use bls12_381::{G1Affine, G1Projective};
+use bls_shamir::pvss::*;
+use bls_shamir::secret::SecretKey;
+use group::Curve;
+use rand::thread_rng;
+
+fn members(n: usize) -> (Vec<SecretKey>, Vec<G1Affine>) {
+    let mut rng = thread_rng();
+    let sks = (0..n)
+        .map(|_| SecretKey::random(&mut rng))
+        .collect::<Vec<_>>();
+    let keys = sks.iter().map(SecretKey::public_key).collect();
+    (sks, keys)
+}
+
+#[test]
+fn any_threshold_of_members_recovers_the_secret() {
+    let mut rng = thread_rng();
+    let (sks, keys) = members(5);
+    let dealing = deal(2, &keys, b"round 1", &mut rng);
+    assert!(verify(2, &keys, &dealing, b"round 1"));
+    assert!(!verify(2, &keys, &dealing, b"round 2"));
+
+    let shares = sks
+        .iter()
+        .zip(1..)
+        .map(|(sk, index)| decrypt(sk, index, &dealing, b"round 1", &mut rng).unwrap())
+        .collect::<Vec<_>>();
+    for (share, key) in shares.iter().zip(&keys) {
+        assert!(verify_share(key, &dealing, share, b"round 1"));
+    }
+    let secret = reconstruct(&shares[..3]);
+    assert_eq!(secret, reconstruct(&shares[2..]));
+    assert_eq!(secret, reconstruct(&[shares[0], shares[2], shares[4]]));
+    assert_ne!(secret, reconstruct(&shares[..2]));
+}
+
+#[test]
+fn rejects_shares_off_the_polynomial() {
+    let mut rng = thread_rng();
+    let (_, keys) = members(5);
+    // A polynomial of degree 3 isn't one of degree 2, even with valid
+    // proofs for every share.
+    let dealing = deal(3, &keys, b"round 1", &mut rng);
+    assert!(!verify(2, &keys, &dealing, b"round 1"));
+
+    // Nor is anything once one share is swapped for another dealing's.
+    let dealing = deal(2, &keys, b"round 1", &mut rng);
+    let other = deal(2, &keys, b"round 1", &mut rng);
+    let mut mixed = dealing.clone();
+    mixed.commitments[1] = other.commitments[1];
+    mixed.encrypted[1] = other.encrypted[1];
+    mixed.proofs[1] = other.proofs[1];
+    assert!(!verify(2, &keys, &mixed, b"round 1"));
+
+    let mut unproven = dealing.clone();
+    unproven.encrypted[0] =
+        (G1Projective::from(unproven.encrypted[0]) + G1Projective::generator()).to_affine();
+    assert!(!verify(2, &keys, &unproven, b"round 1"));
+}
+
+#[test]
+fn rejects_a_wrong_decryption() {
+    let mut rng = thread_rng();
+    let (sks, keys) = members(4);
+    let dealing = deal(1, &keys, b"round 1", &mut rng);
+    let share = decrypt(&sks[0], 1, &dealing, b"round 1", &mut rng).unwrap();
+    assert!(!verify_share(&keys[1], &dealing, &share, b"round 1"));
+
+    let mut forged = share;
+    forged.point = (G1Projective::from(forged.point) + G1Projective::generator()).to_affine();
+    assert!(!verify_share(&keys[0], &dealing, &forged, b"round 1"));
+
+    // Another member's key decrypts to something else, which its proof gives away.
+    let mut wrong = decrypt(&sks[1], 1, &dealing, b"round 1", &mut rng).unwrap();
+    assert!(!verify_share(&keys[0], &dealing, &wrong, b"round 1"));
+    wrong.index = 5;
+    assert!(!verify_share(&keys[0], &dealing, &wrong, b"round 1"));
+    assert!(decrypt(&sks[0], 0, &dealing, b"round 1", &mut rng).is_none());
+}
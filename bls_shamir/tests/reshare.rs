@@ -0,0 +1,119 @@
+use bls12_381::G1Projective;
+use bls_shamir::reshare::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::signature::{self, DST};
+use bls_shamir::threshold;
+use group::Curve;
+use rand::thread_rng;
+
+/// Reshares the 2-of-3 key of `f` from old members 1 and 3 to a 3-of-5
+/// committee, returning the new shares and public coefficients.
+fn handoff(f: &SecretPolynomial) -> (Vec<SecretKey>, Vec<G1Projective>) {
+    let mut rng = thread_rng();
+    let dealers = [1, 3];
+    let dealings = dealers
+        .iter()
+        .map(|i| (*i, deal(&f.evaluate(*i), 2, &mut rng)))
+        .collect::<Vec<_>>();
+    for (i, polynomial) in &dealings {
+        let public_share = f.evaluate(*i).public_key();
+        assert!(verify_dealing(&public_share, &polynomial.commitments(), 2));
+    }
+
+    let shares = (1..=5)
+        .map(|j| {
+            let subshares = dealings
+                .iter()
+                .map(|(i, polynomial)| {
+                    let subshare = polynomial.evaluate(j);
+                    assert!(verify_subshare(&polynomial.commitments(), j, &subshare));
+                    (*i, subshare)
+                })
+                .collect::<Vec<_>>();
+            combine_shares(&subshares).unwrap()
+        })
+        .collect();
+    let commitments = dealings
+        .iter()
+        .map(|(i, polynomial)| (*i, polynomial.commitments()))
+        .collect::<Vec<_>>();
+    (shares, combine_commitments(&commitments).unwrap())
+}
+
+#[test]
+fn the_new_committee_signs_under_the_same_key() {
+    let mut rng = thread_rng();
+    let f = SecretPolynomial::random(1, &mut rng);
+    let (shares, coefficients) = handoff(&f);
+    assert_eq!(coefficients.len(), 3);
+    assert_eq!(coefficients[0].to_affine(), f.secret().public_key());
+    for (share, j) in shares.iter().zip(1..) {
+        assert_eq!(share.public_key(), public_share(&coefficients, j));
+    }
+
+    let hm = signature::hash_to_g2(b"hello", DST).to_affine();
+    let partials = [1, 4, 5]
+        .iter()
+        .map(|j| threshold::sign_share(*j, &shares[*j as usize - 1], &hm, &mut rng))
+        .collect::<Vec<_>>();
+    let signature = threshold::aggregate_shares(&partials).unwrap();
+    assert!(signature::verify(
+        &f.secret().public_key(),
+        b"hello",
+        &signature
+    ));
+
+    // Two of the new shares no longer make it.
+    let too_few = threshold::aggregate_shares(&partials[..2]).unwrap();
+    assert!(!signature::verify(
+        &f.secret().public_key(),
+        b"hello",
+        &too_few
+    ));
+}
+
+#[test]
+fn retired_shares_dont_mix_with_new_ones() {
+    let mut rng = thread_rng();
+    let f = SecretPolynomial::random(1, &mut rng);
+    let (shares, coefficients) = handoff(&f);
+    let hm = signature::hash_to_g2(b"hello", DST).to_affine();
+
+    // Member 2 of the old committee is member 2 of the new one too, its old
+    // share is checked against its new public share.
+    let retired = threshold::sign_share(2, &f.evaluate(2), &hm, &mut rng);
+    assert!(!threshold::verify_share(
+        &public_share(&coefficients, 2),
+        &hm,
+        &retired
+    ));
+    let partials = [
+        retired,
+        threshold::sign_share(1, &shares[0], &hm, &mut rng),
+        threshold::sign_share(3, &shares[2], &hm, &mut rng),
+    ];
+    let signature = threshold::aggregate_shares(&partials).unwrap();
+    assert!(!signature::verify(
+        &f.secret().public_key(),
+        b"hello",
+        &signature
+    ));
+}
+
+#[test]
+fn rejects_a_dealing_of_another_share() {
+    let mut rng = thread_rng();
+    let f = SecretPolynomial::random(1, &mut rng);
+    let forged = deal(&SecretKey::random(&mut rng), 2, &mut rng);
+    let public_share = f.evaluate(1).public_key();
+    assert!(!verify_dealing(&public_share, &forged.commitments(), 2));
+
+    let honest = deal(&f.evaluate(1), 2, &mut rng);
+    assert!(verify_dealing(&public_share, &honest.commitments(), 2));
+    assert!(!verify_dealing(&public_share, &honest.commitments(), 3));
+    assert!(!verify_subshare(
+        &honest.commitments(),
+        2,
+        &honest.evaluate(3)
+    ));
+}
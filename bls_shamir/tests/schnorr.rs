@@ -0,0 +1,64 @@
+use bls12_381::{G1Affine, Scalar};
+use bls_shamir::schnorr::*;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature;
+use rand::thread_rng;
+
+#[test]
+fn signs_and_verifies() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = sk.public_key();
+    let sig = sign(&sk, b"hello", &mut rng);
+    assert!(verify(&pk, b"hello", &sig));
+    assert!(!verify(&pk, b"hullo", &sig));
+    assert!(!verify(
+        &SecretKey::random(&mut rng).public_key(),
+        b"hello",
+        &sig
+    ));
+
+    // Fresh nonces, so signing twice gives two valid signatures.
+    let other = sign(&sk, b"hello", &mut rng);
+    assert_ne!(other, sig);
+    assert!(verify(&pk, b"hello", &other));
+}
+
+#[test]
+fn rejects_tampered_signatures() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = sk.public_key();
+    let sig = sign(&sk, b"hello", &mut rng);
+
+    let mut wrong_z = sig;
+    wrong_z.z += Scalar::one();
+    assert!(!verify(&pk, b"hello", &wrong_z));
+    let mut wrong_r = sig;
+    wrong_r.r = G1Affine::generator();
+    assert!(!verify(&pk, b"hello", &wrong_r));
+    assert!(!verify(&G1Affine::identity(), b"hello", &sig));
+}
+
+#[test]
+fn shares_keys_with_bls() {
+    let mut rng = thread_rng();
+    let sk = SecretKey::random(&mut rng);
+    let pk = signature::public_key(&sk);
+    assert!(signature::verify(
+        &pk,
+        b"hello",
+        &signature::sign(&sk, b"hello")
+    ));
+    assert!(verify(&pk, b"hello", &sign(&sk, b"hello", &mut rng)));
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let mut rng = thread_rng();
+    let sig = sign(&SecretKey::random(&mut rng), b"hello", &mut rng);
+    assert_eq!(Signature::from_bytes(&sig.to_bytes()), Some(sig));
+    let mut bytes = sig.to_bytes();
+    bytes[48..].copy_from_slice(&[0xff; 32]);
+    assert_eq!(Signature::from_bytes(&bytes), None);
+}
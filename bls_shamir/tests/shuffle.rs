@@ -0,0 +1,136 @@
+use bls12_381::{G1Projective, Scalar};
+use bls_shamir::secret::SecretPolynomial;
+use bls_shamir::shuffle::*;
+
+fn polynomial() -> SecretPolynomial {
+    SecretPolynomial::new(vec![Scalar::from(0x1234_5678), Scalar::from(0x8765_4321)])
+}
+
+fn messages(n: u64) -> Vec<G1Projective> {
+    (1..=n)
+        .map(|i| G1Projective::generator() * Scalar::from(i))
+        .collect()
+}
+
+#[test]
+fn mixes_and_decrypts_with_shares() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let pk = f.secret().public_key();
+    let messages = messages(5);
+    let mut ciphertexts: Vec<Ciphertext> =
+        messages.iter().map(|m| encrypt(&pk, m, &mut rng)).collect();
+
+    // A chain of three mix servers, each checked by the next.
+    for _ in 0..3 {
+        let (outputs, proof) = shuffle(&pk, &ciphertexts, &mut rng);
+        assert!(verify(&pk, &ciphertexts, &outputs, &proof));
+        assert_ne!(outputs, ciphertexts);
+        ciphertexts = outputs;
+    }
+
+    let mut decrypted = ciphertexts
+        .iter()
+        .map(|ct| {
+            let shares = (1..=2)
+                .map(|i| decryption_share(i, &f.evaluate(i), ct, &mut rng))
+                .collect::<Vec<_>>();
+            for share in &shares {
+                assert!(verify_share(
+                    &f.evaluate(share.index).public_key(),
+                    ct,
+                    share
+                ));
+            }
+            let message = combine(&shares, ct).unwrap();
+            assert_eq!(message, decrypt(&f.secret(), ct));
+            messages.iter().position(|m| *m == message).unwrap()
+        })
+        .collect::<Vec<_>>();
+    decrypted.sort();
+    assert_eq!(decrypted, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn the_empty_list_and_a_single_ciphertext_shuffle() {
+    let mut rng = rand::thread_rng();
+    let pk = polynomial().secret().public_key();
+    for n in 0..2 {
+        let inputs: Vec<Ciphertext> = messages(n)
+            .iter()
+            .map(|m| encrypt(&pk, m, &mut rng))
+            .collect();
+        let (outputs, proof) = shuffle(&pk, &inputs, &mut rng);
+        assert!(verify(&pk, &inputs, &outputs, &proof));
+    }
+}
+
+#[test]
+fn replaced_ciphertexts_are_rejected() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let pk = f.secret().public_key();
+    let inputs: Vec<Ciphertext> = messages(4)
+        .iter()
+        .map(|m| encrypt(&pk, m, &mut rng))
+        .collect();
+    let (outputs, proof) = shuffle(&pk, &inputs, &mut rng);
+
+    // A server swapping a vote for one of its own.
+    let mut forged = outputs.clone();
+    forged[0] = encrypt(&pk, &G1Projective::generator(), &mut rng);
+    assert!(!verify(&pk, &inputs, &forged, &proof));
+
+    // Or a duplicated one, with an honest proof of the wrong permutation.
+    let permutation = [0, 0, 1, 2];
+    let randomness: Vec<Scalar> = (0..4).map(|i| Scalar::from(i + 1)).collect();
+    let duplicated: Vec<Ciphertext> = permutation
+        .iter()
+        .zip(&randomness)
+        .map(|(i, r)| inputs[*i].reencrypt(&pk, *r))
+        .collect();
+    let proof = prove(
+        &pk,
+        &inputs,
+        &duplicated,
+        &permutation,
+        &randomness,
+        &mut rng,
+    );
+    assert!(!verify(&pk, &inputs, &duplicated, &proof));
+}
+
+#[test]
+fn tampered_proofs_are_rejected() {
+    let mut rng = rand::thread_rng();
+    let pk = polynomial().secret().public_key();
+    let inputs: Vec<Ciphertext> = messages(3)
+        .iter()
+        .map(|m| encrypt(&pk, m, &mut rng))
+        .collect();
+    let (outputs, proof) = shuffle(&pk, &inputs, &mut rng);
+    assert!(verify(&pk, &inputs, &outputs, &proof));
+
+    let mut tampered = proof.clone();
+    tampered.powers.swap(0, 1);
+    assert!(!verify(&pk, &inputs, &outputs, &tampered));
+
+    let mut tampered = proof.clone();
+    tampered.multiplications[1].responses[0] += Scalar::one();
+    assert!(!verify(&pk, &inputs, &outputs, &tampered));
+
+    let mut tampered = proof.clone();
+    tampered.exponentiation.randomness += Scalar::one();
+    assert!(!verify(&pk, &inputs, &outputs, &tampered));
+
+    let mut tampered = proof;
+    tampered.partial_products.pop();
+    assert!(!verify(&pk, &inputs, &outputs, &tampered));
+
+    // Under another key.
+    let other = SecretPolynomial::new(vec![Scalar::from(7)])
+        .secret()
+        .public_key();
+    let (outputs, proof) = shuffle(&pk, &inputs, &mut rng);
+    assert!(!verify(&other, &inputs, &outputs, &proof));
+}
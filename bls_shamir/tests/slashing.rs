@@ -0,0 +1,127 @@
+use bls_shamir::secret::SecretKey;
+use bls_shamir::slashing::*;
+use bls_shamir::threshold;
+use rand::{thread_rng, Rng};
+use std::fs;
+use std::path::PathBuf;
+
+/// A database path of its own for every test, removed when dropped.
+struct Database(PathBuf);
+
+impl Database {
+    fn new() -> Self {
+        let name = format!("slashing-{:016x}.db", thread_rng().gen::<u64>());
+        Database(std::env::temp_dir().join(name))
+    }
+
+    fn open(&self) -> SlashingProtection {
+        SlashingProtection::open(&self.0).unwrap()
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn signs_the_same_message_again_but_not_another() {
+    let database = Database::new();
+    let mut protection = database.open();
+    assert_eq!(protection.newest_epoch(), None);
+
+    protection.check_and_record(3, b"block a").unwrap();
+    protection.check_and_record(3, b"block a").unwrap();
+    assert!(matches!(
+        protection.check_and_record(3, b"block b"),
+        Err(SlashingError::DoubleSign { epoch: 3 })
+    ));
+    assert_eq!(protection.newest_epoch(), Some(3));
+}
+
+#[test]
+fn refuses_epochs_older_than_the_newest() {
+    let database = Database::new();
+    let mut protection = database.open();
+    protection.check_and_record(5, b"block a").unwrap();
+    protection.check_and_record(9, b"block b").unwrap();
+
+    assert!(matches!(
+        protection.check_and_record(7, b"block c"),
+        Err(SlashingError::Stale {
+            epoch: 7,
+            newest: 9
+        })
+    ));
+    // Even for an epoch signed before, with its own message.
+    protection.check_and_record(5, b"block a").unwrap();
+    assert!(matches!(
+        protection.check_and_record(5, b"block c"),
+        Err(SlashingError::DoubleSign { epoch: 5 })
+    ));
+    protection.check_and_record(10, b"block c").unwrap();
+}
+
+#[test]
+fn the_history_survives_a_reopen() {
+    let database = Database::new();
+    {
+        let mut protection = database.open();
+        protection.check_and_record(1, b"block a").unwrap();
+        protection.check_and_record(4, b"block b").unwrap();
+    }
+
+    let mut protection = database.open();
+    assert_eq!(protection.newest_epoch(), Some(4));
+    assert!(matches!(
+        protection.check_and_record(4, b"block c"),
+        Err(SlashingError::DoubleSign { epoch: 4 })
+    ));
+    assert!(matches!(
+        protection.check_and_record(2, b"block c"),
+        Err(SlashingError::Stale {
+            epoch: 2,
+            newest: 4
+        })
+    ));
+    protection.check_and_record(4, b"block b").unwrap();
+    protection.check_and_record(6, b"block c").unwrap();
+
+    // Refused messages left no trace.
+    drop(protection);
+    let lines = fs::read_to_string(&database.0).unwrap().lines().count();
+    assert_eq!(lines, 3);
+}
+
+#[test]
+fn refuses_a_corrupt_database() {
+    let database = Database::new();
+    fs::write(&database.0, "1 00\n").unwrap();
+    assert!(matches!(
+        SlashingProtection::open(&database.0),
+        Err(SlashingError::Corrupt { line: 1 })
+    ));
+}
+
+#[test]
+fn signs_shares_only_when_safe() {
+    let database = Database::new();
+    let mut protection = database.open();
+    let mut rng = thread_rng();
+    let share = SecretKey::random(&mut rng);
+
+    let partial = protection
+        .sign_share(2, 7, &share, b"block a", &mut rng)
+        .unwrap();
+    assert_eq!(partial.index, 7);
+    let hm = bls_shamir::signature::hash_to_g2(b"block a", bls_shamir::signature::DST);
+    assert!(threshold::verify_share(
+        &share.public_key(),
+        &hm.into(),
+        &partial
+    ));
+    assert!(protection
+        .sign_share(2, 7, &share, b"block b", &mut rng)
+        .is_err());
+}
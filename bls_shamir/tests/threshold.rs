@@ -0,0 +1,55 @@
+use bls12_381::{G2Affine, Scalar};
+use bls_shamir::secret::SecretPolynomial;
+use bls_shamir::signature::{self, DST};
+use bls_shamir::threshold::*;
+use group::Curve;
+
+fn polynomial() -> SecretPolynomial {
+    SecretPolynomial::new(vec![Scalar::from(0x1234_5678), Scalar::from(0x8765_4321)])
+}
+
+#[test]
+fn proven_shares_aggregate_into_the_signature() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let hm = signature::hash_to_g2(b"Hello world", DST).to_affine();
+
+    let partials = (1..=3)
+        .map(|i| sign_share(i, &f.evaluate(i), &hm, &mut rng))
+        .collect::<Vec<_>>();
+    for partial in &partials {
+        let public_share = f.evaluate(partial.index).public_key();
+        assert!(verify_share(&public_share, &hm, partial));
+    }
+    let signature = aggregate_shares(&partials[1..]).unwrap();
+    assert!(signature::verify(
+        &f.secret().public_key(),
+        b"Hello world",
+        &signature
+    ));
+}
+
+#[test]
+fn wrong_shares_are_caught() {
+    let mut rng = rand::thread_rng();
+    let f = polynomial();
+    let hm = signature::hash_to_g2(b"Hello world", DST).to_affine();
+    let partial = sign_share(1, &f.evaluate(1), &hm, &mut rng);
+
+    // Checked against another holder's public share, or another message.
+    assert!(!verify_share(&f.evaluate(2).public_key(), &hm, &partial));
+    let other = signature::hash_to_g2(b"Goodbye", DST).to_affine();
+    assert!(!verify_share(&f.evaluate(1).public_key(), &other, &partial));
+
+    // The proof doesn't carry over to another point.
+    let mut forged = partial;
+    forged.point = (hm * Scalar::from(7)).to_affine();
+    assert!(!verify_share(&f.evaluate(1).public_key(), &hm, &forged));
+    forged.point = G2Affine::generator();
+    assert!(!verify_share(&f.evaluate(1).public_key(), &hm, &forged));
+
+    // Nor to another index.
+    let mut replayed = partial;
+    replayed.index = 2;
+    assert!(!verify_share(&f.evaluate(1).public_key(), &hm, &replayed));
+}
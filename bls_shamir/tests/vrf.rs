@@ -0,0 +1,42 @@
+use bls12_381::Scalar;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::signature;
+use bls_shamir::vrf::*;
+
+fn polynomial() -> SecretPolynomial {
+    SecretPolynomial::new(vec![Scalar::from(0x1234_5678), Scalar::from(0x8765_4321)])
+}
+
+#[test]
+fn any_threshold_of_parts_gives_the_same_output() {
+    let f = polynomial();
+    let pk = f.secret().public_key();
+    let parts = (1..=3)
+        .map(|i| vrf_partial_eval(i, &f.evaluate(i), b"epoch 7", &mut rand::thread_rng()))
+        .collect::<Vec<_>>();
+    for (i, part) in parts.iter().enumerate() {
+        let public_share = f.evaluate(i as u64 + 1).public_key();
+        assert!(vrf_verify_partial(&public_share, b"epoch 7", part));
+    }
+
+    let first = vrf_aggregate(&parts[..2]).unwrap();
+    let last = vrf_aggregate(&parts[1..]).unwrap();
+    assert_eq!(first, last);
+    assert_eq!(first, vrf_eval(&f.secret(), b"epoch 7"));
+
+    let output = vrf_verify(&pk, b"epoch 7", &first);
+    assert_eq!(output, Some(first.output()));
+    assert_eq!(vrf_verify(&pk, b"epoch 8", &first), None);
+    assert_ne!(vrf_eval(&f.secret(), b"epoch 8").output(), first.output());
+}
+
+#[test]
+fn proofs_are_not_signatures() {
+    let sk = SecretKey::new(Scalar::from(0x1234_5678));
+    let pk = sk.public_key();
+    let proof = vrf_eval(&sk, b"Hello world");
+
+    assert!(!signature::verify(&pk, b"Hello world", &proof.0));
+    let sig = signature::sign(&sk, b"Hello world");
+    assert_eq!(vrf_verify(&pk, b"Hello world", &Proof(sig)), None);
+}
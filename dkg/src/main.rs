@@ -1,9 +1,18 @@
 use bls12_381::hash_to_curve::*;
 use bls12_381::*;
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
 use group::Curve;
+use std::process;
+use tracing::{debug, info, info_span, warn};
 
 #[allow(non_snake_case)]
 fn main() {
+    let (level, format) = logging::parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\nUsage: dkg {}", e, logging::USAGE);
+        process::exit(2);
+    });
+    logging::init(level, format).expect("No subscriber installed yet");
+
     // We have two dealers f and g, they both come up with a secret polynomial
     // on their own, the coefficients are not shared.
     //
@@ -20,47 +29,52 @@ fn main() {
     // So even if only one of the dealers is honest, we can guarantee the secrecy
     // of `h(x)`.
 
-    let f_coefficients: Vec<u64> = vec![5, 8, 3];
-    let g_coefficients: Vec<u64> = vec![19, 3, 9];
+    let round = info_span!("round", phase = "deal").entered();
+
+    // The coefficients are secret, `SecretPolynomial` wipes them from memory as
+    // soon as the dealer is done with them.
+    let f_polynomial = SecretPolynomial::new(vec![5, 8, 3].into_iter().map(Scalar::from).collect());
+    let g_polynomial =
+        SecretPolynomial::new(vec![19, 3, 9].into_iter().map(Scalar::from).collect());
 
     // Each dealer computes the points (k, y) for 0<k<6, these are the secrets
     // we will associated each `k` with one of the nodes interested in having a
     // share, and secretly communicate the the value of y to only that specific
     // node.
     let f_points = (1..=5)
-        .map(|x| (x, compute_polynomial(&f_coefficients, x)))
+        .map(|x| (x, f_polynomial.evaluate(x)))
         .collect::<Vec<_>>();
     let g_points = (1..=5)
-        .map(|x| (x, compute_polynomial(&g_coefficients, x)))
+        .map(|x| (x, g_polynomial.evaluate(x)))
         .collect::<Vec<_>>();
 
-    println!("F points = {:?}", f_points);
-    println!("G points = {:?}", g_points);
+    info!("Dealt {} shares of f and g", f_points.len());
 
     // Now it's time to generate the data that can be used for validating the shares
     // publicly.
     let G = G1Affine::generator();
 
     // Public coefficients.
-    let f_public_coefficients = f_coefficients
-        .iter()
-        .map(|a| G * Scalar::from(*a))
-        .collect::<Vec<_>>();
-    let g_public_coefficients = g_coefficients
-        .iter()
-        .map(|a| G * Scalar::from(*a))
-        .collect::<Vec<_>>();
+    let f_public_coefficients = f_polynomial.commitments();
+    let g_public_coefficients = g_polynomial.commitments();
+
+    // The dealers no longer need their polynomials.
+    drop(f_polynomial);
+    drop(g_polynomial);
 
     // Public pairs.
     let f_public_points = f_points
         .iter()
-        .map(|(x, y)| (*x, G * Scalar::from(*y)))
+        .map(|(x, y)| (*x, G * y.as_scalar()))
         .collect::<Vec<_>>();
     let g_public_points = g_points
         .iter()
-        .map(|(x, y)| (*x, G * Scalar::from(*y)))
+        .map(|(x, y)| (*x, G * y.as_scalar()))
         .collect::<Vec<_>>();
 
+    drop(round);
+    let round = info_span!("round", phase = "verify").entered();
+
     // Now each node should verify their share:
     // 1. Does the `y` passed to the node actually generates the public yG?
     //    which is to recompute yG for the y that we have, and expect it to
@@ -69,15 +83,15 @@ fn main() {
 
     // Step 1:
     for node in 0..5 {
-        let (_, f) = f_points[node];
+        let _node = info_span!("node", index = node + 1).entered();
+        let (_, f) = &f_points[node];
         let (_, fG) = f_public_points[node];
-        let t = (G * Scalar::from(f)).to_affine();
-        assert_eq!(t, fG.to_affine());
+        assert_eq!(f.public_key(), fG.to_affine());
 
-        let (_, g) = g_points[node];
+        let (_, g) = &g_points[node];
         let (_, gG) = g_public_points[node];
-        let t = (G * Scalar::from(g)).to_affine();
-        assert_eq!(t, gG.to_affine());
+        assert_eq!(g.public_key(), gG.to_affine());
+        debug!("Shares of f and g match their public points");
     }
 
     // Step 2:
@@ -91,8 +105,10 @@ fn main() {
     assert_eq!(f_p, f_public_points);
     assert_eq!(g_p, g_public_points);
 
-    println!("Verification finished without any complaints.");
-    println!("Each node has their share of f and g.");
+    info!("Verification finished without any complaints");
+    info!("Each node has their share of f and g");
+    drop(round);
+    let round = info_span!("round", phase = "combine").entered();
 
     // Now that each node has an (x, y) on both f and g, they can use this
     // information to compute a point on h.
@@ -106,15 +122,21 @@ fn main() {
     let shares = f_points
         .iter()
         .zip(&g_points)
-        .map(|((x, f), (_, g))| (*x, f + g))
+        .map(|((x, f), (_, g))| (*x, SecretKey::new(f.as_scalar() + g.as_scalar())))
         .collect::<Vec<_>>();
 
-    println!("H points = {:?}", shares);
+    // The shares of f and g are no longer needed once h is known.
+    drop(f_points);
+    drop(g_points);
+
+    info!("Each node now holds a share of h ({} shares)", shares.len());
 
     // If we're using `h(0)` as the private key, then `h(0) * G` is gonna be the public
     // key, which can be obtained by aggregating our public information.
     let public_key = compute_polynomial_g(&h_public_coefficients, 0).to_affine();
-    println!("Public key = {:#?}", public_key);
+    info!("Public key = {:?}", public_key);
+    drop(round);
+    let _round = info_span!("round", phase = "sign").entered();
 
     // Now we're gonna sign a message with only 3 nodes.
 
@@ -129,7 +151,7 @@ fn main() {
     // `(x, yM)`.
     let mut sign_shares = shares[0..3]
         .iter()
-        .map(|(x, y)| (*x, M * Scalar::from(*y)))
+        .map(|(x, y)| (*x, M * y.as_scalar()))
         .collect::<Vec<_>>();
 
     // Node 4 returns an invalid signature share. This can mess with the final
@@ -141,7 +163,7 @@ fn main() {
     sign_shares.push((4, M * Scalar::from(29)));
 
     let nodes = sign_shares.len();
-    println!("Using {} nodes to sign the message", sign_shares.len());
+    info!("Using {} nodes to sign the message", sign_shares.len());
 
     // Disqualify invalid shares.
     let sign_shares = sign_shares
@@ -150,15 +172,17 @@ fn main() {
             let node = (x - 1) as usize;
             let yG = (f_public_points[node].1 + g_public_points[node].1).to_affine();
 
-            let l = pairing(&yG, &M);
-            let r = pairing(&G, &yM.to_affine());
-
-            l == r
+            let valid = verify_pairing(&yG, &M, &yM.to_affine());
+            if !valid {
+                let _node = info_span!("node", index = x).entered();
+                warn!("Disqualified an invalid signature share");
+            }
+            valid
         })
         .collect::<Vec<_>>();
 
-    println!(
-        "Validated {} shares ({} node(s) sent invalid share.)",
+    info!(
+        "Validated {} shares ({} node(s) sent invalid share)",
         sign_shares.len(),
         nodes - sign_shares.len()
     );
@@ -167,30 +191,26 @@ fn main() {
     // is the signature.
     let sign = aggregate_shares(&sign_shares);
 
-    println!("Sign={:#?}", sign);
+    info!("Sign = {:?}", sign);
 
     // Now we want to validate this sign.
-    let left = pairing(&public_key, &M);
-    let right = pairing(&G, &sign);
-
-    println!("L={:#?}", left);
-    println!("R={:#?}", right);
-    assert_eq!(left, right);
+    assert!(verify_pairing(&public_key, &M, &sign));
 
-    println!("Signature validated.")
+    info!("Signature validated");
 }
 
-/// Given a vector of coefficients `[a_i]` computes `f(x) = ∑ a_i * x^i`
-fn compute_polynomial(coefficients: &Vec<u64>, x: u64) -> u64 {
-    coefficients
-        .iter()
-        .enumerate()
-        .map(|(i, a)| a * x.pow(i as u32))
-        .sum::<u64>()
+/// Checks `e(P, M) == e(G, S)`.
+///
+/// Instead of computing both pairings and comparing them in Gt, we check
+/// `e(P, M) * e(-G, S) == 1`: the two Miller loops are evaluated together and
+/// share a single final exponentiation, which is the expensive part.
+#[allow(non_snake_case)]
+fn verify_pairing(P: &G1Affine, M: &G2Affine, S: &G2Affine) -> bool {
+    ::pairing::pairing_product_is_one(&[(*P, *M), (-G1Affine::generator(), *S)])
 }
 
 /// Given a vector of coefficients `[a_i * G]` computes `f(x) = ∑ a_i * G * x^i`
-fn compute_polynomial_g(coefficients: &Vec<G1Projective>, x: u64) -> G1Projective {
+fn compute_polynomial_g(coefficients: &[G1Projective], x: u64) -> G1Projective {
     coefficients
         .iter()
         .enumerate()
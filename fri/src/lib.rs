@@ -0,0 +1,330 @@
+//! FRI, the low-degree test behind STARKs: the transparent counterpart of
+//! KZG in the lab, with hashes instead of a trusted setup and pairings.
+//!
+//! A polynomial f of degree below d is committed to as its codeword, its
+//! values on a coset `g H` of a domain `blowup` times larger than d, in a
+//! Merkle tree. Writing `f(x) = f_e(x²) + x f_o(x²)`, a challenge β folds f
+//! into `f_e + β f_o`, of half the degree, whose codeword on the coset
+//! `g² H²` of half the size comes from pairs of values of f:
+//!
+//! f'(x²) = (f(x) + f(-x)) / 2 + β (f(x) - f(-x)) / 2x
+//!
+//! The prover commits to every codeword down to a constant, which it sends
+//! in the clear. The verifier then picks random positions of the first
+//! codeword and follows them down the layers, checking each fold against
+//! the next codeword. A codeword far from the polynomials of degree below
+//! d fails a query with a probability about the distance, `1 - 1/blowup`
+//! at best, so it takes a few dozen queries for a small enough soundness
+//! error.
+//!
+//! The trees hash with SHA-256, the codewords never enter a circuit here,
+//! and the challenges come from the [`Transcript`] the caller gives.
+use bls12_381::Scalar;
+use group::ff::PrimeField;
+use merkle::{MerkleError, MerkleProof, MerkleTree, Sha256};
+use poly::fft::Domain;
+use sigma::transcript::Transcript;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parameters {
+    /// The ratio of the size of the codewords to the degree, a power of two
+    /// of at least 2.
+    pub blowup: usize,
+    pub queries: usize,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Parameters {
+            blowup: 4,
+            queries: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    /// The roots of the codewords, the first being the commitment to f.
+    pub roots: Vec<Scalar>,
+    /// The value of the last fold.
+    pub last: Scalar,
+    pub queries: Vec<Query>,
+}
+
+/// The values of every codeword at the positions of a query, x and -x,
+/// with their paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub layers: Vec<[MerkleProof; 2]>,
+}
+
+/// The values of the polynomial on the coset `g H` of the `degree * blowup`
+/// roots of unity.
+pub fn codeword(poly: &[Scalar], degree: usize, blowup: usize) -> Result<Vec<Scalar>, FriError> {
+    if poly.len() > degree {
+        return Err(FriError::Degree {
+            degree: poly.len(),
+            max: degree,
+        });
+    }
+    let size = degree.checked_mul(blowup).ok_or(FriError::TooLarge)?;
+    let domain = Domain::new(size).ok_or(FriError::TooLarge)?;
+    Ok(domain.coset_fft(poly))
+}
+
+/// Folds the codeword on the coset `offset H` into the codeword on
+/// `offset² H²` of `f_e + β f_o`.
+pub fn fold(codeword: &[Scalar], offset: Scalar, beta: Scalar) -> Vec<Scalar> {
+    let half = codeword.len() / 2;
+    let omega_inverse = invert(
+        Domain::new(codeword.len())
+            .expect("Codewords are in a domain")
+            .omega(),
+    );
+    let two_inverse = two_inverse();
+    // The x of the pairs walk the coset, so do their inverses.
+    let mut x_inverse = invert(offset);
+    (0..half)
+        .map(|i| {
+            let folded = fold_pair(
+                codeword[i],
+                codeword[i + half],
+                x_inverse,
+                beta,
+                two_inverse,
+            );
+            x_inverse *= omega_inverse;
+            folded
+        })
+        .collect()
+}
+
+/// `(a + b) / 2 + β (a - b) / 2x`, for `a = f(x)` and `b = f(-x)`, given
+/// `1 / x` and `1 / 2`.
+fn fold_pair(a: Scalar, b: Scalar, x_inverse: Scalar, beta: Scalar, two_inverse: Scalar) -> Scalar {
+    two_inverse * (a + b + beta * (a - b) * x_inverse)
+}
+
+fn two_inverse() -> Scalar {
+    invert(Scalar::from(2))
+}
+
+fn invert(x: Scalar) -> Scalar {
+    Option::from(x.invert()).expect("Two and the cosets don't contain zero")
+}
+
+/// Proves that the polynomial is of a degree below `degree`, a power of two
+/// of at least 2.
+pub fn prove(
+    parameters: &Parameters,
+    poly: &[Scalar],
+    degree: usize,
+    transcript: &mut Transcript,
+) -> Result<Proof, FriError> {
+    check(parameters, degree)?;
+    statement(transcript, parameters, degree);
+
+    // Commit: every codeword down to a constant.
+    let mut codewords = vec![codeword(poly, degree, parameters.blowup)?];
+    let mut trees = Vec::new();
+    let mut offset = Scalar::multiplicative_generator();
+    let mut roots = Vec::new();
+    while codewords.last().unwrap().len() > parameters.blowup {
+        let codeword = codewords.last().unwrap();
+        let tree = MerkleTree::<Sha256>::new(codeword)?;
+        transcript.append_scalar(b"fri root", &tree.root());
+        roots.push(tree.root());
+        trees.push(tree);
+        let beta = transcript.challenge_scalar(b"fri beta");
+        let folded = fold(codeword, offset, beta);
+        codewords.push(folded);
+        offset = offset.square();
+    }
+    let last = codewords.last().unwrap()[0];
+    transcript.append_scalar(b"fri last", &last);
+
+    // Query: the pairs of every codeword on the way down.
+    let queries = positions(transcript, parameters, degree)
+        .into_iter()
+        .map(|position| -> Result<_, FriError> {
+            let layers = trees
+                .iter()
+                .map(|tree| -> Result<_, FriError> {
+                    let half = tree.leaves().len() / 2;
+                    let i = position % half;
+                    Ok([tree.prove(i)?, tree.prove(i + half)?])
+                })
+                .collect::<Result<_, FriError>>()?;
+            Ok(Query { layers })
+        })
+        .collect::<Result<_, FriError>>()?;
+
+    Ok(Proof {
+        roots,
+        last,
+        queries,
+    })
+}
+
+/// Checks that the first codeword of the proof is close to a polynomial of
+/// a degree below `degree`.
+pub fn verify(
+    parameters: &Parameters,
+    degree: usize,
+    proof: &Proof,
+    transcript: &mut Transcript,
+) -> Result<(), FriError> {
+    check(parameters, degree)?;
+    let layers = degree.trailing_zeros() as usize;
+    if proof.roots.len() != layers || proof.queries.len() != parameters.queries {
+        return Err(FriError::Shape);
+    }
+    statement(transcript, parameters, degree);
+
+    let betas: Vec<Scalar> = proof
+        .roots
+        .iter()
+        .map(|root| {
+            transcript.append_scalar(b"fri root", root);
+            transcript.challenge_scalar(b"fri beta")
+        })
+        .collect();
+    transcript.append_scalar(b"fri last", &proof.last);
+
+    let size = degree * parameters.blowup;
+    let two_inverse = two_inverse();
+    let positions = positions(transcript, parameters, degree);
+    for (query, (position, values)) in positions.iter().zip(&proof.queries).enumerate() {
+        if values.layers.len() != layers {
+            return Err(FriError::Shape);
+        }
+        // The value the previous fold expects, at the position of the
+        // layer.
+        let mut expected: Option<(usize, Scalar)> = None;
+        let mut offset = Scalar::multiplicative_generator();
+        for (layer, (pair, (root, beta))) in values
+            .layers
+            .iter()
+            .zip(proof.roots.iter().zip(&betas))
+            .enumerate()
+        {
+            let len = size >> layer;
+            let half = len / 2;
+            let i = position % half;
+            for (path, index) in pair.iter().zip([i, i + half]) {
+                if path.index != index || merkle::verify::<Sha256>(*root, path).is_err() {
+                    return Err(FriError::Path { query, layer });
+                }
+            }
+            let (a, b) = (pair[0].leaf, pair[1].leaf);
+            if let Some((index, value)) = expected {
+                let revealed = if index == i { a } else { b };
+                if revealed != value {
+                    return Err(FriError::Fold { query, layer });
+                }
+            }
+            let x = offset * Domain::new(len).ok_or(FriError::TooLarge)?.element(i);
+            expected = Some((i, fold_pair(a, b, invert(x), *beta, two_inverse)));
+            offset = offset.square();
+        }
+        if let Some((_, value)) = expected {
+            if value != proof.last {
+                return Err(FriError::Fold {
+                    query,
+                    layer: layers,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check(parameters: &Parameters, degree: usize) -> Result<(), FriError> {
+    if !degree.is_power_of_two()
+        || degree < 2
+        || !parameters.blowup.is_power_of_two()
+        || parameters.blowup < 2
+        || parameters.queries == 0
+    {
+        return Err(FriError::Parameters);
+    }
+    Ok(())
+}
+
+fn statement(transcript: &mut Transcript, parameters: &Parameters, degree: usize) {
+    transcript.append_u64(b"fri degree", degree as u64);
+    transcript.append_u64(b"fri blowup", parameters.blowup as u64);
+    transcript.append_u64(b"fri queries", parameters.queries as u64);
+}
+
+/// The positions of the queries in the first half of the first codeword.
+fn positions(transcript: &mut Transcript, parameters: &Parameters, degree: usize) -> Vec<usize> {
+    let half = degree * parameters.blowup / 2;
+    (0..parameters.queries)
+        .map(|_| {
+            let mut bytes = [0; 8];
+            transcript.challenge_bytes(b"fri query", &mut bytes);
+            (u64::from_be_bytes(bytes) % half as u64) as usize
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FriError {
+    /// The degree or the blowup isn't a power of two of at least 2, or
+    /// there are no queries.
+    Parameters,
+    /// The codewords don't fit in a domain.
+    TooLarge,
+    /// The polynomial has more coefficients than the degree allows.
+    Degree {
+        degree: usize,
+        max: usize,
+    },
+    /// The proof isn't for these parameters.
+    Shape,
+    /// A path of a query doesn't lead to the root of its codeword.
+    Path {
+        query: usize,
+        layer: usize,
+    },
+    /// A fold of a query doesn't match the next codeword.
+    Fold {
+        query: usize,
+        layer: usize,
+    },
+    Merkle(MerkleError),
+}
+
+impl fmt::Display for FriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FriError::Parameters => write!(f, "invalid parameters"),
+            FriError::TooLarge => write!(f, "the codewords don't fit in a domain"),
+            FriError::Degree { degree, max } => {
+                write!(
+                    f,
+                    "a polynomial of {} coefficients, at most {}",
+                    degree, max
+                )
+            }
+            FriError::Shape => write!(f, "the proof isn't for these parameters"),
+            FriError::Path { query, layer } => {
+                write!(f, "query {} has an invalid path in layer {}", query, layer)
+            }
+            FriError::Fold { query, layer } => {
+                write!(f, "query {} doesn't fold into layer {}", query, layer)
+            }
+            FriError::Merkle(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FriError {}
+
+impl From<MerkleError> for FriError {
+    fn from(e: MerkleError) -> Self {
+        FriError::Merkle(e)
+    }
+}
@@ -0,0 +1,96 @@
+use bls12_381::Scalar;
+use fri::{codeword, fold, prove, verify, FriError, Parameters};
+use group::ff::{Field, PrimeField};
+use rand::thread_rng;
+use sigma::transcript::Transcript;
+
+const DOMAIN: &[u8] = b"fri test";
+
+fn random_poly(len: usize) -> Vec<Scalar> {
+    let mut rng = thread_rng();
+    (0..len).map(|_| Scalar::random(&mut rng)).collect()
+}
+
+#[test]
+fn folds_into_half_the_degree() {
+    let poly = random_poly(8);
+    let beta = Scalar::from(5);
+    let offset = Scalar::multiplicative_generator();
+    let folded = fold(&codeword(&poly, 8, 4).unwrap(), offset, beta);
+    assert_eq!(folded.len(), 16);
+
+    // f_e + β f_o on the coset g² H².
+    let expected: Vec<Scalar> = poly
+        .chunks(2)
+        .map(|pair| pair[0] + beta * pair[1])
+        .collect();
+    let omega = poly::fft::Domain::new(16).unwrap().omega();
+    let mut x = offset.square();
+    for value in folded {
+        assert_eq!(value, poly::evaluate(&expected, x));
+        x *= omega;
+    }
+}
+
+#[test]
+fn proves_a_low_degree() {
+    let parameters = Parameters::default();
+    let poly = random_poly(16);
+    let proof = prove(&parameters, &poly, 16, &mut Transcript::new(DOMAIN)).unwrap();
+    assert_eq!(proof.roots.len(), 4);
+    verify(&parameters, 16, &proof, &mut Transcript::new(DOMAIN)).unwrap();
+
+    // A lower degree passes a higher bound.
+    let proof = prove(&parameters, &poly[..5], 16, &mut Transcript::new(DOMAIN)).unwrap();
+    verify(&parameters, 16, &proof, &mut Transcript::new(DOMAIN)).unwrap();
+}
+
+#[test]
+fn rejects_tampered_proofs() {
+    let parameters = Parameters::default();
+    let proof = prove(
+        &parameters,
+        &random_poly(16),
+        16,
+        &mut Transcript::new(DOMAIN),
+    )
+    .unwrap();
+
+    let mut tampered = proof.clone();
+    tampered.last += Scalar::one();
+    // The transcript binds the last value, so the queries move too and
+    // mostly miss the revealed paths.
+    assert!(matches!(
+        verify(&parameters, 16, &tampered, &mut Transcript::new(DOMAIN)),
+        Err(FriError::Path { .. } | FriError::Fold { .. })
+    ));
+
+    let mut tampered = proof.clone();
+    tampered.queries[3].layers[1][0].leaf += Scalar::one();
+    assert_eq!(
+        verify(&parameters, 16, &tampered, &mut Transcript::new(DOMAIN)),
+        Err(FriError::Path { query: 3, layer: 1 })
+    );
+
+    assert_eq!(
+        verify(&parameters, 8, &proof, &mut Transcript::new(DOMAIN)),
+        Err(FriError::Shape)
+    );
+}
+
+#[test]
+fn checks_the_degree() {
+    let parameters = Parameters::default();
+    let poly = random_poly(32);
+    assert_eq!(
+        prove(&parameters, &poly, 16, &mut Transcript::new(DOMAIN)),
+        Err(FriError::Degree {
+            degree: 32,
+            max: 16
+        })
+    );
+    assert_eq!(
+        prove(&parameters, &poly, 12, &mut Transcript::new(DOMAIN)),
+        Err(FriError::Parameters)
+    );
+}
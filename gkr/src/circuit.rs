@@ -0,0 +1,99 @@
+//! Layered arithmetic circuits: each gate adds or multiplies two values of
+//! the layer right below its own, the last layer reading the inputs.
+use crate::GkrError;
+use bls12_381::Scalar;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Mul,
+}
+
+/// A gate of a layer, reading the values `left` and `right` of the layer
+/// below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gate {
+    pub operation: Operation,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Gate {
+    pub fn add(left: usize, right: usize) -> Self {
+        Gate {
+            operation: Operation::Add,
+            left,
+            right,
+        }
+    }
+
+    pub fn mul(left: usize, right: usize) -> Self {
+        Gate {
+            operation: Operation::Mul,
+            left,
+            right,
+        }
+    }
+
+    fn apply(&self, below: &[Scalar]) -> Scalar {
+        let (left, right) = (below[self.left], below[self.right]);
+        match self.operation {
+            Operation::Add => left + right,
+            Operation::Mul => left * right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Circuit {
+    inputs: usize,
+    /// The outputs first.
+    layers: Vec<Vec<Gate>>,
+}
+
+impl Circuit {
+    /// The circuit of `inputs` inputs and of the layers, given from the
+    /// outputs down.
+    pub fn new(inputs: usize, layers: Vec<Vec<Gate>>) -> Result<Self, GkrError> {
+        if inputs == 0 || layers.is_empty() || layers.iter().any(Vec::is_empty) {
+            return Err(GkrError::Shape);
+        }
+        for (i, layer) in layers.iter().enumerate() {
+            let below = layers.get(i + 1).map_or(inputs, Vec::len);
+            if let Some(gate) = layer
+                .iter()
+                .position(|gate| gate.left >= below || gate.right >= below)
+            {
+                return Err(GkrError::Wire { layer: i, gate });
+            }
+        }
+        Ok(Circuit { inputs, layers })
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// The layers from the outputs down.
+    pub fn layers(&self) -> &[Vec<Gate>] {
+        &self.layers
+    }
+
+    /// The values of every layer, the outputs first and the inputs last.
+    pub fn evaluate(&self, inputs: &[Scalar]) -> Result<Vec<Vec<Scalar>>, GkrError> {
+        if inputs.len() != self.inputs {
+            return Err(GkrError::Inputs {
+                expected: self.inputs,
+                got: inputs.len(),
+            });
+        }
+        let mut values = vec![inputs.to_vec()];
+        for layer in self.layers.iter().rev() {
+            let below = values.last().unwrap();
+            let layer = layer.iter().map(|gate| gate.apply(below)).collect();
+            values.push(layer);
+        }
+        values.reverse();
+        Ok(values)
+    }
+}
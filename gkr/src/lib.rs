@@ -0,0 +1,296 @@
+//! A toy GKR: proving the evaluation of a layered arithmetic circuit with
+//! sumchecks, one per layer, and no trusted setup. The verifier's work is
+//! linear in the size of the circuit's description and in its inputs, but
+//! it never evaluates the gates.
+//!
+//! Each layer's values, padded with zeros to a power of two, define a
+//! multilinear extension `W̃_i`, and with the multilinear extensions `add_i`
+//! and `mul_i` of its wiring, 1 at `(z, x, y)` when gate z adds or
+//! multiplies the values x and y of the layer below,
+//!
+//! W̃_i(z) = ∑_{x, y} add_i(z, x, y) (W̃_{i+1}(x) + W̃_{i+1}(y))
+//!                  + mul_i(z, x, y) W̃_{i+1}(x) W̃_{i+1}(y)
+//!
+//! The verifier starts from the outputs, with the claim of `W̃_0` at a
+//! random point r. A sumcheck on the right hand side at `z = r` reduces it
+//! to the values of `W̃_{i+1}` at two points b and c. The prover sends
+//! `W̃_{i+1}` on the line through them, a polynomial of the degree of the
+//! number of variables, the verifier checks it against the sumcheck with
+//! its own `add_i` and `mul_i`, and goes on with the claim at a random
+//! point of the line. What is left after the last layer is a claim on the
+//! extension of the inputs, which the verifier evaluates.
+//!
+//! The round polynomials are sent as of degree 3, the size of the product
+//! `mul_i W̃_{i+1} W̃_{i+1}`, although they are of degree 2.
+use bls12_381::Scalar;
+use sigma::transcript::Transcript;
+use std::fmt;
+use sumcheck::{interpolate, MultilinearExtension, SumcheckError};
+
+pub mod circuit;
+
+pub use circuit::{Circuit, Gate, Operation};
+
+const DOMAIN: &[u8] = b"zk-lab/gkr";
+
+/// The degree the round polynomials are sent as.
+const DEGREE: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    /// From the outputs down.
+    pub layers: Vec<LayerProof>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerProof {
+    pub sumcheck: sumcheck::Proof,
+    /// The extension of the layer below on the line through the points the
+    /// sumcheck reduced to, at `0, ..., k` for k variables.
+    pub line: Vec<Scalar>,
+}
+
+/// Evaluates the circuit and proves it, returns the outputs and the proof.
+pub fn prove(circuit: &Circuit, inputs: &[Scalar]) -> Result<(Vec<Scalar>, Proof), GkrError> {
+    let values = circuit.evaluate(inputs)?;
+    let outputs = values[0].clone();
+    let mut transcript = transcript(circuit, inputs, &outputs);
+    let mut point = challenges(&mut transcript, vars(outputs.len()));
+
+    let mut layers = Vec::with_capacity(circuit.layers().len());
+    for (i, gates) in circuit.layers().iter().enumerate() {
+        let below = extension(&values[i + 1]);
+        let k = below.num_vars();
+        let (add, mul) = wiring(gates, &point, k);
+        // W̃(x) and W̃(y), as extensions of (x, y).
+        let left = MultilinearExtension::new(
+            below
+                .evaluations()
+                .iter()
+                .flat_map(|value| std::iter::repeat_n(*value, 1 << k))
+                .collect(),
+        )
+        .unwrap();
+        let right = MultilinearExtension::new(below.evaluations().repeat(1 << k)).unwrap();
+        let terms = [
+            vec![add.clone(), left.clone()],
+            vec![add, right.clone()],
+            vec![mul, left, right],
+        ];
+        let (sumcheck, claim) = sumcheck::prove_sum(&terms, &mut transcript)
+            .map_err(|error| GkrError::Sumcheck { layer: i, error })?;
+
+        let (b, c) = claim.point.split_at(k);
+        let line: Vec<Scalar> = (0..=k as u64)
+            .map(|t| below.evaluate(&on_line(b, c, Scalar::from(t))))
+            .collect();
+        let r = line_challenge(&mut transcript, &line);
+        point = on_line(b, c, r);
+        layers.push(LayerProof { sumcheck, line });
+    }
+    Ok((outputs, Proof { layers }))
+}
+
+/// Checks that the circuit maps the inputs to the outputs.
+pub fn verify(
+    circuit: &Circuit,
+    inputs: &[Scalar],
+    outputs: &[Scalar],
+    proof: &Proof,
+) -> Result<(), GkrError> {
+    if inputs.len() != circuit.inputs() {
+        return Err(GkrError::Inputs {
+            expected: circuit.inputs(),
+            got: inputs.len(),
+        });
+    }
+    if outputs.len() != circuit.layers()[0].len() || proof.layers.len() != circuit.layers().len() {
+        return Err(GkrError::Shape);
+    }
+    let mut transcript = transcript(circuit, inputs, outputs);
+    let mut point = challenges(&mut transcript, vars(outputs.len()));
+    let mut claim = extension(outputs).evaluate(&point);
+
+    for (i, (gates, layer)) in circuit.layers().iter().zip(&proof.layers).enumerate() {
+        let below = circuit.layers().get(i + 1).map_or(inputs.len(), Vec::len);
+        let k = vars(below);
+        let reduced = sumcheck::verify(2 * k, DEGREE, claim, &layer.sumcheck, &mut transcript)
+            .map_err(|error| GkrError::Sumcheck { layer: i, error })?;
+        if layer.line.len() != k + 1 {
+            return Err(GkrError::Shape);
+        }
+
+        let (b, c) = reduced.point.split_at(k);
+        let (add, mul) = wiring_at(gates, &point, b, c);
+        let at_b = interpolate(&layer.line, Scalar::zero());
+        let at_c = interpolate(&layer.line, Scalar::one());
+        if reduced.value != add * (at_b + at_c) + mul * at_b * at_c {
+            return Err(GkrError::Layer { layer: i });
+        }
+        let r = line_challenge(&mut transcript, &layer.line);
+        point = on_line(b, c, r);
+        claim = interpolate(&layer.line, r);
+    }
+
+    if extension(inputs).evaluate(&point) != claim {
+        return Err(GkrError::Invalid);
+    }
+    Ok(())
+}
+
+/// The number of variables of a layer of `len` values.
+fn vars(len: usize) -> usize {
+    len.next_power_of_two().trailing_zeros() as usize
+}
+
+fn extension(values: &[Scalar]) -> MultilinearExtension {
+    let mut values = values.to_vec();
+    values.resize(values.len().next_power_of_two(), Scalar::zero());
+    MultilinearExtension::new(values).unwrap()
+}
+
+/// `b + t (c - b)`
+fn on_line(b: &[Scalar], c: &[Scalar], t: Scalar) -> Vec<Scalar> {
+    b.iter().zip(c).map(|(b, c)| b + t * (c - b)).collect()
+}
+
+/// `eq(r, x) = ∏ (r_i x_i + (1 - r_i)(1 - x_i))` for every x of the
+/// hypercube, the first variable being the most significant bit.
+fn eq_table(point: &[Scalar]) -> Vec<Scalar> {
+    point.iter().fold(vec![Scalar::one()], |table, r| {
+        table
+            .iter()
+            .flat_map(|e| [e * (Scalar::one() - r), e * r])
+            .collect()
+    })
+}
+
+/// `add_i(r, x, y)` and `mul_i(r, x, y)` as extensions of (x, y), x and y
+/// of k variables.
+fn wiring(
+    gates: &[Gate],
+    point: &[Scalar],
+    k: usize,
+) -> (MultilinearExtension, MultilinearExtension) {
+    let eq = eq_table(point);
+    let mut add = vec![Scalar::zero(); 1 << (2 * k)];
+    let mut mul = add.clone();
+    for (gate, e) in gates.iter().zip(eq) {
+        let table = match gate.operation {
+            Operation::Add => &mut add,
+            Operation::Mul => &mut mul,
+        };
+        table[(gate.left << k) | gate.right] += e;
+    }
+    (
+        MultilinearExtension::new(add).unwrap(),
+        MultilinearExtension::new(mul).unwrap(),
+    )
+}
+
+/// `add_i(r, b, c)` and `mul_i(r, b, c)`, one term per gate.
+fn wiring_at(gates: &[Gate], r: &[Scalar], b: &[Scalar], c: &[Scalar]) -> (Scalar, Scalar) {
+    let (r, b, c) = (eq_table(r), eq_table(b), eq_table(c));
+    let mut add = Scalar::zero();
+    let mut mul = Scalar::zero();
+    for (gate, e) in gates.iter().zip(r) {
+        let term = e * b[gate.left] * c[gate.right];
+        match gate.operation {
+            Operation::Add => add += term,
+            Operation::Mul => mul += term,
+        }
+    }
+    (add, mul)
+}
+
+/// The transcript after the statement: the circuit, its inputs and its
+/// outputs.
+fn transcript(circuit: &Circuit, inputs: &[Scalar], outputs: &[Scalar]) -> Transcript {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_u64(b"inputs", circuit.inputs() as u64);
+    for layer in circuit.layers() {
+        transcript.append_u64(b"layer", layer.len() as u64);
+        for gate in layer {
+            let operation = match gate.operation {
+                Operation::Add => 0,
+                Operation::Mul => 1,
+            };
+            transcript.append_u64(b"operation", operation);
+            transcript.append_u64(b"left", gate.left as u64);
+            transcript.append_u64(b"right", gate.right as u64);
+        }
+    }
+    for input in inputs {
+        transcript.append_scalar(b"input", input);
+    }
+    for output in outputs {
+        transcript.append_scalar(b"output", output);
+    }
+    transcript
+}
+
+fn challenges(transcript: &mut Transcript, count: usize) -> Vec<Scalar> {
+    (0..count)
+        .map(|_| transcript.challenge_scalar(b"point"))
+        .collect()
+}
+
+fn line_challenge(transcript: &mut Transcript, line: &[Scalar]) -> Scalar {
+    for value in line {
+        transcript.append_scalar(b"line", value);
+    }
+    transcript.challenge_scalar(b"line point")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GkrError {
+    /// An empty circuit or layer, or a proof or outputs of another circuit.
+    Shape,
+    /// A gate reads past the layer below.
+    Wire {
+        layer: usize,
+        gate: usize,
+    },
+    Inputs {
+        expected: usize,
+        got: usize,
+    },
+    /// The sumcheck of a layer doesn't hold.
+    Sumcheck {
+        layer: usize,
+        error: SumcheckError,
+    },
+    /// The line of a layer doesn't match its sumcheck.
+    Layer {
+        layer: usize,
+    },
+    /// The claim on the inputs doesn't hold.
+    Invalid,
+}
+
+impl fmt::Display for GkrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GkrError::Shape => write!(f, "the circuit or the proof is malformed"),
+            GkrError::Wire { layer, gate } => {
+                write!(
+                    f,
+                    "gate {} of layer {} reads past the layer below",
+                    gate, layer
+                )
+            }
+            GkrError::Inputs { expected, got } => {
+                write!(f, "expected {} inputs, got {}", expected, got)
+            }
+            GkrError::Sumcheck { layer, error } => {
+                write!(f, "the sumcheck of layer {}: {}", layer, error)
+            }
+            GkrError::Layer { layer } => {
+                write!(f, "the line of layer {} doesn't match its sumcheck", layer)
+            }
+            GkrError::Invalid => write!(f, "the claim on the inputs doesn't hold"),
+        }
+    }
+}
+
+impl std::error::Error for GkrError {}
@@ -0,0 +1,101 @@
+use bls12_381::Scalar;
+use gkr::{prove, verify, Circuit, Gate, GkrError};
+use sumcheck::SumcheckError;
+
+/// Three layers over five inputs, none of them of a power of two.
+fn circuit() -> Circuit {
+    Circuit::new(
+        5,
+        vec![
+            vec![Gate::mul(0, 1), Gate::mul(1, 1), Gate::add(2, 2)],
+            vec![Gate::add(0, 1), Gate::mul(2, 3), Gate::mul(4, 5)],
+            (0..5)
+                .map(|i| Gate::add(i, i))
+                .chain([Gate::mul(0, 4)])
+                .collect(),
+        ],
+    )
+    .unwrap()
+}
+
+fn inputs() -> Vec<Scalar> {
+    [2, 3, 5, 7, 11].into_iter().map(Scalar::from).collect()
+}
+
+#[test]
+fn evaluates_layer_by_layer() {
+    let values = circuit().evaluate(&inputs()).unwrap();
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[3], inputs());
+    let expected = [(4 + 6) * (10 * 14), (10 * 14) * (10 * 14), 2 * (22 * 22)];
+    assert_eq!(
+        values[0],
+        expected.into_iter().map(Scalar::from).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn proves_the_evaluation() {
+    let circuit = circuit();
+    let (outputs, proof) = prove(&circuit, &inputs()).unwrap();
+    verify(&circuit, &inputs(), &outputs, &proof).unwrap();
+}
+
+#[test]
+fn rejects_wrong_outputs_or_inputs() {
+    let circuit = circuit();
+    let (outputs, proof) = prove(&circuit, &inputs()).unwrap();
+
+    let mut wrong = outputs.clone();
+    wrong[2] += Scalar::one();
+    assert!(matches!(
+        verify(&circuit, &inputs(), &wrong, &proof),
+        Err(GkrError::Sumcheck {
+            layer: 0,
+            error: SumcheckError::Sum { round: 0 }
+        })
+    ));
+
+    let mut wrong = inputs();
+    wrong[4] += Scalar::one();
+    assert!(verify(&circuit, &wrong, &outputs, &proof).is_err());
+    assert_eq!(
+        verify(&circuit, &inputs()[..4], &outputs, &proof),
+        Err(GkrError::Inputs {
+            expected: 5,
+            got: 4
+        })
+    );
+}
+
+#[test]
+fn rejects_a_tampered_line() {
+    let circuit = circuit();
+    let (outputs, mut proof) = prove(&circuit, &inputs()).unwrap();
+    proof.layers[1].line[0] += Scalar::one();
+    assert_eq!(
+        verify(&circuit, &inputs(), &outputs, &proof),
+        Err(GkrError::Layer { layer: 1 })
+    );
+}
+
+#[test]
+fn rejects_a_proof_of_another_circuit() {
+    let (outputs, proof) = prove(&circuit(), &inputs()).unwrap();
+    let mut layers = circuit().layers().to_vec();
+    layers[1][0] = Gate::mul(0, 1);
+    let other = Circuit::new(5, layers).unwrap();
+    assert!(verify(&other, &inputs(), &outputs, &proof).is_err());
+}
+
+#[test]
+fn checks_the_wiring() {
+    assert_eq!(
+        Circuit::new(2, vec![vec![Gate::add(0, 2)]]),
+        Err(GkrError::Wire { layer: 0, gate: 0 })
+    );
+    assert_eq!(
+        Circuit::new(2, vec![vec![Gate::add(0, 1)], vec![]]),
+        Err(GkrError::Shape)
+    );
+}
@@ -0,0 +1,20 @@
+//! A circuit to prove knowledge of the preimage of a MiMC hash, the
+//! "hello world" of SNARKs: 330 constraints for the one block of
+//! [`mimc::hash`].
+use bls12_381::Scalar;
+use r1cs::{ConstraintSystem, LinearCombination, Variable};
+
+pub fn hash(preimage: Scalar) -> Scalar {
+    mimc::hash(&[preimage])
+}
+
+/// The system proving that the prover knows a preimage of `digest`, the
+/// public input. The setup can use any values.
+pub fn preimage_circuit(preimage: Scalar, digest: Scalar) -> ConstraintSystem {
+    let mut cs = ConstraintSystem::new();
+    let digest = cs.alloc_input(digest);
+    let preimage = LinearCombination::from(cs.alloc(preimage));
+    let hash = mimc::gadget::hash(&mut cs, &[preimage]);
+    cs.enforce("the digest", hash, Variable::One, digest);
+    cs
+}
@@ -0,0 +1,282 @@
+//! A toy Groth16 over BLS12-381, proving statements of the `r1cs` crate.
+//!
+//! The setup turns the constraint system into a QAP and evaluates its
+//! polynomials at a secret τ, in the exponent, next to the secrets α, β, γ
+//! and δ that keep the prover from mixing them up. A proof is three points
+//! A, B and C, checked against the public inputs x with
+//!
+//! e(A, B) = e(α, β) * e(∑ x_i * IC_i, γ) * e(C, δ)
+//!
+//! as a single product of pairings. The setup is trusted: whoever knows τ
+//! or the other secrets can prove anything, so they are dropped as soon as
+//! the keys are computed. Its cost, and the prover's, is quadratic in the
+//! number of constraints, see the `qap` module.
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use bls_shamir::backend;
+use group::ff::Field;
+use group::Curve;
+use r1cs::{ConstraintSystem, LinearCombination, R1csError};
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroizing;
+
+pub mod example;
+mod qap;
+
+use qap::Rows;
+
+/// What the verifier needs: `IC_i = (β u_i(τ) + α v_i(τ) + w_i(τ)) / γ` for
+/// the constant and each public input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyingKey {
+    pub alpha_g1: G1Affine,
+    pub beta_g2: G2Affine,
+    pub gamma_g2: G2Affine,
+    pub delta_g2: G2Affine,
+    pub ic: Vec<G1Affine>,
+}
+
+/// What the prover needs, for one circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvingKey {
+    pub vk: VerifyingKey,
+    pub beta_g1: G1Affine,
+    pub delta_g1: G1Affine,
+    /// `u_i(τ)` for each variable, in the order of `z = [1, inputs, aux]`.
+    pub a: Vec<G1Affine>,
+    /// `v_i(τ)` for each variable, in both groups.
+    pub b_g1: Vec<G1Affine>,
+    pub b_g2: Vec<G2Affine>,
+    /// `(β u_i(τ) + α v_i(τ) + w_i(τ)) / δ` for each auxiliary value.
+    pub l: Vec<G1Affine>,
+    /// `τ^k Z(τ) / δ`, to commit to the quotient H.
+    pub h: Vec<G1Affine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Proof {
+    pub a: G1Affine,
+    pub b: G2Affine,
+    pub c: G1Affine,
+}
+
+impl Proof {
+    pub const SIZE: usize = 48 + 96 + 48;
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..48].copy_from_slice(&self.a.to_compressed());
+        bytes[48..144].copy_from_slice(&self.b.to_compressed());
+        bytes[144..].copy_from_slice(&self.c.to_compressed());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Option<Self> {
+        Some(Proof {
+            a: Option::from(G1Affine::from_compressed(bytes[..48].try_into().unwrap()))?,
+            b: Option::from(G2Affine::from_compressed(
+                bytes[48..144].try_into().unwrap(),
+            ))?,
+            c: Option::from(G1Affine::from_compressed(bytes[144..].try_into().unwrap()))?,
+        })
+    }
+}
+
+/// Generates the keys of the circuit `cs` describes, its values are
+/// ignored.
+pub fn setup<R: RngCore + CryptoRng>(cs: &ConstraintSystem, rng: &mut R) -> ProvingKey {
+    let rows = Rows::new(cs);
+    let n = rows.rows.len();
+    let variables = 1 + cs.num_inputs() + cs.num_aux();
+
+    let (tau, basis, z) = loop {
+        let tau = Zeroizing::new(Scalar::random(&mut *rng));
+        let (basis, z) = qap::lagrange_at(n, *tau);
+        if z != Scalar::zero() {
+            break (tau, Zeroizing::new(basis), z);
+        }
+    };
+    let alpha = Zeroizing::new(Scalar::random(&mut *rng));
+    let beta = Zeroizing::new(Scalar::random(&mut *rng));
+    let gamma = Zeroizing::new(nonzero(rng));
+    let delta = Zeroizing::new(nonzero(rng));
+    let gamma_inverse = Zeroizing::new(gamma.invert().unwrap());
+    let delta_inverse = Zeroizing::new(delta.invert().unwrap());
+
+    // u_i(τ), v_i(τ) and w_i(τ), from the coefficients of each variable in
+    // each row.
+    let mut u = Zeroizing::new(vec![Scalar::zero(); variables]);
+    let mut v = Zeroizing::new(vec![Scalar::zero(); variables]);
+    let mut w = Zeroizing::new(vec![Scalar::zero(); variables]);
+    for ((a, b, c), l) in rows.rows.iter().zip(basis.iter()) {
+        for (polynomials, lc) in [(&mut u, a), (&mut v, b), (&mut w, c)] {
+            for (variable, coefficient) in lc.terms() {
+                polynomials[rows.index(*variable)] += coefficient * l;
+            }
+        }
+    }
+
+    let g1 = G1Projective::generator();
+    let g2 = G2Projective::generator();
+    let public = 1 + cs.num_inputs();
+    let combined =
+        |i: usize, inverse: &Scalar| g1 * ((*beta * u[i] + *alpha * v[i] + w[i]) * inverse);
+
+    let vk = VerifyingKey {
+        alpha_g1: (g1 * *alpha).to_affine(),
+        beta_g2: (g2 * *beta).to_affine(),
+        gamma_g2: (g2 * *gamma).to_affine(),
+        delta_g2: (g2 * *delta).to_affine(),
+        ic: batch_g1(
+            &(0..public)
+                .map(|i| combined(i, &gamma_inverse))
+                .collect::<Vec<_>>(),
+        ),
+    };
+    let mut power = Zeroizing::new(z * *delta_inverse);
+    let h = (0..n.saturating_sub(1))
+        .map(|_| {
+            let point = g1 * *power;
+            *power *= *tau;
+            point
+        })
+        .collect::<Vec<_>>();
+
+    ProvingKey {
+        vk,
+        beta_g1: (g1 * *beta).to_affine(),
+        delta_g1: (g1 * *delta).to_affine(),
+        a: batch_g1(&u.iter().map(|u| g1 * u).collect::<Vec<_>>()),
+        b_g1: batch_g1(&v.iter().map(|v| g1 * v).collect::<Vec<_>>()),
+        b_g2: v.iter().map(|v| (g2 * v).to_affine()).collect(),
+        l: batch_g1(
+            &(public..variables)
+                .map(|i| combined(i, &delta_inverse))
+                .collect::<Vec<_>>(),
+        ),
+        h: batch_g1(&h),
+    }
+}
+
+/// Proves that the prover knows values satisfying the circuit of `pk` with
+/// the public inputs allocated in `cs`.
+pub fn prove<R: RngCore + CryptoRng>(
+    pk: &ProvingKey,
+    cs: &ConstraintSystem,
+    rng: &mut R,
+) -> Result<Proof, Groth16Error> {
+    cs.is_satisfied().map_err(Groth16Error::Unsatisfied)?;
+    let witness = cs.witness();
+    let z = std::iter::once(Scalar::one())
+        .chain(witness.inputs.iter().copied())
+        .chain(witness.aux.iter().copied())
+        .collect::<Vec<_>>();
+    let rows = Rows::new(cs);
+    if pk.vk.ic.len() != 1 + cs.num_inputs()
+        || pk.a.len() != z.len()
+        || pk.h.len() != rows.rows.len().saturating_sub(1)
+    {
+        return Err(Groth16Error::Shape);
+    }
+
+    // H(x) = (A(x) * B(x) - C(x)) / Z(x), exact since the constraints hold.
+    let value = |lc: &LinearCombination| lc.evaluate(|variable| z[rows.index(variable)]);
+    let vanishing = qap::vanishing(rows.rows.len());
+    let [a, b, c] = [0, 1, 2].map(|k| {
+        let values = rows
+            .rows
+            .iter()
+            .map(|row| value([&row.0, &row.1, &row.2][k]))
+            .collect::<Vec<_>>();
+        qap::interpolate(&values, &vanishing)
+    });
+    let mut p = poly::mul(&a, &b);
+    for (p, c) in p.iter_mut().zip(&c) {
+        *p -= c;
+    }
+    let h = poly::division::divide_exact(&p, &vanishing).ok_or(Groth16Error::Shape)?;
+
+    let r = Zeroizing::new(Scalar::random(&mut *rng));
+    let s = Zeroizing::new(Scalar::random(&mut *rng));
+    let delta_g1 = G1Projective::from(pk.delta_g1);
+    let proof_a = backend::g1_msm(&pk.a, &z) + pk.vk.alpha_g1 + delta_g1 * *r;
+    let proof_b = backend::g2_msm(&pk.b_g2, &z) + pk.vk.beta_g2 + pk.vk.delta_g2 * *s;
+    let b_g1 = backend::g1_msm(&pk.b_g1, &z) + pk.beta_g1 + delta_g1 * *s;
+    let proof_c = backend::g1_msm(&pk.l, &z[pk.vk.ic.len()..])
+        + backend::g1_msm(&pk.h, &h)
+        + proof_a * *s
+        + b_g1 * *r
+        - delta_g1 * (*r * *s);
+
+    Ok(Proof {
+        a: proof_a.to_affine(),
+        b: proof_b.to_affine(),
+        c: proof_c.to_affine(),
+    })
+}
+
+/// Checks a proof against the public inputs, in the order they were
+/// allocated.
+pub fn verify(vk: &VerifyingKey, inputs: &[Scalar], proof: &Proof) -> Result<(), Groth16Error> {
+    if inputs.len() + 1 != vk.ic.len() {
+        return Err(Groth16Error::Inputs {
+            expected: vk.ic.len().saturating_sub(1),
+            got: inputs.len(),
+        });
+    }
+    let ic = backend::g1_msm(&vk.ic[1..], inputs) + vk.ic[0];
+    let valid = pairing::pairing_product_is_one(&[
+        (proof.a, proof.b),
+        (-vk.alpha_g1, vk.beta_g2),
+        (-ic.to_affine(), vk.gamma_g2),
+        (-proof.c, vk.delta_g2),
+    ]);
+    if valid {
+        Ok(())
+    } else {
+        Err(Groth16Error::Invalid)
+    }
+}
+
+fn nonzero<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    loop {
+        let x = Scalar::random(&mut *rng);
+        if x != Scalar::zero() {
+            return x;
+        }
+    }
+}
+
+fn batch_g1(points: &[G1Projective]) -> Vec<G1Affine> {
+    let mut affine = vec![G1Affine::identity(); points.len()];
+    G1Projective::batch_normalize(points, &mut affine);
+    affine
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Groth16Error {
+    /// The values allocated don't satisfy the circuit.
+    Unsatisfied(R1csError),
+    /// The circuit isn't the one the key was generated for.
+    Shape,
+    Inputs {
+        expected: usize,
+        got: usize,
+    },
+    Invalid,
+}
+
+impl fmt::Display for Groth16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Groth16Error::Unsatisfied(e) => write!(f, "nothing to prove: {}", e),
+            Groth16Error::Shape => write!(f, "the key isn't for this circuit"),
+            Groth16Error::Inputs { expected, got } => {
+                write!(f, "the circuit has {} public inputs, got {}", expected, got)
+            }
+            Groth16Error::Invalid => write!(f, "invalid proof"),
+        }
+    }
+}
+
+impl std::error::Error for Groth16Error {}
@@ -0,0 +1,101 @@
+//! The R1CS as a quadratic arithmetic program: constraint j is the
+//! evaluation of the polynomials at the domain point `x_j = j + 1`, so the
+//! constraints all hold iff `A(x) * B(x) - C(x)` vanishes on the domain,
+//! that is iff `Z(x) = ∏ (x - x_j)` divides it.
+//!
+//! The domain is the integers rather than roots of unity, and everything is
+//! quadratic in the number of constraints: fine for the circuits of a lab.
+use bls12_381::Scalar;
+use poly::division;
+use r1cs::{ConstraintSystem, LinearCombination, Variable};
+
+/// The rows of the QAP: the constraints, then `z_i * 0 = 0` for the
+/// constant and each input, which keeps the polynomials of the public
+/// variables independent so the inputs can't be swapped for others.
+pub struct Rows {
+    pub rows: Vec<(LinearCombination, LinearCombination, LinearCombination)>,
+    pub inputs: usize,
+}
+
+impl Rows {
+    pub fn new(cs: &ConstraintSystem) -> Self {
+        let mut rows = cs
+            .constraints()
+            .iter()
+            .map(|constraint| {
+                (
+                    constraint.a.clone(),
+                    constraint.b.clone(),
+                    constraint.c.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let public =
+            std::iter::once(Variable::One).chain((0..cs.num_inputs()).map(Variable::Input));
+        for variable in public {
+            rows.push((
+                variable.into(),
+                LinearCombination::zero(),
+                LinearCombination::zero(),
+            ));
+        }
+        Rows {
+            rows,
+            inputs: cs.num_inputs(),
+        }
+    }
+
+    /// The index of `variable` in `z = [1, inputs, aux]`.
+    pub fn index(&self, variable: Variable) -> usize {
+        match variable {
+            Variable::One => 0,
+            Variable::Input(i) => 1 + i,
+            Variable::Aux(i) => 1 + self.inputs + i,
+        }
+    }
+}
+
+/// The domain point of row `j`.
+pub fn point(j: usize) -> Scalar {
+    Scalar::from(j as u64 + 1)
+}
+
+/// The coefficients of `Z(x)` for a domain of `n` points, lowest first.
+pub fn vanishing(n: usize) -> Vec<Scalar> {
+    division::vanishing(&(0..n).map(point).collect::<Vec<_>>())
+}
+
+/// `L_j(τ)` for every row j, and `Z(τ)`. τ must be outside the domain.
+pub fn lagrange_at(n: usize, tau: Scalar) -> (Vec<Scalar>, Scalar) {
+    let z = (0..n).fold(Scalar::one(), |acc, j| acc * (tau - point(j)));
+    let basis = (0..n)
+        .map(|j| {
+            let denominator = (0..n)
+                .filter(|k| *k != j)
+                .fold(tau - point(j), |acc, k| acc * (point(j) - point(k)));
+            z * denominator.invert().unwrap()
+        })
+        .collect();
+    (basis, z)
+}
+
+/// The polynomial taking `values[j]` at the point of row j.
+pub fn interpolate(values: &[Scalar], vanishing: &[Scalar]) -> Vec<Scalar> {
+    let n = values.len();
+    let mut result = vec![Scalar::zero(); n];
+    for (j, value) in values.iter().enumerate() {
+        if *value == Scalar::zero() {
+            continue;
+        }
+        // L_j(x) = Z(x) / (x - x_j) / ∏ (x_j - x_k)
+        let (quotient, _) = division::divide_by_linear(vanishing, point(j));
+        let denominator = (0..n)
+            .filter(|k| *k != j)
+            .fold(Scalar::one(), |acc, k| acc * (point(j) - point(k)));
+        let scale = value * denominator.invert().unwrap();
+        for (r, q) in result.iter_mut().zip(&quotient) {
+            *r += q * scale;
+        }
+    }
+    result
+}
@@ -0,0 +1,82 @@
+use bls12_381::Scalar;
+use groth16::example::{hash, preimage_circuit};
+use groth16::{prove, setup, verify, Groth16Error, Proof};
+use r1cs::{ConstraintSystem, LinearCombination, R1csError, Variable};
+use rand::thread_rng;
+
+/// `x³ + x + 5 = out`, with `out` public.
+fn cubic(x: u64) -> ConstraintSystem {
+    let mut cs = ConstraintSystem::new();
+    let x_value = Scalar::from(x);
+    let out = cs.alloc_input(x_value * x_value * x_value + x_value + Scalar::from(5));
+    let x = cs.alloc(x_value);
+    let x_squared = cs.mul("x * x", x, x);
+    let x_cubed = cs.mul("x² * x", x_squared, x);
+    cs.enforce(
+        "x³ + x + 5 = out",
+        x_cubed + x + LinearCombination::constant(Scalar::from(5)),
+        Variable::One,
+        out,
+    );
+    cs
+}
+
+#[test]
+fn proves_the_cubic() {
+    let mut rng = thread_rng();
+    // The setup only looks at the shape.
+    let pk = setup(&cubic(0), &mut rng);
+    let proof = prove(&pk, &cubic(3), &mut rng).unwrap();
+    verify(&pk.vk, &[Scalar::from(35)], &proof).unwrap();
+
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(36)], &proof),
+        Err(Groth16Error::Invalid)
+    );
+    assert_eq!(
+        verify(&pk.vk, &[], &proof),
+        Err(Groth16Error::Inputs {
+            expected: 1,
+            got: 0
+        })
+    );
+}
+
+#[test]
+fn proves_knowledge_of_a_preimage() {
+    let mut rng = thread_rng();
+    let pk = setup(&preimage_circuit(Scalar::zero(), Scalar::zero()), &mut rng);
+
+    let preimage = Scalar::from(0x5eed);
+    let digest = hash(preimage);
+    let proof = prove(&pk, &preimage_circuit(preimage, digest), &mut rng).unwrap();
+    verify(&pk.vk, &[digest], &proof).unwrap();
+    assert_eq!(
+        verify(&pk.vk, &[hash(preimage + Scalar::one())], &proof),
+        Err(Groth16Error::Invalid)
+    );
+
+    let proof = Proof::from_bytes(&proof.to_bytes()).unwrap();
+    verify(&pk.vk, &[digest], &proof).unwrap();
+}
+
+#[test]
+fn refuses_to_prove_false_statements() {
+    let mut rng = thread_rng();
+    let pk = setup(&preimage_circuit(Scalar::zero(), Scalar::zero()), &mut rng);
+    let cs = preimage_circuit(Scalar::from(1), Scalar::from(2));
+    assert!(matches!(
+        prove(&pk, &cs, &mut rng),
+        Err(Groth16Error::Unsatisfied(R1csError::Unsatisfied { .. }))
+    ));
+    assert_eq!(prove(&pk, &cubic(3), &mut rng), Err(Groth16Error::Shape));
+}
+
+#[test]
+fn proofs_are_rerandomized() {
+    let mut rng = thread_rng();
+    let pk = setup(&cubic(0), &mut rng);
+    let first = prove(&pk, &cubic(3), &mut rng).unwrap();
+    let second = prove(&pk, &cubic(3), &mut rng).unwrap();
+    assert_ne!(first, second);
+}
@@ -0,0 +1,262 @@
+//! Nguyen's bilinear accumulator: a set of scalars `{a_i}` is accumulated
+//! into the commitment to the polynomial that vanishes on their negations,
+//!
+//! V = f(τ) * G1, f(x) = ∏ (x + a_i)
+//!
+//! a single point whatever the size of the set. The witness that a is in
+//! the set is the commitment `W = (f(τ) / (τ + a)) * G1` to the quotient,
+//! which only exists if `x + a` divides f, and anyone checks
+//!
+//! e(W, τ * G2 + a * G2) == e(V, G2)
+//!
+//! The witness that y isn't in the set is the quotient and the remainder of
+//! f by `x + y`, `W = q(τ) * G1` and `r = f(-y)`, and the check is
+//!
+//! e(W, τ * G2 + y * G2) * e(r * G1, G2) == e(V, G2)
+//!
+//! with `r ≠ 0`, as a remainder of 0 would mean that y is a root.
+//!
+//! The manager of the set adds and removes elements with the setup's
+//! powers, and publishes an [`Update`] with each change, from which the
+//! holders of witnesses update theirs without the powers: after a is added,
+//! `W' = V + (a - x) * W` for the witness W of x, and after a is removed,
+//! `W' = (W - V') / (a - x)` for the new value V'. The remainder of a
+//! non-membership witness is multiplied, or divided, by `a - x` too.
+use crate::{poly, KzgError, Srs};
+use bls12_381::{G1Affine, G1Projective, G2Affine, Scalar};
+use group::Curve;
+use pairing::pairing_product_is_one;
+use std::fmt;
+
+/// The set as its manager keeps it, with the polynomial that vanishes on
+/// the negations of its elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accumulator {
+    elements: Vec<Scalar>,
+    poly: Vec<Scalar>,
+    value: G1Affine,
+}
+
+/// That `element` is in the set accumulated in a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MembershipWitness {
+    pub element: Scalar,
+    pub witness: G1Affine,
+}
+
+/// That `element` isn't in the set accumulated in a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonMembershipWitness {
+    pub element: Scalar,
+    pub witness: G1Affine,
+    pub remainder: Scalar,
+}
+
+/// What the manager publishes when the set changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    /// `element` was added to the set, which had the value `previous`.
+    Added { element: Scalar, previous: G1Affine },
+    /// `element` was removed from the set, which now has the value `value`.
+    Removed { element: Scalar, value: G1Affine },
+}
+
+impl Accumulator {
+    /// Accumulates the elements, which need a setup of their number's
+    /// degree.
+    pub fn new(srs: &Srs, elements: &[Scalar]) -> Result<Self, AccumulatorError> {
+        for (i, a) in elements.iter().enumerate() {
+            if elements[..i].contains(a) {
+                return Err(AccumulatorError::Member);
+            }
+        }
+        let negated = elements.iter().map(|a| -a).collect::<Vec<_>>();
+        let poly = poly::vanishing(&negated);
+        let value = srs.commit(&poly)?.0;
+        Ok(Accumulator {
+            elements: elements.to_vec(),
+            poly,
+            value,
+        })
+    }
+
+    /// `V = f(τ) * G1`
+    pub fn value(&self) -> G1Affine {
+        self.value
+    }
+
+    pub fn elements(&self) -> &[Scalar] {
+        &self.elements
+    }
+
+    pub fn contains(&self, element: &Scalar) -> bool {
+        self.elements.contains(element)
+    }
+
+    /// Adds the element, `f(x) * (x + a)`.
+    pub fn add(&mut self, srs: &Srs, element: Scalar) -> Result<Update, AccumulatorError> {
+        if self.contains(&element) {
+            return Err(AccumulatorError::Member);
+        }
+        let mut poly = vec![Scalar::zero(); self.poly.len() + 1];
+        for (i, c) in self.poly.iter().enumerate() {
+            poly[i] += c * element;
+            poly[i + 1] += c;
+        }
+        let value = srs.commit(&poly)?.0;
+        let previous = std::mem::replace(&mut self.value, value);
+        self.poly = poly;
+        self.elements.push(element);
+        Ok(Update::Added { element, previous })
+    }
+
+    /// Removes the element, `f(x) / (x + a)`.
+    pub fn remove(&mut self, srs: &Srs, element: Scalar) -> Result<Update, AccumulatorError> {
+        let index = self
+            .elements
+            .iter()
+            .position(|a| *a == element)
+            .ok_or(AccumulatorError::NotMember)?;
+        let (poly, _) = poly::divide_by_linear(&self.poly, -element);
+        self.value = srs.commit(&poly)?.0;
+        self.poly = poly;
+        self.elements.swap_remove(index);
+        Ok(Update::Removed {
+            element,
+            value: self.value,
+        })
+    }
+
+    /// The witness that the element is in the set.
+    pub fn membership_witness(
+        &self,
+        srs: &Srs,
+        element: Scalar,
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        if !self.contains(&element) {
+            return Err(AccumulatorError::NotMember);
+        }
+        let (quotient, _) = poly::divide_by_linear(&self.poly, -element);
+        Ok(MembershipWitness {
+            element,
+            witness: srs.commit(&quotient)?.0,
+        })
+    }
+
+    /// The witness that the element isn't in the set.
+    pub fn non_membership_witness(
+        &self,
+        srs: &Srs,
+        element: Scalar,
+    ) -> Result<NonMembershipWitness, AccumulatorError> {
+        if self.contains(&element) {
+            return Err(AccumulatorError::Member);
+        }
+        let (quotient, remainder) = poly::divide_by_linear(&self.poly, -element);
+        Ok(NonMembershipWitness {
+            element,
+            witness: srs.commit(&quotient)?.0,
+            remainder,
+        })
+    }
+}
+
+impl MembershipWitness {
+    /// Follows a change of the set, fails if the element was removed.
+    pub fn update(&mut self, update: &Update) -> Result<(), AccumulatorError> {
+        let (witness, _) = updated(self.element, self.witness, update)?;
+        self.witness = witness;
+        Ok(())
+    }
+}
+
+impl NonMembershipWitness {
+    /// Follows a change of the set, fails if the element was added.
+    pub fn update(&mut self, update: &Update) -> Result<(), AccumulatorError> {
+        let (witness, factor) = updated(self.element, self.witness, update)?;
+        self.witness = witness;
+        self.remainder *= factor;
+        Ok(())
+    }
+}
+
+/// The witness of x after the update, and what its remainder is multiplied
+/// by: `a - x` for an addition and its inverse for a removal.
+fn updated(
+    x: Scalar,
+    witness: G1Affine,
+    update: &Update,
+) -> Result<(G1Affine, Scalar), AccumulatorError> {
+    match *update {
+        Update::Added { element, previous } if element != x => {
+            let factor = element - x;
+            Ok(((previous + witness * factor).to_affine(), factor))
+        }
+        Update::Removed { element, value } if element != x => {
+            let factor = (element - x).invert().unwrap();
+            Ok((
+                ((G1Projective::from(witness) - value) * factor).to_affine(),
+                factor,
+            ))
+        }
+        Update::Added { .. } => Err(AccumulatorError::Member),
+        Update::Removed { .. } => Err(AccumulatorError::NotMember),
+    }
+}
+
+impl Srs {
+    /// Checks that the element of the witness is in the set accumulated in
+    /// `value`.
+    pub fn verify_membership(&self, value: &G1Affine, witness: &MembershipWitness) -> bool {
+        // e(W, τ * G2 + a * G2) * e(-V, G2) == 1
+        pairing_product_is_one(&[
+            (witness.witness, self.shifted(witness.element)),
+            (-value, G2Affine::generator()),
+        ])
+    }
+
+    /// Checks that the element of the witness isn't in the set accumulated
+    /// in `value`.
+    pub fn verify_non_membership(&self, value: &G1Affine, witness: &NonMembershipWitness) -> bool {
+        // e(W, τ * G2 + y * G2) * e(r * G1 - V, G2) == 1
+        let rest = (G1Affine::generator() * witness.remainder - value).to_affine();
+        witness.remainder != Scalar::zero()
+            && pairing_product_is_one(&[
+                (witness.witness, self.shifted(witness.element)),
+                (rest, G2Affine::generator()),
+            ])
+    }
+
+    /// `τ * G2 + a * G2`
+    fn shifted(&self, a: Scalar) -> G2Affine {
+        (self.g2_powers[1] + G2Affine::generator() * a).to_affine()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccumulatorError {
+    /// The element is already in the set.
+    Member,
+    /// The element isn't in the set.
+    NotMember,
+    /// The set outgrew the setup.
+    Kzg(KzgError),
+}
+
+impl fmt::Display for AccumulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccumulatorError::Member => write!(f, "the element is in the set"),
+            AccumulatorError::NotMember => write!(f, "the element isn't in the set"),
+            AccumulatorError::Kzg(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AccumulatorError {}
+
+impl From<KzgError> for AccumulatorError {
+    fn from(e: KzgError) -> Self {
+        AccumulatorError::Kzg(e)
+    }
+}
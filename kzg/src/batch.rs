@@ -0,0 +1,303 @@
+//! Proving several evaluations with a single proof, checked with a single
+//! pairing product:
+//!
+//! - Several polynomials at one point: after the challenge γ, open
+//!   `∑ γ^i * f_i` at z. Its commitment is `∑ γ^i * C_i`, so the verifier
+//!   folds the commitments and values the same way and checks one opening.
+//! - One polynomial at several points: with `I(x)` the polynomial through
+//!   the claimed evaluations and `Z(x) = ∏ (x - z_j)` vanishing on the
+//!   points, `f(x) - I(x)` is divisible by `Z(x)`. The proof is the
+//!   commitment `π` to the quotient and the check is
+//!   `e(C - I(τ) * G1, G2) == e(π, Z(τ) * G2)`, which needs the powers of
+//!   τ in G2 up to the number of points.
+//! - Several polynomials, each at its own points, as in SHPLONK (Boneh,
+//!   Drake, Fisch and Gabizon): two points in G1 whatever the number of
+//!   polynomials and points, only needing `τ * G2`. See
+//!   [`Srs::open_combined`].
+//!
+//! The challenges are derived from everything the verifier is given, in
+//! the order it is given, so the prover can't pick them.
+use crate::{poly, Commitment, KzgError, Proof, Srs};
+use bls12_381::{G1Affine, G1Projective, G2Affine, Scalar};
+use bls_shamir::backend;
+use group::Curve;
+use pairing::pairing_product_is_one;
+use sigma::transcript::Transcript;
+
+const BATCH_DOMAIN: &[u8] = b"zk-lab/kzg/batch";
+const COMBINED_DOMAIN: &[u8] = b"zk-lab/kzg/combined";
+
+/// The claim that a committed polynomial takes `values[j]` at `points[j]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluations {
+    pub commitment: Commitment,
+    pub points: Vec<Scalar>,
+    pub values: Vec<Scalar>,
+}
+
+/// A proof for any number of [`Evaluations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CombinedProof {
+    /// The commitment W to `h(x) = ∑ γ^i * (f_i(x) - r_i(x)) / Z_i(x)`.
+    pub quotient: G1Affine,
+    /// The opening of the linearized `L(x)` at the second challenge.
+    pub opening: G1Affine,
+}
+
+impl Srs {
+    /// Opens every polynomial at `z`, returns their values and the proof of
+    /// all of them.
+    pub fn open_batch(
+        &self,
+        polys: &[Vec<Scalar>],
+        z: Scalar,
+    ) -> Result<(Vec<Scalar>, Proof), KzgError> {
+        let commitments = polys
+            .iter()
+            .map(|poly| self.commit(poly))
+            .collect::<Result<Vec<_>, _>>()?;
+        let values = polys
+            .iter()
+            .map(|poly| poly::evaluate(poly, z))
+            .collect::<Vec<_>>();
+
+        let gamma = batch_challenge(&commitments, z, &values);
+        let mut folded = Vec::new();
+        for (poly, power) in polys.iter().zip(powers(gamma)) {
+            poly::add_scaled(&mut folded, poly, power);
+        }
+        let (_, proof) = self.open(&folded, z)?;
+        Ok((values, proof))
+    }
+
+    /// Checks that the polynomial of `commitments[i]` evaluates to
+    /// `values[i]` at `z`.
+    pub fn verify_batch(
+        &self,
+        commitments: &[Commitment],
+        z: Scalar,
+        values: &[Scalar],
+        proof: &Proof,
+    ) -> bool {
+        if commitments.len() != values.len() {
+            return false;
+        }
+        let gamma = batch_challenge(commitments, z, values);
+        let (commitment, value) = commitments.iter().zip(values).zip(powers(gamma)).fold(
+            (G1Projective::identity(), Scalar::zero()),
+            |(c, y), ((ci, yi), power)| (c + ci.0 * power, y + yi * power),
+        );
+        self.verify(&Commitment(commitment.to_affine()), z, value, proof)
+    }
+
+    /// Opens the polynomial at every point, returns its values and the
+    /// proof of all of them.
+    pub fn open_multi(
+        &self,
+        poly: &[Scalar],
+        points: &[Scalar],
+    ) -> Result<(Vec<Scalar>, Proof), KzgError> {
+        self.check_degree(poly)?;
+        self.check_points(points)?;
+        let values = points
+            .iter()
+            .map(|z| poly::evaluate(poly, *z))
+            .collect::<Vec<_>>();
+        let interpolated = poly::interpolate(points, &values).ok_or(KzgError::DuplicatePoint)?;
+        let quotient = poly::divide_vanishing(&poly::sub(poly, &interpolated), points);
+        Ok((values, Proof(self.combine(&quotient))))
+    }
+
+    /// Checks that the polynomial of `commitment` evaluates to `values[j]`
+    /// at `points[j]`.
+    pub fn verify_multi(
+        &self,
+        commitment: &Commitment,
+        points: &[Scalar],
+        values: &[Scalar],
+        proof: &Proof,
+    ) -> bool {
+        if points.len() != values.len() || self.check_points(points).is_err() {
+            return false;
+        }
+        let interpolated = match poly::interpolate(points, values) {
+            Some(interpolated) => interpolated,
+            None => return false,
+        };
+
+        // e(C - I(τ) * G1, G2) * e(-π, Z(τ) * G2) == 1
+        let lhs = (G1Projective::from(commitment.0) - self.combine(&interpolated)).to_affine();
+        let vanishing = self.combine_g2(&poly::vanishing(points));
+        pairing_product_is_one(&[(lhs, G2Affine::generator()), (-proof.0, vanishing)])
+    }
+
+    /// Opens each of `polys[i]` at its `points[i]` with a single proof.
+    ///
+    /// With `T` all the points, `S_i` those of `f_i`, `r_i` the polynomial
+    /// through the evaluations of `f_i` on `S_i` and `Z_S` vanishing on `S`:
+    ///
+    /// 1. After the challenge γ, commit to
+    ///    `h(x) = ∑ γ^i * (f_i(x) - r_i(x)) / Z_{S_i}(x)` as W.
+    /// 2. After the challenge z, which depends on W, the polynomial
+    ///    `L(x) = ∑ γ^i * Z_{T \ S_i}(z) * (f_i(x) - r_i(z)) - Z_T(z) * h(x)`
+    ///    vanishes at z, open it there as W'.
+    ///
+    /// The verifier computes the commitment to L from W and the `C_i`, and
+    /// checks the opening of it as a single point opening of `L(z) = 0`.
+    pub fn open_combined(
+        &self,
+        polys: &[Vec<Scalar>],
+        points: &[Vec<Scalar>],
+    ) -> Result<(Vec<Evaluations>, CombinedProof), KzgError> {
+        let claims = polys
+            .iter()
+            .zip(points)
+            .map(|(poly, points)| {
+                Ok(Evaluations {
+                    commitment: self.commit(poly)?,
+                    points: points.clone(),
+                    values: points.iter().map(|z| poly::evaluate(poly, *z)).collect(),
+                })
+            })
+            .collect::<Result<Vec<_>, KzgError>>()?;
+        let interpolated = claims
+            .iter()
+            .map(|claim| {
+                poly::interpolate(&claim.points, &claim.values).ok_or(KzgError::DuplicatePoint)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut transcript = combined_transcript(&claims);
+        let gamma = transcript.challenge_scalar(b"gamma");
+        let mut h = Vec::new();
+        for ((poly, r), (claim, power)) in polys
+            .iter()
+            .zip(&interpolated)
+            .zip(claims.iter().zip(powers(gamma)))
+        {
+            let quotient = poly::divide_vanishing(&poly::sub(poly, r), &claim.points);
+            poly::add_scaled(&mut h, &quotient, power);
+        }
+        let quotient = self.combine(&h);
+
+        transcript.append_point(b"quotient", &quotient);
+        let z = transcript.challenge_scalar(b"z");
+        let all = union(&claims);
+        let mut l = Vec::new();
+        for ((poly, r), (claim, power)) in polys
+            .iter()
+            .zip(&interpolated)
+            .zip(claims.iter().zip(powers(gamma)))
+        {
+            let scale = power * vanishing_outside(&all, &claim.points, z);
+            poly::add_scaled(&mut l, &poly::sub(poly, &[poly::evaluate(r, z)]), scale);
+        }
+        poly::add_scaled(&mut l, &h, -poly::evaluate(&poly::vanishing(&all), z));
+        let (opening, _) = poly::divide_by_linear(&l, z);
+
+        Ok((
+            claims,
+            CombinedProof {
+                quotient,
+                opening: self.combine(&opening),
+            },
+        ))
+    }
+
+    /// Checks every claim against the proof.
+    pub fn verify_combined(&self, claims: &[Evaluations], proof: &CombinedProof) -> bool {
+        let mut transcript = combined_transcript(claims);
+        let gamma = transcript.challenge_scalar(b"gamma");
+        transcript.append_point(b"quotient", &proof.quotient);
+        let z = transcript.challenge_scalar(b"z");
+
+        // F = ∑ γ^i * Z_{T \ S_i}(z) * (C_i - r_i(z) * G1) - Z_T(z) * W
+        let all = union(claims);
+        let mut f = G1Projective::identity();
+        for (claim, power) in claims.iter().zip(powers(gamma)) {
+            if claim.points.len() != claim.values.len() {
+                return false;
+            }
+            let r = match poly::interpolate(&claim.points, &claim.values) {
+                Some(r) => r,
+                None => return false,
+            };
+            let scale = power * vanishing_outside(&all, &claim.points, z);
+            let shifted = claim.commitment.0 - G1Affine::generator() * poly::evaluate(&r, z);
+            f += shifted * scale;
+        }
+        f -= proof.quotient * poly::evaluate(&poly::vanishing(&all), z);
+
+        // e(F + z * W', G2) * e(-W', τ * G2) == 1
+        let lhs = (f + proof.opening * z).to_affine();
+        pairing_product_is_one(&[
+            (lhs, G2Affine::generator()),
+            (-proof.opening, self.g2_powers[1]),
+        ])
+    }
+
+    fn check_points(&self, points: &[Scalar]) -> Result<(), KzgError> {
+        // Z(x) has a degree of the number of points.
+        if points.len() >= self.g2_powers.len() {
+            return Err(KzgError::Points {
+                count: points.len(),
+                max: self.g2_powers.len() - 1,
+            });
+        }
+        Ok(())
+    }
+
+    /// `∑ c_i * (τ^i * G2)`, for a polynomial within the setup's G2 powers.
+    fn combine_g2(&self, poly: &[Scalar]) -> G2Affine {
+        backend::g2_msm(&self.g2_powers, poly).to_affine()
+    }
+}
+
+/// `1, γ, γ^2, ...`
+fn powers(gamma: Scalar) -> impl Iterator<Item = Scalar> {
+    std::iter::successors(Some(Scalar::one()), move |power| Some(power * gamma))
+}
+
+/// Every point of the claims, once.
+fn union(claims: &[Evaluations]) -> Vec<Scalar> {
+    let mut all = Vec::new();
+    for z in claims.iter().flat_map(|claim| &claim.points) {
+        if !all.contains(z) {
+            all.push(*z);
+        }
+    }
+    all
+}
+
+/// `Z_{T \ S}(z)`, the product of `z - t` over the points of `all` not in
+/// `points`.
+fn vanishing_outside(all: &[Scalar], points: &[Scalar], z: Scalar) -> Scalar {
+    all.iter()
+        .filter(|t| !points.contains(t))
+        .fold(Scalar::one(), |acc, t| acc * (z - t))
+}
+
+fn batch_challenge(commitments: &[Commitment], z: Scalar, values: &[Scalar]) -> Scalar {
+    let mut transcript = Transcript::new(BATCH_DOMAIN);
+    transcript.append_u64(b"count", commitments.len() as u64);
+    for (commitment, value) in commitments.iter().zip(values) {
+        transcript.append_point(b"commitment", &commitment.0);
+        transcript.append_scalar(b"value", value);
+    }
+    transcript.append_scalar(b"z", &z);
+    transcript.challenge_scalar(b"gamma")
+}
+
+fn combined_transcript(claims: &[Evaluations]) -> Transcript {
+    let mut transcript = Transcript::new(COMBINED_DOMAIN);
+    transcript.append_u64(b"claims", claims.len() as u64);
+    for claim in claims {
+        transcript.append_point(b"commitment", &claim.commitment.0);
+        transcript.append_u64(b"points", claim.points.len() as u64);
+        for (z, y) in claim.points.iter().zip(&claim.values) {
+            transcript.append_scalar(b"point", z);
+            transcript.append_scalar(b"value", y);
+        }
+    }
+    transcript
+}
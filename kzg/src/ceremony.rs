@@ -0,0 +1,252 @@
+//! A powers-of-tau ceremony: the parties take turns multiplying the τ of
+//! the SRS by a secret of their own, so the final τ is the product of all
+//! their secrets and stays unknown unless every one of them colludes.
+//!
+//! The ceremony starts from [`Srs::initial`], where τ is 1. A party with the
+//! secret s turns `τ^i * G` into `s^i * τ^i * G` and publishes an
+//! [`UpdateProof`] with it: `s * G1` and `s * G2`, and a Schnorr proof of
+//! knowledge of s so that nobody can publish an update they can't account
+//! for. Everyone checks that
+//!
+//! - the new SRS is well formed, its points being the powers of one τ' in
+//!   both groups,
+//! - `e(s * G1, G2) == e(G1, s * G2)`, the same s in both groups,
+//! - `e(τ' * G1, G2) == e(τ * G1, s * G2)`, so `τ' = s * τ`.
+//!
+//! The powers are checked together, with a random linear combination of the
+//! pairs of consecutive powers, in two pairing products whatever the degree.
+use crate::Srs;
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use pairing::pairing_product_is_one;
+use rand_core::{CryptoRng, RngCore};
+use sigma::schnorr;
+use std::fmt;
+use zeroize::Zeroizing;
+
+const POK_CONTEXT: &[u8] = b"zk-lab/kzg/ceremony";
+
+/// What a party publishes along with the SRS it updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateProof {
+    /// `s * G1`, s being the party's secret.
+    pub s_g1: G1Affine,
+    /// `s * G2`.
+    pub s_g2: G2Affine,
+    /// The Schnorr proof of knowledge of s.
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+impl UpdateProof {
+    pub const SIZE: usize = 48 + 96 + 32 + 32;
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..48].copy_from_slice(&self.s_g1.to_compressed());
+        bytes[48..144].copy_from_slice(&self.s_g2.to_compressed());
+        bytes[144..176].copy_from_slice(&self.challenge.to_bytes());
+        bytes[176..].copy_from_slice(&self.response.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: &[u8; Self::SIZE] = bytes.try_into().ok()?;
+        let s_g1 = G1Affine::from_compressed(bytes[..48].try_into().expect("48 bytes"));
+        let s_g2 = G2Affine::from_compressed(bytes[48..144].try_into().expect("96 bytes"));
+        let challenge = Scalar::from_bytes(bytes[144..176].try_into().expect("32 bytes"));
+        let response = Scalar::from_bytes(bytes[176..].try_into().expect("32 bytes"));
+        Some(UpdateProof {
+            s_g1: Option::from(s_g1)?,
+            s_g2: Option::from(s_g2)?,
+            challenge: Option::from(challenge)?,
+            response: Option::from(response)?,
+        })
+    }
+}
+
+impl Srs {
+    /// Where a ceremony starts, τ being 1 so every power is the generator.
+    /// The degree is at least 1, updates are checked on `τ * G1`.
+    pub fn initial(max_degree: usize) -> Self {
+        let count = max_degree.max(1) + 1;
+        Srs {
+            powers: vec![G1Affine::generator(); count],
+            g2_powers: vec![G2Affine::generator(); count],
+        }
+    }
+
+    /// Multiplies τ by a fresh secret, which is forgotten once the SRS is
+    /// updated.
+    pub fn contribute<R: RngCore + CryptoRng>(&self, rng: &mut R) -> (Srs, UpdateProof) {
+        let s = Zeroizing::new(Scalar::random(&mut *rng));
+        let mut power = Zeroizing::new(Scalar::one());
+        let mut powers = Vec::with_capacity(self.powers.len());
+        let mut g2_powers = Vec::with_capacity(self.g2_powers.len());
+        for i in 0..self.powers.len().max(self.g2_powers.len()) {
+            if let Some(point) = self.powers.get(i) {
+                powers.push(point * *power);
+            }
+            if let Some(point) = self.g2_powers.get(i) {
+                g2_powers.push(point * *power);
+            }
+            *power *= *s;
+        }
+        let next = Srs {
+            powers: normalize_g1(&powers),
+            g2_powers: normalize_g2(&g2_powers),
+        };
+
+        let pok = schnorr::prove::<G1Projective, _>(
+            &s,
+            &pok_context(&self.powers[1], &next.powers[1]),
+            rng,
+        );
+        let proof = UpdateProof {
+            s_g1: (G1Affine::generator() * *s).to_affine(),
+            s_g2: (G2Affine::generator() * *s).to_affine(),
+            challenge: pok.challenge,
+            response: pok.response,
+        };
+        (next, proof)
+    }
+
+    /// Checks that `next` is this SRS updated by the party of `proof`.
+    pub fn verify_update<R: RngCore + CryptoRng>(
+        &self,
+        next: &Srs,
+        proof: &UpdateProof,
+        rng: &mut R,
+    ) -> Result<(), UpdateError> {
+        if next.powers.len() != self.powers.len() || next.g2_powers.len() != self.g2_powers.len() {
+            return Err(UpdateError::Shape);
+        }
+        if !next.is_well_formed(rng) {
+            return Err(UpdateError::Malformed);
+        }
+        if bool::from(proof.s_g1.is_identity()) {
+            return Err(UpdateError::Identity);
+        }
+
+        let pok = sigma::Proof {
+            challenge: proof.challenge,
+            response: proof.response,
+        };
+        let context = pok_context(&self.powers[1], &next.powers[1]);
+        if !schnorr::verify(&G1Projective::from(proof.s_g1), &context, &pok) {
+            return Err(UpdateError::ProofOfKnowledge);
+        }
+
+        // e(s * G1, G2) * e(-G1, s * G2) == 1
+        let same_secret = pairing_product_is_one(&[
+            (proof.s_g1, G2Affine::generator()),
+            (-G1Affine::generator(), proof.s_g2),
+        ]);
+        // e(τ' * G1, G2) * e(-τ * G1, s * G2) == 1
+        let updated = pairing_product_is_one(&[
+            (next.powers[1], G2Affine::generator()),
+            (-self.powers[1], proof.s_g2),
+        ]);
+        if !same_secret || !updated {
+            return Err(UpdateError::NotAnUpdate);
+        }
+        Ok(())
+    }
+
+    /// Whether the points are the powers of one nonzero τ, starting from the
+    /// generators.
+    pub fn is_well_formed<R: RngCore + CryptoRng>(&self, rng: &mut R) -> bool {
+        if self.powers.len() < 2
+            || self.g2_powers.len() < 2
+            || self.powers[0] != G1Affine::generator()
+            || self.g2_powers[0] != G2Affine::generator()
+            || bool::from(self.powers[1].is_identity())
+        {
+            return false;
+        }
+        let tau_g1 = self.powers[1];
+        let tau_g2 = self.g2_powers[1];
+
+        // With random r_i, ∑ r_i * P_{i+1} == τ * ∑ r_i * P_i only holds for
+        // every pair at once, but with negligible chance.
+        let mut next_g1 = G1Projective::identity();
+        let mut previous_g1 = G1Projective::identity();
+        for pair in self.powers.windows(2) {
+            let r = Scalar::random(&mut *rng);
+            next_g1 += pair[1] * r;
+            previous_g1 += pair[0] * r;
+        }
+        let mut next_g2 = G2Projective::identity();
+        let mut previous_g2 = G2Projective::identity();
+        for pair in self.g2_powers.windows(2) {
+            let r = Scalar::random(&mut *rng);
+            next_g2 += pair[1] * r;
+            previous_g2 += pair[0] * r;
+        }
+
+        // e(∑ r_i * P_{i+1}, G2) * e(-∑ r_i * P_i, τ * G2) == 1
+        let g1_powers = pairing_product_is_one(&[
+            (next_g1.to_affine(), G2Affine::generator()),
+            (-previous_g1.to_affine(), tau_g2),
+        ]);
+        // e(G1, ∑ r_i * Q_{i+1}) * e(-τ * G1, ∑ r_i * Q_i) == 1
+        let g2_powers = pairing_product_is_one(&[
+            (G1Affine::generator(), next_g2.to_affine()),
+            (-tau_g1, previous_g2.to_affine()),
+        ]);
+        g1_powers && g2_powers
+    }
+}
+
+fn normalize_g1(points: &[G1Projective]) -> Vec<G1Affine> {
+    let mut affine = vec![G1Affine::identity(); points.len()];
+    G1Projective::batch_normalize(points, &mut affine);
+    affine
+}
+
+fn normalize_g2(points: &[G2Projective]) -> Vec<G2Affine> {
+    let mut affine = vec![G2Affine::identity(); points.len()];
+    G2Projective::batch_normalize(points, &mut affine);
+    affine
+}
+
+/// Binds the proof to the update it is for, so it can't be carried over to
+/// another.
+fn pok_context(previous: &G1Affine, next: &G1Affine) -> Vec<u8> {
+    let mut context = POK_CONTEXT.to_vec();
+    context.extend_from_slice(&previous.to_compressed());
+    context.extend_from_slice(&next.to_compressed());
+    context
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// The updated SRS doesn't have as many powers as the one it updates.
+    Shape,
+    /// The updated SRS isn't made of the powers of one τ.
+    Malformed,
+    /// A secret of 0 would wipe out every contribution before it.
+    Identity,
+    ProofOfKnowledge,
+    /// The updated τ isn't the previous one times the secret of the proof.
+    NotAnUpdate,
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Shape => write!(f, "the update changes the size of the SRS"),
+            UpdateError::Malformed => write!(f, "the updated SRS isn't made of powers of one τ"),
+            UpdateError::Identity => write!(f, "the update's secret is 0"),
+            UpdateError::ProofOfKnowledge => {
+                write!(f, "invalid proof of knowledge of the update's secret")
+            }
+            UpdateError::NotAnUpdate => {
+                write!(f, "the updated SRS doesn't follow from the update's secret")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {}
@@ -0,0 +1,456 @@
+//! The SRS on disk, for setups too large to be read in one go.
+//!
+//! A file is a 64 byte header followed by the powers in G1, then in G2:
+//!
+//! | bytes  | field                                           |
+//! |--------|-------------------------------------------------|
+//! | 0..8   | [`MAGIC`]                                       |
+//! | 8..10  | [`VERSION`], big-endian like every number here  |
+//! | 10     | the [`Encoding`] of the points                  |
+//! | 11     | 0                                               |
+//! | 12..16 | the number of points in a chunk                 |
+//! | 16..24 | the number of powers in G1                      |
+//! | 24..32 | the number of powers in G2                      |
+//! | 32..64 | the SHA-256 of the 32 bytes before              |
+//!
+//! Each section is cut in chunks of that many points, the last one possibly
+//! shorter, and every chunk is followed by the SHA-256 of `G1` or `G2`, its
+//! number and its points, so a corrupt or misplaced chunk is caught.
+//!
+//! [`MappedSrs`] maps the file into memory instead of reading it. Only the
+//! chunks used are paged in, and a chunk's checksum is checked whenever its
+//! points are decoded, so committing to a short polynomial with a commitment
+//! key of millions of points only touches the first few chunks.
+use crate::{Commitment, Srs};
+use bls12_381::{G1Affine, G1Projective, G2Affine, Scalar};
+use group::Curve;
+use memmap2::Mmap;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+pub const MAGIC: &[u8; 8] = b"ZKLABSRS";
+
+pub const VERSION: u16 = 1;
+
+/// Points per chunk unless told otherwise, 3 MiB of compressed G1 points.
+pub const DEFAULT_CHUNK_SIZE: u32 = 1 << 16;
+
+const HEADER_SIZE: usize = 64;
+
+/// How the points are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Half the size, but every point costs a square root to decode.
+    Compressed = 0,
+    Uncompressed = 1,
+}
+
+/// The sections of the file, one per group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    G1,
+    G2,
+}
+
+impl Encoding {
+    fn size(self, section: Section) -> usize {
+        match (self, section) {
+            (Encoding::Compressed, Section::G1) => 48,
+            (Encoding::Uncompressed, Section::G1) => 96,
+            (Encoding::Compressed, Section::G2) => 96,
+            (Encoding::Uncompressed, Section::G2) => 192,
+        }
+    }
+}
+
+impl Srs {
+    /// Writes the SRS in the format above.
+    pub fn write_file(&self, path: &Path, encoding: Encoding, chunk_size: u32) -> io::Result<()> {
+        assert!(chunk_size > 0, "Chunks hold at least one point");
+        let mut file = BufWriter::new(File::create(path)?);
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[..8].copy_from_slice(MAGIC);
+        header[8..10].copy_from_slice(&VERSION.to_be_bytes());
+        header[10] = encoding as u8;
+        header[12..16].copy_from_slice(&chunk_size.to_be_bytes());
+        header[16..24].copy_from_slice(&(self.powers.len() as u64).to_be_bytes());
+        header[24..32].copy_from_slice(&(self.g2_powers.len() as u64).to_be_bytes());
+        let checksum = Sha256::digest(&header[..32]);
+        header[32..].copy_from_slice(&checksum);
+        file.write_all(&header)?;
+
+        let g1 = self.powers.iter().map(|point| match encoding {
+            Encoding::Compressed => point.to_compressed().to_vec(),
+            Encoding::Uncompressed => point.to_uncompressed().to_vec(),
+        });
+        write_section(&mut file, Section::G1, chunk_size, g1)?;
+        let g2 = self.g2_powers.iter().map(|point| match encoding {
+            Encoding::Compressed => point.to_compressed().to_vec(),
+            Encoding::Uncompressed => point.to_uncompressed().to_vec(),
+        });
+        write_section(&mut file, Section::G2, chunk_size, g2)?;
+
+        file.into_inner()?.sync_all()
+    }
+}
+
+fn write_section<W: Write>(
+    out: &mut W,
+    section: Section,
+    chunk_size: u32,
+    points: impl Iterator<Item = Vec<u8>>,
+) -> io::Result<()> {
+    let mut chunk = Vec::new();
+    let mut index = 0u64;
+    let mut points = points.peekable();
+    while points.peek().is_some() {
+        chunk.clear();
+        for point in points.by_ref().take(chunk_size as usize) {
+            chunk.extend_from_slice(&point);
+        }
+        out.write_all(&chunk)?;
+        out.write_all(&chunk_checksum(section, index, &chunk))?;
+        index += 1;
+    }
+    Ok(())
+}
+
+/// An SRS file mapped into memory, decoded a chunk at a time.
+///
+/// The file must not change while it is mapped: it is read in place, and
+/// a write by another process would show up in the middle of decoding.
+pub struct MappedSrs {
+    map: Mmap,
+    encoding: Encoding,
+    chunk_size: usize,
+    g1_count: usize,
+    g2_count: usize,
+}
+
+impl MappedSrs {
+    /// Maps the file and checks its header, and that it is as long as the
+    /// header says. The chunks are only checked once used.
+    pub fn open(path: &Path) -> Result<Self, FileError> {
+        let file = File::open(path)?;
+        // Safety: the mapping is only ever read, and the file is documented
+        // not to be modified while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map.get(..HEADER_SIZE).ok_or(FileError::Truncated)?;
+        if &header[..8] != MAGIC {
+            return Err(FileError::Magic);
+        }
+        if Sha256::digest(&header[..32])[..] != header[32..] {
+            return Err(FileError::Header);
+        }
+        let version = u16::from_be_bytes(header[8..10].try_into().expect("2 bytes"));
+        if version != VERSION {
+            return Err(FileError::Version(version));
+        }
+        let encoding = match header[10] {
+            0 => Encoding::Compressed,
+            1 => Encoding::Uncompressed,
+            _ => return Err(FileError::Header),
+        };
+        let number = |range: Range<usize>| {
+            let bytes = &header[range];
+            let mut be = [0u8; 8];
+            be[8 - bytes.len()..].copy_from_slice(bytes);
+            usize::try_from(u64::from_be_bytes(be)).map_err(|_| FileError::Header)
+        };
+        let chunk_size = number(12..16)?;
+        let (g1_count, g2_count) = (number(16..24)?, number(24..32)?);
+        if chunk_size == 0 || g2_count < 2 {
+            return Err(FileError::Header);
+        }
+
+        let srs = MappedSrs {
+            map,
+            encoding,
+            chunk_size,
+            g1_count,
+            g2_count,
+        };
+        let end = srs
+            .section_len(Section::G1)
+            .and_then(|g1| srs.section_len(Section::G2)?.checked_add(g1))
+            .and_then(|sections| sections.checked_add(HEADER_SIZE));
+        if end != Some(srs.map.len()) {
+            return Err(FileError::Truncated);
+        }
+        Ok(srs)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The number of powers in the section.
+    pub fn len(&self, section: Section) -> usize {
+        match section {
+            Section::G1 => self.g1_count,
+            Section::G2 => self.g2_count,
+        }
+    }
+
+    /// The largest degree that can be committed to.
+    pub fn max_degree(&self) -> usize {
+        self.g1_count.saturating_sub(1)
+    }
+
+    /// The powers `τ^i * G1` for `i` in `range`.
+    pub fn g1(&self, range: Range<usize>) -> Result<Vec<G1Affine>, FileError> {
+        let mut points = Vec::with_capacity(range.len());
+        self.for_each_point(Section::G1, range, |index, bytes| {
+            points.push(decode_g1(self.encoding, bytes).ok_or(FileError::Point {
+                section: Section::G1,
+                index,
+            })?);
+            Ok(())
+        })?;
+        Ok(points)
+    }
+
+    /// The powers `τ^i * G2` for `i` in `range`.
+    pub fn g2(&self, range: Range<usize>) -> Result<Vec<G2Affine>, FileError> {
+        let mut points = Vec::with_capacity(range.len());
+        self.for_each_point(Section::G2, range, |index, bytes| {
+            points.push(decode_g2(self.encoding, bytes).ok_or(FileError::Point {
+                section: Section::G2,
+                index,
+            })?);
+            Ok(())
+        })?;
+        Ok(points)
+    }
+
+    /// Commits to the polynomial, decoding a single chunk of the commitment
+    /// key at a time.
+    pub fn commit(&self, poly: &[Scalar]) -> Result<Commitment, FileError> {
+        if poly.len() > self.g1_count {
+            return Err(FileError::Degree {
+                degree: poly.len() - 1,
+                max: self.max_degree(),
+            });
+        }
+        let mut sum = G1Projective::identity();
+        self.for_each_point(Section::G1, 0..poly.len(), |index, bytes| {
+            let point = decode_g1(self.encoding, bytes).ok_or(FileError::Point {
+                section: Section::G1,
+                index,
+            })?;
+            sum += point * poly[index];
+            Ok(())
+        })?;
+        Ok(Commitment(sum.to_affine()))
+    }
+
+    /// Reads the powers up to `max_degree` into memory, the G2 powers being
+    /// cut to as many, to work with a smaller SRS than the file holds.
+    pub fn load(&self, max_degree: usize) -> Result<Srs, FileError> {
+        if max_degree >= self.g1_count {
+            return Err(FileError::Degree {
+                degree: max_degree,
+                max: self.max_degree(),
+            });
+        }
+        let g2_count = (max_degree + 1).clamp(2, self.g2_count);
+        Ok(Srs {
+            powers: self.g1(0..max_degree + 1)?,
+            g2_powers: self.g2(0..g2_count)?,
+        })
+    }
+
+    /// Reads the whole SRS into memory.
+    pub fn load_all(&self) -> Result<Srs, FileError> {
+        Ok(Srs {
+            powers: self.g1(0..self.g1_count)?,
+            g2_powers: self.g2(0..self.g2_count)?,
+        })
+    }
+
+    /// Checks the checksum of every chunk, without decoding the points.
+    pub fn verify_checksums(&self) -> Result<(), FileError> {
+        for section in [Section::G1, Section::G2] {
+            for chunk in 0..self.chunks(section) {
+                self.chunk(section, chunk)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with the index and bytes of every point in `range`, checking
+    /// the chunks they are in first.
+    fn for_each_point<F>(
+        &self,
+        section: Section,
+        range: Range<usize>,
+        mut f: F,
+    ) -> Result<(), FileError>
+    where
+        F: FnMut(usize, &[u8]) -> Result<(), FileError>,
+    {
+        if range.end > self.len(section) {
+            return Err(FileError::Range {
+                section,
+                end: range.end,
+                len: self.len(section),
+            });
+        }
+        let size = self.encoding.size(section);
+        let mut index = range.start;
+        while index < range.end {
+            let chunk = index / self.chunk_size;
+            let first = chunk * self.chunk_size;
+            let points = self.chunk(section, chunk)?;
+            let last = (first + self.chunk_size).min(range.end);
+            for i in index..last {
+                f(i, &points[(i - first) * size..(i - first + 1) * size])?;
+            }
+            index = last;
+        }
+        Ok(())
+    }
+
+    /// The points of a chunk, once its checksum checks out.
+    fn chunk(&self, section: Section, chunk: usize) -> Result<&[u8], FileError> {
+        let size = self.encoding.size(section);
+        let stride = self.chunk_size * size + 32;
+        let start = match section {
+            Section::G1 => HEADER_SIZE,
+            Section::G2 => HEADER_SIZE + self.section_len(Section::G1).expect("Checked on open"),
+        } + chunk * stride;
+        let points = (self.len(section) - chunk * self.chunk_size).min(self.chunk_size);
+        let (bytes, checksum) = self.map[start..start + points * size + 32].split_at(points * size);
+        if chunk_checksum(section, chunk as u64, bytes)[..] != *checksum {
+            return Err(FileError::Checksum { section, chunk });
+        }
+        Ok(bytes)
+    }
+
+    fn chunks(&self, section: Section) -> usize {
+        self.len(section).div_ceil(self.chunk_size)
+    }
+
+    /// The bytes the section takes, `None` if more than there are.
+    fn section_len(&self, section: Section) -> Option<usize> {
+        let points = self.len(section).checked_mul(self.encoding.size(section))?;
+        points.checked_add(self.chunks(section).checked_mul(32)?)
+    }
+}
+
+impl fmt::Debug for MappedSrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedSrs")
+            .field("encoding", &self.encoding)
+            .field("chunk_size", &self.chunk_size)
+            .field("g1_count", &self.g1_count)
+            .field("g2_count", &self.g2_count)
+            .finish()
+    }
+}
+
+fn chunk_checksum(section: Section, index: u64, points: &[u8]) -> [u8; 32] {
+    let tag: &[u8] = match section {
+        Section::G1 => b"G1",
+        Section::G2 => b"G2",
+    };
+    Sha256::new()
+        .chain(tag)
+        .chain(index.to_be_bytes())
+        .chain(points)
+        .finalize()
+        .into()
+}
+
+fn decode_g1(encoding: Encoding, bytes: &[u8]) -> Option<G1Affine> {
+    let point = match encoding {
+        Encoding::Compressed => G1Affine::from_compressed(bytes.try_into().ok()?),
+        Encoding::Uncompressed => G1Affine::from_uncompressed(bytes.try_into().ok()?),
+    };
+    Option::from(point)
+}
+
+fn decode_g2(encoding: Encoding, bytes: &[u8]) -> Option<G2Affine> {
+    let point = match encoding {
+        Encoding::Compressed => G2Affine::from_compressed(bytes.try_into().ok()?),
+        Encoding::Uncompressed => G2Affine::from_uncompressed(bytes.try_into().ok()?),
+    };
+    Option::from(point)
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    /// Not an SRS file.
+    Magic,
+    /// A version of the format this crate can't read.
+    Version(u16),
+    /// The header doesn't match its checksum, or makes no sense.
+    Header,
+    /// The file isn't as long as its header says.
+    Truncated,
+    Checksum {
+        section: Section,
+        chunk: usize,
+    },
+    /// A point that doesn't decode, or isn't in its group.
+    Point {
+        section: Section,
+        index: usize,
+    },
+    Range {
+        section: Section,
+        end: usize,
+        len: usize,
+    },
+    Degree {
+        degree: usize,
+        max: usize,
+    },
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Io(e) => e.fmt(f),
+            FileError::Magic => write!(f, "not an SRS file"),
+            FileError::Version(version) => write!(
+                f,
+                "SRS file version {} isn't supported, only version {} is",
+                version, VERSION
+            ),
+            FileError::Header => write!(f, "corrupt SRS file header"),
+            FileError::Truncated => write!(f, "the SRS file is truncated"),
+            FileError::Checksum { section, chunk } => {
+                write!(f, "chunk {} of the {:?} powers is corrupt", chunk, section)
+            }
+            FileError::Point { section, index } => {
+                write!(f, "the {:?} power {} is not a valid point", section, index)
+            }
+            FileError::Range { section, end, len } => write!(
+                f,
+                "asked for the {:?} powers up to {}, the file has {}",
+                section, end, len
+            ),
+            FileError::Degree { degree, max } => write!(
+                f,
+                "can't use a degree of {}, the SRS stops at {}",
+                degree, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+impl From<io::Error> for FileError {
+    fn from(e: io::Error) -> Self {
+        FileError::Io(e)
+    }
+}
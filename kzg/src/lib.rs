@@ -0,0 +1,255 @@
+//! KZG polynomial commitments over BLS12-381.
+//!
+//! A trusted setup samples a secret τ and publishes its powers
+//! `[τ^i * G1]` and `τ * G2`, then forgets τ. The commitment to
+//! `f(x) = ∑ a_i * x^i` is the single point
+//!
+//! C = f(τ) * G1 = ∑ a_i * (τ^i * G1)
+//!
+//! whatever the degree of f. To show that `f(z) = y`, the committer divides
+//! `f(x) - y` by `x - z`, which only works without a remainder if `f(z)` is
+//! indeed y, and publishes the commitment `π = q(τ) * G1` to the quotient.
+//! Anyone checks
+//!
+//! e(C - y * G1, G2) == e(π, τ * G2 - z * G2)
+//!
+//! which is `f(τ) - y = q(τ) * (τ - z)` in the exponent.
+//!
+//! In a DKG the commitment can stand in for the dealer's Feldman
+//! commitments `[a_i * G1]`: it is one point instead of `t + 1`, and member
+//! `i` checks its share `f(i)` against the dealer's opening at `z = i`
+//! instead of evaluating the polynomial in the exponent. The price is the
+//! setup, whoever knows τ can open a commitment to anything.
+//!
+//! See [`batch`] for proving several evaluations at once, [`ceremony`]
+//! for running the setup between several parties so that τ stays secret as
+//! long as one of them is honest. [`accumulator`] commits to a set as the
+//! polynomial vanishing on it, and [`vector`] to vectors with openings of
+//! a single point per position.
+pub mod accumulator;
+pub mod batch;
+pub mod ceremony;
+pub mod file;
+mod poly;
+pub mod vector;
+
+pub use poly::evaluate;
+
+use file::{Encoding, FileError, MappedSrs};
+
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use bls_shamir::backend;
+use group::ff::Field;
+use group::Curve;
+use pairing::pairing_product_is_one;
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// The public parameters of a setup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Srs {
+    /// `τ^i * G1` for `i` up to the largest degree that can be committed to.
+    powers: Vec<G1Affine>,
+    /// `τ^i * G2`, as many as there are in G1 and at least `G2` and `τ * G2`.
+    g2_powers: Vec<G2Affine>,
+}
+
+/// The commitment `f(τ) * G1` to a polynomial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commitment(pub G1Affine);
+
+/// The commitment `q(τ) * G1` to the quotient of an opening.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proof(pub G1Affine);
+
+impl Srs {
+    /// Runs a setup for polynomials up to `max_degree` on a single machine,
+    /// so its τ only stays secret if that machine is trusted. Good for tests
+    /// and the lab, a real deployment would use a ceremony's parameters.
+    pub fn generate<R: RngCore + CryptoRng>(max_degree: usize, rng: &mut R) -> Self {
+        let tau = Zeroizing::new(Scalar::random(&mut *rng));
+        let mut power = Zeroizing::new(Scalar::one());
+        let (powers, g2_powers): (Vec<_>, Vec<_>) = (0..=max_degree.max(1))
+            .map(|_| {
+                let points = (
+                    G1Affine::generator() * *power,
+                    G2Affine::generator() * *power,
+                );
+                *power *= *tau;
+                points
+            })
+            .unzip();
+
+        let mut affine = vec![G1Affine::identity(); max_degree + 1];
+        G1Projective::batch_normalize(&powers[..=max_degree], &mut affine);
+        let mut g2_affine = vec![G2Affine::identity(); g2_powers.len()];
+        G2Projective::batch_normalize(&g2_powers, &mut g2_affine);
+        Srs {
+            powers: affine,
+            g2_powers: g2_affine,
+        }
+    }
+
+    /// Parameters published by someone else, `powers[i]` being `τ^i * G1`
+    /// and `g2_powers[i]` being `τ^i * G2`.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than two powers in G2, the openings at a single
+    /// point need `τ * G2`.
+    pub fn from_powers(powers: Vec<G1Affine>, g2_powers: Vec<G2Affine>) -> Self {
+        assert!(g2_powers.len() >= 2, "The setup needs τ * G2");
+        Srs { powers, g2_powers }
+    }
+
+    /// The SRS as stored on disk: the number of powers in G1 and in G2 as
+    /// big-endian `u32`s, followed by the compressed powers.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.powers.len() * 48 + self.g2_powers.len() * 96);
+        bytes.extend_from_slice(&(self.powers.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.g2_powers.len() as u32).to_be_bytes());
+        for power in &self.powers {
+            bytes.extend_from_slice(&power.to_compressed());
+        }
+        for power in &self.g2_powers {
+            bytes.extend_from_slice(&power.to_compressed());
+        }
+        bytes
+    }
+
+    /// Parses [`Srs::to_bytes`]. The points are checked to be in their
+    /// groups, not to be powers of the same τ, see
+    /// [`Srs::is_well_formed`] for that.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KzgError> {
+        let count = |range: Range<usize>| -> Result<usize, KzgError> {
+            let count: [u8; 4] = bytes
+                .get(range)
+                .and_then(|count| count.try_into().ok())
+                .ok_or(KzgError::Malformed)?;
+            Ok(u32::from_be_bytes(count) as usize)
+        };
+        let (g1_count, g2_count) = (count(0..4)?, count(4..8)?);
+        let g1_end = g1_count
+            .checked_mul(48)
+            .and_then(|len| len.checked_add(8))
+            .ok_or(KzgError::Malformed)?;
+        if g2_count < 2
+            || g2_count
+                .checked_mul(96)
+                .and_then(|len| len.checked_add(g1_end))
+                != Some(bytes.len())
+        {
+            return Err(KzgError::Malformed);
+        }
+
+        let powers = bytes[8..g1_end]
+            .chunks(48)
+            .map(|chunk| {
+                let chunk: [u8; 48] = chunk.try_into().expect("Chunks of 48 bytes");
+                Option::from(G1Affine::from_compressed(&chunk)).ok_or(KzgError::Malformed)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let g2_powers = bytes[g1_end..]
+            .chunks(96)
+            .map(|chunk| {
+                let chunk: [u8; 96] = chunk.try_into().expect("Chunks of 96 bytes");
+                Option::from(G2Affine::from_compressed(&chunk)).ok_or(KzgError::Malformed)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Srs { powers, g2_powers })
+    }
+
+    /// Saves the SRS in the format of [`file`], compressed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.write_file(path, Encoding::Compressed, file::DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Reads a whole SRS file, see [`MappedSrs`] to only read what is used.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        MappedSrs::open(path)
+            .and_then(|srs| srs.load_all())
+            .map_err(|e| match e {
+                FileError::Io(e) => e,
+                e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+            })
+    }
+
+    /// The largest degree that can be committed to.
+    pub fn max_degree(&self) -> usize {
+        self.powers.len().saturating_sub(1)
+    }
+
+    /// Commits to the polynomial with the coefficients `poly`, lowest degree
+    /// first.
+    pub fn commit(&self, poly: &[Scalar]) -> Result<Commitment, KzgError> {
+        self.check_degree(poly)?;
+        Ok(Commitment(self.combine(poly)))
+    }
+
+    /// Evaluates the polynomial at `z`, returns `f(z)` and the proof of it.
+    pub fn open(&self, poly: &[Scalar], z: Scalar) -> Result<(Scalar, Proof), KzgError> {
+        self.check_degree(poly)?;
+        let (quotient, y) = poly::divide_by_linear(poly, z);
+        Ok((y, Proof(self.combine(&quotient))))
+    }
+
+    /// Checks that the polynomial of `commitment` evaluates to `y` at `z`.
+    pub fn verify(&self, commitment: &Commitment, z: Scalar, y: Scalar, proof: &Proof) -> bool {
+        // e(C - y * G1, G2) * e(-π, τ * G2 - z * G2) == 1
+        let lhs = (commitment.0 - G1Affine::generator() * y).to_affine();
+        let shifted = (self.g2_powers[1] - G2Affine::generator() * z).to_affine();
+        pairing_product_is_one(&[(lhs, G2Affine::generator()), (-proof.0, shifted)])
+    }
+
+    fn check_degree(&self, poly: &[Scalar]) -> Result<(), KzgError> {
+        if poly.len() > self.powers.len() {
+            return Err(KzgError::Degree {
+                degree: poly.len() - 1,
+                max: self.max_degree(),
+            });
+        }
+        Ok(())
+    }
+
+    /// `∑ c_i * (τ^i * G1)`, for a polynomial within the setup's degree.
+    fn combine(&self, poly: &[Scalar]) -> G1Affine {
+        backend::g1_msm(&self.powers, poly).to_affine()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KzgError {
+    /// The polynomial has a higher degree than the setup supports.
+    Degree { degree: usize, max: usize },
+    /// Opening at more points at once than the setup has powers in G2 for.
+    Points { count: usize, max: usize },
+    /// The same point appears twice among the points of an opening.
+    DuplicatePoint,
+    /// Bytes that aren't an SRS.
+    Malformed,
+}
+
+impl fmt::Display for KzgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KzgError::Degree { degree, max } => write!(
+                f,
+                "can't commit to a polynomial of degree {}, the setup stops at {}",
+                degree, max
+            ),
+            KzgError::Points { count, max } => write!(
+                f,
+                "can't open at {} points at once, the setup stops at {}",
+                count, max
+            ),
+            KzgError::DuplicatePoint => write!(f, "the points of an opening must be distinct"),
+            KzgError::Malformed => write!(f, "malformed SRS"),
+        }
+    }
+}
+
+impl std::error::Error for KzgError {}
@@ -0,0 +1,55 @@
+//! Arithmetic on polynomials given by their coefficients, lowest degree
+//! first, on top of the `poly` crate.
+use bls12_381::Scalar;
+
+pub(crate) use ::poly::division::{divide_by_linear, vanishing};
+pub use ::poly::evaluate;
+
+/// Divides `f(x)` by `∏ (x - z_j)`, one factor at a time, the remainder is
+/// dropped so `f` should vanish on the points.
+pub(crate) fn divide_vanishing(poly: &[Scalar], points: &[Scalar]) -> Vec<Scalar> {
+    points
+        .iter()
+        .fold(poly.to_vec(), |poly, z| divide_by_linear(&poly, *z).0)
+}
+
+/// The polynomial of the lowest degree through the points, `None` if two of
+/// them have the same x.
+pub(crate) fn interpolate(points: &[Scalar], values: &[Scalar]) -> Option<Vec<Scalar>> {
+    let mut poly = vec![Scalar::zero(); points.len()];
+    for (j, (zj, yj)) in points.iter().zip(values).enumerate() {
+        // y_j * ∏_{k != j} (x - z_k) / (z_j - z_k)
+        let others = points
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| *k != j)
+            .map(|(_, zk)| *zk)
+            .collect::<Vec<_>>();
+        let denominator = others.iter().fold(Scalar::one(), |acc, zk| acc * (zj - zk));
+        let scale = yj * Option::<Scalar>::from(denominator.invert())?;
+        for (c, b) in poly.iter_mut().zip(vanishing(&others)) {
+            *c += b * scale;
+        }
+    }
+    Some(poly)
+}
+
+/// `a(x) - b(x)`.
+pub(crate) fn sub(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    let mut poly = a.to_vec();
+    poly.resize(a.len().max(b.len()), Scalar::zero());
+    for (c, b) in poly.iter_mut().zip(b) {
+        *c -= b;
+    }
+    poly
+}
+
+/// `acc(x) += s * a(x)`.
+pub(crate) fn add_scaled(acc: &mut Vec<Scalar>, a: &[Scalar], s: Scalar) {
+    if acc.len() < a.len() {
+        acc.resize(a.len(), Scalar::zero());
+    }
+    for (c, a) in acc.iter_mut().zip(a) {
+        *c += a * s;
+    }
+}
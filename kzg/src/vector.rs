@@ -0,0 +1,276 @@
+//! Vector commitments with constant-size openings, after Libert and Yung,
+//! as aggregated in Pointproofs (Gorbunov, Reyzin, Wee and Zhang).
+//!
+//! The setup is the powers of a secret α, as for polynomials, but with a
+//! gap: `α^i * G1` for i from 1 to 2N but `N + 1`, and `α^i * G2` for i
+//! from 1 to N. The commitment to `m_1, ..., m_N` is
+//!
+//! C = ∑ m_i * (α^i * G1)
+//!
+//! and the opening of position i is
+//!
+//! π_i = ∑_{j ≠ i} m_j * (α^(N + 1 - i + j) * G1)
+//!
+//! which never needs the missing power. In the exponent,
+//! `α^(N + 1 - i) * C` is `π_i` plus `m_i * α^(N + 1)`, so
+//!
+//! e(C, α^(N + 1 - i) * G2) == e(π_i, G2) * e(m_i * α * G1, α^N * G2)
+//!
+//! and a different `m_i` would need `α^(N + 1) * G1`. Openings of several
+//! positions of one commitment aggregate into one point, `π = ∑ t_i * π_i`
+//! for challenges `t_i` drawn from the commitment and the opened values,
+//! checked with a single pairing product:
+//!
+//! e(C, ∑ t_i * α^(N + 1 - i) * G2) == e(π, G2) * e((∑ t_i * m_i) * α * G1, α^N * G2)
+//!
+//! Positions are numbered from 0 in the API.
+use crate::{Commitment, Proof};
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use bls_shamir::backend;
+use group::ff::Field;
+use group::Curve;
+use pairing::pairing_product_is_one;
+use rand_core::{CryptoRng, RngCore};
+use sigma::transcript::Transcript;
+use std::fmt;
+use zeroize::Zeroizing;
+
+const DOMAIN: &[u8] = b"zk-lab/kzg/vector";
+
+/// The public parameters for vectors of up to N values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameters {
+    /// `α^i * G1` at `i - 1`, for i from 1 to 2N, the identity in place of
+    /// `α^(N + 1) * G1`.
+    g1: Vec<G1Affine>,
+    /// `α^i * G2` at `i - 1`, for i from 1 to N.
+    g2: Vec<G2Affine>,
+}
+
+impl Parameters {
+    /// Runs a setup for vectors of up to `len` values on a single machine,
+    /// like [`Srs::generate`](crate::Srs::generate).
+    pub fn generate<R: RngCore + CryptoRng>(len: usize, rng: &mut R) -> Self {
+        let alpha = Zeroizing::new(Scalar::random(&mut *rng));
+        let mut power = Zeroizing::new(Scalar::one());
+        let mut g1 = Vec::with_capacity(2 * len);
+        let mut g2 = Vec::with_capacity(len);
+        for i in 1..=2 * len {
+            *power *= *alpha;
+            g1.push(if i == len + 1 {
+                G1Projective::identity()
+            } else {
+                G1Projective::generator() * *power
+            });
+            if i <= len {
+                g2.push(G2Projective::generator() * *power);
+            }
+        }
+
+        let mut g1_affine = vec![G1Affine::identity(); g1.len()];
+        G1Projective::batch_normalize(&g1, &mut g1_affine);
+        let mut g2_affine = vec![G2Affine::identity(); g2.len()];
+        G2Projective::batch_normalize(&g2, &mut g2_affine);
+        Parameters {
+            g1: g1_affine,
+            g2: g2_affine,
+        }
+    }
+
+    /// N, the largest number of values that can be committed to.
+    pub fn len(&self) -> usize {
+        self.g2.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.g2.is_empty()
+    }
+
+    /// Commits to the values, the missing ones being 0.
+    pub fn commit(&self, values: &[Scalar]) -> Result<Commitment, VectorError> {
+        self.check_len(values)?;
+        Ok(Commitment(
+            backend::g1_msm(&self.g1[..values.len()], values).to_affine(),
+        ))
+    }
+
+    /// Opens the position `index` of the commitment to the values.
+    pub fn open(&self, values: &[Scalar], index: usize) -> Result<Proof, VectorError> {
+        self.check_len(values)?;
+        self.check_index(index)?;
+        // m_j with `α^(N + 1 - i + j)` at `N - i + j`, zeroed at j = i so
+        // that the missing power doesn't matter.
+        let n = self.len();
+        let mut scalars = values.to_vec();
+        if let Some(value) = scalars.get_mut(index) {
+            *value = Scalar::zero();
+        }
+        let bases = &self.g1[n - index..n - index + values.len()];
+        Ok(Proof(backend::g1_msm(bases, &scalars).to_affine()))
+    }
+
+    /// Checks that the position `index` of the commitment holds `value`.
+    pub fn verify(
+        &self,
+        commitment: &Commitment,
+        index: usize,
+        value: Scalar,
+        proof: &Proof,
+    ) -> bool {
+        index < self.len() && self.check(commitment, self.g2[self.len() - 1 - index], value, proof)
+    }
+
+    /// Aggregates the openings of distinct positions of the commitment,
+    /// given with the positions and their values.
+    pub fn aggregate(
+        &self,
+        commitment: &Commitment,
+        openings: &[(usize, Scalar, Proof)],
+    ) -> Result<Proof, VectorError> {
+        let positions = openings
+            .iter()
+            .map(|(index, value, _)| (*index, *value))
+            .collect::<Vec<_>>();
+        self.check_positions(&positions)?;
+        let proofs = openings
+            .iter()
+            .map(|(_, _, proof)| proof.0)
+            .collect::<Vec<_>>();
+        let challenges = challenges(commitment, &positions);
+        Ok(Proof(backend::g1_msm(&proofs, &challenges).to_affine()))
+    }
+
+    /// Opens all the positions of `indices` at once.
+    pub fn open_aggregated(
+        &self,
+        values: &[Scalar],
+        indices: &[usize],
+    ) -> Result<Proof, VectorError> {
+        let commitment = self.commit(values)?;
+        let openings = indices
+            .iter()
+            .map(|index| {
+                let value = values.get(*index).copied().unwrap_or(Scalar::zero());
+                Ok((*index, value, self.open(values, *index)?))
+            })
+            .collect::<Result<Vec<_>, VectorError>>()?;
+        self.aggregate(&commitment, &openings)
+    }
+
+    /// Checks that the commitment holds `values[k].1` at each position
+    /// `values[k].0`.
+    pub fn verify_aggregated(
+        &self,
+        commitment: &Commitment,
+        values: &[(usize, Scalar)],
+        proof: &Proof,
+    ) -> bool {
+        if self.check_positions(values).is_err() {
+            return false;
+        }
+        let challenges = challenges(commitment, values);
+        let bases = values
+            .iter()
+            .map(|(index, _)| self.g2[self.len() - 1 - index])
+            .collect::<Vec<_>>();
+        let base = bases
+            .iter()
+            .zip(&challenges)
+            .map(|(base, t)| base * t)
+            .sum::<G2Projective>()
+            .to_affine();
+        let value = values
+            .iter()
+            .zip(&challenges)
+            .map(|((_, m), t)| m * t)
+            .sum();
+        self.check(commitment, base, value, proof)
+    }
+
+    /// `e(C, base) * e(-π, G2) * e(-m * α * G1, α^N * G2) == 1`
+    fn check(&self, commitment: &Commitment, base: G2Affine, value: Scalar, proof: &Proof) -> bool {
+        let (Some(alpha), Some(last)) = (self.g1.first(), self.g2.last()) else {
+            return false;
+        };
+        let claimed = (alpha * -value).to_affine();
+        pairing_product_is_one(&[
+            (commitment.0, base),
+            (-proof.0, G2Affine::generator()),
+            (claimed, *last),
+        ])
+    }
+
+    fn check_len(&self, values: &[Scalar]) -> Result<(), VectorError> {
+        if values.len() > self.len() {
+            return Err(VectorError::Length {
+                len: values.len(),
+                max: self.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), VectorError> {
+        if index >= self.len() {
+            return Err(VectorError::Index {
+                index,
+                len: self.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_positions(&self, positions: &[(usize, Scalar)]) -> Result<(), VectorError> {
+        for (k, (index, _)) in positions.iter().enumerate() {
+            self.check_index(*index)?;
+            if positions[..k].iter().any(|(other, _)| other == index) {
+                return Err(VectorError::DuplicateIndex);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `t_i`, from the commitment and all the opened positions and values.
+fn challenges(commitment: &Commitment, positions: &[(usize, Scalar)]) -> Vec<Scalar> {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_point(b"commitment", &commitment.0);
+    for (index, value) in positions {
+        transcript.append_u64(b"index", *index as u64);
+        transcript.append_scalar(b"value", value);
+    }
+    positions
+        .iter()
+        .map(|_| transcript.challenge_scalar(b"t"))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorError {
+    /// More values than the parameters support.
+    Length { len: usize, max: usize },
+    /// A position past the end of the vectors.
+    Index { index: usize, len: usize },
+    /// The same position appears twice in an aggregated opening.
+    DuplicateIndex,
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VectorError::Length { len, max } => write!(
+                f,
+                "can't commit to {} values, the parameters stop at {}",
+                len, max
+            ),
+            VectorError::Index { index, len } => {
+                write!(f, "position {} is out of vectors of {}", index, len)
+            }
+            VectorError::DuplicateIndex => {
+                write!(f, "the positions of an opening must be distinct")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VectorError {}
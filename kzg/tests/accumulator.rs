@@ -0,0 +1,127 @@
+use bls12_381::Scalar;
+use kzg::accumulator::{Accumulator, AccumulatorError, Update};
+use kzg::{KzgError, Srs};
+
+fn elements(xs: &[u64]) -> Vec<Scalar> {
+    xs.iter().copied().map(Scalar::from).collect()
+}
+
+#[test]
+fn members_and_non_members() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let accumulator = Accumulator::new(&srs, &elements(&[3, 5, 8, 13])).unwrap();
+    let value = accumulator.value();
+
+    for x in elements(&[3, 5, 8, 13]) {
+        let witness = accumulator.membership_witness(&srs, x).unwrap();
+        assert!(srs.verify_membership(&value, &witness));
+        assert_eq!(
+            accumulator.non_membership_witness(&srs, x),
+            Err(AccumulatorError::Member)
+        );
+    }
+    for y in elements(&[0, 4, 21]) {
+        let witness = accumulator.non_membership_witness(&srs, y).unwrap();
+        assert!(srs.verify_non_membership(&value, &witness));
+        assert_eq!(
+            accumulator.membership_witness(&srs, y),
+            Err(AccumulatorError::NotMember)
+        );
+    }
+}
+
+#[test]
+fn witnesses_of_other_elements_are_rejected() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let accumulator = Accumulator::new(&srs, &elements(&[3, 5, 8])).unwrap();
+    let value = accumulator.value();
+
+    let mut witness = accumulator
+        .membership_witness(&srs, Scalar::from(3))
+        .unwrap();
+    witness.element = Scalar::from(4);
+    assert!(!srs.verify_membership(&value, &witness));
+
+    let mut witness = accumulator
+        .non_membership_witness(&srs, Scalar::from(4))
+        .unwrap();
+    witness.element = Scalar::from(5);
+    assert!(!srs.verify_non_membership(&value, &witness));
+
+    // A member has a zero remainder, which can't prove anything.
+    witness.remainder = Scalar::zero();
+    witness.witness = accumulator
+        .membership_witness(&srs, Scalar::from(5))
+        .unwrap()
+        .witness;
+    assert!(!srs.verify_non_membership(&value, &witness));
+}
+
+#[test]
+fn witnesses_follow_additions_and_deletions() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let mut accumulator = Accumulator::new(&srs, &elements(&[3, 5])).unwrap();
+    let mut member = accumulator
+        .membership_witness(&srs, Scalar::from(3))
+        .unwrap();
+    let mut non_member = accumulator
+        .non_membership_witness(&srs, Scalar::from(7))
+        .unwrap();
+
+    let updates = [
+        accumulator.add(&srs, Scalar::from(11)).unwrap(),
+        accumulator.add(&srs, Scalar::from(2)).unwrap(),
+        accumulator.remove(&srs, Scalar::from(5)).unwrap(),
+        accumulator.remove(&srs, Scalar::from(11)).unwrap(),
+    ];
+    for update in &updates {
+        member.update(update).unwrap();
+        non_member.update(update).unwrap();
+    }
+    let value = accumulator.value();
+    assert!(srs.verify_membership(&value, &member));
+    assert!(srs.verify_non_membership(&value, &non_member));
+    assert_eq!(
+        accumulator,
+        Accumulator::new(&srs, &elements(&[3, 2])).unwrap()
+    );
+
+    // The witnesses of the elements that change sides can't follow.
+    let update = accumulator.add(&srs, Scalar::from(7)).unwrap();
+    assert_eq!(non_member.update(&update), Err(AccumulatorError::Member));
+    let update = accumulator.remove(&srs, Scalar::from(3)).unwrap();
+    assert!(matches!(update, Update::Removed { .. }));
+    assert_eq!(member.update(&update), Err(AccumulatorError::NotMember));
+}
+
+#[test]
+fn stale_witnesses_are_rejected() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let mut accumulator = Accumulator::new(&srs, &elements(&[3, 5])).unwrap();
+    let witness = accumulator
+        .membership_witness(&srs, Scalar::from(3))
+        .unwrap();
+    accumulator.add(&srs, Scalar::from(9)).unwrap();
+    assert!(!srs.verify_membership(&accumulator.value(), &witness));
+}
+
+#[test]
+fn sets_are_bounded_by_the_setup() {
+    let srs = Srs::generate(2, &mut rand::thread_rng());
+    let mut accumulator = Accumulator::new(&srs, &elements(&[1, 2])).unwrap();
+    assert_eq!(
+        accumulator.add(&srs, Scalar::from(3)),
+        Err(AccumulatorError::Kzg(KzgError::Degree {
+            degree: 3,
+            max: 2
+        }))
+    );
+    assert_eq!(
+        accumulator.add(&srs, Scalar::from(2)),
+        Err(AccumulatorError::Member)
+    );
+    assert_eq!(
+        Accumulator::new(&srs, &elements(&[1, 1])),
+        Err(AccumulatorError::Member)
+    );
+}
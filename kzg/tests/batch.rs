@@ -0,0 +1,94 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use kzg::batch::Evaluations;
+use kzg::{evaluate, KzgError, Srs};
+
+fn polynomial(degree: usize) -> Vec<Scalar> {
+    (0..=degree)
+        .map(|_| Scalar::random(&mut rand::thread_rng()))
+        .collect()
+}
+
+fn points(xs: &[u64]) -> Vec<Scalar> {
+    xs.iter().copied().map(Scalar::from).collect()
+}
+
+#[test]
+fn batch_openings_at_one_point() {
+    let srs = Srs::generate(6, &mut rand::thread_rng());
+    let polys = vec![polynomial(6), polynomial(2), polynomial(4)];
+    let commitments = polys
+        .iter()
+        .map(|poly| srs.commit(poly).unwrap())
+        .collect::<Vec<_>>();
+    let z = Scalar::from(11);
+
+    let (values, proof) = srs.open_batch(&polys, z).unwrap();
+    for (poly, y) in polys.iter().zip(&values) {
+        assert_eq!(*y, evaluate(poly, z));
+    }
+    assert!(srs.verify_batch(&commitments, z, &values, &proof));
+
+    let mut wrong = values.clone();
+    wrong[1] += Scalar::one();
+    assert!(!srs.verify_batch(&commitments, z, &wrong, &proof));
+    assert!(!srs.verify_batch(&commitments[..2], z, &values[..2], &proof));
+}
+
+#[test]
+fn multi_point_openings() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let poly = polynomial(8);
+    let commitment = srs.commit(&poly).unwrap();
+    let zs = points(&[1, 2, 3, 5, 8]);
+
+    let (values, proof) = srs.open_multi(&poly, &zs).unwrap();
+    assert!(srs.verify_multi(&commitment, &zs, &values, &proof));
+
+    let mut wrong = values.clone();
+    wrong[4] += Scalar::one();
+    assert!(!srs.verify_multi(&commitment, &zs, &wrong, &proof));
+    assert!(!srs.verify_multi(&commitment, &zs[..4], &values[..4], &proof));
+}
+
+#[test]
+fn multi_point_openings_need_distinct_points_within_the_setup() {
+    let srs = Srs::generate(3, &mut rand::thread_rng());
+    let poly = polynomial(3);
+    assert_eq!(
+        srs.open_multi(&poly, &points(&[1, 2, 1])),
+        Err(KzgError::DuplicatePoint)
+    );
+    assert_eq!(
+        srs.open_multi(&poly, &points(&[1, 2, 3, 4])),
+        Err(KzgError::Points { count: 4, max: 3 })
+    );
+}
+
+#[test]
+fn combined_openings() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let polys = vec![polynomial(8), polynomial(3), polynomial(5)];
+    let zs = vec![points(&[1, 2]), points(&[2, 7, 9]), points(&[4])];
+
+    let (claims, proof) = srs.open_combined(&polys, &zs).unwrap();
+    for ((claim, poly), points) in claims.iter().zip(&polys).zip(&zs) {
+        assert_eq!(claim.commitment, srs.commit(poly).unwrap());
+        assert_eq!(&claim.points, points);
+    }
+    assert!(srs.verify_combined(&claims, &proof));
+
+    let mut wrong = claims.clone();
+    wrong[1].values[2] += Scalar::one();
+    assert!(!srs.verify_combined(&wrong, &proof));
+
+    // A claim can't be dropped or moved to another commitment.
+    assert!(!srs.verify_combined(&claims[..2], &proof));
+    let mut swapped = claims.clone();
+    swapped.swap(0, 2);
+    assert!(!srs.verify_combined(&swapped, &proof));
+
+    let mut forged: Vec<Evaluations> = claims;
+    forged[2].points = points(&[5]);
+    assert!(!srs.verify_combined(&forged, &proof));
+}
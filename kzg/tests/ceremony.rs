@@ -0,0 +1,88 @@
+use bls12_381::Scalar;
+use kzg::ceremony::{UpdateError, UpdateProof};
+use kzg::Srs;
+
+#[test]
+fn contributions_chain() {
+    let mut rng = rand::thread_rng();
+    let mut srs = Srs::initial(8);
+    assert!(srs.is_well_formed(&mut rng));
+    for _ in 0..3 {
+        let (next, proof) = srs.contribute(&mut rng);
+        srs.verify_update(&next, &proof, &mut rng).unwrap();
+        srs = next;
+    }
+
+    // The final SRS works like any other.
+    let poly = (1..=9).map(Scalar::from).collect::<Vec<_>>();
+    let commitment = srs.commit(&poly).unwrap();
+    let (y, proof) = srs.open(&poly, Scalar::from(4)).unwrap();
+    assert!(srs.verify(&commitment, Scalar::from(4), y, &proof));
+}
+
+#[test]
+fn rejects_updates_that_dont_follow() {
+    let mut rng = rand::thread_rng();
+    let srs = Srs::initial(4);
+    let (next, proof) = srs.contribute(&mut rng);
+
+    // Proofs are bound to the update they are for.
+    let (other, _) = Srs::initial(4).contribute(&mut rng);
+    let (_, unrelated) = other.contribute(&mut rng);
+    assert_eq!(
+        srs.verify_update(&next, &unrelated, &mut rng),
+        Err(UpdateError::ProofOfKnowledge)
+    );
+    let mut mismatched = proof;
+    mismatched.s_g2 = unrelated.s_g2;
+    assert_eq!(
+        srs.verify_update(&next, &mismatched, &mut rng),
+        Err(UpdateError::NotAnUpdate)
+    );
+
+    // Starting over from a τ of our own drops the contributions before.
+    let generated = Srs::generate(4, &mut rng);
+    assert_eq!(
+        srs.verify_update(&generated, &proof, &mut rng),
+        Err(UpdateError::ProofOfKnowledge)
+    );
+    assert_eq!(
+        srs.verify_update(&Srs::initial(5), &proof, &mut rng),
+        Err(UpdateError::Shape)
+    );
+}
+
+#[test]
+fn rejects_malformed_powers() {
+    let mut rng = rand::thread_rng();
+    let (next, proof) = Srs::initial(4).contribute(&mut rng);
+    let mut bytes = next.to_bytes();
+    // Swap the powers τ^2 and τ^3 in G1.
+    let (second, third) = (8 + 2 * 48, 8 + 3 * 48);
+    let power = bytes[second..third].to_vec();
+    bytes.copy_within(third..third + 48, second);
+    bytes[third..third + 48].copy_from_slice(&power);
+    let swapped = Srs::from_bytes(&bytes).unwrap();
+
+    assert!(!swapped.is_well_formed(&mut rng));
+    assert_eq!(
+        Srs::initial(4).verify_update(&swapped, &proof, &mut rng),
+        Err(UpdateError::Malformed)
+    );
+}
+
+#[test]
+fn srs_and_proofs_round_trip() {
+    let mut rng = rand::thread_rng();
+    let (srs, proof) = Srs::initial(3).contribute(&mut rng);
+    assert_eq!(Srs::from_bytes(&srs.to_bytes()), Ok(srs.clone()));
+    assert_eq!(UpdateProof::from_bytes(&proof.to_bytes()), Some(proof));
+
+    let bytes = srs.to_bytes();
+    assert!(Srs::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    let path = std::env::temp_dir().join(format!("srs-{}.bin", std::process::id()));
+    srs.save(&path).unwrap();
+    assert_eq!(Srs::load(&path).unwrap(), srs);
+    std::fs::remove_file(&path).unwrap();
+}
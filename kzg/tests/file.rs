@@ -0,0 +1,98 @@
+use bls12_381::Scalar;
+use kzg::file::{Encoding, FileError, MappedSrs, Section};
+use kzg::Srs;
+use std::fs;
+use std::path::PathBuf;
+
+fn path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("srs-{}-{}.bin", name, std::process::id()))
+}
+
+#[test]
+fn mapped_files_match_the_srs() {
+    let srs = Srs::generate(20, &mut rand::thread_rng());
+    for encoding in [Encoding::Compressed, Encoding::Uncompressed] {
+        let path = path(&format!("{:?}", encoding));
+        srs.write_file(&path, encoding, 8).unwrap();
+
+        let mapped = MappedSrs::open(&path).unwrap();
+        assert_eq!(mapped.encoding(), encoding);
+        assert_eq!(mapped.max_degree(), 20);
+        mapped.verify_checksums().unwrap();
+        assert_eq!(mapped.load_all().unwrap(), srs);
+
+        // Across a chunk boundary.
+        let poly = (0..12).map(Scalar::from).collect::<Vec<_>>();
+        assert_eq!(mapped.commit(&poly).unwrap(), srs.commit(&poly).unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn loads_a_smaller_srs() {
+    let srs = Srs::generate(20, &mut rand::thread_rng());
+    let path = path("smaller");
+    srs.save(&path).unwrap();
+    let mapped = MappedSrs::open(&path).unwrap();
+
+    let small = mapped.load(4).unwrap();
+    assert_eq!(small.max_degree(), 4);
+    let poly = (1..=5).map(Scalar::from).collect::<Vec<_>>();
+    assert_eq!(small.commit(&poly).unwrap(), srs.commit(&poly).unwrap());
+    let (y, proof) = small.open(&poly, Scalar::from(2)).unwrap();
+    assert!(srs.verify(&small.commit(&poly).unwrap(), Scalar::from(2), y, &proof));
+
+    assert!(matches!(
+        mapped.load(21),
+        Err(FileError::Degree {
+            degree: 21,
+            max: 20
+        })
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn corrupt_chunks_are_caught_when_used() {
+    let srs = Srs::generate(20, &mut rand::thread_rng());
+    let path = path("corrupt");
+    srs.write_file(&path, Encoding::Compressed, 8).unwrap();
+    // A byte of the third G1 chunk, points 16 to 20.
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[64 + 2 * (8 * 48 + 32) + 5] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+
+    let mapped = MappedSrs::open(&path).unwrap();
+    let poly = (0..16).map(Scalar::from).collect::<Vec<_>>();
+    assert_eq!(mapped.commit(&poly).unwrap(), srs.commit(&poly).unwrap());
+    assert!(matches!(
+        mapped.g1(10..18),
+        Err(FileError::Checksum {
+            section: Section::G1,
+            chunk: 2
+        })
+    ));
+    assert!(mapped.verify_checksums().is_err());
+    assert!(Srs::load(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_truncated_and_foreign_files() {
+    let srs = Srs::generate(4, &mut rand::thread_rng());
+    let path = path("truncated");
+    srs.save(&path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(matches!(MappedSrs::open(&path), Err(FileError::Truncated)));
+
+    let mut header = bytes.clone();
+    header[20] ^= 1;
+    fs::write(&path, &header).unwrap();
+    assert!(matches!(MappedSrs::open(&path), Err(FileError::Header)));
+
+    fs::write(&path, srs.to_bytes()).unwrap();
+    assert!(matches!(MappedSrs::open(&path), Err(FileError::Magic)));
+    fs::remove_file(&path).unwrap();
+}
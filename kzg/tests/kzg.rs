@@ -0,0 +1,65 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use kzg::{evaluate, KzgError, Srs};
+
+fn polynomial(degree: usize) -> Vec<Scalar> {
+    (0..=degree)
+        .map(|_| Scalar::random(&mut rand::thread_rng()))
+        .collect()
+}
+
+#[test]
+fn openings_verify() {
+    let srs = Srs::generate(8, &mut rand::thread_rng());
+    let poly = polynomial(8);
+    let commitment = srs.commit(&poly).unwrap();
+
+    for z in [Scalar::zero(), Scalar::from(3), -Scalar::one()] {
+        let (y, proof) = srs.open(&poly, z).unwrap();
+        assert_eq!(y, evaluate(&poly, z));
+        assert!(srs.verify(&commitment, z, y, &proof));
+    }
+}
+
+#[test]
+fn rejects_wrong_evaluations() {
+    let srs = Srs::generate(4, &mut rand::thread_rng());
+    let poly = polynomial(3);
+    let commitment = srs.commit(&poly).unwrap();
+    let z = Scalar::from(5);
+    let (y, proof) = srs.open(&poly, z).unwrap();
+
+    assert!(!srs.verify(&commitment, z, y + Scalar::one(), &proof));
+    assert!(!srs.verify(&commitment, Scalar::from(6), y, &proof));
+
+    let other = srs.commit(&polynomial(3)).unwrap();
+    assert!(!srs.verify(&other, z, y, &proof));
+}
+
+#[test]
+fn shares_verify_against_one_point() {
+    // A dealer's polynomial for a 2-of-5 group, opened at each member.
+    let srs = Srs::generate(2, &mut rand::thread_rng());
+    let poly = polynomial(2);
+    let commitment = srs.commit(&poly).unwrap();
+
+    for i in 1..=5u64 {
+        let (share, proof) = srs.open(&poly, Scalar::from(i)).unwrap();
+        assert!(srs.verify(&commitment, Scalar::from(i), share, &proof));
+    }
+}
+
+#[test]
+fn degree_is_bounded_by_the_setup() {
+    let srs = Srs::generate(2, &mut rand::thread_rng());
+    assert_eq!(srs.max_degree(), 2);
+    let poly = polynomial(3);
+    assert_eq!(
+        srs.commit(&poly),
+        Err(KzgError::Degree { degree: 3, max: 2 })
+    );
+    assert_eq!(
+        srs.open(&poly, Scalar::one()),
+        Err(KzgError::Degree { degree: 3, max: 2 })
+    );
+}
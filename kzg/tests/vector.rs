@@ -0,0 +1,83 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use kzg::vector::{Parameters, VectorError};
+
+fn values(len: usize) -> Vec<Scalar> {
+    (0..len)
+        .map(|_| Scalar::random(&mut rand::thread_rng()))
+        .collect()
+}
+
+#[test]
+fn opens_every_position() {
+    let params = Parameters::generate(8, &mut rand::thread_rng());
+    let values = values(8);
+    let commitment = params.commit(&values).unwrap();
+
+    for (i, value) in values.iter().enumerate() {
+        let proof = params.open(&values, i).unwrap();
+        assert!(params.verify(&commitment, i, *value, &proof));
+        assert!(!params.verify(&commitment, i, value + Scalar::one(), &proof));
+        assert!(!params.verify(&commitment, (i + 1) % 8, *value, &proof));
+    }
+    assert_eq!(
+        params.open(&values, 8),
+        Err(VectorError::Index { index: 8, len: 8 })
+    );
+}
+
+#[test]
+fn short_vectors_are_padded_with_zeros() {
+    let params = Parameters::generate(8, &mut rand::thread_rng());
+    let values = values(3);
+    let commitment = params.commit(&values).unwrap();
+
+    let mut padded = values.clone();
+    padded.resize(8, Scalar::zero());
+    assert_eq!(commitment, params.commit(&padded).unwrap());
+    let proof = params.open(&values, 6).unwrap();
+    assert!(params.verify(&commitment, 6, Scalar::zero(), &proof));
+    assert_eq!(
+        params.commit(&self::values(9)),
+        Err(VectorError::Length { len: 9, max: 8 })
+    );
+}
+
+#[test]
+fn aggregates_openings() {
+    let params = Parameters::generate(8, &mut rand::thread_rng());
+    let values = values(8);
+    let commitment = params.commit(&values).unwrap();
+
+    let indices = [1, 4, 7];
+    let openings = indices
+        .iter()
+        .map(|i| (*i, values[*i], params.open(&values, *i).unwrap()))
+        .collect::<Vec<_>>();
+    let proof = params.aggregate(&commitment, &openings).unwrap();
+    assert_eq!(proof, params.open_aggregated(&values, &indices).unwrap());
+
+    let claimed = indices.iter().map(|i| (*i, values[*i])).collect::<Vec<_>>();
+    assert!(params.verify_aggregated(&commitment, &claimed, &proof));
+
+    let mut wrong = claimed.clone();
+    wrong[1].1 += Scalar::one();
+    assert!(!params.verify_aggregated(&commitment, &wrong, &proof));
+    assert!(!params.verify_aggregated(&commitment, &claimed[..2], &proof));
+    let mut duplicated = claimed.clone();
+    duplicated[2] = duplicated[0];
+    assert!(!params.verify_aggregated(&commitment, &duplicated, &proof));
+    assert_eq!(
+        params.open_aggregated(&values, &[2, 2]),
+        Err(VectorError::DuplicateIndex)
+    );
+}
+
+#[test]
+fn openings_are_bound_to_their_commitment() {
+    let params = Parameters::generate(4, &mut rand::thread_rng());
+    let (a, b) = (values(4), values(4));
+    let commitment = params.commit(&a).unwrap();
+    let proof = params.open(&b, 2).unwrap();
+    assert!(!params.verify(&commitment, 2, b[2], &proof));
+}
@@ -0,0 +1,79 @@
+//! Sets up the `tracing` subscriber shared by the binaries of the workspace.
+//! Events go to stderr tagged with the spans they happened in, either as text
+//! for people or as one JSON object per line for log collectors. Records of
+//! the dependencies still using `log`, such as libp2p, are forwarded to it.
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use tracing::level_filters::LevelFilter;
+
+/// The usage of the flags read by [`parse_args`].
+pub const USAGE: &str = "[--log-level off|error|warn|info|debug|trace] [--log-format text|json]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown log format {:?}, expected text or json", s)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Text => "text",
+            Format::Json => "json",
+        })
+    }
+}
+
+/// Installs the subscriber, dropping the events less severe than `level`.
+/// Fails if a subscriber was installed already.
+pub fn init(level: LevelFilter, format: Format) -> Result<(), Box<dyn Error>> {
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr);
+    let installed = match format {
+        Format::Text => subscriber.with_ansi(io::stderr().is_terminal()).try_init(),
+        // Every span the event happened in, not only the innermost one.
+        Format::Json => subscriber
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .try_init(),
+    };
+    installed.map_err(|e| e as Box<dyn Error>)
+}
+
+/// Reads `--log-level` and `--log-format` for the binaries without a command
+/// line parser, defaulting to `info` and text. See [`USAGE`].
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<(LevelFilter, Format), String> {
+    let mut level = LevelFilter::INFO;
+    let mut format = Format::Text;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (arg.as_str(), value) {
+            ("--log-level", Some(value)) => {
+                level = value
+                    .parse()
+                    .map_err(|_| format!("unknown log level {:?}", value))?
+            }
+            ("--log-format", Some(value)) => format = value.parse()?,
+            ("--log-level" | "--log-format", None) => return Err(format!("{} needs a value", arg)),
+            _ => return Err(format!("unexpected argument {:?}", arg)),
+        }
+    }
+    Ok((level, format))
+}
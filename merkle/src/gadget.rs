@@ -0,0 +1,37 @@
+//! Membership in a circuit: the path is private, each level costs a
+//! boolean constraint on the bit of the index, one to order the children
+//! and the hash.
+use crate::{HashGadget, MerkleProof};
+use bls12_381::Scalar;
+use r1cs::{ConstraintSystem, LinearCombination, Variable};
+
+/// Constrains `leaf` to be in the tree of `root`, the index and the
+/// siblings being allocated from the proof, whose leaf is unused. The setup
+/// can be given a proof of zeros of the depth of the tree.
+pub fn verify<H: HashGadget>(
+    cs: &mut ConstraintSystem,
+    root: LinearCombination,
+    leaf: LinearCombination,
+    proof: &MerkleProof,
+) {
+    let node = proof
+        .siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (height, sibling)| {
+            let bit = cs.alloc(Scalar::from(((proof.index >> height) & 1) as u64));
+            cs.enforce(format!("level {} bit", height), bit, bit, bit);
+            let sibling = cs.alloc(*sibling);
+            // The bit swaps the children: `left = node + bit * (sibling -
+            // node)`, and right is what remains of their sum.
+            let swap = cs.mul(
+                format!("level {} swap", height),
+                bit,
+                sibling - node.clone(),
+            );
+            let left = node.clone() + swap;
+            let right = sibling - swap;
+            H::hash_gadget(cs, left, right)
+        });
+    cs.enforce("the root", node, Variable::One, root);
+}
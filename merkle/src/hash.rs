@@ -0,0 +1,65 @@
+//! The two-to-one hashes a tree can be built with.
+use bls12_381::Scalar;
+use r1cs::{ConstraintSystem, LinearCombination};
+use sha2::{Digest, Sha256 as Sha};
+
+/// Hashes two children into their parent.
+pub trait Hasher {
+    fn hash(left: Scalar, right: Scalar) -> Scalar;
+}
+
+/// A hash that can also be constrained in a circuit.
+pub trait HashGadget: Hasher {
+    fn hash_gadget(
+        cs: &mut ConstraintSystem,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> LinearCombination;
+}
+
+/// [`poseidon::hash`]
+#[derive(Debug, Clone, Copy)]
+pub struct Poseidon;
+
+impl Hasher for Poseidon {
+    fn hash(left: Scalar, right: Scalar) -> Scalar {
+        poseidon::hash(&[left, right])
+    }
+}
+
+/// [`mimc::hash`], 660 constraints a level in a circuit.
+#[derive(Debug, Clone, Copy)]
+pub struct Mimc;
+
+impl Hasher for Mimc {
+    fn hash(left: Scalar, right: Scalar) -> Scalar {
+        mimc::hash(&[left, right])
+    }
+}
+
+impl HashGadget for Mimc {
+    fn hash_gadget(
+        cs: &mut ConstraintSystem,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> LinearCombination {
+        mimc::gadget::hash(cs, &[left, right])
+    }
+}
+
+/// SHA-256 of the two children's bytes, reduced into the field. Far too
+/// expensive for a circuit, for trees only checked outside of one.
+#[derive(Debug, Clone, Copy)]
+pub struct Sha256;
+
+impl Hasher for Sha256 {
+    fn hash(left: Scalar, right: Scalar) -> Scalar {
+        let digest = Sha::new()
+            .chain(left.to_bytes())
+            .chain(right.to_bytes())
+            .finalize();
+        let mut wide = [0; 64];
+        wide[..32].copy_from_slice(&digest);
+        Scalar::from_bytes_wide(&wide)
+    }
+}
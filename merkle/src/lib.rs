@@ -0,0 +1,141 @@
+//! Merkle trees over field elements, generic over the two-to-one hash, with
+//! membership proofs that can be checked outside of a circuit or in one.
+//!
+//! The leaves are padded with zeros to a power of two. A proof of the leaf
+//! at `index` is its siblings from the bottom up, the bits of the index,
+//! lowest first, telling on which side each of them is.
+use bls12_381::Scalar;
+use std::fmt;
+use std::marker::PhantomData;
+
+pub mod gadget;
+pub mod hash;
+
+pub use hash::{HashGadget, Hasher, Mimc, Poseidon, Sha256};
+
+#[derive(Debug, Clone)]
+pub struct MerkleTree<H> {
+    /// The leaves first, the root last.
+    levels: Vec<Vec<Scalar>>,
+    hasher: PhantomData<H>,
+}
+
+/// That `leaf` is at `index` in the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub leaf: Scalar,
+    /// From the bottom up.
+    pub siblings: Vec<Scalar>,
+}
+
+impl<H: Hasher> MerkleTree<H> {
+    pub fn new(leaves: &[Scalar]) -> Result<Self, MerkleError> {
+        if leaves.is_empty() {
+            return Err(MerkleError::Empty);
+        }
+        let mut level = leaves.to_vec();
+        level.resize(leaves.len().next_power_of_two(), Scalar::zero());
+        let mut levels = vec![level];
+        while levels.last().unwrap().len() > 1 {
+            let parents = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| H::hash(pair[0], pair[1]))
+                .collect();
+            levels.push(parents);
+        }
+        Ok(MerkleTree {
+            levels,
+            hasher: PhantomData,
+        })
+    }
+
+    pub fn root(&self) -> Scalar {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// The number of levels below the root.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// The leaves, with the padding.
+    pub fn leaves(&self) -> &[Scalar] {
+        &self.levels[0]
+    }
+
+    pub fn prove(&self, index: usize) -> Result<MerkleProof, MerkleError> {
+        let len = self.leaves().len();
+        let leaf = *self
+            .leaves()
+            .get(index)
+            .ok_or(MerkleError::Index { index, len })?;
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(height, level)| level[(index >> height) ^ 1])
+            .collect();
+        Ok(MerkleProof {
+            index,
+            leaf,
+            siblings,
+        })
+    }
+}
+
+impl MerkleProof {
+    /// The root the path leads to.
+    pub fn root<H: Hasher>(&self) -> Scalar {
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(self.leaf, |node, (height, sibling)| {
+                match (self.index >> height) & 1 {
+                    0 => H::hash(node, *sibling),
+                    _ => H::hash(*sibling, node),
+                }
+            })
+    }
+}
+
+/// Checks that the proof leads to `root`.
+pub fn verify<H: Hasher>(root: Scalar, proof: &MerkleProof) -> Result<(), MerkleError> {
+    let depth = proof.siblings.len();
+    if depth < usize::BITS as usize && proof.index >> depth != 0 {
+        return Err(MerkleError::Index {
+            index: proof.index,
+            len: 1 << depth,
+        });
+    }
+    if proof.root::<H>() == root {
+        Ok(())
+    } else {
+        Err(MerkleError::Invalid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    /// A tree needs at least one leaf.
+    Empty,
+    /// The index is past the leaves.
+    Index { index: usize, len: usize },
+    /// The proof doesn't lead to the root.
+    Invalid,
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::Empty => write!(f, "a tree needs at least one leaf"),
+            MerkleError::Index { index, len } => {
+                write!(f, "leaf {} is out of a tree of {} leaves", index, len)
+            }
+            MerkleError::Invalid => write!(f, "the proof doesn't lead to the root"),
+        }
+    }
+}
+
+impl std::error::Error for MerkleError {}
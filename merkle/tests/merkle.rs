@@ -0,0 +1,116 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use merkle::{
+    gadget, verify, Hasher, MerkleError, MerkleProof, MerkleTree, Mimc, Poseidon, Sha256,
+};
+use r1cs::{ConstraintSystem, LinearCombination};
+use rand::thread_rng;
+
+fn leaves(n: usize) -> Vec<Scalar> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| Scalar::random(&mut rng)).collect()
+}
+
+fn proves_every_leaf<H: Hasher>() {
+    let leaves = leaves(5);
+    let tree = MerkleTree::<H>::new(&leaves).unwrap();
+    assert_eq!(tree.depth(), 3);
+    assert_eq!(tree.leaves().len(), 8);
+    for (index, leaf) in leaves.iter().enumerate() {
+        let proof = tree.prove(index).unwrap();
+        assert_eq!(proof.leaf, *leaf);
+        verify::<H>(tree.root(), &proof).unwrap();
+    }
+    assert_eq!(tree.prove(8), Err(MerkleError::Index { index: 8, len: 8 }));
+}
+
+#[test]
+fn proves_with_poseidon() {
+    proves_every_leaf::<Poseidon>();
+}
+
+#[test]
+fn proves_with_mimc() {
+    proves_every_leaf::<Mimc>();
+}
+
+#[test]
+fn proves_with_sha256() {
+    proves_every_leaf::<Sha256>();
+}
+
+#[test]
+fn a_single_leaf_is_the_root() {
+    let leaf = Scalar::from(7);
+    let tree = MerkleTree::<Poseidon>::new(&[leaf]).unwrap();
+    assert_eq!(tree.root(), leaf);
+    verify::<Poseidon>(leaf, &tree.prove(0).unwrap()).unwrap();
+    assert_eq!(
+        MerkleTree::<Poseidon>::new(&[]).map(|tree| tree.root()),
+        Err(MerkleError::Empty)
+    );
+}
+
+#[test]
+fn rejects_a_tampered_proof() {
+    let tree = MerkleTree::<Poseidon>::new(&leaves(4)).unwrap();
+    let proof = tree.prove(2).unwrap();
+
+    let mut wrong_leaf = proof.clone();
+    wrong_leaf.leaf += Scalar::one();
+    assert_eq!(
+        verify::<Poseidon>(tree.root(), &wrong_leaf),
+        Err(MerkleError::Invalid)
+    );
+
+    let mut wrong_index = proof.clone();
+    wrong_index.index = 3;
+    assert_eq!(
+        verify::<Poseidon>(tree.root(), &wrong_index),
+        Err(MerkleError::Invalid)
+    );
+    wrong_index.index = 4;
+    assert_eq!(
+        verify::<Poseidon>(tree.root(), &wrong_index),
+        Err(MerkleError::Index { index: 4, len: 4 })
+    );
+
+    // The same path under another hash.
+    assert_eq!(
+        verify::<Mimc>(tree.root(), &proof),
+        Err(MerkleError::Invalid)
+    );
+}
+
+fn membership(root: Scalar, leaf: Scalar, proof: &MerkleProof) -> ConstraintSystem {
+    let mut cs = ConstraintSystem::new();
+    let root = cs.alloc_input(root);
+    let leaf = cs.alloc(leaf);
+    gadget::verify::<Mimc>(
+        &mut cs,
+        LinearCombination::from(root),
+        LinearCombination::from(leaf),
+        proof,
+    );
+    cs
+}
+
+#[test]
+fn the_gadget_verifies_the_path() {
+    let leaves = leaves(4);
+    let tree = MerkleTree::<Mimc>::new(&leaves).unwrap();
+    for index in [1, 2] {
+        let proof = tree.prove(index).unwrap();
+        membership(tree.root(), leaves[index], &proof)
+            .is_satisfied()
+            .unwrap();
+    }
+
+    let proof = tree.prove(1).unwrap();
+    assert!(membership(tree.root(), leaves[2], &proof)
+        .is_satisfied()
+        .is_err());
+    assert!(membership(tree.root() + Scalar::one(), leaves[1], &proof)
+        .is_satisfied()
+        .is_err());
+}
@@ -0,0 +1,36 @@
+//! MiMC-p/p in an R1CS: three constraints per round, for `x²`, `x⁴` and
+//! `x⁵`, so 330 for a block.
+use crate::constants;
+use r1cs::{ConstraintSystem, LinearCombination};
+
+/// Constrains the encryption of `x` under `key`, returns it.
+pub fn encrypt(
+    cs: &mut ConstraintSystem,
+    key: LinearCombination,
+    mut x: LinearCombination,
+) -> LinearCombination {
+    for (round, c) in constants().iter().enumerate() {
+        let t = x + key.clone() + LinearCombination::constant(*c);
+        let squared = cs.mul(
+            format!("mimc round {} squared", round),
+            t.clone(),
+            t.clone(),
+        );
+        let fourth = cs.mul(
+            format!("mimc round {} to the fourth", round),
+            squared,
+            squared,
+        );
+        x = cs
+            .mul(format!("mimc round {} to the fifth", round), fourth, t)
+            .into();
+    }
+    x + key
+}
+
+/// Constrains the hash of the inputs, returns it.
+pub fn hash(cs: &mut ConstraintSystem, inputs: &[LinearCombination]) -> LinearCombination {
+    inputs.iter().fold(LinearCombination::zero(), |h, m| {
+        encrypt(cs, h.clone(), m.clone()) + h + m.clone()
+    })
+}
@@ -0,0 +1,98 @@
+//! MiMC over the scalar field of BLS12-381: the simplest algebraic hash,
+//! one power map per round, which makes it the cheapest to prove in a
+//! circuit and the easiest to follow.
+//!
+//! MiMC-p/p encrypts x under the key k with [`ROUNDS`] rounds of
+//!
+//! x ↦ (x + k + c_i)⁵
+//!
+//! and a last addition of k. `x⁵` is the smallest power that permutes the
+//! field, as 3 divides `r - 1`, and it takes `⌈log_5 r⌉ = 110` rounds for
+//! the degree of the cipher to cover the field. [`hash`] turns it into a
+//! hash with the Miyaguchi-Preneel construction:
+//!
+//! h_0 = 0, h_{i+1} = E_{h_i}(m_i) + h_i + m_i
+//!
+//! The Feistel variant MiMC-2p/p permutes pairs of elements instead, the
+//! power map of one half being added to the other, over twice as many
+//! rounds. It makes a sponge, see [`feistel_hash`].
+//!
+//! The round constants are SHA-512 of a domain string and their index,
+//! reduced into the field, except for the first one which is zero. See
+//! [`gadget`] for the circuits.
+use bls12_381::Scalar;
+use sha2::{Digest, Sha512};
+use std::sync::OnceLock;
+
+pub mod gadget;
+
+/// The rounds of MiMC-p/p.
+pub const ROUNDS: usize = 110;
+/// The rounds of MiMC-2p/p.
+pub const FEISTEL_ROUNDS: usize = 2 * ROUNDS;
+
+/// The round constants of MiMC-p/p.
+pub fn constants() -> &'static [Scalar] {
+    static CONSTANTS: OnceLock<Vec<Scalar>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| generate(b"zk-lab/mimc/bls12-381/110", ROUNDS))
+}
+
+/// The round constants of MiMC-2p/p.
+pub fn feistel_constants() -> &'static [Scalar] {
+    static CONSTANTS: OnceLock<Vec<Scalar>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| generate(b"zk-lab/mimc-feistel/bls12-381/220", FEISTEL_ROUNDS))
+}
+
+fn generate(domain: &[u8], count: usize) -> Vec<Scalar> {
+    std::iter::once(Scalar::zero())
+        .chain((1..count as u64).map(|i| {
+            let mut wide = [0; 64];
+            wide.copy_from_slice(
+                &Sha512::new()
+                    .chain(domain)
+                    .chain(i.to_be_bytes())
+                    .finalize(),
+            );
+            Scalar::from_bytes_wide(&wide)
+        }))
+        .collect()
+}
+
+/// `x⁵`
+pub fn power(x: Scalar) -> Scalar {
+    x.square().square() * x
+}
+
+/// MiMC-p/p: encrypts `x` under `key`.
+pub fn encrypt(key: Scalar, x: Scalar) -> Scalar {
+    constants().iter().fold(x, |x, c| power(x + key + c)) + key
+}
+
+/// MiMC-2p/p: permutes `(left, right)` under `key`, each round adding the
+/// power map of the left half to the right one and swapping them.
+pub fn feistel(key: Scalar, (left, right): (Scalar, Scalar)) -> (Scalar, Scalar) {
+    feistel_constants()
+        .iter()
+        .fold((left, right), |(left, right), c| {
+            (right + power(left + key + c), left)
+        })
+}
+
+/// Hashes any number of field elements to one with MiMC-p/p.
+pub fn hash(inputs: &[Scalar]) -> Scalar {
+    inputs
+        .iter()
+        .fold(Scalar::zero(), |h, m| encrypt(h, *m) + h + m)
+}
+
+/// Hashes any number of field elements to one with a sponge over
+/// MiMC-2p/p, absorbing into the left half, the right one being the
+/// capacity. The capacity starts at the number of inputs so that inputs of
+/// different lengths never collide.
+pub fn feistel_hash(inputs: &[Scalar]) -> Scalar {
+    let state = (Scalar::zero(), Scalar::from(inputs.len() as u64));
+    let state = inputs.iter().fold(state, |(left, right), m| {
+        feistel(Scalar::zero(), (left + m, right))
+    });
+    feistel(Scalar::zero(), state).0
+}
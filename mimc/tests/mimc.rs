@@ -0,0 +1,82 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use mimc::{constants, encrypt, feistel, feistel_constants, feistel_hash, gadget, hash, power};
+use r1cs::{ConstraintSystem, LinearCombination, Variable};
+use rand::thread_rng;
+
+#[test]
+fn constants_are_fixed() {
+    assert_eq!(constants().len(), mimc::ROUNDS);
+    assert_eq!(feistel_constants().len(), mimc::FEISTEL_ROUNDS);
+    assert_eq!(constants()[0], Scalar::zero());
+    assert_eq!(feistel_constants()[0], Scalar::zero());
+    assert_ne!(constants()[1], feistel_constants()[1]);
+}
+
+#[test]
+fn encrypts_round_by_round() {
+    let mut rng = thread_rng();
+    let key = Scalar::random(&mut rng);
+    let x = Scalar::random(&mut rng);
+    let mut y = x;
+    for c in constants() {
+        let t = y + key + c;
+        y = t * t * t * t * t;
+    }
+    assert_eq!(encrypt(key, x), y + key);
+    assert_ne!(encrypt(key + Scalar::one(), x), encrypt(key, x));
+}
+
+#[test]
+fn the_feistel_rounds_invert() {
+    let mut rng = thread_rng();
+    let key = Scalar::random(&mut rng);
+    let input = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+    let (mut left, mut right) = feistel(key, input);
+    for c in feistel_constants().iter().rev() {
+        let previous = right;
+        right = left - power(right + key + c);
+        left = previous;
+    }
+    assert_eq!((left, right), input);
+}
+
+#[test]
+fn hashes_tell_inputs_apart() {
+    let (a, b) = (Scalar::from(1), Scalar::from(2));
+    assert_eq!(hash(&[a, b]), hash(&[a, b]));
+    assert_ne!(hash(&[a, b]), hash(&[b, a]));
+    assert_ne!(hash(&[a]), hash(&[a, Scalar::zero()]));
+    assert_eq!(feistel_hash(&[a, b]), feistel_hash(&[a, b]));
+    assert_ne!(feistel_hash(&[a, b]), feistel_hash(&[b, a]));
+    assert_ne!(feistel_hash(&[]), feistel_hash(&[Scalar::zero()]));
+}
+
+#[test]
+fn the_gadget_computes_the_hash() {
+    let mut rng = thread_rng();
+    let inputs = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+    let digest = hash(&inputs);
+
+    let mut cs = ConstraintSystem::new();
+    let output = cs.alloc_input(digest);
+    let lcs: Vec<_> = inputs
+        .iter()
+        .map(|x| LinearCombination::from(cs.alloc(*x)))
+        .collect();
+    let hashed = gadget::hash(&mut cs, &lcs);
+    assert_eq!(cs.value(&hashed), digest);
+    cs.enforce("the digest", hashed, Variable::One, output);
+    assert_eq!(cs.num_constraints(), 2 * 3 * mimc::ROUNDS + 1);
+    cs.is_satisfied().unwrap();
+}
+
+#[test]
+fn the_gadget_rejects_a_wrong_digest() {
+    let mut cs = ConstraintSystem::new();
+    let output = cs.alloc_input(hash(&[Scalar::one()]));
+    let x = LinearCombination::from(cs.alloc(Scalar::from(2)));
+    let hashed = gadget::hash(&mut cs, &[x]);
+    cs.enforce("the digest", hashed, Variable::One, output);
+    assert!(cs.is_satisfied().is_err());
+}
@@ -0,0 +1,9 @@
+//! Stops a build without a runtime before it starts, rather than with every
+//! error of the code that needs one.
+fn main() {
+    let tokio = std::env::var_os("CARGO_FEATURE_RT_TOKIO").is_some();
+    let async_std = std::env::var_os("CARGO_FEATURE_RT_ASYNC_STD").is_some();
+    if !tokio && !async_std {
+        println!("cargo::error=Enable either the `rt-async-std` or the `rt-tokio` feature");
+    }
+}
@@ -0,0 +1,46 @@
+//! Remembers the peers in the Kademlia routing table across restarts, so a
+//! node that was part of the network can rejoin it without bootstrap nodes.
+//!
+//! The file holds one address per line, in the `/p2p/<peer id>` terminated
+//! form of `--bootstrap`.
+use crate::cli::parse_bootstrap_node;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Reads the address book at `path`, which is empty if the file doesn't
+/// exist yet.
+pub fn load(path: &Path) -> io::Result<Vec<(PeerId, Multiaddr)>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            parse_bootstrap_node(line).map_err(|e| {
+                let msg = format!("address book line {}: {}", n + 1, e);
+                io::Error::new(io::ErrorKind::InvalidData, msg)
+            })
+        })
+        .collect()
+}
+
+/// Replaces the address book at `path` with `entries`.
+pub fn save(path: &Path, entries: &[(PeerId, Multiaddr)]) -> io::Result<()> {
+    // Written next to the old book first, so a crash can't leave half of it.
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp)?;
+    for (peer_id, address) in entries {
+        let address = address.clone().with(Protocol::P2p(*peer_id));
+        writeln!(file, "{}", address)?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp, path)
+}
@@ -0,0 +1,343 @@
+//! The node's beacons served over HTTP in the shape of drand's API, for the
+//! applications outside the lab consuming them:
+//!
+//! - `GET /chain-info`: the group's public key, the schedule and the scheme.
+//! - `GET /public/latest`: the latest beacon.
+//! - `GET /public/<round>`: the beacon of a round, a 404 until it's there.
+//!
+//! The JSON has the fields of drand's, but the schemes are ours, so the
+//! beacons don't verify as drand's would: see the `beacon` crate for what is
+//! signed. The chain hash is the SHA-256 of the period and genesis time, as
+//! big-endian `u64` seconds, the compressed public key and the session.
+//!
+//! Like the metrics endpoint it is a bare responder, the node only ever has
+//! a handful of small documents to answer with.
+use crate::group_key::GroupKey;
+use crate::metrics::{ACCEPT_BACKOFF, REQUEST_TIMEOUT};
+use crate::runtime::{self, TcpListener};
+use beacon::{genesis_seed, Beacon, Chain, Schedule, Scheme};
+use futures::io::BufReader;
+use futures::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tracing::{info, warn};
+
+#[derive(Serialize)]
+struct ChainInfo {
+    public_key: String,
+    period: u64,
+    genesis_time: u64,
+    hash: String,
+    #[serde(rename = "schemeID")]
+    scheme_id: &'static str,
+    metadata: Metadata,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    #[serde(rename = "beaconID")]
+    beacon_id: String,
+}
+
+#[derive(Serialize)]
+struct BeaconJson {
+    round: u64,
+    randomness: String,
+    signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_signature: Option<String>,
+}
+
+/// The beacons to serve, shared with the node's event loop.
+#[derive(Clone)]
+pub struct BeaconApi {
+    info: Arc<String>,
+    scheme: Scheme,
+    /// What round 1 chains to.
+    seed: [u8; 32],
+    /// Round `i + 1` at index `i`.
+    beacons: Arc<Mutex<Vec<Beacon>>>,
+}
+
+impl BeaconApi {
+    /// Serves the beacons already in `chain`, and the ones [`push`]ed later.
+    ///
+    /// [`push`]: BeaconApi::push
+    pub fn new(group: &GroupKey, schedule: &Schedule, chain: &Chain) -> Self {
+        let public_key = group.public_key.to_compressed();
+        let period = schedule.period().as_secs();
+        let genesis_time = schedule
+            .genesis()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |genesis| genesis.as_secs());
+        let hash = Sha256::new()
+            .chain(period.to_be_bytes())
+            .chain(genesis_time.to_be_bytes())
+            .chain(public_key)
+            .chain(group.session.as_bytes())
+            .finalize();
+        let info = ChainInfo {
+            public_key: hex::encode(public_key),
+            period,
+            genesis_time,
+            hash: hex::encode(hash),
+            scheme_id: match chain.scheme() {
+                Scheme::Chained => "zk-lab-bls-chained",
+                Scheme::Unchained => "zk-lab-bls-unchained",
+            },
+            metadata: Metadata {
+                beacon_id: group.session.clone(),
+            },
+        };
+        let beacons = (1..chain.next_round())
+            .filter_map(|round| chain.get(round).copied())
+            .collect();
+
+        BeaconApi {
+            info: Arc::new(
+                serde_json::to_string(&info).expect("Chain info is always serializable"),
+            ),
+            scheme: chain.scheme(),
+            seed: genesis_seed(&group.public_key),
+            beacons: Arc::new(Mutex::new(beacons)),
+        }
+    }
+
+    /// Adds a beacon the node stored.
+    pub fn push(&self, beacon: Beacon) {
+        let mut beacons = self.beacons.lock().unwrap();
+        if beacon.round == beacons.len() as u64 + 1 {
+            beacons.push(beacon);
+        }
+    }
+
+    /// Serves the beacons at `address` until the node exits.
+    pub async fn serve(&self, address: SocketAddr) -> io::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        info!(
+            "Serving the beacon on http://{}/public/latest",
+            listener.local_addr()?
+        );
+
+        let api = self.clone();
+        runtime::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        // Most likely out of file descriptors, which accepting
+                        // again right away won't help with.
+                        warn!("Failed to accept a connection: {}", e);
+                        runtime::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                };
+                let api = api.clone();
+                runtime::spawn(async move {
+                    let response = api.respond(runtime::compat(stream));
+                    if let Err(e) = runtime::timeout(REQUEST_TIMEOUT, response).await {
+                        warn!("Failed to serve the beacon: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+
+    /// The JSON of the beacon of `round`, the latest one if `None`.
+    fn beacon(&self, round: Option<u64>) -> Option<String> {
+        let beacons = self.beacons.lock().unwrap();
+        let index = match round {
+            Some(round) => usize::try_from(round.checked_sub(1)?).ok()?,
+            None => beacons.len().checked_sub(1)?,
+        };
+        let beacon = beacons.get(index)?;
+        let previous_signature = match (self.scheme, index) {
+            (Scheme::Unchained, _) => None,
+            (Scheme::Chained, 0) => Some(hex::encode(self.seed)),
+            (Scheme::Chained, _) => Some(hex::encode(beacons[index - 1].signature.to_compressed())),
+        };
+        let json = BeaconJson {
+            round: beacon.round,
+            randomness: hex::encode(beacon.randomness()),
+            signature: hex::encode(beacon.signature.to_compressed()),
+            previous_signature,
+        };
+        Some(serde_json::to_string(&json).expect("Beacons are always serializable"))
+    }
+
+    async fn respond<S>(&self, stream: S) -> io::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        // Only the request line matters, the headers are read and ignored.
+        let (reader, mut writer) = stream.split();
+        let mut reader = BufReader::new(reader.take(8 * 1024));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await?;
+        let mut header = String::new();
+        while reader.read_line(&mut header).await? > 2 {
+            header.clear();
+        }
+
+        let path = request_line
+            .strip_prefix("GET ")
+            .and_then(|rest| rest.split(' ').next());
+        let body = match path {
+            Some("/chain-info") => Some(self.info.to_string()),
+            Some("/public/latest") => self.beacon(None),
+            Some(path) => path
+                .strip_prefix("/public/")
+                .and_then(|round| round.parse().ok())
+                .and_then(|round| self.beacon(Some(round))),
+            None => None,
+        };
+        let (status, content_type, body) = match body {
+            Some(body) => ("200 OK", "application/json", body),
+            None => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        };
+
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+            status,
+            content_type,
+            body.len()
+        );
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(body.as_bytes()).await?;
+        writer.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epoch::tests::committee;
+    use bls_shamir::secret::SecretKey;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    /// A chain of `rounds` beacons signed by a group of one, and its API.
+    fn api(scheme: Scheme, rounds: u64) -> (BeaconApi, Chain, SecretKey) {
+        let (group, _, mut shares) = committee(1, 0);
+        let secret = shares.remove(0);
+        let mut chain = Chain::new(group.public_key, scheme);
+        for _ in 0..rounds {
+            chain.append(next(&chain, &secret)).unwrap();
+        }
+        let schedule = Schedule::new(
+            UNIX_EPOCH + Duration::from_secs(1000),
+            Duration::from_secs(30),
+        );
+        (BeaconApi::new(&group, &schedule, &chain), chain, secret)
+    }
+
+    fn next(chain: &Chain, secret: &SecretKey) -> Beacon {
+        Beacon {
+            round: chain.next_round(),
+            signature: (chain.next_hash() * secret.as_scalar()).into(),
+        }
+    }
+
+    fn json(api: &BeaconApi, round: Option<u64>) -> Option<Value> {
+        api.beacon(round)
+            .map(|json| serde_json::from_str(&json).unwrap())
+    }
+
+    fn signature(chain: &Chain, round: u64) -> String {
+        hex::encode(chain.get(round).unwrap().signature.to_compressed())
+    }
+
+    #[test]
+    fn beacons_are_served_by_round() {
+        let (api, mut chain, secret) = api(Scheme::Chained, 2);
+        let third = next(&chain, &secret);
+        chain.append(third).unwrap();
+        api.push(third);
+
+        let first = json(&api, Some(1)).unwrap();
+        assert_eq!(first["round"], 1);
+        assert_eq!(first["signature"], signature(&chain, 1));
+        assert_eq!(
+            first["previous_signature"],
+            hex::encode(genesis_seed(chain.public_key()))
+        );
+        assert_eq!(
+            json(&api, Some(2)).unwrap()["previous_signature"],
+            signature(&chain, 1)
+        );
+
+        let latest = json(&api, None).unwrap();
+        assert_eq!(latest["round"], 3);
+        assert_eq!(
+            latest["randomness"],
+            hex::encode(chain.get(3).unwrap().randomness())
+        );
+        assert_eq!(json(&api, Some(0)), None);
+        assert_eq!(json(&api, Some(4)), None);
+    }
+
+    #[test]
+    fn only_the_next_round_is_pushed() {
+        let (api, chain, secret) = api(Scheme::Chained, 1);
+        let mut skipped = next(&chain, &secret);
+        skipped.round += 1;
+        api.push(skipped);
+        api.push(*chain.get(1).unwrap());
+        assert_eq!(json(&api, None).unwrap()["round"], 1);
+    }
+
+    #[test]
+    fn unchained_beacons_have_no_previous_signature() {
+        let (api, _, _) = api(Scheme::Unchained, 2);
+        assert!(json(&api, Some(2))
+            .unwrap()
+            .get("previous_signature")
+            .is_none());
+        assert!(json(&api, Some(1))
+            .unwrap()
+            .get("previous_signature")
+            .is_none());
+    }
+
+    /// The status line and body of the answer to a GET of `path`.
+    fn get(api: &BeaconApi, path: &str) -> (String, String) {
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        let mut stream = Cursor::new(request.clone().into_bytes());
+        block_on(api.respond(&mut stream)).unwrap();
+        let response = String::from_utf8(stream.into_inner().split_off(request.len())).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().to_string();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn requests_are_routed_by_path() {
+        let (api, _, _) = api(Scheme::Chained, 2);
+
+        let (status, body) = get(&api, "/chain-info");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let info: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(info["period"], 30);
+        assert_eq!(info["genesis_time"], 1000);
+        assert_eq!(info["schemeID"], "zk-lab-bls-chained");
+        assert_eq!(info["metadata"]["beaconID"], "test");
+
+        let (status, body) = get(&api, "/public/latest");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["round"], 2);
+        let (status, body) = get(&api, "/public/1");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["round"], 1);
+
+        for path in ["/public/3", "/public/one", "/metrics"] {
+            assert_eq!(get(&api, path).0, "HTTP/1.1 404 Not Found");
+        }
+    }
+}
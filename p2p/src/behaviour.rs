@@ -0,0 +1,147 @@
+//! The network behaviour of a node: gossipsub carries the chat topic, mDNS
+//! finds peers on the local network and Kademlia finds them everywhere else.
+//! Shares and whispers meant for a single peer each go over their own
+//! request-response protocol, and so do catch-up requests for missed
+//! envelopes, files and private set intersections. The relay client
+//! carries our own relayed connections, the relay server those of other
+//! peers with `--relay-server`, and DCUtR replaces relayed connections with
+//! direct ones by hole punching. Identify tells us the addresses and
+//! protocols of the peers we connect to, ping how far away they are.
+use crate::catch_up::{CatchUpCodec, CatchUpRequest, CatchUpResponse};
+use crate::psi::{PsiCodec, PsiRequest, PsiResponse};
+use crate::share::{Ack, ShareCodec, ShareRequest};
+use crate::transfer::{FileCodec, FileRequest, FileResponse};
+use crate::whisper::{WhisperCodec, WhisperRequest};
+use libp2p::kad::store::MemoryStore;
+use libp2p::request_response::{self, Event as RequestResponseEvent};
+use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::swarm::NetworkBehaviour;
+use libp2p::{dcutr, gossipsub, identify, kad, ping, relay, StreamProtocol};
+
+#[cfg(feature = "mdns")]
+pub use crate::runtime::Mdns;
+#[cfg(feature = "mdns")]
+pub use libp2p::mdns::Event as MdnsEvent;
+
+/// Without the `mdns` feature the mDNS behaviour is always off.
+#[cfg(not(feature = "mdns"))]
+pub type Mdns = libp2p::swarm::dummy::Behaviour;
+#[cfg(not(feature = "mdns"))]
+pub type MdnsEvent = void::Void;
+
+/// Our DHT is separate from the IPFS one, so we use our own protocol name.
+pub const KADEMLIA_PROTOCOL: StreamProtocol = StreamProtocol::new("/zk-lab/kad/1.0.0");
+
+#[derive(NetworkBehaviour)]
+#[behaviour(to_swarm = "Event")]
+pub struct Behaviour {
+    pub gossipsub: gossipsub::Behaviour,
+    pub mdns: Toggle<Mdns>,
+    pub kademlia: kad::Behaviour<MemoryStore>,
+    pub share: request_response::Behaviour<ShareCodec>,
+    pub whisper: request_response::Behaviour<WhisperCodec>,
+    pub catch_up: request_response::Behaviour<CatchUpCodec>,
+    pub file: request_response::Behaviour<FileCodec>,
+    pub psi: request_response::Behaviour<PsiCodec>,
+    /// Only there with `--relay-server`, an open relay lends anyone our
+    /// bandwidth.
+    pub relay: Toggle<relay::Behaviour>,
+    pub relay_client: relay::client::Behaviour,
+    pub dcutr: dcutr::Behaviour,
+    pub identify: identify::Behaviour,
+    pub ping: ping::Behaviour,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    Gossipsub(gossipsub::Event),
+    Mdns(MdnsEvent),
+    Kademlia(kad::Event),
+    Share(RequestResponseEvent<ShareRequest, Ack>),
+    Whisper(RequestResponseEvent<WhisperRequest, Ack>),
+    CatchUp(RequestResponseEvent<CatchUpRequest, CatchUpResponse>),
+    File(RequestResponseEvent<FileRequest, FileResponse>),
+    Psi(RequestResponseEvent<PsiRequest, PsiResponse>),
+    Relay(relay::Event),
+    RelayClient(relay::client::Event),
+    Dcutr(dcutr::Event),
+    Identify(identify::Event),
+    Ping(ping::Event),
+}
+
+impl From<gossipsub::Event> for Event {
+    fn from(event: gossipsub::Event) -> Self {
+        Event::Gossipsub(event)
+    }
+}
+
+impl From<MdnsEvent> for Event {
+    fn from(event: MdnsEvent) -> Self {
+        Event::Mdns(event)
+    }
+}
+
+impl From<kad::Event> for Event {
+    fn from(event: kad::Event) -> Self {
+        Event::Kademlia(event)
+    }
+}
+impl From<RequestResponseEvent<ShareRequest, Ack>> for Event {
+    fn from(event: RequestResponseEvent<ShareRequest, Ack>) -> Self {
+        Event::Share(event)
+    }
+}
+
+impl From<RequestResponseEvent<WhisperRequest, Ack>> for Event {
+    fn from(event: RequestResponseEvent<WhisperRequest, Ack>) -> Self {
+        Event::Whisper(event)
+    }
+}
+
+impl From<RequestResponseEvent<CatchUpRequest, CatchUpResponse>> for Event {
+    fn from(event: RequestResponseEvent<CatchUpRequest, CatchUpResponse>) -> Self {
+        Event::CatchUp(event)
+    }
+}
+
+impl From<RequestResponseEvent<FileRequest, FileResponse>> for Event {
+    fn from(event: RequestResponseEvent<FileRequest, FileResponse>) -> Self {
+        Event::File(event)
+    }
+}
+
+impl From<RequestResponseEvent<PsiRequest, PsiResponse>> for Event {
+    fn from(event: RequestResponseEvent<PsiRequest, PsiResponse>) -> Self {
+        Event::Psi(event)
+    }
+}
+
+impl From<relay::Event> for Event {
+    fn from(event: relay::Event) -> Self {
+        Event::Relay(event)
+    }
+}
+
+impl From<relay::client::Event> for Event {
+    fn from(event: relay::client::Event) -> Self {
+        Event::RelayClient(event)
+    }
+}
+
+impl From<dcutr::Event> for Event {
+    fn from(event: dcutr::Event) -> Self {
+        Event::Dcutr(event)
+    }
+}
+
+impl From<identify::Event> for Event {
+    fn from(event: identify::Event) -> Self {
+        Event::Identify(event)
+    }
+}
+
+impl From<ping::Event> for Event {
+    fn from(event: ping::Event) -> Self {
+        Event::Ping(event)
+    }
+}
@@ -0,0 +1,218 @@
+//! Lets a node that was offline ask a peer for the envelopes it missed.
+//!
+//! The request is the timestamp of the newest envelope the node has, as eight
+//! big endian bytes, and the peer answers with the envelopes it stored since,
+//! bincode encoded. Both are sent with a varint length prefix. A peer without
+//! a store answers with none.
+use crate::envelope::SignedEnvelope;
+use crate::share::{read_length_prefixed, write_length_prefixed};
+use crate::store::{Store, StoredMessage};
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::gossipsub::TopicHash;
+use libp2p::request_response::{self, Codec};
+use libp2p::PeerId;
+use std::io;
+use tracing::{debug, warn};
+
+/// The most envelopes sent in one response, a node far behind asks again
+/// from the last one it got.
+pub const MAX_MESSAGES: usize = 256;
+
+/// Dealings of large committees are big, this leaves room for a few hundred.
+pub const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct CatchUpProtocol;
+
+impl AsRef<str> for CatchUpProtocol {
+    fn as_ref(&self) -> &str {
+        "/zk-lab/catch-up/1.0.0"
+    }
+}
+
+/// Asks for the envelopes stamped at or after `since`, in milliseconds since
+/// the epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatchUpRequest {
+    pub since: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchUpResponse(pub Vec<StoredMessage>);
+
+/// Keeps an envelope for the peers that missed it, `false` if it was kept
+/// already. The node carries on without the store if writing fails.
+pub fn keep(store: &Store, topic: &TopicHash, signed: SignedEnvelope) -> bool {
+    let message = StoredMessage {
+        topic: topic.to_string(),
+        signed,
+    };
+    store.add_message(&message).unwrap_or_else(|e| {
+        warn!("Failed to store a message: {}", e);
+        true
+    })
+}
+
+/// Asks `peer_id` for the envelopes stored since the newest one we have.
+pub fn request(
+    behaviour: &mut request_response::Behaviour<CatchUpCodec>,
+    store: &Store,
+    peer_id: &PeerId,
+) {
+    let since = match store.last_timestamp() {
+        Ok(since) => since.unwrap_or(0),
+        Err(e) => {
+            warn!("Failed to read the store: {}", e);
+            return;
+        }
+    };
+    let request_id = behaviour.send_request(peer_id, CatchUpRequest { since });
+    debug!("Catching up since {} {:?}", since, request_id);
+}
+
+/// Answers a peer catching up with what we stored since its newest
+/// envelope, nothing without a store.
+pub fn answer(store: Option<&Store>, request: &CatchUpRequest) -> CatchUpResponse {
+    let stored = store
+        .map(|store| store.messages_since(request.since, MAX_MESSAGES))
+        .transpose()
+        .unwrap_or_else(|e| {
+            warn!("Failed to read the store: {}", e);
+            None
+        });
+    let messages = stored.unwrap_or_default();
+    debug!(
+        "Sending {} envelopes since {}",
+        messages.len(),
+        request.since
+    );
+    CatchUpResponse(messages)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CatchUpCodec;
+
+#[async_trait]
+impl Codec for CatchUpCodec {
+    type Protocol = CatchUpProtocol;
+    type Request = CatchUpRequest;
+    type Response = CatchUpResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _: &CatchUpProtocol,
+        io: &mut T,
+    ) -> io::Result<CatchUpRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_length_prefixed(io, 8).await?;
+        let since = bytes
+            .try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid timestamp"))?;
+        Ok(CatchUpRequest {
+            since: u64::from_be_bytes(since),
+        })
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &CatchUpProtocol,
+        io: &mut T,
+    ) -> io::Result<CatchUpResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_length_prefixed(io, MAX_RESPONSE_SIZE).await?;
+        let messages: Vec<StoredMessage> = bincode::deserialize(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if messages.len() > MAX_MESSAGES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "too many messages",
+            ));
+        }
+        Ok(CatchUpResponse(messages))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &CatchUpProtocol,
+        io: &mut T,
+        CatchUpRequest { since }: CatchUpRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_length_prefixed(io, since.to_be_bytes()).await?;
+        io.close().await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &CatchUpProtocol,
+        io: &mut T,
+        CatchUpResponse(messages): CatchUpResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let bytes = bincode::serialize(&messages).expect("Messages are always serializable");
+        write_length_prefixed(io, bytes).await?;
+        io.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::{message, TempStore};
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    #[test]
+    fn requests_and_responses_round_trip() {
+        let mut io = Cursor::new(Vec::new());
+        let request = CatchUpRequest { since: 1234 };
+        block_on(CatchUpCodec.write_request(&CatchUpProtocol, &mut io, request)).unwrap();
+        io.set_position(0);
+        let read = block_on(CatchUpCodec.read_request(&CatchUpProtocol, &mut io)).unwrap();
+        assert_eq!(read, request);
+
+        let mut io = Cursor::new(Vec::new());
+        let response = CatchUpResponse(vec![message(10, 1), message(20, 2)]);
+        block_on(CatchUpCodec.write_response(&CatchUpProtocol, &mut io, response.clone())).unwrap();
+        io.set_position(0);
+        let read = block_on(CatchUpCodec.read_response(&CatchUpProtocol, &mut io)).unwrap();
+        assert_eq!(read, response);
+    }
+
+    #[test]
+    fn too_many_messages_are_refused() {
+        let mut io = Cursor::new(Vec::new());
+        let messages = vec![message(10, 1); MAX_MESSAGES + 1];
+        let response = CatchUpResponse(messages);
+        block_on(CatchUpCodec.write_response(&CatchUpProtocol, &mut io, response)).unwrap();
+        io.set_position(0);
+        let error = block_on(CatchUpCodec.read_response(&CatchUpProtocol, &mut io)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn peers_get_what_was_kept_since_their_newest() {
+        let temp = TempStore::new();
+        let topic = TopicHash::from_raw("chat");
+        let (old, new) = (message(10, 1), message(20, 2));
+        assert!(keep(&temp.store, &topic, old.signed));
+        assert!(keep(&temp.store, &topic, new.signed.clone()));
+        assert!(!keep(&temp.store, &topic, new.signed.clone()));
+
+        let request = CatchUpRequest { since: 20 };
+        assert_eq!(
+            answer(Some(&temp.store), &request),
+            CatchUpResponse(vec![new])
+        );
+        assert_eq!(answer(None, &request), CatchUpResponse(Vec::new()));
+    }
+}
@@ -0,0 +1,559 @@
+//! Command line options of the node. The options that used to be read from
+//! the environment can still be set through the same variables, and some can
+//! be set in the config file instead, see `config.rs`.
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use libp2p::gossipsub::ValidationMode;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use logging::Format;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+
+#[derive(Debug, Parser)]
+#[command(name = "p2p", about = "A zk-lab node chatting over gossipsub")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// The config file, see `config.rs` for the format. Defaults to
+    /// `node.toml` if it exists.
+    #[arg(long, value_name = "PATH", env = "P2P_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// The transport connections are made over.
+    #[arg(long, value_enum, default_value_t = TransportKind::Tcp)]
+    pub transport: TransportKind,
+
+    /// Address to listen on, can be repeated. Defaults to a random port on
+    /// all interfaces.
+    #[arg(long, value_name = "MULTIADDR")]
+    pub listen: Vec<Multiaddr>,
+
+    /// Address of a peer to connect to at startup, can be repeated.
+    #[arg(long, value_name = "MULTIADDR")]
+    pub dial: Vec<Multiaddr>,
+
+    /// Relay to listen through when behind a NAT, an address ending in
+    /// `/p2p/<peer id>`. Peers then reach us at
+    /// `<relay>/p2p-circuit/p2p/<our peer id>`.
+    #[arg(long, value_name = "MULTIADDR", value_parser = parse_relay)]
+    pub relay: Vec<Multiaddr>,
+
+    /// Relay connections between other peers. Without it the node only
+    /// listens through the relays it is given and turns down peers asking it
+    /// to relay for them.
+    #[arg(long)]
+    pub relay_server: bool,
+
+    /// A gossipsub topic to join, can be repeated. Chat lines are published
+    /// on the last one. Defaults to `test-net`.
+    #[arg(long)]
+    pub topic: Vec<String>,
+
+    /// File holding the node's keypair, created on first use. Without it the
+    /// peer id changes on every run.
+    #[arg(long, value_name = "PATH")]
+    pub identity: Option<PathBuf>,
+
+    /// EIP-2335 keystore holding the BLS key envelopes are signed with,
+    /// created on first use. Without it a new key is used on every run.
+    #[arg(long, value_name = "PATH", requires = "bls_password")]
+    pub bls_keystore: Option<PathBuf>,
+
+    /// Password of the BLS keystore.
+    #[arg(long, env = "P2P_BLS_PASSWORD", hide_env_values = true)]
+    pub bls_password: Option<String>,
+
+    /// The committee roster, see `roster.rs` for the format. When given,
+    /// only envelopes signed by a member are accepted.
+    #[arg(long, value_name = "PATH")]
+    pub roster: Option<PathBuf>,
+
+    /// The committee roster, in the format of `--roster`. Peers outside the
+    /// committee are also hung up on and their messages ignored, to run a
+    /// closed DKG over a public network. Bootstrap nodes must be members too.
+    #[arg(long, value_name = "PATH", conflicts_with = "roster")]
+    pub committee: Option<PathBuf>,
+
+    /// File the Kademlia routing table is saved to on shutdown and loaded
+    /// from at startup.
+    #[arg(long, value_name = "PATH")]
+    pub address_book: Option<PathBuf>,
+
+    /// Directory of the node's database, keeping the addresses of the peers
+    /// it met and the recent envelopes across restarts. Peers that connect
+    /// are asked for the envelopes missed in between.
+    #[arg(long, value_name = "DIR")]
+    pub store: Option<PathBuf>,
+
+    /// Directory the files peers `/send` us are saved to. Without it they
+    /// are refused.
+    #[arg(long, value_name = "DIR")]
+    pub downloads: Option<PathBuf>,
+
+    /// The largest file, in bytes, peers may send us.
+    #[arg(long, value_name = "BYTES", default_value = "1073741824")]
+    pub max_download_size: u64,
+
+    /// File of the elements, one per line, that peers running `/psi` with
+    /// us intersect their own with. Without it they are refused.
+    #[arg(long, value_name = "PATH")]
+    pub psi_set: Option<PathBuf>,
+
+    /// Address to serve Prometheus metrics on, at `/metrics`.
+    #[arg(long, value_name = "ADDR")]
+    pub metrics: Option<SocketAddr>,
+
+    /// The least severe events logged on stderr: off, error, warn,
+    /// info, debug or trace.
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        env = "P2P_LOG_LEVEL",
+        default_value = "info"
+    )]
+    pub log_level: LevelFilter,
+
+    /// Whether events are logged as text or as JSON lines.
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    pub log_format: Format,
+
+    /// Don't look for peers on the local network, only reach the peers dialed
+    /// and the ones found through the bootstrap nodes. Keeps experiments
+    /// spanning several machines from pulling in unrelated peers on the LAN.
+    #[arg(long)]
+    pub no_mdns: bool,
+
+    /// Kademlia bootstrap node, an address ending in `/p2p/<peer id>`.
+    #[arg(
+        long,
+        value_name = "MULTIADDR",
+        env = "P2P_BOOTSTRAP",
+        value_delimiter = ',',
+        value_parser = parse_bootstrap_node
+    )]
+    pub bootstrap: Vec<(PeerId, Multiaddr)>,
+
+    /// Seconds between two Kademlia bootstraps.
+    #[arg(
+        long,
+        value_name = "SECS",
+        env = "P2P_BOOTSTRAP_INTERVAL",
+        default_value = "300",
+        value_parser = parse_secs
+    )]
+    pub bootstrap_interval: Duration,
+
+    /// Peer to always forward gossipsub messages to, can be repeated.
+    #[arg(long, value_name = "PEER_ID")]
+    pub explicit_peer: Vec<PeerId>,
+
+    /// How often to try reconnecting to a bootstrap node or committee member
+    /// before giving up on it.
+    #[arg(long, value_name = "COUNT", default_value = "8", value_parser = clap::value_parser!(u32).range(1..))]
+    pub redial_attempts: u32,
+
+    /// Offences within the ban duration after which a peer is banned.
+    #[arg(long, value_name = "COUNT", default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+    pub strike_limit: u32,
+
+    /// Seconds a peer stays banned.
+    #[arg(long, value_name = "SECS", default_value = "600", value_parser = parse_secs)]
+    pub ban_duration: Duration,
+
+    /// Messages a peer may send us per second, over gossipsub and requests
+    /// combined. Messages over the limit are dropped.
+    #[arg(long, value_name = "COUNT", default_value = "50", value_parser = clap::value_parser!(u32).range(1..))]
+    pub rate_limit: u32,
+
+    /// Messages a peer may send us at once, before the rate limit kicks in.
+    #[arg(long, value_name = "COUNT", default_value = "100", value_parser = clap::value_parser!(u32).range(1..))]
+    pub rate_burst: u32,
+
+    /// Enable gossipsub peer scoring, which also penalizes the peers that
+    /// publish messages we reject.
+    #[arg(long)]
+    pub peer_scoring: bool,
+
+    /// How strictly gossipsub checks message signatures.
+    #[arg(
+        long,
+        value_enum,
+        env = "P2P_VALIDATION_MODE",
+        default_value_t = Validation::Strict,
+        ignore_case = true
+    )]
+    pub validation_mode: Validation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs a DKG with the rest of the committee, saves this node's share of
+    /// the group key and exits.
+    Dkg(DkgArgs),
+    /// Gets a message signed by the group a DKG left this node in, or signs
+    /// what the other members ask for until shut down.
+    Sign(SignArgs),
+    /// Runs the randomness beacon of the group a DKG left this node in, or
+    /// follows the beacon of a group, until shut down.
+    Beacon(BeaconArgs),
+    /// Runs a powers-of-tau ceremony with the rest of the committee, saves
+    /// the SRS and exits.
+    Setup(SetupArgs),
+    /// Draws shared randomness with the rest of the committee by PVSS,
+    /// prints it and exits.
+    Pvss(PvssArgs),
+    /// Hands the group key a DKG left the committee with over to the next
+    /// committee once every period, until this node leaves the committee or
+    /// is shut down.
+    Rotate(RotateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DkgArgs {
+    /// Names the ceremony, every member runs it with the same session.
+    #[arg(long)]
+    pub session: String,
+
+    /// Degree of the shared polynomial, any `threshold + 1` members can sign
+    /// for the group. May be as high as the committee size less one.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threshold: u32,
+
+    /// How many members may misbehave, at most the threshold, with
+    /// `3 * faults + 1` and `threshold + faults + 1` members at least.
+    /// Defaults to the most the threshold allows.
+    #[arg(long)]
+    pub faults: Option<u32>,
+
+    /// EIP-2335 keystore the share is saved to, the public shares of the
+    /// group go next to it with a `.toml` extension. Defaults to
+    /// `dkg-<session>.json`.
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Password the share is encrypted under.
+    #[arg(long, env = "P2P_DKG_PASSWORD", hide_env_values = true)]
+    pub password: String,
+
+    /// Seconds to wait for every dealer, after which the ones that haven't
+    /// delivered are left out. Unused with `--asynchronous`, which waits for
+    /// no clock.
+    #[arg(long, value_name = "SECS", default_value = "120", value_parser = parse_secs)]
+    pub timeout: Duration,
+
+    /// How many independent group keys to generate in the one ceremony, for
+    /// signing, encryption and a VRF say. With more than one, key `i` is
+    /// saved with `-<i>` appended to the name of the output, and named after
+    /// the session the same way.
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..=16))]
+    pub keys: u32,
+
+    /// Disseminates the dealings with reliable broadcast and settles on
+    /// `n - faults` of them without waiting for rounds, which takes
+    /// `threshold + 2 * faults + 1` members.
+    #[arg(long, requires = "coin_share")]
+    pub asynchronous: bool,
+
+    /// Keystore of this node's share of an earlier DKG of the same
+    /// committee, encrypted under `--password`, whose threshold signatures
+    /// are the coins of `--asynchronous`. The public shares are read from
+    /// next to it.
+    #[arg(long, value_name = "PATH", requires = "asynchronous")]
+    pub coin_share: Option<PathBuf>,
+}
+
+impl DkgArgs {
+    pub fn output(&self) -> PathBuf {
+        self.output
+            .clone()
+            .unwrap_or_else(|| format!("dkg-{}.json", self.session).into())
+    }
+
+    /// Where the share of key `key` goes, the output itself if it is the
+    /// only key.
+    pub fn key_output(&self, key: usize) -> PathBuf {
+        let output = self.output();
+        if self.keys == 1 {
+            return output;
+        }
+        let stem = output
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match output.extension() {
+            Some(extension) => format!("{}-{}.{}", stem, key, extension.to_string_lossy()),
+            None => format!("{}-{}", stem, key),
+        };
+        output.with_file_name(name)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SignArgs {
+    /// Keystore of this node's share, as saved by `dkg`. The public shares
+    /// are read from next to it.
+    #[arg(long, value_name = "PATH")]
+    pub share: PathBuf,
+
+    /// Password of the keystore.
+    #[arg(long, env = "P2P_DKG_PASSWORD", hide_env_values = true)]
+    pub password: String,
+
+    /// Message for the group to sign, this node coordinates the ceremony
+    /// and exits once the signature is published.
+    #[arg(long)]
+    pub message: Option<String>,
+
+    /// Seconds the members asked to sign have to answer, after which others
+    /// are asked in their place.
+    #[arg(long, value_name = "SECS", default_value = "10", value_parser = parse_secs)]
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Args)]
+pub struct BeaconArgs {
+    /// Keystore of this node's share, as saved by `dkg`, to sign the rounds
+    /// with. The public shares are read from next to it.
+    #[arg(
+        long,
+        value_name = "PATH",
+        required_unless_present = "follow",
+        requires = "password"
+    )]
+    pub share: Option<PathBuf>,
+
+    /// Password of the keystore.
+    #[arg(long, env = "P2P_DKG_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+
+    /// Group file, as saved by `dkg`, of the group to only verify and store
+    /// the beacons of, without a share.
+    #[arg(long, value_name = "PATH", conflicts_with = "share")]
+    pub follow: Option<PathBuf>,
+
+    /// Unix time, in seconds, round 1 is due at. Every node of the group
+    /// must use the same.
+    #[arg(long, value_name = "SECS")]
+    pub genesis: u64,
+
+    /// Seconds between two rounds. Every node of the group must use the
+    /// same.
+    #[arg(long, value_name = "SECS", default_value = "30", value_parser = parse_secs)]
+    pub period: Duration,
+
+    /// Signs each round number alone instead of chaining it to the round
+    /// before, so `tlock` can encrypt messages to future rounds. Every node
+    /// of the group must use the same.
+    #[arg(long)]
+    pub unchained: bool,
+
+    /// File the verified beacons are kept in. Defaults to
+    /// `beacon-<session>.chain`.
+    #[arg(long, value_name = "PATH")]
+    pub chain: Option<PathBuf>,
+
+    /// Address to serve the beacons on over HTTP, in the shape of drand's
+    /// API: `/chain-info`, `/public/latest` and `/public/<round>`.
+    #[arg(long, value_name = "ADDRESS")]
+    pub http: Option<SocketAddr>,
+}
+
+impl BeaconArgs {
+    pub fn chain(&self, session: &str) -> PathBuf {
+        self.chain
+            .clone()
+            .unwrap_or_else(|| format!("beacon-{}.chain", session).into())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SetupArgs {
+    /// Names the ceremony, every member runs it with the same session.
+    #[arg(long)]
+    pub session: String,
+
+    /// Largest degree of the polynomials the SRS can commit to. Every member
+    /// must use the same. The whole SRS is published at every turn, which
+    /// caps it.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=65_536))]
+    pub degree: u32,
+
+    /// File the SRS is saved to, in the format of `kzg::Srs::save`.
+    /// Defaults to `srs-<session>.bin`.
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Seconds each member has to take its turn, after which the ceremony
+    /// fails.
+    #[arg(long, value_name = "SECS", default_value = "120", value_parser = parse_secs)]
+    pub timeout: Duration,
+}
+
+impl SetupArgs {
+    pub fn output(&self) -> PathBuf {
+        self.output
+            .clone()
+            .unwrap_or_else(|| format!("srs-{}.bin", self.session).into())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PvssArgs {
+    /// Names the round, every member runs it with the same session.
+    #[arg(long)]
+    pub session: String,
+
+    /// Degree of the shared polynomials, any `threshold + 1` members open
+    /// a dealer's secret. Needs at least `threshold + 2` members.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threshold: u32,
+
+    /// Seconds to wait for every dealing, and then for the secrets to be
+    /// opened.
+    #[arg(long, value_name = "SECS", default_value = "60", value_parser = parse_secs)]
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Args)]
+pub struct RotateArgs {
+    /// Keystore of this node's share of the current epoch, as saved by `dkg`
+    /// or an earlier handoff. Without it the node only joins the next
+    /// committee.
+    #[arg(long, value_name = "PATH")]
+    pub share: Option<PathBuf>,
+
+    /// Group file of the current epoch. Defaults to the one next to the
+    /// share.
+    #[arg(long, value_name = "PATH", required_unless_present = "share")]
+    pub group: Option<PathBuf>,
+
+    /// Password the shares are encrypted under, the current one and those
+    /// of the next epochs.
+    #[arg(long, env = "P2P_DKG_PASSWORD", hide_env_values = true)]
+    pub password: String,
+
+    /// Roster of the next committee, in the format of `--roster`. It is read
+    /// again before every handoff, so the committee can change from one
+    /// epoch to the next.
+    #[arg(long, value_name = "PATH")]
+    pub next: PathBuf,
+
+    /// Degree of the shares of the next committee. Defaults to the current
+    /// threshold.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threshold: Option<u32>,
+
+    /// Unix time, in seconds, the handoff to epoch 1 is due at. Every node
+    /// of both committees must use the same.
+    #[arg(long, value_name = "SECS")]
+    pub genesis: u64,
+
+    /// Seconds between two handoffs. Every node of both committees must use
+    /// the same.
+    #[arg(long, value_name = "SECS", default_value = "86400", value_parser = parse_secs)]
+    pub period: Duration,
+
+    /// Keystore the shares are saved to, the share of epoch `n` with
+    /// `-epoch-<n>` appended to its name and the group file next to it.
+    /// Defaults to `dkg-<session>.json`.
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Seconds to wait for every current member to deal, after which the
+    /// ones that haven't are left out.
+    #[arg(long, value_name = "SECS", default_value = "120", value_parser = parse_secs)]
+    pub timeout: Duration,
+}
+
+impl RotateArgs {
+    /// Where the share of `epoch` of the group of `session` goes.
+    pub fn output(&self, session: &str, epoch: u64) -> PathBuf {
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| format!("dkg-{}.json", session).into());
+        let stem = output
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match output.extension() {
+            Some(extension) => format!("{}-epoch-{}.{}", stem, epoch, extension.to_string_lossy()),
+            None => format!("{}-epoch-{}", stem, epoch),
+        };
+        output.with_file_name(name)
+    }
+}
+
+/// What the node listens and dials on, see [`crate::transport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TransportKind {
+    /// TCP, also accepting WebSocket connections on `/ws` addresses and
+    /// dialing QUIC ones.
+    Tcp,
+    /// WebSocket only, listening on addresses like `/ip4/0.0.0.0/tcp/0/ws`.
+    Ws,
+    /// QUIC only, listening on addresses like `/ip4/0.0.0.0/udp/0/quic-v1`.
+    Quic,
+}
+
+impl TransportKind {
+    /// Where to listen when no `--listen` address is given.
+    pub fn default_listen(self) -> Multiaddr {
+        let address = match self {
+            TransportKind::Tcp => "/ip4/0.0.0.0/tcp/0",
+            TransportKind::Ws => "/ip4/0.0.0.0/tcp/0/ws",
+            TransportKind::Quic => "/ip4/0.0.0.0/udp/0/quic-v1",
+        };
+        address.parse().expect("Valid multiaddr")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Validation {
+    /// Messages must be signed by their author.
+    Strict,
+    /// Signatures are checked when present.
+    Permissive,
+    /// Messages must carry neither a signature nor an author.
+    Anonymous,
+    /// Nothing is checked.
+    None,
+}
+
+impl From<Validation> for ValidationMode {
+    fn from(validation: Validation) -> Self {
+        match validation {
+            Validation::Strict => ValidationMode::Strict,
+            Validation::Permissive => ValidationMode::Permissive,
+            Validation::Anonymous => ValidationMode::Anonymous,
+            Validation::None => ValidationMode::None,
+        }
+    }
+}
+
+pub(crate) fn parse_bootstrap_node(node: &str) -> Result<(PeerId, Multiaddr), String> {
+    let mut address: Multiaddr = node.trim().parse().map_err(|e| format!("{}", e))?;
+    match address.pop() {
+        Some(Protocol::P2p(peer_id)) => Ok((peer_id, address)),
+        _ => Err("the address must end in /p2p/<peer id>".into()),
+    }
+}
+
+fn parse_relay(relay: &str) -> Result<Multiaddr, String> {
+    let address: Multiaddr = relay.trim().parse().map_err(|e| format!("{}", e))?;
+    match address.iter().last() {
+        Some(Protocol::P2p(_)) => Ok(address),
+        _ => Err("the address must end in /p2p/<peer id>".into()),
+    }
+}
+
+fn parse_secs(secs: &str) -> Result<Duration, String> {
+    match secs.parse() {
+        Ok(0) => Err("the interval must be positive".into()),
+        Ok(secs) => Ok(Duration::from_secs(secs)),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
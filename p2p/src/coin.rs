@@ -0,0 +1,203 @@
+//! The common coin of the agreements of the asynchronous DKG, a threshold
+//! BLS signature of the session, proposer and round under a key an earlier
+//! ceremony shared among the committee.
+//!
+//! The signature takes `threshold + 1` partial signatures, more than there
+//! are faulty members, so no one can tell what a coin comes up before an
+//! honest member revealed its share of it. The coin is the low bit of the
+//! hash of the signature, which is the same whichever shares went into it.
+use crate::group_key::GroupKey;
+use bls12_381::{G1Affine, G2Affine};
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature::{self, DST};
+use bls_shamir::threshold::{self, PartialSignature};
+use group::Curve;
+use libp2p::PeerId;
+use sha2::{Digest, Sha256};
+use sigma::Proof;
+use std::fmt;
+use std::sync::Arc;
+
+/// The key the coins of a session are flipped with.
+#[derive(Debug)]
+pub struct CoinKey {
+    session: String,
+    share: SecretKey,
+    /// The index in the group and public share of each member of the
+    /// committee, in the order of the committee.
+    members: Vec<(u64, G1Affine)>,
+    /// Our index in the committee.
+    local: u64,
+    threshold: usize,
+}
+
+impl CoinKey {
+    /// Fails unless the members of `group` are those of the committee,
+    /// sorted, and its threshold leaves the coin out of reach of the
+    /// `faults` and within reach of the others.
+    pub fn new(
+        session: &str,
+        group: &GroupKey,
+        share: SecretKey,
+        members: &[PeerId],
+        local: u64,
+        faults: usize,
+    ) -> Result<Self, CoinError> {
+        let shares = members
+            .iter()
+            .map(|peer_id| {
+                let index = group.index_of(peer_id)?;
+                Some((index, group.member(index)?.public_share))
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|_| group.members.len() == members.len())
+            .ok_or(CoinError::NotTheCommittee)?;
+        if group.threshold < faults || group.threshold + faults >= members.len() {
+            return Err(CoinError::Threshold {
+                threshold: group.threshold,
+                faults,
+                members: members.len(),
+            });
+        }
+        if shares[local as usize - 1].1 != share.public_key() {
+            return Err(CoinError::WrongShare);
+        }
+
+        Ok(CoinKey {
+            session: session.to_string(),
+            share,
+            members: shares,
+            local,
+            threshold: group.threshold,
+        })
+    }
+
+    /// The coin of the agreement on the proposal of `proposer`.
+    pub fn coin(self: &Arc<Self>, proposer: u64) -> ThresholdCoin {
+        ThresholdCoin {
+            key: self.clone(),
+            proposer,
+        }
+    }
+}
+
+/// The coin of one agreement.
+#[derive(Debug, Clone)]
+pub struct ThresholdCoin {
+    key: Arc<CoinKey>,
+    proposer: u64,
+}
+
+impl ThresholdCoin {
+    /// Hashes what the coin of `round` signs.
+    fn hash(&self, round: u32) -> G2Affine {
+        let mut message = b"zk-lab/dkg/coin/".to_vec();
+        message.extend_from_slice(self.key.session.as_bytes());
+        message.extend_from_slice(&self.proposer.to_be_bytes());
+        message.extend_from_slice(&round.to_be_bytes());
+        signature::hash_to_g2(&message, DST).to_affine()
+    }
+
+    /// The partial signature a share of the coin encodes, by the member of
+    /// the committee numbered `node`.
+    fn partial(&self, node: u64, share: &[u8]) -> Option<PartialSignature> {
+        let (index, _) = *self.key.members.get((node as usize).checked_sub(1)?)?;
+        if share.len() != 96 + Proof::SIZE {
+            return None;
+        }
+        let (point, proof) = share.split_at(96);
+        let point = Option::<G2Affine>::from(G2Affine::from_compressed(point.try_into().ok()?))?;
+        Some(PartialSignature {
+            index,
+            point,
+            proof: Proof::from_bytes(proof)?,
+        })
+    }
+}
+
+impl rbc::agreement::Coin<u64> for ThresholdCoin {
+    /// A compressed partial signature followed by its proof.
+    type Share = Vec<u8>;
+
+    fn share(&self, round: u32) -> Vec<u8> {
+        let (index, _) = self.key.members[self.key.local as usize - 1];
+        let partial = threshold::sign_share(
+            index,
+            &self.key.share,
+            &self.hash(round),
+            &mut rand::rngs::OsRng,
+        );
+        [
+            &partial.point.to_compressed()[..],
+            &partial.proof.to_bytes(),
+        ]
+        .concat()
+    }
+
+    fn verify(&self, node: &u64, round: u32, share: &Vec<u8>) -> bool {
+        let partial = match self.partial(*node, share) {
+            Some(partial) => partial,
+            None => return false,
+        };
+        let (_, public_share) = self.key.members[*node as usize - 1];
+        threshold::verify_share(&public_share, &self.hash(round), &partial)
+    }
+
+    fn threshold(&self) -> usize {
+        self.key.threshold + 1
+    }
+
+    fn combine(&self, _: u32, shares: &[(u64, Vec<u8>)]) -> bool {
+        let partials = shares
+            .iter()
+            .take(self.threshold())
+            .filter_map(|(node, share)| self.partial(*node, share))
+            .collect::<Vec<_>>();
+        let signature = threshold::aggregate_shares(&partials)
+            .expect("Members of the committee have distinct indices");
+        Sha256::digest(&signature.to_compressed())[0] & 1 == 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoinError {
+    /// The group of the coin's key isn't shared by the committee.
+    NotTheCommittee,
+    /// The threshold lets the faulty members flip the coin on their own, or
+    /// keeps the others from flipping it.
+    Threshold {
+        threshold: usize,
+        faults: usize,
+        members: usize,
+    },
+    /// Our share doesn't match our public share in the group.
+    WrongShare,
+}
+
+impl fmt::Display for CoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinError::NotTheCommittee => {
+                write!(f, "the coin's group isn't shared by the committee")
+            }
+            CoinError::Threshold {
+                threshold,
+                faults,
+                members,
+            } => write!(
+                f,
+                "the coin's threshold must be from {} to {} with {} faults of {} members, it is {}",
+                faults,
+                members - faults - 1,
+                faults,
+                members,
+                threshold
+            ),
+            CoinError::WrongShare => {
+                write!(f, "the coin's share doesn't match our public share")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CoinError {}
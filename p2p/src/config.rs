@@ -0,0 +1,113 @@
+//! The node's config file, so the setup of a lab node can be kept next to it
+//! rather than in a long command line. Options given on the command line, or
+//! through their environment variables, take precedence over the file.
+//!
+//! ```toml
+//! listen = ["/ip4/0.0.0.0/tcp/4001"]
+//! bootstrap = ["/dns4/boot.example/tcp/4001/p2p/12D3KooW..."]
+//! dial = ["/ip4/10.0.0.2/tcp/4001"]
+//! # Only the peers above and the ones the DHT finds, not the whole LAN.
+//! mdns = false
+//! # Chat lines are published on the last one.
+//! topics = ["dkg", "test-net"]
+//! identity = "node.key"
+//! committee = "committee.txt"
+//! ```
+//!
+//! Relative paths are relative to the directory of the file.
+use crate::cli::{parse_bootstrap_node, Cli};
+use libp2p::Multiaddr;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Read when `--config` isn't given, if it exists.
+pub const DEFAULT_PATH: &str = "node.toml";
+
+/// Chatted on when neither the command line nor the file names a topic.
+pub const DEFAULT_TOPIC: &str = "test-net";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub listen: Vec<String>,
+    pub bootstrap: Vec<String>,
+    pub dial: Vec<String>,
+    pub mdns: Option<bool>,
+    pub topics: Vec<String>,
+    pub identity: Option<PathBuf>,
+    pub committee: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&contents)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for path in [&mut config.identity, &mut config.committee]
+            .into_iter()
+            .flatten()
+        {
+            *path = dir.join(&*path);
+        }
+        Ok(config)
+    }
+}
+
+/// Fills in the options not given on the command line from the config file,
+/// which is `--config` or else `node.toml` if there is one.
+pub fn resolve(cli: Cli) -> Result<Cli, Box<dyn Error>> {
+    let path = cli.config.clone().unwrap_or_else(|| DEFAULT_PATH.into());
+    let config = match Config::load(&path) {
+        Err(e) if cli.config.is_none() && is_not_found(&*e) => Ok(Config::default()),
+        loaded => loaded,
+    };
+    config
+        .and_then(|config| merge(cli, config))
+        .map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+fn merge(mut cli: Cli, config: Config) -> Result<Cli, Box<dyn Error>> {
+    if cli.listen.is_empty() {
+        for address in &config.listen {
+            let parsed = address.parse::<Multiaddr>();
+            cli.listen
+                .push(parsed.map_err(|e| format!("listen {:?}: {}", address, e))?);
+        }
+    }
+    if cli.bootstrap.is_empty() {
+        for node in &config.bootstrap {
+            let parsed = parse_bootstrap_node(node);
+            cli.bootstrap
+                .push(parsed.map_err(|e| format!("bootstrap {:?}: {}", node, e))?);
+        }
+    }
+    if cli.dial.is_empty() {
+        for address in &config.dial {
+            let parsed = address.parse::<Multiaddr>();
+            cli.dial
+                .push(parsed.map_err(|e| format!("dial {:?}: {}", address, e))?);
+        }
+    }
+    // There's no flag turning mDNS back on, the file can only turn it off.
+    cli.no_mdns |= config.mdns == Some(false);
+    if cli.topic.is_empty() {
+        cli.topic = config.topics;
+    }
+    if cli.topic.is_empty() {
+        cli.topic.push(DEFAULT_TOPIC.into());
+    }
+    cli.identity = cli.identity.or(config.identity);
+    // A roster on the command line replaces the committee of the file.
+    if cli.roster.is_none() {
+        cli.committee = cli.committee.or(config.committee);
+    }
+    Ok(cli)
+}
+
+fn is_not_found(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
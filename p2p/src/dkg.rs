@@ -0,0 +1,2343 @@
+//! Runs the DKG of the `dkg` example between the members of a committee,
+//! over the network instead of in a single process.
+//!
+//! Members are numbered from 1 in the order of their peer ids and the
+//! ceremony withstands `faults` misbehaving ones, see
+//! `bls_shamir::dkg_config`. Each member deals a random polynomial of degree
+//! `threshold` per key: it publishes the Feldman commitments with a Schnorr
+//! proof of the constant term, so that no dealer can bias the key, and seals
+//! each member's shares to its peer id, to be sent over the share protocol.
+//! A dealer cheating on any of its polynomials is left out of every key.
+//!
+//! In the round-based mode members echo the digest of the commitments they
+//! got and only use a dealing echoed by `2 * faults + 1` of them. A member
+//! complaining about its share is answered by the dealer revealing it for
+//! everyone to check, and an unanswered complaint leaves the dealer out.
+//! Once no complaint came in for [`COMPLAINT_WINDOW`], members publish the
+//! digest of the dealers they settled on, and only write their keys once a
+//! quorum settled on the same ones.
+//!
+//! The asynchronous mode waits for no clock. Each key is dealt as a
+//! symmetric bivariate polynomial `f(x, y)`, member `i` getting its row
+//! `f(i, y)`, and members vouch for a dealing once their row of it checks
+//! out. Dealings and then proposals of `n - f` dealers `f + t + 1` members
+//! vouched for go over reliable broadcast, a binary agreement per proposal
+//! picks the proposals voted in, and everyone settles on the one of the
+//! lowest member. A member left without its share of a dealer settled on
+//! asks for it, and `t + 1` of the honest members that vouched send it
+//! their row at its index, `f(j, i) = f(i, j)`, from which it interpolates
+//! `f(i, 0)`. The agreements flip a threshold coin, see `coin`, with the
+//! share of a key the committee holds from an earlier ceremony.
+use crate::cli::DkgArgs;
+use crate::coin::{CoinError, CoinKey, ThresholdCoin};
+use crate::envelope::{BroadcastKind, Payload};
+use crate::group_key::{self, GroupKey, Member};
+use crate::roster::Roster;
+use crate::share::ShareRequest;
+use crate::strikes::Offence;
+use crate::whisper;
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use bls_shamir::dkg_config::{ConfigError, DkgConfig};
+use bls_shamir::keystore::{Kdf, Keystore};
+use bls_shamir::secret::{SecretKey, SecretPolynomial};
+use bls_shamir::threshold::interpolate_at_zero;
+use group::ff::Field;
+use group::Curve;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::identity::{ed25519, PublicKey};
+use libp2p::PeerId;
+use rand::{CryptoRng, RngCore};
+use rbc::{Broadcast, Message, Step};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sigma::schnorr;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use zeroize::{Zeroize, Zeroizing};
+
+/// How long a node that got everything waits for complaints before it
+/// settles on the dealers it keeps.
+pub const COMPLAINT_WINDOW: Duration = Duration::from_secs(10);
+
+/// How long a node done with an asynchronous DKG keeps relaying the
+/// broadcasts for the members still at it.
+pub const LINGER: Duration = Duration::from_secs(10);
+
+/// The topic dealings and complaints of `session` are published on.
+pub fn topic(session: &str) -> Topic {
+    Topic::new(format!("zk-lab/dkg/{}", session))
+}
+
+/// What a ceremony generates and how it runs, the same for every member.
+#[derive(Debug, Clone, Copy)]
+pub struct Params {
+    pub threshold: usize,
+    /// `None` for as many as the threshold allows.
+    pub faults: Option<usize>,
+    /// How many independent keys to generate.
+    pub keys: usize,
+    pub asynchronous: bool,
+}
+
+/// What is sealed in a share request, the member's share of each key, or
+/// its row of each in the asynchronous mode. Naming the session keeps
+/// shares from one ceremony from being replayed into another. Points sent
+/// to recover a share are sealed the same way.
+#[derive(Debug, Serialize, Deserialize)]
+struct ShareMessage {
+    session: String,
+    dealer: u64,
+    shares: Vec<[u8; 32]>,
+}
+
+/// What a dealer reliably broadcasts in the asynchronous mode, laid out as
+/// in `Payload::DkgDealing`.
+#[derive(Debug, Serialize, Deserialize)]
+struct DealingMessage {
+    commitments: Vec<Vec<u8>>,
+    proof: Vec<u8>,
+}
+
+/// The state of the asynchronous mode: a broadcast of each member's dealing
+/// and one of its proposal.
+#[derive(Debug, Default)]
+struct Agreement {
+    broadcasts: BTreeMap<(BroadcastKind, u64), Broadcast<u64, Vec<u8>>>,
+    /// Each dealer's commitments to the polynomial of each key, by column:
+    /// column `l` commits to the coefficients of `x^k y^l`.
+    matrices: BTreeMap<u64, Vec<Vec<Vec<G1Projective>>>>,
+    /// The digest of each dealing delivered, which members vouch for.
+    digests: BTreeMap<u64, [u8; 32]>,
+    /// Our row of each dealer's polynomial of each key, by coefficient.
+    rows: BTreeMap<u64, Vec<Vec<SecretKey>>>,
+    /// The checked points of other members' rows at our index, by dealer
+    /// and member, until they recover our share.
+    points: BTreeMap<u64, BTreeMap<u64, Vec<Scalar>>>,
+    /// The members we sent our points to, as `(dealer, accuser)`.
+    answered: BTreeSet<(u64, u64)>,
+    /// Our messages in the broadcasts, republished until the end.
+    sent: Vec<Payload>,
+    proposed: bool,
+    /// The valid proposals delivered, by proposer.
+    proposals: BTreeMap<u64, Vec<u64>>,
+    /// The agreement on each member's proposal, whether to settle on it.
+    votes: BTreeMap<u64, rbc::agreement::Agreement<u64, ThresholdCoin>>,
+    /// The dealers we settled on.
+    decided: Option<Vec<u64>>,
+}
+
+/// The outcome of the ceremony for this node.
+#[derive(Debug)]
+pub struct Output {
+    pub index: u64,
+    /// Our share of each key, with the key.
+    pub keys: Vec<(SecretKey, GroupKey)>,
+    /// The dealers whose polynomials were added up.
+    pub qualified: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub struct Session {
+    id: String,
+    config: DkgConfig,
+    keys: usize,
+    /// Sorted, member `i` holds share `i + 1`.
+    members: Vec<PeerId>,
+    index: u64,
+    /// Opens the points other members send to recover our shares.
+    keypair: ed25519::Keypair,
+    /// Our commitments and proof, republished until the ceremony is over
+    /// since members joining the topic late miss the first publication.
+    dealing: Option<Payload>,
+    /// The sealed shares the recipients haven't acknowledged yet.
+    undelivered: BTreeMap<PeerId, Vec<u8>>,
+    /// The shares we dealt to each member, revealed if it complains.
+    dealt: BTreeMap<u64, Vec<SecretKey>>,
+    /// Each dealer's commitments to each of its polynomials.
+    commitments: BTreeMap<u64, Vec<Vec<G1Projective>>>,
+    /// Checked against the dealer's commitments, one per key.
+    shares: BTreeMap<u64, Vec<SecretKey>>,
+    /// Arrived before the dealer's commitments.
+    unchecked: BTreeMap<u64, Vec<SecretKey>>,
+    /// The digest of each dealer's commitments, as echoed by each member.
+    echoes: BTreeMap<u64, BTreeMap<u64, [u8; 32]>>,
+    /// The dealers whose commitments enough members echoed.
+    agreed: BTreeSet<u64>,
+    disqualified: BTreeSet<u64>,
+    /// The members complaining about each dealer that it hasn't answered.
+    complaints: BTreeMap<u64, BTreeSet<u64>>,
+    /// The complaints a dealer answered with a valid share, as
+    /// `(dealer, accuser)`.
+    justified: BTreeSet<(u64, u64)>,
+    /// Our complaints and justifications, republished like our dealing.
+    disputes: Vec<Payload>,
+    /// The dealers we settled on in the round-based mode, and when.
+    settled: Option<(Vec<u64>, Instant)>,
+    /// The digest of the dealers each member settled on.
+    settlements: BTreeMap<u64, [u8; 32]>,
+    /// `None` in the round-based mode.
+    agreement: Option<Agreement>,
+    /// The key the agreements' coins are flipped with.
+    coin: Option<Arc<CoinKey>>,
+    /// Only the round-based mode gives up on dealers.
+    deadline: Instant,
+    complete_since: Option<Instant>,
+}
+
+impl Session {
+    /// Starts the ceremony `id` between `members` as the one holding
+    /// `keypair`. The round-based mode gives up on the dealers that haven't
+    /// delivered after `timeout`, the asynchronous one waits for no clock
+    /// and flips its coins with our share of the `coin` group.
+    pub fn new(
+        id: String,
+        params: Params,
+        mut members: Vec<PeerId>,
+        keypair: &ed25519::Keypair,
+        timeout: Duration,
+        coin: Option<(&GroupKey, SecretKey)>,
+    ) -> Result<Self, DkgError> {
+        let local = PeerId::from(PublicKey::from(keypair.public()));
+        members.sort();
+        members.dedup();
+        let config = match params.faults {
+            Some(faults) => DkgConfig::new(members.len(), params.threshold, faults),
+            None => DkgConfig::with_threshold(members.len(), params.threshold),
+        }
+        .map_err(DkgError::Config)?;
+        if params.keys == 0 {
+            return Err(DkgError::NoKeys);
+        }
+        if params.asynchronous && !config.allows_asynchrony() {
+            return Err(DkgError::Asynchronous {
+                threshold: config.threshold(),
+                faults: config.faults(),
+                members: members.len(),
+            });
+        }
+        let index = match members.iter().position(|member| *member == local) {
+            Some(i) => i as u64 + 1,
+            None => return Err(DkgError::NotAMember),
+        };
+        if let Some(member) = members
+            .iter()
+            .find(|member| whisper::encryption_key(member).is_none())
+        {
+            return Err(DkgError::NoEncryptionKey(*member));
+        }
+        let coin = match coin.filter(|_| params.asynchronous) {
+            Some((group, share)) => {
+                let key = CoinKey::new(&id, group, share, &members, index, config.faults())
+                    .map_err(DkgError::Coin)?;
+                Some(Arc::new(key))
+            }
+            None if params.asynchronous => return Err(DkgError::NoCoin),
+            None => None,
+        };
+
+        if config.is_high_threshold() {
+            info!(
+                "Running with a high threshold of {}, tolerating {} faults",
+                config.threshold(),
+                config.faults()
+            );
+        }
+
+        Ok(Session {
+            id,
+            config,
+            keys: params.keys,
+            members,
+            index,
+            keypair: keypair.clone(),
+            dealing: None,
+            undelivered: BTreeMap::new(),
+            dealt: BTreeMap::new(),
+            commitments: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            unchecked: BTreeMap::new(),
+            echoes: BTreeMap::new(),
+            agreed: BTreeSet::new(),
+            disqualified: BTreeSet::new(),
+            complaints: BTreeMap::new(),
+            justified: BTreeSet::new(),
+            disputes: Vec::new(),
+            settled: None,
+            settlements: BTreeMap::new(),
+            agreement: params.asynchronous.then(Agreement::default),
+            coin,
+            deadline: Instant::now() + timeout,
+            complete_since: None,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn index_of(&self, peer_id: &PeerId) -> Option<u64> {
+        let i = self.members.iter().position(|member| member == peer_id)?;
+        Some(i as u64 + 1)
+    }
+
+    /// Samples our polynomials, keeps our own shares and seals the others to
+    /// their recipients. Does nothing if we dealt already.
+    pub fn deal<R: RngCore + CryptoRng>(&mut self, rng: &mut R) {
+        // Our own rows wait for our dealing like anyone's.
+        if self.shares.contains_key(&self.index) || self.unchecked.contains_key(&self.index) {
+            return;
+        }
+        if self.agreement.is_some() {
+            return self.deal_rows(rng);
+        }
+
+        let polynomials = (0..self.keys)
+            .map(|_| SecretPolynomial::random(self.config.threshold(), rng))
+            .collect::<Vec<_>>();
+        for x in 1..=self.members.len() as u64 {
+            let shares = polynomials
+                .iter()
+                .map(|polynomial| polynomial.evaluate(x))
+                .collect::<Vec<_>>();
+            if x == self.index {
+                self.shares.insert(x, shares);
+                continue;
+            }
+            self.seal(x, &shares, rng);
+            self.dealt.insert(x, shares);
+        }
+
+        let commitments = polynomials
+            .iter()
+            .map(SecretPolynomial::commitments)
+            .collect::<Vec<_>>();
+        let dealing = compress(&commitments.concat());
+        let proof = self.prove(polynomials.iter().map(SecretPolynomial::secret), rng);
+        self.commitments.insert(self.index, commitments);
+        info!("Dealt {} shares of {} keys", self.members.len(), self.keys);
+        self.echo(self.index, self.index, digest(&dealing));
+        self.dealing = Some(Payload::DkgDealing {
+            dealer: self.index,
+            commitments: dealing,
+            proof,
+        });
+    }
+
+    /// Deals a symmetric bivariate polynomial per key in the asynchronous
+    /// mode, sealing each member its row and broadcasting the commitments to
+    /// the coefficients, only once each since `f(x, y) = f(y, x)`.
+    fn deal_rows<R: RngCore + CryptoRng>(&mut self, rng: &mut R) {
+        let degree = self.config.threshold();
+        let polynomials = (0..self.keys)
+            .map(|_| bivariate(degree, rng))
+            .collect::<Vec<_>>();
+        for x in 1..=self.members.len() as u64 {
+            let rows = polynomials
+                .iter()
+                .map(|columns| columns.iter().map(|column| column.evaluate(x)).collect())
+                .collect::<Vec<Vec<_>>>();
+            if x == self.index {
+                self.unchecked.insert(x, rows.concat());
+                continue;
+            }
+            self.seal(x, &rows.concat(), rng);
+        }
+
+        let commitments = polynomials
+            .iter()
+            .flat_map(|columns| {
+                let matrix = columns
+                    .iter()
+                    .map(SecretPolynomial::commitments)
+                    .collect::<Vec<_>>();
+                (0..=degree)
+                    .flat_map(|l| matrix[l][l..].to_vec())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let proof = self.prove(polynomials.iter().map(|columns| columns[0].secret()), rng);
+        info!("Dealt {} rows of {} keys", self.members.len(), self.keys);
+        let message = DealingMessage {
+            commitments: compress(&commitments),
+            proof,
+        };
+        let bytes = bincode::serialize(&message).expect("Dealings are always serializable");
+        // Published with the rest of our broadcast messages.
+        self.broadcast(BroadcastKind::Dealing, bytes);
+    }
+
+    /// Seals `shares` to member `x`, to be sent until it acknowledges them.
+    fn seal<R: RngCore + CryptoRng>(&mut self, x: u64, shares: &[SecretKey], rng: &mut R) {
+        let member = self.members[x as usize - 1];
+        let mut message = ShareMessage {
+            session: self.id.clone(),
+            dealer: self.index,
+            shares: shares
+                .iter()
+                .map(|share| share.as_scalar().to_bytes())
+                .collect(),
+        };
+        let plaintext =
+            Zeroizing::new(bincode::serialize(&message).expect("Shares are always serializable"));
+        message.shares.zeroize();
+        let sealed = whisper::seal(&member, &plaintext, rng)
+            .expect("Members were checked to have an encryption key");
+        self.undelivered.insert(member, sealed);
+    }
+
+    /// Proves knowledge of each secret, one after the other.
+    fn prove<R: RngCore + CryptoRng>(
+        &self,
+        secrets: impl Iterator<Item = SecretKey>,
+        rng: &mut R,
+    ) -> Vec<u8> {
+        secrets
+            .zip(0..)
+            .flat_map(|(secret, key)| {
+                schnorr::prove::<G1Projective, _>(
+                    secret.as_scalar(),
+                    &proof_context(&self.id, self.index, key),
+                    rng,
+                )
+                .to_bytes()
+            })
+            .collect()
+    }
+
+    /// Our dealing, once we dealt in the round-based mode.
+    pub fn dealing(&self) -> Option<Payload> {
+        self.dealing.clone()
+    }
+
+    /// Our messages in the broadcasts of the asynchronous mode, republished
+    /// like our dealing since a message lost on the way stalls a broadcast.
+    pub fn broadcasts(&self) -> Vec<Payload> {
+        self.agreement
+            .as_ref()
+            .map_or_else(Vec::new, |agreement| agreement.sent.clone())
+    }
+
+    /// Our echoes of the dealings we got and of the dealers we settled on,
+    /// republished along with our dealing.
+    pub fn echoes(&self) -> Vec<Payload> {
+        self.echoes
+            .iter()
+            .filter_map(|(dealer, echoes)| {
+                Some(Payload::DkgEcho {
+                    dealer: *dealer,
+                    digest: *echoes.get(&self.index)?,
+                })
+            })
+            .chain(
+                self.settlements
+                    .get(&self.index)
+                    .map(|digest| Payload::DkgQualified { digest: *digest }),
+            )
+            .collect()
+    }
+
+    /// Our complaints and our answers to the complaints about us.
+    pub fn disputes(&self) -> Vec<Payload> {
+        self.disputes.clone()
+    }
+
+    /// The shares still to be sent, and to whom.
+    pub fn undelivered(&self) -> impl Iterator<Item = (&PeerId, &Vec<u8>)> {
+        self.undelivered.iter()
+    }
+
+    /// `peer_id` acknowledged its share.
+    pub fn delivered(&mut self, peer_id: &PeerId) {
+        self.undelivered.remove(peer_id);
+    }
+
+    /// Handles a dealing, echo, complaint or anything else published on the
+    /// session's topic, returning what we have to publish in answer.
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        let sender = match self.index_of(sender) {
+            Some(index) => index,
+            None => {
+                warn!(
+                    "Ignoring a DKG message from {:?} outside the committee",
+                    sender
+                );
+                return Vec::new();
+            }
+        };
+
+        let rounds = matches!(
+            payload,
+            Payload::DkgDealing { .. }
+                | Payload::DkgJustification { .. }
+                | Payload::DkgQualified { .. }
+        );
+        let broadcast = matches!(
+            payload,
+            Payload::DkgBroadcast { .. }
+                | Payload::DkgAgreement { .. }
+                | Payload::DkgRecovery { .. }
+        );
+        if (rounds && self.agreement.is_some()) || (broadcast && self.agreement.is_none()) {
+            warn!(
+                "Ignoring a message of the other DKG mode from member {}",
+                sender
+            );
+            return Vec::new();
+        }
+
+        match payload {
+            Payload::DkgBroadcast {
+                origin,
+                kind,
+                message,
+            } => self.handle_broadcast(sender, *origin, *kind, message),
+            Payload::DkgAgreement { proposer, message } => {
+                self.handle_vote(sender, *proposer, message.clone())
+            }
+            Payload::DkgDealing {
+                dealer,
+                commitments,
+                proof,
+            } if *dealer == sender => self.handle_dealing(sender, commitments, proof),
+            Payload::DkgEcho { dealer, digest } if self.agreement.is_some() => {
+                self.vouch(sender, *dealer, *digest)
+            }
+            Payload::DkgEcho { dealer, digest } => {
+                self.echo(sender, *dealer, *digest).into_iter().collect()
+            }
+            Payload::DkgRecovery {
+                dealer,
+                accuser,
+                sealed,
+            } => self.handle_recovery(sender, *dealer, *accuser, sealed),
+            Payload::Complaint {
+                accuser, accused, ..
+            } if *accuser == sender => self.handle_complaint(sender, *accused),
+            Payload::DkgJustification {
+                dealer,
+                accuser,
+                shares,
+            } if *dealer == sender => self.handle_justification(sender, *accuser, shares),
+            Payload::DkgQualified { digest } => {
+                if *self.settlements.entry(sender).or_insert(*digest) != *digest {
+                    warn!("Member {} settled on two sets of dealers", sender);
+                }
+                Vec::new()
+            }
+            Payload::DkgDealing { .. }
+            | Payload::Complaint { .. }
+            | Payload::DkgJustification { .. } => {
+                warn!(
+                    "Ignoring a DKG message member {} sent in another's name",
+                    sender
+                );
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn handle_dealing(
+        &mut self,
+        dealer: u64,
+        compressed: &[Vec<u8>],
+        proof: &[u8],
+    ) -> Vec<Payload> {
+        let commitments = match self.decompress_dealing(compressed) {
+            Ok(commitments) => commitments,
+            Err(reason) => return self.disqualify(dealer, reason).into_iter().collect(),
+        };
+
+        match self.commitments.get(&dealer) {
+            Some(known) if *known == commitments => return Vec::new(),
+            Some(_) => {
+                return self
+                    .disqualify(dealer, "two different dealings".into())
+                    .into_iter()
+                    .collect()
+            }
+            None => {}
+        }
+        if !self.proven(dealer, &commitments, proof) {
+            return self
+                .disqualify(dealer, "no proof of knowledge of the secret".into())
+                .into_iter()
+                .collect();
+        }
+
+        info!("Got the dealing of dealer {}", dealer);
+        let digest = digest(&compress(&commitments.concat()));
+        self.commitments.insert(dealer, commitments);
+        let mut replies = vec![Payload::DkgEcho { dealer, digest }];
+        replies.extend(self.echo(self.index, dealer, digest));
+        if let Some(shares) = self.unchecked.remove(&dealer) {
+            replies.extend(self.check_shares(dealer, shares));
+        }
+        replies
+    }
+
+    /// Splits the commitments of a dealing into those of each polynomial.
+    fn decompress_dealing(&self, compressed: &[Vec<u8>]) -> Result<Vec<Vec<G1Projective>>, String> {
+        let per_key = self.config.threshold() + 1;
+        match decompress(compressed) {
+            Some(commitments) if commitments.len() == self.keys * per_key => {
+                Ok(commitments.chunks(per_key).map(<[_]>::to_vec).collect())
+            }
+            Some(commitments) => Err(format!(
+                "{} commitments for {} polynomials of degree {}",
+                commitments.len(),
+                self.keys,
+                self.config.threshold()
+            )),
+            None => Err("malformed commitments".into()),
+        }
+    }
+
+    /// Fills in each key's matrix of commitments from its upper triangle,
+    /// by column.
+    fn decompress_matrices(
+        &self,
+        compressed: &[Vec<u8>],
+    ) -> Result<Vec<Vec<Vec<G1Projective>>>, String> {
+        let degree = self.config.threshold();
+        let per_key = (degree + 1) * (degree + 2) / 2;
+        let commitments = match decompress(compressed) {
+            Some(commitments) if commitments.len() == self.keys * per_key => commitments,
+            Some(commitments) => {
+                return Err(format!(
+                    "{} commitments for {} polynomials of degree {}",
+                    commitments.len(),
+                    self.keys,
+                    degree
+                ))
+            }
+            None => return Err("malformed commitments".into()),
+        };
+        Ok(commitments
+            .chunks(per_key)
+            .map(|triangle| {
+                let mut triangle = triangle.iter();
+                let mut matrix = Vec::<Vec<G1Projective>>::with_capacity(degree + 1);
+                for l in 0..=degree {
+                    let mut column = matrix.iter().map(|earlier| earlier[l]).collect::<Vec<_>>();
+                    column.extend(triangle.by_ref().take(degree + 1 - l));
+                    matrix.push(column);
+                }
+                matrix
+            })
+            .collect())
+    }
+
+    /// Checks the dealer's proofs of knowledge of its secrets, one after the
+    /// other.
+    fn proven(&self, dealer: u64, commitments: &[Vec<G1Projective>], proof: &[u8]) -> bool {
+        proof.len() == 64 * commitments.len()
+            && commitments.iter().zip(proof.chunks(64)).zip(0..).all(
+                |((commitments, proof), key)| {
+                    sigma::Proof::from_bytes(proof).is_some_and(|proof| {
+                        schnorr::verify(
+                            &commitments[0],
+                            &proof_context(&self.id, dealer, key),
+                            &proof,
+                        )
+                    })
+                },
+            )
+    }
+
+    /// Hands a message of the broadcast of `origin` to it.
+    fn handle_broadcast(
+        &mut self,
+        sender: u64,
+        origin: u64,
+        kind: BroadcastKind,
+        message: &Message<Vec<u8>>,
+    ) -> Vec<Payload> {
+        if origin == 0 || origin > self.members.len() as u64 {
+            warn!("Ignoring a broadcast of unknown member {}", origin);
+            return Vec::new();
+        }
+        let step = match self
+            .broadcast_of(kind, origin)
+            .handle(&sender, message.clone())
+        {
+            Ok(step) => step,
+            Err(e) => {
+                warn!(
+                    "Ignoring a message of member {} in the broadcast of member {}: {}",
+                    sender, origin, e
+                );
+                return Vec::new();
+            }
+        };
+        self.take_step(kind, origin, step)
+    }
+
+    /// Starts the broadcast of our `kind` of message.
+    fn broadcast(&mut self, kind: BroadcastKind, message: Vec<u8>) -> Vec<Payload> {
+        let step = self
+            .broadcast_of(kind, self.index)
+            .broadcast(message)
+            .expect("We are the sender of our broadcasts");
+        self.take_step(kind, self.index, step)
+    }
+
+    fn broadcast_of(&mut self, kind: BroadcastKind, origin: u64) -> &mut Broadcast<u64, Vec<u8>> {
+        let (index, members) = (self.index, self.members.len() as u64);
+        self.agreement
+            .as_mut()
+            .expect("Only the asynchronous mode broadcasts")
+            .broadcasts
+            .entry((kind, origin))
+            .or_insert_with(|| {
+                Broadcast::new(index, origin, 1..=members).expect("Both are members")
+            })
+    }
+
+    /// Keeps the messages of a step to publish, and acts on what it
+    /// delivered.
+    fn take_step(&mut self, kind: BroadcastKind, origin: u64, step: Step<Vec<u8>>) -> Vec<Payload> {
+        let mut replies = step
+            .messages
+            .into_iter()
+            .map(|message| Payload::DkgBroadcast {
+                origin,
+                kind,
+                message,
+            })
+            .collect::<Vec<_>>();
+        if let Some(agreement) = &mut self.agreement {
+            agreement.sent.extend(replies.iter().cloned());
+        }
+        match (kind, step.delivered) {
+            (BroadcastKind::Dealing, Some(bytes)) => {
+                replies.extend(self.deliver_dealing(origin, &bytes))
+            }
+            (BroadcastKind::Proposal, Some(bytes)) => {
+                replies.extend(self.deliver_proposal(origin, &bytes))
+            }
+            (_, None) => {}
+        }
+        replies.extend(self.propose());
+        replies.extend(self.vote_attested());
+        replies
+    }
+
+    /// Every member delivers the same dealing, so every member that checks
+    /// it leaves it out if it doesn't check out.
+    fn deliver_dealing(&mut self, dealer: u64, bytes: &[u8]) -> Vec<Payload> {
+        let dealing = bincode::deserialize::<DealingMessage>(bytes)
+            .map_err(|_| "malformed dealing".to_string())
+            .and_then(|dealing| {
+                let matrices = self.decompress_matrices(&dealing.commitments)?;
+                let commitments = matrices
+                    .iter()
+                    .map(|matrix| matrix[0].clone())
+                    .collect::<Vec<_>>();
+                if !self.proven(dealer, &commitments, &dealing.proof) {
+                    return Err("no proof of knowledge of the secret".into());
+                }
+                Ok((matrices, commitments, digest(&dealing.commitments)))
+            });
+        let (matrices, commitments, digest) = match dealing {
+            Ok(dealing) => dealing,
+            Err(reason) => return self.disqualify(dealer, reason).into_iter().collect(),
+        };
+
+        info!("Delivered the dealing of dealer {}", dealer);
+        self.commitments.insert(dealer, commitments);
+        self.agreed.insert(dealer);
+        if let Some(agreement) = &mut self.agreement {
+            agreement.matrices.insert(dealer, matrices);
+            agreement.digests.insert(dealer, digest);
+        }
+        let mut replies = match self.unchecked.remove(&dealer) {
+            Some(rows) => self.check_rows(dealer, rows),
+            None => Vec::new(),
+        };
+        // The dealing may be one we settled on without our share of it.
+        replies.extend(self.recover());
+        replies
+    }
+
+    /// Checks our row of each of `dealer`'s polynomials,
+    /// `row_l * G == ∑ (a_kl * G) * x^k` for our `x` and every `l`, and
+    /// vouches for the dealing if they all check out. Our share is the
+    /// constant term of the row, `f(x, 0)`.
+    fn check_rows(&mut self, dealer: u64, rows: Vec<SecretKey>) -> Vec<Payload> {
+        let agreement = self
+            .agreement
+            .as_ref()
+            .expect("Only the asynchronous mode has rows");
+        let matrices = &agreement.matrices[&dealer];
+        let digest = agreement.digests[&dealer];
+        let rows = rows
+            .chunks(self.config.threshold() + 1)
+            .map(<[_]>::to_vec)
+            .collect::<Vec<_>>();
+        let valid = rows.len() == matrices.len()
+            && rows.iter().zip(matrices).all(|(row, matrix)| {
+                row.len() == matrix.len()
+                    && row.iter().zip(matrix).all(|(coefficient, column)| {
+                        coefficient.public_key() == evaluate(column, self.index).to_affine()
+                    })
+            });
+        if !valid {
+            return self
+                .complain(dealer, "row doesn't match the commitments".into())
+                .into_iter()
+                .collect();
+        }
+
+        info!("Got a valid row from dealer {}", dealer);
+        self.shares
+            .insert(dealer, rows.iter().map(|row| row[0].clone()).collect());
+        let accusers = self
+            .complaints
+            .get(&dealer)
+            .map(|accusers| accusers.iter().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        if let Some(agreement) = &mut self.agreement {
+            agreement.rows.insert(dealer, rows);
+        }
+        let mut replies = vec![Payload::DkgEcho { dealer, digest }];
+        replies.extend(self.vouch(self.index, dealer, digest));
+        // Those who asked for their share before we had our row.
+        for accuser in accusers {
+            replies.extend(self.answer(dealer, accuser));
+        }
+        replies
+    }
+
+    /// Counts `member` vouching for the dealing of `dealer`, which it does
+    /// once it delivered it and its row checked out.
+    fn vouch(&mut self, member: u64, dealer: u64, digest: [u8; 32]) -> Vec<Payload> {
+        let echoes = self.echoes.entry(dealer).or_default();
+        match echoes.get(&member) {
+            Some(echoed) if *echoed == digest => return Vec::new(),
+            Some(_) => {
+                warn!(
+                    "Member {} vouched for two dealings of dealer {}",
+                    member, dealer
+                );
+                return Vec::new();
+            }
+            None => echoes.insert(member, digest),
+        };
+        if self.is_attested(dealer) {
+            info!(
+                "Enough members vouched for the dealing of dealer {}",
+                dealer
+            );
+        }
+        let mut replies = self.propose();
+        replies.extend(self.vote_attested());
+        replies
+    }
+
+    /// Whether we delivered the dealing of `dealer` and enough members
+    /// vouched for it that `t + 1` honest ones hold their rows, enough to
+    /// recover the share of anyone without it.
+    fn is_attested(&self, dealer: u64) -> bool {
+        let digest = match self
+            .agreement
+            .as_ref()
+            .and_then(|agreement| agreement.digests.get(&dealer))
+        {
+            Some(digest) => digest,
+            None => return false,
+        };
+        !self.disqualified.contains(&dealer)
+            && self.echoes.get(&dealer).is_some_and(|echoes| {
+                echoes.values().filter(|echoed| *echoed == digest).count()
+                    >= self.config.recovery_quorum()
+            })
+    }
+
+    /// Broadcasts the dealers we have a valid share of a delivered dealing
+    /// from, once there are `n - f` of them.
+    fn propose(&mut self) -> Vec<Payload> {
+        match &self.agreement {
+            Some(agreement) if !agreement.proposed => {}
+            _ => return Vec::new(),
+        }
+        let dealers = self.complete_dealers();
+        if dealers.len() < self.members.len() - self.config.faults() {
+            return Vec::new();
+        }
+
+        info!("Proposing the dealers {:?}", dealers);
+        if let Some(agreement) = &mut self.agreement {
+            agreement.proposed = true;
+        }
+        let bytes = bincode::serialize(&dealers).expect("Proposals are always serializable");
+        self.broadcast(BroadcastKind::Proposal, bytes)
+    }
+
+    /// Votes for settling on a valid proposal, against an invalid one.
+    fn deliver_proposal(&mut self, proposer: u64, bytes: &[u8]) -> Vec<Payload> {
+        let (members, faults) = (self.members.len() as u64, self.config.faults() as u64);
+        let dealers = match bincode::deserialize::<Vec<u64>>(bytes) {
+            Ok(dealers)
+                if dealers.len() as u64 >= members - faults
+                    && dealers.windows(2).all(|pair| pair[0] < pair[1])
+                    && dealers.iter().all(|dealer| (1..=members).contains(dealer)) =>
+            {
+                dealers
+            }
+            _ => {
+                warn!("Ignoring the invalid proposal of member {}", proposer);
+                return self.vote(proposer, false);
+            }
+        };
+
+        if let Some(agreement) = &mut self.agreement {
+            agreement.proposals.insert(proposer, dealers);
+        }
+        // The vote may have been over before the proposal got here.
+        self.decide()
+    }
+
+    /// Votes for settling on each proposal delivered whose dealers are all
+    /// attested, which may take a while for a proposal that got here first.
+    fn vote_attested(&mut self) -> Vec<Payload> {
+        let ready = match &self.agreement {
+            Some(agreement) => agreement
+                .proposals
+                .iter()
+                .filter(|(proposer, dealers)| {
+                    !agreement
+                        .votes
+                        .get(proposer)
+                        .is_some_and(|vote| vote.has_input())
+                        && dealers.iter().all(|dealer| self.is_attested(*dealer))
+                })
+                .map(|(proposer, _)| *proposer)
+                .collect::<Vec<_>>(),
+            None => return Vec::new(),
+        };
+        ready
+            .into_iter()
+            .flat_map(|proposer| self.vote(proposer, true))
+            .collect()
+    }
+
+    /// Hands a message of the agreement on the proposal of `proposer` to it.
+    fn handle_vote(
+        &mut self,
+        sender: u64,
+        proposer: u64,
+        message: rbc::agreement::Message<Vec<u8>>,
+    ) -> Vec<Payload> {
+        if proposer == 0 || proposer > self.members.len() as u64 {
+            warn!("Ignoring a vote on unknown member {}", proposer);
+            return Vec::new();
+        }
+        match self.vote_of(proposer).handle(&sender, message) {
+            Ok(step) => self.take_vote(proposer, step),
+            Err(e) => {
+                warn!(
+                    "Ignoring a message of member {} in the vote on member {}: {}",
+                    sender, proposer, e
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Starts the agreement on the proposal of `proposer` with our vote,
+    /// only the first one counts.
+    fn vote(&mut self, proposer: u64, bit: bool) -> Vec<Payload> {
+        let step = self.vote_of(proposer).input(bit);
+        self.take_vote(proposer, step)
+    }
+
+    fn vote_of(&mut self, proposer: u64) -> &mut rbc::agreement::Agreement<u64, ThresholdCoin> {
+        let (index, members) = (self.index, self.members.len() as u64);
+        let coin = self
+            .coin
+            .as_ref()
+            .expect("The asynchronous mode has a coin")
+            .coin(proposer);
+        self.agreement
+            .as_mut()
+            .expect("Only the asynchronous mode votes")
+            .votes
+            .entry(proposer)
+            .or_insert_with(|| {
+                rbc::agreement::Agreement::new(index, 1..=members, coin).expect("We are a member")
+            })
+    }
+
+    /// Keeps the messages of a step to publish. Once `n - f` proposals were
+    /// voted in, we vote against the ones we didn't get, so that every vote
+    /// ends.
+    fn take_vote(&mut self, proposer: u64, step: rbc::agreement::Step<Vec<u8>>) -> Vec<Payload> {
+        let mut replies = step
+            .messages
+            .into_iter()
+            .map(|message| Payload::DkgAgreement { proposer, message })
+            .collect::<Vec<_>>();
+        let agreement = match &mut self.agreement {
+            Some(agreement) => agreement,
+            None => return replies,
+        };
+        agreement.sent.extend(replies.iter().cloned());
+        let decided = match step.decided {
+            Some(decided) => decided,
+            None => return replies,
+        };
+
+        info!(
+            "Voted {} the proposal of member {}",
+            if decided { "for" } else { "against" },
+            proposer
+        );
+        let accepted = agreement
+            .votes
+            .values()
+            .filter(|vote| vote.decided() == Some(true))
+            .count();
+        if accepted >= self.members.len() - self.config.faults() {
+            for other in 1..=self.members.len() as u64 {
+                if !self.vote_of(other).has_input() {
+                    replies.extend(self.vote(other, false));
+                }
+            }
+        }
+        replies.extend(self.decide());
+        replies
+    }
+
+    /// Settles on the proposal of the lowest member voted in, once every
+    /// vote is over and the proposal was delivered, and asks for the shares
+    /// of it we lack.
+    fn decide(&mut self) -> Vec<Payload> {
+        let members = self.members.len() as u64;
+        let agreement = match &mut self.agreement {
+            Some(agreement) if agreement.decided.is_none() => agreement,
+            _ => return Vec::new(),
+        };
+        let mut accepted = Vec::new();
+        for proposer in 1..=members {
+            match agreement
+                .votes
+                .get(&proposer)
+                .and_then(|vote| vote.decided())
+            {
+                Some(true) => accepted.push(proposer),
+                Some(false) => {}
+                None => return Vec::new(),
+            }
+        }
+        let dealers = match accepted
+            .first()
+            .and_then(|lowest| agreement.proposals.get(lowest))
+        {
+            Some(dealers) => dealers.clone(),
+            None => return Vec::new(),
+        };
+        info!(
+            "Settling on the dealers {:?} proposed by member {}, of the proposals voted in {:?}",
+            dealers, accepted[0], accepted
+        );
+        agreement.decided = Some(dealers);
+        self.recover()
+    }
+
+    /// Asks for our share of each dealer settled on whose dealing we have
+    /// but whose row we didn't get, or got wrong.
+    fn recover(&mut self) -> Vec<Payload> {
+        let decided = match self
+            .agreement
+            .as_ref()
+            .and_then(|agreement| agreement.decided.clone())
+        {
+            Some(decided) => decided,
+            None => return Vec::new(),
+        };
+        let missing = decided
+            .into_iter()
+            .filter(|dealer| {
+                self.commitments.contains_key(dealer) && !self.shares.contains_key(dealer)
+            })
+            .collect::<Vec<_>>();
+        missing
+            .into_iter()
+            .filter_map(|dealer| self.complain(dealer, "no row by the time we settled".into()))
+            .collect()
+    }
+
+    /// Sends `accuser` the points of our rows of `dealer`'s polynomials at
+    /// its index, `f(x, i) = f(i, x)`, sealed to it.
+    fn answer(&mut self, dealer: u64, accuser: u64) -> Option<Payload> {
+        let agreement = self.agreement.as_mut()?;
+        let rows = agreement.rows.get(&dealer)?;
+        if !agreement.answered.insert((dealer, accuser)) {
+            return None;
+        }
+        let x = Scalar::from(accuser);
+        let mut message = ShareMessage {
+            session: self.id.clone(),
+            dealer,
+            shares: rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .rev()
+                        .fold(Scalar::zero(), |acc, c| acc * x + c.as_scalar())
+                        .to_bytes()
+                })
+                .collect(),
+        };
+        let plaintext =
+            Zeroizing::new(bincode::serialize(&message).expect("Shares are always serializable"));
+        message.shares.zeroize();
+        let sealed = whisper::seal(
+            &self.members[accuser as usize - 1],
+            &plaintext,
+            &mut rand::rngs::OsRng,
+        )
+        .expect("Members were checked to have an encryption key");
+        info!(
+            "Sending member {} the points to recover its share of dealer {}",
+            accuser, dealer
+        );
+        let recovery = Payload::DkgRecovery {
+            dealer,
+            accuser,
+            sealed,
+        };
+        self.disputes.push(recovery.clone());
+        Some(recovery)
+    }
+
+    /// Checks the points `sender` sent us of its rows of `dealer`'s
+    /// polynomials, `p * G == ∑ (∑ (a_kl * G) * m^k) * x^l` for its `m` and
+    /// our `x`, and recovers our share from `t + 1` valid ones.
+    fn handle_recovery(
+        &mut self,
+        sender: u64,
+        dealer: u64,
+        accuser: u64,
+        sealed: &[u8],
+    ) -> Vec<Payload> {
+        if accuser != self.index || self.shares.contains_key(&dealer) {
+            return Vec::new();
+        }
+        let agreement = self
+            .agreement
+            .as_ref()
+            .expect("Only the asynchronous mode recovers");
+        // Sent again until the end, we check it once we have the dealing.
+        let matrices = match agreement.matrices.get(&dealer) {
+            Some(matrices) => matrices,
+            None => return Vec::new(),
+        };
+        let points = whisper::open(&self.keypair, sealed)
+            .and_then(|plaintext| bincode::deserialize::<ShareMessage>(&plaintext).ok())
+            .filter(|message| message.session == self.id && message.dealer == dealer)
+            .and_then(|message| {
+                let points = Zeroizing::new(message.shares);
+                points
+                    .iter()
+                    .map(|point| Option::<Scalar>::from(Scalar::from_bytes(point)))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .filter(|points| {
+                points.len() == matrices.len()
+                    && points.iter().zip(matrices).all(|(point, matrix)| {
+                        let row = matrix
+                            .iter()
+                            .map(|column| evaluate(column, sender))
+                            .collect::<Vec<_>>();
+                        G1Projective::generator() * point == evaluate(&row, self.index)
+                    })
+            });
+        let points = match points {
+            Some(points) => points,
+            None => {
+                warn!(
+                    "Member {} sent bad points of dealer {} to recover our share",
+                    sender, dealer
+                );
+                return Vec::new();
+            }
+        };
+
+        let agreement = self.agreement.as_mut().expect("Checked above");
+        let collected = agreement.points.entry(dealer).or_default();
+        collected.insert(sender, points);
+        if collected.len() <= self.config.threshold() {
+            return Vec::new();
+        }
+        let collected = agreement.points.remove(&dealer).expect("Just inserted");
+        let shares = (0..self.keys)
+            .map(|key| {
+                let points = collected
+                    .iter()
+                    .map(|(member, points)| (*member, points[key]))
+                    .collect::<Vec<_>>();
+                interpolate_at_zero(&points)
+                    .map(SecretKey::new)
+                    .expect("Members are distinct and nonzero")
+            })
+            .collect::<Vec<_>>();
+        info!("Recovered our share of dealer {}", dealer);
+        self.unchecked.remove(&dealer);
+        self.shares.insert(dealer, shares);
+        Vec::new()
+    }
+
+    /// The dealers whose dealing we agreed on and whose share we checked,
+    /// with no complaint left unanswered, or the attested ones in the
+    /// asynchronous mode.
+    fn complete_dealers(&self) -> Vec<u64> {
+        if self.agreement.is_some() {
+            return (1..=self.members.len() as u64)
+                .filter(|dealer| self.is_attested(*dealer))
+                .collect();
+        }
+        self.shares
+            .keys()
+            .copied()
+            .filter(|dealer| {
+                self.agreed.contains(dealer)
+                    && !self.disqualified.contains(dealer)
+                    && !self.is_disputed(*dealer)
+            })
+            .collect()
+    }
+
+    /// Whether a complaint about `dealer` is still waiting for its answer.
+    fn is_disputed(&self, dealer: u64) -> bool {
+        self.complaints
+            .get(&dealer)
+            .is_some_and(|accusers| !accusers.is_empty())
+    }
+
+    /// Counts the echo of `member`, agreeing on the dealing once enough
+    /// members echoed the commitments we got.
+    fn echo(&mut self, member: u64, dealer: u64, digest: [u8; 32]) -> Option<Payload> {
+        let echoes = self.echoes.entry(dealer).or_default();
+        match echoes.get(&member) {
+            Some(echoed) if *echoed == digest => return None,
+            Some(_) => {
+                warn!("Member {} echoed two dealings of dealer {}", member, dealer);
+                return None;
+            }
+            None => echoes.insert(member, digest),
+        };
+
+        let mut counts = BTreeMap::<[u8; 32], usize>::new();
+        for echoed in echoes.values() {
+            *counts.entry(*echoed).or_default() += 1;
+        }
+        let seen_by_honest = counts
+            .values()
+            .filter(|count| **count > self.config.faults())
+            .count();
+        if seen_by_honest > 1 {
+            return self.disqualify(
+                dealer,
+                "sent different commitments to different members".into(),
+            );
+        }
+
+        let ours = self
+            .commitments
+            .get(&dealer)
+            .map(|commitments| self::digest(&compress(&commitments.concat())));
+        let quorum = self.config.quorum();
+        match counts.iter().find(|(_, count)| **count >= quorum) {
+            Some((agreed, count)) if Some(*agreed) == ours => {
+                if self.agreed.insert(dealer) {
+                    info!(
+                        "{} members agree on the dealing of dealer {}",
+                        count, dealer
+                    );
+                }
+                None
+            }
+            Some(_) if ours.is_some() => {
+                self.disqualify(dealer, "our commitments differ from the agreed ones".into())
+            }
+            _ => None,
+        }
+    }
+
+    /// Handles the opened share request of `sender`, returning our complaint
+    /// if the shares don't match its dealing, or what vouching for it takes.
+    /// Fails if the request isn't a share of this session from its sender.
+    pub fn handle_share(
+        &mut self,
+        sender: &PeerId,
+        plaintext: &[u8],
+    ) -> Result<Vec<Payload>, &'static str> {
+        let ShareMessage {
+            session,
+            dealer,
+            shares,
+        } = bincode::deserialize(plaintext).map_err(|_| "malformed share")?;
+        let shares = Zeroizing::new(shares);
+        if session != self.id {
+            return Err("share of another session");
+        }
+        if self.index_of(sender) != Some(dealer) {
+            return Err("share sent in another dealer's name");
+        }
+        let expected = match self.agreement {
+            Some(_) => self.keys * (self.config.threshold() + 1),
+            None => self.keys,
+        };
+        if shares.len() != expected {
+            let reason = format!("{} shares for {} keys", shares.len(), self.keys);
+            return Ok(self.complain(dealer, reason).into_iter().collect());
+        }
+        let shares = match shares
+            .iter()
+            .map(|share| Option::<Scalar>::from(Scalar::from_bytes(share)).map(SecretKey::new))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(shares) => shares,
+            None => {
+                let complaint = self.complain(dealer, "share isn't a scalar".into());
+                return Ok(complaint.into_iter().collect());
+            }
+        };
+
+        if let Some(agreement) = &self.agreement {
+            // A share we recovered is replaced by the row it comes from, to
+            // answer the others with.
+            if agreement.rows.contains_key(&dealer) {
+                return Ok(Vec::new());
+            }
+        } else {
+            match self.shares.get(&dealer) {
+                Some(known) if *known == shares => return Ok(Vec::new()),
+                Some(_) => {
+                    let complaint = self.complain(dealer, "two different shares".into());
+                    return Ok(complaint.into_iter().collect());
+                }
+                None => {}
+            }
+        }
+        if !self.commitments.contains_key(&dealer) {
+            self.unchecked.insert(dealer, shares);
+            return Ok(Vec::new());
+        }
+        Ok(self.check_shares(dealer, shares))
+    }
+
+    /// Checks `share * G == ∑ (a_i * G) * x^i` for our `x`, for the share of
+    /// every key, or our rows in the asynchronous mode.
+    fn check_shares(&mut self, dealer: u64, shares: Vec<SecretKey>) -> Vec<Payload> {
+        if self.agreement.is_some() {
+            return self.check_rows(dealer, shares);
+        }
+        let valid = shares
+            .iter()
+            .zip(&self.commitments[&dealer])
+            .all(|(share, commitments)| {
+                share.public_key() == evaluate(commitments, self.index).to_affine()
+            });
+        if !valid {
+            return self
+                .complain(dealer, "share doesn't match the commitments".into())
+                .into_iter()
+                .collect();
+        }
+
+        info!("Got a valid share from dealer {}", dealer);
+        self.shares.insert(dealer, shares);
+        Vec::new()
+    }
+
+    /// Complains about the shares `accused` dealt us, which only the dealer
+    /// can answer by revealing them. In the asynchronous mode the complaint
+    /// asks the members that vouched for the dealing for our share instead.
+    fn complain(&mut self, accused: u64, reason: String) -> Option<Payload> {
+        if self.justified.contains(&(accused, self.index))
+            || !self
+                .complaints
+                .entry(accused)
+                .or_default()
+                .insert(self.index)
+        {
+            return None;
+        }
+        warn!("Complaining about dealer {}: {}", accused, reason);
+        self.complete_since = None;
+        let complaint = Payload::Complaint {
+            accuser: self.index,
+            accused,
+            reason,
+        };
+        self.disputes.push(complaint.clone());
+        Some(complaint)
+    }
+
+    /// Leaves out `accused` for something every member can see for itself,
+    /// telling the others in case they missed it.
+    fn disqualify(&mut self, accused: u64, reason: String) -> Option<Payload> {
+        if !self.disqualified.insert(accused) {
+            return None;
+        }
+        if self.agreement.is_some() {
+            // Without rounds there is no telling when everyone heard of it,
+            // the dealer is only left out of our proposal.
+            warn!("Leaving out dealer {}: {}", accused, reason);
+            return None;
+        }
+        warn!("Disqualifying dealer {}: {}", accused, reason);
+        self.complete_since = None;
+        let complaint = Payload::Complaint {
+            accuser: self.index,
+            accused,
+            reason,
+        };
+        self.disputes.push(complaint.clone());
+        Some(complaint)
+    }
+
+    /// Reveals the shares we dealt `accuser` if we're the accused, or waits
+    /// for the accused to. In the asynchronous mode we send `accuser` the
+    /// points to recover its share with, now or once we have our row.
+    fn handle_complaint(&mut self, accuser: u64, accused: u64) -> Vec<Payload> {
+        if self.agreement.is_some() {
+            if self.complaints.entry(accused).or_default().insert(accuser) {
+                info!(
+                    "Member {} asked for its share of dealer {}",
+                    accuser, accused
+                );
+            }
+            return self.answer(accused, accuser).into_iter().collect();
+        }
+        if accused != self.index {
+            if !self.justified.contains(&(accused, accuser))
+                && self.complaints.entry(accused).or_default().insert(accuser)
+            {
+                info!("Member {} complained about dealer {}", accuser, accused);
+                self.complete_since = None;
+            }
+            return Vec::new();
+        }
+
+        let shares = match self.dealt.get(&accuser) {
+            Some(shares) => shares,
+            None => return Vec::new(),
+        };
+        let justification = Payload::DkgJustification {
+            dealer: self.index,
+            accuser,
+            shares: shares
+                .iter()
+                .map(|share| share.as_scalar().to_bytes())
+                .collect(),
+        };
+        if self.disputes.contains(&justification) {
+            return Vec::new();
+        }
+        info!(
+            "Revealing the shares of member {} it complained about",
+            accuser
+        );
+        self.disputes.push(justification.clone());
+        vec![justification]
+    }
+
+    /// Checks the shares `dealer` revealed against its commitments. Valid
+    /// ones settle the complaint, and are ours to use if we complained.
+    fn handle_justification(
+        &mut self,
+        dealer: u64,
+        accuser: u64,
+        shares: &[[u8; 32]],
+    ) -> Vec<Payload> {
+        if self.justified.contains(&(dealer, accuser)) || self.disqualified.contains(&dealer) {
+            return Vec::new();
+        }
+        // Sent again until the end, we check it once we have the dealing.
+        let commitments = match self.commitments.get(&dealer) {
+            Some(commitments) => commitments,
+            None => return Vec::new(),
+        };
+        let shares = shares
+            .iter()
+            .map(|share| Option::<Scalar>::from(Scalar::from_bytes(share)).map(SecretKey::new))
+            .collect::<Option<Vec<_>>>()
+            .filter(|shares| {
+                shares.len() == self.keys
+                    && shares.iter().zip(commitments).all(|(share, commitments)| {
+                        share.public_key() == evaluate(commitments, accuser).to_affine()
+                    })
+            });
+        let shares = match shares {
+            Some(shares) => shares,
+            None => {
+                let reason = format!("revealed a bad share of member {}", accuser);
+                return self.disqualify(dealer, reason).into_iter().collect();
+            }
+        };
+
+        info!(
+            "Dealer {} answered the complaint of member {}",
+            dealer, accuser
+        );
+        self.justified.insert((dealer, accuser));
+        if let Some(accusers) = self.complaints.get_mut(&dealer) {
+            accusers.remove(&accuser);
+        }
+        if accuser == self.index {
+            self.unchecked.remove(&dealer);
+            self.shares.insert(dealer, shares);
+        }
+        Vec::new()
+    }
+
+    /// Settles on the dealers we have everything from, to be confirmed by
+    /// the others before the keys are written.
+    fn settle(&mut self, now: Instant) {
+        let qualified = self.complete_dealers();
+        info!("Settling on the dealers {:?}", qualified);
+        self.settlements
+            .insert(self.index, qualified_digest(&qualified));
+        self.settled = Some((qualified, now));
+    }
+
+    /// How many members settled on the same dealers as us, us included.
+    fn confirmations(&self) -> usize {
+        let ours = self.settlements.get(&self.index);
+        self.settlements
+            .values()
+            .filter(|digest| Some(*digest) == ours)
+            .count()
+    }
+
+    /// Whether the ceremony is over for us: we got the share of every dealer
+    /// left, agreed on its dealing, and no complaint came in for a while, or
+    /// we ran out of time. In the round-based mode we then settle on the
+    /// dealers, and are done once a quorum settled on the same ones or
+    /// another complaint window went by. The asynchronous mode is over once
+    /// we got or recovered the share of every dealer settled on.
+    pub fn is_ready(&mut self) -> bool {
+        let now = Instant::now();
+        if let Some((_, settled)) = self.settled {
+            return self.confirmations() >= self.config.quorum()
+                || now.duration_since(settled) >= COMPLAINT_WINDOW;
+        }
+        if now >= self.deadline && self.agreement.is_none() {
+            self.settle(now);
+            return false;
+        }
+
+        let complete = match &self.agreement {
+            Some(agreement) => agreement.decided.as_ref().is_some_and(|decided| {
+                decided
+                    .iter()
+                    .all(|dealer| self.shares.contains_key(dealer) && self.agreed.contains(dealer))
+            }),
+            None => (1..=self.members.len() as u64)
+                .filter(|dealer| !self.disqualified.contains(dealer) && !self.is_disputed(*dealer))
+                .all(|dealer| self.shares.contains_key(&dealer) && self.agreed.contains(&dealer)),
+        };
+        if !complete {
+            self.complete_since = None;
+            return false;
+        }
+        let since = *self.complete_since.get_or_insert(now);
+        if self.agreement.is_some() {
+            return now.duration_since(since) >= LINGER;
+        }
+        if now.duration_since(since) >= COMPLAINT_WINDOW {
+            self.settle(now);
+        }
+        false
+    }
+
+    /// Adds up the shares and public coefficients of the qualified dealers,
+    /// the ones settled on in the asynchronous mode.
+    pub fn finish(self) -> Result<Output, DkgError> {
+        let qualified = match &self.agreement {
+            Some(agreement) => {
+                let decided = agreement.decided.clone().ok_or(DkgError::Undecided)?;
+                let missing = decided
+                    .iter()
+                    .copied()
+                    .filter(|dealer| {
+                        !self.shares.contains_key(dealer) || !self.agreed.contains(dealer)
+                    })
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    return Err(DkgError::MissingShares(missing));
+                }
+                decided
+            }
+            None => {
+                let (qualified, _) = self.settled.clone().ok_or(DkgError::Undecided)?;
+                if self.confirmations() < self.config.quorum() {
+                    return Err(DkgError::Unconfirmed {
+                        confirmed: self.confirmations(),
+                        needed: self.config.quorum(),
+                    });
+                }
+                qualified
+            }
+        };
+        if qualified.len() < self.config.min_qualified() {
+            return Err(DkgError::TooFewDealers {
+                qualified: qualified.len(),
+                needed: self.config.min_qualified(),
+            });
+        }
+
+        let keys = (0..self.keys)
+            .map(|key| self.finish_key(key, &qualified))
+            .collect();
+        Ok(Output {
+            index: self.index,
+            keys,
+            qualified,
+        })
+    }
+
+    /// Adds up the shares and public coefficients of key `key`. Each key but
+    /// the only one is named after the session and its number.
+    fn finish_key(&self, key: usize, qualified: &[u64]) -> (SecretKey, GroupKey) {
+        let share = qualified
+            .iter()
+            .map(|dealer| self.shares[dealer][key].as_scalar())
+            .sum::<Scalar>();
+        // The commitments to the sum of the qualified polynomials, which give
+        // every member's public share as well as the group key.
+        let coefficients = (0..=self.config.threshold())
+            .map(|i| {
+                qualified
+                    .iter()
+                    .map(|dealer| self.commitments[dealer][key][i])
+                    .sum::<G1Projective>()
+            })
+            .collect::<Vec<_>>();
+        let members = self
+            .members
+            .iter()
+            .zip(1..)
+            .map(|(peer_id, index)| Member {
+                index,
+                peer_id: *peer_id,
+                public_share: evaluate(&coefficients, index).to_affine(),
+            })
+            .collect();
+        let session = match self.keys {
+            1 => self.id.clone(),
+            _ => format!("{}-{}", self.id, key),
+        };
+        let group = GroupKey {
+            session,
+            epoch: 0,
+            threshold: self.config.threshold(),
+            public_key: coefficients[0].to_affine(),
+            members,
+        };
+        (SecretKey::new(share), group)
+    }
+}
+
+/// The DKG of `p2p dkg`: the session, the topic it runs on and what it
+/// takes to save our shares once it is over.
+pub struct DkgTask {
+    session: Session,
+    topic: Topic,
+    args: DkgArgs,
+}
+
+impl DkgTask {
+    /// Starts the DKG between the members of `roster` and deals our
+    /// polynomials.
+    pub fn start(
+        args: DkgArgs,
+        roster: &Roster,
+        keypair: &ed25519::Keypair,
+    ) -> Result<Self, Box<dyn Error>> {
+        let params = Params {
+            threshold: args.threshold as usize,
+            faults: args.faults.map(|faults| faults as usize),
+            keys: args.keys as usize,
+            asynchronous: args.asynchronous,
+        };
+        let coin = match &args.coin_share {
+            Some(path) => {
+                let share = Keystore::load(path)?.decrypt(&args.password)?;
+                let group_path = group_key::path_for(path);
+                let group = GroupKey::load(&group_path)
+                    .map_err(|e| format!("{}: {}", group_path.display(), e))?;
+                Some((group, share))
+            }
+            None => None,
+        };
+        let mut session = Session::new(
+            args.session.clone(),
+            params,
+            roster.peer_ids().copied().collect(),
+            keypair,
+            args.timeout,
+            coin.as_ref().map(|(group, share)| (group, share.clone())),
+        )?;
+        info!(
+            "Running DKG {} as member {} of {}",
+            session.id(),
+            session.index(),
+            roster.len()
+        );
+        session.deal(&mut rand::rngs::OsRng);
+        Ok(DkgTask {
+            topic: topic(&args.session),
+            session,
+            args,
+        })
+    }
+
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    pub fn is_ready(&mut self) -> bool {
+        self.session.is_ready()
+    }
+
+    /// What we publish on every tick, republished until the end for the
+    /// members that joined the topic after us.
+    pub fn payloads(&self) -> Vec<Payload> {
+        self.session
+            .dealing()
+            .into_iter()
+            .chain(self.session.echoes())
+            .chain(self.session.disputes())
+            .chain(self.session.broadcasts())
+            .collect()
+    }
+
+    /// The shares to send again, their recipients haven't acknowledged them.
+    pub fn undelivered(&self) -> Vec<(PeerId, ShareRequest)> {
+        self.session
+            .undelivered()
+            .map(|(peer_id, sealed)| (*peer_id, ShareRequest(sealed.clone())))
+            .collect()
+    }
+
+    pub fn delivered(&mut self, peer_id: &PeerId) {
+        self.session.delivered(peer_id);
+    }
+
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        self.session.handle(sender, payload)
+    }
+
+    /// Opens a share `peer_id` sent us and hands it to the session,
+    /// returning our complaint about the dealer if the share is bad, or what
+    /// we publish once it checks out. `Err(Some(_))` if the peer misbehaved
+    /// and `Err(None)` if the share should just be refused.
+    pub fn receive_share(
+        &mut self,
+        peer_id: &PeerId,
+        request: &ShareRequest,
+    ) -> Result<Vec<Payload>, Option<Offence>> {
+        let plaintext = match whisper::open(&self.session.keypair, &request.0) {
+            Some(plaintext) => Zeroizing::new(plaintext),
+            None => {
+                warn!("Dropping a share we can't open");
+                return Err(Some(Offence::BadWhisper));
+            }
+        };
+        self.session
+            .handle_share(peer_id, &plaintext)
+            .map_err(|reason| {
+                warn!("Refusing the share: {}", reason);
+                None
+            })
+    }
+
+    /// Saves our share of each group key.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        let args = self.args;
+        let output = self.session.finish()?;
+        info!(
+            "DKG {} done with dealers {:?}",
+            args.session, output.qualified
+        );
+
+        for (key, (share, group)) in output.keys.iter().enumerate() {
+            let group_public_key = hex::encode(group.public_key.to_compressed());
+            info!(
+                "Group public key of {}: {}",
+                group.session, group_public_key
+            );
+            let mut keystore = Keystore::encrypt(
+                share,
+                &args.password,
+                "",
+                Kdf::scrypt(&mut rand::rngs::OsRng),
+                &mut rand::rngs::OsRng,
+            )?;
+            keystore.description = format!(
+                "zk-lab DKG {}, share {} of group key {}",
+                group.session, output.index, group_public_key
+            );
+            let path = args.key_output(key);
+            keystore.save(&path)?;
+            info!("Saved share {} to {}", output.index, path.display());
+            let group_path = group_key::path_for(&path);
+            group.save(&group_path)?;
+            info!("Saved the public shares to {}", group_path.display());
+        }
+        Ok(())
+    }
+}
+
+/// A random symmetric polynomial `f(x, y)` of `degree` in both, by column:
+/// column `l` holds the coefficients of `x^k y^l`.
+fn bivariate<R: RngCore + CryptoRng>(degree: usize, rng: &mut R) -> Vec<SecretPolynomial> {
+    let mut columns = Vec::<Vec<Scalar>>::with_capacity(degree + 1);
+    for l in 0..=degree {
+        let mut column = columns.iter().map(|earlier| earlier[l]).collect::<Vec<_>>();
+        column.extend((l..=degree).map(|_| Scalar::random(&mut *rng)));
+        columns.push(column);
+    }
+    columns.into_iter().map(SecretPolynomial::new).collect()
+}
+
+/// Computes `∑ (a_i * G) * x^i` from the commitments `[a_i * G]`.
+fn evaluate(coefficients: &[G1Projective], x: u64) -> G1Projective {
+    let x = Scalar::from(x);
+    // Horner's method.
+    coefficients
+        .iter()
+        .rev()
+        .fold(G1Projective::identity(), |acc, c| acc * x + c)
+}
+
+/// What members echo in place of the commitments.
+fn digest(commitments: &[Vec<u8>]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for commitment in commitments {
+        hasher.update(commitment);
+    }
+    hasher.finalize().into()
+}
+
+/// What members echo in place of the dealers they settled on.
+fn qualified_digest(qualified: &[u64]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for dealer in qualified {
+        hasher.update(dealer.to_be_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// What a dealer's proof of knowledge is bound to, so it can't be replayed
+/// by another dealer, for another key or in another session.
+fn proof_context(session: &str, dealer: u64, key: u64) -> Vec<u8> {
+    let mut context = b"zk-lab/dkg/".to_vec();
+    context.extend_from_slice(session.as_bytes());
+    context.extend_from_slice(&dealer.to_be_bytes());
+    context.extend_from_slice(&key.to_be_bytes());
+    context
+}
+
+fn compress(points: &[G1Projective]) -> Vec<Vec<u8>> {
+    points
+        .iter()
+        .map(|point| point.to_affine().to_compressed().to_vec())
+        .collect()
+}
+
+fn decompress(points: &[Vec<u8>]) -> Option<Vec<G1Projective>> {
+    points
+        .iter()
+        .map(|bytes| {
+            let bytes: [u8; 48] = bytes.as_slice().try_into().ok()?;
+            Option::<G1Affine>::from(G1Affine::from_compressed(&bytes)).map(G1Projective::from)
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum DkgError {
+    /// This node isn't in the committee running the ceremony.
+    NotAMember,
+    /// The ceremony was asked for no key at all.
+    NoKeys,
+    /// A member's peer id doesn't carry an ed25519 key to seal its share to.
+    NoEncryptionKey(PeerId),
+    /// The threshold and faults don't fit the committee.
+    Config(ConfigError),
+    /// The asynchronous mode was given no share to flip its coins with.
+    NoCoin,
+    /// The share given to flip the coins with doesn't fit the committee.
+    Coin(CoinError),
+    /// There are too few members for reliable broadcast to outlast the
+    /// faults, and for the honest ones to recover the shares of the others.
+    Asynchronous {
+        threshold: usize,
+        faults: usize,
+        members: usize,
+    },
+    /// Time ran out before settling on the dealers.
+    Undecided,
+    /// Too few members confirmed they settled on the same dealers as us.
+    Unconfirmed { confirmed: usize, needed: usize },
+    /// We have no valid share from these dealers the members settled on.
+    MissingShares(Vec<u64>),
+    /// Too many dealers were left out for one of the others to surely be
+    /// honest.
+    TooFewDealers { qualified: usize, needed: usize },
+}
+
+impl fmt::Display for DkgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DkgError::NotAMember => write!(f, "this node isn't a member of the committee"),
+            DkgError::NoKeys => write!(f, "the ceremony generates no key"),
+            DkgError::NoEncryptionKey(peer_id) => {
+                write!(f, "{} has no ed25519 key to seal its share to", peer_id)
+            }
+            DkgError::Config(e) => write!(f, "{}", e),
+            DkgError::NoCoin => write!(
+                f,
+                "the asynchronous DKG needs a share of a key of the committee to flip its coins"
+            ),
+            DkgError::Coin(e) => write!(f, "{}", e),
+            DkgError::Asynchronous {
+                threshold,
+                faults,
+                members,
+            } => write!(
+                f,
+                "the asynchronous DKG of threshold {} tolerating {} faults needs at least {} members, the committee has {}",
+                threshold,
+                faults,
+                threshold + 2 * faults + 1,
+                members
+            ),
+            DkgError::Undecided => write!(f, "gave up before settling on the dealers"),
+            DkgError::Unconfirmed { confirmed, needed } => write!(
+                f,
+                "only {} members settled on the same dealers as us, {} are needed",
+                confirmed, needed
+            ),
+            DkgError::MissingShares(dealers) => write!(
+                f,
+                "no valid share from the dealers {:?} the members settled on",
+                dealers
+            ),
+            DkgError::TooFewDealers { qualified, needed } => write!(
+                f,
+                "only {} dealers qualified, at least {} are needed for one to be honest",
+                qualified, needed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DkgError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Decides what becomes of a payload member `index` publishes: it can
+    /// be changed, or dropped by returning false.
+    type Tamper = Box<dyn FnMut(u64, &mut Payload) -> bool>;
+
+    /// A committee running a ceremony in memory, every message published
+    /// reaching every other member.
+    struct Committee {
+        keypairs: Vec<ed25519::Keypair>,
+        peer_ids: Vec<PeerId>,
+        sessions: Vec<Session>,
+        /// Published payloads, who published them and who they are for.
+        queue: VecDeque<(usize, usize, Payload)>,
+        tamper: Tamper,
+        /// A dealer and a member whose share of it arrives corrupted.
+        corrupt: Option<(u64, u64)>,
+        /// A xorshift state picking the next payload to deliver, in the
+        /// order they were published if `None`.
+        shuffle: Option<u64>,
+    }
+
+    impl Committee {
+        fn new(members: usize, params: Params) -> Self {
+            let keypairs = (0..members)
+                .map(|_| ed25519::Keypair::generate())
+                .collect::<Vec<_>>();
+            let mut peer_ids = keypairs
+                .iter()
+                .map(|keypair| PeerId::from(PublicKey::from(keypair.public())))
+                .collect::<Vec<_>>();
+            // In the order of the members' indices.
+            let mut order = (0..members).collect::<Vec<_>>();
+            order.sort_by_key(|i| peer_ids[*i]);
+            let keypairs = order
+                .iter()
+                .map(|i| keypairs[*i].clone())
+                .collect::<Vec<_>>();
+            peer_ids.sort();
+            let (coin, shares) = coin_group(&peer_ids, params.threshold);
+            let sessions = keypairs
+                .iter()
+                .zip(shares)
+                .map(|(keypair, share)| {
+                    let mut session = Session::new(
+                        "test".into(),
+                        params,
+                        peer_ids.clone(),
+                        keypair,
+                        Duration::from_secs(60),
+                        Some((&coin, share)),
+                    )
+                    .unwrap();
+                    session.deal(&mut rand::thread_rng());
+                    session
+                })
+                .collect();
+            Committee {
+                keypairs,
+                peer_ids,
+                sessions,
+                queue: VecDeque::new(),
+                tamper: Box::new(|_, _| true),
+                corrupt: None,
+                shuffle: None,
+            }
+        }
+
+        fn tamper(mut self, tamper: impl FnMut(u64, &mut Payload) -> bool + 'static) -> Self {
+            self.tamper = Box::new(tamper);
+            self
+        }
+
+        fn publish(&mut self, from: usize, mut payload: Payload) {
+            if (self.tamper)(from as u64 + 1, &mut payload) {
+                for to in (0..self.sessions.len()).filter(|to| *to != from) {
+                    self.queue.push_back((from, to, payload.clone()));
+                }
+            }
+        }
+
+        /// Republishes what every member republishes on its timer.
+        fn republish(&mut self) {
+            for i in 0..self.sessions.len() {
+                let session = &self.sessions[i];
+                let payloads = session
+                    .dealing()
+                    .into_iter()
+                    .chain(session.echoes())
+                    .chain(session.disputes())
+                    .chain(session.broadcasts())
+                    .collect::<Vec<_>>();
+                for payload in payloads {
+                    self.publish(i, payload);
+                }
+            }
+        }
+
+        /// Sends the shares no one acknowledged yet.
+        fn send_shares(&mut self) {
+            for dealer in 0..self.sessions.len() {
+                let undelivered = self.sessions[dealer]
+                    .undelivered()
+                    .map(|(peer_id, sealed)| (*peer_id, sealed.clone()))
+                    .collect::<Vec<_>>();
+                for (peer_id, sealed) in undelivered {
+                    let to = self.peer_ids.binary_search(&peer_id).unwrap();
+                    let mut plaintext = whisper::open(&self.keypairs[to], &sealed).unwrap();
+                    if self.corrupt == Some((dealer as u64 + 1, to as u64 + 1)) {
+                        // The low bit of the last share.
+                        let last = plaintext.len() - 32;
+                        plaintext[last] ^= 1;
+                    }
+                    let sender = self.peer_ids[dealer];
+                    let replies = self.sessions[to].handle_share(&sender, &plaintext);
+                    for reply in replies.unwrap() {
+                        self.publish(to, reply);
+                    }
+                    self.sessions[dealer].delivered(&peer_id);
+                }
+            }
+        }
+
+        /// Delivers everything in the queue, and whatever that publishes.
+        fn deliver(&mut self) {
+            while let Some((from, to, payload)) = self.next() {
+                let sender = self.peer_ids[from];
+                for reply in self.sessions[to].handle(&sender, &payload) {
+                    self.publish(to, reply);
+                }
+            }
+        }
+
+        fn next(&mut self) -> Option<(usize, usize, Payload)> {
+            let state = match &mut self.shuffle {
+                Some(state) => state,
+                None => return self.queue.pop_front(),
+            };
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            let len = self.queue.len().max(1);
+            self.queue.swap_remove_back(*state as usize % len)
+        }
+
+        /// A few republishing rounds, enough for an honest committee.
+        fn run(&mut self) {
+            for _ in 0..6 {
+                self.republish();
+                self.send_shares();
+                self.deliver();
+            }
+        }
+
+        /// Settles every member on the dealers it has, as once the complaint
+        /// window is over, and exchanges the digests.
+        fn finish(mut self) -> Vec<Result<Output, DkgError>> {
+            for session in &mut self.sessions {
+                if session.agreement.is_none() {
+                    session.settle(Instant::now());
+                }
+            }
+            self.republish();
+            self.deliver();
+            self.sessions.into_iter().map(Session::finish).collect()
+        }
+    }
+
+    /// A group of the committee, as if from an earlier ceremony, for the
+    /// coins of the asynchronous mode.
+    fn coin_group(peer_ids: &[PeerId], threshold: usize) -> (GroupKey, Vec<SecretKey>) {
+        let polynomial = SecretPolynomial::random(threshold, &mut rand::thread_rng());
+        let shares = (1..=peer_ids.len() as u64)
+            .map(|x| polynomial.evaluate(x))
+            .collect::<Vec<_>>();
+        let group = GroupKey {
+            session: "coin".into(),
+            epoch: 0,
+            threshold,
+            public_key: polynomial.secret().public_key(),
+            members: peer_ids
+                .iter()
+                .zip(&shares)
+                .zip(1..)
+                .map(|((peer_id, share), index)| Member {
+                    index,
+                    peer_id: *peer_id,
+                    public_share: share.public_key(),
+                })
+                .collect(),
+        };
+        (group, shares)
+    }
+
+    fn params(keys: usize, asynchronous: bool) -> Params {
+        Params {
+            threshold: 1,
+            faults: None,
+            keys,
+            asynchronous,
+        }
+    }
+
+    /// Checks that every member ended up with the same keys and a share of
+    /// each that interpolates to it.
+    fn check_keys(outputs: Vec<Result<Output, DkgError>>, keys: usize) -> Vec<Output> {
+        let outputs = outputs.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        for output in &outputs {
+            assert_eq!(output.keys.len(), keys);
+            assert_eq!(output.qualified, outputs[0].qualified);
+        }
+        for key in 0..keys {
+            let group = &outputs[0].keys[key].1;
+            for output in &outputs {
+                let (share, theirs) = &output.keys[key];
+                assert_eq!(theirs.public_key, group.public_key);
+                let member = &group.members[output.index as usize - 1];
+                assert_eq!(member.public_share, share.public_key());
+            }
+            let points = outputs[..group.threshold + 1]
+                .iter()
+                .map(|output| (output.index, *output.keys[key].0.as_scalar()))
+                .collect::<Vec<_>>();
+            let secret = SecretKey::new(interpolate_at_zero(&points).unwrap());
+            assert_eq!(secret.public_key(), group.public_key);
+        }
+        outputs
+    }
+
+    #[test]
+    fn keys_come_out_independent() {
+        let mut committee = Committee::new(4, params(3, false));
+        committee.run();
+        let outputs = check_keys(committee.finish(), 3);
+        assert_eq!(outputs[0].qualified, vec![1, 2, 3, 4]);
+
+        let keys = &outputs[0].keys;
+        for (i, (_, first)) in keys.iter().enumerate() {
+            assert_eq!(first.session, format!("test-{}", i));
+            for (_, second) in &keys[i + 1..] {
+                assert_ne!(first.public_key, second.public_key);
+            }
+        }
+        // Each key has its own shares, one key's don't interpolate to
+        // another.
+        let points = outputs[..2]
+            .iter()
+            .map(|output| (output.index, *output.keys[0].0.as_scalar()))
+            .collect::<Vec<_>>();
+        let secret = SecretKey::new(interpolate_at_zero(&points).unwrap());
+        assert_ne!(secret.public_key(), keys[1].1.public_key);
+    }
+
+    #[test]
+    fn a_single_key_is_named_after_the_session() {
+        let mut committee = Committee::new(4, params(1, false));
+        committee.run();
+        let outputs = check_keys(committee.finish(), 1);
+        assert_eq!(outputs[0].keys[0].1.session, "test");
+    }
+
+    #[test]
+    fn a_bad_polynomial_disqualifies_the_dealer_for_every_key() {
+        // Dealer 2 publishes commitments that don't match the shares of the
+        // second key, and only of that key. Its justifications don't match
+        // them either.
+        let mut committee = Committee::new(4, params(3, false)).tamper(|dealer, payload| {
+            if let Payload::DkgDealing { commitments, .. } = payload {
+                if dealer == 2 {
+                    commitments[2 + 1] = G1Affine::generator().to_compressed().to_vec();
+                }
+            }
+            true
+        });
+        committee.run();
+        let outputs = check_keys(committee.finish(), 3);
+        assert_eq!(outputs[0].qualified, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn a_false_complaint_is_answered() {
+        let mut committee = Committee::new(4, params(1, false));
+        let complaint = Payload::Complaint {
+            accuser: 4,
+            accused: 1,
+            reason: "lies".into(),
+        };
+        committee.publish(3, complaint);
+        committee.run();
+        let outputs = check_keys(committee.finish(), 1);
+        assert_eq!(outputs[0].qualified, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn a_revealed_share_replaces_a_bad_one() {
+        let mut committee = Committee::new(4, params(2, false));
+        committee.corrupt = Some((1, 3));
+        committee.run();
+        // Everyone but the dealer checked the revealed shares.
+        assert!(committee.sessions[1..]
+            .iter()
+            .all(|session| session.justified.contains(&(1, 3))));
+        let outputs = check_keys(committee.finish(), 2);
+        assert_eq!(outputs[0].qualified, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn an_unanswered_complaint_disqualifies_the_dealer() {
+        let mut committee = Committee::new(4, params(1, false)).tamper(
+            |member, payload| !matches!(payload, Payload::DkgJustification { .. } if member == 1),
+        );
+        committee.corrupt = Some((1, 3));
+        committee.run();
+        let mut outputs = committee.finish();
+        // The dealer itself never heard of its answer missing.
+        assert!(matches!(
+            outputs.remove(0),
+            Err(DkgError::Unconfirmed { confirmed: 1, .. })
+        ));
+        let outputs = check_keys(outputs, 1);
+        assert_eq!(outputs[0].qualified, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn keys_wait_for_a_quorum_to_confirm() {
+        let mut committee = Committee::new(4, params(1, false))
+            .tamper(|_, payload| !matches!(payload, Payload::DkgQualified { .. }));
+        committee.run();
+        for output in committee.finish() {
+            assert!(matches!(
+                output,
+                Err(DkgError::Unconfirmed {
+                    confirmed: 1,
+                    needed: 3
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn asynchronous_sessions_agree_in_any_order() {
+        for seed in 1..=4 {
+            let mut committee = Committee::new(4, params(1, true));
+            committee.shuffle = Some(seed);
+            committee.run();
+            let outputs = check_keys(committee.finish(), 1);
+            assert!(outputs[0].qualified.len() >= 3);
+        }
+    }
+
+    #[test]
+    fn asynchronous_sessions_go_on_without_a_silent_member() {
+        let mut committee = Committee::new(4, params(1, true)).tamper(|member, _| member != 4);
+        committee.shuffle = Some(7);
+        committee.run();
+        let mut outputs = committee.finish();
+        outputs.pop();
+        let outputs = check_keys(outputs, 1);
+        assert_eq!(outputs[0].qualified, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn asynchronous_sessions_recover_a_corrupt_share() {
+        // Member 5 is silent, so dealer 1 is settled on in any case, and
+        // member 3 only gets its share of it from the others' rows.
+        let params = Params {
+            threshold: 1,
+            faults: Some(1),
+            keys: 2,
+            asynchronous: true,
+        };
+        for seed in 1..=4 {
+            let mut committee = Committee::new(5, params).tamper(|member, _| member != 5);
+            committee.corrupt = Some((1, 3));
+            committee.shuffle = Some(seed);
+            committee.run();
+            assert!(!committee.sessions[2]
+                .agreement
+                .as_ref()
+                .unwrap()
+                .rows
+                .contains_key(&1));
+            let mut outputs = committee.finish();
+            outputs.pop();
+            let outputs = check_keys(outputs, 2);
+            assert_eq!(outputs[0].qualified, vec![1, 2, 3, 4]);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! The framing of everything published on gossipsub, so that the chat and
+//! the threshold protocols can share the same swarm.
+//!
+//! Group elements travel in their compressed encoding and are only parsed by
+//! the protocol that handles the payload.
+//!
+//! Every envelope starts with the [`VERSION`] of its encoding, so a node
+//! tells an envelope it can't read from a malformed one instead of decoding
+//! it into garbage.
+//!
+//! Envelopes are published signed with the sender's BLS key, over the
+//! bincode encoding of the envelope prefixed with [`SIGNING_CONTEXT`], so
+//! the signatures can't be replayed as signatures on protocol messages.
+use bls12_381::{G1Affine, G2Affine};
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SIGNING_CONTEXT: &[u8] = b"zk-lab/envelope/v1";
+
+/// Bumped, along with the major version of `peers::PROTOCOL_VERSION`, whenever
+/// the encoding of envelopes changes. Only envelopes of this version are read.
+pub const VERSION: u16 = 15;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Comes first, so it can be read before the rest.
+    pub version: u16,
+    #[serde(with = "peer_id_bytes")]
+    pub sender: PeerId,
+    /// Counts up with every envelope the sender publishes. Together with the
+    /// sender it identifies the envelope.
+    pub seq: u64,
+    /// Milliseconds since the unix epoch, according to the sender.
+    pub timestamp: u64,
+    pub payload: Payload,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Payload {
+    Chat(String),
+    /// A dealer's Feldman commitments to its secret polynomials, one after
+    /// the other, the shares themselves are sent to each recipient directly.
+    DkgDealing {
+        dealer: u64,
+        commitments: Vec<Vec<u8>>,
+        /// Schnorr proofs of knowledge of the secret behind the first
+        /// commitment of each polynomial, 64 bytes each.
+        proof: Vec<u8>,
+    },
+    /// The digest of the commitments of `dealer` as the sender got them, so
+    /// members can tell whether they all got the same.
+    DkgEcho {
+        dealer: u64,
+        digest: [u8; 32],
+    },
+    PartialSignature {
+        index: u64,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        /// The DLEQ proof that the signature matches the member's public
+        /// share, 64 bytes.
+        proof: Vec<u8>,
+        /// The epoch of the share that signed.
+        epoch: u64,
+    },
+    /// Raised by `accuser` when the share it got from `accused` doesn't
+    /// match the dealer's commitments.
+    Complaint {
+        accuser: u64,
+        accused: u64,
+        reason: String,
+    },
+    /// The shares `dealer` dealt to `accuser`, one per key, revealed in
+    /// answer to its complaint for everyone to check against the dealer's
+    /// commitments.
+    DkgJustification {
+        dealer: u64,
+        accuser: u64,
+        shares: Vec<[u8; 32]>,
+    },
+    /// The digest of the dealers the sender settled on, keys are only
+    /// written once a quorum of members settled on the same ones.
+    DkgQualified {
+        digest: [u8; 32],
+    },
+    /// Asks the group members numbered in `signers` for their partial
+    /// signature on `message`.
+    SignRequest {
+        message: Vec<u8>,
+        signers: Vec<u64>,
+        /// The epoch of the shares asked for.
+        epoch: u64,
+    },
+    /// The group's signature on `message`, aggregated from the partial
+    /// signatures.
+    GroupSignature {
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    },
+    /// A member's partial signature of a beacon round.
+    BeaconPartial {
+        round: u64,
+        index: u64,
+        signature: Vec<u8>,
+        /// The DLEQ proof that the signature matches the member's public
+        /// share, 64 bytes.
+        proof: Vec<u8>,
+    },
+    /// The group's signature of a beacon round.
+    Beacon {
+        round: u64,
+        signature: Vec<u8>,
+    },
+    /// Asks for the beacons of rounds `from` to `to`, inclusive.
+    BeaconRequest {
+        from: u64,
+        to: u64,
+    },
+    /// The SRS after the sender's turn of a powers-of-tau ceremony, and the
+    /// proof that it updates the SRS of the turn before.
+    SrsUpdate {
+        turn: u64,
+        srs: Vec<u8>,
+        proof: Vec<u8>,
+    },
+    /// A dealer's PVSS dealing: the commitments to every member's share,
+    /// the shares encrypted to the members' BLS keys and the proofs that
+    /// they match, which anyone can check.
+    PvssDealing {
+        dealer: u64,
+        commitments: Vec<Vec<u8>>,
+        encrypted: Vec<Vec<u8>>,
+        proofs: Vec<Vec<u8>>,
+    },
+    /// Member `index`'s decryption of its share of `dealer`'s secret, with
+    /// the proof that it is right.
+    PvssShare {
+        dealer: u64,
+        index: u64,
+        share: Vec<u8>,
+        proof: Vec<u8>,
+    },
+    /// A message of the reliable broadcast of member `origin`'s dealing or
+    /// proposal in an asynchronous DKG, over their bincode encoding.
+    DkgBroadcast {
+        origin: u64,
+        kind: BroadcastKind,
+        message: rbc::Message<Vec<u8>>,
+    },
+    /// The points of the sender's rows of `dealer`'s polynomials at the
+    /// index of `accuser`, one per key, sealed to it in answer to its
+    /// complaint in an asynchronous DKG, to recover its share from.
+    DkgRecovery {
+        dealer: u64,
+        accuser: u64,
+        sealed: Vec<u8>,
+    },
+    /// A message of the binary agreement on whether to settle on member
+    /// `proposer`'s proposal in an asynchronous DKG, whose coin shares are
+    /// compressed partial signatures followed by their proofs.
+    DkgAgreement {
+        proposer: u64,
+        message: rbc::agreement::Message<Vec<u8>>,
+    },
+    /// A member's dealing of its share to the committee of `epoch`: the
+    /// commitments to the polynomial it shares it with, and the sub-share of
+    /// each member of the next committee sealed to its peer id.
+    HandoffDealing {
+        epoch: u64,
+        dealer: u64,
+        commitments: Vec<Vec<u8>>,
+        shares: Vec<Vec<u8>>,
+    },
+    /// Raised by `accuser` of the committee of `epoch` when the sub-share it
+    /// got from `accused` doesn't match the dealer's commitments.
+    HandoffComplaint {
+        epoch: u64,
+        accuser: u64,
+        accused: u64,
+        reason: String,
+    },
+    /// The sub-share `dealer` dealt to `accuser` in the handoff to `epoch`,
+    /// revealed in answer to its complaint for everyone to check.
+    HandoffJustification {
+        epoch: u64,
+        dealer: u64,
+        accuser: u64,
+        share: [u8; 32],
+    },
+    /// The digest of the dealers the sender settled on combining in the
+    /// handoff to `epoch`.
+    HandoffQualified {
+        epoch: u64,
+        digest: [u8; 32],
+    },
+}
+
+/// What an asynchronous DKG broadcasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BroadcastKind {
+    /// A dealer's commitments and proof.
+    Dealing,
+    /// The dealers a member got everything from, to be settled on.
+    Proposal,
+}
+
+impl Envelope {
+    /// Wraps the payload, stamped with the current time.
+    pub fn new(sender: PeerId, seq: u64, payload: Payload) -> Self {
+        Envelope {
+            version: VERSION,
+            sender,
+            seq,
+            timestamp: unix_millis(),
+            payload,
+        }
+    }
+
+    pub fn sign(self, sk: &SecretKey) -> SignedEnvelope {
+        let signature = signature::sign(sk, &self.signing_bytes());
+        SignedEnvelope {
+            envelope: self,
+            signature: signature.to_compressed().to_vec(),
+        }
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = SIGNING_CONTEXT.to_vec();
+        bincode::serialize_into(&mut bytes, self).expect("Envelopes are always serializable");
+        bytes
+    }
+}
+
+/// Milliseconds since the unix epoch.
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// What is actually published, an envelope and the sender's BLS signature
+/// on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedEnvelope {
+    pub envelope: Envelope,
+    /// A compressed G2 point.
+    pub signature: Vec<u8>,
+}
+
+impl SignedEnvelope {
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Envelopes are always serializable")
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if let [low, high, ..] = *bytes {
+            let version = u16::from_le_bytes([low, high]);
+            if version != VERSION {
+                return Err(DecodeError::Version(version));
+            }
+        }
+        bincode::deserialize(bytes).map_err(DecodeError::Malformed)
+    }
+
+    /// Checks the signature against the sender's public key.
+    pub fn verify(&self, pk: &G1Affine) -> bool {
+        let bytes: [u8; 96] = match self.signature.as_slice().try_into() {
+            Ok(bytes) => bytes,
+            Err(_) => return false,
+        };
+
+        match Option::<G2Affine>::from(G2Affine::from_compressed(&bytes)) {
+            Some(sig) => signature::verify(pk, &self.envelope.signing_bytes(), &sig),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// An envelope of another version, which we can't read.
+    Version(u16),
+    Malformed(bincode::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Version(version) => write!(
+                f,
+                "envelope version {} isn't supported, this node reads version {}",
+                version, VERSION
+            ),
+            DecodeError::Malformed(e) => e.fmt(f),
+        }
+    }
+}
+
+pub(crate) mod peer_id_bytes {
+    use libp2p::PeerId;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(peer_id: &PeerId, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(&peer_id.to_bytes())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<PeerId, D::Error> {
+        let bytes = Vec::<u8>::deserialize(d)?;
+        PeerId::from_bytes(&bytes).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(sk: &SecretKey) -> SignedEnvelope {
+        Envelope::new(
+            PeerId::random(),
+            1,
+            Payload::Chat("Hello world".to_string()),
+        )
+        .sign(sk)
+    }
+
+    #[test]
+    fn signed_envelopes_round_trip() {
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signed = signed(&sk);
+        let decoded = SignedEnvelope::decode(&signed.encode()).unwrap();
+        assert_eq!(decoded, signed);
+        assert!(decoded.verify(&sk.public_key()));
+    }
+
+    #[test]
+    fn signatures_only_verify_on_what_was_signed() {
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signed = signed(&sk);
+        let other = SecretKey::random(&mut rand::thread_rng());
+        assert!(!signed.verify(&other.public_key()));
+
+        let mut tampered = signed.clone();
+        tampered.envelope.payload = Payload::Chat("Goodbye world".to_string());
+        assert!(!tampered.verify(&sk.public_key()));
+
+        let mut truncated = signed;
+        truncated.signature.pop();
+        assert!(!truncated.verify(&sk.public_key()));
+    }
+
+    #[test]
+    fn other_versions_are_told_apart_from_malformed_envelopes() {
+        let mut bytes = signed(&SecretKey::random(&mut rand::thread_rng())).encode();
+        assert!(matches!(
+            SignedEnvelope::decode(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Malformed(_))
+        ));
+
+        bytes[..2].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            SignedEnvelope::decode(&bytes),
+            Err(DecodeError::Version(version)) if version == VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,501 @@
+//! Committee rotation: the key a DKG left a committee with is handed over to
+//! the next committee once every period, see `handoff`, and each handoff
+//! starts a new epoch. The group public key stays the same across epochs,
+//! the shares and public shares don't.
+//!
+//! The handoff to epoch e is due on the same schedule as beacon round e, so
+//! the handoff to epoch 1 is due at the genesis time. A share is retired as
+//! soon as the handoff to the next epoch is over: sign requests and partial
+//! signatures carry the epoch of the shares they are for, and those of
+//! another epoch than the one of our group file are turned down. A retired
+//! share's partial signature wouldn't verify against the public shares of
+//! the next epoch anyway, the epoch tells it apart from a corrupted one.
+use crate::cli::RotateArgs;
+use crate::envelope::Payload;
+use crate::group_key::{self, GroupKey};
+use crate::handoff::{self, Handoff};
+use crate::roster::Roster;
+use beacon::Schedule;
+use bls_shamir::keystore::{Kdf, Keystore};
+use bls_shamir::secret::SecretKey;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::identity::ed25519;
+use libp2p::PeerId;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Keeps the group and our share of the current epoch.
+#[derive(Debug)]
+pub struct EpochManager {
+    group: GroupKey,
+    local: PeerId,
+    /// Our index and share, `None` while we aren't in the committee.
+    share: Option<(u64, SecretKey)>,
+    schedule: Schedule,
+}
+
+impl EpochManager {
+    /// Starts from the group of the current epoch, with our share if we are
+    /// a member of its committee.
+    pub fn new(
+        group: GroupKey,
+        share: Option<SecretKey>,
+        local: &PeerId,
+        schedule: Schedule,
+    ) -> Result<Self, EpochError> {
+        let share = share
+            .map(|share| member_share(&group, local, share))
+            .transpose()?;
+        Ok(EpochManager {
+            group,
+            local: *local,
+            share,
+            schedule,
+        })
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.group.epoch
+    }
+
+    pub fn group(&self) -> &GroupKey {
+        &self.group
+    }
+
+    /// Our index in the committee of the current epoch.
+    pub fn index(&self) -> Option<u64> {
+        self.share.as_ref().map(|(index, _)| *index)
+    }
+
+    pub fn share(&self) -> Option<&SecretKey> {
+        self.share.as_ref().map(|(_, share)| share)
+    }
+
+    /// When the handoff to the next epoch is due.
+    pub fn next_handoff(&self) -> SystemTime {
+        self.schedule.time_of(self.epoch() + 1)
+    }
+
+    /// Whether the handoff to the next epoch is due at `time`.
+    pub fn is_due(&self, time: SystemTime) -> bool {
+        self.schedule.round_at(time) > self.epoch()
+    }
+
+    /// Moves on to the group of the next epoch, retiring our share for the
+    /// new one, `None` if we left the committee.
+    pub fn advance(&mut self, group: GroupKey, share: Option<SecretKey>) -> Result<(), EpochError> {
+        check(self.epoch() + 1, group.epoch)?;
+        let share = share
+            .map(|share| member_share(&group, &self.local, share))
+            .transpose()?;
+        // Dropping the old share wipes it.
+        self.group = group;
+        self.share = share;
+        Ok(())
+    }
+}
+
+/// The rotation of `p2p rotate`: the current epoch, the handoff to the next
+/// one while it runs, and where the shares of the next epochs go.
+pub struct RotationTask {
+    manager: EpochManager,
+    handoff: Option<Handoff>,
+    topic: Topic,
+    args: RotateArgs,
+    local: PeerId,
+    /// Opens the sub-shares sealed to us.
+    keypair: ed25519::Keypair,
+}
+
+impl RotationTask {
+    pub fn start(
+        args: RotateArgs,
+        local: &PeerId,
+        keypair: ed25519::Keypair,
+    ) -> Result<Self, Box<dyn Error>> {
+        let group_path = match (&args.group, &args.share) {
+            (Some(path), _) => path.clone(),
+            (None, Some(share)) => group_key::path_for(share),
+            (None, None) => unreachable!("--group or --share is required"),
+        };
+        let group =
+            GroupKey::load(&group_path).map_err(|e| format!("{}: {}", group_path.display(), e))?;
+        let share = match &args.share {
+            Some(path) => Some(Keystore::load(path)?.decrypt(&args.password)?),
+            None => None,
+        };
+        let schedule = Schedule::new(UNIX_EPOCH + Duration::from_secs(args.genesis), args.period);
+        let manager = EpochManager::new(group, share, local, schedule)?;
+        let session = &manager.group().session;
+        match manager.index() {
+            Some(index) => info!(
+                "Rotating the committee of DKG {} as member {} of epoch {}",
+                session,
+                index,
+                manager.epoch()
+            ),
+            None => info!(
+                "Joining the committee of DKG {} after epoch {}",
+                session,
+                manager.epoch()
+            ),
+        }
+        log_next_handoff(&manager);
+        Ok(RotationTask {
+            topic: handoff::topic(session),
+            manager,
+            handoff: None,
+            args,
+            local: *local,
+            keypair,
+        })
+    }
+
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Hands over once the handoff is over and starts the next one once it
+    /// is due, returning what we publish: our dealing and disputes,
+    /// republished until the end for the members that joined the topic
+    /// after us. `None` once we left the committee.
+    pub fn tick(&mut self) -> Result<Option<Vec<Payload>>, Box<dyn Error>> {
+        if self
+            .handoff
+            .as_mut()
+            .is_some_and(|handoff| handoff.is_ready())
+        {
+            let finished = self.handoff.take().expect("The handoff is running");
+            if !self.hand_over(finished)? {
+                return Ok(None);
+            }
+        }
+        if self.handoff.is_none() && self.manager.is_due(SystemTime::now()) {
+            self.handoff = Some(self.start_handoff()?);
+        }
+        let payloads = self.handoff.as_ref().map_or_else(Vec::new, |handoff| {
+            handoff
+                .dealing()
+                .into_iter()
+                .chain(handoff.disputes())
+                .collect()
+        });
+        Ok(Some(payloads))
+    }
+
+    /// Whether a handoff is running.
+    pub fn is_handing_over(&self) -> bool {
+        self.handoff.is_some()
+    }
+
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        match &mut self.handoff {
+            Some(handoff) => handoff.handle(sender, payload, &self.keypair),
+            None => Vec::new(),
+        }
+    }
+
+    /// Starts handing the current epoch over to the committee of `--next`,
+    /// which is read again for every handoff.
+    fn start_handoff(&self) -> Result<Handoff, Box<dyn Error>> {
+        let args = &self.args;
+        let next =
+            Roster::load(&args.next).map_err(|e| format!("{}: {}", args.next.display(), e))?;
+        let threshold = args
+            .threshold
+            .map_or(self.manager.group().threshold, |threshold| {
+                threshold as usize
+            });
+        let mut handoff = Handoff::new(
+            &self.manager,
+            next.peer_ids().copied().collect(),
+            threshold,
+            &self.local,
+            args.timeout,
+        )?;
+        info!(
+            "Handing epoch {} over to the {} members of epoch {}, any {} can sign",
+            self.manager.epoch(),
+            handoff.members(),
+            handoff.epoch(),
+            threshold + 1
+        );
+        handoff.deal(&mut rand::rngs::OsRng);
+        Ok(handoff)
+    }
+
+    /// Saves our share of the next epoch once the handoff is over and moves
+    /// on to it, returning whether we are still in the committee.
+    fn hand_over(&mut self, handoff: Handoff) -> Result<bool, Box<dyn Error>> {
+        let output = handoff.finish()?;
+        let group = &output.group;
+        info!(
+            "Handed over to the {} members of epoch {} with dealers {:?}",
+            group.members.len(),
+            group.epoch,
+            output.dealers
+        );
+
+        let path = self.args.output(&group.session, group.epoch);
+        if let Some(share) = &output.share {
+            let group_public_key = hex::encode(group.public_key.to_compressed());
+            let mut keystore = Keystore::encrypt(
+                share,
+                &self.args.password,
+                "",
+                Kdf::scrypt(&mut rand::rngs::OsRng),
+                &mut rand::rngs::OsRng,
+            )?;
+            keystore.description = format!(
+                "zk-lab DKG {}, epoch {} share of group key {}",
+                group.session, group.epoch, group_public_key
+            );
+            keystore.save(&path)?;
+            info!(
+                "Saved our share of epoch {} to {}",
+                group.epoch,
+                path.display()
+            );
+        }
+        // Saved by the members leaving the committee too, to check the
+        // signatures of the next one.
+        let group_path = group_key::path_for(&path);
+        group.save(&group_path)?;
+        info!("Saved the public shares to {}", group_path.display());
+
+        self.manager.advance(output.group, output.share)?;
+        match self.manager.index() {
+            Some(index) => {
+                info!("Member {} of epoch {}", index, self.manager.epoch());
+                log_next_handoff(&self.manager);
+                Ok(true)
+            }
+            None => {
+                info!("Left the committee, our share is retired");
+                Ok(false)
+            }
+        }
+    }
+}
+
+fn log_next_handoff(manager: &EpochManager) {
+    let wait = manager
+        .next_handoff()
+        .duration_since(SystemTime::now())
+        .unwrap_or_default();
+    info!(
+        "The handoff to epoch {} is due in {}s",
+        manager.epoch() + 1,
+        wait.as_secs()
+    );
+}
+
+/// Checks that a message for the shares of `epoch` is for those of the
+/// `current` one.
+pub fn check(current: u64, epoch: u64) -> Result<(), EpochError> {
+    if epoch < current {
+        return Err(EpochError::Retired { epoch, current });
+    }
+    if epoch > current {
+        return Err(EpochError::Ahead { epoch, current });
+    }
+    Ok(())
+}
+
+fn member_share(
+    group: &GroupKey,
+    local: &PeerId,
+    share: SecretKey,
+) -> Result<(u64, SecretKey), EpochError> {
+    let index = group.index_of(local).ok_or(EpochError::NotAMember)?;
+    let member = group.member(index).expect("Indices are of members");
+    if member.public_share != share.public_key() {
+        return Err(EpochError::WrongShare);
+    }
+    Ok((index, share))
+}
+
+#[derive(Debug)]
+pub enum EpochError {
+    /// We have a share but aren't in the committee of the epoch.
+    NotAMember,
+    /// The share doesn't match our public share in the group file.
+    WrongShare,
+    /// The shares of `epoch` were handed over already.
+    Retired { epoch: u64, current: u64 },
+    /// The shares of `epoch` aren't ours yet.
+    Ahead { epoch: u64, current: u64 },
+}
+
+impl fmt::Display for EpochError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpochError::NotAMember => {
+                write!(f, "this node isn't a member of the committee of the epoch")
+            }
+            EpochError::WrongShare => {
+                write!(
+                    f,
+                    "the share doesn't match its public share in the group file"
+                )
+            }
+            EpochError::Retired { epoch, current } => write!(
+                f,
+                "the shares of epoch {} are retired, the committee is at epoch {}",
+                epoch, current
+            ),
+            EpochError::Ahead { epoch, current } => write!(
+                f,
+                "epoch {} is ahead of ours, {}, is the group file out of date?",
+                epoch, current
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EpochError {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::group_key::Member;
+    use bls_shamir::secret::SecretPolynomial;
+    use libp2p::identity::{ed25519, PublicKey};
+    use std::time::Duration;
+
+    /// The group of a committee of `members` fresh peers at epoch 0, in the
+    /// order of their indices, with their keypairs and shares.
+    pub(crate) fn committee(
+        members: usize,
+        threshold: usize,
+    ) -> (GroupKey, Vec<ed25519::Keypair>, Vec<SecretKey>) {
+        let mut keypairs = (0..members)
+            .map(|_| ed25519::Keypair::generate())
+            .collect::<Vec<_>>();
+        keypairs.sort_by_key(peer_id);
+        let polynomial = SecretPolynomial::random(threshold, &mut rand::thread_rng());
+        let shares = (1..=members as u64)
+            .map(|x| polynomial.evaluate(x))
+            .collect::<Vec<_>>();
+        let group = GroupKey {
+            session: "test".into(),
+            epoch: 0,
+            threshold,
+            public_key: polynomial.secret().public_key(),
+            members: keypairs
+                .iter()
+                .zip(&shares)
+                .zip(1..)
+                .map(|((keypair, share), index)| Member {
+                    index,
+                    peer_id: peer_id(keypair),
+                    public_share: share.public_key(),
+                })
+                .collect(),
+        };
+        (group, keypairs, shares)
+    }
+
+    pub(crate) fn peer_id(keypair: &ed25519::Keypair) -> PeerId {
+        PeerId::from(PublicKey::from(keypair.public()))
+    }
+
+    fn schedule() -> Schedule {
+        Schedule::new(SystemTime::UNIX_EPOCH, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn check_only_lets_the_current_epoch_through() {
+        assert!(check(3, 3).is_ok());
+        assert!(matches!(
+            check(3, 2),
+            Err(EpochError::Retired {
+                epoch: 2,
+                current: 3
+            })
+        ));
+        assert!(matches!(
+            check(3, 4),
+            Err(EpochError::Ahead {
+                epoch: 4,
+                current: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn new_checks_the_share_against_the_group() {
+        let (group, keypairs, shares) = committee(3, 1);
+        let local = peer_id(&keypairs[1]);
+
+        let manager =
+            EpochManager::new(group.clone(), Some(shares[1].clone()), &local, schedule()).unwrap();
+        assert_eq!(manager.index(), Some(2));
+        assert_eq!(manager.share(), Some(&shares[1]));
+
+        let wrong = EpochManager::new(group.clone(), Some(shares[0].clone()), &local, schedule());
+        assert!(matches!(wrong, Err(EpochError::WrongShare)));
+
+        let outsider = peer_id(&ed25519::Keypair::generate());
+        let stranger = EpochManager::new(
+            group.clone(),
+            Some(shares[1].clone()),
+            &outsider,
+            schedule(),
+        );
+        assert!(matches!(stranger, Err(EpochError::NotAMember)));
+
+        let watcher = EpochManager::new(group, None, &outsider, schedule()).unwrap();
+        assert_eq!(watcher.index(), None);
+    }
+
+    #[test]
+    fn advance_retires_the_share_for_the_next_epoch_only() {
+        let (group, keypairs, shares) = committee(3, 1);
+        let local = peer_id(&keypairs[0]);
+        let mut manager =
+            EpochManager::new(group.clone(), Some(shares[0].clone()), &local, schedule()).unwrap();
+
+        let mut skipped = group.clone();
+        skipped.epoch = 2;
+        assert!(matches!(
+            manager.advance(skipped, None),
+            Err(EpochError::Ahead { .. })
+        ));
+        assert!(matches!(
+            manager.advance(group.clone(), None),
+            Err(EpochError::Retired { .. })
+        ));
+        assert_eq!(manager.epoch(), 0);
+
+        // The next committee leaves us out.
+        let (mut next, _, _) = committee(3, 1);
+        next.epoch = 1;
+        manager.advance(next, None).unwrap();
+        assert_eq!(manager.epoch(), 1);
+        assert_eq!(manager.index(), None);
+        assert_eq!(manager.share(), None);
+    }
+
+    #[test]
+    fn handoffs_follow_the_beacon_schedule() {
+        let (group, _, _) = committee(3, 1);
+        let local = peer_id(&ed25519::Keypair::generate());
+        let mut manager = EpochManager::new(group.clone(), None, &local, schedule()).unwrap();
+        let genesis = SystemTime::UNIX_EPOCH;
+        let period = Duration::from_secs(60);
+
+        // The handoff to epoch 1 is due at the genesis.
+        assert_eq!(manager.next_handoff(), genesis);
+        assert!(manager.is_due(genesis));
+
+        let mut next = group;
+        next.epoch = 1;
+        manager.advance(next, None).unwrap();
+        assert_eq!(manager.next_handoff(), genesis + period);
+        assert!(!manager.is_due(genesis + period - Duration::from_secs(1)));
+        assert!(manager.is_due(genesis + period));
+    }
+}
@@ -0,0 +1,292 @@
+//! Splits envelopes too large for a gossipsub frame into fragments, and puts
+//! them back together on the other end.
+//!
+//! A fragment is published as [`MAGIC`] followed by the bincode encoding of
+//! [`Fragment`], which no encoded envelope starts with. It names the envelope
+//! it belongs to by sender and sequence number and carries the SHA-256 of its
+//! own piece, so a corrupt fragment is caught on its own rather than as a bad
+//! signature on the whole envelope. Fragments whose siblings don't all show
+//! up within [`REASSEMBLY_TIMEOUT`] are dropped.
+use libp2p::PeerId;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+pub const MAGIC: &[u8] = b"zk-lab/fragment/v1";
+
+/// The largest piece of an envelope in a fragment, which leaves room under
+/// the 64 KiB gossipsub frame for the rest of the message.
+pub const FRAGMENT_SIZE: usize = 48 * 1024;
+
+/// An envelope of at most 12 MiB, plenty for dealings of large committees.
+pub const MAX_FRAGMENTS: u16 = 256;
+
+pub const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many envelopes can be in pieces at once, the least recently added to
+/// is dropped first.
+pub const CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fragment {
+    #[serde(with = "crate::envelope::peer_id_bytes")]
+    pub sender: PeerId,
+    pub seq: u64,
+    pub index: u16,
+    pub count: u16,
+    pub checksum: [u8; 32],
+    pub data: Vec<u8>,
+}
+
+impl Fragment {
+    /// Decodes a published message, `None` if it isn't a fragment at all and
+    /// `Some(Err(_))` if it is a malformed one.
+    pub fn decode(bytes: &[u8]) -> Option<Result<Self, bincode::Error>> {
+        let bytes = bytes.strip_prefix(MAGIC)?;
+        Some(bincode::deserialize(bytes))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, self).expect("Fragments are always serializable");
+        bytes
+    }
+
+    /// Whether the fragment is consistent with itself.
+    pub fn is_valid(&self) -> bool {
+        self.index < self.count
+            && self.count <= MAX_FRAGMENTS
+            && <[u8; 32]>::from(Sha256::digest(&self.data)) == self.checksum
+    }
+}
+
+/// Splits an encoded envelope into fragments, a single one if it fits.
+pub fn split(sender: PeerId, seq: u64, bytes: &[u8]) -> Vec<Fragment> {
+    let count = bytes.len().div_ceil(FRAGMENT_SIZE).max(1) as u16;
+    (0..count)
+        .map(|index| {
+            let start = index as usize * FRAGMENT_SIZE;
+            let data = bytes[start..bytes.len().min(start + FRAGMENT_SIZE)].to_vec();
+            Fragment {
+                sender,
+                seq,
+                index,
+                count,
+                checksum: Sha256::digest(&data).into(),
+                data,
+            }
+        })
+        .collect()
+}
+
+pub struct Reassembler {
+    timeout: Duration,
+    partial: LruCache<(PeerId, u64), Partial>,
+}
+
+struct Partial {
+    started: Instant,
+    fragments: Vec<Option<Vec<u8>>>,
+}
+
+/// What became of a fragment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reassembly {
+    /// The envelope is still missing fragments.
+    Pending,
+    /// The fragment was the last one missing, here is the whole envelope.
+    Complete(Vec<u8>),
+    /// The fragment doesn't agree with its siblings on how many there are.
+    Inconsistent,
+}
+
+impl Reassembler {
+    pub fn new(capacity: usize, timeout: Duration) -> Self {
+        Reassembler {
+            timeout,
+            partial: LruCache::new(capacity),
+        }
+    }
+
+    /// Adds a fragment that [`Fragment::is_valid`].
+    pub fn insert(&mut self, fragment: Fragment) -> Reassembly {
+        let key = (fragment.sender, fragment.seq);
+        if self.partial.peek(&key).is_none() {
+            let partial = Partial {
+                started: Instant::now(),
+                fragments: vec![None; fragment.count as usize],
+            };
+            self.partial.put(key, partial);
+        }
+        let partial = self.partial.get_mut(&key).expect("Just inserted");
+        if partial.fragments.len() != fragment.count as usize {
+            return Reassembly::Inconsistent;
+        }
+
+        partial.fragments[fragment.index as usize] = Some(fragment.data);
+        if partial.fragments.iter().any(Option::is_none) {
+            return Reassembly::Pending;
+        }
+        let partial = self.partial.pop(&key).expect("Looked up above");
+        Reassembly::Complete(partial.fragments.into_iter().flatten().flatten().collect())
+    }
+
+    /// Drops the envelopes that have waited too long for their fragments,
+    /// returning their senders and sequence numbers.
+    pub fn expire(&mut self) -> Vec<(PeerId, u64)> {
+        let timeout = self.timeout;
+        let expired: Vec<_> = self
+            .partial
+            .iter()
+            .filter(|(_, partial)| partial.started.elapsed() > timeout)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &expired {
+            self.partial.pop(key);
+        }
+        expired
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Reassembler::new(CAPACITY, REASSEMBLY_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn reassembles_out_of_order() {
+        let sender = PeerId::random();
+        let bytes = envelope(FRAGMENT_SIZE * 2 + 100);
+        let mut fragments = split(sender, 7, &bytes);
+        assert_eq!(fragments.len(), 3);
+        assert!(fragments.iter().all(Fragment::is_valid));
+        fragments.reverse();
+
+        let mut reassembler = Reassembler::default();
+        let last = fragments.pop().unwrap();
+        for fragment in fragments {
+            let decoded = Fragment::decode(&fragment.encode()).unwrap().unwrap();
+            assert_eq!(reassembler.insert(decoded), Reassembly::Pending);
+        }
+        assert_eq!(reassembler.insert(last), Reassembly::Complete(bytes));
+    }
+
+    #[test]
+    fn small_and_empty_envelopes_are_one_fragment() {
+        let sender = PeerId::random();
+        let mut reassembler = Reassembler::default();
+        for bytes in [vec![], envelope(10), envelope(FRAGMENT_SIZE)] {
+            let fragments = split(sender, bytes.len() as u64, &bytes);
+            assert_eq!(fragments.len(), 1);
+            let fragment = fragments.into_iter().next().unwrap();
+            assert_eq!(reassembler.insert(fragment), Reassembly::Complete(bytes));
+        }
+    }
+
+    #[test]
+    fn duplicates_do_not_complete_an_envelope() {
+        let sender = PeerId::random();
+        let bytes = envelope(FRAGMENT_SIZE + 1);
+        let fragments = split(sender, 1, &bytes);
+
+        let mut reassembler = Reassembler::default();
+        assert_eq!(
+            reassembler.insert(fragments[0].clone()),
+            Reassembly::Pending
+        );
+        assert_eq!(
+            reassembler.insert(fragments[0].clone()),
+            Reassembly::Pending
+        );
+        assert_eq!(
+            reassembler.insert(fragments[1].clone()),
+            Reassembly::Complete(bytes)
+        );
+    }
+
+    #[test]
+    fn envelopes_are_kept_apart() {
+        let (alice, bob) = (PeerId::random(), PeerId::random());
+        let (first, second) = (envelope(FRAGMENT_SIZE + 1), vec![1; FRAGMENT_SIZE + 1]);
+        let a = split(alice, 1, &first);
+        let b = split(bob, 1, &second);
+        let c = split(alice, 2, &second);
+
+        let mut reassembler = Reassembler::default();
+        for fragment in [&a[0], &b[0], &c[0]] {
+            assert_eq!(reassembler.insert(fragment.clone()), Reassembly::Pending);
+        }
+        assert_eq!(
+            reassembler.insert(b[1].clone()),
+            Reassembly::Complete(second.clone())
+        );
+        assert_eq!(
+            reassembler.insert(a[1].clone()),
+            Reassembly::Complete(first)
+        );
+        assert_eq!(
+            reassembler.insert(c[1].clone()),
+            Reassembly::Complete(second)
+        );
+    }
+
+    #[test]
+    fn mismatched_counts_are_inconsistent() {
+        let sender = PeerId::random();
+        let fragments = split(sender, 1, &envelope(FRAGMENT_SIZE * 2 + 1));
+        let mut liar = fragments[1].clone();
+        liar.count = 2;
+
+        let mut reassembler = Reassembler::default();
+        assert_eq!(
+            reassembler.insert(fragments[0].clone()),
+            Reassembly::Pending
+        );
+        assert_eq!(reassembler.insert(liar), Reassembly::Inconsistent);
+    }
+
+    #[test]
+    fn corrupt_fragments_are_invalid() {
+        let mut fragment = split(PeerId::random(), 1, &envelope(100)).remove(0);
+        fragment.data[0] ^= 1;
+        assert!(!fragment.is_valid());
+
+        let mut fragment = split(PeerId::random(), 1, &envelope(100)).remove(0);
+        fragment.index = 1;
+        assert!(!fragment.is_valid());
+
+        assert!(Fragment::decode(b"not a fragment").is_none());
+        assert!(Fragment::decode(MAGIC).unwrap().is_err());
+    }
+
+    #[test]
+    fn incomplete_envelopes_time_out() {
+        let sender = PeerId::random();
+        let fragments = split(sender, 3, &envelope(FRAGMENT_SIZE + 1));
+
+        let mut reassembler = Reassembler::new(CAPACITY, Duration::from_millis(10));
+        assert_eq!(
+            reassembler.insert(fragments[0].clone()),
+            Reassembly::Pending
+        );
+        assert!(reassembler.expire().is_empty());
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(reassembler.expire(), vec![(sender, 3)]);
+        // The late fragment starts over instead of completing the envelope.
+        assert_eq!(
+            reassembler.insert(fragments[1].clone()),
+            Reassembly::Pending
+        );
+    }
+}
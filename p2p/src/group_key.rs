@@ -0,0 +1,138 @@
+//! What a DKG leaves public, saved by every member next to the keystore of
+//! its share: the group public key, the threshold and each member's public
+//! share, which partial signatures are checked against. A handoff to the
+//! next committee saves a new one for the next epoch, with the same group
+//! public key.
+//!
+//! ```toml
+//! session = "lab-1"
+//! epoch = 0
+//! threshold = 1
+//! public_key = "a5f0..."
+//!
+//! [[members]]
+//! index = 1
+//! peer_id = "12D3KooW..."
+//! public_share = "8e21..."
+//! ```
+//!
+//! Points are the hex of their compressed encoding.
+use bls12_381::G1Affine;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct GroupKey {
+    pub session: String,
+    /// How many handoffs the key went through since the DKG, 0 for the
+    /// committee of the DKG itself.
+    pub epoch: u64,
+    /// Any `threshold + 1` members can sign for the group.
+    pub threshold: usize,
+    pub public_key: G1Affine,
+    /// Sorted by index.
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub index: u64,
+    pub peer_id: PeerId,
+    /// The public key of the member's share.
+    pub public_share: G1Affine,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    session: String,
+    /// Missing from the files saved before committees rotated.
+    #[serde(default)]
+    epoch: u64,
+    threshold: usize,
+    public_key: String,
+    members: Vec<MemberEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MemberEntry {
+    index: u64,
+    peer_id: String,
+    public_share: String,
+}
+
+/// Where the group file of the share kept in `keystore` is.
+pub fn path_for(keystore: &Path) -> PathBuf {
+    keystore.with_extension("toml")
+}
+
+impl GroupKey {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file: File = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut members = Vec::with_capacity(file.members.len());
+        for entry in file.members {
+            let peer_id = entry
+                .peer_id
+                .parse()
+                .map_err(|_| format!("member {}: invalid peer id", entry.index))?;
+            let public_share = parse_point(&entry.public_share)
+                .ok_or_else(|| format!("member {}: invalid public share", entry.index))?;
+            members.push(Member {
+                index: entry.index,
+                peer_id,
+                public_share,
+            });
+        }
+        members.sort_by_key(|member| member.index);
+
+        Ok(GroupKey {
+            session: file.session,
+            epoch: file.epoch,
+            threshold: file.threshold,
+            public_key: parse_point(&file.public_key).ok_or("invalid public key")?,
+            members,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File {
+            session: self.session.clone(),
+            epoch: self.epoch,
+            threshold: self.threshold,
+            public_key: hex::encode(self.public_key.to_compressed()),
+            members: self
+                .members
+                .iter()
+                .map(|member| MemberEntry {
+                    index: member.index,
+                    peer_id: member.peer_id.to_string(),
+                    public_share: hex::encode(member.public_share.to_compressed()),
+                })
+                .collect(),
+        };
+        let contents = toml::to_string(&file).expect("Group files are always serializable");
+        fs::write(path, contents)
+    }
+
+    pub fn member(&self, index: u64) -> Option<&Member> {
+        self.members.iter().find(|member| member.index == index)
+    }
+
+    pub fn index_of(&self, peer_id: &PeerId) -> Option<u64> {
+        let member = self
+            .members
+            .iter()
+            .find(|member| member.peer_id == *peer_id)?;
+        Some(member.index)
+    }
+}
+
+fn parse_point(hex: &str) -> Option<G1Affine> {
+    let bytes: [u8; 48] = hex::decode(hex).ok()?.try_into().ok()?;
+    Option::from(G1Affine::from_compressed(&bytes))
+}
@@ -0,0 +1,936 @@
+//! The handoff of a group key to the committee of the next epoch, with the
+//! resharing of `bls_shamir::reshare`, over a topic of the group.
+//!
+//! Members of the next committee are numbered from 1 in the order of their
+//! peer ids, like in the DKG, and may be members of the current one too.
+//! Every current member deals its share: it publishes the commitments to a
+//! polynomial of the next threshold whose constant term is its share, with
+//! the sub-share of every next member sealed to that member's peer id.
+//! Anyone checks the commitments against the dealer's public share in the
+//! group file, so a dealing that doesn't share the dealer's share is ignored
+//! by everyone alike, and a dealer publishing two different dealings is left
+//! out. A sub-share that doesn't match the commitments is complained about
+//! by its recipient, and as in the DKG the dealer answers by revealing it
+//! for everyone to check: a valid one settles the complaint, a bad or
+//! missing answer leaves the dealer out.
+//!
+//! Once every current member dealt and no complaint came in for
+//! [`COMPLAINT_WINDOW`], or the timeout passed, members settle on the
+//! `threshold + 1` lowest dealers left and publish their digest. The shares
+//! are only combined, and the current ones retired, once a quorum of the
+//! next committee settled on the same dealers, so that the new shares
+//! interpolate together. The group public key stays the same.
+use crate::envelope::Payload;
+use crate::epoch::EpochManager;
+use crate::group_key::{GroupKey, Member};
+use crate::whisper;
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use bls_shamir::dkg_config::{ConfigError, DkgConfig};
+use bls_shamir::reshare;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::threshold::InterpolationError;
+use group::Curve;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::identity::ed25519;
+use libp2p::PeerId;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use zeroize::{Zeroize, Zeroizing};
+
+/// How long a node that got every dealing waits for complaints before it
+/// settles on the dealers it combines.
+pub const COMPLAINT_WINDOW: Duration = Duration::from_secs(10);
+
+/// The topic the handoffs of the group of `session` are published on, with
+/// the epoch in every message.
+pub fn topic(session: &str) -> Topic {
+    Topic::new(format!("zk-lab/handoff/{}", session))
+}
+
+/// What is sealed to each member of the next committee. Naming the session,
+/// epoch and dealer keeps sub-shares from being replayed into another
+/// handoff or dealing.
+#[derive(Debug, Serialize, Deserialize)]
+struct SubshareMessage {
+    session: String,
+    epoch: u64,
+    dealer: u64,
+    share: [u8; 32],
+}
+
+/// The outcome of the handoff for this node.
+#[derive(Debug)]
+pub struct Output {
+    /// The group of the next epoch.
+    pub group: GroupKey,
+    /// Our share of the next epoch, `None` if we aren't in its committee.
+    pub share: Option<SecretKey>,
+    /// The current members whose sub-shares were combined.
+    pub dealers: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub struct Handoff {
+    /// The group of the current epoch.
+    group: GroupKey,
+    /// Of the next committee.
+    config: DkgConfig,
+    /// Sorted, member `i` of the next committee holds share `i + 1`.
+    next: Vec<PeerId>,
+    /// Our index in the current committee and our share, to deal.
+    dealer: Option<(u64, SecretKey)>,
+    /// Our index in the next committee.
+    index: Option<u64>,
+    /// Our dealing, republished until the handoff is over.
+    dealing: Option<Payload>,
+    /// The sub-share we dealt each next member, revealed if it complains.
+    dealt: BTreeMap<u64, SecretKey>,
+    /// Each dealer's commitments, checked against its public share.
+    commitments: BTreeMap<u64, Vec<G1Projective>>,
+    /// Our sub-share from each dealer, checked against its commitments.
+    subshares: BTreeMap<u64, SecretKey>,
+    disqualified: BTreeSet<u64>,
+    /// The next members whose complaint about each dealer is unanswered.
+    complaints: BTreeMap<u64, BTreeSet<u64>>,
+    /// The dealers and accusers whose complaint was answered.
+    justified: BTreeSet<(u64, u64)>,
+    /// Our complaints and answers, republished until the end.
+    disputes: Vec<Payload>,
+    /// The dealers we settled on and when.
+    settled: Option<(Vec<u64>, Instant)>,
+    /// The digest of the dealers each next member settled on.
+    settlements: BTreeMap<u64, [u8; 32]>,
+    deadline: Instant,
+    complete_since: Option<Instant>,
+}
+
+impl Handoff {
+    /// Starts handing the group of `manager` over to `next`, whose shares
+    /// are of degree `threshold`, giving up on the dealers that haven't
+    /// dealt after `timeout`.
+    pub fn new(
+        manager: &EpochManager,
+        mut next: Vec<PeerId>,
+        threshold: usize,
+        local: &PeerId,
+        timeout: Duration,
+    ) -> Result<Self, HandoffError> {
+        next.sort();
+        next.dedup();
+        let config =
+            DkgConfig::with_threshold(next.len(), threshold).map_err(HandoffError::Config)?;
+        let dealer = manager.index().zip(manager.share().cloned());
+        let index = next
+            .iter()
+            .position(|member| member == local)
+            .map(|i| i as u64 + 1);
+        if dealer.is_none() && index.is_none() {
+            return Err(HandoffError::NotAMember);
+        }
+        if let Some(member) = next
+            .iter()
+            .find(|member| whisper::encryption_key(member).is_none())
+        {
+            return Err(HandoffError::NoEncryptionKey(*member));
+        }
+
+        Ok(Handoff {
+            group: manager.group().clone(),
+            config,
+            next,
+            dealer,
+            index,
+            dealing: None,
+            dealt: BTreeMap::new(),
+            commitments: BTreeMap::new(),
+            subshares: BTreeMap::new(),
+            disqualified: BTreeSet::new(),
+            complaints: BTreeMap::new(),
+            justified: BTreeSet::new(),
+            disputes: Vec::new(),
+            settled: None,
+            settlements: BTreeMap::new(),
+            deadline: Instant::now() + timeout,
+            complete_since: None,
+        })
+    }
+
+    /// The epoch handed over to.
+    pub fn epoch(&self) -> u64 {
+        self.group.epoch + 1
+    }
+
+    pub fn members(&self) -> usize {
+        self.next.len()
+    }
+
+    /// Deals our share to the next committee, keeping our own sub-share if
+    /// we are in it. Does nothing if we dealt already or have no share.
+    pub fn deal<R: RngCore + CryptoRng>(&mut self, rng: &mut R) {
+        let (dealer, share) = match &self.dealer {
+            Some((dealer, share)) if !self.commitments.contains_key(dealer) => (*dealer, share),
+            _ => return,
+        };
+
+        let polynomial = reshare::deal(share, self.config.threshold(), rng);
+        let mut sealed = Vec::with_capacity(self.next.len());
+        for (member, x) in self.next.iter().zip(1..) {
+            let subshare = polynomial.evaluate(x);
+            let mut message = SubshareMessage {
+                session: self.group.session.clone(),
+                epoch: self.epoch(),
+                dealer,
+                share: subshare.as_scalar().to_bytes(),
+            };
+            let plaintext = Zeroizing::new(
+                bincode::serialize(&message).expect("Sub-shares are always serializable"),
+            );
+            message.share.zeroize();
+            sealed.push(
+                whisper::seal(member, &plaintext, rng)
+                    .expect("Members were checked to have an encryption key"),
+            );
+            if Some(x) == self.index {
+                self.subshares.insert(dealer, subshare.clone());
+            }
+            self.dealt.insert(x, subshare);
+        }
+
+        let commitments = polynomial.commitments();
+        self.dealing = Some(Payload::HandoffDealing {
+            epoch: self.epoch(),
+            dealer,
+            commitments: commitments
+                .iter()
+                .map(|point| point.to_affine().to_compressed().to_vec())
+                .collect(),
+            shares: sealed,
+        });
+        self.commitments.insert(dealer, commitments);
+        info!(
+            "Dealt our share to the {} members of epoch {}",
+            self.next.len(),
+            self.epoch()
+        );
+    }
+
+    /// Our dealing, once we dealt.
+    pub fn dealing(&self) -> Option<Payload> {
+        self.dealing.clone()
+    }
+
+    /// Our complaints, our answers to the complaints about us and the
+    /// digest of the dealers we settled on.
+    pub fn disputes(&self) -> Vec<Payload> {
+        let settlement = self
+            .settled
+            .as_ref()
+            .map(|(dealers, _)| Payload::HandoffQualified {
+                epoch: self.epoch(),
+                digest: dealers_digest(dealers),
+            });
+        self.disputes.iter().cloned().chain(settlement).collect()
+    }
+
+    /// Handles a message published on the group's topic, returning what we
+    /// have to publish in answer.
+    pub fn handle(
+        &mut self,
+        sender: &PeerId,
+        payload: &Payload,
+        whisper_key: &ed25519::Keypair,
+    ) -> Vec<Payload> {
+        match payload {
+            Payload::HandoffDealing { epoch, .. }
+            | Payload::HandoffComplaint { epoch, .. }
+            | Payload::HandoffJustification { epoch, .. }
+            | Payload::HandoffQualified { epoch, .. }
+                if *epoch != self.epoch() =>
+            {
+                warn!(
+                    "Ignoring a handoff message for epoch {}, we are handing over to {}",
+                    epoch,
+                    self.epoch()
+                );
+                Vec::new()
+            }
+            Payload::HandoffDealing {
+                dealer,
+                commitments,
+                shares,
+                ..
+            } if self.group.index_of(sender) == Some(*dealer) => self
+                .handle_dealing(*dealer, commitments, shares, whisper_key)
+                .into_iter()
+                .collect(),
+            Payload::HandoffComplaint {
+                accuser, accused, ..
+            } if self.index_of(sender) == Some(*accuser) => {
+                self.handle_complaint(*accuser, *accused)
+            }
+            Payload::HandoffJustification {
+                dealer,
+                accuser,
+                share,
+                ..
+            } if self.group.index_of(sender) == Some(*dealer) => {
+                self.handle_justification(*dealer, *accuser, share);
+                Vec::new()
+            }
+            Payload::HandoffQualified { digest, .. } => {
+                if let Some(member) = self.index_of(sender) {
+                    if *self.settlements.entry(member).or_insert(*digest) != *digest {
+                        warn!("Member {} settled on two sets of dealers", member);
+                    }
+                }
+                Vec::new()
+            }
+            Payload::HandoffDealing { .. }
+            | Payload::HandoffComplaint { .. }
+            | Payload::HandoffJustification { .. } => {
+                warn!(
+                    "Ignoring a handoff message from {:?} sent in another's name",
+                    sender
+                );
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn index_of(&self, peer_id: &PeerId) -> Option<u64> {
+        let i = self.next.iter().position(|member| member == peer_id)?;
+        Some(i as u64 + 1)
+    }
+
+    fn handle_dealing(
+        &mut self,
+        dealer: u64,
+        compressed: &[Vec<u8>],
+        sealed: &[Vec<u8>],
+        whisper_key: &ed25519::Keypair,
+    ) -> Option<Payload> {
+        let public_share = &self.group.member(dealer)?.public_share;
+        let commitments = match decompress(compressed) {
+            Some(commitments)
+                if sealed.len() == self.next.len()
+                    && reshare::verify_dealing(
+                        public_share,
+                        &commitments,
+                        self.config.threshold(),
+                    ) =>
+            {
+                commitments
+            }
+            _ => {
+                warn!("Ignoring an invalid dealing of member {}", dealer);
+                return None;
+            }
+        };
+        match self.commitments.get(&dealer) {
+            Some(known) if *known == commitments => return None,
+            Some(_) => {
+                if self.disqualified.insert(dealer) {
+                    warn!("Leaving out member {}, it dealt twice", dealer);
+                    self.complete_since = None;
+                }
+                return None;
+            }
+            None => {}
+        }
+
+        info!("Got the dealing of member {}", dealer);
+        self.commitments.insert(dealer, commitments);
+        let index = self.index?;
+        match self.open(dealer, &sealed[index as usize - 1], whisper_key) {
+            Ok(subshare) => {
+                info!("Got a valid sub-share from member {}", dealer);
+                self.subshares.insert(dealer, subshare);
+                None
+            }
+            Err(reason) => self.complain(dealer, reason.into()),
+        }
+    }
+
+    /// Opens and checks our sub-share of `dealer`'s dealing.
+    fn open(
+        &self,
+        dealer: u64,
+        sealed: &[u8],
+        whisper_key: &ed25519::Keypair,
+    ) -> Result<SecretKey, &'static str> {
+        let plaintext = whisper::open(whisper_key, sealed)
+            .map(Zeroizing::new)
+            .ok_or("sub-share we can't open")?;
+        let message: SubshareMessage =
+            bincode::deserialize(&plaintext).map_err(|_| "malformed sub-share")?;
+        let share = Zeroizing::new(message.share);
+        if message.session != self.group.session
+            || message.epoch != self.epoch()
+            || message.dealer != dealer
+        {
+            return Err("sub-share of another handoff");
+        }
+        let subshare = Option::<Scalar>::from(Scalar::from_bytes(&share))
+            .map(SecretKey::new)
+            .ok_or("sub-share isn't a scalar")?;
+        let index = self.index.expect("Only members of the next committee open");
+        if !reshare::verify_subshare(&self.commitments[&dealer], index, &subshare) {
+            return Err("sub-share doesn't match the commitments");
+        }
+        Ok(subshare)
+    }
+
+    /// Complains about the sub-share `accused` dealt us, which only the
+    /// dealer can answer by revealing it.
+    fn complain(&mut self, accused: u64, reason: String) -> Option<Payload> {
+        let index = self.index?;
+        if self.justified.contains(&(accused, index))
+            || !self.complaints.entry(accused).or_default().insert(index)
+        {
+            return None;
+        }
+        warn!("Complaining about member {}: {}", accused, reason);
+        self.complete_since = None;
+        let complaint = Payload::HandoffComplaint {
+            epoch: self.epoch(),
+            accuser: index,
+            accused,
+            reason,
+        };
+        self.disputes.push(complaint.clone());
+        Some(complaint)
+    }
+
+    /// Reveals the sub-share we dealt `accuser` if we're the accused, or
+    /// waits for the accused to.
+    fn handle_complaint(&mut self, accuser: u64, accused: u64) -> Vec<Payload> {
+        if self.dealer.as_ref().map(|(dealer, _)| *dealer) != Some(accused) {
+            if !self.justified.contains(&(accused, accuser))
+                && self.complaints.entry(accused).or_default().insert(accuser)
+            {
+                info!("Member {} complained about dealer {}", accuser, accused);
+                self.complete_since = None;
+            }
+            return Vec::new();
+        }
+
+        let share = match self.dealt.get(&accuser) {
+            Some(share) => share.as_scalar().to_bytes(),
+            None => return Vec::new(),
+        };
+        let justification = Payload::HandoffJustification {
+            epoch: self.epoch(),
+            dealer: accused,
+            accuser,
+            share,
+        };
+        if self.disputes.contains(&justification) {
+            return Vec::new();
+        }
+        info!(
+            "Revealing the sub-share of member {} it complained about",
+            accuser
+        );
+        self.disputes.push(justification.clone());
+        vec![justification]
+    }
+
+    /// Checks the sub-share `dealer` revealed against its commitments. A
+    /// valid one settles the complaint and is ours to use if we complained,
+    /// a bad one leaves the dealer out.
+    fn handle_justification(&mut self, dealer: u64, accuser: u64, share: &[u8; 32]) {
+        if self.justified.contains(&(dealer, accuser)) || self.disqualified.contains(&dealer) {
+            return;
+        }
+        // Republished until the end, we check it once we have the dealing.
+        let commitments = match self.commitments.get(&dealer) {
+            Some(commitments) => commitments,
+            None => return,
+        };
+        let subshare = Option::<Scalar>::from(Scalar::from_bytes(share))
+            .map(SecretKey::new)
+            .filter(|subshare| reshare::verify_subshare(commitments, accuser, subshare));
+        let subshare = match subshare {
+            Some(subshare) => subshare,
+            None => {
+                warn!(
+                    "Leaving out member {}, it revealed a bad sub-share of member {}",
+                    dealer, accuser
+                );
+                self.disqualified.insert(dealer);
+                self.complete_since = None;
+                return;
+            }
+        };
+
+        info!(
+            "Dealer {} answered the complaint of member {}",
+            dealer, accuser
+        );
+        self.justified.insert((dealer, accuser));
+        if let Some(accusers) = self.complaints.get_mut(&dealer) {
+            accusers.remove(&accuser);
+        }
+        if Some(accuser) == self.index {
+            self.subshares.insert(dealer, subshare);
+        }
+    }
+
+    /// Whether a complaint about `dealer` is still waiting for its answer.
+    fn is_disputed(&self, dealer: u64) -> bool {
+        self.complaints
+            .get(&dealer)
+            .is_some_and(|accusers| !accusers.is_empty())
+    }
+
+    /// The lowest `threshold + 1` dealers with no complaint left unanswered.
+    fn dealers(&self) -> Vec<u64> {
+        self.commitments
+            .keys()
+            .copied()
+            .filter(|dealer| !self.disqualified.contains(dealer) && !self.is_disputed(*dealer))
+            .take(self.group.threshold + 1)
+            .collect()
+    }
+
+    /// Settles on the dealers to combine, to be confirmed by the next
+    /// committee before the sub-shares are.
+    fn settle(&mut self, now: Instant) {
+        let dealers = self.dealers();
+        info!("Settling on the dealers {:?}", dealers);
+        if let Some(index) = self.index {
+            self.settlements.insert(index, dealers_digest(&dealers));
+        }
+        self.settled = Some((dealers, now));
+    }
+
+    /// How many next members settled on the same dealers as us.
+    fn confirmations(&self) -> usize {
+        let ours = match &self.settled {
+            Some((dealers, _)) => dealers_digest(dealers),
+            None => return 0,
+        };
+        self.settlements
+            .values()
+            .filter(|digest| **digest == ours)
+            .count()
+    }
+
+    /// Whether the handoff is over for us: every current member dealt or
+    /// was left out and no complaint came in for a while, or we ran out of
+    /// time. We then settle on the dealers, and are done once a quorum of
+    /// the next committee settled on the same ones or another complaint
+    /// window went by.
+    pub fn is_ready(&mut self) -> bool {
+        let now = Instant::now();
+        if let Some((_, settled)) = self.settled {
+            return self.confirmations() >= self.config.quorum()
+                || now.duration_since(settled) >= COMPLAINT_WINDOW;
+        }
+        if now >= self.deadline {
+            self.settle(now);
+            return false;
+        }
+
+        let complete = self.group.members.iter().all(|member| {
+            self.disqualified.contains(&member.index)
+                || self.is_disputed(member.index)
+                || (self.commitments.contains_key(&member.index)
+                    && (self.index.is_none() || self.subshares.contains_key(&member.index)))
+        });
+        if !complete {
+            self.complete_since = None;
+            return false;
+        }
+        let since = *self.complete_since.get_or_insert(now);
+        if now.duration_since(since) >= COMPLAINT_WINDOW {
+            self.settle(now);
+        }
+        false
+    }
+
+    /// Combines the sub-shares and commitments of the dealers settled on
+    /// into the group of the next epoch and our share of it. Fails unless a
+    /// quorum of the next committee settled on the same dealers, before
+    /// anything replaces the current shares.
+    pub fn finish(self) -> Result<Output, HandoffError> {
+        let (confirmed, quorum) = (self.confirmations(), self.config.quorum());
+        if confirmed < quorum {
+            return Err(HandoffError::Unconfirmed {
+                confirmed,
+                needed: quorum,
+            });
+        }
+        let needed = self.group.threshold + 1;
+        let (dealers, _) = self
+            .settled
+            .clone()
+            .expect("Confirmed dealers were settled on");
+        if dealers.len() < needed {
+            return Err(HandoffError::TooFewDealers {
+                dealt: dealers.len(),
+                needed,
+            });
+        }
+
+        let dealings = dealers
+            .iter()
+            .map(|dealer| (*dealer, self.commitments[dealer].clone()))
+            .collect::<Vec<_>>();
+        let coefficients = reshare::combine_commitments(&dealings)?;
+        // Can't differ with checked dealings, but it is cheap enough to be
+        // sure.
+        if coefficients[0].to_affine() != self.group.public_key {
+            return Err(HandoffError::KeyChanged);
+        }
+        let members = self
+            .next
+            .iter()
+            .zip(1..)
+            .map(|(peer_id, index)| Member {
+                index,
+                peer_id: *peer_id,
+                public_share: reshare::public_share(&coefficients, index),
+            })
+            .collect();
+        let group = GroupKey {
+            session: self.group.session.clone(),
+            epoch: self.epoch(),
+            threshold: self.config.threshold(),
+            public_key: self.group.public_key,
+            members,
+        };
+
+        let share = match self.index {
+            Some(_) => {
+                let subshares = dealers
+                    .iter()
+                    .map(|dealer| {
+                        let subshare = self.subshares.get(dealer).cloned();
+                        subshare.map(|subshare| (*dealer, subshare))
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(HandoffError::MissingShares)?;
+                Some(reshare::combine_shares(&subshares)?)
+            }
+            None => None,
+        };
+        Ok(Output {
+            group,
+            share,
+            dealers,
+        })
+    }
+}
+
+/// What members settling on `dealers` publish.
+fn dealers_digest(dealers: &[u64]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for dealer in dealers {
+        hasher.update(dealer.to_be_bytes());
+    }
+    hasher.finalize().into()
+}
+
+fn decompress(points: &[Vec<u8>]) -> Option<Vec<G1Projective>> {
+    points
+        .iter()
+        .map(|bytes| {
+            let bytes: [u8; 48] = bytes.as_slice().try_into().ok()?;
+            Option::<G1Affine>::from(G1Affine::from_compressed(&bytes)).map(G1Projective::from)
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum HandoffError {
+    /// This node is in neither the current committee nor the next.
+    NotAMember,
+    /// A member's peer id doesn't carry an ed25519 key to seal its sub-shares
+    /// to.
+    NoEncryptionKey(PeerId),
+    /// The next threshold doesn't fit the next committee.
+    Config(ConfigError),
+    /// Too few current members dealt to put the shares back together.
+    TooFewDealers { dealt: usize, needed: usize },
+    /// Too few members of the next committee settled on the same dealers as
+    /// us, the sub-shares may not add up.
+    Unconfirmed { confirmed: usize, needed: usize },
+    /// We have no valid sub-share from a dealer the others combine.
+    MissingShares,
+    /// The dealings add up to another group key.
+    KeyChanged,
+    /// The dealers don't interpolate, two have the same index.
+    Interpolation(InterpolationError),
+}
+
+impl fmt::Display for HandoffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandoffError::NotAMember => {
+                write!(
+                    f,
+                    "this node is in neither the current committee nor the next"
+                )
+            }
+            HandoffError::NoEncryptionKey(peer_id) => {
+                write!(
+                    f,
+                    "{} has no ed25519 key to seal its sub-shares to",
+                    peer_id
+                )
+            }
+            HandoffError::Config(e) => write!(f, "the next committee: {}", e),
+            HandoffError::TooFewDealers { dealt, needed } => write!(
+                f,
+                "only {} members dealt their share, {} are needed",
+                dealt, needed
+            ),
+            HandoffError::Unconfirmed { confirmed, needed } => write!(
+                f,
+                "only {} next members settled on the same dealers as us, {} are needed",
+                confirmed, needed
+            ),
+            HandoffError::MissingShares => {
+                write!(f, "no valid sub-share from every dealer combined")
+            }
+            HandoffError::KeyChanged => write!(f, "the dealings add up to another group key"),
+            HandoffError::Interpolation(e) => write!(f, "combining the dealings: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HandoffError {}
+
+impl From<InterpolationError> for HandoffError {
+    fn from(e: InterpolationError) -> Self {
+        HandoffError::Interpolation(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epoch::tests::{committee, peer_id};
+    use beacon::Schedule;
+    use bls_shamir::threshold::interpolate_at_zero;
+    use std::collections::VecDeque;
+    use std::time::SystemTime;
+
+    /// Decides what becomes of a payload node `i` publishes: it can be
+    /// changed, or dropped by returning false.
+    type Tamper = Box<dyn FnMut(usize, &mut Payload) -> bool>;
+
+    /// A committee of 4 handing over to its last 3 members and a newcomer,
+    /// node 4, every message published reaching every node.
+    struct Rotation {
+        keypairs: Vec<ed25519::Keypair>,
+        handoffs: Vec<Handoff>,
+        /// Published payloads and who published them.
+        queue: VecDeque<(usize, Payload)>,
+        tamper: Tamper,
+    }
+
+    impl Rotation {
+        fn new() -> Self {
+            let (group, mut keypairs, shares) = committee(4, 1);
+            keypairs.push(ed25519::Keypair::generate());
+            let next = keypairs[1..].iter().map(peer_id).collect::<Vec<_>>();
+            let handoffs = keypairs
+                .iter()
+                .enumerate()
+                .map(|(i, keypair)| {
+                    let local = peer_id(keypair);
+                    let schedule = Schedule::new(SystemTime::UNIX_EPOCH, Duration::from_secs(60));
+                    let manager =
+                        EpochManager::new(group.clone(), shares.get(i).cloned(), &local, schedule)
+                            .unwrap();
+                    let mut handoff =
+                        Handoff::new(&manager, next.clone(), 1, &local, Duration::from_secs(60))
+                            .unwrap();
+                    handoff.deal(&mut rand::thread_rng());
+                    handoff
+                })
+                .collect();
+            Rotation {
+                keypairs,
+                handoffs,
+                queue: VecDeque::new(),
+                tamper: Box::new(|_, _| true),
+            }
+        }
+
+        fn tamper(mut self, tamper: impl FnMut(usize, &mut Payload) -> bool + 'static) -> Self {
+            self.tamper = Box::new(tamper);
+            self
+        }
+
+        /// Has the dealing of `dealer` seal a sub-share that doesn't match
+        /// its commitments to `node`.
+        fn corrupt(&mut self, dealer: usize, node: usize) {
+            let index = self.handoffs[node].index.unwrap();
+            let message = SubshareMessage {
+                session: "test".into(),
+                epoch: 1,
+                dealer: dealer as u64 + 1,
+                share: Scalar::one().to_bytes(),
+            };
+            let plaintext = bincode::serialize(&message).unwrap();
+            let sealed = whisper::seal(
+                &peer_id(&self.keypairs[node]),
+                &plaintext,
+                &mut rand::thread_rng(),
+            )
+            .unwrap();
+            if let Some(Payload::HandoffDealing { shares, .. }) = &mut self.handoffs[dealer].dealing
+            {
+                shares[index as usize - 1] = sealed;
+            }
+        }
+
+        fn publish(&mut self, from: usize, mut payload: Payload) {
+            if (self.tamper)(from, &mut payload) {
+                self.queue.push_back((from, payload));
+            }
+        }
+
+        fn republish(&mut self) {
+            for i in 0..self.handoffs.len() {
+                let handoff = &self.handoffs[i];
+                let payloads = handoff
+                    .dealing()
+                    .into_iter()
+                    .chain(handoff.disputes())
+                    .collect::<Vec<_>>();
+                for payload in payloads {
+                    self.publish(i, payload);
+                }
+            }
+        }
+
+        fn deliver(&mut self) {
+            while let Some((from, payload)) = self.queue.pop_front() {
+                let sender = peer_id(&self.keypairs[from]);
+                for to in (0..self.handoffs.len()).filter(|to| *to != from) {
+                    let replies = self.handoffs[to].handle(&sender, &payload, &self.keypairs[to]);
+                    for reply in replies {
+                        self.publish(to, reply);
+                    }
+                }
+            }
+        }
+
+        fn run(&mut self) {
+            for _ in 0..3 {
+                self.republish();
+                self.deliver();
+            }
+        }
+
+        /// Runs the handoff, then settles every node on the dealers it has,
+        /// as once the complaint window is over, and exchanges the digests.
+        fn finish(mut self) -> Vec<Result<Output, HandoffError>> {
+            self.run();
+            for handoff in &mut self.handoffs {
+                handoff.settle(Instant::now());
+            }
+            self.republish();
+            self.deliver();
+            self.handoffs.into_iter().map(Handoff::finish).collect()
+        }
+    }
+
+    /// Checks that the nodes ended up with the same group and shares of the
+    /// next committee that interpolate to the group key, returning the
+    /// dealers combined.
+    fn check_shares(outputs: Vec<Result<Output, HandoffError>>) -> Vec<u64> {
+        let outputs = outputs.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        let group = &outputs[0].group;
+        assert_eq!(group.epoch, 1);
+        let mut points = Vec::new();
+        for output in &outputs {
+            assert_eq!(output.dealers, outputs[0].dealers);
+            for (theirs, ours) in output.group.members.iter().zip(&group.members) {
+                assert_eq!(theirs.public_share, ours.public_share);
+            }
+            if let Some(share) = &output.share {
+                let index = output
+                    .group
+                    .members
+                    .iter()
+                    .find(|member| member.public_share == share.public_key())
+                    .unwrap()
+                    .index;
+                points.push((index, *share.as_scalar()));
+            }
+        }
+        assert_eq!(points.len(), 4);
+        let secret = SecretKey::new(interpolate_at_zero(&points[..2]).unwrap());
+        assert_eq!(secret.public_key(), group.public_key);
+        outputs[0].dealers.clone()
+    }
+
+    #[test]
+    fn shares_are_handed_over() {
+        let rotation = Rotation::new();
+        // The node leaving deals but gets no share.
+        assert_eq!(rotation.handoffs[0].index, None);
+        assert_eq!(check_shares(rotation.finish()), vec![1, 2]);
+    }
+
+    #[test]
+    fn a_false_complaint_is_answered() {
+        let mut rotation = Rotation::new();
+        let complaint = Payload::HandoffComplaint {
+            epoch: 1,
+            accuser: rotation.handoffs[4].index.unwrap(),
+            accused: 1,
+            reason: "lies".into(),
+        };
+        rotation.publish(4, complaint);
+        assert_eq!(check_shares(rotation.finish()), vec![1, 2]);
+    }
+
+    #[test]
+    fn a_revealed_sub_share_replaces_a_bad_one() {
+        let mut rotation = Rotation::new();
+        rotation.corrupt(0, 2);
+        rotation.run();
+        // Everyone but the dealer checked the revealed sub-share.
+        let accuser = rotation.handoffs[2].index.unwrap();
+        assert!(rotation.handoffs[1..]
+            .iter()
+            .all(|handoff| handoff.justified.contains(&(1, accuser))));
+        assert_eq!(check_shares(rotation.finish()), vec![1, 2]);
+    }
+
+    #[test]
+    fn an_unanswered_complaint_leaves_the_dealer_out() {
+        let mut rotation = Rotation::new().tamper(
+            |node, payload| !matches!(payload, Payload::HandoffJustification { .. } if node == 0),
+        );
+        rotation.corrupt(0, 2);
+        let mut outputs = rotation.finish();
+        // The dealer itself never heard of its answer missing.
+        assert!(matches!(
+            outputs.remove(0),
+            Err(HandoffError::Unconfirmed { confirmed: 0, .. })
+        ));
+        assert_eq!(check_shares(outputs), vec![2, 3]);
+    }
+
+    #[test]
+    fn shares_wait_for_a_quorum_to_confirm() {
+        let rotation = Rotation::new()
+            .tamper(|_, payload| !matches!(payload, Payload::HandoffQualified { .. }));
+        for output in rotation.finish() {
+            assert!(matches!(output, Err(HandoffError::Unconfirmed { .. })));
+        }
+    }
+}
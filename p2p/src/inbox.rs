@@ -0,0 +1,390 @@
+//! Checks what peers publish before it reaches the protocols. Envelopes
+//! are put back together from their fragments, checked against the roster
+//! and dropped if they were seen already. The envelopes a peer sends us to
+//! catch up are checked the same way, except for their age.
+use crate::catch_up;
+use crate::envelope::{DecodeError, Envelope, Payload, SignedEnvelope};
+use crate::fragment::{Fragment, Reassembler, Reassembly};
+use crate::metrics::Metrics;
+use crate::roster::Roster;
+use crate::seen::{Seen, SeenCache};
+use crate::store::{Store, StoredMessage};
+use crate::strikes::Offence;
+use bls12_381::G1Affine;
+use libp2p::gossipsub::{Message as GossipsubMessage, MessageId, TopicHash};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+/// An envelope is identified by its sender and sequence number, so a resent
+/// envelope is a duplicate even if it was signed again, and a fragment also
+/// by its index. Anything else is identified by its content.
+pub fn message_id(data: &[u8]) -> MessageId {
+    let mut s = DefaultHasher::new();
+    match Fragment::decode(data) {
+        Some(Ok(fragment)) => (fragment.sender, fragment.seq, fragment.index).hash(&mut s),
+        Some(Err(_)) => data.hash(&mut s),
+        None => match SignedEnvelope::decode(data) {
+            Ok(signed) => (signed.envelope.sender, signed.envelope.seq).hash(&mut s),
+            Err(_) => data.hash(&mut s),
+        },
+    }
+    MessageId::from(s.finish().to_string())
+}
+
+/// The envelopes already handled and the fragments of those still coming.
+#[derive(Default)]
+pub struct Inbox {
+    seen: SeenCache,
+    reassembler: Reassembler,
+}
+
+impl Inbox {
+    /// Checks the envelope published in `message`, or the one it is a
+    /// fragment of, `Ok(None)` while other fragments are missing.
+    /// `Err(Some(_))` if the peer it came from misbehaved and `Err(None)` if
+    /// it should just not be passed on.
+    pub fn receive(
+        &mut self,
+        message: &GossipsubMessage,
+        id: &MessageId,
+        roster: Option<&Roster>,
+        metrics: &Metrics,
+    ) -> Result<Option<SignedEnvelope>, Option<Offence>> {
+        let data = match Fragment::decode(&message.data) {
+            None => {
+                return check_message(&message.data, message, id, roster, &mut self.seen, metrics)
+                    .map(Some)
+            }
+            Some(fragment) => match reassemble(fragment, message, id, &mut self.reassembler)? {
+                Some(data) => data,
+                None => return Ok(None),
+            },
+        };
+        check_message(&data, message, id, roster, &mut self.seen, metrics).map(Some)
+    }
+
+    /// Handles the envelopes a peer sent in answer to our catch-up request,
+    /// returning how many we hadn't seen. They are checked like gossipsub
+    /// messages, except for their age: they are old by design.
+    pub fn catch_up(
+        &mut self,
+        messages: Vec<StoredMessage>,
+        store: &Store,
+        roster: Option<&Roster>,
+        metrics: &Metrics,
+    ) -> Result<usize, Offence> {
+        let mut missed = 0;
+        for StoredMessage { topic, signed } in messages {
+            let id = message_id(&signed.encode());
+            match check_signature(&signed, &id, roster, metrics) {
+                Ok(()) => {}
+                Err(None) => continue,
+                Err(Some(offence)) => return Err(offence),
+            }
+
+            let topic = TopicHash::from_raw(topic);
+            let envelope = signed.envelope.clone();
+            if catch_up::keep(store, &topic, signed) {
+                // So the gossipsub copy, if it is still around, is a duplicate.
+                self.seen.check(&envelope);
+                log_envelope(&envelope, &topic, &id);
+                missed += 1;
+            }
+        }
+        Ok(missed)
+    }
+
+    /// Drops the envelopes whose fragments timed out.
+    pub fn expire(&mut self) {
+        for (sender, seq) in self.reassembler.expire() {
+            info!(
+                "Dropping envelope {} from {:?}, its fragments timed out",
+                seq, sender
+            );
+        }
+    }
+}
+
+/// Checks an envelope published in `message`, whole or reassembled from
+/// fragments, `Err(Some(_))` if the peer it came from misbehaved and
+/// `Err(None)` if it should just not be passed on.
+fn check_message(
+    data: &[u8],
+    message: &GossipsubMessage,
+    id: &MessageId,
+    roster: Option<&Roster>,
+    seen: &mut SeenCache,
+    metrics: &Metrics,
+) -> Result<SignedEnvelope, Option<Offence>> {
+    let signed = match SignedEnvelope::decode(data) {
+        Ok(signed) => signed,
+        // Not held against the peer, it may have been relayed from a node
+        // that never identified itself to us.
+        Err(e @ DecodeError::Version(_)) => {
+            warn!("Dropping message {}: {}", id, e);
+            return Err(None);
+        }
+        Err(e) => {
+            warn!("Dropping malformed message {}: {}", id, e);
+            return Err(Some(Offence::MalformedEnvelope));
+        }
+    };
+
+    let envelope = &signed.envelope;
+
+    // With signed messages the envelope must come from the peer who signed it.
+    if message
+        .source
+        .is_some_and(|source| source != envelope.sender)
+    {
+        warn!(
+            "Dropping message {} signed by {:?} on behalf of {:?}",
+            id, message.source, envelope.sender
+        );
+        return Err(Some(Offence::ForgedSender));
+    }
+
+    check_signature(&signed, id, roster, metrics)?;
+
+    // Checked last, so an invalid copy can't shadow the real envelope.
+    match seen.check(envelope) {
+        Seen::New => {}
+        Seen::Duplicate => {
+            debug!("Ignoring duplicate message {}", id);
+            return Err(None);
+        }
+        Seen::Stale => {
+            info!(
+                "Dropping stale message {} from {:?} (seq {})",
+                id, envelope.sender, envelope.seq
+            );
+            return Err(None);
+        }
+    }
+
+    log_envelope(envelope, &message.topic, id);
+    Ok(signed)
+}
+
+/// Adds a published fragment to the envelope it belongs to, returning the
+/// envelope once it is complete.
+fn reassemble(
+    data: Result<Fragment, bincode::Error>,
+    message: &GossipsubMessage,
+    id: &MessageId,
+    reassembler: &mut Reassembler,
+) -> Result<Option<Vec<u8>>, Option<Offence>> {
+    let fragment = match data {
+        Ok(fragment) if fragment.is_valid() => fragment,
+        Ok(_) => {
+            warn!("Dropping corrupt fragment {}", id);
+            return Err(Some(Offence::BadFragment));
+        }
+        Err(e) => {
+            warn!("Dropping malformed fragment {}: {}", id, e);
+            return Err(Some(Offence::BadFragment));
+        }
+    };
+
+    if message
+        .source
+        .is_some_and(|source| source != fragment.sender)
+    {
+        warn!(
+            "Dropping fragment {} signed by {:?} on behalf of {:?}",
+            id, message.source, fragment.sender
+        );
+        return Err(Some(Offence::ForgedSender));
+    }
+
+    let (index, count) = (fragment.index, fragment.count);
+    match reassembler.insert(fragment) {
+        Reassembly::Pending => {
+            debug!("Got fragment {} of {} in {}", index + 1, count, id);
+            Ok(None)
+        }
+        Reassembly::Complete(data) => Ok(Some(data)),
+        Reassembly::Inconsistent => {
+            warn!("Dropping fragment {} that disagrees with its siblings", id);
+            Err(Some(Offence::BadFragment))
+        }
+    }
+}
+
+/// Checks the envelope was signed by its sender, when there is a roster to
+/// check it against.
+fn check_signature(
+    signed: &SignedEnvelope,
+    id: &MessageId,
+    roster: Option<&Roster>,
+    metrics: &Metrics,
+) -> Result<(), Option<Offence>> {
+    let roster = match roster {
+        Some(roster) => roster,
+        None => return Ok(()),
+    };
+
+    let sender = &signed.envelope.sender;
+    match roster.public_key(sender) {
+        Some(pk) if verify(signed, pk, metrics) => Ok(()),
+        Some(_) => {
+            warn!(
+                "Dropping message {} with a bad signature from {:?}",
+                id, sender
+            );
+            Err(Some(Offence::BadSignature))
+        }
+        None => {
+            info!(
+                "Dropping message {} from {:?} outside the roster",
+                id, sender
+            );
+            Err(None)
+        }
+    }
+}
+
+/// Checks the envelope's signature, timing how long it takes.
+fn verify(signed: &SignedEnvelope, pk: &G1Affine, metrics: &Metrics) -> bool {
+    let start = Instant::now();
+    let valid = signed.verify(pk);
+    let elapsed = start.elapsed().as_secs_f64();
+    metrics.signature_verification.observe(elapsed);
+    valid
+}
+
+fn log_envelope(envelope: &Envelope, topic: &TopicHash, id: &MessageId) {
+    let sender = &envelope.sender;
+    match &envelope.payload {
+        Payload::Chat(text) => info!(
+            "Got message: {} on {} with id: {} from peer: {:?} (seq {})",
+            text, topic, id, sender, envelope.seq
+        ),
+        Payload::DkgDealing {
+            dealer,
+            commitments,
+            ..
+        } => info!(
+            "Got dealing of dealer {} with {} commitments from {:?}",
+            dealer,
+            commitments.len(),
+            sender
+        ),
+        Payload::DkgEcho { dealer, digest } => info!(
+            "Got echo of dealing {} of dealer {} from {:?}",
+            hex::encode(digest),
+            dealer,
+            sender
+        ),
+        Payload::PartialSignature { index, epoch, .. } => info!(
+            "Got partial signature {} of epoch {} from {:?}",
+            index, epoch, sender
+        ),
+        Payload::Complaint {
+            accuser,
+            accused,
+            reason,
+        } => info!(
+            "Got complaint of {} against {} from {:?}: {}",
+            accuser, accused, sender, reason
+        ),
+        Payload::DkgJustification {
+            dealer, accuser, ..
+        } => info!(
+            "Got the share dealer {} revealed for member {} from {:?}",
+            dealer, accuser, sender
+        ),
+        Payload::DkgQualified { digest } => info!(
+            "Got the digest {} of the dealers settled on from {:?}",
+            hex::encode(digest),
+            sender
+        ),
+        Payload::SignRequest {
+            message,
+            signers,
+            epoch,
+        } => info!(
+            "Got request to sign {} for members {:?} of epoch {} from {:?}",
+            String::from_utf8_lossy(message),
+            signers,
+            epoch,
+            sender
+        ),
+        Payload::GroupSignature { message, .. } => info!(
+            "Got group signature on {} from {:?}",
+            String::from_utf8_lossy(message),
+            sender
+        ),
+        Payload::BeaconPartial { round, index, .. } => info!(
+            "Got partial signature {} of beacon round {} from {:?}",
+            index, round, sender
+        ),
+        Payload::Beacon { round, .. } => info!("Got beacon round {} from {:?}", round, sender),
+        Payload::BeaconRequest { from, to } => info!(
+            "Got request for beacon rounds {} to {} from {:?}",
+            from, to, sender
+        ),
+        Payload::SrsUpdate { turn, srs, .. } => info!(
+            "Got SRS update of turn {} ({} bytes) from {:?}",
+            turn,
+            srs.len(),
+            sender
+        ),
+        Payload::PvssDealing {
+            dealer, encrypted, ..
+        } => info!(
+            "Got PVSS dealing of dealer {} with {} shares from {:?}",
+            dealer,
+            encrypted.len(),
+            sender
+        ),
+        Payload::PvssShare { dealer, index, .. } => info!(
+            "Got decryption of member {} for PVSS dealer {} from {:?}",
+            index, dealer, sender
+        ),
+        Payload::DkgBroadcast { origin, kind, .. } => info!(
+            "Got a message of the broadcast of the {:?} of member {} from {:?}",
+            kind, origin, sender
+        ),
+        Payload::DkgRecovery {
+            dealer, accuser, ..
+        } => info!(
+            "Got points of the share of member {} of dealer {} from {:?}",
+            accuser, dealer, sender
+        ),
+        Payload::DkgAgreement { proposer, message } => info!(
+            "Got {:?} of the agreement on the proposal of member {} from {:?}",
+            message, proposer, sender
+        ),
+        Payload::HandoffDealing { epoch, dealer, .. } => info!(
+            "Got the dealing of member {} to the committee of epoch {} from {:?}",
+            dealer, epoch, sender
+        ),
+        Payload::HandoffComplaint {
+            epoch,
+            accuser,
+            accused,
+            reason,
+        } => info!(
+            "Got complaint of {} of epoch {} against {} from {:?}: {}",
+            accuser, epoch, accused, sender, reason
+        ),
+        Payload::HandoffJustification {
+            epoch,
+            dealer,
+            accuser,
+            ..
+        } => info!(
+            "Got the sub-share member {} revealed for {} of epoch {} from {:?}",
+            dealer, accuser, epoch, sender
+        ),
+        Payload::HandoffQualified { epoch, digest } => info!(
+            "Got the digest {} of the dealers of epoch {} settled on from {:?}",
+            hex::encode(digest),
+            epoch,
+            sender
+        ),
+    }
+}
@@ -0,0 +1,63 @@
+//! Keeps the node's keys on disk so they survive restarts, which committee
+//! membership relies on.
+//!
+//! The libp2p identity file holds the 32 byte ed25519 secret key in hex
+//! followed by a newline. The BLS key used to sign envelopes is kept in an
+//! EIP-2335 keystore.
+use bls_shamir::keystore::{Kdf, Keystore, KeystoreError};
+use bls_shamir::secret::SecretKey;
+use libp2p::identity::{ed25519, Keypair};
+use rand::rngs::OsRng;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Loads the keypair stored at `path`, or generates one and stores it there
+/// if the file doesn't exist yet.
+pub fn load_or_create(path: &Path) -> io::Result<Keypair> {
+    match fs::read_to_string(path) {
+        Ok(contents) => load(&contents),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => create(path),
+        Err(e) => Err(e),
+    }
+}
+
+fn load(contents: &str) -> io::Result<Keypair> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let secret = hex::decode(contents.trim()).map_err(|_| invalid("identity file is not hex"))?;
+    let secret = ed25519::SecretKey::try_from_bytes(secret)
+        .map_err(|_| invalid("identity file doesn't hold an ed25519 secret key"))?;
+
+    Ok(ed25519::Keypair::from(secret).into())
+}
+
+fn create(path: &Path) -> io::Result<Keypair> {
+    let keypair = ed25519::Keypair::generate();
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", hex::encode(keypair.secret()))?;
+    file.sync_all()?;
+
+    Ok(keypair.into())
+}
+
+/// Decrypts the BLS key in the keystore at `path`, or generates a key and
+/// stores it there encrypted under `password` if the file doesn't exist yet.
+pub fn load_or_create_bls(path: &Path, password: &str) -> Result<SecretKey, KeystoreError> {
+    if path.exists() {
+        return Keystore::load(path)?.decrypt(password);
+    }
+
+    let secret = SecretKey::random(&mut OsRng);
+    Keystore::encrypt(&secret, password, "", Kdf::scrypt(&mut OsRng), &mut OsRng)?.save(path)?;
+    Ok(secret)
+}
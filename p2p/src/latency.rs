@@ -0,0 +1,53 @@
+//! Round trip times to our peers, measured by ping. A signing ceremony only
+//! needs t signatures, so it can ask the fastest peers first.
+use libp2p::PeerId;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// How many of the latest pings the average is taken over.
+pub const WINDOW: usize = 8;
+
+#[derive(Debug, Default)]
+pub struct LatencyTable {
+    peers: HashMap<PeerId, VecDeque<Duration>>,
+}
+
+impl LatencyTable {
+    pub fn record(&mut self, peer_id: PeerId, rtt: Duration) {
+        let samples = self.peers.entry(peer_id).or_default();
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(rtt);
+    }
+
+    /// Forgets a peer that stopped answering, so it isn't picked for its
+    /// old round trip time.
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    /// The average over the last [`WINDOW`] pings.
+    pub fn average(&self, peer_id: &PeerId) -> Option<Duration> {
+        let samples = self.peers.get(peer_id)?;
+        let total: Duration = samples.iter().sum();
+        Some(total / samples.len() as u32)
+    }
+
+    /// Returns up to `n` peers with the lowest average round trip time,
+    /// fastest first.
+    pub fn fastest(&self, n: usize) -> Vec<(PeerId, Duration)> {
+        let mut peers: Vec<_> = self
+            .peers
+            .keys()
+            .filter_map(|peer_id| Some((*peer_id, self.average(peer_id)?)))
+            .collect();
+        peers.sort_by_key(|(_, rtt)| *rtt);
+        peers.truncate(n);
+        peers
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+}
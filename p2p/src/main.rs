@@ -1,115 +1,1442 @@
-use async_std::io;
+mod address_book;
+mod beacon_api;
+mod behaviour;
+mod catch_up;
+mod cli;
+mod coin;
+mod config;
+mod dkg;
+mod envelope;
+mod epoch;
+mod fragment;
+mod group_key;
+mod handoff;
+mod inbox;
+mod keyfile;
+mod latency;
+mod metrics;
+mod outbox;
+mod peers;
+mod psi;
+mod pvss;
+mod randomness;
+mod rate_limit;
+mod redial;
+mod roster;
+mod runtime;
+mod seen;
+mod setup;
+mod share;
+mod signing;
+mod store;
+mod strikes;
+mod transfer;
+mod transport;
+mod whisper;
+
+use behaviour::{Behaviour, Event, KADEMLIA_PROTOCOL};
+use bls_shamir::secret::SecretKey;
+use catch_up::{CatchUpCodec, CatchUpProtocol, CatchUpResponse};
+use clap::Parser;
+use cli::{Cli, Command};
+use dkg::DkgTask;
+use envelope::Payload;
+use epoch::RotationTask;
+use futures::channel::mpsc;
 use futures::{prelude::*, select};
-use libp2p::gossipsub::MessageId;
+use inbox::Inbox;
+use latency::LatencyTable;
+use libp2p::core::ConnectedPoint;
+use libp2p::gossipsub::Behaviour as Gossipsub;
 use libp2p::gossipsub::{
-    GossipsubEvent, GossipsubMessage, IdentTopic as Topic, MessageAuthenticity, ValidationMode,
+    Event as GossipsubEvent, IdentTopic as Topic, Message as GossipsubMessage, MessageAcceptance,
+    MessageAuthenticity, PeerScoreParams, PeerScoreThresholds, TopicScoreParams, ValidationMode,
+};
+use libp2p::identify::{self, Event as IdentifyEvent};
+use libp2p::kad::store::MemoryStore;
+use libp2p::kad::{
+    Behaviour as Kademlia, Config as KademliaConfig, Event as KademliaEvent, QueryResult,
+};
+#[cfg(feature = "mdns")]
+use libp2p::mdns::{self, Event as MdnsEvent};
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{
+    self, Event as RequestResponseEvent, Message as RequestResponseMessage,
+    OutboundRequestId as RequestId, ProtocolSupport,
 };
-use libp2p::{gossipsub, identity, swarm::SwarmEvent, Multiaddr, PeerId};
-use std::collections::hash_map::DefaultHasher;
+use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::{dcutr, ping, relay};
+use libp2p::{gossipsub, identity, swarm::SwarmEvent, PeerId};
+use metrics::Metrics;
+use outbox::Outbox;
+use peers::PeerTable;
+use psi::{PsiCodec, PsiProtocol, PsiQuery, PsiResponse, PsiServer};
+use pvss::PvssTask;
+use randomness::BeaconTask;
+use rate_limit::{Limit, RateLimiter};
+use redial::{Redialer, Retry};
+use roster::Roster;
+use setup::SetupTask;
+use share::{Ack, ShareCodec, ShareProtocol, ShareRequest};
+use signal_hook::consts::TERM_SIGNALS;
+use signal_hook::iterator::Signals;
+use signing::SigningTask;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::hash::{Hash, Hasher};
+use std::fmt;
+use std::io;
+use std::iter;
+use std::process;
+use std::thread;
 use std::time::Duration;
+use store::Store;
+use strikes::{Offence, Strikes};
+use tracing::{debug, info, info_span, warn, Instrument};
+use transfer::{Downloads, FileCodec, FileProtocol, FileResponse, Transfers};
+use whisper::{WhisperCodec, WhisperProtocol, WhisperRequest};
+
+/// Yields every `period`, starting one period from now.
+fn interval(period: Duration) -> stream::Fuse<stream::BoxStream<'static, ()>> {
+    stream::unfold((), move |()| async move {
+        runtime::sleep(period).await;
+        Some(((), ()))
+    })
+    .boxed()
+    .fuse()
+}
+
+/// Yields SIGINT, SIGTERM and SIGQUIT, so the node can shut down cleanly.
+/// Once the receiver is dropped, as the node shuts down, the next one exits
+/// right away, in case shutting down hangs.
+fn shutdown_signals() -> io::Result<mpsc::UnboundedReceiver<i32>> {
+    let mut signals = Signals::new(TERM_SIGNALS)?;
+    let (sender, receiver) = mpsc::unbounded();
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            if sender.unbounded_send(signal).is_err() {
+                process::exit(128 + signal);
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Prints the peers in the Kademlia routing table, bucket by bucket.
+fn print_peers(kademlia: &mut Kademlia<MemoryStore>) {
+    let mut count = 0;
+    for bucket in kademlia.kbuckets() {
+        for entry in bucket.iter() {
+            println!(
+                "{} {:?} {:?}",
+                entry.node.key.preimage(),
+                entry.status,
+                entry.node.value.iter().collect::<Vec<_>>()
+            );
+            count += 1;
+        }
+    }
+    println!("{} peers in the routing table", count);
+}
+
+/// Handles `/share <peer id> <hex>`, sending the bytes to that peer.
+fn send_share(swarm: &mut libp2p::Swarm<Behaviour>, args: &str) {
+    let mut args = args.split_whitespace();
+    let (peer_id, share) = match (args.next(), args.next()) {
+        (Some(peer_id), Some(share)) => (peer_id, share),
+        _ => {
+            println!("Usage: /share <peer id> <hex>");
+            return;
+        }
+    };
+
+    let peer_id: PeerId = match peer_id.parse() {
+        Ok(peer_id) => peer_id,
+        Err(e) => {
+            println!("Invalid peer id: {:?}", e);
+            return;
+        }
+    };
+
+    match hex::decode(share) {
+        Ok(share) if !share.is_empty() => {
+            let _peer = info_span!("peer", id = %peer_id).entered();
+            let request_id = swarm
+                .behaviour_mut()
+                .share
+                .send_request(&peer_id, ShareRequest(share));
+            info!("Sending share {:?}", request_id);
+        }
+        _ => println!("The share must be non-empty hex"),
+    }
+}
+
+/// Handles `/whisper <peer id> <text>`, sealing the text to that peer.
+fn send_whisper(swarm: &mut libp2p::Swarm<Behaviour>, args: &str) {
+    let (peer_id, text) = match args.trim_start().split_once(' ') {
+        Some((peer_id, text)) if !text.trim().is_empty() => (peer_id, text.trim()),
+        _ => {
+            println!("Usage: /whisper <peer id> <text>");
+            return;
+        }
+    };
+
+    let peer_id: PeerId = match peer_id.parse() {
+        Ok(peer_id) => peer_id,
+        Err(e) => {
+            println!("Invalid peer id: {:?}", e);
+            return;
+        }
+    };
+
+    match whisper::seal(&peer_id, text.as_bytes(), &mut rand::rngs::OsRng) {
+        Some(sealed) => {
+            let _peer = info_span!("peer", id = %peer_id).entered();
+            let request_id = swarm
+                .behaviour_mut()
+                .whisper
+                .send_request(&peer_id, WhisperRequest(sealed));
+            info!("Whispering {:?}", request_id);
+        }
+        None => println!("{:?} has no ed25519 key to whisper to", peer_id),
+    }
+}
+
+/// Handles `/send <peer id> <path>`, offering the file to that peer. The
+/// rest is sent as the peer asks for it.
+fn send_file(swarm: &mut libp2p::Swarm<Behaviour>, transfers: &mut Transfers, args: &str) {
+    let (peer_id, path) = match args.trim_start().split_once(' ') {
+        Some((peer_id, path)) if !path.trim().is_empty() => (peer_id, path.trim()),
+        _ => {
+            println!("Usage: /send <peer id> <path>");
+            return;
+        }
+    };
+
+    let peer_id: PeerId = match peer_id.parse() {
+        Ok(peer_id) => peer_id,
+        Err(e) => {
+            println!("Invalid peer id: {:?}", e);
+            return;
+        }
+    };
+
+    let _peer = info_span!("peer", id = %peer_id).entered();
+    let file = &mut swarm.behaviour_mut().file;
+    if let Err(e) = transfers.send(file, &peer_id, path.as_ref()) {
+        println!("Can't send {}: {}", path, e);
+    }
+}
+
+/// Handles `/psi <peer id> <path>`, intersecting the elements in the file
+/// with the peer's.
+fn run_psi(
+    swarm: &mut libp2p::Swarm<Behaviour>,
+    queries: &mut HashMap<RequestId, PsiQuery>,
+    args: &str,
+) {
+    let (peer_id, path) = match args.trim_start().split_once(' ') {
+        Some((peer_id, path)) if !path.trim().is_empty() => (peer_id, path.trim()),
+        _ => {
+            println!("Usage: /psi <peer id> <path>");
+            return;
+        }
+    };
+
+    let peer_id: PeerId = match peer_id.parse() {
+        Ok(peer_id) => peer_id,
+        Err(e) => {
+            println!("Invalid peer id: {:?}", e);
+            return;
+        }
+    };
+
+    match PsiQuery::open(path.as_ref(), &mut rand::rngs::OsRng) {
+        Ok(query) => {
+            let _peer = info_span!("peer", id = %peer_id).entered();
+            let request_id = swarm
+                .behaviour_mut()
+                .psi
+                .send_request(&peer_id, query.request());
+            info!("Intersecting {} elements {:?}", query.size(), request_id);
+            queries.insert(request_id, query);
+        }
+        Err(e) => println!("Can't read {}: {}", path, e),
+    }
+}
+
+/// Handles `/info <peer id>`, printing what the peer told us about itself.
+fn print_info(peers: &PeerTable, args: &str) {
+    match args.trim().parse::<PeerId>() {
+        Ok(peer_id) => match peers.get(&peer_id) {
+            Some(info) => println!("{}\n{}", peer_id, info),
+            None => println!(
+                "{} hasn't identified itself, {} peers have",
+                peer_id,
+                peers.len()
+            ),
+        },
+        Err(e) => println!("Invalid peer id: {:?}", e),
+    }
+}
+
+/// Handles `/join <topic>`, subscribing to the topic and publishing chat
+/// lines on it from now on.
+fn join_topic(gossipsub: &mut Gossipsub, topics: &mut Vec<Topic>, name: &str) {
+    let topic = Topic::new(name.trim());
+    if let Some(i) = topics
+        .iter()
+        .position(|joined| joined.hash() == topic.hash())
+    {
+        topics.remove(i);
+    } else if let Err(e) = gossipsub.subscribe(&topic) {
+        println!("Failed to join {}: {:?}", topic, e);
+        return;
+    }
+
+    // Fails only when peer scoring is off.
+    let _ = gossipsub.set_topic_params(topic.clone(), TopicScoreParams::default());
+    println!("Chatting on {}", topic);
+    topics.push(topic);
+}
+
+/// Handles `/leave <topic>`. Chat lines go back to the topic joined before.
+fn leave_topic(gossipsub: &mut Gossipsub, topics: &mut Vec<Topic>, name: &str) {
+    let topic = Topic::new(name.trim());
+    match topics
+        .iter()
+        .position(|joined| joined.hash() == topic.hash())
+    {
+        Some(i) => {
+            topics.remove(i);
+            let _ = gossipsub.unsubscribe(&topic);
+            match topics.last() {
+                Some(current) => println!("Left {}, chatting on {}", topic, current),
+                None => println!("Left {}, /join a topic to chat again", topic),
+            }
+        }
+        None => println!("Not in {}", topic),
+    }
+}
+
+/// Handles `/topics`, the last one listed is the one we chat on.
+fn print_topics(topics: &[Topic]) {
+    for topic in topics {
+        println!("{}", topic);
+    }
+    println!("{} topics joined", topics.len());
+}
+
+/// Handles `/latency`, listing peers from the fastest to the slowest.
+fn print_latency(latency: &LatencyTable) {
+    for (peer_id, rtt) in latency.fastest(latency.len()) {
+        println!("{} {:?}", peer_id, rtt);
+    }
+    println!("{} peers answered our pings", latency.len());
+}
+
+/// Schedules the next attempt to reach `peer_id` after one failed.
+fn redial_failed(redialer: &mut Redialer, peer_id: &PeerId, error: &dyn fmt::Debug) {
+    match redialer.failed(peer_id) {
+        Some(Retry::After(delay)) => {
+            info!("Redialing in {:?}, it failed: {:?}", delay, error)
+        }
+        Some(Retry::GaveUp { attempts }) => warn!(
+            "Giving up on {:?}, unreachable after {} attempts: {:?}",
+            peer_id, attempts, error
+        ),
+        None => {}
+    }
+}
+
+/// Takes a token for a message from `peer_id`, `false` if the message should
+/// be dropped.
+fn within_rate(rate_limiter: &mut RateLimiter, peer_id: PeerId, metrics: &Metrics) -> bool {
+    match rate_limiter.check(peer_id) {
+        Limit::Allowed => return true,
+        Limit::Exceeded => warn!("Over the rate limit, dropping its messages"),
+        Limit::StillExceeded => debug!("Dropping a message over the rate limit"),
+    }
+    metrics.messages_throttled.inc();
+    false
+}
+
+/// Counts an offence against `peer_id`, banning it once it has too many.
+fn strike(
+    swarm: &mut libp2p::Swarm<Behaviour>,
+    strikes: &mut Strikes,
+    peer_id: PeerId,
+    offence: Offence,
+) {
+    warn!("Peer sent {}", offence);
+    if strikes.strike(peer_id) {
+        warn!("Banning {:?} for {:?}", peer_id, strikes.ban_duration());
+        swarm.behaviour_mut().gossipsub.blacklist_peer(&peer_id);
+        // The swarm has no bans of its own, we hang up on the peer now and
+        // on its next connections while the ban lasts.
+        let _ = swarm.disconnect_peer_id(peer_id);
+    }
+}
+
+/// The peer a swarm event is about, which tags the records logged while
+/// handling it.
+fn event_peer(event: &SwarmEvent<Event>) -> Option<PeerId> {
+    match event {
+        SwarmEvent::ConnectionEstablished { peer_id, .. }
+        | SwarmEvent::ConnectionClosed { peer_id, .. }
+        | SwarmEvent::OutgoingConnectionError {
+            peer_id: Some(peer_id),
+            ..
+        }
+        | SwarmEvent::Dialing {
+            peer_id: Some(peer_id),
+            ..
+        } => Some(*peer_id),
+        SwarmEvent::Behaviour(event) => match event {
+            Event::Gossipsub(GossipsubEvent::Message {
+                propagation_source, ..
+            }) => Some(*propagation_source),
+            Event::Gossipsub(
+                GossipsubEvent::Subscribed { peer_id, .. }
+                | GossipsubEvent::Unsubscribed { peer_id, .. }
+                | GossipsubEvent::GossipsubNotSupported { peer_id },
+            ) => Some(*peer_id),
+            Event::Kademlia(KademliaEvent::RoutingUpdated { peer, .. }) => Some(*peer),
+            Event::Share(event) => request_response_peer(event),
+            Event::Whisper(event) => request_response_peer(event),
+            Event::CatchUp(event) => request_response_peer(event),
+            Event::File(event) => request_response_peer(event),
+            Event::Psi(event) => request_response_peer(event),
+            Event::Identify(
+                IdentifyEvent::Received { peer_id, .. }
+                | IdentifyEvent::Sent { peer_id, .. }
+                | IdentifyEvent::Pushed { peer_id, .. }
+                | IdentifyEvent::Error { peer_id, .. },
+            ) => Some(*peer_id),
+            Event::Dcutr(dcutr::Event { remote_peer_id, .. }) => Some(*remote_peer_id),
+            Event::Ping(ping::Event { peer, .. }) => Some(*peer),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
+fn request_response_peer<Req, Resp>(event: &RequestResponseEvent<Req, Resp>) -> Option<PeerId> {
+    match event {
+        RequestResponseEvent::Message { peer, .. }
+        | RequestResponseEvent::OutboundFailure { peer, .. }
+        | RequestResponseEvent::InboundFailure { peer, .. }
+        | RequestResponseEvent::ResponseSent { peer, .. } => Some(*peer),
+    }
+}
 
-#[async_std::main]
+#[cfg_attr(not(feature = "rt-tokio"), async_std::main)]
+#[cfg_attr(feature = "rt-tokio", tokio::main)]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let local_key = identity::Keypair::generate_ed25519();
-    let local_peer_id = PeerId::from(local_key.public());
+    let cli = config::resolve(Cli::parse())?;
+    logging::init(cli.log_level, cli.log_format)?;
+
+    // Without an identity file we get a new peer id on every run.
+    let local_key = match &cli.identity {
+        Some(path) => keyfile::load_or_create(path)?,
+        None => identity::Keypair::generate_ed25519(),
+    };
+
+    // Tells apart the events of several nodes logging to the same place.
+    let node = info_span!("node", id = %PeerId::from(local_key.public()));
+    run(cli, local_key).instrument(node).await
+}
+
+/// Runs the node until it is asked to shut down.
+async fn run(mut cli: Cli, local_key: identity::Keypair) -> Result<(), Box<dyn Error>> {
+    let local_public_key = local_key.public();
+    let local_peer_id = PeerId::from(local_public_key.clone());
+    let whisper_key = local_key
+        .clone()
+        .try_into_ed25519()
+        .expect("Identities are always ed25519");
+
+    info!("Local peer id: {:?}", local_peer_id);
+    let (transport, relay_client) = transport::build(cli.transport, &local_key).await?;
+
+    let bls_key = match (&cli.bls_keystore, &cli.bls_password) {
+        (Some(path), Some(password)) => keyfile::load_or_create_bls(path, password)?,
+        _ => SecretKey::random(&mut rand::rngs::OsRng),
+    };
+    info!(
+        "BLS public key: {}",
+        hex::encode(bls_key.public_key().to_compressed())
+    );
+
+    // A committee is a roster that also keeps everyone else out.
+    let roster_path = cli.committee.as_deref().or(cli.roster.as_deref());
+    let roster = roster_path.map(Roster::load).transpose()?;
+    match &roster {
+        Some(roster) if cli.committee.is_some() => {
+            info!("Only letting in the {} committee members", roster.len())
+        }
+        Some(roster) => info!("Accepting envelopes from {} roster members", roster.len()),
+        None => info!("No roster given, envelope signatures are not checked"),
+    }
+
+    // The ceremonies run between the members of the roster, or of the
+    // committee.
+    let committee = |ceremony: &str| {
+        roster
+            .as_ref()
+            .ok_or(format!("The {} needs a --committee or --roster", ceremony))
+    };
+    let (mut dkg, mut signing, mut beacon) = (None, None, None);
+    let (mut setup, mut pvss, mut rotation) = (None, None, None);
+    match cli.command.take() {
+        Some(Command::Dkg(args)) => {
+            dkg = Some(DkgTask::start(args, committee("DKG")?, &whisper_key)?)
+        }
+        Some(Command::Sign(args)) => signing = Some(SigningTask::start(args, &local_peer_id)?),
+        Some(Command::Beacon(args)) => {
+            beacon = Some(BeaconTask::start(args, &local_peer_id).await?)
+        }
+        Some(Command::Setup(args)) => {
+            setup = Some(SetupTask::start(args, committee("setup")?, &local_peer_id)?)
+        }
+        Some(Command::Pvss(args)) => {
+            let roster = committee("PVSS round")?;
+            pvss = Some(PvssTask::start(
+                args,
+                roster,
+                &local_peer_id,
+                bls_key.clone(),
+            )?)
+        }
+        // Committees rotate on the schedule of a beacon.
+        Some(Command::Rotate(args)) => {
+            rotation = Some(RotationTask::start(
+                args,
+                &local_peer_id,
+                whisper_key.clone(),
+            )?)
+        }
+        None => {}
+    }
+    // The topics of the ceremonies, which aren't chatted on.
+    let ceremony_topics = dkg
+        .iter()
+        .map(DkgTask::topic)
+        .chain(signing.iter().map(SigningTask::topic))
+        .chain(beacon.iter().map(BeaconTask::topic))
+        .chain(setup.iter().map(SetupTask::topic))
+        .chain(pvss.iter().map(PvssTask::topic))
+        .chain(rotation.iter().map(RotationTask::topic))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut known_peers = match &cli.address_book {
+        Some(path) => address_book::load(path)?,
+        None => Vec::new(),
+    };
+    let store = cli.store.as_deref().map(Store::open).transpose()?;
+    // The peers met on earlier runs are redialed right away.
+    let mut stored_peers = HashSet::new();
+    if let Some(store) = &store {
+        let addresses = store.addresses()?;
+        stored_peers.extend(addresses.iter().map(|(peer_id, _)| *peer_id));
+        info!(
+            "Loaded {} addresses of {} peers from the store",
+            addresses.len(),
+            stored_peers.len()
+        );
+        known_peers.extend(addresses);
+    }
 
-    println!("Local peer id: {:?}", local_peer_id);
-    let transport = libp2p::development_transport(local_key.clone()).await?;
+    let validation_mode = ValidationMode::from(cli.validation_mode);
+    info!("Validation mode: {:?}", validation_mode);
 
-    // Create a Gossipsub topic
-    let topic = Topic::new("test-net");
+    // Chat lines are published on the last topic joined.
+    let mut topics: Vec<_> = cli.topic.iter().map(Topic::new).collect();
+    topics.dedup_by(|a, b| a.hash() == b.hash());
 
     // Create a Swarm to manage peers and events
     let mut swarm = {
-        // To content-address message, we can take the hash of message and use it as an ID.
-        let message_id_fn = |message: &GossipsubMessage| {
-            let mut s = DefaultHasher::new();
-            message.data.hash(&mut s);
-            MessageId::from(s.finish().to_string())
-        };
+        let message_id_fn = |message: &GossipsubMessage| inbox::message_id(&message.data);
 
         // Set a custom gossipsub
-        let gossipsub_config = gossipsub::GossipsubConfigBuilder::default()
+        let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(10)) // This is set to aid debugging by not cluttering the log space
-            .validation_mode(ValidationMode::Strict) // This sets the kind of message validation. The default is Strict (enforce message signing)
-            .message_id_fn(message_id_fn) // content-address messages. No two messages of the
-            // same content will be propagated.
+            .validation_mode(validation_mode.clone()) // This sets the kind of message validation. The default is Strict (enforce message signing)
+            .message_id_fn(message_id_fn) // No two messages with the same id will be propagated.
+            .validate_messages() // only forward the messages `handle_message` accepts
             .build()
             .expect("Valid config");
+        // Anonymous validation rejects any message carrying a signature or an
+        // author, so we have to stop signing ours in that mode.
+        let authenticity = match validation_mode {
+            ValidationMode::Anonymous => MessageAuthenticity::Anonymous,
+            _ => MessageAuthenticity::Signed(local_key),
+        };
+
         // build a gossipsub network behaviour
-        let mut gossipsub: gossipsub::Gossipsub =
-            gossipsub::Gossipsub::new(MessageAuthenticity::Signed(local_key), gossipsub_config)
-                .expect("Correct configuration");
-
-        // subscribes to our topic
-        gossipsub.subscribe(&topic).unwrap();
-
-        // add an explicit peer if one was provided
-        if let Some(explicit) = std::env::args().nth(2) {
-            let explicit = explicit.clone();
-            match explicit.parse() {
-                Ok(id) => gossipsub.add_explicit_peer(&id),
-                Err(err) => println!("Failed to parse explicit peer id: {:?}", err),
+        let mut gossipsub =
+            Gossipsub::new(authenticity, gossipsub_config).expect("Correct configuration");
+
+        // Rejected messages count against the peer that sent them, until it
+        // is pruned from the mesh and finally ignored.
+        if cli.peer_scoring {
+            let mut params = PeerScoreParams::default();
+            for topic in topics.iter().chain(&ceremony_topics) {
+                params
+                    .topics
+                    .insert(topic.hash(), TopicScoreParams::default());
             }
+            gossipsub
+                .with_peer_score(params, PeerScoreThresholds::default())
+                .expect("Valid peer score parameters");
         }
 
+        // subscribes to our topics
+        for topic in topics.iter().chain(&ceremony_topics) {
+            gossipsub.subscribe(topic).unwrap();
+        }
+
+        // add the explicit peers we were given
+        for peer_id in &cli.explicit_peer {
+            gossipsub.add_explicit_peer(peer_id);
+        }
+
+        // Kademlia, seeded with the bootstrap nodes.
+        let kademlia_config = KademliaConfig::new(KADEMLIA_PROTOCOL);
+        let store = MemoryStore::new(local_peer_id);
+        let mut kademlia = Kademlia::with_config(local_peer_id, store, kademlia_config);
+        for (peer_id, address) in cli.bootstrap.iter().chain(&known_peers) {
+            kademlia.add_address(peer_id, address.clone());
+        }
+
+        #[cfg(feature = "mdns")]
+        let mdns = if cli.no_mdns {
+            None
+        } else {
+            Some(behaviour::Mdns::new(
+                mdns::Config::default(),
+                local_peer_id,
+            )?)
+        };
+        #[cfg(not(feature = "mdns"))]
+        let mdns = None;
+
+        let share = request_response::Behaviour::with_codec(
+            ShareCodec,
+            iter::once((ShareProtocol, ProtocolSupport::Full)),
+            Default::default(),
+        );
+
+        let whisper = request_response::Behaviour::with_codec(
+            WhisperCodec,
+            iter::once((WhisperProtocol, ProtocolSupport::Full)),
+            Default::default(),
+        );
+
+        let catch_up = request_response::Behaviour::with_codec(
+            CatchUpCodec,
+            iter::once((CatchUpProtocol, ProtocolSupport::Full)),
+            Default::default(),
+        );
+
+        let file = request_response::Behaviour::with_codec(
+            FileCodec,
+            iter::once((FileProtocol, ProtocolSupport::Full)),
+            Default::default(),
+        );
+
+        let psi = request_response::Behaviour::with_codec(
+            PsiCodec,
+            iter::once((PsiProtocol, ProtocolSupport::Full)),
+            Default::default(),
+        );
+
+        let identify = identify::Behaviour::new(
+            identify::Config::new(peers::PROTOCOL_VERSION.into(), local_public_key)
+                .with_agent_version(peers::AGENT_VERSION.into()),
+        );
+
+        // Relaying for others is opt-in.
+        let relay = cli
+            .relay_server
+            .then(|| relay::Behaviour::new(local_peer_id, Default::default()));
+
+        let ping = ping::Behaviour::new(ping::Config::new());
+
         // build the swarm
-        libp2p::Swarm::new(transport, gossipsub, local_peer_id)
+        let behaviour = Behaviour {
+            gossipsub,
+            mdns: Toggle::from(mdns),
+            kademlia,
+            share,
+            whisper,
+            catch_up,
+            file,
+            psi,
+            relay: Toggle::from(relay),
+            relay_client,
+            dcutr: dcutr::Behaviour::new(local_peer_id),
+            identify,
+            ping,
+        };
+        // Peers keep their connection to the relay open while listening
+        // through it, so connections aren't closed as soon as they go quiet.
+        let config = runtime::swarm_config().with_idle_connection_timeout(Duration::from_secs(60));
+        libp2p::Swarm::new(transport, behaviour, local_peer_id, config)
     };
 
-    if let Some(to_dial) = std::env::args().nth(1) {
-        let address: Multiaddr = to_dial.parse().expect("User to provide valid address.");
+    if !cli.bootstrap.is_empty() || !known_peers.is_empty() {
+        swarm.behaviour_mut().kademlia.bootstrap()?;
+    }
+    let mut bootstrap_timer = interval(cli.bootstrap_interval);
+    let mut ban_timer = interval(Duration::from_secs(10));
+    let mut redial_timer = interval(Duration::from_secs(1));
+    let mut dkg_timer = interval(Duration::from_secs(5));
+    let mut sign_timer = interval(Duration::from_secs(1));
+    let mut beacon_timer = interval(Duration::from_secs(1));
+    let mut setup_timer = interval(Duration::from_secs(5));
+    let mut pvss_timer = interval(Duration::from_secs(5));
+    let mut rotate_timer = interval(Duration::from_secs(5));
+
+    for address in &cli.dial {
         match swarm.dial(address.clone()) {
-            Ok(_) => println!("Dialed {:?}", address),
-            Err(e) => println!("Dial {:?} failed: {:?}", address, e),
+            Ok(_) => info!("Dialed {:?}", address),
+            Err(e) => warn!("Dial {:?} failed: {:?}", address, e),
         };
     }
+    for peer_id in stored_peers {
+        // Dials the addresses Kademlia was given for the peer.
+        if let Err(e) = swarm.dial(peer_id) {
+            debug!("Dial {:?} failed: {:?}", peer_id, e);
+        }
+    }
 
-    let mut stdin = io::BufReader::new(io::stdin()).lines().fuse();
+    let mut stdin = Box::pin(runtime::stdin_lines()).fuse();
+    let mut outbox = Outbox::new(local_peer_id, bls_key);
+    let mut inbox = Inbox::default();
+    let metrics = Metrics::new();
+    if let Some(address) = cli.metrics {
+        metrics.serve(address).await?;
+    }
+    let mut peer_table = PeerTable::default();
+    let mut latency = LatencyTable::default();
+    let mut strikes = Strikes::new(cli.strike_limit, cli.ban_duration);
+    let mut redialer = Redialer::new(cli.redial_attempts);
+    let mut rate_limiter = RateLimiter::new(cli.rate_limit, cli.rate_burst);
+    let downloads = cli
+        .downloads
+        .clone()
+        .map(|dir| Downloads::new(dir, cli.max_download_size));
+    let mut transfers = Transfers::new(downloads);
+    let psi_server = match &cli.psi_set {
+        Some(path) => {
+            let server = PsiServer::load(path, &mut rand::rngs::OsRng)?;
+            info!(
+                "Intersecting with {} elements from {}",
+                server.size(),
+                path.display()
+            );
+            Some(server)
+        }
+        None => None,
+    };
+    // Our elements waiting for the peer's evaluations, by request.
+    let mut psi_queries = HashMap::new();
+    // The peers we can't do without, we keep reconnecting to them.
+    let is_required = |peer_id: &PeerId| {
+        cli.bootstrap
+            .iter()
+            .any(|(bootstrap, _)| bootstrap == peer_id)
+            || roster
+                .as_ref()
+                .is_some_and(|roster| roster.contains(peer_id))
+    };
+    let is_outsider = |peer_id: &PeerId| {
+        cli.committee.is_some()
+            && !roster
+                .as_ref()
+                .is_some_and(|roster| roster.contains(peer_id))
+    };
+
+    if cli.listen.is_empty() {
+        swarm.listen_on(cli.transport.default_listen())?;
+    }
+    for address in &cli.listen {
+        swarm.listen_on(address.clone())?;
+    }
+    for relay in &cli.relay {
+        swarm.listen_on(relay.clone().with(Protocol::P2pCircuit))?;
+    }
 
-    swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+    let mut shutdown = shutdown_signals()?;
+    // Set once the ceremony is over, returned after shutting down.
+    let mut outcome = Ok(());
+    // Set once we were asked to shut down in the middle of a ceremony, the
+    // next signal abandons it.
+    let mut abandoning = false;
 
     loop {
         select! {
+            _ = shutdown.select_next_some() => {
+                let ceremony = if dkg.is_some() {
+                    Some("DKG")
+                } else if setup.is_some() {
+                    Some("setup")
+                } else if pvss.is_some() {
+                    Some("PVSS round")
+                } else if rotation.as_ref().is_some_and(RotationTask::is_handing_over) {
+                    Some("handoff")
+                } else {
+                    None
+                };
+                match ceremony {
+                    // Our part of it only lives in memory: leaving loses it
+                    // and stalls the members still at it.
+                    Some(ceremony) if !abandoning => {
+                        warn!("The {} isn't over, leaving now loses our part of it. Signal again to abandon it", ceremony);
+                        abandoning = true;
+                    }
+                    Some(ceremony) => {
+                        outcome = Err(format!("Abandoned the {}", ceremony).into());
+                        break;
+                    }
+                    None => break,
+                }
+            },
             line = stdin.select_next_some() => {
-                if let Err(e) = swarm
-                    .behaviour_mut()
-                    .publish(topic.clone(), line.expect("Stdin not to close").as_bytes())
-                {
-                    println!("Publish error: {:?}", e);
+                let line = line.expect("Stdin not to close");
+                if line.trim() == "/peers" {
+                    print_peers(&mut swarm.behaviour_mut().kademlia);
+                } else if line.trim() == "/latency" {
+                    print_latency(&latency);
+                } else if let Some(args) = line.strip_prefix("/info ") {
+                    print_info(&peer_table, args);
+                } else if let Some(args) = line.strip_prefix("/share ") {
+                    send_share(&mut swarm, args);
+                } else if let Some(args) = line.strip_prefix("/whisper ") {
+                    send_whisper(&mut swarm, args);
+                } else if let Some(args) = line.strip_prefix("/send ") {
+                    send_file(&mut swarm, &mut transfers, args);
+                } else if let Some(args) = line.strip_prefix("/psi ") {
+                    run_psi(&mut swarm, &mut psi_queries, args);
+                } else if let Some(name) = line.strip_prefix("/join ") {
+                    join_topic(&mut swarm.behaviour_mut().gossipsub, &mut topics, name);
+                } else if let Some(name) = line.strip_prefix("/leave ") {
+                    leave_topic(&mut swarm.behaviour_mut().gossipsub, &mut topics, name);
+                } else if line.trim() == "/topics" {
+                    print_topics(&topics);
+                } else if let Some(topic) = topics.last() {
+                    let signed = outbox.seal(Payload::Chat(line));
+                    match outbox::publish(&mut swarm.behaviour_mut().gossipsub, topic, &signed) {
+                        Ok(()) => {
+                            metrics.messages_published.inc();
+                        }
+                        Err(e) => warn!("Publish error: {:?}", e),
+                    }
+                    // Kept even if no one got it now, peers catch up on it later.
+                    if let Some(store) = &store {
+                        catch_up::keep(store, &topic.hash(), signed);
+                    }
+                } else {
+                    println!("Not in any topic, /join one first");
                 }
             },
-            event = swarm.select_next_some() => match event {
-                SwarmEvent::NewListenAddr { address, .. } => {
-                    println!("Listening on {:?}", address)
+            _ = dkg_timer.select_next_some() => {
+                if dkg.as_mut().is_some_and(DkgTask::is_ready) {
+                    outcome = dkg.take().expect("The DKG is running").finish();
+                    break;
                 }
-                SwarmEvent::IncomingConnection { send_back_addr, .. } => {
-                    println!("Incoming connecting {:?}", send_back_addr)
+                let task = match &mut dkg {
+                    Some(task) => task,
+                    None => continue,
+                };
+                let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                outbox.republish(gossipsub, task.topic(), task.payloads(), &metrics);
+                for (peer_id, request) in task.undelivered() {
+                    swarm.behaviour_mut().share.send_request(&peer_id, request);
                 }
-                SwarmEvent::ConnectionEstablished { peer_id, num_established, .. } => {
-                    println!("Connection established ({}) {:?}", num_established, peer_id);
+            },
+            _ = sign_timer.select_next_some() => {
+                let task = match &mut signing {
+                    Some(task) => task,
+                    None => continue,
+                };
+                let request = match task.tick(|peer_id| latency.average(peer_id)) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        outcome = Err(e.into());
+                        break;
+                    }
+                };
+                // Published again on the next tick if no one was there to get it.
+                if let Some(request) = request {
+                    let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                    match outbox.publish(gossipsub, task.topic(), request) {
+                        Ok(()) => {
+                            metrics.messages_published.inc();
+                            task.published();
+                        }
+                        Err(e) => debug!("Publishing the sign request failed: {:?}", e),
+                    }
                 }
-                SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                    println!("Connection closed {:?}", peer_id);
+            },
+            _ = beacon_timer.select_next_some() => {
+                if let Some(task) = &mut beacon {
+                    let payloads = task.tick();
+                    let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                    outbox.republish(gossipsub, task.topic(), payloads, &metrics);
                 }
-                SwarmEvent::Dialing(peer_id) => {
-                    println!("Dialing {:?}", peer_id);
+            },
+            _ = setup_timer.select_next_some() => {
+                if setup.as_mut().is_some_and(SetupTask::is_ready) {
+                    outcome = setup.take().expect("The setup is running").finish();
+                    break;
+                }
+                let task = match &mut setup {
+                    Some(task) => task,
+                    None => continue,
+                };
+                match task.tick() {
+                    Ok(update) => {
+                        let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                        outbox.republish(gossipsub, task.topic(), update, &metrics);
+                    }
+                    Err(e) => {
+                        outcome = Err(e.into());
+                        break;
+                    }
+                }
+            },
+            _ = pvss_timer.select_next_some() => {
+                if pvss.as_mut().is_some_and(PvssTask::is_ready) {
+                    pvss.take().expect("The PVSS round is running").finish();
+                    break;
+                }
+                let task = match &mut pvss {
+                    Some(task) => task,
+                    None => continue,
+                };
+                match task.tick() {
+                    Ok(payloads) => {
+                        let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                        outbox.republish(gossipsub, task.topic(), payloads, &metrics);
+                    }
+                    Err(e) => {
+                        outcome = Err(e.into());
+                        break;
+                    }
+                }
+            },
+            _ = rotate_timer.select_next_some() => {
+                let task = match &mut rotation {
+                    Some(task) => task,
+                    None => continue,
+                };
+                match task.tick() {
+                    Ok(Some(payloads)) => {
+                        let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                        outbox.republish(gossipsub, task.topic(), payloads, &metrics);
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        outcome = Err(e);
+                        break;
+                    }
+                }
+            },
+            _ = bootstrap_timer.select_next_some() => {
+                // Refreshes the routing table, fails only while it's empty.
+                let _ = swarm.behaviour_mut().kademlia.bootstrap();
+            },
+            _ = redial_timer.select_next_some() => {
+                for peer_id in redialer.due() {
+                    let _peer = info_span!("peer", id = %peer_id).entered();
+                    // Dials the addresses Kademlia knows for the peer.
+                    if let Err(e) = swarm.dial(peer_id) {
+                        redial_failed(&mut redialer, &peer_id, &e);
+                    }
                 }
-                SwarmEvent::Behaviour(GossipsubEvent::Message {
-                    propagation_source: peer_id,
-                    message_id: id,
-                    message,
-                }) => println!(
-                    "Got message: {} with id: {} from peer: {:?}",
-                    String::from_utf8_lossy(&message.data),
-                    id,
-                    peer_id
-                ),
-                _ => {}
+            },
+            _ = ban_timer.select_next_some() => {
+                for peer_id in strikes.expire() {
+                    info!("Lifting the ban on {:?}", peer_id);
+                    swarm.behaviour_mut().gossipsub.remove_blacklisted_peer(&peer_id);
+                }
+                rate_limiter.forget_idle();
+                inbox.expire();
+            },
+            event = swarm.select_next_some() => {
+                let _peer = event_peer(&event).map(|peer_id| info_span!("peer", id = %peer_id).entered());
+                match event {
+                    SwarmEvent::NewListenAddr { address, .. } => {
+                        info!("Listening on {:?}", address);
+                        // A relay hands its external addresses out with the
+                        // reservations, without them no one can listen
+                        // through it.
+                        if cli.relay_server {
+                            swarm.add_external_address(address);
+                        }
+                    }
+                    SwarmEvent::ListenerClosed { addresses, reason: Err(e), .. } => {
+                        warn!("Stopped listening on {:?}: {}", addresses, e);
+                    }
+                    SwarmEvent::IncomingConnection { send_back_addr, .. } => {
+                        debug!("Incoming connection from {:?}", send_back_addr)
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, .. } if strikes.is_banned(&peer_id) => {
+                        info!("Hanging up on a banned peer");
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, .. } if is_outsider(&peer_id) => {
+                        info!("Hanging up on a peer outside the committee");
+                        let behaviour = swarm.behaviour_mut();
+                        behaviour.kademlia.remove_peer(&peer_id);
+                        behaviour.gossipsub.blacklist_peer(&peer_id);
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established, .. } => {
+                        metrics.connected_peers.set(swarm.network_info().num_peers() as u64);
+                        // DCUtR follows a relayed connection with a direct
+                        // one, if hole punching works out.
+                        let address = match &endpoint {
+                            ConnectedPoint::Dialer { address, .. } => address,
+                            ConnectedPoint::Listener { local_addr, .. } => local_addr,
+                        };
+                        let relayed = address.iter().any(|protocol| protocol == Protocol::P2pCircuit);
+                        let via = if relayed { "relayed" } else { "direct" };
+                        redialer.connected(&peer_id);
+                        info!("Connection established ({}, {})", num_established, via);
+                        if let (Some(store), 1) = (&store, num_established.get()) {
+                            catch_up::request(&mut swarm.behaviour_mut().catch_up, store, &peer_id);
+                        }
+                    }
+                    SwarmEvent::ConnectionClosed { peer_id, num_established, .. } => {
+                        info!("Connection closed");
+                        metrics.connected_peers.set(swarm.network_info().num_peers() as u64);
+                        if num_established == 0 && is_required(&peer_id) && !strikes.is_banned(&peer_id) {
+                            let delay = redialer.disconnected(peer_id);
+                            info!("Redialing in {:?}", delay);
+                        }
+                    }
+                    SwarmEvent::OutgoingConnectionError { peer_id: Some(peer_id), error, .. } => {
+                        redial_failed(&mut redialer, &peer_id, &error);
+                    }
+                    SwarmEvent::Dialing { .. } => {
+                        debug!("Dialing");
+                    }
+                    SwarmEvent::Behaviour(Event::Gossipsub(GossipsubEvent::Message {
+                        propagation_source: peer_id,
+                        message_id: id,
+                        ..
+                    })) if is_outsider(&peer_id) || !within_rate(&mut rate_limiter, peer_id, &metrics) => {
+                        metrics.messages_received.inc();
+                        // Ignored rather than rejected, the message may well be valid.
+                        let _ = swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                            &id,
+                            &peer_id,
+                            MessageAcceptance::Ignore,
+                        );
+                    }
+                    // Requests are answered all the same, a peer left without a
+                    // response closes the whole connection.
+                    SwarmEvent::Behaviour(Event::Share(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { channel, .. },
+                    })) if is_outsider(&peer) || !within_rate(&mut rate_limiter, peer, &metrics) => {
+                        let _ = swarm.behaviour_mut().share.send_response(channel, Ack::Rejected);
+                    }
+                    SwarmEvent::Behaviour(Event::Whisper(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { channel, .. },
+                    })) if is_outsider(&peer) || !within_rate(&mut rate_limiter, peer, &metrics) => {
+                        let _ = swarm.behaviour_mut().whisper.send_response(channel, Ack::Rejected);
+                    }
+                    SwarmEvent::Behaviour(Event::CatchUp(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { channel, .. },
+                    })) if is_outsider(&peer) || !within_rate(&mut rate_limiter, peer, &metrics) => {
+                        let response = CatchUpResponse(Vec::new());
+                        let _ = swarm.behaviour_mut().catch_up.send_response(channel, response);
+                    }
+                    // Not rate limited, the sender waits for each chunk to be
+                    // answered before sending the next.
+                    SwarmEvent::Behaviour(Event::File(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { channel, .. },
+                    })) if is_outsider(&peer) => {
+                        let response = FileResponse::Rejected("not in the committee".into());
+                        let _ = swarm.behaviour_mut().file.send_response(channel, response);
+                    }
+                    // Rate limited, each element costs a hash to G2 and a proof.
+                    SwarmEvent::Behaviour(Event::Psi(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { channel, .. },
+                    })) if is_outsider(&peer) || !within_rate(&mut rate_limiter, peer, &metrics) => {
+                        let response = PsiResponse::Rejected("too many requests or not in the committee".into());
+                        let _ = swarm.behaviour_mut().psi.send_response(channel, response);
+                    }
+                    SwarmEvent::Behaviour(Event::Gossipsub(GossipsubEvent::Message {
+                        propagation_source: peer_id,
+                        message_id: id,
+                        message,
+                    })) => {
+                        metrics.messages_received.inc();
+                        // What we publish in answer on the ceremony's topic.
+                        let mut replies = Vec::new();
+                        let acceptance = match inbox.receive(&message, &id, roster.as_ref(), &metrics) {
+                            Ok(Some(signed)) => {
+                                let (sender, payload) = (&signed.envelope.sender, &signed.envelope.payload);
+                                if let Some(task) = dkg.as_mut().filter(|task| message.topic == task.topic().hash()) {
+                                    replies.push((task.topic().clone(), task.handle(sender, payload)));
+                                }
+                                if let Some(task) = signing.as_mut().filter(|task| message.topic == task.topic().hash()) {
+                                    replies.push((task.topic().clone(), task.handle(sender, payload)));
+                                }
+                                if let Some(task) = beacon.as_mut().filter(|task| message.topic == task.topic().hash()) {
+                                    replies.push((task.topic().clone(), task.handle(sender, payload)));
+                                }
+                                if let Some(task) = setup.as_mut().filter(|task| message.topic == task.topic().hash()) {
+                                    replies.push((task.topic().clone(), task.handle(sender, payload)));
+                                }
+                                if let Some(task) = pvss.as_mut().filter(|task| message.topic == task.topic().hash()) {
+                                    replies.push((task.topic().clone(), task.handle(sender, payload)));
+                                }
+                                if let Some(task) = rotation.as_mut().filter(|task| message.topic == task.topic().hash()) {
+                                    replies.push((task.topic().clone(), task.handle(sender, payload)));
+                                }
+                                if let Some(store) = &store {
+                                    catch_up::keep(store, &message.topic, signed);
+                                }
+                                MessageAcceptance::Accept
+                            }
+                            // Passed on before the envelope is whole, so the
+                            // rest of the mesh gets it as fast as we do.
+                            Ok(None) => MessageAcceptance::Accept,
+                            Err(None) => MessageAcceptance::Ignore,
+                            Err(Some(offence)) => {
+                                metrics.messages_rejected.inc();
+                                strike(&mut swarm, &mut strikes, peer_id, offence);
+                                MessageAcceptance::Reject
+                            }
+                        };
+                        // Fails only if the message was already dropped from the cache.
+                        let _ = swarm
+                            .behaviour_mut()
+                            .gossipsub
+                            .report_message_validation_result(&id, &peer_id, acceptance);
+                        for (topic, payloads) in replies {
+                            outbox.reply(&mut swarm.behaviour_mut().gossipsub, &topic, payloads);
+                        }
+                        // Done once the group signed the message we asked for.
+                        if signing.as_ref().is_some_and(SigningTask::is_done) {
+                            break;
+                        }
+                    }
+                    #[cfg(feature = "mdns")]
+                    SwarmEvent::Behaviour(Event::Mdns(MdnsEvent::Discovered(list))) => {
+                        let behaviour = swarm.behaviour_mut();
+                        for (peer_id, address) in list.into_iter().filter(|(peer_id, _)| !is_outsider(peer_id)) {
+                            info!("Discovered {:?} at {:?}", peer_id, address);
+                            behaviour.kademlia.add_address(&peer_id, address);
+                            behaviour.gossipsub.add_explicit_peer(&peer_id);
+                        }
+                    }
+                    #[cfg(feature = "mdns")]
+                    SwarmEvent::Behaviour(Event::Mdns(MdnsEvent::Expired(list))) => {
+                        let behaviour = swarm.behaviour_mut();
+                        for (peer_id, _) in list {
+                            let mdns = behaviour.mdns.as_ref();
+                            if !mdns.is_some_and(|mdns| mdns.discovered_nodes().any(|node| *node == peer_id)) {
+                                behaviour.gossipsub.remove_explicit_peer(&peer_id);
+                            }
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Identify(IdentifyEvent::Received {
+                        peer_id,
+                        info,
+                        ..
+                    })) if peers::is_compatible(&info.protocol_version) == Some(false) => {
+                        warn!(
+                            "Hanging up, it speaks {} but this node speaks {}",
+                            info.protocol_version,
+                            peers::PROTOCOL_VERSION
+                        );
+                        swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                    SwarmEvent::Behaviour(Event::Identify(IdentifyEvent::Received {
+                        peer_id,
+                        info,
+                        ..
+                    })) => {
+                        // Only zk-lab nodes speak our Kademlia protocol, other
+                        // peers' addresses would just pollute the routing table.
+                        if info.protocols.contains(&KADEMLIA_PROTOCOL) {
+                            let kademlia = &mut swarm.behaviour_mut().kademlia;
+                            for address in &info.listen_addrs {
+                                kademlia.add_address(&peer_id, address.clone());
+                            }
+                        }
+                        info!("Identified as {}", info.agent_version);
+                        peer_table.insert(peer_id, info);
+                    }
+                    SwarmEvent::Behaviour(Event::Ping(ping::Event { peer, result, .. })) => match result {
+                        Ok(rtt) => latency.record(peer, rtt),
+                        Err(_) => latency.remove(&peer),
+                    },
+                    SwarmEvent::Behaviour(Event::Kademlia(KademliaEvent::RoutingUpdated {
+                        peer,
+                        addresses,
+                        ..
+                    })) => {
+                        debug!("Routing table updated");
+                        if let Some(store) = &store {
+                            for address in addresses.iter() {
+                                if let Err(e) = store.add_address(peer, address) {
+                                    warn!("Failed to store an address: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Share(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { request, channel, .. },
+                    })) => {
+                        info!("Got a share of {} bytes", request.0.len());
+                        let ack = match &mut dkg {
+                            Some(task) => match task.receive_share(&peer, &request) {
+                                Ok(replies) => {
+                                    let complained = replies.iter().any(|reply| matches!(reply, Payload::Complaint { .. }));
+                                    outbox.reply(&mut swarm.behaviour_mut().gossipsub, task.topic(), replies);
+                                    if complained {
+                                        Ack::Rejected
+                                    } else {
+                                        Ack::Accepted
+                                    }
+                                }
+                                Err(offence) => {
+                                    if let Some(offence) = offence {
+                                        strike(&mut swarm, &mut strikes, peer, offence);
+                                    }
+                                    Ack::Rejected
+                                }
+                            },
+                            // Without a DKG running, decrypting and checking the
+                            // share is up to whoever sent it, here we only confirm
+                            // its delivery.
+                            None => Ack::Accepted,
+                        };
+                        if swarm.behaviour_mut().share.send_response(channel, ack).is_err() {
+                            warn!("Failed to acknowledge the share");
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Share(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Response { request_id, response },
+                    })) => {
+                        info!("Share {:?}: {:?}", request_id, response);
+                        // Shares that didn't go through are sent again.
+                        if let (Some(task), Ack::Accepted) = (&mut dkg, response) {
+                            task.delivered(&peer);
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Share(RequestResponseEvent::OutboundFailure {
+                        request_id,
+                        error,
+                        ..
+                    })) => {
+                        warn!("Share {:?} failed: {:?}", request_id, error);
+                    }
+                    SwarmEvent::Behaviour(Event::Share(RequestResponseEvent::InboundFailure {
+                        error,
+                        ..
+                    })) => {
+                        warn!("Incoming share failed: {:?}", error);
+                    }
+                    SwarmEvent::Behaviour(Event::Whisper(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Request { request, channel, .. },
+                    })) => {
+                        let ack = match whisper::open(&whisper_key, &request.0) {
+                            Some(text) => {
+                                info!("Whispers: {}", String::from_utf8_lossy(&text));
+                                Ack::Accepted
+                            }
+                            None => {
+                                warn!("Dropping a whisper we can't open");
+                                strike(&mut swarm, &mut strikes, peer, Offence::BadWhisper);
+                                Ack::Rejected
+                            }
+                        };
+                        if swarm.behaviour_mut().whisper.send_response(channel, ack).is_err() {
+                            warn!("Failed to acknowledge the whisper");
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Whisper(RequestResponseEvent::Message {
+                        message: RequestResponseMessage::Response { request_id, response },
+                        ..
+                    })) => {
+                        info!("Whisper {:?}: {:?}", request_id, response);
+                    }
+                    SwarmEvent::Behaviour(Event::Whisper(RequestResponseEvent::OutboundFailure {
+                        request_id,
+                        error,
+                        ..
+                    })) => {
+                        warn!("Whisper {:?} failed: {:?}", request_id, error);
+                    }
+                    SwarmEvent::Behaviour(Event::CatchUp(RequestResponseEvent::Message {
+                        message: RequestResponseMessage::Request { request, channel, .. },
+                        ..
+                    })) => {
+                        let response = catch_up::answer(store.as_ref(), &request);
+                        if swarm.behaviour_mut().catch_up.send_response(channel, response).is_err() {
+                            warn!("Failed to answer the catch-up request");
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::CatchUp(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Response { response: CatchUpResponse(messages), .. },
+                    })) => {
+                        let store = match &store {
+                            Some(store) => store,
+                            None => continue,
+                        };
+                        let full = messages.len() == catch_up::MAX_MESSAGES;
+                        match inbox.catch_up(messages, store, roster.as_ref(), &metrics) {
+                            Ok(0) => debug!("Nothing to catch up on"),
+                            Ok(missed) => {
+                                info!("Caught up on {} envelopes", missed);
+                                // There may be more than fit in one response.
+                                if full {
+                                    catch_up::request(&mut swarm.behaviour_mut().catch_up, store, &peer);
+                                }
+                            }
+                            Err(offence) => strike(&mut swarm, &mut strikes, peer, offence),
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::CatchUp(RequestResponseEvent::OutboundFailure {
+                        error,
+                        ..
+                    })) => {
+                        warn!("Catching up failed: {:?}", error);
+                    }
+                    SwarmEvent::Behaviour(Event::File(RequestResponseEvent::Message {
+                        message: RequestResponseMessage::Request { request, channel, .. },
+                        ..
+                    })) => {
+                        let response = transfers.handle_request(request);
+                        if swarm.behaviour_mut().file.send_response(channel, response).is_err() {
+                            warn!("Failed to answer a file request");
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::File(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Response { request_id, response },
+                    })) => {
+                        transfers.handle_response(&mut swarm.behaviour_mut().file, &peer, request_id, response);
+                    }
+                    SwarmEvent::Behaviour(Event::File(RequestResponseEvent::OutboundFailure {
+                        request_id,
+                        error,
+                        ..
+                    })) => {
+                        transfers.failed(request_id, &error);
+                    }
+                    SwarmEvent::Behaviour(Event::Psi(RequestResponseEvent::Message {
+                        message: RequestResponseMessage::Request { request, channel, .. },
+                        ..
+                    })) => {
+                        let response = match &psi_server {
+                            Some(server) => server.evaluate(&request, &mut rand::rngs::OsRng),
+                            None => PsiResponse::Rejected("no set to intersect with".into()),
+                        };
+                        if swarm.behaviour_mut().psi.send_response(channel, response).is_err() {
+                            warn!("Failed to answer a PSI request");
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Psi(RequestResponseEvent::Message {
+                        peer,
+                        message: RequestResponseMessage::Response { request_id, response },
+                    })) => {
+                        let query = match psi_queries.remove(&request_id) {
+                            Some(query) => query,
+                            None => continue,
+                        };
+                        match query.intersect(response) {
+                            Ok((common, theirs)) => {
+                                println!("{} elements in common with {} of {}:", common.len(), theirs, peer);
+                                for element in common {
+                                    println!("  {}", element);
+                                }
+                            }
+                            Err(e) => warn!("Intersecting with {} failed: {}", peer, e),
+                        }
+                    }
+                    SwarmEvent::Behaviour(Event::Psi(RequestResponseEvent::OutboundFailure {
+                        request_id,
+                        error,
+                        ..
+                    })) if psi_queries.remove(&request_id).is_some() => {
+                        warn!("Intersecting failed: {:?}", error);
+                    }
+                    SwarmEvent::Behaviour(Event::RelayClient(relay::client::Event::ReservationReqAccepted {
+                        relay_peer_id,
+                        renewal: false,
+                        ..
+                    })) => {
+                        info!("Listening through the relay {}", relay_peer_id);
+                    }
+                    SwarmEvent::Behaviour(Event::Relay(relay::Event::ReservationReqAccepted {
+                        src_peer_id,
+                        renewed: false,
+                    })) => {
+                        info!("Relaying for {}", src_peer_id);
+                    }
+                    SwarmEvent::Behaviour(Event::Dcutr(dcutr::Event { result, .. })) => match result {
+                        Ok(_) => info!("Upgraded the relayed connection to a direct one"),
+                        Err(e) => info!("Staying relayed: {}", e),
+                    },
+                    SwarmEvent::Behaviour(Event::Relay(event)) => {
+                        debug!("Relay: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(Event::Kademlia(KademliaEvent::OutboundQueryProgressed {
+                        result: QueryResult::Bootstrap(Err(e)),
+                        ..
+                    })) => {
+                        warn!("Bootstrap failed: {:?}", e);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    drop(shutdown);
+    info!("Shutting down");
+    if let Some(path) = &cli.address_book {
+        let kademlia = &mut swarm.behaviour_mut().kademlia;
+        let mut entries = Vec::new();
+        for bucket in kademlia.kbuckets() {
+            for entry in bucket.iter() {
+                let peer_id = *entry.node.key.preimage();
+                entries.extend(
+                    entry
+                        .node
+                        .value
+                        .iter()
+                        .map(|address| (peer_id, address.clone())),
+                );
             }
         }
+        address_book::save(path, &entries)?;
+        info!("Saved {} addresses to {}", entries.len(), path.display());
     }
+    if let Some(store) = &store {
+        store.flush()?;
+    }
+
+    // Returning, rather than exiting, runs the destructors: the BLS key is
+    // wiped from memory when it is dropped.
+    outcome
 }
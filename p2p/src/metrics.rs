@@ -0,0 +1,161 @@
+//! Counters and histograms of the node in the OpenMetrics text format, served
+//! over HTTP at `/metrics` for Prometheus to scrape during multi-node runs.
+//!
+//! The `open-metrics-client` crate is what `prometheus-client` was called
+//! before it was renamed. The HTTP side is a bare responder, all it has to do
+//! is answer a scrape.
+use crate::runtime::{self, TcpListener};
+use futures::io::BufReader;
+use futures::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use open_metrics_client::encoding::text::encode;
+use open_metrics_client::metrics::counter::Counter;
+use open_metrics_client::metrics::gauge::Gauge;
+use open_metrics_client::metrics::histogram::{exponential_buckets, Histogram};
+use open_metrics_client::registry::Registry;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// How long a client has to send its request and read the response, so
+/// connections left open don't pile up.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait after failing to accept a connection before trying again.
+pub const ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct Metrics {
+    pub connected_peers: Gauge,
+    pub messages_received: Counter,
+    pub messages_rejected: Counter,
+    pub messages_throttled: Counter,
+    pub messages_published: Counter,
+    /// Seconds taken to check a BLS signature on an envelope.
+    pub signature_verification: Histogram,
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let connected_peers = Gauge::default();
+        let messages_received = Counter::default();
+        let messages_rejected = Counter::default();
+        let messages_throttled = Counter::default();
+        let messages_published = Counter::default();
+        // From 100µs to about 3s.
+        let signature_verification = Histogram::new(exponential_buckets(0.0001, 2.0, 16));
+
+        let mut registry = <Registry>::default();
+        let zk_lab = registry.sub_registry_with_prefix("zk_lab");
+        zk_lab.register(
+            "connected_peers",
+            "Peers with at least one open connection",
+            Box::new(connected_peers.clone()),
+        );
+        zk_lab.register(
+            "messages_received",
+            "Gossipsub messages received",
+            Box::new(messages_received.clone()),
+        );
+        zk_lab.register(
+            "messages_rejected",
+            "Gossipsub messages rejected as invalid",
+            Box::new(messages_rejected.clone()),
+        );
+        zk_lab.register(
+            "messages_throttled",
+            "Messages dropped for going over the sender's rate limit",
+            Box::new(messages_throttled.clone()),
+        );
+        zk_lab.register(
+            "messages_published",
+            "Gossipsub messages published",
+            Box::new(messages_published.clone()),
+        );
+        zk_lab.register(
+            "signature_verification_seconds",
+            "Time taken to verify the BLS signature of an envelope",
+            Box::new(signature_verification.clone()),
+        );
+
+        Metrics {
+            connected_peers,
+            messages_received,
+            messages_rejected,
+            messages_throttled,
+            messages_published,
+            signature_verification,
+            registry: Arc::new(Mutex::new(registry)),
+        }
+    }
+
+    /// Serves the metrics at `address` until the node exits.
+    pub async fn serve(&self, address: SocketAddr) -> io::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        info!(
+            "Serving metrics on http://{}/metrics",
+            listener.local_addr()?
+        );
+
+        let registry = self.registry.clone();
+        runtime::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        // Most likely out of file descriptors, which accepting
+                        // again right away won't help with.
+                        warn!("Failed to accept a connection: {}", e);
+                        runtime::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                };
+                let registry = registry.clone();
+                runtime::spawn(async move {
+                    let response = respond(runtime::compat(stream), &registry);
+                    if let Err(e) = runtime::timeout(REQUEST_TIMEOUT, response).await {
+                        warn!("Failed to serve metrics: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+}
+
+async fn respond<S>(stream: S, registry: &Mutex<Registry>) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Only the request line matters, the headers are read and ignored.
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader.take(8 * 1024));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut header = String::new();
+    while reader.read_line(&mut header).await? > 2 {
+        header.clear();
+    }
+
+    let (status, content_type, body) = if request_line.starts_with("GET /metrics ") {
+        let mut body = Vec::new();
+        encode(&mut body, &registry.lock().unwrap())?;
+        ("200 OK", CONTENT_TYPE, body)
+    } else {
+        ("404 Not Found", "text/plain", b"Not found\n".to_vec())
+    };
+
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.close().await
+}
@@ -0,0 +1,108 @@
+//! What the node publishes on gossipsub: its own envelopes, numbered and
+//! signed with its BLS key, split into fragments when they don't fit in one
+//! message.
+use crate::envelope::{self, Envelope, Payload, SignedEnvelope};
+use crate::fragment;
+use crate::metrics::Metrics;
+use bls_shamir::secret::SecretKey;
+use libp2p::gossipsub::{Behaviour as Gossipsub, IdentTopic as Topic, PublishError};
+use libp2p::PeerId;
+use tracing::{debug, warn};
+
+/// Numbers and signs our envelopes.
+pub struct Outbox {
+    sender: PeerId,
+    seq: u64,
+    key: SecretKey,
+}
+
+impl Outbox {
+    pub fn new(sender: PeerId, key: SecretKey) -> Self {
+        Outbox {
+            sender,
+            // Starting from the clock keeps sequence numbers increasing
+            // across restarts, so our new envelopes aren't mistaken for old
+            // ones.
+            seq: envelope::unix_millis(),
+            key,
+        }
+    }
+
+    /// Numbers and signs an envelope of our own.
+    pub fn seal(&mut self, payload: Payload) -> SignedEnvelope {
+        self.seq += 1;
+        Envelope::new(self.sender, self.seq, payload).sign(&self.key)
+    }
+
+    /// Signs and publishes an envelope of our own.
+    pub fn publish(
+        &mut self,
+        gossipsub: &mut Gossipsub,
+        topic: &Topic,
+        payload: Payload,
+    ) -> Result<(), PublishError> {
+        let signed = self.seal(payload);
+        publish(gossipsub, topic, &signed)
+    }
+
+    /// Publishes what a ceremony republishes on every tick. Failing is
+    /// common, no one may be on the topic yet.
+    pub fn republish(
+        &mut self,
+        gossipsub: &mut Gossipsub,
+        topic: &Topic,
+        payloads: impl IntoIterator<Item = Payload>,
+        metrics: &Metrics,
+    ) {
+        for payload in payloads {
+            match self.publish(gossipsub, topic, payload) {
+                Ok(()) => {
+                    metrics.messages_published.inc();
+                }
+                Err(e) => debug!("Publishing on {} failed: {:?}", topic, e),
+            }
+        }
+    }
+
+    /// Publishes what a ceremony answered an envelope with.
+    pub fn reply(
+        &mut self,
+        gossipsub: &mut Gossipsub,
+        topic: &Topic,
+        payloads: impl IntoIterator<Item = Payload>,
+    ) {
+        for payload in payloads {
+            if let Err(e) = self.publish(gossipsub, topic, payload) {
+                warn!("Publishing our answer failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Publishes an envelope, in fragments if it is too large for one message.
+pub fn publish(
+    gossipsub: &mut Gossipsub,
+    topic: &Topic,
+    signed: &SignedEnvelope,
+) -> Result<(), PublishError> {
+    let data = signed.encode();
+    if data.len() <= fragment::FRAGMENT_SIZE {
+        gossipsub.publish(topic.clone(), data)?;
+        return Ok(());
+    }
+    if data.len() > fragment::FRAGMENT_SIZE * fragment::MAX_FRAGMENTS as usize {
+        return Err(PublishError::MessageTooLarge);
+    }
+
+    let envelope = &signed.envelope;
+    let fragments = fragment::split(envelope.sender, envelope.seq, &data);
+    debug!(
+        "Publishing envelope {} in {} fragments",
+        envelope.seq,
+        fragments.len()
+    );
+    for fragment in fragments {
+        gossipsub.publish(topic.clone(), fragment.encode())?;
+    }
+    Ok(())
+}
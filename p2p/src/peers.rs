@@ -0,0 +1,72 @@
+//! What the identify protocol told us about the peers we've connected to, so
+//! multi-node experiments can be debugged from the prompt.
+use libp2p::identify::Info as IdentifyInfo;
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+/// Identifies us to other peers. Nodes of another major version can't read
+/// our envelopes, they are hung up on once they identify themselves.
+pub const PROTOCOL_VERSION: &str = "/zk-lab/15.0.0";
+
+pub const AGENT_VERSION: &str = concat!("zk-lab-p2p/", env!("CARGO_PKG_VERSION"));
+
+/// Whether a peer identifying with `protocol_version` speaks our protocol,
+/// `None` if it isn't a zk-lab node at all, like a plain relay.
+pub fn is_compatible(protocol_version: &str) -> Option<bool> {
+    let major = |version: &str| {
+        let version = version.strip_prefix("/zk-lab/")?;
+        Some(version.split('.').next().unwrap_or(version).to_owned())
+    };
+    let ours = major(PROTOCOL_VERSION).expect("Ours is a zk-lab version");
+    Some(major(protocol_version)? == ours)
+}
+
+#[derive(Debug, Default)]
+pub struct PeerTable {
+    peers: HashMap<PeerId, PeerInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    pub info: IdentifyInfo,
+    /// When the peer last identified itself.
+    pub updated: Instant,
+}
+
+impl PeerTable {
+    /// Records the latest info of `peer_id`, peers are kept after they
+    /// disconnect.
+    pub fn insert(&mut self, peer_id: PeerId, info: IdentifyInfo) {
+        let updated = Instant::now();
+        self.peers.insert(peer_id, PeerInfo { info, updated });
+    }
+
+    pub fn get(&self, peer_id: &PeerId) -> Option<&PeerInfo> {
+        self.peers.get(peer_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+impl fmt::Display for PeerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = &self.info;
+        writeln!(f, "agent:    {}", info.agent_version)?;
+        writeln!(f, "protocol: {}", info.protocol_version)?;
+        writeln!(f, "updated:  {}s ago", self.updated.elapsed().as_secs())?;
+        writeln!(f, "observed us at {}", info.observed_addr)?;
+        writeln!(f, "listening on:")?;
+        for address in &info.listen_addrs {
+            writeln!(f, "  {}", address)?;
+        }
+        write!(f, "protocols:")?;
+        for protocol in &info.protocols {
+            write!(f, "\n  {}", protocol)?;
+        }
+        Ok(())
+    }
+}
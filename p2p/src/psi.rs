@@ -0,0 +1,436 @@
+//! Private set intersection with a single peer on top of the OPRF of
+//! `bls_shamir::oprf`: each side has a set of lines, and the side asking
+//! learns which of its lines the other side has too, and nothing else of
+//! them.
+//!
+//! The client blinds each of its elements and sends them over. The server
+//! evaluates them with its OPRF key, proving with a DLEQ that every one was
+//! evaluated with the same key, and sends along the outputs of its own
+//! elements, sorted so that their order says nothing. The client unblinds
+//! its evaluations into outputs and keeps the elements whose output is
+//! among the server's. The server sees blinded points and how many there
+//! are, and the client sees the size of the server's set.
+//!
+//! The key is picked anew at every start of the node, so outputs can't be
+//! compared across runs. Without the proofs the server could evaluate each
+//! element with another key, and learn which of them are in the
+//! intersection from the outputs it picked to match.
+use crate::share::{read_length_prefixed, write_length_prefixed};
+use async_trait::async_trait;
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use bls_shamir::oprf::{self, Blind, BlindedElement, DleqProof, EvaluatedElement};
+use bls_shamir::secret::SecretKey;
+use futures::prelude::*;
+use libp2p::request_response::Codec;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The most elements on either side.
+pub const MAX_ELEMENTS: usize = 1024;
+
+/// A compressed G2 point and a little around it per element.
+pub const MAX_REQUEST_SIZE: usize = MAX_ELEMENTS * 128;
+
+/// An evaluation with its proof and an output per element.
+pub const MAX_RESPONSE_SIZE: usize = MAX_ELEMENTS * 256;
+
+#[derive(Debug, Clone)]
+pub struct PsiProtocol;
+
+impl AsRef<str> for PsiProtocol {
+    fn as_ref(&self) -> &str {
+        "/zk-lab/psi/1.0.0"
+    }
+}
+
+/// The compressed blinded elements of the client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PsiRequest(pub Vec<Vec<u8>>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PsiResponse {
+    Evaluated {
+        /// The compressed public key of the OPRF key.
+        public_key: Vec<u8>,
+        /// In the order of the request.
+        evaluations: Vec<Evaluation>,
+        /// The outputs of the server's elements, sorted.
+        outputs: Vec<[u8; 32]>,
+    },
+    Rejected(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub point: Vec<u8>,
+    pub challenge: [u8; 32],
+    pub response: [u8; 32],
+}
+
+#[derive(Debug)]
+pub enum PsiError {
+    Rejected(String),
+    /// The response doesn't decode, or doesn't answer every element.
+    Malformed,
+    /// The evaluation of the element at this position isn't under the key.
+    Proof(usize),
+}
+
+impl fmt::Display for PsiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PsiError::Rejected(reason) => write!(f, "refused: {}", reason),
+            PsiError::Malformed => write!(f, "malformed response"),
+            PsiError::Proof(index) => write!(f, "invalid proof for element {}", index),
+        }
+    }
+}
+
+impl std::error::Error for PsiError {}
+
+/// The server side: the OPRF key and the outputs of our elements.
+pub struct PsiServer {
+    key: SecretKey,
+    outputs: Vec<[u8; 32]>,
+}
+
+impl PsiServer {
+    /// Reads the elements, one per line, and evaluates them with a new key.
+    pub fn load<R: RngCore + CryptoRng>(path: &Path, rng: &mut R) -> io::Result<Self> {
+        let elements = read_elements(path)?;
+        let key = SecretKey::random(rng);
+        let mut outputs = elements
+            .iter()
+            .map(|element| oprf::evaluate_input(&key, element.as_bytes()))
+            .collect::<Vec<_>>();
+        outputs.sort_unstable();
+        Ok(PsiServer { key, outputs })
+    }
+
+    /// The number of our elements.
+    pub fn size(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Evaluates the client's blinded elements.
+    pub fn evaluate<R: RngCore + CryptoRng>(
+        &self,
+        request: &PsiRequest,
+        rng: &mut R,
+    ) -> PsiResponse {
+        if request.0.len() > MAX_ELEMENTS {
+            return PsiResponse::Rejected("too many elements".into());
+        }
+        let blinded = request
+            .0
+            .iter()
+            .map(|bytes| decode_g2(bytes).map(BlindedElement))
+            .collect::<Option<Vec<_>>>();
+        let blinded = match blinded {
+            Some(blinded) => blinded,
+            None => return PsiResponse::Rejected("invalid blinded element".into()),
+        };
+        let evaluations = blinded
+            .iter()
+            .map(|blinded| {
+                let (evaluated, proof) = oprf::evaluate_verifiable(&self.key, blinded, &mut *rng);
+                Evaluation {
+                    point: evaluated.0.to_compressed().to_vec(),
+                    challenge: proof.challenge.to_bytes(),
+                    response: proof.response.to_bytes(),
+                }
+            })
+            .collect();
+        PsiResponse::Evaluated {
+            public_key: self.key.public_key().to_compressed().to_vec(),
+            evaluations,
+            outputs: self.outputs.clone(),
+        }
+    }
+}
+
+/// The client side of a run: our elements and their blinds, kept until the
+/// response arrives.
+pub struct PsiQuery {
+    elements: Vec<String>,
+    blinds: Vec<Blind>,
+    blinded: Vec<BlindedElement>,
+}
+
+impl PsiQuery {
+    /// Reads the elements, one per line, and blinds them.
+    pub fn open<R: RngCore + CryptoRng>(path: &Path, rng: &mut R) -> io::Result<Self> {
+        let elements = read_elements(path)?;
+        let (blinds, blinded) = elements
+            .iter()
+            .map(|element| oprf::blind(element.as_bytes(), &mut *rng))
+            .unzip();
+        Ok(PsiQuery {
+            elements,
+            blinds,
+            blinded,
+        })
+    }
+
+    /// The number of our elements.
+    pub fn size(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn request(&self) -> PsiRequest {
+        PsiRequest(
+            self.blinded
+                .iter()
+                .map(|blinded| blinded.0.to_compressed().to_vec())
+                .collect(),
+        )
+    }
+
+    /// Checks the evaluations and returns our elements the server has too,
+    /// along with the size of its set.
+    pub fn intersect(self, response: PsiResponse) -> Result<(Vec<String>, usize), PsiError> {
+        let (public_key, evaluations, outputs) = match response {
+            PsiResponse::Evaluated {
+                public_key,
+                evaluations,
+                outputs,
+            } => (public_key, evaluations, outputs),
+            PsiResponse::Rejected(reason) => return Err(PsiError::Rejected(reason)),
+        };
+        let public_key = decode_g1(&public_key).ok_or(PsiError::Malformed)?;
+        if evaluations.len() != self.elements.len() || outputs.len() > MAX_ELEMENTS {
+            return Err(PsiError::Malformed);
+        }
+        let theirs = outputs.iter().collect::<HashSet<_>>();
+        let mut common = Vec::new();
+        for (index, evaluation) in evaluations.iter().enumerate() {
+            let (evaluated, proof) = decode_evaluation(evaluation).ok_or(PsiError::Malformed)?;
+            if !oprf::verify_evaluation(&public_key, &self.blinded[index], &evaluated, &proof) {
+                return Err(PsiError::Proof(index));
+            }
+            let element = &self.elements[index];
+            let output = oprf::finalize(element.as_bytes(), &self.blinds[index], &evaluated);
+            if theirs.contains(&output) {
+                common.push(element.clone());
+            }
+        }
+        Ok((common, outputs.len()))
+    }
+}
+
+/// The distinct non-empty lines of the file, trimmed.
+fn read_elements(path: &Path) -> io::Result<Vec<String>> {
+    let contents = fs::read_to_string(path)?;
+    let mut seen = HashSet::new();
+    let elements = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && seen.insert(*line))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    if elements.len() > MAX_ELEMENTS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("more than {} elements", MAX_ELEMENTS),
+        ));
+    }
+    Ok(elements)
+}
+
+fn decode_evaluation(evaluation: &Evaluation) -> Option<(EvaluatedElement, DleqProof)> {
+    let point = decode_g2(&evaluation.point)?;
+    let challenge = Option::from(Scalar::from_bytes(&evaluation.challenge))?;
+    let response = Option::from(Scalar::from_bytes(&evaluation.response))?;
+    Some((
+        EvaluatedElement(point),
+        DleqProof {
+            challenge,
+            response,
+        },
+    ))
+}
+
+fn decode_g1(bytes: &[u8]) -> Option<G1Affine> {
+    let bytes = bytes.try_into().ok()?;
+    Option::from(G1Affine::from_compressed(&bytes))
+}
+
+fn decode_g2(bytes: &[u8]) -> Option<G2Affine> {
+    let bytes = bytes.try_into().ok()?;
+    Option::from(G2Affine::from_compressed(&bytes))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PsiCodec;
+
+#[async_trait]
+impl Codec for PsiCodec {
+    type Protocol = PsiProtocol;
+    type Request = PsiRequest;
+    type Response = PsiResponse;
+
+    async fn read_request<T>(&mut self, _: &PsiProtocol, io: &mut T) -> io::Result<PsiRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_length_prefixed(io, MAX_REQUEST_SIZE).await?;
+        bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn read_response<T>(&mut self, _: &PsiProtocol, io: &mut T) -> io::Result<PsiResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_length_prefixed(io, MAX_RESPONSE_SIZE).await?;
+        bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &PsiProtocol,
+        io: &mut T,
+        request: PsiRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let bytes = bincode::serialize(&request).expect("Requests are always serializable");
+        write_length_prefixed(io, bytes).await?;
+        io.close().await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &PsiProtocol,
+        io: &mut T,
+        response: PsiResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let bytes = bincode::serialize(&response).expect("Responses are always serializable");
+        write_length_prefixed(io, bytes).await?;
+        io.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Writes the lines to a new file under the temporary directory.
+    fn set(lines: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("psi-{:016x}", rand::random::<u64>()));
+        fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    /// Runs the protocol, returning the client's result.
+    fn run(client: &[&str], server: &[&str]) -> Result<(Vec<String>, usize), PsiError> {
+        let rng = &mut rand::thread_rng();
+        let (client, server) = (set(client), set(server));
+        let query = PsiQuery::open(&client, rng).unwrap();
+        let response = PsiServer::load(&server, rng)
+            .unwrap()
+            .evaluate(&query.request(), rng);
+        fs::remove_file(client).unwrap();
+        fs::remove_file(server).unwrap();
+        query.intersect(response)
+    }
+
+    #[test]
+    fn finds_the_intersection() {
+        let (common, size) = run(&["alice", "bob", "carol"], &["dave", "carol", "alice"]).unwrap();
+        assert_eq!(common, vec!["alice", "carol"]);
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn disjoint_sets() {
+        let (common, size) = run(&["alice", "bob"], &["carol", "dave", "eve"]).unwrap();
+        assert!(common.is_empty());
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn empty_sets() {
+        assert_eq!(run(&[], &["alice"]).unwrap(), (vec![], 1));
+        assert_eq!(run(&["alice"], &[]).unwrap(), (vec![], 0));
+        assert_eq!(run(&[], &[]).unwrap(), (vec![], 0));
+    }
+
+    #[test]
+    fn lines_are_trimmed_and_deduplicated() {
+        let (common, size) = run(&[" alice ", "", "alice", "bob"], &["alice", "alice\t"]).unwrap();
+        assert_eq!(common, vec!["alice"]);
+        assert_eq!(size, 1);
+    }
+
+    #[test]
+    fn rejects_evaluations_under_another_key() {
+        let rng = &mut rand::thread_rng();
+        let (client, server) = (set(&["alice", "bob"]), set(&["alice"]));
+        let query = PsiQuery::open(&client, rng).unwrap();
+        let request = query.request();
+        let first = PsiServer::load(&server, rng).unwrap();
+        let second = PsiServer::load(&server, rng).unwrap();
+        fs::remove_file(client).unwrap();
+        fs::remove_file(server).unwrap();
+
+        // The second element evaluated with another key, to learn whether
+        // it is in the intersection.
+        let mut response = first.evaluate(&request, rng);
+        let other = second.evaluate(&request, rng);
+        if let (
+            PsiResponse::Evaluated { evaluations, .. },
+            PsiResponse::Evaluated {
+                evaluations: others,
+                ..
+            },
+        ) = (&mut response, other)
+        {
+            evaluations[1] = others[1].clone();
+        }
+        assert!(matches!(query.intersect(response), Err(PsiError::Proof(1))));
+    }
+
+    #[test]
+    fn rejects_malformed_requests_and_responses() {
+        let rng = &mut rand::thread_rng();
+        let path = set(&["alice"]);
+        let server = PsiServer::load(&path, rng).unwrap();
+        let queries = [
+            PsiQuery::open(&path, rng).unwrap(),
+            PsiQuery::open(&path, rng).unwrap(),
+        ];
+        fs::remove_file(path).unwrap();
+
+        let garbage = PsiRequest(vec![vec![0; 96]]);
+        assert!(matches!(
+            server.evaluate(&garbage, rng),
+            PsiResponse::Rejected(_)
+        ));
+
+        let [missing, bad_key] = queries;
+        let response = server.evaluate(&PsiRequest(vec![]), rng);
+        assert!(matches!(
+            missing.intersect(response),
+            Err(PsiError::Malformed)
+        ));
+
+        let mut response = server.evaluate(&bad_key.request(), rng);
+        if let PsiResponse::Evaluated { public_key, .. } = &mut response {
+            public_key.pop();
+        }
+        assert!(matches!(
+            bad_key.intersect(response),
+            Err(PsiError::Malformed)
+        ));
+    }
+}
@@ -0,0 +1,540 @@
+//! A round of shared randomness between the members of a committee, with the
+//! publicly verifiable secret sharing of `bls_shamir::pvss` instead of the
+//! sealed shares and complaints of the DKG.
+//!
+//! Members are numbered from 1 in the order of their peer ids, like in the
+//! DKG, and shares are encrypted to the BLS keys of the roster. Every member
+//! deals a random secret and publishes the dealing on the session's topic,
+//! where anyone checks it on their own: an invalid dealing is ignored by
+//! everyone alike, with no complaint to agree on. A dealer that publishes
+//! two different dealings is left out.
+//!
+//! Once every member dealt, or the timeout passed, the dealings are closed
+//! and each member publishes the decryption of its share of each of them,
+//! so no dealer saw the others' secrets before its own was fixed. Any
+//! `threshold + 1` decryptions open a dealer's secret `s_j * G`, whether the
+//! dealer takes part or not, and the randomness of the round is the hash of
+//! the sum of the secrets.
+//!
+//! There is no ledger: a dealing that reaches some members before they
+//! close the dealings and others after leaves them with different outputs.
+//! Republishing until the end makes it unlikely, it doesn't rule it out.
+use crate::cli::PvssArgs;
+use crate::envelope::Payload;
+use crate::roster::Roster;
+use bls12_381::{G1Affine, G1Projective};
+use bls_shamir::pvss::{self, Dealing, DecryptedShare};
+use bls_shamir::secret::SecretKey;
+use group::Curve;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::PeerId;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long a node keeps republishing once it has the randomness, for the
+/// members that haven't.
+pub const LINGER: Duration = Duration::from_secs(10);
+
+const OUTPUT_TAG: &[u8] = b"zk-lab/pvss/randomness";
+
+/// The topic the dealings and decryptions of `session` are published on.
+pub fn topic(session: &str) -> Topic {
+    Topic::new(format!("zk-lab/pvss/{}", session))
+}
+
+/// The outcome of the round.
+#[derive(Debug)]
+pub struct Output {
+    /// The dealers whose secrets were added up.
+    pub dealers: Vec<u64>,
+    pub randomness: [u8; 32],
+}
+
+#[derive(Debug)]
+pub struct Round {
+    id: String,
+    threshold: usize,
+    /// Sorted, member `i` is dealt share `i + 1`.
+    members: Vec<PeerId>,
+    /// The BLS keys of the members, in the same order.
+    keys: Vec<G1Affine>,
+    index: u64,
+    /// Our dealing, republished until the round is over.
+    dealing: Option<Payload>,
+    dealings: BTreeMap<u64, Dealing>,
+    equivocated: BTreeSet<u64>,
+    /// Our decryptions once the dealings are closed, republished too.
+    decryptions: Option<Vec<Payload>>,
+    /// The valid decryptions of each dealing, by member.
+    shares: BTreeMap<u64, BTreeMap<u64, DecryptedShare>>,
+    secrets: BTreeMap<u64, G1Affine>,
+    timeout: Duration,
+    /// When the dealings close, then when every secret must be open.
+    deadline: Instant,
+    done_since: Option<Instant>,
+}
+
+impl Round {
+    /// Starts the round `id` between the members of the roster, closing the
+    /// dealings after `timeout` and giving up `timeout` later.
+    pub fn new(
+        id: String,
+        threshold: usize,
+        roster: &Roster,
+        local: &PeerId,
+        timeout: Duration,
+    ) -> Result<Self, PvssError> {
+        let mut members = roster.peer_ids().copied().collect::<Vec<_>>();
+        members.sort();
+        if threshold + 2 > members.len() {
+            return Err(PvssError::Threshold {
+                threshold,
+                members: members.len(),
+            });
+        }
+        let index = match members.iter().position(|member| member == local) {
+            Some(i) => i as u64 + 1,
+            None => return Err(PvssError::NotAMember),
+        };
+        let keys = members
+            .iter()
+            .map(|member| {
+                *roster
+                    .public_key(member)
+                    .expect("Members are in the roster")
+            })
+            .collect();
+
+        Ok(Round {
+            id,
+            threshold,
+            members,
+            keys,
+            index,
+            dealing: None,
+            dealings: BTreeMap::new(),
+            equivocated: BTreeSet::new(),
+            decryptions: None,
+            shares: BTreeMap::new(),
+            secrets: BTreeMap::new(),
+            timeout,
+            deadline: Instant::now() + timeout,
+            done_since: None,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    fn index_of(&self, peer_id: &PeerId) -> Option<u64> {
+        let i = self.members.iter().position(|member| member == peer_id)?;
+        Some(i as u64 + 1)
+    }
+
+    /// Deals our secret. Does nothing if we dealt already.
+    pub fn deal<R: RngCore + CryptoRng>(&mut self, rng: &mut R) {
+        if self.dealing.is_some() {
+            return;
+        }
+
+        let context = context(&self.id, self.index);
+        let dealing = pvss::deal(self.threshold, &self.keys, &context, rng);
+        self.dealing = Some(Payload::PvssDealing {
+            dealer: self.index,
+            commitments: compress(&dealing.commitments),
+            encrypted: compress(&dealing.encrypted),
+            proofs: dealing
+                .proofs
+                .iter()
+                .map(|proof| proof.to_bytes().to_vec())
+                .collect(),
+        });
+        self.dealings.insert(self.index, dealing);
+        info!("Dealt {} shares", self.members.len());
+    }
+
+    /// What we republish: our dealing, then our decryptions once the
+    /// dealings are closed.
+    pub fn payloads(&self) -> Vec<Payload> {
+        self.dealing
+            .iter()
+            .chain(self.decryptions.iter().flatten())
+            .cloned()
+            .collect()
+    }
+
+    /// Handles a dealing or decryption published on the session's topic,
+    /// returning what we have to publish in answer.
+    pub fn handle<R: RngCore + CryptoRng>(
+        &mut self,
+        sender: &PeerId,
+        payload: &Payload,
+        sk: &SecretKey,
+        rng: &mut R,
+    ) -> Vec<Payload> {
+        let sender = match self.index_of(sender) {
+            Some(index) => index,
+            None => {
+                warn!(
+                    "Ignoring a PVSS message from {:?} outside the committee",
+                    sender
+                );
+                return Vec::new();
+            }
+        };
+
+        match payload {
+            Payload::PvssDealing {
+                dealer,
+                commitments,
+                encrypted,
+                proofs,
+            } if *dealer == sender => {
+                self.handle_dealing(sender, commitments, encrypted, proofs);
+                if self.dealings.len() == self.members.len() {
+                    return self.close(sk, rng);
+                }
+                Vec::new()
+            }
+            Payload::PvssShare {
+                dealer,
+                index,
+                share,
+                proof,
+            } if *index == sender => {
+                self.handle_share(*dealer, sender, share, proof);
+                Vec::new()
+            }
+            Payload::PvssDealing { .. } | Payload::PvssShare { .. } => {
+                warn!(
+                    "Ignoring a PVSS message member {} sent in another's name",
+                    sender
+                );
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn handle_dealing(
+        &mut self,
+        dealer: u64,
+        commitments: &[Vec<u8>],
+        encrypted: &[Vec<u8>],
+        proofs: &[Vec<u8>],
+    ) {
+        if self.equivocated.contains(&dealer) {
+            return;
+        }
+        let proofs = proofs
+            .iter()
+            .map(|proof| sigma::Proof::from_bytes(proof))
+            .collect::<Option<Vec<_>>>();
+        let dealing = match (decompress(commitments), decompress(encrypted), proofs) {
+            (Some(commitments), Some(encrypted), Some(proofs)) => Dealing {
+                commitments,
+                encrypted,
+                proofs,
+            },
+            _ => {
+                warn!("Ignoring the malformed dealing of dealer {}", dealer);
+                return;
+            }
+        };
+
+        match self.dealings.get(&dealer) {
+            Some(known) if *known == dealing => return,
+            Some(_) if self.decryptions.is_none() => {
+                warn!("Leaving out dealer {}, it published two dealings", dealer);
+                self.dealings.remove(&dealer);
+                self.equivocated.insert(dealer);
+                return;
+            }
+            // The dealings we decrypted are the ones we stick to.
+            Some(_) => return,
+            None => {}
+        }
+        if self.decryptions.is_some() {
+            warn!(
+                "Ignoring the dealing of dealer {}, it came too late",
+                dealer
+            );
+            return;
+        }
+        let context = context(&self.id, dealer);
+        if !pvss::verify(self.threshold, &self.keys, &dealing, &context) {
+            warn!("Ignoring the invalid dealing of dealer {}", dealer);
+            return;
+        }
+        info!("Got the valid dealing of dealer {}", dealer);
+        self.dealings.insert(dealer, dealing);
+    }
+
+    fn handle_share(&mut self, dealer: u64, index: u64, share: &[u8], proof: &[u8]) {
+        let dealing = match self.dealings.get(&dealer) {
+            Some(dealing) => dealing,
+            // Republished, it comes again if we get the dealing.
+            None => return,
+        };
+        if self.secrets.contains_key(&dealer) {
+            return;
+        }
+        let share = match (decompress_point(share), sigma::Proof::from_bytes(proof)) {
+            (Some(point), Some(proof)) => DecryptedShare {
+                index,
+                point,
+                proof,
+            },
+            _ => {
+                warn!("Ignoring a malformed decryption from member {}", index);
+                return;
+            }
+        };
+        let key = &self.keys[index as usize - 1];
+        if !pvss::verify_share(key, dealing, &share, &context(&self.id, dealer)) {
+            warn!(
+                "Ignoring the invalid decryption of member {} for dealer {}",
+                index, dealer
+            );
+            return;
+        }
+        self.add_share(dealer, share);
+    }
+
+    fn add_share(&mut self, dealer: u64, share: DecryptedShare) {
+        let shares = self.shares.entry(dealer).or_default();
+        shares.insert(share.index, share);
+        if shares.len() > self.threshold {
+            let shares = shares.values().copied().collect::<Vec<_>>();
+            match pvss::reconstruct(&shares) {
+                Ok(secret) => {
+                    self.secrets.insert(dealer, secret);
+                    info!("Opened the secret of dealer {}", dealer);
+                }
+                Err(e) => warn!("Opening the secret of dealer {} failed: {}", dealer, e),
+            }
+        }
+    }
+
+    /// Closes the dealings once the deadline passed, our decryptions are
+    /// then among the payloads.
+    pub fn close_if_due<R: RngCore + CryptoRng>(&mut self, sk: &SecretKey, rng: &mut R) {
+        if Instant::now() >= self.deadline {
+            self.close(sk, rng);
+        }
+    }
+
+    fn close<R: RngCore + CryptoRng>(&mut self, sk: &SecretKey, rng: &mut R) -> Vec<Payload> {
+        if self.decryptions.is_some() {
+            return Vec::new();
+        }
+        info!(
+            "Closing with the dealings of {} dealers",
+            self.dealings.len()
+        );
+        let mut decryptions = Vec::new();
+        let mut shares = Vec::new();
+        for (dealer, dealing) in &self.dealings {
+            let context = context(&self.id, *dealer);
+            let share = pvss::decrypt(sk, self.index, dealing, &context, rng)
+                .expect("Valid dealings have a share for every member");
+            decryptions.push(Payload::PvssShare {
+                dealer: *dealer,
+                index: self.index,
+                share: share.point.to_compressed().to_vec(),
+                proof: share.proof.to_bytes().to_vec(),
+            });
+            shares.push((*dealer, share));
+        }
+        for (dealer, share) in shares {
+            self.add_share(dealer, share);
+        }
+        self.decryptions = Some(decryptions.clone());
+        self.deadline = Instant::now() + self.timeout;
+        decryptions
+    }
+
+    /// Whether the round is over for us: every secret we kept is open and we
+    /// kept republishing our decryptions for a while.
+    pub fn is_ready(&mut self) -> bool {
+        if self.decryptions.is_none() || self.secrets.len() < self.dealings.len() {
+            return false;
+        }
+        let now = Instant::now();
+        let since = *self.done_since.get_or_insert(now);
+        now.duration_since(since) >= LINGER
+    }
+
+    /// Fails once the secrets weren't all opened in time.
+    pub fn check_deadline(&self) -> Result<(), PvssError> {
+        if self.decryptions.is_none() || Instant::now() < self.deadline {
+            return Ok(());
+        }
+        let unopened = self
+            .dealings
+            .keys()
+            .copied()
+            .filter(|dealer| !self.secrets.contains_key(dealer))
+            .collect::<Vec<_>>();
+        if unopened.is_empty() {
+            return Ok(());
+        }
+        Err(PvssError::Unopened(unopened))
+    }
+
+    /// Hashes the sum of the secrets into the randomness of the round.
+    pub fn finish(self) -> Output {
+        let sum = self
+            .secrets
+            .values()
+            .map(G1Projective::from)
+            .sum::<G1Projective>();
+        let randomness = Sha256::new()
+            .chain(OUTPUT_TAG)
+            .chain(self.id.as_bytes())
+            .chain(sum.to_affine().to_compressed())
+            .finalize()
+            .into();
+        Output {
+            dealers: self.secrets.keys().copied().collect(),
+            randomness,
+        }
+    }
+}
+
+/// The round of `p2p pvss`, with the topic it runs on and the BLS key our
+/// shares are encrypted to.
+pub struct PvssTask {
+    round: Round,
+    topic: Topic,
+    key: SecretKey,
+}
+
+impl PvssTask {
+    /// Starts the round between the members of `roster` and deals our
+    /// secret.
+    pub fn start(
+        args: PvssArgs,
+        roster: &Roster,
+        local: &PeerId,
+        key: SecretKey,
+    ) -> Result<Self, PvssError> {
+        let mut round = Round::new(
+            args.session.clone(),
+            args.threshold as usize,
+            roster,
+            local,
+            args.timeout,
+        )?;
+        info!(
+            "Running PVSS round {} as member {} of {}",
+            round.id(),
+            round.index(),
+            roster.len()
+        );
+        round.deal(&mut rand::rngs::OsRng);
+        Ok(PvssTask {
+            round,
+            topic: topic(&args.session),
+            key,
+        })
+    }
+
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    pub fn is_ready(&mut self) -> bool {
+        self.round.is_ready()
+    }
+
+    /// Returns what we publish on every tick: our dealing, joined by our
+    /// decryptions once the dealings close. Republished until the end, for
+    /// the members that joined the topic after us.
+    pub fn tick(&mut self) -> Result<Vec<Payload>, PvssError> {
+        self.round.check_deadline()?;
+        self.round.close_if_due(&self.key, &mut rand::rngs::OsRng);
+        Ok(self.round.payloads())
+    }
+
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        self.round
+            .handle(sender, payload, &self.key, &mut rand::rngs::OsRng)
+    }
+
+    /// Prints the randomness of the round.
+    pub fn finish(self) {
+        let output = self.round.finish();
+        info!(
+            "PVSS round done with the secrets of dealers {:?}",
+            output.dealers
+        );
+        println!("{}", hex::encode(output.randomness));
+    }
+}
+
+/// What a dealer's proofs are bound to, so a dealing can't be replayed by
+/// another dealer or in another session.
+fn context(session: &str, dealer: u64) -> Vec<u8> {
+    let mut context = b"zk-lab/pvss/".to_vec();
+    context.extend_from_slice(session.as_bytes());
+    context.extend_from_slice(&dealer.to_be_bytes());
+    context
+}
+
+fn compress(points: &[G1Affine]) -> Vec<Vec<u8>> {
+    points
+        .iter()
+        .map(|point| point.to_compressed().to_vec())
+        .collect()
+}
+
+fn decompress(points: &[Vec<u8>]) -> Option<Vec<G1Affine>> {
+    points.iter().map(|bytes| decompress_point(bytes)).collect()
+}
+
+fn decompress_point(bytes: &[u8]) -> Option<G1Affine> {
+    let bytes: [u8; 48] = bytes.try_into().ok()?;
+    Option::from(G1Affine::from_compressed(&bytes))
+}
+
+#[derive(Debug)]
+pub enum PvssError {
+    /// This node isn't in the committee running the round.
+    NotAMember,
+    /// There are too few members to check the degree of the dealings.
+    Threshold { threshold: usize, members: usize },
+    /// The secrets of these dealers weren't opened in time.
+    Unopened(Vec<u64>),
+}
+
+impl fmt::Display for PvssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PvssError::NotAMember => write!(f, "this node isn't a member of the committee"),
+            PvssError::Threshold { threshold, members } => write!(
+                f,
+                "a threshold of {} needs at least {} members, the committee has {}",
+                threshold,
+                threshold + 2,
+                members
+            ),
+            PvssError::Unopened(dealers) => write!(
+                f,
+                "the secrets of dealers {:?} weren't opened in time",
+                dealers
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PvssError {}
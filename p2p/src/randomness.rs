@@ -0,0 +1,241 @@
+//! The randomness beacon of a group that came out of a DKG, run over a topic
+//! of its own with the `beacon` crate.
+//!
+//! Members publish their partial signature of each round once it is due, and
+//! whoever gathers `threshold + 1` of them first publishes the round's
+//! beacon. Every node, followers included, verifies the beacons and stores
+//! them. A node that gets a beacon it can't chain yet asks for the rounds it
+//! is missing, and the nodes that have them publish them again.
+use crate::beacon_api::BeaconApi;
+use crate::cli::BeaconArgs;
+use crate::envelope::Payload;
+use crate::group_key::{self, GroupKey};
+use beacon::{Beacon, Chain, Node, Schedule, Scheme, Step};
+use bls12_381::G2Affine;
+use bls_shamir::keystore::Keystore;
+use bls_shamir::threshold::PartialSignature;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::PeerId;
+use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// How many beacons are published again at most for one request.
+const MAX_REPLAY: u64 = 16;
+
+/// The topic the beacon of the group of `session` is run on.
+pub fn topic(session: &str) -> Topic {
+    Topic::new(format!("zk-lab/beacon/{}", session))
+}
+
+/// A follower of the beacon of `group`, to be given a share to take part.
+pub fn node(group: &GroupKey, chain: Chain) -> Node {
+    let public_shares = group
+        .members
+        .iter()
+        .map(|member| (member.index, member.public_share));
+    Node::new(chain, group.threshold, public_shares)
+}
+
+/// The beacon of `p2p beacon`: the node, the group it runs for and the
+/// schedule of its rounds, with the HTTP API if it is served.
+pub struct BeaconTask {
+    node: Node,
+    group: GroupKey,
+    topic: Topic,
+    schedule: Schedule,
+    api: Option<BeaconApi>,
+}
+
+impl BeaconTask {
+    /// Loads the chain of the group and starts serving it, taking part in
+    /// the rounds if we were given a share.
+    pub async fn start(args: BeaconArgs, local: &PeerId) -> Result<Self, Box<dyn Error>> {
+        let group_path = match (&args.share, &args.follow) {
+            (_, Some(path)) => path.clone(),
+            (Some(share), None) => group_key::path_for(share),
+            (None, None) => unreachable!("--share or --follow is required"),
+        };
+        let group =
+            GroupKey::load(&group_path).map_err(|e| format!("{}: {}", group_path.display(), e))?;
+        let chain_path = args.chain(&group.session);
+        let scheme = if args.unchained {
+            Scheme::Unchained
+        } else {
+            Scheme::Chained
+        };
+        let chain = Chain::open(&chain_path, group.public_key, scheme)
+            .map_err(|e| format!("{}: {}", chain_path.display(), e))?;
+        let mut node = node(&group, chain);
+        if let (Some(share), Some(password)) = (&args.share, &args.password) {
+            let share = Keystore::load(share)?.decrypt(password)?;
+            let index = group
+                .index_of(local)
+                .ok_or("This node isn't a member of the group")?;
+            node = node.with_share(index, share)?;
+            info!(
+                "Running the beacon of DKG {} as member {}",
+                group.session, index
+            );
+        } else {
+            info!("Following the beacon of DKG {}", group.session);
+        }
+        info!(
+            "Loaded {} beacons from {}",
+            node.chain().next_round() - 1,
+            chain_path.display()
+        );
+        let schedule = Schedule::new(UNIX_EPOCH + Duration::from_secs(args.genesis), args.period);
+        let api = match args.http {
+            Some(address) => {
+                let api = BeaconApi::new(&group, &schedule, node.chain());
+                api.serve(address).await?;
+                Some(api)
+            }
+            None => None,
+        };
+        Ok(BeaconTask {
+            node,
+            topic: topic(&group.session),
+            group,
+            schedule,
+            api,
+        })
+    }
+
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Signs the round that is due, if we haven't yet, returning what to
+    /// publish.
+    pub fn tick(&mut self) -> Vec<Payload> {
+        let step = self.node.tick(self.schedule.round_at(SystemTime::now()));
+        payloads(step, self.api.as_ref())
+    }
+
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        handle(
+            &mut self.node,
+            &self.group,
+            self.api.as_ref(),
+            sender,
+            payload,
+        )
+    }
+}
+
+/// What to publish for a step of the node, logging the beacons it stored
+/// and handing them to the HTTP API, if there is one.
+fn payloads(step: Step, api: Option<&BeaconApi>) -> Vec<Payload> {
+    for beacon in &step.stored {
+        if let Some(api) = api {
+            api.push(*beacon);
+        }
+        info!(
+            "Beacon round {}: {}",
+            beacon.round,
+            hex::encode(beacon.randomness())
+        );
+    }
+    let partial = step.partial.map(|(round, partial)| Payload::BeaconPartial {
+        round,
+        index: partial.index,
+        signature: partial.point.to_compressed().to_vec(),
+        proof: partial.proof.to_bytes().to_vec(),
+    });
+    partial
+        .into_iter()
+        .chain(step.aggregated.iter().map(beacon_payload))
+        .collect()
+}
+
+/// Handles a partial signature, beacon or request published on the beacon's
+/// topic, returning what we have to publish in answer.
+fn handle(
+    node: &mut Node,
+    group: &GroupKey,
+    api: Option<&BeaconApi>,
+    sender: &PeerId,
+    payload: &Payload,
+) -> Vec<Payload> {
+    match payload {
+        Payload::BeaconPartial {
+            round,
+            index,
+            signature,
+            proof,
+        } => {
+            if group.index_of(sender) != Some(*index) {
+                warn!(
+                    "Ignoring a partial signature from {:?} in member {}'s name",
+                    sender, index
+                );
+                return Vec::new();
+            }
+            let partial = match (decompress(signature), sigma::Proof::from_bytes(proof)) {
+                (Some(point), Some(proof)) => PartialSignature {
+                    index: *index,
+                    point,
+                    proof,
+                },
+                _ => {
+                    warn!("Member {} sent a malformed partial signature", index);
+                    return Vec::new();
+                }
+            };
+            match node.handle_partial(*round, partial) {
+                Ok(step) => payloads(step, api),
+                Err(e) => {
+                    warn!("Ignoring a partial signature of member {}: {}", index, e);
+                    Vec::new()
+                }
+            }
+        }
+        Payload::Beacon { round, signature } => {
+            let signature = match decompress(signature) {
+                Some(signature) => signature,
+                None => {
+                    warn!("Got a malformed beacon for round {}", round);
+                    return Vec::new();
+                }
+            };
+            let beacon = Beacon {
+                round: *round,
+                signature,
+            };
+            let mut replies = match node.handle_beacon(beacon) {
+                Ok(step) => payloads(step, api),
+                Err(e) => {
+                    warn!("Ignoring a beacon from {:?}: {}", sender, e);
+                    return Vec::new();
+                }
+            };
+            if let Some((from, to)) = node.missing() {
+                info!("Asking for the beacons of rounds {} to {}", from, to);
+                replies.push(Payload::BeaconRequest { from, to });
+            }
+            replies
+        }
+        Payload::BeaconRequest { from, to } => {
+            let to = (*to).min(from.saturating_add(MAX_REPLAY - 1));
+            (*from..=to)
+                .map_while(|round| node.chain().get(round))
+                .map(beacon_payload)
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn beacon_payload(beacon: &Beacon) -> Payload {
+    Payload::Beacon {
+        round: beacon.round,
+        signature: beacon.signature.to_compressed().to_vec(),
+    }
+}
+
+fn decompress(bytes: &[u8]) -> Option<G2Affine> {
+    let bytes: [u8; 96] = bytes.try_into().ok()?;
+    Option::from(G2Affine::from_compressed(&bytes))
+}
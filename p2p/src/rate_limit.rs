@@ -0,0 +1,150 @@
+//! Limits how many messages each peer may send us, over gossipsub and the
+//! request-response protocols together, so a chatty or malicious peer can't
+//! keep the handlers busy checking signatures and opening whispers.
+//!
+//! Every peer gets a token bucket: it holds up to the burst size, refills at
+//! the configured rate and each message takes a token. Messages arriving to an
+//! empty bucket are dropped.
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Tokens added per second.
+    rate: f64,
+    burst: f64,
+    buckets: HashMap<PeerId, Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    /// Whether the last message was dropped.
+    exceeded: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Allowed,
+    /// The peer just went over its rate, the message is dropped.
+    Exceeded,
+    /// The peer is still over its rate, the message is dropped.
+    StillExceeded,
+}
+
+impl RateLimiter {
+    /// Lets each peer send `rate` messages per second, and up to `burst` at
+    /// once.
+    pub fn new(rate: u32, burst: u32) -> Self {
+        RateLimiter {
+            rate: rate.into(),
+            burst: burst.into(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token for a message from `peer_id`.
+    pub fn check(&mut self, peer_id: PeerId) -> Limit {
+        let now = Instant::now();
+        let bucket = self.buckets.entry(peer_id).or_insert(Bucket {
+            tokens: self.burst,
+            refilled: now,
+            exceeded: false,
+        });
+
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.refilled = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.exceeded = false;
+            Limit::Allowed
+        } else if bucket.exceeded {
+            Limit::StillExceeded
+        } else {
+            bucket.exceeded = true;
+            Limit::Exceeded
+        }
+    }
+
+    /// Forgets the peers whose bucket has refilled since their last message,
+    /// they are back where they started. Reconnecting doesn't refill a bucket.
+    pub fn forget_idle(&mut self) {
+        let now = Instant::now();
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+            bucket.tokens + elapsed * rate < burst
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn allows_a_burst_then_drops() {
+        let mut limiter = RateLimiter::new(1, 3);
+        let peer_id = PeerId::random();
+        for _ in 0..3 {
+            assert_eq!(limiter.check(peer_id), Limit::Allowed);
+        }
+        assert_eq!(limiter.check(peer_id), Limit::Exceeded);
+        assert_eq!(limiter.check(peer_id), Limit::StillExceeded);
+        assert_eq!(limiter.check(peer_id), Limit::StillExceeded);
+
+        // Other peers have their own bucket.
+        assert_eq!(limiter.check(PeerId::random()), Limit::Allowed);
+    }
+
+    #[test]
+    fn refills_at_the_rate() {
+        let mut limiter = RateLimiter::new(50, 1);
+        let peer_id = PeerId::random();
+        assert_eq!(limiter.check(peer_id), Limit::Allowed);
+        assert_eq!(limiter.check(peer_id), Limit::Exceeded);
+
+        // One token every 20ms.
+        sleep(Duration::from_millis(30));
+        assert_eq!(limiter.check(peer_id), Limit::Allowed);
+        assert_eq!(limiter.check(peer_id), Limit::Exceeded);
+    }
+
+    #[test]
+    fn refill_is_capped_at_the_burst() {
+        let mut limiter = RateLimiter::new(1000, 2);
+        let peer_id = PeerId::random();
+        assert_eq!(limiter.check(peer_id), Limit::Allowed);
+
+        // Worth 50 tokens, but the bucket only holds two.
+        sleep(Duration::from_millis(50));
+        assert_eq!(limiter.check(peer_id), Limit::Allowed);
+        assert_eq!(limiter.check(peer_id), Limit::Allowed);
+        assert_eq!(limiter.check(peer_id), Limit::Exceeded);
+    }
+
+    #[test]
+    fn forgets_only_refilled_peers() {
+        let mut limiter = RateLimiter::new(1, 2);
+        let (idle, busy) = (PeerId::random(), PeerId::random());
+        assert_eq!(limiter.check(busy), Limit::Allowed);
+        limiter.buckets.insert(
+            idle,
+            Bucket {
+                tokens: 2.0,
+                refilled: Instant::now(),
+                exceeded: false,
+            },
+        );
+
+        limiter.forget_idle();
+        assert!(!limiter.buckets.contains_key(&idle));
+        assert!(limiter.buckets.contains_key(&busy));
+    }
+}
@@ -0,0 +1,172 @@
+//! Reconnects to the peers the node depends on, the bootstrap nodes and the
+//! rest of the committee, when their connection drops.
+//!
+//! Attempts are spaced out exponentially from [`BASE_DELAY`] up to
+//! [`MAX_DELAY`], with each delay drawn from its upper half so that a whole
+//! committee losing a peer doesn't redial it in lockstep. A peer that is
+//! still unreachable after the configured number of attempts is given up on.
+use libp2p::PeerId;
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub const BASE_DELAY: Duration = Duration::from_secs(1);
+
+pub const MAX_DELAY: Duration = Duration::from_secs(300);
+
+#[derive(Debug)]
+pub struct Redialer {
+    max_attempts: u32,
+    peers: HashMap<PeerId, Backoff>,
+}
+
+#[derive(Debug)]
+struct Backoff {
+    /// Attempts made since the peer was last connected.
+    attempts: u32,
+    /// When to dial next, `None` while a dial is in flight.
+    next: Option<Instant>,
+}
+
+/// What became of a peer after a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retry {
+    After(Duration),
+    GaveUp { attempts: u32 },
+}
+
+impl Redialer {
+    pub fn new(max_attempts: u32) -> Self {
+        Redialer {
+            max_attempts,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Starts redialing `peer_id`, which just lost its last connection.
+    pub fn disconnected(&mut self, peer_id: PeerId) -> Duration {
+        let delay = delay(0);
+        let next = Some(Instant::now() + delay);
+        self.peers.insert(peer_id, Backoff { attempts: 0, next });
+        delay
+    }
+
+    /// Stops redialing `peer_id`.
+    pub fn connected(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    /// Returns the peers whose next attempt is due, which are then considered
+    /// in flight until [`Redialer::failed`] or [`Redialer::connected`].
+    pub fn due(&mut self) -> Vec<PeerId> {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        for (peer_id, backoff) in &mut self.peers {
+            if backoff.next.is_some_and(|next| next <= now) {
+                backoff.attempts += 1;
+                backoff.next = None;
+                due.push(*peer_id);
+            }
+        }
+        due
+    }
+
+    /// Schedules the next attempt after one failed, `None` if we weren't
+    /// redialing `peer_id`.
+    pub fn failed(&mut self, peer_id: &PeerId) -> Option<Retry> {
+        let backoff = self.peers.get_mut(peer_id)?;
+        // Other dials of the peer can fail while we wait to retry.
+        if backoff.next.is_some() {
+            return None;
+        }
+
+        if backoff.attempts >= self.max_attempts {
+            let attempts = backoff.attempts;
+            self.peers.remove(peer_id);
+            return Some(Retry::GaveUp { attempts });
+        }
+
+        let delay = delay(backoff.attempts);
+        backoff.next = Some(Instant::now() + delay);
+        Some(Retry::After(delay))
+    }
+}
+
+/// A random delay in the upper half of `BASE_DELAY * 2^attempts`, capped at
+/// [`MAX_DELAY`].
+fn delay(attempts: u32) -> Duration {
+    let ceiling = BASE_DELAY
+        .checked_mul(1 << attempts.min(31))
+        .map_or(MAX_DELAY, |delay| delay.min(MAX_DELAY));
+    rand::thread_rng().gen_range(ceiling / 2..=ceiling)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes the next attempt of `peer_id` due right away.
+    fn make_due(redialer: &mut Redialer, peer_id: &PeerId) {
+        redialer.peers.get_mut(peer_id).unwrap().next = Some(Instant::now());
+    }
+
+    #[test]
+    fn delays_double_up_to_the_cap() {
+        for attempts in 0..12 {
+            let ceiling = (BASE_DELAY * 2u32.pow(attempts)).min(MAX_DELAY);
+            for _ in 0..16 {
+                let delay = delay(attempts);
+                assert!(ceiling / 2 <= delay && delay <= ceiling);
+            }
+        }
+        for attempts in [9, 31, 32, 1000, u32::MAX] {
+            assert!(delay(attempts) <= MAX_DELAY);
+            assert!(delay(attempts) >= MAX_DELAY / 2);
+        }
+    }
+
+    #[test]
+    fn retries_until_the_limit() {
+        let mut redialer = Redialer::new(3);
+        let peer_id = PeerId::random();
+        let first = redialer.disconnected(peer_id);
+        assert!(BASE_DELAY / 2 <= first && first <= BASE_DELAY);
+        assert!(redialer.due().is_empty());
+
+        for attempt in 1..3 {
+            make_due(&mut redialer, &peer_id);
+            assert_eq!(redialer.due(), vec![peer_id]);
+            // In flight, not due again until it fails.
+            assert!(redialer.due().is_empty());
+
+            let ceiling = BASE_DELAY * 2u32.pow(attempt);
+            match redialer.failed(&peer_id) {
+                Some(Retry::After(delay)) => assert!(ceiling / 2 <= delay && delay <= ceiling),
+                retry => panic!("Unexpected {:?}", retry),
+            }
+            // Failures of other dials while waiting are ignored.
+            assert_eq!(redialer.failed(&peer_id), None);
+        }
+
+        make_due(&mut redialer, &peer_id);
+        assert_eq!(redialer.due(), vec![peer_id]);
+        assert_eq!(
+            redialer.failed(&peer_id),
+            Some(Retry::GaveUp { attempts: 3 })
+        );
+        assert_eq!(redialer.failed(&peer_id), None);
+    }
+
+    #[test]
+    fn connecting_stops_redialing() {
+        let mut redialer = Redialer::new(3);
+        let peer_id = PeerId::random();
+        redialer.disconnected(peer_id);
+        make_due(&mut redialer, &peer_id);
+        assert_eq!(redialer.due(), vec![peer_id]);
+
+        redialer.connected(&peer_id);
+        assert_eq!(redialer.failed(&peer_id), None);
+        assert!(redialer.peers.is_empty());
+    }
+}
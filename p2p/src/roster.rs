@@ -0,0 +1,67 @@
+//! The committee roster, mapping each member's peer id to the BLS public key
+//! it signs its envelopes with.
+//!
+//! The file lists one member per line as `<peer id> <public key>`, where the
+//! public key is the hex of a compressed G1 point. Blank lines and lines
+//! starting with `#` are ignored.
+use bls12_381::G1Affine;
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct Roster {
+    members: HashMap<PeerId, G1Affine>,
+}
+
+impl Roster {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut members = HashMap::new();
+        for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (peer_id, public_key) = parse_member(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid roster entry on line {}", i + 1),
+                )
+            })?;
+            members.insert(peer_id, public_key);
+        }
+
+        Ok(Roster { members })
+    }
+
+    pub fn public_key(&self, peer_id: &PeerId) -> Option<&G1Affine> {
+        self.members.get(peer_id)
+    }
+
+    pub fn contains(&self, peer_id: &PeerId) -> bool {
+        self.members.contains_key(peer_id)
+    }
+
+    pub fn peer_ids(&self) -> impl Iterator<Item = &PeerId> {
+        self.members.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+}
+
+fn parse_member(line: &str) -> Option<(PeerId, G1Affine)> {
+    let mut fields = line.split_whitespace();
+    let peer_id = fields.next()?.parse().ok()?;
+    let bytes: [u8; 48] = hex::decode(fields.next()?).ok()?.try_into().ok()?;
+    let public_key = Option::from(G1Affine::from_compressed(&bytes))?;
+    if fields.next().is_some() {
+        return None;
+    }
+
+    Some((peer_id, public_key))
+}
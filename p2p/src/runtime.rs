@@ -0,0 +1,130 @@
+//! The async runtime the node runs on: tokio by default, or async-std with
+//! the `rt-async-std` feature. Build with
+//! `--no-default-features --features rt-async-std,mdns,stdin` to leave tokio
+//! out.
+//!
+//! Everything else only needs the `futures` traits, so this is all that
+//! differs between the two. The build script turns down a build with
+//! neither.
+#[cfg(not(feature = "rt-tokio"))]
+pub use self::with_async_std::*;
+#[cfg(feature = "rt-tokio")]
+pub use self::with_tokio::*;
+
+/// Runs `future`, failing with `TimedOut` if it takes longer than `duration`.
+pub async fn timeout<T>(
+    duration: std::time::Duration,
+    future: impl futures::Future<Output = std::io::Result<T>>,
+) -> std::io::Result<T> {
+    use futures::future::{self, Either};
+    futures::pin_mut!(future);
+    let sleep = sleep(duration);
+    futures::pin_mut!(sleep);
+    match future::select(future, sleep).await {
+        Either::Left((output, _)) => output,
+        Either::Right(_) => Err(std::io::ErrorKind::TimedOut.into()),
+    }
+}
+
+/// Without the `stdin` feature no line ever comes in.
+#[cfg(not(feature = "stdin"))]
+pub fn stdin_lines() -> impl futures::Stream<Item = std::io::Result<String>> {
+    futures::stream::pending()
+}
+
+#[cfg(not(feature = "rt-tokio"))]
+mod with_async_std {
+    use async_std::io;
+    use async_std::net::TcpStream;
+    use futures::{AsyncRead, AsyncWrite, Future};
+    use libp2p::{dns, swarm};
+    use std::time::Duration;
+
+    pub use async_std::net::TcpListener;
+    pub use libp2p::quic::async_std::Transport as Quic;
+    pub use libp2p::tcp::async_io::Transport as Tcp;
+
+    #[cfg(feature = "mdns")]
+    pub type Mdns = libp2p::mdns::async_io::Behaviour;
+
+    pub async fn dns<T>(transport: T) -> io::Result<dns::async_std::Transport<T>> {
+        dns::async_std::Transport::system(transport).await
+    }
+
+    pub fn swarm_config() -> swarm::Config {
+        swarm::Config::with_async_std_executor()
+    }
+
+    pub async fn sleep(duration: Duration) {
+        async_std::task::sleep(duration).await
+    }
+
+    pub fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+        async_std::task::spawn(future);
+    }
+
+    #[cfg(feature = "stdin")]
+    pub fn stdin_lines() -> impl futures::Stream<Item = io::Result<String>> {
+        use async_std::io::{prelude::BufReadExt, BufReader};
+        BufReader::new(io::stdin()).lines()
+    }
+
+    pub fn compat(stream: TcpStream) -> impl AsyncRead + AsyncWrite + Unpin {
+        stream
+    }
+}
+
+#[cfg(feature = "rt-tokio")]
+mod with_tokio {
+    use futures::{AsyncRead, AsyncWrite, Future};
+    use libp2p::{dns, swarm};
+    use std::io;
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    pub use libp2p::quic::tokio::Transport as Quic;
+    pub use libp2p::tcp::tokio::Transport as Tcp;
+    pub use tokio::net::TcpListener;
+
+    #[cfg(feature = "mdns")]
+    pub type Mdns = libp2p::mdns::tokio::Behaviour;
+
+    pub async fn dns<T>(transport: T) -> io::Result<dns::tokio::Transport<T>> {
+        dns::tokio::Transport::system(transport)
+    }
+
+    pub fn swarm_config() -> swarm::Config {
+        swarm::Config::with_tokio_executor()
+    }
+
+    pub async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    pub fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+        tokio::spawn(future);
+    }
+
+    /// Read on a thread of its own: the runtime waits for the blocking read
+    /// of `tokio::io::stdin` when it shuts down, which lasts until the next
+    /// line comes in.
+    #[cfg(feature = "stdin")]
+    pub fn stdin_lines() -> impl futures::Stream<Item = io::Result<String>> {
+        use futures::channel::mpsc;
+        use std::io::BufRead;
+        let (sender, receiver) = mpsc::unbounded();
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                if sender.unbounded_send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
+    pub fn compat(stream: TcpStream) -> impl AsyncRead + AsyncWrite + Unpin {
+        stream.compat()
+    }
+}
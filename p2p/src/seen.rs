@@ -0,0 +1,123 @@
+//! Remembers the envelopes already handled, by sender and sequence number,
+//! so a message that loops back or is replayed isn't handled twice.
+//!
+//! Gossipsub only drops duplicates for a minute. Here envelopes are kept for
+//! [`TTL`], and envelopes older than that are refused outright, so a replay
+//! can't outlive its entry.
+use crate::envelope::{unix_millis, Envelope};
+use libp2p::PeerId;
+use lru::LruCache;
+use std::time::Duration;
+
+pub const CAPACITY: usize = 16 * 1024;
+
+pub const TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seen {
+    New,
+    Duplicate,
+    /// Older than the cache remembers.
+    Stale,
+}
+
+pub struct SeenCache {
+    ttl: Duration,
+    /// When each envelope was first seen, in milliseconds since the epoch.
+    entries: LruCache<(PeerId, u64), u64>,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        SeenCache {
+            ttl,
+            entries: LruCache::new(capacity),
+        }
+    }
+
+    /// Checks whether `envelope` was handled before, marking it as handled.
+    pub fn check(&mut self, envelope: &Envelope) -> Seen {
+        let now = unix_millis();
+        let ttl = self.ttl.as_millis() as u64;
+        if envelope.timestamp.saturating_add(ttl) < now {
+            return Seen::Stale;
+        }
+
+        let key = (envelope.sender, envelope.seq);
+        match self.entries.get(&key) {
+            Some(seen) if seen.saturating_add(ttl) >= now => Seen::Duplicate,
+            _ => {
+                self.entries.put(key, now);
+                Seen::New
+            }
+        }
+    }
+}
+
+impl Default for SeenCache {
+    fn default() -> Self {
+        SeenCache::new(CAPACITY, TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::Payload;
+
+    fn envelope(sender: PeerId, seq: u64) -> Envelope {
+        Envelope::new(sender, seq, Payload::Chat("Hello world".to_string()))
+    }
+
+    #[test]
+    fn duplicates_are_caught() {
+        let mut seen = SeenCache::default();
+        let sender = PeerId::random();
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::New);
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::Duplicate);
+        assert_eq!(seen.check(&envelope(sender, 2)), Seen::New);
+        assert_eq!(seen.check(&envelope(PeerId::random(), 1)), Seen::New);
+    }
+
+    #[test]
+    fn least_recently_seen_is_evicted() {
+        let mut seen = SeenCache::new(2, TTL);
+        let sender = PeerId::random();
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::New);
+        assert_eq!(seen.check(&envelope(sender, 2)), Seen::New);
+        // Touching 1 leaves 2 as the oldest entry.
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::Duplicate);
+        assert_eq!(seen.check(&envelope(sender, 3)), Seen::New);
+
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::Duplicate);
+        assert_eq!(seen.check(&envelope(sender, 2)), Seen::New);
+    }
+
+    #[test]
+    fn old_envelopes_are_stale() {
+        let mut seen = SeenCache::default();
+        let mut old = envelope(PeerId::random(), 1);
+        old.timestamp -= TTL.as_millis() as u64 + 1000;
+        assert_eq!(seen.check(&old), Seen::Stale);
+        // Stale envelopes aren't remembered either.
+        assert!(seen.entries.is_empty());
+
+        let mut recent = envelope(PeerId::random(), 1);
+        recent.timestamp -= TTL.as_millis() as u64 - 1000;
+        assert_eq!(seen.check(&recent), Seen::New);
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let mut seen = SeenCache::new(CAPACITY, Duration::from_secs(60));
+        let sender = PeerId::random();
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::New);
+
+        // The entry outlives the TTL while the envelope doesn't, as with a
+        // sender whose clock runs ahead.
+        let entry = seen.entries.get_mut(&(sender, 1)).unwrap();
+        *entry -= 61_000;
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::New);
+        assert_eq!(seen.check(&envelope(sender, 1)), Seen::Duplicate);
+    }
+}
@@ -0,0 +1,294 @@
+//! A powers-of-tau ceremony between the members of a committee, producing
+//! the SRS of the `kzg` crate without anyone knowing its τ.
+//!
+//! Members are numbered from 1 in the order of their peer ids, like in the
+//! DKG, and take turns in that order: member `i` takes the SRS after turn
+//! `i - 1`, the initial one for member 1, multiplies its τ by a secret of
+//! its own and publishes the result with the proof of the update on the
+//! session's topic. Everyone checks each update against the SRS before it,
+//! so the final SRS carries every member's contribution and τ stays secret
+//! as long as one of them forgot its secret.
+//!
+//! Each member republishes its update until the ceremony is over, for the
+//! members that joined the topic after it. Updates that arrive before the
+//! ones they follow are kept until they can be checked. There is no skipping
+//! a member: the ceremony fails if a turn isn't taken within the timeout.
+use crate::cli::SetupArgs;
+use crate::envelope::Payload;
+use crate::roster::Roster;
+use kzg::ceremony::UpdateProof;
+use kzg::Srs;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::PeerId;
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long a node keeps republishing its update once it has the final SRS,
+/// for the members that haven't.
+pub const LINGER: Duration = Duration::from_secs(10);
+
+/// The topic the updates of `session` are published on.
+pub fn topic(session: &str) -> Topic {
+    Topic::new(format!("zk-lab/setup/{}", session))
+}
+
+#[derive(Debug)]
+pub struct Ceremony {
+    id: String,
+    /// Sorted, member `i` takes turn `i + 1`.
+    members: Vec<PeerId>,
+    index: u64,
+    /// The SRS after `turn` updates.
+    srs: Srs,
+    turn: u64,
+    /// Our update, once we made it.
+    update: Option<Payload>,
+    /// Updates that arrived before the one they follow, by turn.
+    early: BTreeMap<u64, (Vec<u8>, Vec<u8>)>,
+    timeout: Duration,
+    /// When the member whose turn it is has to have published its update.
+    deadline: Instant,
+    done_since: Option<Instant>,
+}
+
+impl Ceremony {
+    /// Starts the ceremony `id` between `members` for an SRS of up to
+    /// `max_degree`, giving every member `timeout` to take its turn.
+    pub fn new(
+        id: String,
+        max_degree: usize,
+        mut members: Vec<PeerId>,
+        local: &PeerId,
+        timeout: Duration,
+    ) -> Result<Self, SetupError> {
+        members.sort();
+        members.dedup();
+        let index = match members.iter().position(|member| member == local) {
+            Some(i) => i as u64 + 1,
+            None => return Err(SetupError::NotAMember),
+        };
+
+        Ok(Ceremony {
+            id,
+            members,
+            index,
+            srs: Srs::initial(max_degree),
+            turn: 0,
+            update: None,
+            early: BTreeMap::new(),
+            timeout,
+            deadline: Instant::now() + timeout,
+            done_since: None,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Takes our turn if it has come, returning the update to publish.
+    pub fn contribute<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Option<Payload> {
+        if self.update.is_some() || self.turn + 1 != self.index {
+            return None;
+        }
+
+        let (srs, proof) = self.srs.contribute(rng);
+        let update = Payload::SrsUpdate {
+            turn: self.index,
+            srs: srs.to_bytes(),
+            proof: proof.to_bytes().to_vec(),
+        };
+        info!("Contributed to the SRS, turn {}", self.index);
+        self.advance(srs);
+        self.update = Some(update.clone());
+        Some(update)
+    }
+
+    /// Our update, republished until the ceremony is over.
+    pub fn update(&self) -> Option<Payload> {
+        self.update.clone()
+    }
+
+    /// Handles an update published on the session's topic, returning ours
+    /// if it is now our turn.
+    pub fn handle<R: RngCore + CryptoRng>(
+        &mut self,
+        sender: &PeerId,
+        payload: &Payload,
+        rng: &mut R,
+    ) -> Option<Payload> {
+        let (turn, srs, proof) = match payload {
+            Payload::SrsUpdate { turn, srs, proof } => (*turn, srs, proof),
+            _ => return None,
+        };
+        if self.members.get((turn as usize).wrapping_sub(1)) != Some(sender) {
+            warn!(
+                "Ignoring an update of turn {} from {:?}, not the member of that turn",
+                turn, sender
+            );
+            return None;
+        }
+        if turn <= self.turn {
+            return None;
+        }
+        self.early.insert(turn, (srs.clone(), proof.clone()));
+
+        while let Some((srs, proof)) = self.early.remove(&(self.turn + 1)) {
+            let turn = self.turn + 1;
+            let next = Srs::from_bytes(&srs).map_err(|e| e.to_string());
+            let proof =
+                UpdateProof::from_bytes(&proof).ok_or_else(|| "malformed proof".to_string());
+            let checked = next.and_then(|next| {
+                let proof = proof?;
+                self.srs
+                    .verify_update(&next, &proof, rng)
+                    .map(|()| next)
+                    .map_err(|e| e.to_string())
+            });
+            match checked {
+                Ok(next) => {
+                    info!("Checked the update of turn {}", turn);
+                    self.advance(next);
+                }
+                // Left to the deadline, in case a valid one follows.
+                Err(reason) => warn!("Invalid update of turn {}: {}", turn, reason),
+            }
+        }
+        self.contribute(rng)
+    }
+
+    fn advance(&mut self, srs: Srs) {
+        self.srs = srs;
+        self.turn += 1;
+        self.deadline = Instant::now() + self.timeout;
+    }
+
+    /// Whether the ceremony is over for us: every member took its turn and
+    /// we kept republishing our update for a while.
+    pub fn is_ready(&mut self) -> bool {
+        if self.turn < self.members.len() as u64 {
+            return false;
+        }
+        let now = Instant::now();
+        let since = *self.done_since.get_or_insert(now);
+        now.duration_since(since) >= LINGER
+    }
+
+    /// Fails once the member whose turn it is has taken too long.
+    pub fn check_deadline(&self) -> Result<(), SetupError> {
+        let turn = self.turn + 1;
+        match self.members.get(self.turn as usize) {
+            Some(member) if Instant::now() >= self.deadline => Err(SetupError::Timeout {
+                turn,
+                member: *member,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The final SRS.
+    pub fn finish(self) -> Srs {
+        self.srs
+    }
+}
+
+/// The ceremony of `p2p setup`, with the topic it runs on and where the SRS
+/// goes once it is over.
+pub struct SetupTask {
+    ceremony: Ceremony,
+    topic: Topic,
+    args: SetupArgs,
+}
+
+impl SetupTask {
+    pub fn start(args: SetupArgs, roster: &Roster, local: &PeerId) -> Result<Self, SetupError> {
+        let ceremony = Ceremony::new(
+            args.session.clone(),
+            args.degree as usize,
+            roster.peer_ids().copied().collect(),
+            local,
+            args.timeout,
+        )?;
+        info!(
+            "Running setup {} as member {} of {}",
+            ceremony.id(),
+            ceremony.index(),
+            roster.len()
+        );
+        Ok(SetupTask {
+            ceremony,
+            topic: topic(&args.session),
+            args,
+        })
+    }
+
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    pub fn is_ready(&mut self) -> bool {
+        self.ceremony.is_ready()
+    }
+
+    /// Takes our turn if it has come, returning our update. Member 1 goes
+    /// first, the others once the turn before them is in, and the update is
+    /// republished until the end for the members that joined the topic
+    /// after us.
+    pub fn tick(&mut self) -> Result<Option<Payload>, SetupError> {
+        self.ceremony.check_deadline()?;
+        self.ceremony.contribute(&mut rand::rngs::OsRng);
+        Ok(self.ceremony.update())
+    }
+
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        self.ceremony
+            .handle(sender, payload, &mut rand::rngs::OsRng)
+            .into_iter()
+            .collect()
+    }
+
+    /// Saves the SRS once every member took its turn.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        let srs = self.ceremony.finish();
+        let path = self.args.output();
+        srs.save(&path)?;
+        info!(
+            "Setup {} done, saved the SRS of degree {} to {}",
+            self.args.session,
+            srs.max_degree(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum SetupError {
+    /// This node isn't in the committee running the ceremony.
+    NotAMember,
+    /// The member of `turn` didn't publish a valid update in time.
+    Timeout { turn: u64, member: PeerId },
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::NotAMember => write!(f, "this node isn't a member of the committee"),
+            SetupError::Timeout { turn, member } => write!(
+                f,
+                "member {} of turn {} didn't contribute in time",
+                member, turn
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
@@ -0,0 +1,188 @@
+//! Direct delivery of encrypted shares from a dealer to a single peer.
+//!
+//! Each request is one share, already encrypted to its recipient, so the
+//! protocol treats it as opaque bytes. The recipient answers with a one byte
+//! acknowledgment. Both are sent with a varint length prefix.
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::request_response::Codec;
+use std::io;
+
+/// Shares are a handful of group elements, anything larger is rejected
+/// before it is read.
+pub const MAX_SHARE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ShareProtocol;
+
+impl AsRef<str> for ShareProtocol {
+    fn as_ref(&self) -> &str {
+        "/zk-lab/share/1.0.0"
+    }
+}
+
+/// An encrypted share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareRequest(pub Vec<u8>);
+
+/// The recipient's answer to a direct message, also used by the whisper
+/// protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ack {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShareCodec;
+
+#[async_trait]
+impl Codec for ShareCodec {
+    type Protocol = ShareProtocol;
+    type Request = ShareRequest;
+    type Response = Ack;
+
+    async fn read_request<T>(&mut self, _: &ShareProtocol, io: &mut T) -> io::Result<ShareRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let share = read_length_prefixed(io, MAX_SHARE_SIZE).await?;
+        if share.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(ShareRequest(share))
+    }
+
+    async fn read_response<T>(&mut self, _: &ShareProtocol, io: &mut T) -> io::Result<Ack>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_ack(io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &ShareProtocol,
+        io: &mut T,
+        ShareRequest(share): ShareRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_length_prefixed(io, share).await?;
+        io.close().await
+    }
+
+    async fn write_response<T>(&mut self, _: &ShareProtocol, io: &mut T, ack: Ack) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_ack(io, ack).await
+    }
+}
+
+pub(crate) async fn read_ack<T>(io: &mut T) -> io::Result<Ack>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    match read_length_prefixed(io, 1).await?.as_slice() {
+        [0] => Ok(Ack::Accepted),
+        [1] => Ok(Ack::Rejected),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid ack")),
+    }
+}
+
+pub(crate) async fn write_ack<T>(io: &mut T, ack: Ack) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+{
+    let byte = match ack {
+        Ack::Accepted => 0u8,
+        Ack::Rejected => 1u8,
+    };
+    write_length_prefixed(io, [byte]).await?;
+    io.close().await
+}
+
+/// Reads a varint length, then as many bytes, refusing more than `max_size`.
+/// A stream closed before the length reads as empty.
+pub(crate) async fn read_length_prefixed<T>(io: &mut T, max_size: usize) -> io::Result<Vec<u8>>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    let mut len = 0usize;
+    for i in 0.. {
+        let mut byte = [0u8];
+        if io.read(&mut byte).await? == 0 {
+            if i == 0 {
+                return Ok(Vec::new());
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if i == 9 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "varint overflow",
+            ));
+        }
+        len |= usize::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if len > max_size {
+        let message = format!("{} bytes is more than the {} allowed", len, max_size);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    let mut data = vec![0; len];
+    io.read_exact(&mut data).await?;
+    Ok(data)
+}
+
+/// Writes the length of `data` as a varint, then `data`, and flushes.
+pub(crate) async fn write_length_prefixed<T>(io: &mut T, data: impl AsRef<[u8]>) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+{
+    let data = data.as_ref();
+    let mut len = data.len();
+    let mut prefix = Vec::with_capacity(10);
+    while len >= 0x80 {
+        prefix.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    prefix.push(len as u8);
+    io.write_all(&prefix).await?;
+    io.write_all(data).await?;
+    io.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    #[test]
+    fn length_prefix_round_trip() {
+        for len in [0, 1, 127, 128, 300, MAX_SHARE_SIZE] {
+            let data = vec![7u8; len];
+            let mut io = Cursor::new(Vec::new());
+            block_on(write_length_prefixed(&mut io, &data)).unwrap();
+            io.set_position(0);
+            let read = block_on(read_length_prefixed(&mut io, MAX_SHARE_SIZE)).unwrap();
+            assert_eq!(read, data);
+        }
+    }
+
+    #[test]
+    fn too_long_is_refused() {
+        let mut io = Cursor::new(Vec::new());
+        block_on(write_length_prefixed(&mut io, [0u8; 2])).unwrap();
+        io.set_position(0);
+        let error = block_on(read_length_prefixed(&mut io, 1)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
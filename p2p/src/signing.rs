@@ -0,0 +1,455 @@
+//! The threshold signing ceremony, run over a topic of the group that came
+//! out of a DKG.
+//!
+//! A coordinator publishes a [`Payload::SignRequest`] naming just enough
+//! members to reach the threshold, fastest first. Each of them checks its
+//! partial signature against its own public share before publishing it, and
+//! the coordinator checks it again before keeping it. Members that sent
+//! nothing valid by the end of a round are passed over, and the next round
+//! asks as many others as are still missing. Once `threshold + 1` partial
+//! signatures are in, the coordinator aggregates them and publishes the
+//! group's signature.
+//!
+//! Requests and partial signatures carry the epoch of the group file, see
+//! `epoch`, and those of another epoch are turned down.
+use crate::cli::SignArgs;
+use crate::envelope::Payload;
+use crate::epoch;
+use crate::group_key::{self, GroupKey};
+use bls12_381::G2Affine;
+use bls_shamir::keystore::Keystore;
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature::{self, DST};
+use bls_shamir::threshold::{self, PartialSignature};
+use group::Curve;
+use libp2p::gossipsub::IdentTopic as Topic;
+use libp2p::PeerId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// The topic sign requests and signatures of the group of `session` are
+/// published on.
+pub fn topic(session: &str) -> Topic {
+    Topic::new(format!("zk-lab/sign/{}", session))
+}
+
+/// Hashes a message to be signed by the group.
+pub fn hash(message: &[u8]) -> G2Affine {
+    signature::hash_to_g2(message, DST).to_affine()
+}
+
+/// A member of the group, holding its share.
+#[derive(Debug)]
+pub struct Signer {
+    group: GroupKey,
+    index: u64,
+    share: SecretKey,
+}
+
+impl Signer {
+    pub fn new(group: GroupKey, share: SecretKey, local: &PeerId) -> Result<Self, SignError> {
+        let member = group
+            .members
+            .iter()
+            .find(|member| member.peer_id == *local)
+            .ok_or(SignError::NotAMember)?;
+        if member.public_share != share.public_key() {
+            return Err(SignError::WrongShare);
+        }
+        let index = member.index;
+
+        Ok(Signer {
+            group,
+            index,
+            share,
+        })
+    }
+
+    pub fn group(&self) -> &GroupKey {
+        &self.group
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Our partial signature on `message`, `None` if it doesn't verify, which
+    /// only happens if the share was corrupted.
+    pub fn sign(&self, message: &[u8]) -> Option<PartialSignature> {
+        let hm = hash(message);
+        let partial = threshold::sign_share(self.index, &self.share, &hm, &mut rand::rngs::OsRng);
+        let public_share = &self.group.member(self.index)?.public_share;
+        if !threshold::verify_share(public_share, &hm, &partial) {
+            warn!("Our partial signature doesn't verify, is the share corrupted?");
+            return None;
+        }
+        Some(partial)
+    }
+
+    /// Handles a request or signature published on the group's topic,
+    /// returning our partial signature if `sender` asked us for it.
+    pub fn handle(&self, sender: &PeerId, payload: &Payload) -> Option<Payload> {
+        let member = self.group.index_of(sender);
+        match payload {
+            Payload::SignRequest {
+                message,
+                signers,
+                epoch,
+            } if signers.contains(&self.index) => {
+                let member = match member {
+                    Some(member) => member,
+                    None => {
+                        warn!(
+                            "Ignoring a sign request from {:?} outside the group",
+                            sender
+                        );
+                        return None;
+                    }
+                };
+                if let Err(e) = epoch::check(self.group.epoch, *epoch) {
+                    warn!("Ignoring the sign request of member {}: {}", member, e);
+                    return None;
+                }
+                info!(
+                    "Member {} asks us to sign {}",
+                    member,
+                    String::from_utf8_lossy(message)
+                );
+                let partial = self.sign(message)?;
+                Some(Payload::PartialSignature {
+                    index: partial.index,
+                    message: message.clone(),
+                    signature: partial.point.to_compressed().to_vec(),
+                    proof: partial.proof.to_bytes().to_vec(),
+                    epoch: self.group.epoch,
+                })
+            }
+            Payload::GroupSignature { message, signature } => {
+                let valid = decompress(signature)
+                    .is_some_and(|signature| verify(&self.group, message, &signature));
+                let message = String::from_utf8_lossy(message);
+                if valid {
+                    info!("The group signed {}", message);
+                } else {
+                    warn!("Got an invalid group signature on {}", message);
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Collects the partial signatures of one message.
+#[derive(Debug)]
+pub struct Coordinator {
+    message: Vec<u8>,
+    hm: G2Affine,
+    round_timeout: Duration,
+    /// The signers of the current round.
+    asked: BTreeSet<u64>,
+    /// Ourselves and the members asked in earlier rounds.
+    passed_over: BTreeSet<u64>,
+    partials: BTreeMap<u64, PartialSignature>,
+    /// The current round's request, until it is published.
+    pending: Option<Payload>,
+    deadline: Option<Instant>,
+}
+
+impl Coordinator {
+    /// Starts collecting partial signatures on `message`, ours included.
+    pub fn new(signer: &Signer, message: Vec<u8>, round_timeout: Duration) -> Self {
+        let mut partials = BTreeMap::new();
+        if let Some(partial) = signer.sign(&message) {
+            partials.insert(signer.index, partial);
+        }
+        Coordinator {
+            hm: hash(&message),
+            message,
+            round_timeout,
+            asked: BTreeSet::new(),
+            passed_over: BTreeSet::from([signer.index]),
+            partials,
+            pending: None,
+            deadline: None,
+        }
+    }
+
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// Whether the current round is over without enough partial signatures,
+    /// or none was started yet.
+    pub fn is_overdue(&self) -> bool {
+        self.deadline
+            .is_none_or(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Passes over the signers of the current round that haven't delivered
+    /// and asks the next ones in `ranked`, the member indices by preference.
+    pub fn next_round(&mut self, group: &GroupKey, ranked: &[u64]) -> Result<(), SignError> {
+        for index in &self.asked {
+            if !self.partials.contains_key(index) {
+                info!("Member {} didn't sign in time, passing it over", index);
+            }
+        }
+        self.passed_over.append(&mut self.asked);
+
+        let missing = (group.threshold + 1).saturating_sub(self.partials.len());
+        let signers = ranked
+            .iter()
+            .copied()
+            .filter(|index| !self.passed_over.contains(index))
+            .take(missing)
+            .collect::<Vec<_>>();
+        if signers.len() < missing {
+            return Err(SignError::TooFewSigners {
+                signed: self.partials.len(),
+                threshold: group.threshold,
+            });
+        }
+
+        info!("Asking members {:?} to sign", signers);
+        self.asked = signers.iter().copied().collect();
+        self.pending = Some(Payload::SignRequest {
+            message: self.message.clone(),
+            signers,
+            epoch: group.epoch,
+        });
+        self.deadline = Some(Instant::now() + self.round_timeout);
+        Ok(())
+    }
+
+    /// The request of the current round, until it is [`published`].
+    ///
+    /// [`published`]: Coordinator::published
+    pub fn pending(&self) -> Option<&Payload> {
+        self.pending.as_ref()
+    }
+
+    /// The round's signers have until the end of the timeout from now on.
+    pub fn published(&mut self) {
+        self.pending = None;
+        self.deadline = Some(Instant::now() + self.round_timeout);
+    }
+
+    /// Handles a partial signature published on the group's topic, returning
+    /// the group's signature once there are enough of them.
+    pub fn handle(
+        &mut self,
+        group: &GroupKey,
+        sender: &PeerId,
+        payload: &Payload,
+    ) -> Option<G2Affine> {
+        let (index, signature, proof, epoch) = match payload {
+            Payload::PartialSignature {
+                index,
+                message,
+                signature,
+                proof,
+                epoch,
+            } if *message == self.message => (*index, signature, proof, *epoch),
+            _ => return None,
+        };
+        if group.index_of(sender) != Some(index) || self.partials.contains_key(&index) {
+            return None;
+        }
+        // Passed over like an invalid one, a retired share is no use.
+        if let Err(e) = epoch::check(group.epoch, epoch) {
+            warn!("Rejecting the partial signature of member {}: {}", index, e);
+            return None;
+        }
+
+        let partial = decompress(signature)
+            .zip(sigma::Proof::from_bytes(proof))
+            .map(|(point, proof)| PartialSignature {
+                index,
+                point,
+                proof,
+            });
+        let public_share = &group.member(index)?.public_share;
+        match partial {
+            Some(partial) if threshold::verify_share(public_share, &self.hm, &partial) => {
+                info!("Got a valid partial signature from member {}", index);
+                self.partials.insert(index, partial);
+            }
+            _ => {
+                // Passed over at the end of the round, like a member that
+                // sent nothing.
+                warn!("Member {} sent an invalid partial signature", index);
+                return None;
+            }
+        }
+        if self.partials.len() <= group.threshold {
+            return None;
+        }
+
+        let partials = self.partials.values().copied().collect::<Vec<_>>();
+        // Can't fail with checked partial signatures of distinct members, but
+        // it is cheap enough to be sure.
+        match threshold::aggregate_shares(&partials[..=group.threshold]) {
+            Ok(signature) if signature::verify_hashed(&group.public_key, &self.hm, &signature) => {
+                Some(signature)
+            }
+            _ => {
+                warn!("The aggregated signature doesn't verify");
+                None
+            }
+        }
+    }
+}
+
+/// The signing of `p2p sign`: our share, the topic the group signs on and,
+/// if we asked for a signature, the coordinator collecting it.
+pub struct SigningTask {
+    signer: Signer,
+    topic: Topic,
+    coordinator: Option<Coordinator>,
+    /// The group's signature, once the coordinator has it.
+    signature: Option<G2Affine>,
+}
+
+impl SigningTask {
+    pub fn start(args: SignArgs, local: &PeerId) -> Result<Self, Box<dyn Error>> {
+        let share = Keystore::load(&args.share)?.decrypt(&args.password)?;
+        let group_path = group_key::path_for(&args.share);
+        let group =
+            GroupKey::load(&group_path).map_err(|e| format!("{}: {}", group_path.display(), e))?;
+        let signer = Signer::new(group, share, local)?;
+        let group = signer.group();
+        info!(
+            "Signing as member {} of the {} of DKG {}, any {} can sign",
+            signer.index(),
+            group.members.len(),
+            group.session,
+            group.threshold + 1
+        );
+        let topic = topic(&group.session);
+        let coordinator = args
+            .message
+            .map(|message| Coordinator::new(&signer, message.into_bytes(), args.timeout));
+        Ok(SigningTask {
+            signer,
+            topic,
+            coordinator,
+            signature: None,
+        })
+    }
+
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Asks the next members once a round is overdue, ranked by `rtt`, and
+    /// returns the request to publish until someone gets it.
+    pub fn tick(
+        &mut self,
+        rtt: impl Fn(&PeerId) -> Option<Duration>,
+    ) -> Result<Option<Payload>, SignError> {
+        let coordinator = match &mut self.coordinator {
+            Some(coordinator) => coordinator,
+            None => return Ok(None),
+        };
+        if coordinator.is_overdue() {
+            let ranked = rank(self.signer.group(), rtt);
+            coordinator.next_round(self.signer.group(), &ranked)?;
+        }
+        Ok(coordinator.pending().cloned())
+    }
+
+    /// The request returned by `tick` went out.
+    pub fn published(&mut self) {
+        if let Some(coordinator) = &mut self.coordinator {
+            coordinator.published();
+        }
+    }
+
+    /// Returns our partial signature if we were asked for it, and the
+    /// group's signature once the coordinator has enough of them.
+    pub fn handle(&mut self, sender: &PeerId, payload: &Payload) -> Vec<Payload> {
+        let mut replies = self
+            .signer
+            .handle(sender, payload)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let coordinator = match &mut self.coordinator {
+            Some(coordinator) => coordinator,
+            None => return replies,
+        };
+        if let Some(signature) = coordinator.handle(self.signer.group(), sender, payload) {
+            let message = coordinator.message();
+            info!("The group signed {}", String::from_utf8_lossy(message));
+            println!("{}", hex::encode(signature.to_compressed()));
+            replies.push(Payload::GroupSignature {
+                message: message.to_vec(),
+                signature: signature.to_compressed().to_vec(),
+            });
+            self.signature = Some(signature);
+        }
+        replies
+    }
+
+    /// Whether the group signed the message we asked for.
+    pub fn is_done(&self) -> bool {
+        self.signature.is_some()
+    }
+}
+
+/// Checks the group's signature on `message`.
+pub fn verify(group: &GroupKey, message: &[u8], signature: &G2Affine) -> bool {
+    signature::verify_hashed(&group.public_key, &hash(message), signature)
+}
+
+fn decompress(bytes: &[u8]) -> Option<G2Affine> {
+    let bytes: [u8; 96] = bytes.try_into().ok()?;
+    Option::from(G2Affine::from_compressed(&bytes))
+}
+
+/// Ranks the members of `group` for signing: the ones `rtt` knows the round
+/// trip time of first, fastest first, then the others by index.
+pub fn rank(group: &GroupKey, rtt: impl Fn(&PeerId) -> Option<Duration>) -> Vec<u64> {
+    let mut members = group
+        .members
+        .iter()
+        .map(|member| (rtt(&member.peer_id).unwrap_or(Duration::MAX), member.index))
+        .collect::<Vec<_>>();
+    members.sort();
+    members.into_iter().map(|(_, index)| index).collect()
+}
+
+#[derive(Debug)]
+pub enum SignError {
+    /// This node isn't a member of the group.
+    NotAMember,
+    /// The share doesn't match our public share in the group file.
+    WrongShare,
+    /// Everyone was asked and there still aren't enough partial signatures.
+    TooFewSigners { signed: usize, threshold: usize },
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignError::NotAMember => write!(f, "this node isn't a member of the group"),
+            SignError::WrongShare => {
+                write!(
+                    f,
+                    "the share doesn't match its public share in the group file"
+                )
+            }
+            SignError::TooFewSigners { signed, threshold } => write!(
+                f,
+                "only {} members signed, a threshold of {} needs {}",
+                signed,
+                threshold,
+                threshold + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignError {}
@@ -0,0 +1,207 @@
+//! The node's database, so a restarted node picks up where it left off: the
+//! addresses of the peers it met, to redial them right away, and the recent
+//! envelopes, to serve the catch-up requests of peers that missed them.
+//!
+//! Addresses are keyed by their `/p2p/<peer id>` terminated form, like the
+//! lines of the address book. Envelopes are keyed by timestamp, sequence
+//! number and sender, so they are iterated oldest first and the same envelope
+//! always lands on the same key.
+use crate::cli::parse_bootstrap_node;
+use crate::envelope::SignedEnvelope;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How many envelopes are kept, the oldest are dropped first.
+pub const MESSAGE_CAPACITY: usize = 4096;
+
+/// An envelope and the topic it was published on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub topic: String,
+    pub signed: SignedEnvelope,
+}
+
+pub struct Store {
+    db: sled::Db,
+    addresses: sled::Tree,
+    messages: sled::Tree,
+}
+
+impl Store {
+    /// Opens the database in the directory at `path`, creating it if needed.
+    pub fn open(path: &Path) -> sled::Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Store {
+            addresses: db.open_tree("addresses")?,
+            messages: db.open_tree("messages")?,
+            db,
+        })
+    }
+
+    /// The addresses of the peers met before, skipping any that don't parse.
+    pub fn addresses(&self) -> sled::Result<Vec<(PeerId, Multiaddr)>> {
+        let mut addresses = Vec::new();
+        for entry in self.addresses.iter() {
+            let (key, _) = entry?;
+            let parsed = std::str::from_utf8(&key).ok().map(parse_bootstrap_node);
+            if let Some(Ok(address)) = parsed {
+                addresses.push(address);
+            }
+        }
+        Ok(addresses)
+    }
+
+    pub fn add_address(&self, peer_id: PeerId, address: &Multiaddr) -> sled::Result<()> {
+        let address = address.clone().with(Protocol::P2p(peer_id));
+        self.addresses.insert(address.to_string(), &[])?;
+        Ok(())
+    }
+
+    /// Stores `message`, `false` if it was stored already.
+    pub fn add_message(&self, message: &StoredMessage) -> sled::Result<bool> {
+        let value = bincode::serialize(message).expect("Messages are always serializable");
+        let previous = self.messages.insert(message_key(&message.signed), value)?;
+        while self.messages.len() > MESSAGE_CAPACITY {
+            self.messages.pop_min()?;
+        }
+        Ok(previous.is_none())
+    }
+
+    /// Up to `limit` of the envelopes stamped at or after `since`, in
+    /// milliseconds since the epoch, oldest first.
+    pub fn messages_since(&self, since: u64, limit: usize) -> sled::Result<Vec<StoredMessage>> {
+        let mut messages = Vec::new();
+        for entry in self.messages.range(since.to_be_bytes()..).take(limit) {
+            let (_, value) = entry?;
+            // Only ever written by us, but the format may change under it.
+            if let Ok(message) = bincode::deserialize(&value) {
+                messages.push(message);
+            }
+        }
+        Ok(messages)
+    }
+
+    /// The timestamp of the newest envelope, where catching up starts from.
+    pub fn last_timestamp(&self) -> sled::Result<Option<u64>> {
+        let last = self.messages.last()?;
+        Ok(last.map(|(key, _)| {
+            let bytes = key[..8].try_into().expect("Keys start with the timestamp");
+            u64::from_be_bytes(bytes)
+        }))
+    }
+
+    /// Writes everything out, sled otherwise only does it periodically.
+    pub fn flush(&self) -> sled::Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+fn message_key(signed: &SignedEnvelope) -> Vec<u8> {
+    let envelope = &signed.envelope;
+    let mut key = envelope.timestamp.to_be_bytes().to_vec();
+    key.extend_from_slice(&envelope.seq.to_be_bytes());
+    key.extend_from_slice(&envelope.sender.to_bytes());
+    key
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::envelope::{Envelope, Payload};
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A store in a new directory under the temporary directory, removed
+    /// when dropped.
+    pub(crate) struct TempStore {
+        pub(crate) store: Store,
+        path: PathBuf,
+    }
+
+    impl TempStore {
+        pub(crate) fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("store-{:016x}", rand::random::<u64>()));
+            TempStore {
+                store: Store::open(&path).unwrap(),
+                path,
+            }
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    /// A chat message stamped at `timestamp`, the signature isn't checked
+    /// by the store.
+    pub(crate) fn message(timestamp: u64, seq: u64) -> StoredMessage {
+        let mut envelope = Envelope::new(PeerId::random(), seq, Payload::Chat("Hello".into()));
+        envelope.timestamp = timestamp;
+        StoredMessage {
+            topic: "chat".to_string(),
+            signed: SignedEnvelope {
+                envelope,
+                signature: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn messages_are_stored_once() {
+        let temp = TempStore::new();
+        let message = message(10, 1);
+        assert!(temp.store.add_message(&message).unwrap());
+        assert!(!temp.store.add_message(&message).unwrap());
+        assert_eq!(temp.store.messages_since(0, 10).unwrap(), vec![message]);
+    }
+
+    #[test]
+    fn messages_come_oldest_first_from_the_given_time() {
+        let temp = TempStore::new();
+        assert_eq!(temp.store.last_timestamp().unwrap(), None);
+        let messages = [message(30, 1), message(10, 2), message(20, 3)];
+        for message in &messages {
+            temp.store.add_message(message).unwrap();
+        }
+
+        assert_eq!(temp.store.last_timestamp().unwrap(), Some(30));
+        assert_eq!(
+            temp.store.messages_since(0, 10).unwrap(),
+            vec![
+                messages[1].clone(),
+                messages[2].clone(),
+                messages[0].clone()
+            ]
+        );
+        assert_eq!(
+            temp.store.messages_since(20, 10).unwrap(),
+            vec![messages[2].clone(), messages[0].clone()]
+        );
+        assert_eq!(
+            temp.store.messages_since(0, 1).unwrap(),
+            vec![messages[1].clone()]
+        );
+    }
+
+    #[test]
+    fn addresses_outlive_the_store() {
+        let path = std::env::temp_dir().join(format!("store-{:016x}", rand::random::<u64>()));
+        let peer_id = PeerId::random();
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/4001".parse().unwrap();
+        let store = Store::open(&path).unwrap();
+        store.add_address(peer_id, &address).unwrap();
+        store.add_address(peer_id, &address).unwrap();
+        store.flush().unwrap();
+        drop(store);
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.addresses().unwrap(), vec![(peer_id, address)]);
+        drop(store);
+        fs::remove_dir_all(path).unwrap();
+    }
+}
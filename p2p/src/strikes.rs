@@ -0,0 +1,174 @@
+//! Counts how often peers misbehave and bans them once they've done it too
+//! often. A ban only lasts for a while: the peer may have been relaying
+//! someone else's garbage, or its operator may have fixed it since.
+//!
+//! Strikes decay as well, only the offences within the last ban duration
+//! count, so the odd bad message every now and then never adds up to a ban.
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offence {
+    /// Published something that isn't a signed envelope.
+    MalformedEnvelope,
+    /// Published an envelope in someone else's name.
+    ForgedSender,
+    /// Published an envelope whose BLS signature doesn't verify.
+    BadSignature,
+    /// Sent a whisper that isn't sealed to us or was tampered with.
+    BadWhisper,
+    /// Published a fragment that doesn't match its checksum or siblings.
+    BadFragment,
+}
+
+impl fmt::Display for Offence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Offence::MalformedEnvelope => "a malformed envelope",
+            Offence::ForgedSender => "a forged sender",
+            Offence::BadSignature => "a bad signature",
+            Offence::BadWhisper => "a bad whisper",
+            Offence::BadFragment => "a bad fragment",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Strikes {
+    limit: u32,
+    ban_duration: Duration,
+    /// When each peer's recent offences happened, oldest first.
+    strikes: HashMap<PeerId, Vec<Instant>>,
+    banned: HashMap<PeerId, Instant>,
+}
+
+impl Strikes {
+    /// Peers are banned for `ban_duration` once they reach `limit` strikes
+    /// within that long.
+    pub fn new(limit: u32, ban_duration: Duration) -> Self {
+        Strikes {
+            limit,
+            ban_duration,
+            strikes: HashMap::new(),
+            banned: HashMap::new(),
+        }
+    }
+
+    /// Records an offence of `peer_id`, returns whether it is now banned.
+    /// Offences of a banned peer don't count towards its next ban.
+    pub fn strike(&mut self, peer_id: PeerId) -> bool {
+        if self.banned.contains_key(&peer_id) {
+            return false;
+        }
+
+        let now = Instant::now();
+        let window = self.ban_duration;
+        let strikes = self.strikes.entry(peer_id).or_default();
+        strikes.retain(|at| now.duration_since(*at) < window);
+        strikes.push(now);
+        if strikes.len() < self.limit as usize {
+            return false;
+        }
+
+        self.strikes.remove(&peer_id);
+        self.banned.insert(peer_id, now + self.ban_duration);
+        true
+    }
+
+    /// Lifts the bans that ran out, returning the peers they applied to, and
+    /// forgets the strikes that decayed.
+    pub fn expire(&mut self) -> Vec<PeerId> {
+        let now = Instant::now();
+        let window = self.ban_duration;
+        self.strikes.retain(|_, strikes| {
+            strikes.retain(|at| now.duration_since(*at) < window);
+            !strikes.is_empty()
+        });
+
+        let expired: Vec<_> = self
+            .banned
+            .iter()
+            .filter(|(_, until)| **until <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        for peer_id in &expired {
+            self.banned.remove(peer_id);
+        }
+        expired
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned.contains_key(peer_id)
+    }
+
+    pub fn ban_duration(&self) -> Duration {
+        self.ban_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn bans_at_the_limit() {
+        let mut strikes = Strikes::new(3, Duration::from_secs(60));
+        let (peer_id, other) = (PeerId::random(), PeerId::random());
+        assert!(!strikes.strike(peer_id));
+        assert!(!strikes.strike(other));
+        assert!(!strikes.strike(peer_id));
+        assert!(!strikes.is_banned(&peer_id));
+
+        assert!(strikes.strike(peer_id));
+        assert!(strikes.is_banned(&peer_id));
+        assert!(!strikes.is_banned(&other));
+
+        // Already banned, nothing more to report.
+        assert!(!strikes.strike(peer_id));
+        assert!(strikes.expire().is_empty());
+    }
+
+    #[test]
+    fn bans_run_out() {
+        let mut strikes = Strikes::new(1, Duration::from_millis(20));
+        let peer_id = PeerId::random();
+        assert!(strikes.strike(peer_id));
+        assert!(strikes.is_banned(&peer_id));
+
+        sleep(Duration::from_millis(30));
+        assert_eq!(strikes.expire(), vec![peer_id]);
+        assert!(!strikes.is_banned(&peer_id));
+        assert!(strikes.strike(peer_id));
+    }
+
+    #[test]
+    fn offences_while_banned_do_not_count() {
+        let mut strikes = Strikes::new(2, Duration::from_millis(20));
+        let peer_id = PeerId::random();
+        assert!(!strikes.strike(peer_id));
+        assert!(strikes.strike(peer_id));
+        assert!(!strikes.strike(peer_id));
+
+        sleep(Duration::from_millis(30));
+        strikes.expire();
+        assert!(!strikes.strike(peer_id));
+    }
+
+    #[test]
+    fn strikes_decay() {
+        let mut strikes = Strikes::new(2, Duration::from_millis(20));
+        let peer_id = PeerId::random();
+        assert!(!strikes.strike(peer_id));
+
+        sleep(Duration::from_millis(30));
+        assert!(!strikes.strike(peer_id));
+        assert!(!strikes.is_banned(&peer_id));
+
+        sleep(Duration::from_millis(30));
+        strikes.expire();
+        assert!(strikes.strikes.is_empty());
+    }
+}
@@ -0,0 +1,471 @@
+//! Sends files to a single peer, for handing SRS and transcript files around
+//! the lab without another tool.
+//!
+//! The sender first offers the file by name, size and SHA-256 digest, and the
+//! recipient answers with the offset to send from: the length of what it kept
+//! of an earlier attempt at the same file, or zero. The file then goes over
+//! one [`CHUNK_SIZE`] chunk per request, each answered with the next offset,
+//! until the recipient has all of it and the digest matches. An interrupted
+//! transfer resumes from where it stopped when the file is offered again.
+//!
+//! Files are read, written and hashed on the event loop, which is fine for
+//! the files of a lab but would stall the node on huge ones.
+use crate::share::{read_length_prefixed, write_length_prefixed};
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::request_response::{self, Codec, OutboundFailure, OutboundRequestId};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A chunk and the little around it.
+pub const MAX_REQUEST_SIZE: usize = CHUNK_SIZE + 1024;
+
+pub const MAX_RESPONSE_SIZE: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct FileProtocol;
+
+impl AsRef<str> for FileProtocol {
+    fn as_ref(&self) -> &str {
+        "/zk-lab/file/1.0.0"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileRequest {
+    Offer {
+        name: String,
+        size: u64,
+        digest: [u8; 32],
+    },
+    Chunk {
+        digest: [u8; 32],
+        offset: u64,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileResponse {
+    /// Send the file from this offset on.
+    Continue(u64),
+    /// The whole file arrived and matched its digest.
+    Complete,
+    Rejected(String),
+}
+
+/// A file being sent.
+#[derive(Debug)]
+pub struct Outgoing {
+    pub name: String,
+    pub size: u64,
+    pub digest: [u8; 32],
+    file: File,
+}
+
+impl Outgoing {
+    /// Opens and hashes the file at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file")),
+        };
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let digest = hash(&mut file)?;
+        Ok(Outgoing {
+            name,
+            size,
+            digest,
+            file,
+        })
+    }
+
+    pub fn offer(&self) -> FileRequest {
+        FileRequest::Offer {
+            name: self.name.clone(),
+            size: self.size,
+            digest: self.digest,
+        }
+    }
+
+    /// Reads the chunk starting at `offset`.
+    pub fn chunk(&mut self, offset: u64) -> io::Result<FileRequest> {
+        let len = self.size.saturating_sub(offset).min(CHUNK_SIZE as u64);
+        let mut data = vec![0; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut data)?;
+        Ok(FileRequest::Chunk {
+            digest: self.digest,
+            offset,
+            data,
+        })
+    }
+}
+
+/// The files being received, kept in a directory as `<digest>.part` until
+/// they are complete and renamed to the name they were offered under, or
+/// with a number after it if a file of that name is already there.
+#[derive(Debug)]
+pub struct Downloads {
+    dir: PathBuf,
+    /// Larger offers are refused.
+    max_size: u64,
+    offers: HashMap<[u8; 32], Offer>,
+}
+
+#[derive(Debug)]
+struct Offer {
+    name: String,
+    size: u64,
+}
+
+impl Downloads {
+    pub fn new(dir: PathBuf, max_size: u64) -> Self {
+        Downloads {
+            dir,
+            max_size,
+            offers: HashMap::new(),
+        }
+    }
+
+    /// Answers an offer or a chunk, along with where the file was saved once
+    /// it is complete.
+    pub fn handle(&mut self, request: FileRequest) -> io::Result<(FileResponse, Option<PathBuf>)> {
+        match request {
+            FileRequest::Offer { name, size, digest } => {
+                // Only the last component, the sender doesn't pick the directory.
+                let name = match Path::new(&name).file_name() {
+                    Some(name) => name.to_string_lossy().into_owned(),
+                    None => return Ok((FileResponse::Rejected("invalid name".into()), None)),
+                };
+                if size > self.max_size {
+                    let reason = format!("larger than {} bytes", self.max_size);
+                    return Ok((FileResponse::Rejected(reason), None));
+                }
+                fs::create_dir_all(&self.dir)?;
+                let part = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.part_path(&digest))?;
+                let mut offset = part.metadata()?.len();
+                if offset > size {
+                    part.set_len(0)?;
+                    offset = 0;
+                }
+                if fs2::available_space(&self.dir)? < size - offset {
+                    return Ok((FileResponse::Rejected("not enough space".into()), None));
+                }
+                self.offers.insert(digest, Offer { name, size });
+                if offset == size {
+                    self.finish(&digest)
+                } else {
+                    Ok((FileResponse::Continue(offset), None))
+                }
+            }
+            FileRequest::Chunk {
+                digest,
+                offset,
+                data,
+            } => {
+                let size = match self.offers.get(&digest) {
+                    Some(offer) => offer.size,
+                    None => return Ok((FileResponse::Rejected("not offered".into()), None)),
+                };
+                let part = self.part_path(&digest);
+                let mut file = OpenOptions::new().create(true).append(true).open(&part)?;
+                let len = file.metadata()?.len();
+                if offset != len {
+                    return Ok((FileResponse::Continue(len), None));
+                }
+                if len + data.len() as u64 > size {
+                    fs::remove_file(&part)?;
+                    self.offers.remove(&digest);
+                    return Ok((FileResponse::Rejected("larger than offered".into()), None));
+                }
+                file.write_all(&data)?;
+                let len = len + data.len() as u64;
+                if len == size {
+                    self.finish(&digest)
+                } else {
+                    Ok((FileResponse::Continue(len), None))
+                }
+            }
+        }
+    }
+
+    /// Checks the digest of a file that arrived whole, then moves it into
+    /// place.
+    fn finish(&mut self, digest: &[u8; 32]) -> io::Result<(FileResponse, Option<PathBuf>)> {
+        let offer = self
+            .offers
+            .remove(digest)
+            .expect("Finished files were offered");
+        let part = self.part_path(digest);
+        if hash(&mut File::open(&part)?)? != *digest {
+            fs::remove_file(&part)?;
+            return Ok((FileResponse::Rejected("digest mismatch".into()), None));
+        }
+        let path = self.free_path(&offer.name);
+        fs::rename(part, &path)?;
+        Ok((FileResponse::Complete, Some(path)))
+    }
+
+    /// `name` in the directory, or `<stem>-<n>.<extension>` for the first n
+    /// not taken, so a download never replaces a file.
+    fn free_path(&self, name: &str) -> PathBuf {
+        let path = self.dir.join(name);
+        if !path.exists() {
+            return path;
+        }
+        let name = Path::new(name);
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = name
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        (1..)
+            .map(|n| self.dir.join(format!("{}-{}{}", stem, n, extension)))
+            .find(|path| !path.exists())
+            .expect("Some number isn't taken")
+    }
+
+    fn part_path(&self, digest: &[u8; 32]) -> PathBuf {
+        self.dir.join(format!("{}.part", hex::encode(digest)))
+    }
+}
+
+/// The files we are sending, by the request carrying their latest chunk,
+/// and the ones we are receiving, if we accept any.
+#[derive(Debug)]
+pub struct Transfers {
+    downloads: Option<Downloads>,
+    outgoing: HashMap<OutboundRequestId, Outgoing>,
+}
+
+impl Transfers {
+    pub fn new(downloads: Option<Downloads>) -> Self {
+        Transfers {
+            downloads,
+            outgoing: HashMap::new(),
+        }
+    }
+
+    /// Offers the file at `path` to `peer_id`. The rest is sent as the peer
+    /// asks for it.
+    pub fn send(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<FileCodec>,
+        peer_id: &PeerId,
+        path: &Path,
+    ) -> io::Result<()> {
+        let file = Outgoing::open(path)?;
+        let request_id = behaviour.send_request(peer_id, file.offer());
+        info!(
+            "Offering {} ({} bytes) {:?}",
+            file.name, file.size, request_id
+        );
+        self.outgoing.insert(request_id, file);
+        Ok(())
+    }
+
+    /// Answers a peer's offer or chunk.
+    pub fn handle_request(&mut self, request: FileRequest) -> FileResponse {
+        if let FileRequest::Offer { name, size, .. } = &request {
+            info!("Offered {} ({} bytes)", name, size);
+        }
+        let response = match &mut self.downloads {
+            Some(downloads) => match downloads.handle(request) {
+                Ok((response, Some(path))) => {
+                    info!("Received {}", path.display());
+                    response
+                }
+                Ok((response, None)) => response,
+                Err(e) => {
+                    warn!("Failed to save a file: {}", e);
+                    FileResponse::Rejected(e.to_string())
+                }
+            },
+            None => FileResponse::Rejected("not accepting files".into()),
+        };
+        if let FileResponse::Rejected(reason) = &response {
+            info!("Refusing the file: {}", reason);
+        }
+        response
+    }
+
+    /// Sends the chunk `peer_id` asked for next, if any.
+    pub fn handle_response(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<FileCodec>,
+        peer_id: &PeerId,
+        request_id: OutboundRequestId,
+        response: FileResponse,
+    ) {
+        let mut file = match self.outgoing.remove(&request_id) {
+            Some(file) => file,
+            None => return,
+        };
+        match response {
+            FileResponse::Continue(offset) if offset < file.size => match file.chunk(offset) {
+                Ok(chunk) => {
+                    debug!(
+                        "Sending {} from {} of {} bytes",
+                        file.name, offset, file.size
+                    );
+                    let request_id = behaviour.send_request(peer_id, chunk);
+                    self.outgoing.insert(request_id, file);
+                }
+                Err(e) => warn!("Failed to read {}: {}", file.name, e),
+            },
+            FileResponse::Continue(offset) => {
+                warn!(
+                    "Asked for {} from {}, past its {} bytes",
+                    file.name, offset, file.size
+                );
+            }
+            FileResponse::Complete => info!("Sent {} ({} bytes)", file.name, file.size),
+            FileResponse::Rejected(reason) => warn!("{} was refused: {}", file.name, reason),
+        }
+    }
+
+    /// Gives up on the file `request_id` was carrying.
+    pub fn failed(&mut self, request_id: OutboundRequestId, error: &OutboundFailure) {
+        if let Some(file) = self.outgoing.remove(&request_id) {
+            warn!(
+                "Sending {} failed, /send it again to resume: {:?}",
+                file.name, error
+            );
+        }
+    }
+}
+
+fn hash(file: &mut File) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(file, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileCodec;
+
+#[async_trait]
+impl Codec for FileCodec {
+    type Protocol = FileProtocol;
+    type Request = FileRequest;
+    type Response = FileResponse;
+
+    async fn read_request<T>(&mut self, _: &FileProtocol, io: &mut T) -> io::Result<FileRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_length_prefixed(io, MAX_REQUEST_SIZE).await?;
+        bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn read_response<T>(&mut self, _: &FileProtocol, io: &mut T) -> io::Result<FileResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_length_prefixed(io, MAX_RESPONSE_SIZE).await?;
+        bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &FileProtocol,
+        io: &mut T,
+        request: FileRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let bytes = bincode::serialize(&request).expect("Requests are always serializable");
+        write_length_prefixed(io, bytes).await?;
+        io.close().await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &FileProtocol,
+        io: &mut T,
+        response: FileResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let bytes = bincode::serialize(&response).expect("Responses are always serializable");
+        write_length_prefixed(io, bytes).await?;
+        io.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir() -> PathBuf {
+        std::env::temp_dir().join(format!("downloads-{:016x}", rand::random::<u64>()))
+    }
+
+    /// Sends `data` as `name` in a single chunk.
+    fn send(downloads: &mut Downloads, name: &str, data: &[u8]) -> io::Result<FileResponse> {
+        let digest = Sha256::digest(data).into();
+        let offer = FileRequest::Offer {
+            name: name.into(),
+            size: data.len() as u64,
+            digest,
+        };
+        match downloads.handle(offer)? {
+            (FileResponse::Continue(0), None) => {}
+            (response, _) => return Ok(response),
+        }
+        let chunk = FileRequest::Chunk {
+            digest,
+            offset: 0,
+            data: data.to_vec(),
+        };
+        Ok(downloads.handle(chunk)?.0)
+    }
+
+    #[test]
+    fn keeps_files_of_the_same_name() {
+        let dir = dir();
+        let mut downloads = Downloads::new(dir.clone(), 1024);
+        for data in [&b"first"[..], b"second", b"third"] {
+            assert_eq!(
+                send(&mut downloads, "srs.bin", data).unwrap(),
+                FileResponse::Complete
+            );
+        }
+        assert_eq!(fs::read(dir.join("srs.bin")).unwrap(), b"first");
+        assert_eq!(fs::read(dir.join("srs-1.bin")).unwrap(), b"second");
+        assert_eq!(fs::read(dir.join("srs-2.bin")).unwrap(), b"third");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_files_over_the_limit() {
+        let dir = dir();
+        let mut downloads = Downloads::new(dir.clone(), 4);
+        assert!(matches!(
+            send(&mut downloads, "large", b"hello").unwrap(),
+            FileResponse::Rejected(_)
+        ));
+        assert!(!dir.exists());
+
+        assert_eq!(
+            send(&mut downloads, "small", b"hey").unwrap(),
+            FileResponse::Complete
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+//! The transports a node can run on. Whatever carries the bytes, connections
+//! are authenticated with noise and multiplexed with yamux, or come both from
+//! QUIC, so peers on different transports only differ in the addresses they
+//! use.
+//!
+//! Every transport is joined by the circuit relay v2 client transport, which
+//! lets peers behind a NAT listen through a relay until DCUtR punches a hole
+//! for a direct connection. Relaying for others is
+//! the relay server behaviour's job, which is opt-in: an open relay lends
+//! anyone our bandwidth.
+use crate::cli::TransportKind;
+use crate::runtime::{self, Quic, Tcp};
+use futures::future::Either;
+use futures::{AsyncRead, AsyncWrite};
+use libp2p::core::muxing::StreamMuxerBox;
+use libp2p::core::transport::Boxed;
+use libp2p::core::upgrade::Version;
+use libp2p::identity::Keypair;
+use libp2p::websocket::WsConfig;
+use libp2p::{noise, quic, relay, tcp, yamux, PeerId, Transport};
+use std::io;
+use std::time::Duration;
+
+/// Returns the transport and the relay client behaviour driving its relayed
+/// connections.
+pub async fn build(
+    kind: TransportKind,
+    keypair: &Keypair,
+) -> io::Result<(Boxed<(PeerId, StreamMuxerBox)>, relay::client::Behaviour)> {
+    let tcp = || Tcp::new(tcp::Config::new().nodelay(true));
+    let quic = || {
+        Quic::new(quic::Config::new(keypair))
+            .map(|(peer_id, connection), _| (peer_id, StreamMuxerBox::new(connection)))
+            .boxed()
+    };
+    // DNS has to wrap the choice between the transports, it doesn't let
+    // addresses it can't dial fall through to the next transport.
+    let transport = match kind {
+        TransportKind::Tcp => {
+            let transport = upgrade(tcp().or_transport(WsConfig::new(tcp())), keypair);
+            either(quic().or_transport(transport))
+        }
+        TransportKind::Ws => upgrade(WsConfig::new(tcp()), keypair),
+        TransportKind::Quic => quic(),
+    };
+    let transport = runtime::dns(transport).await?;
+
+    let local_peer_id = keypair.public().to_peer_id();
+    let (relayed, behaviour) = relay::client::new(local_peer_id);
+    // First for the same reason, DNS would turn down `/p2p-circuit` addresses.
+    let transport = either(upgrade(relayed, keypair).or_transport(transport));
+    Ok((transport, behaviour))
+}
+
+fn upgrade<T>(transport: T, keypair: &Keypair) -> Boxed<(PeerId, StreamMuxerBox)>
+where
+    T: Transport + Send + Unpin + 'static,
+    T::Output: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T::Error: Send + Sync,
+    T::ListenerUpgrade: Send,
+    T::Dial: Send,
+{
+    transport
+        .upgrade(Version::V1)
+        .authenticate(
+            noise::Config::new(keypair)
+                .expect("Signing the noise static key with an ed25519 key can't fail"),
+        )
+        .multiplex(yamux::Config::default())
+        .timeout(Duration::from_secs(20))
+        .boxed()
+}
+
+fn either<A>(transport: A) -> Boxed<(PeerId, StreamMuxerBox)>
+where
+    A: Transport<Output = Either<(PeerId, StreamMuxerBox), (PeerId, StreamMuxerBox)>>
+        + Send
+        + Unpin
+        + 'static,
+    A::Error: Send + Sync,
+    A::ListenerUpgrade: Send,
+    A::Dial: Send,
+{
+    transport
+        .map(|output, _| match output {
+            Either::Left(output) | Either::Right(output) => output,
+        })
+        .boxed()
+}
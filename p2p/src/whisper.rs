@@ -0,0 +1,242 @@
+//! Private messages to a single peer, so that data like shares never shows up
+//! in plaintext on the gossip topic.
+//!
+//! A whisper is sealed to the X25519 key of its recipient, which is the
+//! Montgomery form of the ed25519 key inlined in their peer id, so nothing
+//! has to be registered before the first message. The sender picks an
+//! ephemeral key, derives a single use ChaCha20-Poly1305 key from the Diffie
+//! Hellman secret with HKDF-SHA256 and sends
+//!
+//! ```text
+//! ephemeral public key (32) || ciphertext || tag (16)
+//! ```
+//!
+//! over the whisper request-response protocol. The sealed box itself is
+//! anonymous, the sender is the peer on the other end of the connection.
+use crate::share::{read_ack, read_length_prefixed, write_ack, write_length_prefixed, Ack};
+use async_trait::async_trait;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::constants::X25519_BASEPOINT;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use futures::prelude::*;
+use hkdf::Hkdf;
+use libp2p::identity::{ed25519, PublicKey};
+use libp2p::request_response::Codec;
+use libp2p::PeerId;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use std::io;
+
+pub const KDF_INFO: &[u8] = b"zk-lab/whisper/v1";
+
+pub const MAX_WHISPER_SIZE: usize = 64 * 1024;
+
+/// The multihash code of peer ids that inline the public key.
+const IDENTITY_HASH: u64 = 0x00;
+
+/// Returns the X25519 key whispers to `peer_id` are sealed to, if the peer id
+/// inlines an ed25519 key.
+pub fn encryption_key(peer_id: &PeerId) -> Option<MontgomeryPoint> {
+    let multihash = peer_id.as_ref();
+    if multihash.code() != IDENTITY_HASH {
+        return None;
+    }
+
+    let pk = PublicKey::try_decode_protobuf(multihash.digest()).ok()?;
+    let point = CompressedEdwardsY(pk.try_into_ed25519().ok()?.to_bytes()).decompress()?;
+    Some(point.to_montgomery())
+}
+
+/// The X25519 secret matching [`encryption_key`], derived the same way
+/// ed25519 derives its signing scalar from the seed.
+pub fn decryption_key(keypair: &ed25519::Keypair) -> Scalar {
+    let hash = Sha512::digest(keypair.secret().as_ref());
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&hash[..32]);
+    clamp(bytes)
+}
+
+fn clamp(mut bytes: [u8; 32]) -> Scalar {
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(bytes)
+}
+
+/// Derives the message key, bound to both public keys.
+fn message_key(
+    shared: &MontgomeryPoint,
+    ephemeral: &MontgomeryPoint,
+    recipient: &MontgomeryPoint,
+) -> Option<ChaCha20Poly1305> {
+    // A low order key from either side gives an all zero secret.
+    if shared.as_bytes() == &[0u8; 32] {
+        return None;
+    }
+
+    let mut info = KDF_INFO.to_vec();
+    info.extend_from_slice(ephemeral.as_bytes());
+    info.extend_from_slice(recipient.as_bytes());
+
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared.as_bytes())
+        .expand(&info, &mut key)
+        .expect("32 bytes is a valid output length");
+    Some(ChaCha20Poly1305::new(&Key::from(key)))
+}
+
+/// Seals `plaintext` to `recipient`, `None` if its peer id doesn't carry an
+/// ed25519 key.
+pub fn seal<R: RngCore>(recipient: &PeerId, plaintext: &[u8], rng: &mut R) -> Option<Vec<u8>> {
+    let recipient = encryption_key(recipient)?;
+
+    let mut bytes = [0u8; 32];
+    rng.fill_bytes(&mut bytes);
+    let secret = clamp(bytes);
+    let ephemeral = X25519_BASEPOINT * secret;
+
+    let cipher = message_key(&(recipient * secret), &ephemeral, &recipient)?;
+    // Every message has its own key, so a fixed nonce is never reused.
+    let ciphertext = cipher
+        .encrypt(&Nonce::default(), plaintext)
+        .expect("Messages fit in a ChaCha20 stream");
+
+    let mut sealed = ephemeral.to_bytes().to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Some(sealed)
+}
+
+/// Opens a whisper sealed to the key of `keypair`, `None` if it was sealed to
+/// someone else or tampered with.
+pub fn open(keypair: &ed25519::Keypair, sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < 32 {
+        return None;
+    }
+    let (ephemeral, ciphertext) = sealed.split_at(32);
+    let ephemeral = MontgomeryPoint(ephemeral.try_into().ok()?);
+
+    let secret = decryption_key(keypair);
+    let recipient = X25519_BASEPOINT * secret;
+    let cipher = message_key(&(ephemeral * secret), &ephemeral, &recipient)?;
+
+    cipher.decrypt(&Nonce::default(), ciphertext).ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct WhisperProtocol;
+
+impl AsRef<str> for WhisperProtocol {
+    fn as_ref(&self) -> &str {
+        "/zk-lab/whisper/1.0.0"
+    }
+}
+
+/// A sealed whisper.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhisperRequest(pub Vec<u8>);
+
+#[derive(Debug, Clone, Default)]
+pub struct WhisperCodec;
+
+#[async_trait]
+impl Codec for WhisperCodec {
+    type Protocol = WhisperProtocol;
+    type Request = WhisperRequest;
+    type Response = Ack;
+
+    async fn read_request<T>(
+        &mut self,
+        _: &WhisperProtocol,
+        io: &mut T,
+    ) -> io::Result<WhisperRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let sealed = read_length_prefixed(io, MAX_WHISPER_SIZE).await?;
+        if sealed.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(WhisperRequest(sealed))
+    }
+
+    async fn read_response<T>(&mut self, _: &WhisperProtocol, io: &mut T) -> io::Result<Ack>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_ack(io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &WhisperProtocol,
+        io: &mut T,
+        WhisperRequest(sealed): WhisperRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_length_prefixed(io, sealed).await?;
+        io.close().await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &WhisperProtocol,
+        io: &mut T,
+        ack: Ack,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_ack(io, ack).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer_id(keypair: &ed25519::Keypair) -> PeerId {
+        PeerId::from(PublicKey::from(keypair.public()))
+    }
+
+    #[test]
+    fn the_recipient_opens_what_was_sealed() {
+        let keypair = ed25519::Keypair::generate();
+        let sealed = seal(&peer_id(&keypair), b"a share", &mut rand::thread_rng()).unwrap();
+        assert_eq!(sealed.len(), 32 + b"a share".len() + 16);
+        assert_eq!(open(&keypair, &sealed).unwrap(), b"a share");
+    }
+
+    #[test]
+    fn someone_else_cannot_open_it() {
+        let recipient = ed25519::Keypair::generate();
+        let sealed = seal(&peer_id(&recipient), b"a share", &mut rand::thread_rng()).unwrap();
+        assert_eq!(open(&ed25519::Keypair::generate(), &sealed), None);
+    }
+
+    #[test]
+    fn tampered_whispers_are_refused() {
+        let keypair = ed25519::Keypair::generate();
+        let sealed = seal(&peer_id(&keypair), b"a share", &mut rand::thread_rng()).unwrap();
+        for i in [0, 32, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert_eq!(open(&keypair, &tampered), None);
+        }
+        assert_eq!(open(&keypair, &sealed[..sealed.len() - 1]), None);
+        assert_eq!(open(&keypair, &sealed[..31]), None);
+    }
+
+    #[test]
+    fn peer_ids_without_an_ed25519_key_cannot_be_whispered_to() {
+        assert_eq!(
+            seal(&PeerId::random(), b"a share", &mut rand::thread_rng()),
+            None
+        );
+    }
+}
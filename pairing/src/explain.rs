@@ -0,0 +1,154 @@
+//! A step by step look at what `pairing(P, Q)` computes.
+//!
+//! The optimal ate pairing on BLS12-381 is a Miller loop over the bits of the
+//! curve parameter `x = -0xd201000000010000`, followed by a final
+//! exponentiation:
+//!
+//! ```text
+//! T = Q, f = 1
+//! for each bit of |x| after the leading one, from the top:
+//!     f = f^2 * ℓ_{T,T}(P), T = 2T        (doubling step)
+//!     if the bit is set:
+//!         f = f * ℓ_{T,Q}(P), T = T + Q   (addition step)
+//! f = conjugate(f)                        (x is negative)
+//! e(P, Q) = f^((p^12 - 1) / r)
+//! ```
+//!
+//! where `ℓ_{A,B}` is the line through A and B (the tangent when they are
+//! equal). Only the lines depend on Q, so their coefficients can be computed
+//! once, like `bls12_381` does in `G2Prepared`, and evaluated at P as the
+//! sparse Fp12 element
+//!
+//! ```text
+//! ℓ(P) = c + (x_P * b) * v + (y_P * a) * v * w
+//! ```
+//!
+//! The trace runs the crate's own pairing, which takes the same steps as
+//! `bls12_381`'s, and records the line coefficients `(a, b, c)` and the
+//! running point T of every step. The final exponentiation is reported as a
+//! single step: it is split into the
+//! easy part `f^((p^6 - 1)(p^2 + 1))`, which only needs Frobenius maps and an
+//! inversion, and the hard part `^((p^4 - p^2 + 1) / r)`, computed with a
+//! chain of cyclotomic squarings and multiplications by powers of x.
+use crate::gt::GtElement;
+use crate::miller;
+use bls12_381::{G1Affine, G2Affine};
+use std::fmt;
+
+/// The absolute value of the BLS12-381 parameter x, which is negative.
+pub const BLS_X: u64 = 0xd201_0000_0001_0000;
+
+/// Number of Miller loop steps, one line per step.
+pub const STEPS: usize = 68;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    Doubling,
+    Addition,
+}
+
+/// The Fp2 coefficients of one line, each as `c0 || c1` in canonical
+/// big-endian bytes, see the module documentation for how they are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub a: [u8; 96],
+    pub b: [u8; 96],
+    pub c: [u8; 96],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    /// The bit of |x| being processed.
+    pub bit: u32,
+    pub kind: StepKind,
+    /// The line through the points the step adds.
+    pub line: Line,
+    /// The running point after the step.
+    pub t: G2Affine,
+}
+
+/// Everything `pairing(p, q)` computes on the way to its result.
+#[derive(Debug, Clone)]
+pub struct PairingTrace {
+    pub p: G1Affine,
+    pub q: G2Affine,
+    pub steps: Vec<Step>,
+    /// The Fp12 output of the Miller loop before the final exponentiation,
+    /// encoded like [`GtElement::to_bytes`].
+    pub miller_loop: [u8; GtElement::BYTES],
+    pub result: GtElement,
+}
+
+impl PairingTrace {
+    pub fn doublings(&self) -> usize {
+        self.count(StepKind::Doubling)
+    }
+
+    pub fn additions(&self) -> usize {
+        self.count(StepKind::Addition)
+    }
+
+    fn count(&self, kind: StepKind) -> usize {
+        self.steps.iter().filter(|step| step.kind == kind).count()
+    }
+}
+
+/// Traces `pairing(p, q)`. As in `bls12_381`, the lines are those of the
+/// generators when either point is the identity, and the result is one.
+pub fn trace(p: &G1Affine, q: &G2Affine) -> PairingTrace {
+    let mut steps = Vec::with_capacity(STEPS);
+    let f = miller::miller_loop(p, q, |bit, kind, line, t| {
+        steps.push(Step {
+            bit,
+            kind,
+            line: Line {
+                a: line.a.to_bytes(),
+                b: line.b.to_bytes(),
+                c: line.c.to_bytes(),
+            },
+            t: t.to_affine(),
+        })
+    });
+
+    PairingTrace {
+        p: *p,
+        q: *q,
+        steps,
+        miller_loop: f.to_bytes(),
+        result: GtElement::from_miller_loop(&f),
+    }
+}
+
+impl fmt::Display for PairingTrace {
+    /// One line per step with the first bytes of each value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Miller loop over |x| = {:#x}", BLS_X)?;
+        for step in &self.steps {
+            let kind = match step.kind {
+                StepKind::Doubling => "double",
+                StepKind::Addition => "add",
+            };
+            writeln!(
+                f,
+                "bit {:>2} {:<6} a={}.. b={}.. c={}.. T={}..",
+                step.bit,
+                kind,
+                short(&step.line.a),
+                short(&step.line.b),
+                short(&step.line.c),
+                short(&step.t.to_compressed()),
+            )?;
+        }
+        writeln!(f, "conjugate, since x is negative")?;
+        writeln!(f, "f = {}..", short(&self.miller_loop))?;
+        write!(
+            f,
+            "final exponentiation f^((p^12 - 1) / r) = {}..",
+            short(&self.result.to_bytes())
+        )
+    }
+}
+
+fn short(bytes: &[u8]) -> String {
+    bytes[..6].iter().map(|b| format!("{:02x}", b)).collect()
+}
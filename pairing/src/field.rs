@@ -0,0 +1,658 @@
+//! The Fp12 tower of BLS12-381, which `bls12_381` keeps private: enough of
+//! it to compute the pairing into elements of Gt of our own, encode them
+//! and compress them on the torus.
+//!
+//! Fp2 = Fp[u] / (u^2 + 1)
+//! Fp6 = Fp2[v] / (v^3 - (u + 1))
+//! Fp12 = Fp6[w] / (w^2 - v)
+//!
+//! Nothing branches on the values, elements of Gt are often secrets.
+
+/// p, little-endian limbs.
+const MODULUS: [u64; 6] = [
+    0xb9fe_ffff_ffff_aaab,
+    0x1eab_fffe_b153_ffff,
+    0x6730_d2a0_f6b0_f624,
+    0x6477_4b84_f385_12bf,
+    0x4b1b_a7b6_434b_acd7,
+    0x1a01_11ea_397f_e69a,
+];
+
+/// `-p^-1 mod 2^64`
+const INV: u64 = 0x89f3_fffc_fffc_fffd;
+
+/// `R = 2^384 mod p`, one in Montgomery form.
+const R: [u64; 6] = [
+    0x7609_0000_0002_fffd,
+    0xebf4_000b_c40c_0002,
+    0x5f48_9857_53c7_58ba,
+    0x77ce_5853_7052_5745,
+    0x5c07_1a97_a256_ec6d,
+    0x15f6_5ec3_fa80_e493,
+];
+
+/// `R^2 mod p`
+const R2: [u64; 6] = [
+    0xf4df_1f34_1c34_1746,
+    0x0a76_e6a6_09d1_04f1,
+    0x8de5_476c_4c95_b6d5,
+    0x67eb_88a9_939d_83c0,
+    0x9a79_3e85_b519_952d,
+    0x1198_8fe5_92ca_e3aa,
+];
+
+/// An element of Fp as `a * R mod p`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fp(pub(crate) [u64; 6]);
+
+impl Fp {
+    pub const ZERO: Fp = Fp([0; 6]);
+    pub const ONE: Fp = Fp(R);
+
+    /// The canonical big-endian encoding, `None` if it isn't below p.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Fp> {
+        let mut limbs = [0u64; 6];
+        for (limb, chunk) in limbs.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        let (_, borrow) = sub_limbs(&limbs, &MODULUS);
+        // Only a borrow means the value is below p.
+        (borrow != 0).then(|| Fp(limbs).mul(&Fp(R2)))
+    }
+
+    pub fn to_bytes(self) -> [u8; 48] {
+        let mut wide = [0u64; 12];
+        wide[..6].copy_from_slice(&self.0);
+        let canonical = reduce(wide);
+        let mut bytes = [0u8; 48];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(canonical.0.iter().rev()) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().fold(0, |acc, limb| acc | limb) == 0
+    }
+
+    pub fn add(&self, rhs: &Fp) -> Fp {
+        let mut sum = [0u64; 6];
+        let mut carry = 0;
+        for ((out, a), b) in sum.iter_mut().zip(self.0).zip(rhs.0) {
+            (*out, carry) = adc(a, b, carry);
+        }
+        // Both are below p, the sum fits in 384 bits.
+        subtract_p(sum)
+    }
+
+    pub fn sub(&self, rhs: &Fp) -> Fp {
+        let (difference, borrow) = sub_limbs(&self.0, &rhs.0);
+        // Adds p back when it wrapped around, the borrow being all ones.
+        let mut out = [0u64; 6];
+        let mut carry = 0;
+        for ((out, d), p) in out.iter_mut().zip(difference).zip(MODULUS) {
+            (*out, carry) = adc(d, p & borrow, carry);
+        }
+        Fp(out)
+    }
+
+    pub fn neg(&self) -> Fp {
+        Fp::ZERO.sub(self)
+    }
+
+    /// `a` where `mask` is all ones, `b` where it is zero.
+    pub fn select(a: &Fp, b: &Fp, mask: u64) -> Fp {
+        let mut out = [0u64; 6];
+        for ((out, a), b) in out.iter_mut().zip(a.0).zip(b.0) {
+            *out = (a & mask) | (b & !mask);
+        }
+        Fp(out)
+    }
+
+    pub fn mul(&self, rhs: &Fp) -> Fp {
+        let mut wide = [0u64; 12];
+        for i in 0..6 {
+            let mut carry = 0;
+            for j in 0..6 {
+                (wide[i + j], carry) = mac(wide[i + j], self.0[i], rhs.0[j], carry);
+            }
+            wide[i + 6] = carry;
+        }
+        reduce(wide)
+    }
+
+    /// `self^(p - 2)`, zero for zero.
+    pub fn invert(&self) -> Fp {
+        let mut exponent = MODULUS;
+        exponent[0] -= 2;
+        // The exponent is public, so square-and-multiply may branch on it.
+        let mut acc = Fp::ONE;
+        for limb in exponent.iter().rev() {
+            for bit in (0..64).rev() {
+                acc = acc.mul(&acc);
+                if (limb >> bit) & 1 == 1 {
+                    acc = acc.mul(self);
+                }
+            }
+        }
+        acc
+    }
+}
+
+/// Montgomery reduction of a 768-bit product, `t * R^-1 mod p`, after
+/// Algorithm 14.32 of the Handbook of Applied Cryptography.
+fn reduce(mut t: [u64; 12]) -> Fp {
+    let mut high = 0;
+    for i in 0..6 {
+        let k = t[i].wrapping_mul(INV);
+        let mut carry = 0;
+        for j in 0..6 {
+            (t[i + j], carry) = mac(t[i + j], k, MODULUS[j], carry);
+        }
+        (t[i + 6], high) = adc(t[i + 6], high, carry);
+    }
+    subtract_p(t[6..].try_into().unwrap())
+}
+
+/// Brings a value below 2p under p.
+fn subtract_p(value: [u64; 6]) -> Fp {
+    let (difference, borrow) = sub_limbs(&value, &MODULUS);
+    let mut out = [0u64; 6];
+    for ((out, v), d) in out.iter_mut().zip(value).zip(difference) {
+        *out = (v & borrow) | (d & !borrow);
+    }
+    Fp(out)
+}
+
+/// `a - b` and an all-ones borrow if it wrapped around.
+fn sub_limbs(a: &[u64; 6], b: &[u64; 6]) -> ([u64; 6], u64) {
+    let mut out = [0u64; 6];
+    let mut borrow = 0;
+    for ((out, a), b) in out.iter_mut().zip(a).zip(b) {
+        (*out, borrow) = sbb(*a, *b, borrow);
+    }
+    (out, borrow)
+}
+
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let sum = a as u128 + b as u128 + carry as u128;
+    (sum as u64, (sum >> 64) as u64)
+}
+
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let difference = (a as u128).wrapping_sub(b as u128 + (borrow >> 63) as u128);
+    (difference as u64, (difference >> 64) as u64)
+}
+
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let sum = a as u128 + b as u128 * c as u128 + carry as u128;
+    (sum as u64, (sum >> 64) as u64)
+}
+
+/// `c0 + c1 u`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fp2 {
+    pub c0: Fp,
+    pub c1: Fp,
+}
+
+impl Fp2 {
+    pub const ZERO: Fp2 = Fp2 {
+        c0: Fp::ZERO,
+        c1: Fp::ZERO,
+    };
+    pub const ONE: Fp2 = Fp2 {
+        c0: Fp::ONE,
+        c1: Fp::ZERO,
+    };
+
+    pub fn add(&self, rhs: &Fp2) -> Fp2 {
+        Fp2 {
+            c0: self.c0.add(&rhs.c0),
+            c1: self.c1.add(&rhs.c1),
+        }
+    }
+
+    pub fn sub(&self, rhs: &Fp2) -> Fp2 {
+        Fp2 {
+            c0: self.c0.sub(&rhs.c0),
+            c1: self.c1.sub(&rhs.c1),
+        }
+    }
+
+    pub fn neg(&self) -> Fp2 {
+        Fp2 {
+            c0: self.c0.neg(),
+            c1: self.c1.neg(),
+        }
+    }
+
+    pub fn double(&self) -> Fp2 {
+        self.add(self)
+    }
+
+    pub fn mul(&self, rhs: &Fp2) -> Fp2 {
+        Fp2 {
+            c0: self.c0.mul(&rhs.c0).sub(&self.c1.mul(&rhs.c1)),
+            c1: self.c0.mul(&rhs.c1).add(&self.c1.mul(&rhs.c0)),
+        }
+    }
+
+    pub fn square(&self) -> Fp2 {
+        self.mul(self)
+    }
+
+    /// Times an element of Fp.
+    pub fn scale(&self, k: &Fp) -> Fp2 {
+        Fp2 {
+            c0: self.c0.mul(k),
+            c1: self.c1.mul(k),
+        }
+    }
+
+    /// `self^p`, which is the conjugate.
+    pub fn frobenius_map(&self) -> Fp2 {
+        Fp2 {
+            c0: self.c0,
+            c1: self.c1.neg(),
+        }
+    }
+
+    /// Times `u + 1`.
+    pub fn mul_by_nonresidue(&self) -> Fp2 {
+        Fp2 {
+            c0: self.c0.sub(&self.c1),
+            c1: self.c0.add(&self.c1),
+        }
+    }
+
+    /// Zero for zero.
+    pub fn invert(&self) -> Fp2 {
+        let t = self.c0.mul(&self.c0).add(&self.c1.mul(&self.c1)).invert();
+        Fp2 {
+            c0: self.c0.mul(&t),
+            c1: self.c1.mul(&t).neg(),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() & self.c1.is_zero()
+    }
+
+    fn select(a: &Fp2, b: &Fp2, mask: u64) -> Fp2 {
+        Fp2 {
+            c0: Fp::select(&a.c0, &b.c0, mask),
+            c1: Fp::select(&a.c1, &b.c1, mask),
+        }
+    }
+
+    /// The big-endian encoding `bls12_381` uses for points, `c1` first.
+    pub fn from_point_bytes(bytes: &[u8]) -> Option<Fp2> {
+        Some(Fp2 {
+            c0: Fp::from_bytes(&bytes[48..96])?,
+            c1: Fp::from_bytes(&bytes[..48])?,
+        })
+    }
+
+    pub fn to_point_bytes(self) -> [u8; 96] {
+        let mut bytes = [0u8; 96];
+        bytes[..48].copy_from_slice(&self.c1.to_bytes());
+        bytes[48..].copy_from_slice(&self.c0.to_bytes());
+        bytes
+    }
+
+    /// `c0 || c1` in canonical big-endian bytes.
+    pub fn to_bytes(self) -> [u8; 96] {
+        let mut bytes = [0u8; 96];
+        bytes[..48].copy_from_slice(&self.c0.to_bytes());
+        bytes[48..].copy_from_slice(&self.c1.to_bytes());
+        bytes
+    }
+}
+
+/// `c0 + c1 v + c2 v^2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fp6 {
+    pub c0: Fp2,
+    pub c1: Fp2,
+    pub c2: Fp2,
+}
+
+impl Fp6 {
+    pub const ZERO: Fp6 = Fp6 {
+        c0: Fp2::ZERO,
+        c1: Fp2::ZERO,
+        c2: Fp2::ZERO,
+    };
+    pub const ONE: Fp6 = Fp6 {
+        c0: Fp2 {
+            c0: Fp::ONE,
+            c1: Fp::ZERO,
+        },
+        c1: Fp2::ZERO,
+        c2: Fp2::ZERO,
+    };
+    /// v itself.
+    pub const V: Fp6 = Fp6 {
+        c0: Fp2::ZERO,
+        c1: Fp2 {
+            c0: Fp::ONE,
+            c1: Fp::ZERO,
+        },
+        c2: Fp2::ZERO,
+    };
+
+    pub fn add(&self, rhs: &Fp6) -> Fp6 {
+        Fp6 {
+            c0: self.c0.add(&rhs.c0),
+            c1: self.c1.add(&rhs.c1),
+            c2: self.c2.add(&rhs.c2),
+        }
+    }
+
+    pub fn sub(&self, rhs: &Fp6) -> Fp6 {
+        Fp6 {
+            c0: self.c0.sub(&rhs.c0),
+            c1: self.c1.sub(&rhs.c1),
+            c2: self.c2.sub(&rhs.c2),
+        }
+    }
+
+    pub fn mul(&self, rhs: &Fp6) -> Fp6 {
+        let (a, b) = (self, rhs);
+        Fp6 {
+            c0: a
+                .c0
+                .mul(&b.c0)
+                .add(&a.c1.mul(&b.c2).add(&a.c2.mul(&b.c1)).mul_by_nonresidue()),
+            c1: a
+                .c0
+                .mul(&b.c1)
+                .add(&a.c1.mul(&b.c0))
+                .add(&a.c2.mul(&b.c2).mul_by_nonresidue()),
+            c2: a.c0.mul(&b.c2).add(&a.c1.mul(&b.c1)).add(&a.c2.mul(&b.c0)),
+        }
+    }
+
+    pub fn neg(&self) -> Fp6 {
+        Fp6 {
+            c0: self.c0.neg(),
+            c1: self.c1.neg(),
+            c2: self.c2.neg(),
+        }
+    }
+
+    /// Times v.
+    pub fn mul_by_nonresidue(&self) -> Fp6 {
+        Fp6 {
+            c0: self.c2.mul_by_nonresidue(),
+            c1: self.c0,
+            c2: self.c1,
+        }
+    }
+
+    /// Times `c1 v`.
+    fn mul_by_1(&self, c1: &Fp2) -> Fp6 {
+        Fp6 {
+            c0: self.c2.mul(c1).mul_by_nonresidue(),
+            c1: self.c0.mul(c1),
+            c2: self.c1.mul(c1),
+        }
+    }
+
+    /// Times `c0 + c1 v`.
+    fn mul_by_01(&self, c0: &Fp2, c1: &Fp2) -> Fp6 {
+        Fp6 {
+            c0: self.c0.mul(c0).add(&self.c2.mul(c1).mul_by_nonresidue()),
+            c1: self.c0.mul(c1).add(&self.c1.mul(c0)),
+            c2: self.c1.mul(c1).add(&self.c2.mul(c0)),
+        }
+    }
+
+    /// `self^p`.
+    fn frobenius_map(&self) -> Fp6 {
+        // (u + 1)^((p - 1) / 3) and (u + 1)^((2p - 2) / 3), in Montgomery
+        // form.
+        const V: Fp = Fp([
+            0xcd03_c9e4_8671_f071,
+            0x5dab_2246_1fcd_a5d2,
+            0x5870_42af_d385_1b95,
+            0x8eb6_0ebe_01ba_cb9e,
+            0x03f9_7d6e_83d0_50d2,
+            0x18f0_2065_5463_8741,
+        ]);
+        const V2: Fp = Fp([
+            0x890d_c9e4_8675_45c3,
+            0x2af3_2253_3285_a5d5,
+            0x5088_0866_309b_7e2c,
+            0xa20d_1b8c_7e88_1024,
+            0x14e4_f04f_e2db_9068,
+            0x14e5_6d3f_1564_853a,
+        ]);
+        Fp6 {
+            c0: self.c0.frobenius_map(),
+            c1: self.c1.frobenius_map().mul(&Fp2 {
+                c0: Fp::ZERO,
+                c1: V,
+            }),
+            c2: self.c2.frobenius_map().scale(&V2),
+        }
+    }
+
+    /// Zero for zero.
+    pub fn invert(&self) -> Fp6 {
+        let (a0, a1, a2) = (self.c0, self.c1, self.c2);
+        let c0 = a0.mul(&a0).sub(&a1.mul(&a2).mul_by_nonresidue());
+        let c1 = a2.mul(&a2).mul_by_nonresidue().sub(&a0.mul(&a1));
+        let c2 = a1.mul(&a1).sub(&a0.mul(&a2));
+        let t = a1
+            .mul(&c2)
+            .add(&a2.mul(&c1))
+            .mul_by_nonresidue()
+            .add(&a0.mul(&c0))
+            .invert();
+        Fp6 {
+            c0: c0.mul(&t),
+            c1: c1.mul(&t),
+            c2: c2.mul(&t),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.c0.is_zero() & self.c1.is_zero() & self.c2.is_zero()
+    }
+
+    fn select(a: &Fp6, b: &Fp6, mask: u64) -> Fp6 {
+        Fp6 {
+            c0: Fp2::select(&a.c0, &b.c0, mask),
+            c1: Fp2::select(&a.c1, &b.c1, mask),
+            c2: Fp2::select(&a.c2, &b.c2, mask),
+        }
+    }
+
+    fn coefficients(&self) -> [Fp; 6] {
+        [
+            self.c0.c0, self.c0.c1, self.c1.c0, self.c1.c1, self.c2.c0, self.c2.c1,
+        ]
+    }
+
+    fn from_coefficients(c: &[Fp]) -> Fp6 {
+        let fp2 = |i: usize| Fp2 {
+            c0: c[i],
+            c1: c[i + 1],
+        };
+        Fp6 {
+            c0: fp2(0),
+            c1: fp2(2),
+            c2: fp2(4),
+        }
+    }
+
+    /// The six coefficients in canonical big-endian bytes, `c0.c0` first.
+    pub fn to_bytes(self) -> [u8; 288] {
+        let mut bytes = [0u8; 288];
+        for (chunk, c) in bytes.chunks_exact_mut(48).zip(self.coefficients()) {
+            chunk.copy_from_slice(&c.to_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; 288]) -> Option<Fp6> {
+        let coefficients = bytes
+            .chunks_exact(48)
+            .map(Fp::from_bytes)
+            .collect::<Option<Vec<_>>>()?;
+        Some(Fp6::from_coefficients(&coefficients))
+    }
+}
+
+/// `c0 + c1 w`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fp12 {
+    pub c0: Fp6,
+    pub c1: Fp6,
+}
+
+impl Fp12 {
+    pub const ONE: Fp12 = Fp12 {
+        c0: Fp6::ONE,
+        c1: Fp6::ZERO,
+    };
+
+    pub fn mul(&self, rhs: &Fp12) -> Fp12 {
+        let aa = self.c0.mul(&rhs.c0);
+        let bb = self.c1.mul(&rhs.c1);
+        let c1 = self.c0.add(&self.c1).mul(&rhs.c0.add(&rhs.c1));
+        Fp12 {
+            c0: bb.mul_by_nonresidue().add(&aa),
+            c1: c1.sub(&aa).sub(&bb),
+        }
+    }
+
+    pub fn square(&self) -> Fp12 {
+        let ab = self.c0.mul(&self.c1);
+        let c0 = self
+            .c1
+            .mul_by_nonresidue()
+            .add(&self.c0)
+            .mul(&self.c0.add(&self.c1))
+            .sub(&ab)
+            .sub(&ab.mul_by_nonresidue());
+        Fp12 {
+            c0,
+            c1: ab.add(&ab),
+        }
+    }
+
+    /// Times the sparse `c0 + c1 v + c4 v w` of a line.
+    pub fn mul_by_014(&self, c0: &Fp2, c1: &Fp2, c4: &Fp2) -> Fp12 {
+        let aa = self.c0.mul_by_01(c0, c1);
+        let bb = self.c1.mul_by_1(c4);
+        let c1 = self
+            .c1
+            .add(&self.c0)
+            .mul_by_01(c0, &c1.add(c4))
+            .sub(&aa)
+            .sub(&bb);
+        Fp12 {
+            c0: bb.mul_by_nonresidue().add(&aa),
+            c1,
+        }
+    }
+
+    /// `c0 - c1 w`, the inverse of an element of Gt.
+    pub fn conjugate(&self) -> Fp12 {
+        Fp12 {
+            c0: self.c0,
+            c1: self.c1.neg(),
+        }
+    }
+
+    /// Zero for zero.
+    pub fn invert(&self) -> Fp12 {
+        let t = self
+            .c0
+            .mul(&self.c0)
+            .sub(&self.c1.mul(&self.c1).mul_by_nonresidue())
+            .invert();
+        Fp12 {
+            c0: self.c0.mul(&t),
+            c1: self.c1.mul(&t.neg()),
+        }
+    }
+
+    /// `self^p`.
+    pub fn frobenius_map(&self) -> Fp12 {
+        // (u + 1)^((p - 1) / 6), in Montgomery form.
+        const W: Fp2 = Fp2 {
+            c0: Fp([
+                0x0708_9552_b319_d465,
+                0xc669_5f92_b50a_8313,
+                0x97e8_3ccc_d117_228f,
+                0xa35b_aeca_b2dc_29ee,
+                0x1ce3_93ea_5daa_ce4d,
+                0x08f2_220f_b0fb_66eb,
+            ]),
+            c1: Fp([
+                0xb2f6_6aad_4ce5_d646,
+                0x5842_a06b_fc49_7cec,
+                0xcf48_95d4_2599_d394,
+                0xc11b_9cba_40a8_e8d0,
+                0x2e38_13cb_e5a0_de89,
+                0x110e_efda_8884_7faf,
+            ]),
+        };
+        let c1 = self.c1.frobenius_map();
+        Fp12 {
+            c0: self.c0.frobenius_map(),
+            c1: Fp6 {
+                c0: c1.c0.mul(&W),
+                c1: c1.c1.mul(&W),
+                c2: c1.c2.mul(&W),
+            },
+        }
+    }
+
+    /// `a` where `mask` is all ones, `b` where it is zero.
+    pub fn select(a: &Fp12, b: &Fp12, mask: u64) -> Fp12 {
+        Fp12 {
+            c0: Fp6::select(&a.c0, &b.c0, mask),
+            c1: Fp6::select(&a.c1, &b.c1, mask),
+        }
+    }
+
+    /// Compares every coefficient, whether they differ or not.
+    pub fn ct_eq(&self, rhs: &Fp12) -> bool {
+        let coefficients = self
+            .c0
+            .coefficients()
+            .into_iter()
+            .chain(self.c1.coefficients());
+        let other = rhs
+            .c0
+            .coefficients()
+            .into_iter()
+            .chain(rhs.c1.coefficients());
+        coefficients
+            .zip(other)
+            .flat_map(|(a, b)| a.0.into_iter().zip(b.0))
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
+
+    /// The twelve coefficients in canonical big-endian bytes, `c0.c0.c0`
+    /// first.
+    pub fn to_bytes(self) -> [u8; 576] {
+        let mut bytes = [0u8; 576];
+        bytes[..288].copy_from_slice(&self.c0.to_bytes());
+        bytes[288..].copy_from_slice(&self.c1.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; 576]) -> Option<Fp12> {
+        Some(Fp12 {
+            c0: Fp6::from_bytes(bytes[..288].try_into().unwrap())?,
+            c1: Fp6::from_bytes(bytes[288..].try_into().unwrap())?,
+        })
+    }
+}
@@ -0,0 +1,156 @@
+//! Byte encodings and helpers for elements of the target group.
+//!
+//! Gt is written multiplicatively here, as opposed to `bls12_381`'s own
+//! `Gt`. That one keeps its Fp12 private, so the elements here are those of
+//! [`crate::field`], computed by the pairing of [`crate::miller`], which
+//! matches `bls12_381`'s step for step. The uncompressed encoding is the
+//! twelve coefficients as canonical big-endian bytes, in the order
+//! `c0.c0.c0, c0.c0.c1, c0.c1.c0, ..., c1.c2.c1`.
+//!
+//! The compressed form is the torus T2 compression. Elements of Gt are
+//! unitary, `x^-1 = c0 - c1 * w` for `x = c0 + c1 * w`, and every one of
+//! them other than 1 can be written as
+//!
+//! ```text
+//! x = (m + w) / (m - w),   m = (1 + c0) / c1
+//! c0 = (m^2 + v) / (m^2 - v),   c1 = 2m / (m^2 - v)
+//! ```
+//!
+//! so only the Fp6 element `m` is kept. `m = 0` would give -1, which has
+//! order two and isn't in Gt, and encodes the identity instead.
+//!
+//! Decoding either form checks that the result is in Gt, `x^r = 1`.
+use crate::field::{Fp12, Fp6};
+use crate::miller;
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use std::fmt;
+
+/// An element of Gt.
+#[derive(Debug, Clone, Copy)]
+pub struct GtElement(Fp12);
+
+impl GtElement {
+    /// Size of the uncompressed encoding, twelve 48 byte coefficients.
+    pub const BYTES: usize = 576;
+
+    /// Size of the compressed encoding.
+    pub const COMPRESSED_BYTES: usize = 288;
+
+    pub fn identity() -> Self {
+        GtElement(Fp12::ONE)
+    }
+
+    /// `e(G1, G2)`.
+    pub fn generator() -> Self {
+        Self::pairing(&G1Affine::generator(), &G2Affine::generator())
+    }
+
+    /// `e(p, q)`.
+    pub fn pairing(p: &G1Affine, q: &G2Affine) -> Self {
+        Self::from_miller_loop(&miller::miller_loop(p, q, |_, _, _, _| {}))
+    }
+
+    pub(crate) fn from_miller_loop(f: &Fp12) -> Self {
+        GtElement(miller::final_exponentiation(f))
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.0.ct_eq(&Fp12::ONE)
+    }
+
+    /// `self * rhs`.
+    pub fn mul(&self, rhs: &Self) -> Self {
+        GtElement(self.0.mul(&rhs.0))
+    }
+
+    /// `self^-1`.
+    pub fn invert(&self) -> Self {
+        // Elements of Gt are unitary.
+        GtElement(self.0.conjugate())
+    }
+
+    /// `self^exp`.
+    pub fn pow(&self, exp: &Scalar) -> Self {
+        let mut acc = Fp12::ONE;
+        for byte in exp.to_bytes().iter().rev() {
+            for bit in (0..8).rev() {
+                acc = acc.square();
+                let mask = 0u64.wrapping_sub(((byte >> bit) & 1) as u64);
+                acc = Fp12::select(&acc.mul(&self.0), &acc, mask);
+            }
+        }
+        GtElement(acc)
+    }
+
+    /// The canonical encoding of all twelve coefficients.
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_bytes()
+    }
+
+    /// `None` unless every coefficient is canonical and the element is in
+    /// Gt.
+    pub fn from_bytes(bytes: &[u8; Self::BYTES]) -> Option<Self> {
+        in_subgroup(Fp12::from_bytes(bytes)?)
+    }
+
+    /// The canonical encoding of `m`, all zeros for the identity.
+    pub fn to_compressed(&self) -> [u8; Self::COMPRESSED_BYTES] {
+        let x = self.0;
+        // c1 is zero only for the identity, where the inverse is zero too.
+        let m = Fp6::ONE.add(&x.c0).mul(&x.c1.invert());
+        m.to_bytes()
+    }
+
+    /// `None` unless `m` is canonical and decompresses to an element of Gt.
+    pub fn from_compressed(bytes: &[u8; Self::COMPRESSED_BYTES]) -> Option<Self> {
+        let m = Fp6::from_bytes(bytes)?;
+        if m.is_zero() {
+            return Some(Self::identity());
+        }
+
+        let m2 = m.mul(&m);
+        // v isn't a square in Fp6, so the denominator is never zero.
+        let d = m2.sub(&Fp6::V).invert();
+        in_subgroup(Fp12 {
+            c0: m2.add(&Fp6::V).mul(&d),
+            c1: m.add(&m).mul(&d),
+        })
+    }
+}
+
+/// `x^r == 1`, with `r - 1` as the largest scalar.
+fn in_subgroup(x: Fp12) -> Option<GtElement> {
+    let x = GtElement(x);
+    x.pow(&-Scalar::one()).mul(&x).is_identity().then_some(x)
+}
+
+impl Default for GtElement {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Compares all coefficients, whether the first ones differ or not.
+impl PartialEq for GtElement {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Eq for GtElement {}
+
+/// Lowercase hex of the compressed encoding.
+impl fmt::Display for GtElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.to_compressed().iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks `lhs[i] == rhs[i]` for every i. All pairs are compared even after
+/// a mismatch, so the running time only depends on the lengths.
+pub fn batch_eq(lhs: &[GtElement], rhs: &[GtElement]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs).fold(true, |eq, (a, b)| eq & (a == b))
+}
@@ -0,0 +1,40 @@
+//! Helpers for checking pairing equations.
+//!
+//! Verification equations are usually written as `e(A, B) == e(C, D)`.
+//! Computing both sides means two Miller loops and two final exponentiations,
+//! followed by a comparison in Gt. Moving everything to one side gives the
+//! equivalent `e(A, B) * e(-C, D) == 1`, where the Miller loops can be run
+//! together and only a single final exponentiation is needed.
+pub mod explain;
+mod field;
+pub mod gt;
+mod miller;
+pub mod tripartite;
+
+use bls12_381::{multi_miller_loop, G1Affine, G2Affine, G2Prepared};
+use group::Group;
+
+/// Checks `∏ e(P_i, Q_i) == 1`.
+pub fn pairing_product_is_one(terms: &[(G1Affine, G2Affine)]) -> bool {
+    let prepared = terms
+        .iter()
+        .map(|(_, q)| G2Prepared::from(*q))
+        .collect::<Vec<_>>();
+
+    let terms = terms
+        .iter()
+        .zip(&prepared)
+        .map(|((p, _), q)| (p, q))
+        .collect::<Vec<_>>();
+
+    prepared_product_is_one(&terms)
+}
+
+/// Same as [`pairing_product_is_one`] for G2 points whose Miller loop lines
+/// were already computed, e.g. a fixed public key.
+pub fn prepared_product_is_one(terms: &[(&G1Affine, &G2Prepared)]) -> bool {
+    multi_miller_loop(terms)
+        .final_exponentiation()
+        .is_identity()
+        .into()
+}
@@ -1,4 +1,7 @@
-use bls12_381::{pairing, G1Affine, G2Affine, Scalar};
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use pairing::explain;
+use pairing::gt::GtElement;
+use pairing::pairing_product_is_one;
 
 /// To demonstrate the basic property of EC pairing which is:
 ///
@@ -20,13 +23,26 @@ fn main() {
     let R = H * s;
     let RAffine = G2Affine::from(&R);
 
-    let e = pairing(&PAffine, &G2Affine::from(Q + R));
-    println!("e(P, Q + R) = {:?}", e);
+    let e = GtElement::pairing(&PAffine, &G2Affine::from(Q + R));
+    println!("e(P, Q + R) = {}", e);
 
-    let l = pairing(&PAffine, &QAffine);
-    let r = pairing(&PAffine, &RAffine);
-    let t = l + r;
-    println!("e(P, Q) * e(P, R) = {:?}", t);
+    let l = GtElement::pairing(&PAffine, &QAffine);
+    let r = GtElement::pairing(&PAffine, &RAffine);
+    let t = l.mul(&r);
+    println!("e(P, Q) * e(P, R) = {}", t);
 
     assert_eq!(e, t);
+
+    // The same identity written as a product that should be one:
+    // e(P, Q + R) * e(-P, Q) * e(-P, R) = 1
+    let NegP = -PAffine;
+    assert!(pairing_product_is_one(&[
+        (PAffine, G2Affine::from(Q + R)),
+        (NegP, QAffine),
+        (NegP, RAffine),
+    ]));
+    println!("e(P, Q + R) * e(-P, Q) * e(-P, R) = 1");
+
+    // And what a single pairing computes on the way to its result.
+    println!("{}", explain::trace(&PAffine, &QAffine));
 }
@@ -0,0 +1,248 @@
+//! The optimal ate pairing on the Fp12 of [`crate::field`].
+//!
+//! The steps are those of `bls12_381`, so that the Miller loop and the
+//! final exponentiation come out the same, not just their product: the
+//! doubling and addition of Algorithms 26 and 27 of Costello, Lange and
+//! Naehrig (https://eprint.iacr.org/2010/354) on Jacobian coordinates, and
+//! the hard part of the exponentiation as a chain of cyclotomic squarings
+//! and powers of x.
+use crate::explain::{StepKind, BLS_X};
+use crate::field::{Fp, Fp12, Fp2, Fp6};
+use bls12_381::{G1Affine, G2Affine};
+
+/// The coefficients of one line, evaluated at P as
+/// `c + (x_P * b) * v + (y_P * a) * v * w`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Line {
+    pub a: Fp2,
+    pub b: Fp2,
+    pub c: Fp2,
+}
+
+/// A point of G2 as `(x / z^2, y / z^3)`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct G2Jacobian {
+    x: Fp2,
+    y: Fp2,
+    z: Fp2,
+}
+
+impl G2Jacobian {
+    /// Only for points other than the identity, which the running point of
+    /// the loop never is.
+    pub fn to_affine(self) -> G2Affine {
+        let zinv = self.z.invert();
+        let zinv2 = zinv.square();
+        let mut bytes = [0u8; 192];
+        bytes[..96].copy_from_slice(&self.x.mul(&zinv2).to_point_bytes());
+        bytes[96..].copy_from_slice(&self.y.mul(&zinv2.mul(&zinv)).to_point_bytes());
+        G2Affine::from_uncompressed_unchecked(&bytes).unwrap()
+    }
+
+    /// `2T` and the tangent at T.
+    fn double(&mut self) -> Line {
+        let tmp0 = self.x.square();
+        let tmp1 = self.y.square();
+        let tmp2 = tmp1.square();
+        let tmp3 = tmp1.add(&self.x).square().sub(&tmp0).sub(&tmp2).double();
+        let tmp4 = tmp0.double().add(&tmp0);
+        let tmp6 = self.x.add(&tmp4);
+        let tmp5 = tmp4.square();
+        let zsquared = self.z.square();
+        self.x = tmp5.sub(&tmp3).sub(&tmp3);
+        self.z = self.z.add(&self.y).square().sub(&tmp1).sub(&zsquared);
+        self.y = tmp3
+            .sub(&self.x)
+            .mul(&tmp4)
+            .sub(&tmp2.double().double().double());
+        Line {
+            a: self.z.mul(&zsquared).double(),
+            b: tmp4.mul(&zsquared).double().neg(),
+            c: tmp6
+                .square()
+                .sub(&tmp0)
+                .sub(&tmp5)
+                .sub(&tmp1.double().double()),
+        }
+    }
+
+    /// `T + Q` and the line through both.
+    fn add(&mut self, q: &(Fp2, Fp2)) -> Line {
+        let (qx, qy) = q;
+        let zsquared = self.z.square();
+        let ysquared = qy.square();
+        let t0 = zsquared.mul(qx);
+        let t1 = qy
+            .add(&self.z)
+            .square()
+            .sub(&ysquared)
+            .sub(&zsquared)
+            .mul(&zsquared);
+        let t2 = t0.sub(&self.x);
+        let t3 = t2.square();
+        let t4 = t3.double().double();
+        let t5 = t4.mul(&t2);
+        let t6 = t1.sub(&self.y).sub(&self.y);
+        let t9 = t6.mul(qx);
+        let t7 = t4.mul(&self.x);
+        self.x = t6.square().sub(&t5).sub(&t7).sub(&t7);
+        self.z = self.z.add(&t2).square().sub(&zsquared).sub(&t3);
+        let t10 = qy.add(&self.z);
+        let t8 = t7.sub(&self.x).mul(&t6);
+        self.y = t8.sub(&self.y.mul(&t5).double());
+        let t10 = t10.square().sub(&ysquared).sub(&self.z.square());
+        Line {
+            a: self.z.double(),
+            b: t6.neg().double(),
+            c: t9.double().sub(&t10),
+        }
+    }
+}
+
+/// The Miller loop of `e(p, q)`, handing `step` the bit of |x|, the line and
+/// the running point of every step. As in `bls12_381`, it is one when either
+/// point is the identity.
+pub(crate) fn miller_loop(
+    p: &G1Affine,
+    q: &G2Affine,
+    mut step: impl FnMut(u32, StepKind, &Line, &G2Jacobian),
+) -> Fp12 {
+    let identity = bool::from(p.is_identity() | q.is_identity());
+    // The lines are computed for the generators instead, and thrown away.
+    let mask = 0u64.wrapping_sub(identity as u64);
+    let (px, py) = g1_coordinates(p, identity);
+    let q = g2_coordinates(q, identity);
+
+    let mut t = G2Jacobian {
+        x: q.0,
+        y: q.1,
+        z: Fp2::ONE,
+    };
+    let mut f = Fp12::ONE;
+    let top = 63 - BLS_X.leading_zeros();
+    for bit in (0..top).rev() {
+        let line = t.double();
+        f = ell(&f, &line, &px, &py);
+        step(bit, StepKind::Doubling, &line, &t);
+        if (BLS_X >> bit) & 1 == 1 {
+            let line = t.add(&q);
+            f = ell(&f, &line, &px, &py);
+            step(bit, StepKind::Addition, &line, &t);
+        }
+        if bit > 0 {
+            f = f.square();
+        }
+    }
+
+    // x is negative.
+    Fp12::select(&Fp12::ONE, &f.conjugate(), mask)
+}
+
+/// `f * ℓ(P)`.
+fn ell(f: &Fp12, line: &Line, px: &Fp, py: &Fp) -> Fp12 {
+    f.mul_by_014(&line.c, &line.b.scale(px), &line.a.scale(py))
+}
+
+/// The affine coordinates of `p`, or of the generator for `identity`.
+fn g1_coordinates(p: &G1Affine, identity: bool) -> (Fp, Fp) {
+    let point = if identity { G1Affine::generator() } else { *p };
+    let mut bytes = point.to_uncompressed();
+    // The flags, none of which is set for a point other than the identity.
+    bytes[0] &= 0x1f;
+    let x = Fp::from_bytes(&bytes[..48]).expect("Points have canonical coordinates");
+    let y = Fp::from_bytes(&bytes[48..]).expect("Points have canonical coordinates");
+    (x, y)
+}
+
+/// The affine coordinates of `q`, or of the generator for `identity`.
+fn g2_coordinates(q: &G2Affine, identity: bool) -> (Fp2, Fp2) {
+    let point = if identity { G2Affine::generator() } else { *q };
+    let mut bytes = point.to_uncompressed();
+    bytes[0] &= 0x1f;
+    let x = Fp2::from_point_bytes(&bytes[..96]).expect("Points have canonical coordinates");
+    let y = Fp2::from_point_bytes(&bytes[96..]).expect("Points have canonical coordinates");
+    (x, y)
+}
+
+/// `f^((p^12 - 1) / r)`, the easy part `(p^6 - 1)(p^2 + 1)` with Frobenius
+/// maps and an inversion, then the hard part. `f` is never zero.
+pub(crate) fn final_exponentiation(f: &Fp12) -> Fp12 {
+    let mut t0 = *f;
+    for _ in 0..6 {
+        t0 = t0.frobenius_map();
+    }
+    let mut t1 = f.invert();
+    let mut t2 = t0.mul(&t1);
+    t1 = t2;
+    t2 = t2.frobenius_map().frobenius_map();
+    t2 = t2.mul(&t1);
+    t1 = cyclotomic_square(&t2).conjugate();
+    let mut t3 = cyclotomic_exp(&t2);
+    let mut t4 = cyclotomic_square(&t3);
+    let mut t5 = t1.mul(&t3);
+    t1 = cyclotomic_exp(&t5);
+    t0 = cyclotomic_exp(&t1);
+    let mut t6 = cyclotomic_exp(&t0);
+    t6 = t6.mul(&t4);
+    t4 = cyclotomic_exp(&t6);
+    t5 = t5.conjugate();
+    t4 = t4.mul(&t5.mul(&t2));
+    t5 = t2.conjugate();
+    t1 = t1.mul(&t2);
+    t1 = t1.frobenius_map().frobenius_map().frobenius_map();
+    t6 = t6.mul(&t5);
+    t6 = t6.frobenius_map();
+    t3 = t3.mul(&t0);
+    t3 = t3.frobenius_map().frobenius_map();
+    t3 = t3.mul(&t1);
+    t3 = t3.mul(&t6);
+    t3.mul(&t4)
+}
+
+/// `f^x`, x being public.
+fn cyclotomic_exp(f: &Fp12) -> Fp12 {
+    let top = 63 - BLS_X.leading_zeros();
+    let mut acc = *f;
+    for bit in (0..top).rev() {
+        acc = cyclotomic_square(&acc);
+        if (BLS_X >> bit) & 1 == 1 {
+            acc = acc.mul(f);
+        }
+    }
+    acc.conjugate()
+}
+
+/// Squares an element of the cyclotomic subgroup, after Granger and Scott,
+/// Faster Squaring in the Cyclotomic Subgroup of Sixth Degree Extensions
+/// (https://eprint.iacr.org/2009/565).
+fn cyclotomic_square(f: &Fp12) -> Fp12 {
+    // (a + b w^3)^2 in Fp4 = Fp2[w^3] / (w^6 - (u + 1)).
+    fn fp4_square(a: &Fp2, b: &Fp2) -> (Fp2, Fp2) {
+        let t0 = a.square();
+        let t1 = b.square();
+        let c0 = t1.mul_by_nonresidue().add(&t0);
+        let c1 = a.add(b).square().sub(&t0).sub(&t1);
+        (c0, c1)
+    }
+    // 2 * t - z and 2 * t + z.
+    let minus = |t: &Fp2, z: &Fp2| t.sub(z).double().add(t);
+    let plus = |t: &Fp2, z: &Fp2| t.add(z).double().add(t);
+
+    let (z0, z4, z3) = (f.c0.c0, f.c0.c1, f.c0.c2);
+    let (z2, z1, z5) = (f.c1.c0, f.c1.c1, f.c1.c2);
+    let (a0, a1) = fp4_square(&z0, &z1);
+    let (b0, b1) = fp4_square(&z2, &z3);
+    let (c0, c1) = fp4_square(&z4, &z5);
+    Fp12 {
+        c0: Fp6 {
+            c0: minus(&a0, &z0),
+            c1: minus(&b0, &z4),
+            c2: minus(&c0, &z3),
+        },
+        c1: Fp6 {
+            c0: plus(&c1.mul_by_nonresidue(), &z2),
+            c1: plus(&a1, &z1),
+            c2: plus(&b1, &z5),
+        },
+    }
+}
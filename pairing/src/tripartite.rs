@@ -0,0 +1,62 @@
+//! Joux's one round tripartite Diffie-Hellman.
+//!
+//! Alice, Bob and Carol pick secrets a, b and c and publish `aP`, `bP` and
+//! `cP` in a single round. Each of them can then compute the same key using
+//! their own secret and the other two contributions:
+//!
+//! Alice: e(bP, cQ)^a
+//! Bob:   e(aP, cQ)^b
+//! Carol: e(aP, bQ)^c
+//!
+//! All of which are equal to `e(P, Q)^(abc)`. Since BLS12-381 is an
+//! asymmetric pairing, every contribution carries the secret times both
+//! generators.
+use bls12_381::{pairing, G1Affine, G2Affine, Gt, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+
+/// The public message broadcast by each party, `(xP, xQ)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contribution {
+    pub g1: G1Affine,
+    pub g2: G2Affine,
+}
+
+impl Contribution {
+    /// Checks that both halves hide the same secret, `e(xP, Q) == e(P, xQ)`.
+    pub fn is_consistent(&self) -> bool {
+        crate::pairing_product_is_one(&[
+            (self.g1, G2Affine::generator()),
+            (-G1Affine::generator(), self.g2),
+        ])
+    }
+}
+
+/// One of the three participants.
+#[derive(Debug, Clone)]
+pub struct Party {
+    secret: Scalar,
+}
+
+impl Party {
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Party {
+            secret: Scalar::random(rng),
+        }
+    }
+
+    /// The value to broadcast to the other two parties.
+    pub fn contribution(&self) -> Contribution {
+        Contribution {
+            g1: (G1Affine::generator() * self.secret).to_affine(),
+            g2: (G2Affine::generator() * self.secret).to_affine(),
+        }
+    }
+
+    /// Derives the shared key from the other parties' contributions, the
+    /// order of the two contributions doesn't matter.
+    pub fn shared_key(&self, first: &Contribution, second: &Contribution) -> Gt {
+        pairing(&first.g1, &second.g2) * self.secret
+    }
+}
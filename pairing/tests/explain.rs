@@ -0,0 +1,37 @@
+use bls12_381::{multi_miller_loop, G1Affine, G2Affine, G2Prepared, Scalar};
+use group::Curve;
+use pairing::explain::{self, BLS_X, STEPS};
+use pairing::gt::GtElement;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn traces_the_pairing() {
+    let p = (G1Affine::generator() * Scalar::from(12)).to_affine();
+    let q = (G2Affine::generator() * Scalar::from(15)).to_affine();
+    let trace = explain::trace(&p, &q);
+
+    assert_eq!(trace.steps.len(), STEPS);
+    assert_eq!(trace.additions(), BLS_X.count_ones() as usize - 1);
+    // The running point ends at |x| * Q.
+    let end = (q * Scalar::from(BLS_X)).to_affine();
+    assert_eq!(trace.steps.last().unwrap().t, end);
+    assert_eq!(trace.result, GtElement::pairing(&p, &q));
+
+    // Its Debug output prints the coefficients in the order of `to_bytes`.
+    let debug = format!("{:?}", multi_miller_loop(&[(&p, &G2Prepared::from(q))]))
+        .split("0x")
+        .skip(1)
+        .map(|coefficient| coefficient[..96].to_string())
+        .collect::<String>();
+    assert_eq!(hex(&trace.miller_loop), debug);
+}
+
+#[test]
+fn the_identity_pairs_to_one() {
+    let trace = explain::trace(&G1Affine::generator(), &G2Affine::identity());
+    assert_eq!(trace.steps.len(), STEPS);
+    assert!(trace.result.is_identity());
+}
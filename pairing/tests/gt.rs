@@ -0,0 +1,102 @@
+use bls12_381::{pairing, G1Affine, G2Affine, Scalar};
+use group::Curve;
+use pairing::gt::GtElement;
+
+fn samples() -> Vec<GtElement> {
+    let g = GtElement::generator();
+    vec![
+        GtElement::identity(),
+        g,
+        g.pow(&-Scalar::one()),
+        g.pow(&Scalar::from(0x1234_5678)),
+        GtElement::pairing(&G1Affine::generator(), &G2Affine::generator()).pow(&Scalar::from(3)),
+    ]
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn pairing_matches_bls12_381() {
+    // Its Debug output prints the coefficients in the order of `to_bytes`.
+    let p = (G1Affine::generator() * Scalar::from(12)).to_affine();
+    let q = (G2Affine::generator() * Scalar::from(15)).to_affine();
+    for (p, q) in [
+        (p, q),
+        (G1Affine::generator(), G2Affine::generator()),
+        (G1Affine::identity(), q),
+    ] {
+        let debug = format!("{:?}", pairing(&p, &q))
+            .split("0x")
+            .skip(1)
+            .map(|coefficient| coefficient[..96].to_string())
+            .collect::<String>();
+        assert_eq!(hex(&GtElement::pairing(&p, &q).to_bytes()), debug);
+    }
+}
+
+#[test]
+fn is_a_group() {
+    let g = GtElement::generator();
+    let a = Scalar::from(0x1234_5678);
+    assert_eq!(g.pow(&a).mul(&g), g.pow(&(a + Scalar::one())));
+    assert!(g.mul(&g.invert()).is_identity());
+    assert!(g.pow(&Scalar::zero()).is_identity());
+}
+
+#[test]
+fn bytes_round_trip() {
+    for gt in samples() {
+        assert_eq!(GtElement::from_bytes(&gt.to_bytes()), Some(gt));
+    }
+}
+
+#[test]
+fn compressed_round_trip() {
+    for gt in samples() {
+        assert_eq!(GtElement::from_compressed(&gt.to_compressed()), Some(gt));
+    }
+    assert_eq!(
+        GtElement::identity().to_compressed(),
+        [0u8; GtElement::COMPRESSED_BYTES]
+    );
+}
+
+#[test]
+fn compressed_is_unique() {
+    let encodings = samples()
+        .iter()
+        .map(GtElement::to_compressed)
+        .collect::<Vec<_>>();
+    for (i, a) in encodings.iter().enumerate() {
+        assert!(encodings[i + 1..].iter().all(|b| a != b));
+    }
+}
+
+#[test]
+fn rejects_non_canonical_coefficients() {
+    let mut bytes = GtElement::generator().to_bytes();
+    bytes[..48].fill(0xff);
+    assert_eq!(GtElement::from_bytes(&bytes), None);
+
+    let mut compressed = GtElement::generator().to_compressed();
+    compressed[48..96].fill(0xff);
+    assert_eq!(GtElement::from_compressed(&compressed), None);
+}
+
+#[test]
+fn rejects_elements_outside_gt() {
+    // Zero isn't even invertible, and one tweaked coefficient of the
+    // generator leaves the subgroup.
+    assert_eq!(GtElement::from_bytes(&[0; GtElement::BYTES]), None);
+
+    let mut bytes = GtElement::generator().to_bytes();
+    bytes[47] ^= 1;
+    assert_eq!(GtElement::from_bytes(&bytes), None);
+
+    // Every m decompresses to a unitary element, almost none are in Gt.
+    let mut compressed = [0u8; GtElement::COMPRESSED_BYTES];
+    compressed[47] = 1;
+    assert_eq!(GtElement::from_compressed(&compressed), None);
+}
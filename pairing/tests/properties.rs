@@ -0,0 +1,38 @@
+use bls12_381::{pairing, G1Affine, G2Affine, Scalar};
+use group::Curve;
+use pairing::pairing_product_is_one;
+use proptest::prelude::*;
+
+fn scalar() -> impl Strategy<Value = Scalar> {
+    any::<[u8; 32]>().prop_map(|bytes| {
+        let mut wide = [0u8; 64];
+        wide[..32].copy_from_slice(&bytes);
+        Scalar::from_bytes_wide(&wide)
+    })
+}
+
+proptest! {
+    // Every case runs a handful of full pairings.
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn pairing_is_bilinear(a in scalar(), b in scalar()) {
+        let p = G1Affine::generator();
+        let q = G2Affine::generator();
+        let ap = (p * a).to_affine();
+        let bq = (q * b).to_affine();
+
+        prop_assert_eq!(pairing(&ap, &bq), pairing(&p, &q) * (a * b));
+        prop_assert_eq!(pairing(&ap, &q), pairing(&p, &(q * a).to_affine()));
+    }
+
+    #[test]
+    fn product_check_agrees_with_pairing(a in scalar(), b in scalar()) {
+        let p = (G1Affine::generator() * a).to_affine();
+        let q = (G2Affine::generator() * b).to_affine();
+        let pq = (G1Affine::generator() * (a * b)).to_affine();
+
+        prop_assert!(pairing_product_is_one(&[(p, q), (-pq, G2Affine::generator())]));
+        prop_assert!(!pairing_product_is_one(&[(p, q), (pq, G2Affine::generator())]) || a * b == Scalar::zero());
+    }
+}
@@ -0,0 +1,58 @@
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use group::Curve;
+use pairing::tripartite::{Contribution, Party};
+
+#[test]
+fn all_parties_derive_the_same_key() {
+    let rng = &mut rand::thread_rng();
+    let (alice, bob, carol) = (Party::random(rng), Party::random(rng), Party::random(rng));
+    let (a, b, c) = (
+        alice.contribution(),
+        bob.contribution(),
+        carol.contribution(),
+    );
+    assert!(a.is_consistent() && b.is_consistent() && c.is_consistent());
+
+    let key = alice.shared_key(&b, &c);
+    assert_eq!(alice.shared_key(&c, &b), key);
+    assert_eq!(bob.shared_key(&a, &c), key);
+    assert_eq!(bob.shared_key(&c, &a), key);
+    assert_eq!(carol.shared_key(&a, &b), key);
+    assert_eq!(carol.shared_key(&b, &a), key);
+}
+
+#[test]
+fn keys_differ_between_sessions() {
+    let rng = &mut rand::thread_rng();
+    let alice = Party::random(rng);
+    let b = Party::random(rng).contribution();
+    let (c, d) = (
+        Party::random(rng).contribution(),
+        Party::random(rng).contribution(),
+    );
+
+    assert_ne!(alice.shared_key(&b, &c), alice.shared_key(&b, &d));
+}
+
+#[test]
+fn inconsistent_contribution_is_detected() {
+    let rng = &mut rand::thread_rng();
+    let honest = Party::random(rng).contribution();
+    let other = Party::random(rng).contribution();
+    let mixed = Contribution {
+        g1: honest.g1,
+        g2: other.g2,
+    };
+    assert!(!mixed.is_consistent());
+
+    let doubled = Contribution {
+        g1: (honest.g1 * Scalar::from(2)).to_affine(),
+        g2: honest.g2,
+    };
+    assert!(!doubled.is_consistent());
+    assert!(Contribution {
+        g1: G1Affine::generator(),
+        g2: G2Affine::generator(),
+    }
+    .is_consistent());
+}
@@ -0,0 +1,91 @@
+//! The Pedersen hash to Jubjub in an R1CS. A chunk costs one constraint to
+//! look its magnitude up in the window table, `b0 * b1`, the table being
+//! constants, one to negate it with `b2`, and seven for the complete
+//! addition to the running sum, the first one excepted.
+use crate::jubjub::{self, Point};
+use crate::{bits, Parameters, PedersenHashError};
+use bls12_381::Scalar;
+use r1cs::{ConstraintSystem, LinearCombination};
+
+/// The coordinates of a Jubjub point.
+pub type PointVar = (LinearCombination, LinearCombination);
+
+/// Allocates the bits of the input, least significant first in each byte,
+/// each constrained to be 0 or 1.
+pub fn alloc_bits(cs: &mut ConstraintSystem, input: &[u8]) -> Vec<LinearCombination> {
+    bits(input)
+        .into_iter()
+        .enumerate()
+        .map(|(i, bit)| {
+            let bit = cs.alloc(Scalar::from(bit as u64));
+            cs.enforce(format!("pedersen hash bit {} is boolean", i), bit, bit, bit);
+            bit.into()
+        })
+        .collect()
+}
+
+/// Constrains the hash of the bits, which must be boolean and as many as
+/// in whole bytes for the hash to be the one of [`Parameters::hash`].
+pub fn hash(
+    cs: &mut ConstraintSystem,
+    parameters: &Parameters<Point>,
+    bits: &[LinearCombination],
+) -> Result<PointVar, PedersenHashError> {
+    parameters.check_len(bits.len().div_ceil(8))?;
+    let mut sum: Option<PointVar> = None;
+    for (i, (chunk, table)) in bits.chunks(3).zip(&parameters.tables).enumerate() {
+        let bit = |k: usize| {
+            chunk
+                .get(k)
+                .cloned()
+                .unwrap_or_else(LinearCombination::zero)
+        };
+        let (b0, b1, b2) = (bit(0), bit(1), bit(2));
+        let both = cs.mul(
+            format!("pedersen hash chunk {} b0 * b1", i),
+            b0.clone(),
+            b1.clone(),
+        );
+        // t_0 + b0 * (t_1 - t_0) + b1 * (t_2 - t_0) + b0 * b1 * (t_3 - t_2 - t_1 + t_0)
+        let lookup = |coordinate: fn(&Point) -> Scalar| {
+            let [t0, t1, t2, t3] = table.map(|point| coordinate(&point));
+            LinearCombination::constant(t0)
+                + b0.clone() * (t1 - t0)
+                + b1.clone() * (t2 - t0)
+                + both * (t3 - t2 - t1 + t0)
+        };
+        let (x, y) = (lookup(|p| p.x), lookup(|p| p.y));
+        let sign = LinearCombination::constant(Scalar::one()) - b2 * Scalar::from(2);
+        let x = cs.mul(format!("pedersen hash chunk {} sign", i), x, sign);
+        let point = (x.into(), y);
+        sum = Some(match sum {
+            None => point,
+            Some(sum) => add(cs, sum, point),
+        });
+    }
+    Ok(sum.unwrap_or_else(|| {
+        (
+            LinearCombination::zero(),
+            LinearCombination::constant(Scalar::one()),
+        )
+    }))
+}
+
+/// Constrains the sum of two Jubjub points with the complete addition law.
+pub fn add(cs: &mut ConstraintSystem, (x1, y1): PointVar, (x2, y2): PointVar) -> PointVar {
+    let x1y2 = cs.mul("jubjub x1 * y2", x1.clone(), y2.clone());
+    let y1x2 = cs.mul("jubjub y1 * x2", y1.clone(), x2.clone());
+    let x1x2 = cs.mul("jubjub x1 * x2", x1, x2);
+    let y1y2 = cs.mul("jubjub y1 * y2", y1, y2);
+    let t = cs.mul("jubjub x1 * x2 * y1 * y2", x1y2, y1x2);
+
+    let one = LinearCombination::constant(Scalar::one());
+    let (x_denominator, y_denominator) = (one.clone() + t * jubjub::d(), one - t * jubjub::d());
+    let (x_numerator, y_numerator) = (x1y2 + y1x2, y1y2 + x1x2);
+    let x = cs.value(&x_numerator) * cs.value(&x_denominator).invert().unwrap();
+    let y = cs.value(&y_numerator) * cs.value(&y_denominator).invert().unwrap();
+    let (x, y) = (cs.alloc(x), cs.alloc(y));
+    cs.enforce("jubjub x3", x, x_denominator, x_numerator);
+    cs.enforce("jubjub y3", y, y_denominator, y_numerator);
+    (x.into(), y.into())
+}
@@ -0,0 +1,110 @@
+//! Jubjub, the twisted Edwards curve of Zcash Sapling over the scalar
+//! field of BLS12-381,
+//!
+//! -x² + y² = 1 + d * x² * y²,    d = -10240 / 10241
+//!
+//! whose points are pairs of circuit values. As d is not a square and -1
+//! is, the addition law
+//!
+//! (x1, y1) + (x2, y2) = ((x1 * y2 + y1 * x2) / (1 + d * x1 * x2 * y1 * y2),
+//!                        (y1 * y2 + x1 * x2) / (1 - d * x1 * x2 * y1 * y2))
+//!
+//! is complete: no denominator vanishes, doubling and the identity `(0, 1)`
+//! included. The group has order 8 * r_J for a prime r_J of 252 bits, only
+//! the subgroup of order r_J being used. Only what the Pedersen hash needs
+//! is here, points are kept in affine coordinates.
+use bls12_381::Scalar;
+use sha2::{Digest, Sha512};
+use std::ops::{Add, Neg};
+use std::sync::OnceLock;
+
+/// `(x, y)` in affine coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    pub x: Scalar,
+    pub y: Scalar,
+}
+
+/// The curve constant d.
+pub fn d() -> Scalar {
+    static D: OnceLock<Scalar> = OnceLock::new();
+    *D.get_or_init(|| -Scalar::from(10240) * Scalar::from(10241).invert().unwrap())
+}
+
+impl Point {
+    pub fn is_on_curve(&self) -> bool {
+        let (xx, yy) = (self.x.square(), self.y.square());
+        yy - xx == Scalar::one() + d() * xx * yy
+    }
+
+    /// The point with the given y and an x of the given parity, if any.
+    fn from_y(y: Scalar, odd: bool) -> Option<Point> {
+        // x² = (y² - 1) / (d * y² + 1)
+        let yy = y.square();
+        let xx =
+            (yy - Scalar::one()) * Option::<Scalar>::from((d() * yy + Scalar::one()).invert())?;
+        let x = Option::<Scalar>::from(xx.sqrt())?;
+        let x = if (x.to_bytes()[0] & 1 == 1) == odd {
+            x
+        } else {
+            -x
+        };
+        Some(Point { x, y })
+    }
+}
+
+impl crate::Point for Point {
+    fn identity() -> Self {
+        Point {
+            x: Scalar::zero(),
+            y: Scalar::one(),
+        }
+    }
+
+    fn double(&self) -> Self {
+        *self + *self
+    }
+
+    /// Try-and-increment: y is SHA-512 of the label and a counter reduced
+    /// into the field, until it is on the curve and, times the cofactor,
+    /// not the identity.
+    fn hash_to_point(label: &[u8]) -> Self {
+        (0u32..)
+            .find_map(|counter| {
+                let mut wide = [0u8; 64];
+                wide.copy_from_slice(
+                    &Sha512::new()
+                        .chain(b"zk-lab/pedersen_hash/jubjub")
+                        .chain(label)
+                        .chain(counter.to_le_bytes())
+                        .finalize(),
+                );
+                let point = Point::from_y(Scalar::from_bytes_wide(&wide), false)?;
+                let point = point.double().double().double();
+                (point != Point::identity()).then_some(point)
+            })
+            .expect("Half of the y are on the curve")
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        let t = d() * self.x * other.x * self.y * other.y;
+        let x = (self.x * other.y + self.y * other.x) * (Scalar::one() + t).invert().unwrap();
+        let y = (self.y * other.y + self.x * other.x) * (Scalar::one() - t).invert().unwrap();
+        Point { x, y }
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point {
+            x: -self.x,
+            y: self.y,
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! The Pedersen hash of Zcash Sapling: byte strings to a group element, as
+//! a sum of fixed generators with small coefficients, cheap to prove in a
+//! circuit where [`hash_to_curve`](bls12_381::hash_to_curve) is not.
+//!
+//! The bits of the input, least significant first in each byte, padded
+//! with zeros to a multiple of 3, are split into chunks `(b0, b1, b2)`,
+//! each encoded as the non-zero integer
+//!
+//! enc(b0, b1, b2) = (1 + b0 + 2 * b1) * (1 - 2 * b2)    ∈ {±1, ±2, ±3, ±4}
+//!
+//! and the chunks into segments of [`CHUNKS_PER_SEGMENT`]. With a
+//! generator `G_j` for segment j, hashed from a personalization string and
+//! j, the hash is
+//!
+//! H = ∑_j ∑_i enc(c_ji) * 2^(4 * i) * G_j
+//!
+//! The points `k * 2^(4 * i) * G_j` for k from 1 to 4 are precomputed in a
+//! window table, so hashing only adds table entries, negated or not.
+//!
+//! A collision gives `∑_j (s_j - s'_j) * G_j = 0` for the segment scalars
+//! `s_j = ∑_i enc(c_ji) * 2^(4 * i)`, a discrete logarithm relation
+//! between the generators unless every `s_j = s'_j` in the group order.
+//! With digits in `{±1, ..., ±4}` in base 16, the `s_j` are distinct
+//! integers for distinct chunk sequences of one segment, the sign of the
+//! top digit deciding the sign of the sum, and 63 chunks keep
+//! `|s_j| < 4 * 16^63 / 15`, below half of the group order of both G1 and
+//! Jubjub, so they are distinct in the group too. Inputs of different
+//! lengths have different chunk counts, and as no chunk encodes to zero,
+//! different scalars: the hash is collision resistant for byte strings of
+//! any length, under the discrete logarithm assumption. It is not a random
+//! oracle, being linear in the segments.
+//!
+//! The coordinates of G1 live in the base field of BLS12-381, not in its
+//! scalar field where the circuits of this lab work, so the [`gadget`]
+//! hashes to [`jubjub`] instead, the twisted Edwards curve defined over
+//! the scalar field, with the same encoding.
+pub mod gadget;
+pub mod jubjub;
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::G1Projective;
+use std::fmt;
+use std::ops::{Add, Neg};
+
+/// Chunks in a segment, the most that keep the segment scalars distinct.
+pub const CHUNKS_PER_SEGMENT: usize = 63;
+
+const DST: &[u8] = b"ZK_LAB_PEDERSEN_HASH_XMD:SHA-256_SSWU_RO_";
+
+/// A group to hash to.
+pub trait Point: Copy + PartialEq + Add<Output = Self> + Neg<Output = Self> {
+    fn identity() -> Self;
+
+    fn double(&self) -> Self;
+
+    /// A point of the prime order subgroup for the label, with no known
+    /// discrete logarithm relation to the points of other labels.
+    fn hash_to_point(label: &[u8]) -> Self;
+}
+
+impl Point for G1Projective {
+    fn identity() -> Self {
+        G1Projective::identity()
+    }
+
+    fn double(&self) -> Self {
+        G1Projective::double(self)
+    }
+
+    fn hash_to_point(label: &[u8]) -> Self {
+        <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(label, DST)
+    }
+}
+
+/// The window tables of the generators of a personalization, for inputs
+/// of up to a maximum length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameters<P> {
+    max_len: usize,
+    /// `[1, 2, 3, 4] * 2^(4 * i) * G_j` for the chunk i of segment j, in
+    /// order of the chunks.
+    tables: Vec<[P; 4]>,
+}
+
+impl<P: Point> Parameters<P> {
+    /// The tables for inputs of up to `max_len` bytes, the generators
+    /// being hashed from the personalization and their index.
+    pub fn new(personalization: &[u8], max_len: usize) -> Self {
+        let chunks = (8 * max_len).div_ceil(3);
+        let mut tables = Vec::with_capacity(chunks);
+        for segment in 0..chunks.div_ceil(CHUNKS_PER_SEGMENT) {
+            let mut label = personalization.to_vec();
+            label.extend_from_slice(&(segment as u32).to_le_bytes());
+            let mut base = P::hash_to_point(&label);
+            let count = (chunks - segment * CHUNKS_PER_SEGMENT).min(CHUNKS_PER_SEGMENT);
+            for _ in 0..count {
+                let double = base.double();
+                tables.push([base, double, double + base, double.double()]);
+                base = double.double().double().double();
+            }
+        }
+        Parameters { max_len, tables }
+    }
+
+    /// The longest input, in bytes.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Hashes the input.
+    pub fn hash(&self, input: &[u8]) -> Result<P, PedersenHashError> {
+        self.check_len(input.len())?;
+        let bits = bits(input);
+        Ok(bits
+            .chunks(3)
+            .zip(&self.tables)
+            .fold(P::identity(), |h, (chunk, table)| {
+                let bit = |k: usize| chunk.get(k).copied().unwrap_or(false);
+                let point = table[bit(0) as usize + 2 * bit(1) as usize];
+                if bit(2) {
+                    h + -point
+                } else {
+                    h + point
+                }
+            }))
+    }
+
+    fn check_len(&self, len: usize) -> Result<(), PedersenHashError> {
+        if len > self.max_len {
+            return Err(PedersenHashError::TooLong {
+                len,
+                max: self.max_len,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The bits of the input, least significant first in each byte.
+pub fn bits(input: &[u8]) -> Vec<bool> {
+    input
+        .iter()
+        .flat_map(|byte| (0..8).map(move |k| (byte >> k) & 1 == 1))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PedersenHashError {
+    /// An input longer than the parameters support, in bytes.
+    TooLong { len: usize, max: usize },
+}
+
+impl fmt::Display for PedersenHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PedersenHashError::TooLong { len, max } => write!(
+                f,
+                "can't hash {} bytes, the parameters stop at {}",
+                len, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PedersenHashError {}
@@ -0,0 +1,113 @@
+use bls12_381::{G1Projective, Scalar};
+use pedersen_hash::jubjub::Point as Jubjub;
+use pedersen_hash::{bits, gadget, Parameters, PedersenHashError, Point, CHUNKS_PER_SEGMENT};
+use r1cs::ConstraintSystem;
+use rand::{thread_rng, RngCore};
+
+/// `∑_j ∑_i enc(c_ji) * 2^(4 * i) * G_j` without the window tables.
+fn naive<P: Point>(personalization: &[u8], input: &[u8]) -> P {
+    let mut bits = bits(input);
+    bits.resize(bits.len().div_ceil(3) * 3, false);
+    let mut sum = P::identity();
+    for (j, segment) in bits.chunks(3 * CHUNKS_PER_SEGMENT).enumerate() {
+        let mut label = personalization.to_vec();
+        label.extend_from_slice(&(j as u32).to_le_bytes());
+        let mut base = P::hash_to_point(&label);
+        for chunk in segment.chunks(3) {
+            let magnitude = 1 + chunk[0] as usize + 2 * chunk[1] as usize;
+            let mut point = (1..magnitude).fold(base, |point, _| point + base);
+            if chunk[2] {
+                point = -point;
+            }
+            sum = sum + point;
+            base = base.double().double().double().double();
+        }
+    }
+    sum
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut input = vec![0u8; len];
+    thread_rng().fill_bytes(&mut input);
+    input
+}
+
+#[test]
+fn matches_the_definition_over_g1() {
+    let parameters = Parameters::<G1Projective>::new(b"test", 40);
+    for len in [0, 1, 23, 24, 40] {
+        let input = random_bytes(len);
+        assert_eq!(
+            parameters.hash(&input).unwrap(),
+            naive::<G1Projective>(b"test", &input)
+        );
+    }
+}
+
+#[test]
+fn matches_the_definition_over_jubjub() {
+    let parameters = Parameters::<Jubjub>::new(b"test", 30);
+    let input = random_bytes(30);
+    let hash = parameters.hash(&input).unwrap();
+    assert!(hash.is_on_curve());
+    assert_eq!(hash, naive::<Jubjub>(b"test", &input));
+}
+
+#[test]
+fn separates_inputs_and_personalizations() {
+    let parameters = Parameters::<G1Projective>::new(b"one", 4);
+    let other = Parameters::<G1Projective>::new(b"two", 4);
+    let hash = parameters.hash(b"abc").unwrap();
+    assert_ne!(hash, parameters.hash(b"abd").unwrap());
+    assert_ne!(hash, parameters.hash(b"abc\0").unwrap());
+    assert_ne!(hash, parameters.hash(b"ab").unwrap());
+    assert_ne!(hash, other.hash(b"abc").unwrap());
+    assert_eq!(
+        parameters.hash(b"abcde"),
+        Err(PedersenHashError::TooLong { len: 5, max: 4 })
+    );
+}
+
+#[test]
+fn generates_jubjub_points_of_prime_order() {
+    let point = Jubjub::hash_to_point(b"generator");
+    assert!(point.is_on_curve());
+    assert_ne!(point, Jubjub::identity());
+    assert_eq!(point + Jubjub::identity(), point);
+    assert_eq!(point + -point, Jubjub::identity());
+    // Not of an order dividing the cofactor 8.
+    let small = (0..3).fold(point, |point, _| point.double());
+    assert_ne!(small, Jubjub::identity());
+}
+
+#[test]
+fn the_gadget_matches_the_hash() {
+    let parameters = Parameters::<Jubjub>::new(b"test", 32);
+    let input = random_bytes(32);
+    let mut cs = ConstraintSystem::new();
+    let bits = gadget::alloc_bits(&mut cs, &input);
+    let (x, y) = gadget::hash(&mut cs, &parameters, &bits).unwrap();
+    let hash = parameters.hash(&input).unwrap();
+    assert_eq!((cs.value(&x), cs.value(&y)), (hash.x, hash.y));
+    cs.is_satisfied().unwrap();
+    // 256 bits, 86 chunks: 2 constraints for the first, 9 for the others.
+    assert_eq!(cs.num_constraints(), 256 + 2 + 85 * 9);
+}
+
+#[test]
+fn the_gadget_rejects_a_wrong_hash() {
+    let parameters = Parameters::<Jubjub>::new(b"test", 2);
+    let mut cs = ConstraintSystem::new();
+    let bits = gadget::alloc_bits(&mut cs, b"hi");
+    let (x, y) = gadget::hash(&mut cs, &parameters, &bits).unwrap();
+    let hash = parameters.hash(b"ho").unwrap();
+    let (hx, hy) = (cs.alloc_input(hash.x), cs.alloc_input(hash.y));
+    cs.enforce("x", x, r1cs::LinearCombination::constant(Scalar::one()), hx);
+    cs.enforce("y", y, r1cs::LinearCombination::constant(Scalar::one()), hy);
+    assert!(cs.is_satisfied().is_err());
+    let bits = gadget::alloc_bits(&mut cs, b"hey");
+    assert_eq!(
+        gadget::hash(&mut cs, &parameters, &bits),
+        Err(PedersenHashError::TooLong { len: 3, max: 2 })
+    );
+}
@@ -0,0 +1,40 @@
+//! The polynomial arithmetic of the prover on top of the `poly` crate.
+//! Polynomials are given by their coefficients, lowest degree first.
+use bls12_381::Scalar;
+use poly::fft::Domain;
+
+/// `p(x) + b(x) Z_H(x)`, which takes the same values on the domain.
+pub fn blind(domain: &Domain, poly: &[Scalar], blinding: &[Scalar]) -> Vec<Scalar> {
+    let mut blinded = poly.to_vec();
+    blinded.resize(
+        poly.len().max(domain.size() + blinding.len()),
+        Scalar::zero(),
+    );
+    for (i, b) in blinding.iter().enumerate() {
+        blinded[i + domain.size()] += b;
+        blinded[i] -= b;
+    }
+    blinded
+}
+
+/// `acc(x) += s * a(x)`
+pub fn add_scaled(acc: &mut Vec<Scalar>, a: &[Scalar], s: Scalar) {
+    if acc.len() < a.len() {
+        acc.resize(a.len(), Scalar::zero());
+    }
+    for (c, a) in acc.iter_mut().zip(a) {
+        *c += a * s;
+    }
+}
+
+/// `p(c x)`
+pub fn scale_variable(poly: &[Scalar], c: Scalar) -> Vec<Scalar> {
+    let mut power = Scalar::one();
+    poly.iter()
+        .map(|a| {
+            let scaled = a * power;
+            power *= c;
+            scaled
+        })
+        .collect()
+}
@@ -0,0 +1,205 @@
+//! Circuits as rows of gates. Each gate reads three wires a, b and c and
+//! holds when
+//!
+//! q_L * a + q_R * b + q_O * c + q_M * a * b + q_C = 0
+//!
+//! Wires are variables: two wires of the same variable, in the same gate or
+//! in different ones, must carry the same value, which is what the
+//! permutation argument enforces.
+//!
+//! A gate can also look its wire a up in the table of the circuit, see
+//! [`crate::lookup`].
+use bls12_381::Scalar;
+
+/// A value of the circuit, given to as many wires as it is used by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Variable(pub(crate) usize);
+
+/// The coefficients of a gate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Selectors {
+    pub left: Scalar,
+    pub right: Scalar,
+    pub output: Scalar,
+    pub mul: Scalar,
+    pub constant: Scalar,
+    /// `q_K`, 1 if a is looked up in the table, 0 otherwise.
+    pub lookup: Scalar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gate {
+    pub selectors: Selectors,
+    pub wires: [Variable; 3],
+}
+
+impl Gate {
+    fn holds(&self, values: &[Scalar], table: &[Scalar]) -> bool {
+        let [a, b, c] = self.wires.map(|wire| values[wire.0]);
+        let q = &self.selectors;
+        let found = q.lookup == Scalar::zero() || (q.lookup == Scalar::one() && table.contains(&a));
+        found
+            && q.left * a + q.right * b + q.output * c + q.mul * a * b + q.constant
+                == Scalar::zero()
+    }
+}
+
+/// A circuit with the values of its variables, a setup only needs the gates
+/// so it can be given any values.
+#[derive(Debug, Clone)]
+pub struct Circuit {
+    /// The unused wires of gates, and those of the padding rows, are given
+    /// the variable 0.
+    values: Vec<Scalar>,
+    public: Vec<Variable>,
+    gates: Vec<Gate>,
+    table: Vec<Scalar>,
+}
+
+impl Default for Circuit {
+    fn default() -> Self {
+        Circuit {
+            values: vec![Scalar::zero()],
+            public: Vec::new(),
+            gates: Vec::new(),
+            table: vec![Scalar::zero()],
+        }
+    }
+}
+
+impl Circuit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A private value.
+    pub fn alloc(&mut self, value: Scalar) -> Variable {
+        self.values.push(value);
+        Variable(self.values.len() - 1)
+    }
+
+    /// A value the verifier is given, in the order they were allocated.
+    pub fn public_input(&mut self, value: Scalar) -> Variable {
+        let variable = self.alloc(value);
+        self.public.push(variable);
+        variable
+    }
+
+    /// Adds a gate on the wires, a wire it doesn't use can be any variable.
+    pub fn gate(&mut self, selectors: Selectors, wires: [Variable; 3]) {
+        self.gates.push(Gate { selectors, wires });
+    }
+
+    /// `a + b`
+    pub fn add(&mut self, a: Variable, b: Variable) -> Variable {
+        let sum = self.alloc(self.value(a) + self.value(b));
+        let selectors = Selectors {
+            left: Scalar::one(),
+            right: Scalar::one(),
+            output: -Scalar::one(),
+            ..Selectors::default()
+        };
+        self.gate(selectors, [a, b, sum]);
+        sum
+    }
+
+    /// `a * b`
+    pub fn mul(&mut self, a: Variable, b: Variable) -> Variable {
+        let product = self.alloc(self.value(a) * self.value(b));
+        let selectors = Selectors {
+            output: -Scalar::one(),
+            mul: Scalar::one(),
+            ..Selectors::default()
+        };
+        self.gate(selectors, [a, b, product]);
+        product
+    }
+
+    /// A variable fixed to `value` by the circuit.
+    pub fn constant(&mut self, value: Scalar) -> Variable {
+        let variable = self.alloc(value);
+        let selectors = Selectors {
+            left: Scalar::one(),
+            constant: -value,
+            ..Selectors::default()
+        };
+        self.gate(selectors, [variable, Variable(0), Variable(0)]);
+        variable
+    }
+
+    /// Constrains `a` and `b` to be equal.
+    pub fn assert_equal(&mut self, a: Variable, b: Variable) {
+        let selectors = Selectors {
+            left: Scalar::one(),
+            right: -Scalar::one(),
+            ..Selectors::default()
+        };
+        self.gate(selectors, [a, b, Variable(0)]);
+    }
+
+    /// Constrains `a` to be in the table.
+    pub fn lookup(&mut self, a: Variable) {
+        let selectors = Selectors {
+            lookup: Scalar::one(),
+            ..Selectors::default()
+        };
+        self.gate(selectors, [a, Variable(0), Variable(0)]);
+    }
+
+    /// Sets the values lookups are checked against, `{0}` until then. See
+    /// [`crate::lookup::range`] for range checks.
+    ///
+    /// # Panics
+    ///
+    /// If the table is empty.
+    pub fn set_table(&mut self, table: Vec<Scalar>) {
+        assert!(!table.is_empty(), "A table needs at least one value");
+        self.table = table;
+    }
+
+    pub fn table(&self) -> &[Scalar] {
+        &self.table
+    }
+
+    pub fn value(&self, variable: Variable) -> Scalar {
+        self.values[variable.0]
+    }
+
+    /// The values of the public inputs.
+    pub fn public_inputs(&self) -> Vec<Scalar> {
+        self.public.iter().map(|input| self.value(*input)).collect()
+    }
+
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    /// The rows of the circuit: a gate `a = x_i` for each public input,
+    /// which the verifier completes with the value, followed by the gates.
+    pub(crate) fn rows(&self) -> Vec<Gate> {
+        let inputs = self.public.iter().map(|input| Gate {
+            selectors: Selectors {
+                left: Scalar::one(),
+                ..Selectors::default()
+            },
+            wires: [*input, Variable(0), Variable(0)],
+        });
+        inputs.chain(self.gates.iter().copied()).collect()
+    }
+
+    pub(crate) fn num_inputs(&self) -> usize {
+        self.public.len()
+    }
+
+    pub(crate) fn values(&self) -> &[Scalar] {
+        &self.values
+    }
+
+    /// The index of the first gate that doesn't hold with the values
+    /// allocated, if any.
+    pub fn unsatisfied(&self) -> Option<usize> {
+        self.gates
+            .iter()
+            .position(|gate| !gate.holds(&self.values, &self.table))
+    }
+}
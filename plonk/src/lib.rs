@@ -0,0 +1,610 @@
+//! A minimal PLONK over BLS12-381, with the polynomials committed to with
+//! the `kzg` crate.
+//!
+//! Unlike Groth16, the setup isn't tied to a circuit: one [`Srs`] serves
+//! every circuit small enough for it, and the per-circuit preprocessing
+//! only commits to public polynomials, so anyone can run it.
+//!
+//! The rows of the circuit are spread over the domain H of the n-th roots
+//! of unity, see [`circuit`] for the gates. With the wire polynomials a, b
+//! and c, the selectors q and the public inputs folded into `PI(x)`, the
+//! gates all hold iff
+//!
+//! q_M a b + q_L a + q_R b + q_O c + q_C + PI
+//!
+//! vanishes on H. The wires of a variable are tied together by the
+//! permutation σ of the 3n wires that cycles through them: after the
+//! challenges β and γ the prover builds the running product z of
+//!
+//! (w_i + β id(i) + γ) / (w_i + β σ(i) + γ)
+//!
+//! over the rows, which only comes back to 1 if the values are preserved
+//! by σ. Its recurrence between `z(x)` and `z(ω x)` is checked on H along
+//! with `z(1) = 1`. The rows with the lookup selector look their wire a up
+//! in the table of the circuit, with a Plookup argument: a second running
+//! product, after the challenges δ and ε, over the sorted values h1 and h2,
+//! see [`lookup`]. The checks are combined with the challenge α and
+//! divided by `Z_H(x) = x^n - 1` into the quotient t. At the challenge ζ,
+//! every polynomial is opened, the running products, the table and h1 and
+//! h2 at `ω ζ` as well, with a single combined KZG proof, and the verifier
+//! checks the identity on the values.
+//!
+//! The wire polynomials, h1 and h2, and the running products are blinded
+//! with random multiples of `Z_H`, so the openings don't leak the values.
+//! It is the unoptimized PLONK: the selectors and σ are opened rather than
+//! linearized, and t isn't split, which needs an SRS of degree `3n + 5`.
+use bls12_381::{G1Affine, Scalar};
+use group::ff::Field;
+use kzg::batch::{CombinedProof, Evaluations};
+use kzg::{Commitment, KzgError, Srs};
+use rand_core::{CryptoRng, RngCore};
+use sigma::transcript::Transcript;
+use std::fmt;
+
+mod arithmetic;
+pub mod circuit;
+pub mod lookup;
+
+pub use circuit::{Circuit, Gate, Selectors, Variable};
+
+use poly::fft::Domain;
+
+const DOMAIN: &[u8] = b"zk-lab/plonk";
+
+/// The cosets `k_j H` of the three columns of wires are disjoint: 7
+/// generates the whole multiplicative group, so neither 7 nor 49 is in H.
+const COSETS: [u64; 3] = [1, 7, 49];
+
+/// What the verifier needs for a circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyingKey {
+    pub srs: Srs,
+    /// The size of the domain.
+    pub size: usize,
+    pub inputs: usize,
+    /// `q_L`, `q_R`, `q_O`, `q_M`, `q_C` and `q_K`.
+    pub selectors: [Commitment; 6],
+    /// σ, one polynomial per column.
+    pub permutation: [Commitment; 3],
+    /// The table of the lookups.
+    pub table: Commitment,
+}
+
+/// What the prover needs for a circuit: the polynomials of the verifying
+/// key, and how they were laid out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvingKey {
+    pub vk: VerifyingKey,
+    selectors: [Vec<Scalar>; 6],
+    permutation: [Vec<Scalar>; 3],
+    /// σ on the domain, column by column.
+    sigma: [Vec<Scalar>; 3],
+    table: Vec<Scalar>,
+    /// The table on the domain.
+    table_values: Vec<Scalar>,
+}
+
+/// The values of the polynomials at ζ, in the order they are opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofEvaluations {
+    pub wires: [Scalar; 3],
+    pub selectors: [Scalar; 6],
+    pub permutation: [Scalar; 3],
+    pub quotient: Scalar,
+    pub z: Scalar,
+    /// `z(ω ζ)`
+    pub z_shifted: Scalar,
+    pub table: Scalar,
+    pub table_shifted: Scalar,
+    /// h1 and h2.
+    pub sorted: [Scalar; 2],
+    pub sorted_shifted: [Scalar; 2],
+    /// The running product of the lookups.
+    pub lookup_z: Scalar,
+    pub lookup_z_shifted: Scalar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proof {
+    pub wires: [Commitment; 3],
+    /// h1 and h2.
+    pub sorted: [Commitment; 2],
+    pub z: Commitment,
+    pub lookup_z: Commitment,
+    pub quotient: Commitment,
+    pub evaluations: ProofEvaluations,
+    pub opening: CombinedProof,
+}
+
+/// The polynomials of the circuit on the domain: the selectors, the
+/// variable of each wire and the table.
+struct Layout {
+    domain: Domain,
+    selectors: [Vec<Scalar>; 6],
+    wires: [Vec<Variable>; 3],
+    table: Vec<Scalar>,
+}
+
+impl Layout {
+    fn new(circuit: &Circuit) -> Result<Self, PlonkError> {
+        let rows = circuit.rows();
+        // The last row can't look anything up, and the quotient of the
+        // lookups only fits in the SRS with two rows or more.
+        let spare = rows
+            .last()
+            .is_some_and(|row| row.selectors.lookup != Scalar::zero());
+        let size = (rows.len() + spare as usize)
+            .max(circuit.table().len())
+            .max(2);
+        let domain = Domain::new(size).ok_or(PlonkError::TooLarge)?;
+        let padding = Gate {
+            selectors: Selectors::default(),
+            wires: [Variable(0); 3],
+        };
+        let rows = rows
+            .into_iter()
+            .chain(std::iter::repeat(padding))
+            .take(domain.size())
+            .collect::<Vec<_>>();
+
+        let selector = |f: fn(&Selectors) -> Scalar| -> Vec<Scalar> {
+            rows.iter().map(|row| f(&row.selectors)).collect()
+        };
+        Ok(Layout {
+            domain,
+            selectors: [
+                selector(|q| q.left),
+                selector(|q| q.right),
+                selector(|q| q.output),
+                selector(|q| q.mul),
+                selector(|q| q.constant),
+                selector(|q| q.lookup),
+            ],
+            wires: [0, 1, 2].map(|column| rows.iter().map(|row| row.wires[column]).collect()),
+            table: lookup::column(circuit.table(), domain.size()),
+        })
+    }
+
+    /// σ on the domain: each wire points to the next wire of its variable,
+    /// the last one back to the first.
+    fn sigma(&self) -> [Vec<Scalar>; 3] {
+        let elements = self.domain.elements();
+        let id = |(column, row): (usize, usize)| Scalar::from(COSETS[column]) * elements[row];
+
+        let mut cycles = std::collections::HashMap::<Variable, Vec<(usize, usize)>>::new();
+        for (column, wires) in self.wires.iter().enumerate() {
+            for (row, variable) in wires.iter().enumerate() {
+                cycles.entry(*variable).or_default().push((column, row));
+            }
+        }
+        let mut sigma = [0, 1, 2].map(|_| vec![Scalar::zero(); self.domain.size()]);
+        for wires in cycles.values() {
+            for (i, (column, row)) in wires.iter().enumerate() {
+                sigma[*column][*row] = id(wires[(i + 1) % wires.len()]);
+            }
+        }
+        sigma
+    }
+}
+
+/// Preprocesses the circuit, whose values are ignored, for the SRS.
+pub fn setup(srs: &Srs, circuit: &Circuit) -> Result<ProvingKey, PlonkError> {
+    let layout = Layout::new(circuit)?;
+    let degree = 3 * layout.domain.size() + 5;
+    if srs.max_degree() < degree {
+        return Err(PlonkError::Kzg(KzgError::Degree {
+            degree,
+            max: srs.max_degree(),
+        }));
+    }
+
+    let sigma = layout.sigma();
+    let selectors = layout
+        .selectors
+        .each_ref()
+        .map(|values| layout.domain.ifft(values));
+    let permutation = sigma.each_ref().map(|values| layout.domain.ifft(values));
+    let table = layout.domain.ifft(&layout.table);
+    let commit = |poly: &Vec<Scalar>| srs.commit(poly);
+    let vk = VerifyingKey {
+        srs: srs.clone(),
+        size: layout.domain.size(),
+        inputs: circuit.num_inputs(),
+        selectors: [
+            commit(&selectors[0])?,
+            commit(&selectors[1])?,
+            commit(&selectors[2])?,
+            commit(&selectors[3])?,
+            commit(&selectors[4])?,
+            commit(&selectors[5])?,
+        ],
+        permutation: [
+            commit(&permutation[0])?,
+            commit(&permutation[1])?,
+            commit(&permutation[2])?,
+        ],
+        table: commit(&table)?,
+    };
+    Ok(ProvingKey {
+        vk,
+        selectors,
+        permutation,
+        sigma,
+        table,
+        table_values: layout.table,
+    })
+}
+
+/// Proves that the values allocated in the circuit satisfy it.
+pub fn prove<R: RngCore + CryptoRng>(
+    pk: &ProvingKey,
+    circuit: &Circuit,
+    rng: &mut R,
+) -> Result<Proof, PlonkError> {
+    if let Some(gate) = circuit.unsatisfied() {
+        return Err(PlonkError::Unsatisfied { gate });
+    }
+    let layout = Layout::new(circuit)?;
+    if layout.domain.size() != pk.vk.size
+        || circuit.num_inputs() != pk.vk.inputs
+        || layout
+            .selectors
+            .iter()
+            .zip(&pk.selectors)
+            .any(|(values, poly)| layout.domain.ifft(values) != *poly)
+        || layout.sigma() != pk.sigma
+        || layout.table != pk.table_values
+    {
+        return Err(PlonkError::Shape);
+    }
+    let domain = layout.domain;
+    let srs = &pk.vk.srs;
+    let commit = |poly: &[Scalar]| srs.commit(poly);
+    let mut blinding = |count: usize| {
+        (0..count)
+            .map(|_| Scalar::random(&mut *rng))
+            .collect::<Vec<_>>()
+    };
+
+    let inputs = circuit.public_inputs();
+    let mut transcript = transcript(&pk.vk, &inputs);
+
+    // Round 1: the wires, and the sorted values of the lookups.
+    let values = layout.wires.each_ref().map(|wires| {
+        wires
+            .iter()
+            .map(|wire| circuit.values()[wire.0])
+            .collect::<Vec<_>>()
+    });
+    let wires = values
+        .each_ref()
+        .map(|values| arithmetic::blind(&domain, &domain.ifft(values), &blinding(2)));
+    let wire_commitments = [commit(&wires[0])?, commit(&wires[1])?, commit(&wires[2])?];
+    let looked_up = lookup::looked_up(&layout.selectors[5], &values[0], &layout.table);
+    let (h1, h2) = lookup::sorted(&looked_up, &layout.table);
+    let sorted =
+        [&h1, &h2].map(|values| arithmetic::blind(&domain, &domain.ifft(values), &blinding(3)));
+    let sorted_commitments = [commit(&sorted[0])?, commit(&sorted[1])?];
+    for commitment in &wire_commitments {
+        transcript.append_point(b"wire", &commitment.0);
+    }
+    for commitment in &sorted_commitments {
+        transcript.append_point(b"sorted", &commitment.0);
+    }
+    let beta = transcript.challenge_scalar(b"beta");
+    let gamma = transcript.challenge_scalar(b"gamma");
+    let delta = transcript.challenge_scalar(b"delta");
+    let epsilon = transcript.challenge_scalar(b"epsilon");
+
+    // Round 2: the running products of the permutation and the lookups.
+    let elements = domain.elements();
+    let mut running = vec![Scalar::one()];
+    for row in 0..domain.size() - 1 {
+        let (numerator, denominator) = (0..3).fold(
+            (Scalar::one(), Scalar::one()),
+            |(numerator, denominator), column| {
+                let value = values[column][row] + gamma;
+                (
+                    numerator * (value + beta * Scalar::from(COSETS[column]) * elements[row]),
+                    denominator * (value + beta * pk.sigma[column][row]),
+                )
+            },
+        );
+        // The challenges only hit a zero with a negligible probability.
+        let denominator =
+            Option::<Scalar>::from(denominator.invert()).ok_or(PlonkError::Degenerate)?;
+        running.push(running[row] * numerator * denominator);
+    }
+    let z = arithmetic::blind(&domain, &domain.ifft(&running), &blinding(3));
+    let z_commitment = commit(&z)?;
+    let running = lookup::running_product(&looked_up, &layout.table, (&h1, &h2), delta, epsilon)?;
+    let lookup_z = arithmetic::blind(&domain, &domain.ifft(&running), &blinding(3));
+    let lookup_z_commitment = commit(&lookup_z)?;
+    transcript.append_point(b"z", &z_commitment.0);
+    transcript.append_point(b"lookup z", &lookup_z_commitment.0);
+    let alpha = transcript.challenge_scalar(b"alpha");
+
+    // Round 3: the quotient.
+    let mut public = vec![Scalar::zero(); domain.size()];
+    for (value, input) in public.iter_mut().zip(&inputs) {
+        *value = -input;
+    }
+    let [q_l, q_r, q_o, q_m, q_c, q_k] = &pk.selectors;
+    let mut numerator = poly::mul(&poly::mul(q_m, &wires[0]), &wires[1]);
+    arithmetic::add_scaled(&mut numerator, &poly::mul(q_l, &wires[0]), Scalar::one());
+    arithmetic::add_scaled(&mut numerator, &poly::mul(q_r, &wires[1]), Scalar::one());
+    arithmetic::add_scaled(&mut numerator, &poly::mul(q_o, &wires[2]), Scalar::one());
+    arithmetic::add_scaled(&mut numerator, q_c, Scalar::one());
+    arithmetic::add_scaled(&mut numerator, &domain.ifft(&public), Scalar::one());
+
+    // z(x) ∏ (w(x) + β k x + γ) - z(ω x) ∏ (w(x) + β σ(x) + γ)
+    let mut identity = z.clone();
+    let mut permuted = arithmetic::scale_variable(&z, domain.omega());
+    for column in 0..3 {
+        let mut shifted = wires[column].clone();
+        arithmetic::add_scaled(
+            &mut shifted,
+            &[gamma, beta * Scalar::from(COSETS[column])],
+            Scalar::one(),
+        );
+        identity = poly::mul(&identity, &shifted);
+
+        let mut shifted = wires[column].clone();
+        arithmetic::add_scaled(&mut shifted, &pk.permutation[column], beta);
+        arithmetic::add_scaled(&mut shifted, &[gamma], Scalar::one());
+        permuted = poly::mul(&permuted, &shifted);
+    }
+    arithmetic::add_scaled(&mut numerator, &identity, alpha);
+    arithmetic::add_scaled(&mut numerator, &permuted, -alpha);
+
+    // (z(x) - 1) L_0(x)
+    let mut first = vec![Scalar::zero(); domain.size()];
+    first[0] = Scalar::one();
+    let mut shifted = z.clone();
+    shifted[0] -= Scalar::one();
+    arithmetic::add_scaled(
+        &mut numerator,
+        &poly::mul(&shifted, &domain.ifft(&first)),
+        alpha.square(),
+    );
+
+    let lookup_polys = lookup::Polynomials {
+        wire: &wires[0],
+        selector: q_k,
+        table: &pk.table,
+        sorted: [&sorted[0], &sorted[1]],
+        z: &lookup_z,
+    };
+    let lookups = lookup::identities(&domain, &lookup_polys, (delta, epsilon), alpha);
+    arithmetic::add_scaled(&mut numerator, &lookups, alpha.square() * alpha);
+
+    let (quotient, remainder) = domain.divide_by_vanishing(&numerator);
+    assert!(
+        poly::division::is_zero(&remainder),
+        "The identities hold on the domain for a satisfied circuit"
+    );
+    let quotient_commitment = commit(&quotient)?;
+    transcript.append_point(b"quotient", &quotient_commitment.0);
+    let zeta = transcript.challenge_scalar(b"zeta");
+
+    // Round 4: the openings.
+    let mut polys = wires.to_vec();
+    polys.extend(pk.selectors.iter().cloned());
+    polys.extend(pk.permutation.iter().cloned());
+    polys.push(quotient);
+    let mut points = vec![vec![zeta]; polys.len()];
+    polys.push(z);
+    polys.push(pk.table.clone());
+    polys.extend(sorted);
+    polys.push(lookup_z);
+    points.resize(polys.len(), vec![zeta, domain.omega() * zeta]);
+    let (claims, opening) = srs.open_combined(&polys, &points)?;
+    let value = |i: usize| claims[i].values[0];
+    let shifted = |i: usize| claims[i].values[1];
+
+    Ok(Proof {
+        wires: wire_commitments,
+        sorted: sorted_commitments,
+        z: z_commitment,
+        lookup_z: lookup_z_commitment,
+        quotient: quotient_commitment,
+        evaluations: ProofEvaluations {
+            wires: [value(0), value(1), value(2)],
+            selectors: [value(3), value(4), value(5), value(6), value(7), value(8)],
+            permutation: [value(9), value(10), value(11)],
+            quotient: value(12),
+            z: value(13),
+            z_shifted: shifted(13),
+            table: value(14),
+            table_shifted: shifted(14),
+            sorted: [value(15), value(16)],
+            sorted_shifted: [shifted(15), shifted(16)],
+            lookup_z: value(17),
+            lookup_z_shifted: shifted(17),
+        },
+        opening,
+    })
+}
+
+/// Checks a proof against the public inputs, in the order they were
+/// allocated.
+pub fn verify(vk: &VerifyingKey, inputs: &[Scalar], proof: &Proof) -> Result<(), PlonkError> {
+    if inputs.len() != vk.inputs {
+        return Err(PlonkError::Inputs {
+            expected: vk.inputs,
+            got: inputs.len(),
+        });
+    }
+    let domain = Domain::new(vk.size).ok_or(PlonkError::TooLarge)?;
+
+    let mut transcript = transcript(vk, inputs);
+    for commitment in &proof.wires {
+        transcript.append_point(b"wire", &commitment.0);
+    }
+    for commitment in &proof.sorted {
+        transcript.append_point(b"sorted", &commitment.0);
+    }
+    let beta = transcript.challenge_scalar(b"beta");
+    let gamma = transcript.challenge_scalar(b"gamma");
+    let delta = transcript.challenge_scalar(b"delta");
+    let epsilon = transcript.challenge_scalar(b"epsilon");
+    transcript.append_point(b"z", &proof.z.0);
+    transcript.append_point(b"lookup z", &proof.lookup_z.0);
+    let alpha = transcript.challenge_scalar(b"alpha");
+    transcript.append_point(b"quotient", &proof.quotient.0);
+    let zeta = transcript.challenge_scalar(b"zeta");
+
+    // On H the quotient identity holds for any quotient.
+    if domain.vanishing_at(zeta) == Scalar::zero() {
+        return Err(PlonkError::Invalid);
+    }
+    // PI(ζ) = -∑ x_i L_i(ζ), and L_0(ζ) and L_{n-1}(ζ).
+    let public = inputs
+        .iter()
+        .enumerate()
+        .fold(Scalar::zero(), |acc, (i, input)| {
+            acc - input * domain.lagrange_at(i, zeta)
+        });
+    let first = domain.lagrange_at(0, zeta);
+    let last = domain.lagrange_at(vk.size - 1, zeta);
+
+    let e = &proof.evaluations;
+    let [a, b, c] = e.wires;
+    let [q_l, q_r, q_o, q_m, q_c, q_k] = e.selectors;
+    let gates = q_m * a * b + q_l * a + q_r * b + q_o * c + q_c + public;
+    let (identity, permuted) = (0..3).fold((e.z, e.z_shifted), |(identity, permuted), column| {
+        let value = e.wires[column] + gamma;
+        (
+            identity * (value + beta * Scalar::from(COSETS[column]) * zeta),
+            permuted * (value + beta * e.permutation[column]),
+        )
+    });
+    let values = lookup::Values {
+        wire: a,
+        selector: q_k,
+        table: [e.table, e.table_shifted],
+        sorted: [
+            [e.sorted[0], e.sorted_shifted[0]],
+            [e.sorted[1], e.sorted_shifted[1]],
+        ],
+        z: [e.lookup_z, e.lookup_z_shifted],
+    };
+    let lookups = lookup::identities_at(
+        &domain,
+        zeta,
+        &values,
+        (delta, epsilon),
+        alpha,
+        (first, last),
+    );
+    let combined = gates
+        + alpha * (identity - permuted)
+        + alpha.square() * (e.z - Scalar::one()) * first
+        + alpha.square() * alpha * lookups;
+    if combined != e.quotient * domain.vanishing_at(zeta) {
+        return Err(PlonkError::Invalid);
+    }
+
+    let claim = |commitment: &Commitment, value: Scalar| Evaluations {
+        commitment: *commitment,
+        points: vec![zeta],
+        values: vec![value],
+    };
+    let shifted_claim = |commitment: &Commitment, value: Scalar, shifted: Scalar| Evaluations {
+        commitment: *commitment,
+        points: vec![zeta, domain.omega() * zeta],
+        values: vec![value, shifted],
+    };
+    let mut claims = proof
+        .wires
+        .iter()
+        .zip(e.wires)
+        .chain(vk.selectors.iter().zip(e.selectors))
+        .chain(vk.permutation.iter().zip(e.permutation))
+        .map(|(commitment, value)| claim(commitment, value))
+        .collect::<Vec<_>>();
+    claims.push(claim(&proof.quotient, e.quotient));
+    claims.push(shifted_claim(&proof.z, e.z, e.z_shifted));
+    claims.push(shifted_claim(&vk.table, e.table, e.table_shifted));
+    for (commitment, (value, shifted)) in proof
+        .sorted
+        .iter()
+        .zip(e.sorted.into_iter().zip(e.sorted_shifted))
+    {
+        claims.push(shifted_claim(commitment, value, shifted));
+    }
+    claims.push(shifted_claim(
+        &proof.lookup_z,
+        e.lookup_z,
+        e.lookup_z_shifted,
+    ));
+    if vk.srs.verify_combined(&claims, &proof.opening) {
+        Ok(())
+    } else {
+        Err(PlonkError::Invalid)
+    }
+}
+
+/// The transcript after the statement: the circuit and its public inputs.
+fn transcript(vk: &VerifyingKey, inputs: &[Scalar]) -> Transcript {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_u64(b"size", vk.size as u64);
+    let commitments = vk
+        .selectors
+        .iter()
+        .chain(&vk.permutation)
+        .chain([&vk.table]);
+    for commitment in commitments {
+        transcript.append_point::<G1Affine>(b"circuit", &commitment.0);
+    }
+    for input in inputs {
+        transcript.append_scalar(b"input", input);
+    }
+    transcript
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlonkError {
+    /// The values allocated don't satisfy the gate.
+    Unsatisfied {
+        gate: usize,
+    },
+    /// The circuit has more rows than the field has roots of unity.
+    TooLarge,
+    /// The circuit isn't the one the key was preprocessed for.
+    Shape,
+    Inputs {
+        expected: usize,
+        got: usize,
+    },
+    /// The SRS is too small for the circuit.
+    Kzg(KzgError),
+    /// A challenge is a zero of a running product's denominator, proving
+    /// again gets other challenges.
+    Degenerate,
+    Invalid,
+}
+
+impl fmt::Display for PlonkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlonkError::Unsatisfied { gate } => write!(f, "gate {} doesn't hold", gate),
+            PlonkError::TooLarge => write!(f, "the circuit doesn't fit in a domain"),
+            PlonkError::Shape => write!(f, "the key isn't for this circuit"),
+            PlonkError::Inputs { expected, got } => {
+                write!(f, "the circuit has {} public inputs, got {}", expected, got)
+            }
+            PlonkError::Kzg(e) => write!(f, "{}", e),
+            PlonkError::Degenerate => write!(f, "a challenge hit a zero, prove again"),
+            PlonkError::Invalid => write!(f, "invalid proof"),
+        }
+    }
+}
+
+impl std::error::Error for PlonkError {}
+
+impl From<KzgError> for PlonkError {
+    fn from(e: KzgError) -> Self {
+        PlonkError::Kzg(e)
+    }
+}
@@ -0,0 +1,194 @@
+//! The Plookup argument: the values the circuit looks up are all in its
+//! table.
+//!
+//! The rows with the lookup selector `q_K` set look their wire a up, the
+//! others look up the table's own value on the row, so that every row
+//! looks up
+//!
+//! f = q_K a + (1 - q_K) t
+//!
+//! with the table t padded to the domain by repeating its last value. The
+//! prover sorts f and t together by the order of t, and splits the result
+//! s into the two halves h1 and h2, which overlap on a value. Then f is in
+//! t iff every pair of neighbours of s is either a repeated value, or a
+//! pair of neighbours of t, which the running product
+//!
+//! (1 + δ)(ε + f_i)(ε(1 + δ) + t_i + δ t_{i+1})
+//! / (ε(1 + δ) + h1_i + δ h1_{i+1})(ε(1 + δ) + h2_i + δ h2_{i+1})
+//!
+//! over the rows checks by coming back to 1. It skips the last row, which
+//! can't look anything up, to wrap around, so that f only has `n - 1`
+//! values.
+use crate::arithmetic;
+use crate::PlonkError;
+use bls12_381::Scalar;
+use poly::fft::Domain;
+use std::collections::HashMap;
+
+/// The table of the values below 2^bits, for range checks.
+pub fn range(bits: u32) -> Vec<Scalar> {
+    (0..1u64 << bits).map(Scalar::from).collect()
+}
+
+/// The table padded to the domain by repeating its last value.
+pub(crate) fn column(table: &[Scalar], size: usize) -> Vec<Scalar> {
+    let mut column = table.to_vec();
+    column.resize(size, *table.last().expect("Tables aren't empty"));
+    column
+}
+
+/// `q_K a + (1 - q_K) t` on the domain.
+pub(crate) fn looked_up(selector: &[Scalar], a: &[Scalar], table: &[Scalar]) -> Vec<Scalar> {
+    selector
+        .iter()
+        .zip(a)
+        .zip(table)
+        .map(|((q, a), t)| q * (a - t) + t)
+        .collect()
+}
+
+/// h1 and h2: f, but for its value on the last row, and t sorted by the
+/// order of t, split in two halves with a value in common.
+pub(crate) fn sorted(looked_up: &[Scalar], table: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>) {
+    let mut first = HashMap::new();
+    for (i, t) in table.iter().enumerate() {
+        first.entry(t.to_bytes()).or_insert(i);
+    }
+    let mut count = vec![0; table.len()];
+    for f in &looked_up[..looked_up.len() - 1] {
+        count[first[&f.to_bytes()]] += 1;
+    }
+    let s: Vec<Scalar> = table
+        .iter()
+        .zip(count)
+        .flat_map(|(t, count)| std::iter::repeat_n(*t, count + 1))
+        .collect();
+    let n = table.len();
+    (s[..n].to_vec(), s[n - 1..].to_vec())
+}
+
+/// The values of the running product on the domain.
+pub(crate) fn running_product(
+    looked_up: &[Scalar],
+    table: &[Scalar],
+    (h1, h2): (&[Scalar], &[Scalar]),
+    delta: Scalar,
+    epsilon: Scalar,
+) -> Result<Vec<Scalar>, PlonkError> {
+    let shift = epsilon * (Scalar::one() + delta);
+    let mut running = vec![Scalar::one()];
+    for i in 0..table.len() - 1 {
+        let numerator = (Scalar::one() + delta)
+            * (epsilon + looked_up[i])
+            * (shift + table[i] + delta * table[i + 1]);
+        let denominator = (shift + h1[i] + delta * h1[i + 1]) * (shift + h2[i] + delta * h2[i + 1]);
+        // The challenges only hit a zero with a negligible probability.
+        let denominator =
+            Option::<Scalar>::from(denominator.invert()).ok_or(PlonkError::Degenerate)?;
+        running.push(running[i] * numerator * denominator);
+    }
+    Ok(running)
+}
+
+/// The polynomials of the argument, in coefficients.
+pub(crate) struct Polynomials<'a> {
+    pub wire: &'a [Scalar],
+    pub selector: &'a [Scalar],
+    pub table: &'a [Scalar],
+    pub sorted: [&'a [Scalar]; 2],
+    pub z: &'a [Scalar],
+}
+
+/// The identities of the argument, which vanish on the domain, combined
+/// with the powers of α from `alpha`:
+///
+/// (x - ω^(n-1)) (Z(x) (1 + δ)(ε + f(x))(ε(1 + δ) + t(x) + δ t(ω x))
+///     - Z(ω x)(ε(1 + δ) + h1(x) + δ h1(ω x))(ε(1 + δ) + h2(x) + δ h2(ω x)))
+/// (Z(x) - 1) L_0(x)
+/// (h1(x) - h2(ω x)) L_{n-1}(x)
+/// (Z(x) - 1) L_{n-1}(x)
+pub(crate) fn identities(
+    domain: &Domain,
+    polys: &Polynomials,
+    (delta, epsilon): (Scalar, Scalar),
+    alpha: Scalar,
+) -> Vec<Scalar> {
+    let omega = domain.omega();
+    let shift = epsilon * (Scalar::one() + delta);
+    // a + δ a(ω x) + ε(1 + δ)
+    let neighbours = |poly: &[Scalar]| {
+        let mut sum = poly.to_vec();
+        arithmetic::add_scaled(&mut sum, &arithmetic::scale_variable(poly, omega), delta);
+        arithmetic::add_scaled(&mut sum, &[shift], Scalar::one());
+        sum
+    };
+
+    // f = q_K (a - t) + t
+    let mut difference = polys.wire.to_vec();
+    arithmetic::add_scaled(&mut difference, polys.table, -Scalar::one());
+    let mut looked_up = poly::mul(polys.selector, &difference);
+    arithmetic::add_scaled(&mut looked_up, polys.table, Scalar::one());
+    arithmetic::add_scaled(&mut looked_up, &[epsilon], Scalar::one());
+
+    let mut product = poly::mul(polys.z, &looked_up);
+    product = poly::mul(&product, &neighbours(polys.table));
+    let mut identity = Vec::new();
+    arithmetic::add_scaled(&mut identity, &product, Scalar::one() + delta);
+    let mut sorted = arithmetic::scale_variable(polys.z, omega);
+    for h in polys.sorted {
+        sorted = poly::mul(&sorted, &neighbours(h));
+    }
+    arithmetic::add_scaled(&mut identity, &sorted, -Scalar::one());
+    let last_row = domain.element(domain.size() - 1);
+    let mut combined = poly::mul(&identity, &[-last_row, Scalar::one()]);
+
+    let lagrange = |i: usize| {
+        let mut values = vec![Scalar::zero(); domain.size()];
+        values[i] = Scalar::one();
+        domain.ifft(&values)
+    };
+    let (first, last) = (lagrange(0), lagrange(domain.size() - 1));
+    let mut z = polys.z.to_vec();
+    z[0] -= Scalar::one();
+    let mut overlap = polys.sorted[0].to_vec();
+    arithmetic::add_scaled(
+        &mut overlap,
+        &arithmetic::scale_variable(polys.sorted[1], omega),
+        -Scalar::one(),
+    );
+    arithmetic::add_scaled(&mut combined, &poly::mul(&z, &first), alpha);
+    arithmetic::add_scaled(&mut combined, &poly::mul(&overlap, &last), alpha.square());
+    arithmetic::add_scaled(&mut combined, &poly::mul(&z, &last), alpha.square() * alpha);
+    combined
+}
+
+/// The values of the polynomials at ζ, and at `ω ζ` for the shifted ones.
+pub(crate) struct Values {
+    pub wire: Scalar,
+    pub selector: Scalar,
+    pub table: [Scalar; 2],
+    pub sorted: [[Scalar; 2]; 2],
+    pub z: [Scalar; 2],
+}
+
+/// [`identities`] at ζ, given `L_0(ζ)` and `L_{n-1}(ζ)`.
+pub(crate) fn identities_at(
+    domain: &Domain,
+    zeta: Scalar,
+    values: &Values,
+    (delta, epsilon): (Scalar, Scalar),
+    alpha: Scalar,
+    (first, last): (Scalar, Scalar),
+) -> Scalar {
+    let shift = epsilon * (Scalar::one() + delta);
+    let neighbours = |[x, shifted]: [Scalar; 2]| x + delta * shifted + shift;
+    let [t, _] = values.table;
+    let looked_up = values.selector * (values.wire - t) + t;
+    let [z, z_shifted] = values.z;
+    let identity = z * (Scalar::one() + delta) * (epsilon + looked_up) * neighbours(values.table)
+        - z_shifted * neighbours(values.sorted[0]) * neighbours(values.sorted[1]);
+    (zeta - domain.element(domain.size() - 1)) * identity
+        + alpha * (z - Scalar::one()) * first
+        + alpha.square() * (values.sorted[0][0] - values.sorted[1][1]) * last
+        + alpha.square() * alpha * (z - Scalar::one()) * last
+}
@@ -0,0 +1,174 @@
+use bls12_381::Scalar;
+use kzg::{KzgError, Srs};
+use plonk::{lookup, prove, setup, verify, Circuit, PlonkError};
+use rand::thread_rng;
+
+/// `x³ + x + 5 = out`, with `out` public.
+fn cubic(x: u64) -> Circuit {
+    let mut circuit = Circuit::new();
+    let x_value = Scalar::from(x);
+    let out = circuit.public_input(x_value * x_value * x_value + x_value + Scalar::from(5));
+    let x = circuit.alloc(x_value);
+    let x_squared = circuit.mul(x, x);
+    let x_cubed = circuit.mul(x_squared, x);
+    let sum = circuit.add(x_cubed, x);
+    let five = circuit.constant(Scalar::from(5));
+    let result = circuit.add(sum, five);
+    circuit.assert_equal(result, out);
+    circuit
+}
+
+#[test]
+fn proves_the_cubic() {
+    let mut rng = thread_rng();
+    // 7 rows, in a domain of 8.
+    let srs = Srs::generate(3 * 8 + 5, &mut rng);
+    let pk = setup(&srs, &cubic(0)).unwrap();
+
+    let proof = prove(&pk, &cubic(3), &mut rng).unwrap();
+    verify(&pk.vk, &[Scalar::from(35)], &proof).unwrap();
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(36)], &proof),
+        Err(PlonkError::Invalid)
+    );
+    assert_eq!(
+        verify(&pk.vk, &[], &proof),
+        Err(PlonkError::Inputs {
+            expected: 1,
+            got: 0
+        })
+    );
+}
+
+#[test]
+fn one_srs_serves_several_circuits() {
+    let mut rng = thread_rng();
+    let srs = Srs::generate(3 * 8 + 5, &mut rng);
+
+    let mut product = Circuit::new();
+    let x = product.alloc(Scalar::from(6));
+    let y = product.alloc(Scalar::from(7));
+    let out = product.public_input(Scalar::from(42));
+    let xy = product.mul(x, y);
+    product.assert_equal(xy, out);
+
+    let pk = setup(&srs, &product).unwrap();
+    let proof = prove(&pk, &product, &mut rng).unwrap();
+    verify(&pk.vk, &[Scalar::from(42)], &proof).unwrap();
+
+    let pk = setup(&srs, &cubic(0)).unwrap();
+    let proof = prove(&pk, &cubic(2), &mut rng).unwrap();
+    verify(&pk.vk, &[Scalar::from(15)], &proof).unwrap();
+}
+
+#[test]
+fn rejects_tampered_proofs() {
+    let mut rng = thread_rng();
+    let srs = Srs::generate(3 * 8 + 5, &mut rng);
+    let pk = setup(&srs, &cubic(0)).unwrap();
+    let proof = prove(&pk, &cubic(3), &mut rng).unwrap();
+
+    let mut tampered = proof;
+    tampered.evaluations.wires[0] += Scalar::one();
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(35)], &tampered),
+        Err(PlonkError::Invalid)
+    );
+    let mut tampered = proof;
+    tampered.wires.swap(0, 1);
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(35)], &tampered),
+        Err(PlonkError::Invalid)
+    );
+}
+
+#[test]
+fn refuses_to_prove_false_statements() {
+    let mut rng = thread_rng();
+    let srs = Srs::generate(3 * 8 + 5, &mut rng);
+    let pk = setup(&srs, &cubic(0)).unwrap();
+
+    let mut circuit = cubic(3);
+    let wrong = circuit.alloc(Scalar::from(4));
+    let five = circuit.constant(Scalar::from(5));
+    circuit.assert_equal(wrong, five);
+    assert_eq!(
+        prove(&pk, &circuit, &mut rng),
+        Err(PlonkError::Unsatisfied { gate: 7 })
+    );
+
+    let mut other = Circuit::new();
+    let x = other.public_input(Scalar::from(2));
+    other.mul(x, x);
+    assert_eq!(prove(&pk, &other, &mut rng), Err(PlonkError::Shape));
+}
+
+#[test]
+fn needs_an_srs_of_degree_3n_plus_5() {
+    let srs = Srs::generate(3 * 8 + 4, &mut thread_rng());
+    assert_eq!(
+        setup(&srs, &cubic(0)),
+        Err(PlonkError::Kzg(KzgError::Degree {
+            degree: 29,
+            max: 28
+        }))
+    );
+}
+
+/// `x * y = out` with x and y below 8, `out` public.
+fn small_product(x: u64, y: u64) -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.set_table(lookup::range(3));
+    let out = circuit.public_input(Scalar::from(x * y));
+    let x = circuit.alloc(Scalar::from(x));
+    let y = circuit.alloc(Scalar::from(y));
+    let xy = circuit.mul(x, y);
+    circuit.assert_equal(xy, out);
+    circuit.lookup(x);
+    circuit.lookup(y);
+    circuit
+}
+
+#[test]
+fn proves_lookups() {
+    let mut rng = thread_rng();
+    // 5 rows and a spare one for the last lookup, in a domain of 8.
+    let srs = Srs::generate(3 * 8 + 5, &mut rng);
+    let pk = setup(&srs, &small_product(0, 0)).unwrap();
+
+    let proof = prove(&pk, &small_product(6, 7), &mut rng).unwrap();
+    verify(&pk.vk, &[Scalar::from(42)], &proof).unwrap();
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(41)], &proof),
+        Err(PlonkError::Invalid)
+    );
+
+    let mut tampered = proof;
+    tampered.evaluations.sorted[1] += Scalar::one();
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(42)], &tampered),
+        Err(PlonkError::Invalid)
+    );
+    let mut tampered = proof;
+    tampered.evaluations.lookup_z_shifted += Scalar::one();
+    assert_eq!(
+        verify(&pk.vk, &[Scalar::from(42)], &tampered),
+        Err(PlonkError::Invalid)
+    );
+}
+
+#[test]
+fn refuses_values_out_of_the_table() {
+    let mut rng = thread_rng();
+    let srs = Srs::generate(3 * 8 + 5, &mut rng);
+    let pk = setup(&srs, &small_product(0, 0)).unwrap();
+    // 3 * 14 = 42 too, but 14 isn't below 8.
+    assert_eq!(
+        prove(&pk, &small_product(3, 14), &mut rng),
+        Err(PlonkError::Unsatisfied { gate: 3 })
+    );
+
+    let mut other = small_product(0, 0);
+    other.set_table(lookup::range(4));
+    assert_eq!(prove(&pk, &other, &mut rng), Err(PlonkError::Shape));
+}
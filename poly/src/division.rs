@@ -0,0 +1,81 @@
+//! Polynomial division, the workhorse of the opening proofs: `f(z) = y`
+//! exactly when `x - z` divides `f(x) - y`, and a polynomial vanishes on a
+//! set of points exactly when their vanishing polynomial divides it. The
+//! remainder is what tells the two cases apart.
+use bls12_381::Scalar;
+
+/// Divides `f(x)` by `x - z` with synthetic division, returns the quotient
+/// and the remainder, which is `f(z)`.
+pub fn divide_by_linear(poly: &[Scalar], z: Scalar) -> (Vec<Scalar>, Scalar) {
+    let mut quotient = vec![Scalar::zero(); poly.len().saturating_sub(1)];
+    let mut carry = Scalar::zero();
+    for (i, a) in poly.iter().enumerate().rev() {
+        let value = *a + carry * z;
+        match i.checked_sub(1) {
+            Some(j) => quotient[j] = value,
+            None => return (quotient, value),
+        }
+        carry = value;
+    }
+    // The zero polynomial.
+    (quotient, Scalar::zero())
+}
+
+/// Long division of `f(x)` by `d(x)`, returns the quotient and the
+/// remainder, of a lower degree than d. `None` if d is zero.
+pub fn divide(poly: &[Scalar], divisor: &[Scalar]) -> Option<(Vec<Scalar>, Vec<Scalar>)> {
+    let divisor = trimmed(divisor);
+    let (leading, lower) = divisor.split_last()?;
+    let inverse = Option::<Scalar>::from(leading.invert())?;
+
+    let mut remainder = trimmed(poly).to_vec();
+    if remainder.len() < divisor.len() {
+        return Some((Vec::new(), remainder));
+    }
+    let mut quotient = vec![Scalar::zero(); remainder.len() - lower.len()];
+    for i in (0..quotient.len()).rev() {
+        let c = remainder[i + lower.len()] * inverse;
+        quotient[i] = c;
+        for (j, d) in lower.iter().enumerate() {
+            remainder[i + j] -= c * d;
+        }
+    }
+    remainder.truncate(lower.len());
+    Some((quotient, trimmed(&remainder).to_vec()))
+}
+
+/// `f(x) / d(x)`, `None` if d doesn't divide f.
+pub fn divide_exact(poly: &[Scalar], divisor: &[Scalar]) -> Option<Vec<Scalar>> {
+    match divide(poly, divisor)? {
+        (quotient, remainder) if remainder.is_empty() => Some(quotient),
+        _ => None,
+    }
+}
+
+/// `∏ (x - z_j)`, which vanishes exactly on the points.
+pub fn vanishing(points: &[Scalar]) -> Vec<Scalar> {
+    let mut poly = vec![Scalar::one()];
+    for z in points {
+        // Multiply by x, then subtract z times the old polynomial.
+        poly.insert(0, Scalar::zero());
+        for i in 0..poly.len() - 1 {
+            let shifted = poly[i + 1] * z;
+            poly[i] -= shifted;
+        }
+    }
+    poly
+}
+
+/// Whether all the coefficients are zero.
+pub fn is_zero(poly: &[Scalar]) -> bool {
+    poly.iter().all(|c| *c == Scalar::zero())
+}
+
+/// The polynomial without its leading zeros.
+fn trimmed(poly: &[Scalar]) -> &[Scalar] {
+    let len = poly
+        .iter()
+        .rposition(|c| *c != Scalar::zero())
+        .map_or(0, |i| i + 1);
+    &poly[..len]
+}
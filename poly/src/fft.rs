@@ -0,0 +1,197 @@
+//! Radix-2 FFTs over the scalar field, also known as NTTs.
+//!
+//! The multiplicative group of the field has a subgroup of order 2^32, so
+//! for any power of two n up to that there is a primitive n-th root of
+//! unity ω and the domain `H = {ω^i}` of its powers. The FFT evaluates a
+//! polynomial of degree below n on all of H in `O(n log n)`, instead of
+//! `O(n²)` one point at a time, and the inverse FFT interpolates the
+//! polynomial back from its values.
+//!
+//! Quotients by `Z_H(x) = x^n - 1` can't be computed from values on H,
+//! where it vanishes, so there are coset FFTs too: the same on `g H`, for
+//! the generator g of the whole multiplicative group, which is disjoint
+//! from H.
+use bls12_381::Scalar;
+use group::ff::PrimeField;
+
+/// The n-th roots of unity, n a power of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Domain {
+    size: usize,
+    omega: Scalar,
+    omega_inverse: Scalar,
+    size_inverse: Scalar,
+}
+
+impl Domain {
+    /// The smallest domain of at least `size` elements, `None` past the
+    /// 2^32 roots of unity of the field.
+    pub fn new(size: usize) -> Option<Self> {
+        let size = size.max(1).checked_next_power_of_two()?;
+        let log = size.trailing_zeros();
+        if log > Scalar::S {
+            return None;
+        }
+        let omega = Scalar::root_of_unity().pow_vartime(&[1 << (Scalar::S - log), 0, 0, 0]);
+        Some(Domain {
+            size,
+            omega,
+            omega_inverse: omega.invert().unwrap(),
+            size_inverse: Scalar::from(size as u64).invert().unwrap(),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The primitive n-th root of unity ω.
+    pub fn omega(&self) -> Scalar {
+        self.omega
+    }
+
+    /// `ω^i`
+    pub fn element(&self, i: usize) -> Scalar {
+        self.omega.pow_vartime(&[i as u64, 0, 0, 0])
+    }
+
+    /// `ω^i` for i in `0..n`.
+    pub fn elements(&self) -> Vec<Scalar> {
+        std::iter::successors(Some(Scalar::one()), |x| Some(x * self.omega))
+            .take(self.size)
+            .collect()
+    }
+
+    /// `Z_H(x) = x^n - 1`
+    pub fn vanishing_at(&self, x: Scalar) -> Scalar {
+        x.pow_vartime(&[self.size as u64, 0, 0, 0]) - Scalar::one()
+    }
+
+    /// The coefficients of `Z_H(x) = x^n - 1`.
+    pub fn vanishing(&self) -> Vec<Scalar> {
+        let mut poly = vec![Scalar::zero(); self.size + 1];
+        poly[0] = -Scalar::one();
+        poly[self.size] = Scalar::one();
+        poly
+    }
+
+    /// Divides `f(x)` by `Z_H(x)`, returns the quotient and the remainder,
+    /// which is zero iff f vanishes on H. Linear rather than a long
+    /// division, as `x^n = 1` modulo `Z_H`.
+    pub fn divide_by_vanishing(&self, poly: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>) {
+        let n = self.size;
+        let mut remainder = poly.to_vec();
+        let mut quotient = vec![Scalar::zero(); poly.len().saturating_sub(n)];
+        for i in (n..poly.len()).rev() {
+            let c = remainder[i];
+            quotient[i - n] = c;
+            remainder[i - n] += c;
+        }
+        remainder.truncate(n);
+        (quotient, remainder)
+    }
+
+    /// `L_i(x) = ω^i (x^n - 1) / (n (x - ω^i))`, the polynomial that is 1 at
+    /// `ω^i` and 0 on the rest of H.
+    pub fn lagrange_at(&self, i: usize, x: Scalar) -> Scalar {
+        let point = self.element(i);
+        let inverse: Option<Scalar> = (Scalar::from(self.size as u64) * (x - point))
+            .invert()
+            .into();
+        // Off H the formula holds, and on H only `ω^i` itself divides by
+        // zero, the numerator vanishing at the others.
+        inverse.map_or(Scalar::one(), |inverse| {
+            point * self.vanishing_at(x) * inverse
+        })
+    }
+
+    /// The values of the polynomial on H, `values[i]` at `ω^i`.
+    ///
+    /// # Panics
+    ///
+    /// If the polynomial has more coefficients than the domain has elements.
+    pub fn fft(&self, coefficients: &[Scalar]) -> Vec<Scalar> {
+        let mut values = self.padded(coefficients);
+        transform(&mut values, self.omega);
+        values
+    }
+
+    /// The polynomial of degree below n taking `values[i]` at `ω^i`.
+    pub fn ifft(&self, values: &[Scalar]) -> Vec<Scalar> {
+        let mut coefficients = self.padded(values);
+        transform(&mut coefficients, self.omega_inverse);
+        for c in &mut coefficients {
+            *c *= self.size_inverse;
+        }
+        coefficients
+    }
+
+    /// The values of the polynomial on `g H`, `values[i]` at `g ω^i`.
+    pub fn coset_fft(&self, coefficients: &[Scalar]) -> Vec<Scalar> {
+        self.fft(&scale(coefficients, Scalar::multiplicative_generator()))
+    }
+
+    /// The polynomial of degree below n taking `values[i]` at `g ω^i`.
+    pub fn coset_ifft(&self, values: &[Scalar]) -> Vec<Scalar> {
+        let inverse = Scalar::multiplicative_generator().invert().unwrap();
+        scale(&self.ifft(values), inverse)
+    }
+
+    fn padded(&self, values: &[Scalar]) -> Vec<Scalar> {
+        assert!(
+            values.len() <= self.size,
+            "{} values don't fit in a domain of {}",
+            values.len(),
+            self.size
+        );
+        let mut padded = values.to_vec();
+        padded.resize(self.size, Scalar::zero());
+        padded
+    }
+}
+
+/// `a_i c^i`, the coefficients of `a(c x)`.
+fn scale(coefficients: &[Scalar], c: Scalar) -> Vec<Scalar> {
+    let mut power = Scalar::one();
+    coefficients
+        .iter()
+        .map(|a| {
+            let scaled = a * power;
+            power *= c;
+            scaled
+        })
+        .collect()
+}
+
+/// `a_i ← ∑ a_j ω^(ij)` in place, for ω of the order of the length of `a`:
+/// iterative Cooley-Tukey, on the input in bit-reversed order.
+fn transform(a: &mut [Scalar], omega: Scalar) {
+    let n = a.len();
+    if n <= 1 {
+        return;
+    }
+    let log = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - log);
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < n {
+        // A primitive (2 * half)-th root of unity.
+        let step = omega.pow_vartime(&[(n / (2 * half)) as u64, 0, 0, 0]);
+        for chunk in a.chunks_mut(2 * half) {
+            let mut twiddle = Scalar::one();
+            for j in 0..half {
+                let t = chunk[j + half] * twiddle;
+                let u = chunk[j];
+                chunk[j] = u + t;
+                chunk[j + half] = u - t;
+                twiddle *= step;
+            }
+        }
+        half *= 2;
+    }
+}
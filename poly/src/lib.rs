@@ -0,0 +1,37 @@
+//! Polynomials over the scalar field of BLS12-381, given by their
+//! coefficients, lowest degree first.
+//!
+//! See [`fft`] for moving between the coefficients and the values on the
+//! roots of unity, which is what makes products quasi-linear, and
+//! [`division`] for quotients and remainders.
+use bls12_381::Scalar;
+
+pub mod division;
+pub mod fft;
+
+use fft::Domain;
+
+/// Evaluates the polynomial at `x`.
+pub fn evaluate(poly: &[Scalar], x: Scalar) -> Scalar {
+    // Horner's method.
+    poly.iter().rev().fold(Scalar::zero(), |acc, a| acc * x + a)
+}
+
+/// `a(x) * b(x)`, by multiplying their values on a domain large enough for
+/// the product.
+///
+/// # Panics
+///
+/// If the product has more than 2^32 coefficients.
+pub fn mul(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let len = a.len() + b.len() - 1;
+    let domain = Domain::new(len).expect("The product fits in a domain");
+    let (a, b) = (domain.fft(a), domain.fft(b));
+    let values = a.iter().zip(&b).map(|(a, b)| a * b).collect::<Vec<_>>();
+    let mut product = domain.ifft(&values);
+    product.truncate(len);
+    product
+}
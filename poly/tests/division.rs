@@ -0,0 +1,82 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use poly::division::{divide, divide_by_linear, divide_exact, is_zero, vanishing};
+use poly::fft::Domain;
+use poly::{evaluate, mul};
+use rand::thread_rng;
+
+fn random(len: usize) -> Vec<Scalar> {
+    (0..len).map(|_| Scalar::random(thread_rng())).collect()
+}
+
+fn add(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    let mut sum = a.to_vec();
+    sum.resize(a.len().max(b.len()), Scalar::zero());
+    for (s, b) in sum.iter_mut().zip(b) {
+        *s += b;
+    }
+    sum
+}
+
+#[test]
+fn synthetic_division_leaves_the_value() {
+    let f = random(9);
+    let z = Scalar::random(thread_rng());
+    let (quotient, remainder) = divide_by_linear(&f, z);
+    assert_eq!(remainder, evaluate(&f, z));
+    // f(x) = q(x) (x - z) + f(z)
+    assert_eq!(add(&mul(&quotient, &[-z, Scalar::one()]), &[remainder]), f);
+
+    assert_eq!(divide_by_linear(&[], z), (vec![], Scalar::zero()));
+}
+
+#[test]
+fn long_division_matches_the_product() {
+    let divisor = random(4);
+    let quotient = random(6);
+    let remainder = random(3);
+    let f = add(&mul(&quotient, &divisor), &remainder);
+    assert_eq!(divide(&f, &divisor), Some((quotient.clone(), remainder)));
+
+    let product = mul(&quotient, &divisor);
+    assert_eq!(divide_exact(&product, &divisor), Some(quotient));
+    assert_eq!(divide_exact(&f, &divisor), None);
+    assert_eq!(divide(&f, &[Scalar::zero(); 3]), None);
+}
+
+#[test]
+fn divisors_of_a_higher_degree_leave_everything() {
+    let f = random(3);
+    let (quotient, remainder) = divide(&f, &random(5)).unwrap();
+    assert!(quotient.is_empty());
+    assert_eq!(remainder, f);
+}
+
+#[test]
+fn vanishing_polynomials_vanish_on_their_points() {
+    let points = random(5);
+    let z = vanishing(&points);
+    assert_eq!(z.len(), 6);
+    for point in &points {
+        assert_eq!(evaluate(&z, *point), Scalar::zero());
+    }
+
+    let f = mul(&z, &random(3));
+    assert!(divide_exact(&f, &z).is_some());
+}
+
+#[test]
+fn divides_by_the_domain_vanishing_polynomial() {
+    let domain = Domain::new(8).unwrap();
+    let quotient = random(10);
+    let f = mul(&quotient, &domain.vanishing());
+    let (q, remainder) = domain.divide_by_vanishing(&f);
+    assert_eq!(q, quotient);
+    assert!(is_zero(&remainder));
+
+    let shifted = add(&f, &[Scalar::one()]);
+    let (q, remainder) = domain.divide_by_vanishing(&shifted);
+    assert_eq!(q, quotient);
+    assert!(!is_zero(&remainder));
+    assert_eq!(divide(&shifted, &domain.vanishing()).unwrap().0, q);
+}
@@ -0,0 +1,83 @@
+use bls12_381::Scalar;
+use group::ff::{Field, PrimeField};
+use poly::fft::Domain;
+use poly::{evaluate, mul};
+use rand::thread_rng;
+
+fn random(len: usize) -> Vec<Scalar> {
+    (0..len).map(|_| Scalar::random(thread_rng())).collect()
+}
+
+fn naive_mul(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    let mut product = vec![Scalar::zero(); a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            product[i + j] += a * b;
+        }
+    }
+    product
+}
+
+#[test]
+fn builds_power_of_two_domains() {
+    let domain = Domain::new(5).unwrap();
+    assert_eq!(domain.size(), 8);
+    assert_eq!(domain.omega().pow_vartime(&[8, 0, 0, 0]), Scalar::one());
+    assert_ne!(domain.omega().pow_vartime(&[4, 0, 0, 0]), Scalar::one());
+    assert_eq!(domain.elements()[3], domain.element(3));
+
+    assert_eq!(Domain::new(0).unwrap().size(), 1);
+    assert_eq!(Domain::new(1 << 32).unwrap().size(), 1 << 32);
+    assert_eq!(Domain::new((1 << 32) + 1), None);
+}
+
+#[test]
+fn fft_matches_naive_evaluation() {
+    for size in [1, 2, 4, 16, 64] {
+        let domain = Domain::new(size).unwrap();
+        // Fewer coefficients than elements are padded with zeros.
+        let poly = random(size - size / 4);
+        let values = domain.fft(&poly);
+        for (value, x) in values.iter().zip(domain.elements()) {
+            assert_eq!(*value, evaluate(&poly, x));
+        }
+
+        let mut padded = poly.clone();
+        padded.resize(size, Scalar::zero());
+        assert_eq!(domain.ifft(&values), padded);
+    }
+}
+
+#[test]
+fn coset_fft_matches_naive_evaluation() {
+    let domain = Domain::new(32).unwrap();
+    let poly = random(32);
+    let g = Scalar::multiplicative_generator();
+    let values = domain.coset_fft(&poly);
+    for (value, x) in values.iter().zip(domain.elements()) {
+        assert_eq!(*value, evaluate(&poly, g * x));
+    }
+    assert_eq!(domain.coset_ifft(&values), poly);
+}
+
+#[test]
+fn lagrange_basis_and_vanishing_polynomial() {
+    let domain = Domain::new(8).unwrap();
+    let x = Scalar::random(thread_rng());
+    let mut values = vec![Scalar::zero(); 8];
+    values[3] = Scalar::one();
+    assert_eq!(domain.lagrange_at(3, x), evaluate(&domain.ifft(&values), x));
+    for (j, value) in values.iter().enumerate() {
+        assert_eq!(domain.lagrange_at(3, domain.element(j)), *value);
+    }
+    assert_eq!(domain.vanishing_at(domain.element(5)), Scalar::zero());
+}
+
+#[test]
+fn multiplies_like_the_schoolbook() {
+    for (a, b) in [(1, 1), (3, 5), (17, 40), (64, 65)] {
+        let (a, b) = (random(a), random(b));
+        assert_eq!(mul(&a, &b), naive_mul(&a, &b));
+    }
+    assert!(mul(&[], &random(3)).is_empty());
+}
@@ -0,0 +1,166 @@
+//! The Poseidon hash over the scalar field of BLS12-381, cheap to prove in
+//! a circuit where SHA-256 would take tens of thousands of constraints.
+//!
+//! The permutation works on a state of [`WIDTH`] field elements, in rounds
+//! of three steps: add the round constants, apply the S-box `x ↦ x⁵`, and
+//! multiply by an MDS matrix. Half of the [`FULL_ROUNDS`] come first and
+//! half last, and apply the S-box to the whole state. The
+//! [`PARTIAL_ROUNDS`] in between only apply it to the first element, which
+//! is where the savings come from. `x⁵` is the smallest power that permutes
+//! the field, as 3 divides `r - 1`, and the round counts are the paper's
+//! for a state of three elements of a 255-bit field at the 128-bit
+//! security level.
+//!
+//! The round constants are generated rather than taken from the reference
+//! implementation: SHA-512 of a domain string and their index, reduced into
+//! the field. The MDS matrix is the Cauchy matrix `1 / (x_i + y_j)` with
+//! `x_i = i` and `y_j = WIDTH + j`. So the digests are our own, they don't
+//! match other Poseidon instances.
+//!
+//! [`hash`] runs a sponge of rate 2 and capacity 1 over its inputs, the
+//! capacity element starting at the number of inputs so that inputs of
+//! different lengths never collide through padding. [`Sponge`] exposes the
+//! same construction for absorbing and squeezing along the way, as a
+//! Fiat-Shamir transcript inside a circuit would.
+use bls12_381::Scalar;
+use sha2::{Digest, Sha512};
+use std::sync::OnceLock;
+
+pub const WIDTH: usize = 3;
+pub const RATE: usize = WIDTH - 1;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+
+const DOMAIN: &[u8] = b"zk-lab/poseidon/bls12-381/3/8/57";
+
+/// The constants of the permutation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constants {
+    /// Added to the state at the start of each round.
+    pub round_constants: Vec<[Scalar; WIDTH]>,
+    pub mds: [[Scalar; WIDTH]; WIDTH],
+}
+
+impl Constants {
+    fn generate() -> Self {
+        let mut counter = 0u64;
+        let mut next = || {
+            let mut wide = [0; 64];
+            wide.copy_from_slice(
+                &Sha512::new()
+                    .chain(DOMAIN)
+                    .chain(counter.to_be_bytes())
+                    .finalize(),
+            );
+            counter += 1;
+            Scalar::from_bytes_wide(&wide)
+        };
+        let round_constants = (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+            .map(|_| [next(), next(), next()])
+            .collect();
+
+        let mut mds = [[Scalar::zero(); WIDTH]; WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                let sum = Scalar::from((i + WIDTH + j) as u64);
+                *entry = sum.invert().unwrap();
+            }
+        }
+        Constants {
+            round_constants,
+            mds,
+        }
+    }
+}
+
+/// The constants, generated on first use.
+pub fn constants() -> &'static Constants {
+    static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+    CONSTANTS.get_or_init(Constants::generate)
+}
+
+/// The S-box.
+pub fn sbox(x: Scalar) -> Scalar {
+    let x2 = x.square();
+    x2.square() * x
+}
+
+/// Whether `round` applies the S-box to the whole state.
+pub fn is_full_round(round: usize) -> bool {
+    !(FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round)
+}
+
+/// The Poseidon permutation, in place.
+pub fn permute(state: &mut [Scalar; WIDTH]) {
+    let constants = constants();
+    for (round, round_constants) in constants.round_constants.iter().enumerate() {
+        for (x, c) in state.iter_mut().zip(round_constants) {
+            *x += c;
+        }
+        if is_full_round(round) {
+            for x in state.iter_mut() {
+                *x = sbox(*x);
+            }
+        } else {
+            state[0] = sbox(state[0]);
+        }
+        *state = mix(&constants.mds, state);
+    }
+}
+
+/// The state multiplied by the MDS matrix.
+pub fn mix(mds: &[[Scalar; WIDTH]; WIDTH], state: &[Scalar; WIDTH]) -> [Scalar; WIDTH] {
+    let mut mixed = [Scalar::zero(); WIDTH];
+    for (out, row) in mixed.iter_mut().zip(mds) {
+        *out = row
+            .iter()
+            .zip(state)
+            .fold(Scalar::zero(), |acc, (m, x)| acc + m * x);
+    }
+    mixed
+}
+
+/// Hashes any number of field elements to one.
+pub fn hash(inputs: &[Scalar]) -> Scalar {
+    let mut sponge = Sponge::new(Scalar::from(inputs.len() as u64));
+    for input in inputs {
+        sponge.absorb(*input);
+    }
+    sponge.squeeze()
+}
+
+/// A duplex sponge over the permutation: the first element of the state is
+/// the capacity, the others the rate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sponge {
+    state: [Scalar; WIDTH],
+    /// How many elements were absorbed into the rate since the last
+    /// permutation.
+    position: usize,
+}
+
+impl Sponge {
+    /// A sponge whose capacity starts at `domain`, to separate its uses.
+    pub fn new(domain: Scalar) -> Self {
+        let mut state = [Scalar::zero(); WIDTH];
+        state[0] = domain;
+        Sponge { state, position: 0 }
+    }
+
+    pub fn absorb(&mut self, input: Scalar) {
+        if self.position == RATE {
+            permute(&mut self.state);
+            self.position = 0;
+        }
+        self.state[1 + self.position] += input;
+        self.position += 1;
+    }
+
+    /// Permutes, so the output depends on everything absorbed, and returns
+    /// the first element of the rate.
+    pub fn squeeze(&mut self) -> Scalar {
+        permute(&mut self.state);
+        self.position = 0;
+        self.state[1]
+    }
+}
@@ -0,0 +1,83 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use poseidon::{constants, hash, permute, Sponge, FULL_ROUNDS, PARTIAL_ROUNDS, WIDTH};
+use rand::thread_rng;
+
+#[test]
+fn hashes_deterministically() {
+    let inputs = [Scalar::from(1), Scalar::from(2), Scalar::from(3)];
+    assert_eq!(hash(&inputs), hash(&inputs));
+    assert_ne!(hash(&inputs), hash(&[Scalar::from(1), Scalar::from(2)]));
+    assert_ne!(
+        hash(&[Scalar::from(1), Scalar::from(2)]),
+        hash(&[Scalar::from(2), Scalar::from(1)])
+    );
+}
+
+#[test]
+fn lengths_are_separated() {
+    let zero = Scalar::zero();
+    let digests = [
+        hash(&[]),
+        hash(&[zero]),
+        hash(&[zero, zero]),
+        hash(&[zero, zero, zero]),
+    ];
+    for (i, a) in digests.iter().enumerate() {
+        for b in &digests[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
+
+#[test]
+fn hash_is_the_sponge() {
+    let inputs = (0..5)
+        .map(|_| Scalar::random(thread_rng()))
+        .collect::<Vec<_>>();
+    let mut sponge = Sponge::new(Scalar::from(5));
+    for input in &inputs {
+        sponge.absorb(*input);
+    }
+    assert_eq!(sponge.squeeze(), hash(&inputs));
+    // Squeezing again gives a fresh output.
+    assert_ne!(sponge.squeeze(), hash(&inputs));
+}
+
+#[test]
+fn permutation_mixes_the_whole_state() {
+    let mut a = [Scalar::zero(); WIDTH];
+    let mut b = a;
+    b[WIDTH - 1] = Scalar::one();
+    permute(&mut a);
+    permute(&mut b);
+    for (a, b) in a.iter().zip(&b) {
+        assert_ne!(a, b);
+    }
+}
+
+#[test]
+fn mds_matrix_is_mds() {
+    let m = constants().mds;
+    assert_eq!(
+        constants().round_constants.len(),
+        FULL_ROUNDS + PARTIAL_ROUNDS
+    );
+
+    // Every square submatrix is invertible.
+    for row in &m {
+        for entry in row {
+            assert_ne!(*entry, Scalar::zero());
+        }
+    }
+    for (r0, r1) in [(0, 1), (0, 2), (1, 2)] {
+        for (c0, c1) in [(0, 1), (0, 2), (1, 2)] {
+            let det = m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+            assert_ne!(det, Scalar::zero());
+        }
+    }
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    assert_ne!(det, Scalar::zero());
+}
@@ -0,0 +1,187 @@
+//! Pointcheval-Sanders signatures over BLS12-381: short signatures on lists
+//! of messages that anyone can re-randomize into an unlinkable signature on
+//! the same messages, an alternative to BBS+ for anonymous credentials.
+//!
+//! The signing key is `x, y_1, ..., y_L`, published as `X = x * G2` and
+//! `Y_i = y_i * G2`. The signature on the scalars `m_1, ..., m_L` is a
+//! random `σ1 = h` of G1 with
+//!
+//! σ2 = (x + ∑ y_i * m_i) * h
+//!
+//! checked with
+//!
+//! e(σ1, X + ∑ m_i * Y_i) == e(σ2, G2)
+//!
+//! for `σ1` not the identity. Any `(r * σ1, r * σ2)` is a signature on the
+//! same messages too, which is how a holder shows a credential twice
+//! without being recognized. See [`proof`] for proving knowledge of a
+//! signature on hidden messages with the sigma protocols of the `sigma`
+//! crate.
+pub mod proof;
+
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use bls_shamir::secret::SecretKey;
+use group::ff::Field;
+use group::Curve;
+use pairing::pairing_product_is_one;
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+
+/// `x` and the `y_i`, one for each message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigningKey {
+    x: SecretKey,
+    y: Vec<SecretKey>,
+}
+
+/// `X = x * G2` and `Y_i = y_i * G2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub x: G2Affine,
+    pub y: Vec<G2Affine>,
+}
+
+/// `(σ1, σ2)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub sigma1: G1Affine,
+    pub sigma2: G1Affine,
+}
+
+impl SigningKey {
+    /// A key for signing lists of `count` messages.
+    pub fn random<R: RngCore + CryptoRng>(count: usize, rng: &mut R) -> Self {
+        SigningKey {
+            x: SecretKey::random(&mut *rng),
+            y: (0..count).map(|_| SecretKey::random(&mut *rng)).collect(),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.y.len()
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        let public = |secret: &SecretKey| (G2Affine::generator() * secret.as_scalar()).to_affine();
+        PublicKey {
+            x: public(&self.x),
+            y: self.y.iter().map(public).collect(),
+        }
+    }
+
+    /// Signs the messages, one for each `y_i`.
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        messages: &[Scalar],
+        rng: &mut R,
+    ) -> Result<Signature, PsError> {
+        check_count(self.count(), messages)?;
+        let exponent = self.x.as_scalar()
+            + self
+                .y
+                .iter()
+                .zip(messages)
+                .map(|(y, m)| y.as_scalar() * m)
+                .sum::<Scalar>();
+        let h = G1Projective::generator() * random_nonzero(rng);
+        Ok(Signature {
+            sigma1: h.to_affine(),
+            sigma2: (h * exponent).to_affine(),
+        })
+    }
+}
+
+impl PublicKey {
+    pub fn count(&self) -> usize {
+        self.y.len()
+    }
+
+    /// `X + ∑ m_i * Y_i`, over the messages at the given indices.
+    fn combine<'a>(&self, messages: impl IntoIterator<Item = (usize, &'a Scalar)>) -> G2Projective {
+        G2Projective::from(self.x)
+            + messages
+                .into_iter()
+                .map(|(i, m)| self.y[i] * m)
+                .sum::<G2Projective>()
+    }
+}
+
+impl Signature {
+    /// The same signature on the same messages under a new disguise,
+    /// `(r * σ1, r * σ2)`.
+    pub fn randomize<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Self {
+        let r = random_nonzero(rng);
+        Signature {
+            sigma1: (self.sigma1 * r).to_affine(),
+            sigma2: (self.sigma2 * r).to_affine(),
+        }
+    }
+}
+
+/// Checks the signature on the messages.
+pub fn verify(pk: &PublicKey, messages: &[Scalar], signature: &Signature) -> Result<(), PsError> {
+    check_count(pk.count(), messages)?;
+    let combined = pk.combine(messages.iter().enumerate()).to_affine();
+    // e(σ1, X + ∑ m_i * Y_i) * e(-σ2, G2) == 1
+    let valid = !bool::from(signature.sigma1.is_identity())
+        && pairing_product_is_one(&[
+            (signature.sigma1, combined),
+            (-signature.sigma2, G2Affine::generator()),
+        ]);
+    if !valid {
+        return Err(PsError::Invalid);
+    }
+    Ok(())
+}
+
+fn check_count(count: usize, messages: &[Scalar]) -> Result<(), PsError> {
+    if messages.len() != count {
+        return Err(PsError::Messages {
+            expected: count,
+            got: messages.len(),
+        });
+    }
+    Ok(())
+}
+
+fn random_nonzero<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    loop {
+        let r = Scalar::random(&mut *rng);
+        if r != Scalar::zero() {
+            return r;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsError {
+    /// Not as many messages as the key has `y_i`.
+    Messages {
+        expected: usize,
+        got: usize,
+    },
+    /// A disclosed message that isn't in the list.
+    Index {
+        index: usize,
+        count: usize,
+    },
+    Invalid,
+}
+
+impl fmt::Display for PsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PsError::Messages { expected, got } => {
+                write!(f, "expected {} messages, got {}", expected, got)
+            }
+            PsError::Index { index, count } => write!(
+                f,
+                "message {} doesn't exist, there are {} messages",
+                index, count
+            ),
+            PsError::Invalid => write!(f, "invalid signature"),
+        }
+    }
+}
+
+impl std::error::Error for PsError {}
@@ -0,0 +1,256 @@
+//! Proofs of knowledge of a signature on messages of which only some are
+//! disclosed.
+//!
+//! Re-randomizing hides the signature but not the messages, which the
+//! verifier needs to check it. To hide them too, the holder turns its
+//! signature into
+//!
+//! σ1' = r * σ1
+//! σ2' = r * (σ2 + t * σ1)
+//!
+//! for random r and t, a signature on the messages and t under the extra
+//! generator G2. Then, in Gt,
+//!
+//! e(σ2', G2) - e(σ1', X + ∑_disclosed m_i * Y_i)
+//!     = ∑_hidden m_j * e(σ1', Y_j) + t * e(σ1', G2)
+//!
+//! and [`Knowledge`] proves knowledge of the hidden `m_j` and t, a Schnorr
+//! proof with as many bases, as a [`SigmaProtocol`] so that it can be
+//! combined with [`And`](sigma::protocol::And) and
+//! [`Or`](sigma::protocol::Or), for instance to tie a hidden message to a
+//! Pedersen commitment.
+use crate::{PsError, PublicKey, Signature};
+use ::pairing::gt::GtElement;
+use bls12_381::{G2Affine, G2Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use sigma::protocol::{self, NizkProof, SigmaProtocol};
+use sigma::transcript::Transcript;
+
+/// The statement that the prover knows the hidden messages, and the t, of
+/// the blinded `signature` on the `disclosed` ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Knowledge {
+    pub public_key: PublicKey,
+    pub signature: Signature,
+    /// The disclosed messages with their indices, in increasing order.
+    pub disclosed: Vec<(usize, Scalar)>,
+}
+
+/// The hidden messages, in order, and t.
+#[derive(Clone, PartialEq)]
+pub struct Witness {
+    pub messages: Vec<Scalar>,
+    pub blinding: Scalar,
+}
+
+/// A blinded signature with the proof of its messages.
+#[derive(Debug, Clone)]
+pub struct Presentation {
+    pub signature: Signature,
+    pub proof: NizkProof<Knowledge>,
+}
+
+impl Knowledge {
+    /// The indices of the hidden messages.
+    pub fn hidden(&self) -> Vec<usize> {
+        (0..self.public_key.count())
+            .filter(|i| self.disclosed.iter().all(|(j, _)| j != i))
+            .collect()
+    }
+
+    /// `∑ z_j * Y_j + z_t * G2`, over the hidden messages.
+    fn bases(&self, responses: &[Scalar]) -> G2Affine {
+        let (blinding, messages) = responses.split_last().expect("There is a response for t");
+        let sum = self
+            .hidden()
+            .into_iter()
+            .zip(messages)
+            .map(|(j, z)| self.public_key.y[j] * z)
+            .sum::<G2Projective>();
+        (sum + G2Projective::generator() * blinding).to_affine()
+    }
+
+    /// `e(σ2', G2) - e(σ1', X + ∑_disclosed m_i * Y_i)`
+    fn target(&self) -> GtElement {
+        let disclosed = self
+            .public_key
+            .combine(self.disclosed.iter().map(|(i, m)| (*i, m)))
+            .to_affine();
+        GtElement::pairing(&self.signature.sigma2, &G2Affine::generator())
+            .mul(&GtElement::pairing(&self.signature.sigma1, &disclosed).invert())
+    }
+
+    fn is_well_formed(&self) -> bool {
+        let count = self.public_key.count();
+        !bool::from(self.signature.sigma1.is_identity())
+            && self.disclosed.iter().all(|(i, _)| *i < count)
+            && self.disclosed.windows(2).all(|w| w[0].0 < w[1].0)
+    }
+}
+
+impl SigmaProtocol for Knowledge {
+    type Witness = Witness;
+    type Commitment = GtElement;
+    type State = Vec<Scalar>;
+    /// For the hidden messages, then t.
+    type Response = Vec<Scalar>;
+
+    fn commit<R: RngCore + CryptoRng>(
+        &self,
+        witness: &Witness,
+        rng: &mut R,
+    ) -> (GtElement, Vec<Scalar>) {
+        let nonces = (0..=witness.messages.len())
+            .map(|_| Scalar::random(&mut *rng))
+            .collect::<Vec<_>>();
+        let commitment = GtElement::pairing(&self.signature.sigma1, &self.bases(&nonces));
+        (commitment, nonces)
+    }
+
+    fn respond(&self, witness: &Witness, nonces: Vec<Scalar>, challenge: &Scalar) -> Vec<Scalar> {
+        let secrets = witness.messages.iter().chain([&witness.blinding]);
+        nonces
+            .iter()
+            .zip(secrets)
+            .map(|(k, secret)| k - challenge * secret)
+            .collect()
+    }
+
+    fn verify(&self, commitment: &GtElement, challenge: &Scalar, responses: &Vec<Scalar>) -> bool {
+        self.is_well_formed()
+            && responses.len() == self.hidden().len() + 1
+            && GtElement::pairing(&self.signature.sigma1, &self.bases(responses))
+                .mul(&self.target().pow(challenge))
+                == *commitment
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        challenge: &Scalar,
+        rng: &mut R,
+    ) -> (GtElement, Vec<Scalar>) {
+        let responses = (0..=self.hidden().len())
+            .map(|_| Scalar::random(&mut *rng))
+            .collect::<Vec<_>>();
+        let commitment = GtElement::pairing(&self.signature.sigma1, &self.bases(&responses))
+            .mul(&self.target().pow(challenge));
+        (commitment, responses)
+    }
+
+    fn append_statement(&self, transcript: &mut Transcript) {
+        transcript.append_point(b"ps/X", &self.public_key.x);
+        for y in &self.public_key.y {
+            transcript.append_point(b"ps/Y", y);
+        }
+        transcript.append_point(b"ps/sigma1", &self.signature.sigma1);
+        transcript.append_point(b"ps/sigma2", &self.signature.sigma2);
+        for (i, m) in &self.disclosed {
+            transcript.append_u64(b"ps/disclosed index", *i as u64);
+            transcript.append_scalar(b"ps/disclosed message", m);
+        }
+    }
+
+    fn append_commitment(&self, commitment: &GtElement, transcript: &mut Transcript) {
+        transcript.append_message(b"ps/T", &commitment.to_bytes());
+    }
+}
+
+/// Blinds the signature on the messages and proves knowledge of it,
+/// disclosing the messages at the indices of `disclosed`.
+pub fn present<R: RngCore + CryptoRng>(
+    pk: &PublicKey,
+    signature: &Signature,
+    messages: &[Scalar],
+    disclosed: &[usize],
+    context: &[u8],
+    rng: &mut R,
+) -> Result<Presentation, PsError> {
+    let (statement, witness) = blind(pk, signature, messages, disclosed, rng)?;
+    Ok(Presentation {
+        proof: protocol::prove(&statement, &witness, context, rng),
+        signature: statement.signature,
+    })
+}
+
+/// Checks a presentation of a signature on messages including the
+/// `disclosed` ones, given with their indices.
+pub fn verify(
+    pk: &PublicKey,
+    disclosed: &[(usize, Scalar)],
+    context: &[u8],
+    presentation: &Presentation,
+) -> Result<(), PsError> {
+    let statement = statement(pk, presentation.signature, disclosed.to_vec())?;
+    if !protocol::verify(&statement, context, &presentation.proof) {
+        return Err(PsError::Invalid);
+    }
+    Ok(())
+}
+
+/// The statement of a blinded signature on the messages and its witness,
+/// for composing with other statements.
+pub fn blind<R: RngCore + CryptoRng>(
+    pk: &PublicKey,
+    signature: &Signature,
+    messages: &[Scalar],
+    disclosed: &[usize],
+    rng: &mut R,
+) -> Result<(Knowledge, Witness), PsError> {
+    crate::check_count(pk.count(), messages)?;
+    let disclosed = disclosed
+        .iter()
+        .map(|i| match messages.get(*i) {
+            Some(m) => Ok((*i, *m)),
+            None => Err(PsError::Index {
+                index: *i,
+                count: messages.len(),
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (r, t) = (crate::random_nonzero(&mut *rng), Scalar::random(&mut *rng));
+    let sigma1 = signature.sigma1 * r;
+    let sigma2 = (signature.sigma2 + signature.sigma1 * t) * r;
+    let statement = statement(
+        pk,
+        Signature {
+            sigma1: sigma1.to_affine(),
+            sigma2: sigma2.to_affine(),
+        },
+        disclosed,
+    )?;
+    let witness = Witness {
+        messages: statement
+            .hidden()
+            .into_iter()
+            .map(|j| messages[j])
+            .collect(),
+        blinding: t,
+    };
+    Ok((statement, witness))
+}
+
+/// The statement with its disclosed messages sorted, after checking that
+/// they are in the list and given once.
+fn statement(
+    pk: &PublicKey,
+    signature: Signature,
+    mut disclosed: Vec<(usize, Scalar)>,
+) -> Result<Knowledge, PsError> {
+    let count = pk.count();
+    if let Some((index, _)) = disclosed.iter().find(|(i, _)| *i >= count) {
+        return Err(PsError::Index {
+            index: *index,
+            count,
+        });
+    }
+    disclosed.sort_by_key(|(i, _)| *i);
+    disclosed.dedup_by_key(|(i, _)| *i);
+    Ok(Knowledge {
+        public_key: pk.clone(),
+        signature,
+        disclosed,
+    })
+}
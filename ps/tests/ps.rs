@@ -0,0 +1,134 @@
+use bls12_381::{G1Projective, Scalar};
+use ps::proof::{self, blind, Knowledge};
+use ps::{verify, PsError, SigningKey};
+use sigma::pedersen::{self, Generators};
+use sigma::protocol::{self as sigma_protocol, And};
+
+fn messages() -> Vec<Scalar> {
+    [1990u64, 42, 7].iter().copied().map(Scalar::from).collect()
+}
+
+#[test]
+fn signatures_randomize() {
+    let mut rng = rand::thread_rng();
+    let sk = SigningKey::random(3, &mut rng);
+    let pk = sk.public_key();
+    let messages = messages();
+
+    let signature = sk.sign(&messages, &mut rng).unwrap();
+    assert_eq!(verify(&pk, &messages, &signature), Ok(()));
+    let randomized = signature.randomize(&mut rng);
+    assert_ne!(randomized, signature);
+    assert_eq!(verify(&pk, &messages, &randomized), Ok(()));
+
+    let mut changed = messages.clone();
+    changed[1] += Scalar::one();
+    assert_eq!(verify(&pk, &changed, &randomized), Err(PsError::Invalid));
+    let other = SigningKey::random(3, &mut rng).public_key();
+    assert_eq!(
+        verify(&other, &messages, &randomized),
+        Err(PsError::Invalid)
+    );
+    assert_eq!(
+        verify(&pk, &messages[..2], &randomized),
+        Err(PsError::Messages {
+            expected: 3,
+            got: 2
+        })
+    );
+
+    // (O, O) would verify on anything.
+    let mut trivial = signature;
+    trivial.sigma1 = bls12_381::G1Affine::identity();
+    trivial.sigma2 = bls12_381::G1Affine::identity();
+    assert_eq!(verify(&pk, &messages, &trivial), Err(PsError::Invalid));
+}
+
+#[test]
+fn presentations_disclose_chosen_messages() {
+    let mut rng = rand::thread_rng();
+    let sk = SigningKey::random(3, &mut rng);
+    let pk = sk.public_key();
+    let messages = messages();
+    let signature = sk.sign(&messages, &mut rng).unwrap();
+
+    for disclosed in [vec![], vec![1], vec![2, 0], vec![0, 1, 2]] {
+        let presentation =
+            proof::present(&pk, &signature, &messages, &disclosed, b"nonce", &mut rng).unwrap();
+        assert_ne!(presentation.signature, signature);
+        let revealed = disclosed
+            .iter()
+            .map(|i| (*i, messages[*i]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            proof::verify(&pk, &revealed, b"nonce", &presentation),
+            Ok(())
+        );
+    }
+}
+
+#[test]
+fn wrong_presentations_are_rejected() {
+    let mut rng = rand::thread_rng();
+    let sk = SigningKey::random(3, &mut rng);
+    let pk = sk.public_key();
+    let messages = messages();
+    let signature = sk.sign(&messages, &mut rng).unwrap();
+    let presentation =
+        proof::present(&pk, &signature, &messages, &[0], b"nonce", &mut rng).unwrap();
+
+    let wrong = [(0, messages[0] + Scalar::one())];
+    assert_eq!(
+        proof::verify(&pk, &wrong, b"nonce", &presentation),
+        Err(PsError::Invalid)
+    );
+    let revealed = [(0, messages[0])];
+    assert_eq!(
+        proof::verify(&pk, &revealed, b"other nonce", &presentation),
+        Err(PsError::Invalid)
+    );
+    assert_eq!(
+        proof::verify(&pk, &[(3, messages[0])], b"nonce", &presentation),
+        Err(PsError::Index { index: 3, count: 3 })
+    );
+
+    // A forged signature can't be presented.
+    let mut forged = signature;
+    forged.sigma2 = (G1Projective::from(forged.sigma2) + G1Projective::generator()).into();
+    let presentation = proof::present(&pk, &forged, &messages, &[0], b"nonce", &mut rng).unwrap();
+    assert_eq!(
+        proof::verify(&pk, &revealed, b"nonce", &presentation),
+        Err(PsError::Invalid)
+    );
+}
+
+#[test]
+fn composes_with_a_pedersen_commitment() {
+    let mut rng = rand::thread_rng();
+    let sk = SigningKey::random(3, &mut rng);
+    let pk = sk.public_key();
+    let messages = messages();
+    let signature = sk.sign(&messages, &mut rng).unwrap();
+
+    let (knowledge, witness) = blind(&pk, &signature, &messages, &[0, 2], &mut rng).unwrap();
+    assert_eq!(knowledge.hidden(), vec![1]);
+    let generators = Generators::default();
+    let (commitment, opening) = generators.commit_random(Scalar::from(5), &mut rng);
+    let statement = And(
+        knowledge.clone(),
+        pedersen::Knowledge {
+            generators,
+            commitment,
+        },
+    );
+    let proof = sigma_protocol::prove(&statement, &(witness, opening), b"context", &mut rng);
+    assert!(sigma_protocol::verify(&statement, b"context", &proof));
+
+    // The blinded signature is bound to its disclosed messages.
+    let mut moved = statement;
+    moved.0 = Knowledge {
+        disclosed: vec![(0, messages[0]), (2, messages[1])],
+        ..knowledge
+    };
+    assert!(!sigma_protocol::verify(&moved, b"context", &proof));
+}
@@ -0,0 +1,111 @@
+//! Variables and the linear combinations constraints are made of.
+use bls12_381::Scalar;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A variable of a constraint system: the constant 1, a public input or a
+/// private auxiliary value, numbered in the order they were allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Variable {
+    One,
+    Input(usize),
+    Aux(usize),
+}
+
+/// `∑ c_i * v_i`, terms of the same variable being kept apart until
+/// evaluated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinearCombination(pub Vec<(Variable, Scalar)>);
+
+impl LinearCombination {
+    pub fn zero() -> Self {
+        LinearCombination(Vec::new())
+    }
+
+    /// The constant `c`.
+    pub fn constant(c: Scalar) -> Self {
+        LinearCombination(vec![(Variable::One, c)])
+    }
+
+    pub fn terms(&self) -> &[(Variable, Scalar)] {
+        &self.0
+    }
+
+    /// The value of the combination, given the value of each variable.
+    pub fn evaluate(&self, value: impl Fn(Variable) -> Scalar) -> Scalar {
+        self.0.iter().fold(Scalar::zero(), |acc, (variable, c)| {
+            acc + value(*variable) * c
+        })
+    }
+}
+
+impl From<Variable> for LinearCombination {
+    fn from(variable: Variable) -> Self {
+        LinearCombination(vec![(variable, Scalar::one())])
+    }
+}
+
+impl From<(Variable, Scalar)> for LinearCombination {
+    fn from(term: (Variable, Scalar)) -> Self {
+        LinearCombination(vec![term])
+    }
+}
+
+impl<T: Into<LinearCombination>> Add<T> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn add(mut self, other: T) -> LinearCombination {
+        self.0.extend(other.into().0);
+        self
+    }
+}
+
+impl<T: Into<LinearCombination>> Sub<T> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn sub(self, other: T) -> LinearCombination {
+        self + -other.into()
+    }
+}
+
+impl Neg for LinearCombination {
+    type Output = LinearCombination;
+
+    fn neg(self) -> LinearCombination {
+        self * -Scalar::one()
+    }
+}
+
+impl Mul<Scalar> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn mul(mut self, c: Scalar) -> LinearCombination {
+        for (_, coefficient) in &mut self.0 {
+            *coefficient *= c;
+        }
+        self
+    }
+}
+
+impl<T: Into<LinearCombination>> Add<T> for Variable {
+    type Output = LinearCombination;
+
+    fn add(self, other: T) -> LinearCombination {
+        LinearCombination::from(self) + other
+    }
+}
+
+impl<T: Into<LinearCombination>> Sub<T> for Variable {
+    type Output = LinearCombination;
+
+    fn sub(self, other: T) -> LinearCombination {
+        LinearCombination::from(self) - other
+    }
+}
+
+impl Mul<Scalar> for Variable {
+    type Output = LinearCombination;
+
+    fn mul(self, c: Scalar) -> LinearCombination {
+        LinearCombination::from((self, c))
+    }
+}
@@ -0,0 +1,189 @@
+//! Rank-1 constraint systems over the scalar field of BLS12-381, the
+//! arithmetization the lab's SNARKs prove statements in.
+//!
+//! A statement is a list of constraints `<A, z> * <B, z> = <C, z>`, where A,
+//! B and C are linear combinations of the variables z: the constant 1, the
+//! public inputs and the private auxiliary values. A [`ConstraintSystem`] is
+//! built by allocating variables with their values and enforcing
+//! constraints between them, so the same code describes the circuit and
+//! computes its [`Witness`]. A setup that only needs the shape of the
+//! circuit allocates zeros.
+use bls12_381::Scalar;
+use std::fmt;
+
+mod lc;
+pub mod witness;
+
+pub use lc::{LinearCombination, Variable};
+pub use witness::Witness;
+
+/// `a * b = c`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+    /// What the constraint is for, to tell which one isn't satisfied.
+    pub annotation: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintSystem {
+    constraints: Vec<Constraint>,
+    witness: Witness,
+}
+
+impl ConstraintSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a public input.
+    pub fn alloc_input(&mut self, value: Scalar) -> Variable {
+        self.witness.inputs.push(value);
+        Variable::Input(self.witness.inputs.len() - 1)
+    }
+
+    /// Allocates a private value.
+    pub fn alloc(&mut self, value: Scalar) -> Variable {
+        self.witness.aux.push(value);
+        Variable::Aux(self.witness.aux.len() - 1)
+    }
+
+    pub fn enforce(
+        &mut self,
+        annotation: impl Into<String>,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+        c: impl Into<LinearCombination>,
+    ) {
+        self.constraints.push(Constraint {
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+            annotation: annotation.into(),
+        });
+    }
+
+    /// Allocates `a * b` as a private value, constrained to be the product.
+    pub fn mul(
+        &mut self,
+        annotation: impl Into<String>,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+    ) -> Variable {
+        let (a, b) = (a.into(), b.into());
+        let product = self.alloc(self.value(&a) * self.value(&b));
+        self.enforce(annotation, a, b, product);
+        product
+    }
+
+    /// The value of `lc` with the values allocated so far.
+    pub fn value(&self, lc: &LinearCombination) -> Scalar {
+        evaluate(lc, &self.witness)
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.witness.inputs.len()
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.witness.aux.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// The values allocated.
+    pub fn witness(&self) -> &Witness {
+        &self.witness
+    }
+
+    /// Checks the constraints against the values allocated.
+    pub fn is_satisfied(&self) -> Result<(), R1csError> {
+        self.is_satisfied_by(&self.witness)
+    }
+
+    /// Checks the constraints against another witness of the same shape,
+    /// such as one read from a file.
+    pub fn is_satisfied_by(&self, witness: &Witness) -> Result<(), R1csError> {
+        if witness.inputs.len() != self.num_inputs() || witness.aux.len() != self.num_aux() {
+            return Err(R1csError::Shape {
+                inputs: witness.inputs.len(),
+                aux: witness.aux.len(),
+            });
+        }
+        match self.constraints.iter().position(|constraint| {
+            evaluate(&constraint.a, witness) * evaluate(&constraint.b, witness)
+                != evaluate(&constraint.c, witness)
+        }) {
+            Some(index) => Err(R1csError::Unsatisfied {
+                index,
+                annotation: self.constraints[index].annotation.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Variables the witness has no value for count as zero, the shape is
+/// checked separately.
+fn evaluate(lc: &LinearCombination, witness: &Witness) -> Scalar {
+    lc.evaluate(|variable| witness.value(variable).unwrap_or_else(Scalar::zero))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum R1csError {
+    Unsatisfied {
+        index: usize,
+        annotation: String,
+    },
+    /// The witness doesn't have a value for each variable of the system.
+    Shape {
+        inputs: usize,
+        aux: usize,
+    },
+    /// Not a witness.
+    Magic,
+    /// A version of the witness format this crate can't read.
+    Version(u16),
+    /// The witness isn't as long as its header says.
+    Truncated,
+    /// The value at `index` isn't a canonical scalar.
+    Scalar {
+        index: usize,
+    },
+}
+
+impl fmt::Display for R1csError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            R1csError::Unsatisfied { index, annotation } => {
+                write!(f, "constraint {} ({}) isn't satisfied", index, annotation)
+            }
+            R1csError::Shape { inputs, aux } => write!(
+                f,
+                "a witness of {} inputs and {} auxiliary values doesn't fit the system",
+                inputs, aux
+            ),
+            R1csError::Magic => write!(f, "not a witness"),
+            R1csError::Version(version) => write!(
+                f,
+                "witness version {} isn't supported, only version {} is",
+                version,
+                witness::VERSION
+            ),
+            R1csError::Truncated => write!(f, "truncated witness"),
+            R1csError::Scalar { index } => {
+                write!(f, "value {} of the witness isn't a scalar", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for R1csError {}
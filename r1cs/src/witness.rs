@@ -0,0 +1,92 @@
+//! The values of the variables of a constraint system, and their encoding
+//! for handing a witness from whoever computed it to a prover:
+//!
+//! | bytes  | field                                       |
+//! |--------|---------------------------------------------|
+//! | 0..8   | [`MAGIC`]                                   |
+//! | 8..10  | [`VERSION`], big-endian like the counts     |
+//! | 10..16 | 0                                           |
+//! | 16..24 | the number of public inputs                 |
+//! | 24..32 | the number of auxiliary values              |
+//!
+//! followed by the inputs, then the auxiliary values, as canonical 32 byte
+//! little-endian scalars. The constant 1 isn't written.
+use crate::{R1csError, Variable};
+use bls12_381::Scalar;
+
+pub const MAGIC: &[u8; 8] = b"ZKLABWIT";
+
+pub const VERSION: u16 = 1;
+
+const HEADER_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Witness {
+    pub inputs: Vec<Scalar>,
+    pub aux: Vec<Scalar>,
+}
+
+impl Witness {
+    /// The value of `variable`, if the witness has one.
+    pub fn value(&self, variable: Variable) -> Option<Scalar> {
+        match variable {
+            Variable::One => Some(Scalar::one()),
+            Variable::Input(i) => self.inputs.get(i).copied(),
+            Variable::Aux(i) => self.aux.get(i).copied(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + 32 * (self.inputs.len() + self.aux.len()));
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_be_bytes());
+        bytes.extend_from_slice(&[0; 6]);
+        bytes.extend_from_slice(&(self.inputs.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&(self.aux.len() as u64).to_be_bytes());
+        for value in self.inputs.iter().chain(&self.aux) {
+            bytes.extend_from_slice(&value.to_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, R1csError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(R1csError::Truncated);
+        }
+        if &bytes[..8] != MAGIC {
+            return Err(R1csError::Magic);
+        }
+        let version = u16::from_be_bytes([bytes[8], bytes[9]]);
+        if version != VERSION {
+            return Err(R1csError::Version(version));
+        }
+        let count = |at: usize| {
+            let count = u64::from_be_bytes(bytes[at..at + 8].try_into().expect("8 bytes"));
+            usize::try_from(count).map_err(|_| R1csError::Truncated)
+        };
+        let (inputs, aux) = (count(16)?, count(24)?);
+        let values = &bytes[HEADER_SIZE..];
+        match inputs
+            .checked_add(aux)
+            .and_then(|total| total.checked_mul(32))
+        {
+            Some(len) if len == values.len() => {}
+            _ => return Err(R1csError::Truncated),
+        }
+
+        let values = values
+            .chunks_exact(32)
+            .enumerate()
+            .map(|(index, bytes)| {
+                let bytes: [u8; 32] = bytes.try_into().expect("32 bytes");
+                Option::<Scalar>::from(Scalar::from_bytes(&bytes))
+                    .ok_or(R1csError::Scalar { index })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (inputs, aux) = values.split_at(inputs);
+        Ok(Witness {
+            inputs: inputs.to_vec(),
+            aux: aux.to_vec(),
+        })
+    }
+}
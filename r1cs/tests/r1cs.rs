@@ -0,0 +1,100 @@
+use bls12_381::Scalar;
+use r1cs::{ConstraintSystem, LinearCombination, R1csError, Variable, Witness};
+
+/// `x³ + x + 5 = out`, with `out` public.
+fn cubic(x: u64) -> ConstraintSystem {
+    let mut cs = ConstraintSystem::new();
+    let x_value = Scalar::from(x);
+    let out = cs.alloc_input(x_value * x_value * x_value + x_value + Scalar::from(5));
+    let x = cs.alloc(x_value);
+    let x_squared = cs.mul("x * x", x, x);
+    let x_cubed = cs.mul("x² * x", x_squared, x);
+    cs.enforce(
+        "x³ + x + 5 = out",
+        x_cubed + x + LinearCombination::constant(Scalar::from(5)),
+        Variable::One,
+        out,
+    );
+    cs
+}
+
+#[test]
+fn builds_and_checks_a_circuit() {
+    let cs = cubic(3);
+    assert_eq!(cs.num_inputs(), 1);
+    assert_eq!(cs.num_aux(), 3);
+    assert_eq!(cs.num_constraints(), 3);
+    assert_eq!(cs.witness().inputs, vec![Scalar::from(35)]);
+    cs.is_satisfied().unwrap();
+}
+
+#[test]
+fn names_the_unsatisfied_constraint() {
+    let cs = cubic(3);
+    let mut witness = cs.witness().clone();
+    witness.inputs[0] = Scalar::from(36);
+    assert_eq!(
+        cs.is_satisfied_by(&witness),
+        Err(R1csError::Unsatisfied {
+            index: 2,
+            annotation: "x³ + x + 5 = out".into()
+        })
+    );
+
+    // The same circuit with another x.
+    cs.is_satisfied_by(cubic(4).witness()).unwrap();
+
+    let mut short = cs.witness().clone();
+    short.aux.pop();
+    assert!(matches!(
+        cs.is_satisfied_by(&short),
+        Err(R1csError::Shape { inputs: 1, aux: 2 })
+    ));
+}
+
+#[test]
+fn combines_linearly() {
+    let mut cs = ConstraintSystem::new();
+    let a = cs.alloc(Scalar::from(7));
+    let b = cs.alloc(Scalar::from(2));
+    let lc = (a - b) * Scalar::from(3) + (b, Scalar::from(4));
+    assert_eq!(cs.value(&lc), Scalar::from(23));
+    assert_eq!(cs.value(&-lc), -Scalar::from(23));
+    assert_eq!(cs.value(&LinearCombination::zero()), Scalar::zero());
+}
+
+#[test]
+fn roundtrips_witnesses() {
+    let witness = cubic(3).witness().clone();
+    let bytes = witness.to_bytes();
+    assert_eq!(bytes.len(), 32 + 4 * 32);
+    assert_eq!(Witness::from_bytes(&bytes), Ok(witness));
+    assert_eq!(
+        Witness::from_bytes(&Witness::default().to_bytes()),
+        Ok(Witness::default())
+    );
+}
+
+#[test]
+fn rejects_malformed_witnesses() {
+    let bytes = cubic(3).witness().to_bytes();
+    assert_eq!(
+        Witness::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(R1csError::Truncated)
+    );
+
+    let mut magic = bytes.clone();
+    magic[0] ^= 1;
+    assert_eq!(Witness::from_bytes(&magic), Err(R1csError::Magic));
+
+    let mut version = bytes.clone();
+    version[9] = 2;
+    assert_eq!(Witness::from_bytes(&version), Err(R1csError::Version(2)));
+
+    let mut scalar = bytes;
+    scalar[32 + 2 * 32..32 + 3 * 32].copy_from_slice(&[0xff; 32]);
+    assert_eq!(
+        Witness::from_bytes(&scalar),
+        Err(R1csError::Scalar { index: 2 })
+    );
+}
@@ -0,0 +1,361 @@
+//! Binary agreement, by which `n >= 3f + 1` nodes each holding a bit decide
+//! on the same one, a bit some honest node held, with up to `f` of them
+//! byzantine and no clock.
+//!
+//! This is the signature-free protocol of Mostéfaoui, Moumen and Raynal,
+//! with the `Conf` step of Crain. It goes in rounds, in each of which a node
+//! holds an estimate, its input in the first round:
+//!
+//! 1. It sends `BVal(r, est)`. On `f + 1` of `BVal(r, b)` it sends
+//!    `BVal(r, b)` too, and on `2f + 1` it adds `b` to its `bin_values`, a
+//!    bit some honest node sent.
+//! 2. On the first bit added, it sends `Aux(r, b)`.
+//! 3. On `n - f` auxes carrying bits of `bin_values`, it sends `Conf(r, v)`
+//!    with the bits they carry.
+//! 4. On `n - f` confs whose bits are all in `bin_values`, it reveals its
+//!    share of the coin of the round. Once the coin is in, if the confs all
+//!    carry the same bit alone, that is its next estimate and it decides it
+//!    if the coin came up the same. Otherwise the coin is its next estimate.
+//!
+//! Once an honest node decided `b` in round `r`, every honest node holds `b`
+//! from round `r + 1` on and decides it in the first round after `r` whose
+//! coin is `b`. A node that decided stops once it is past that round, when
+//! there is no one left for it to wait for.
+//!
+//! Safety never depends on the coin, but an adversary that schedules the
+//! messages knowing what it comes up can keep the nodes from deciding. The
+//! [`Coin`] is a threshold signature of the round, which takes more shares
+//! than there are byzantine nodes, and honest nodes only reveal theirs once
+//! the confs fixed the bits they compare it with.
+//!
+//! Like [`Broadcast`](crate::Broadcast), [`Agreement`] keeps the state of one
+//! agreement without doing any IO.
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Rounds further ahead than this are dropped, so that a byzantine node
+/// can't have us store messages of every round there is.
+const MAX_FUTURE_ROUNDS: u32 = 100;
+
+/// The common coin of an agreement, of which each node holds a share.
+pub trait Coin<N> {
+    type Share: Clone;
+
+    /// Our share of the coin of `round`.
+    fn share(&self, round: u32) -> Self::Share;
+
+    /// Whether `share` is the share `node` holds of the coin of `round`.
+    fn verify(&self, node: &N, round: u32, share: &Self::Share) -> bool;
+
+    /// How many shares tell what the coin came up, more than the byzantine
+    /// nodes so they can't tell on their own.
+    fn threshold(&self) -> usize;
+
+    /// What the coin of `round` came up, from at least `threshold` valid
+    /// shares. Any of them give the same.
+    fn combine(&self, round: u32, shares: &[(N, Self::Share)]) -> bool;
+}
+
+/// What nodes send each other in an agreement, each for a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Message<S> {
+    BVal(u32, bool),
+    Aux(u32, bool),
+    /// The bits of the auxes a node went on with.
+    Conf(u32, [bool; 2]),
+    /// A node's share of the coin.
+    Coin(u32, S),
+}
+
+/// What a node has to do after taking a message in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<S> {
+    /// To be sent to every other node. They are already accounted for here.
+    pub messages: Vec<Message<S>>,
+    /// The bit agreed on, the one time it is decided.
+    pub decided: Option<bool>,
+}
+
+impl<S> Default for Step<S> {
+    fn default() -> Self {
+        Step {
+            messages: Vec::new(),
+            decided: None,
+        }
+    }
+}
+
+/// The messages of one round.
+#[derive(Debug, Clone)]
+struct Round<N, S> {
+    /// The nodes that sent `BVal` of each bit.
+    bvals: [BTreeSet<N>; 2],
+    sent: [bool; 2],
+    bin_values: [bool; 2],
+    /// The aux of each node, only the first one counts.
+    auxes: BTreeMap<N, bool>,
+    /// The conf of each node, only the first one counts.
+    confs: BTreeMap<N, [bool; 2]>,
+    /// The bits of the confs we went on with, once we revealed our share.
+    values: Option<[bool; 2]>,
+    /// The valid share of the coin of each node.
+    shares: BTreeMap<N, S>,
+}
+
+impl<N, S> Default for Round<N, S> {
+    fn default() -> Self {
+        Round {
+            bvals: [BTreeSet::new(), BTreeSet::new()],
+            sent: [false; 2],
+            bin_values: [false; 2],
+            auxes: BTreeMap::new(),
+            confs: BTreeMap::new(),
+            values: None,
+            shares: BTreeMap::new(),
+        }
+    }
+}
+
+/// One agreement, as seen by one of the nodes.
+#[derive(Debug, Clone)]
+pub struct Agreement<N, C: Coin<N>> {
+    local: N,
+    nodes: Vec<N>,
+    coin: C,
+    round: u32,
+    /// Our estimate for the current round, `None` until we got our input.
+    estimate: Option<bool>,
+    rounds: BTreeMap<u32, Round<N, C::Share>>,
+    /// The bit decided and the round it was decided in.
+    decided: Option<(bool, u32)>,
+    halted: bool,
+}
+
+impl<N, C> Agreement<N, C>
+where
+    N: Ord + Clone,
+    C: Coin<N>,
+{
+    /// Joins the agreement of `nodes`, of which `local` is the one running
+    /// this, flipping `coin`.
+    pub fn new(local: N, nodes: impl IntoIterator<Item = N>, coin: C) -> Result<Self, Error> {
+        let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        if nodes.binary_search(&local).is_err() {
+            return Err(Error::UnknownNode);
+        }
+
+        Ok(Agreement {
+            local,
+            nodes,
+            coin,
+            round: 0,
+            estimate: None,
+            rounds: BTreeMap::new(),
+            decided: None,
+            halted: false,
+        })
+    }
+
+    /// How many byzantine nodes are tolerated, `⌊(n - 1) / 3⌋`.
+    pub fn faulty(&self) -> usize {
+        (self.nodes.len() - 1) / 3
+    }
+
+    /// The bit, once it has been decided.
+    pub fn decided(&self) -> Option<bool> {
+        self.decided.map(|(bit, _)| bit)
+    }
+
+    /// Whether we got our input yet.
+    pub fn has_input(&self) -> bool {
+        self.estimate.is_some()
+    }
+
+    /// Starts the agreement with our bit, only the first input counts.
+    pub fn input(&mut self, bit: bool) -> Step<C::Share> {
+        let mut step = Step::default();
+        if self.has_input() {
+            return step;
+        }
+        self.estimate = Some(bit);
+        self.progress(&mut step);
+        step
+    }
+
+    /// Takes in a message from `from`. Fails if it can't come from an honest
+    /// node, the step is then empty.
+    pub fn handle(
+        &mut self,
+        from: &N,
+        message: Message<C::Share>,
+    ) -> Result<Step<C::Share>, Error> {
+        if self.nodes.binary_search(from).is_err() {
+            return Err(Error::UnknownNode);
+        }
+
+        let mut step = Step::default();
+        let round = match message {
+            Message::BVal(round, _)
+            | Message::Aux(round, _)
+            | Message::Conf(round, _)
+            | Message::Coin(round, _) => round,
+        };
+        if round > self.round.saturating_add(MAX_FUTURE_ROUNDS) {
+            return Ok(step);
+        }
+        let state = self.rounds.entry(round).or_default();
+        match message {
+            Message::BVal(_, bit) => {
+                state.bvals[bit as usize].insert(from.clone());
+            }
+            Message::Aux(_, bit) => match state.auxes.get(from) {
+                Some(aux) if *aux != bit => return Err(Error::Equivocation),
+                Some(_) => return Ok(step),
+                None => {
+                    state.auxes.insert(from.clone(), bit);
+                }
+            },
+            Message::Conf(_, [false, false]) => return Err(Error::Invalid),
+            Message::Conf(_, bits) => match state.confs.get(from) {
+                Some(conf) if *conf != bits => return Err(Error::Equivocation),
+                Some(_) => return Ok(step),
+                None => {
+                    state.confs.insert(from.clone(), bits);
+                }
+            },
+            Message::Coin(_, share) => {
+                if state.shares.contains_key(from) {
+                    return Ok(step);
+                }
+                if !self.coin.verify(from, round, &share) {
+                    return Err(Error::Invalid);
+                }
+                state.shares.insert(from.clone(), share);
+            }
+        }
+
+        // Older rounds only need our relays, for the nodes still at them.
+        if round < self.round {
+            self.update(round, &mut step);
+        } else if round == self.round && self.estimate.is_some() {
+            self.progress(&mut step);
+        }
+        Ok(step)
+    }
+
+    /// Relays the bits of `round` enough nodes sent, adds those enough sent
+    /// to `bin_values`, and sends our aux once there is one.
+    fn update(&mut self, round: u32, step: &mut Step<C::Share>) {
+        let f = self.faulty();
+        let local = self.local.clone();
+        let state = self.rounds.entry(round).or_default();
+        for bit in [false, true] {
+            let i = bit as usize;
+            if state.bvals[i].len() > f && !state.sent[i] {
+                state.sent[i] = true;
+                state.bvals[i].insert(local.clone());
+                step.messages.push(Message::BVal(round, bit));
+            }
+            if state.bvals[i].len() > 2 * f && !state.bin_values[i] {
+                state.bin_values[i] = true;
+                if !state.auxes.contains_key(&local) {
+                    state.auxes.insert(local.clone(), bit);
+                    step.messages.push(Message::Aux(round, bit));
+                }
+            }
+        }
+    }
+
+    /// Runs the current round as far as the messages in allow, and the
+    /// rounds after it.
+    fn progress(&mut self, step: &mut Step<C::Share>) {
+        let quorum = self.nodes.len() - self.faulty();
+        while let Some(estimate) = self.estimate.filter(|_| !self.halted) {
+            let round = self.round;
+            let local = self.local.clone();
+            let state = self.rounds.entry(round).or_default();
+            if !state.sent[estimate as usize] {
+                state.sent[estimate as usize] = true;
+                state.bvals[estimate as usize].insert(local.clone());
+                step.messages.push(Message::BVal(round, estimate));
+            }
+            self.update(round, step);
+
+            let state = self.rounds.get_mut(&round).expect("Created above");
+            if !state.confs.contains_key(&local) {
+                let auxes = state.auxes.values().map(|bit| bit_set(*bit));
+                let values = match supported(auxes, state.bin_values, quorum) {
+                    Some(values) => values,
+                    None => return,
+                };
+                state.confs.insert(local.clone(), values);
+                step.messages.push(Message::Conf(round, values));
+            }
+            if state.values.is_none() {
+                let confs = state.confs.values().copied();
+                state.values = match supported(confs, state.bin_values, quorum) {
+                    Some(values) => Some(values),
+                    None => return,
+                };
+                let share = self.coin.share(round);
+                step.messages.push(Message::Coin(round, share.clone()));
+                let state = self.rounds.get_mut(&round).expect("Created above");
+                state.shares.insert(local, share);
+            }
+
+            let state = &self.rounds[&round];
+            if state.shares.len() < self.coin.threshold() {
+                return;
+            }
+            let shares = state
+                .shares
+                .iter()
+                .map(|(node, share)| (node.clone(), share.clone()))
+                .collect::<Vec<_>>();
+            let values = state.values.expect("Set above");
+            let coin = self.coin.combine(round, &shares);
+            let next = match values {
+                [true, true] => coin,
+                _ => values[1],
+            };
+            if values != [true, true] && next == coin && self.decided.is_none() {
+                self.decided = Some((next, round));
+                step.decided = Some(next);
+            }
+            if let Some((bit, decided)) = self.decided {
+                if round > decided && coin == bit {
+                    self.halted = true;
+                }
+            }
+            self.estimate = Some(next);
+            self.round += 1;
+        }
+    }
+}
+
+/// The bits `bit` stands for.
+fn bit_set(bit: bool) -> [bool; 2] {
+    [!bit, bit]
+}
+
+/// The bits carried by the `sets` of bits all in `bin_values`, once there
+/// are `quorum` of them.
+fn supported(
+    sets: impl Iterator<Item = [bool; 2]>,
+    bin_values: [bool; 2],
+    quorum: usize,
+) -> Option<[bool; 2]> {
+    let mut values = [false; 2];
+    let mut count = 0;
+    for set in sets.filter(|set| (0..2).all(|i| !set[i] || bin_values[i])) {
+        values = [values[0] || set[0], values[1] || set[1]];
+        count += 1;
+    }
+    (count >= quorum).then_some(values)
+}
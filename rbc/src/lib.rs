@@ -0,0 +1,232 @@
+//! Bracha's reliable broadcast, which gets a sender's message to every honest
+//! node even when up to `f` of the `n >= 3f + 1` nodes, the sender included,
+//! are byzantine.
+//!
+//! Gossip only promises that what one node got, others will likely get too.
+//! A sender telling different nodes different things goes unnoticed. With
+//! reliable broadcast, if any honest node delivers a message then every
+//! honest node eventually delivers that same message:
+//!
+//! 1. The sender sends `Send(m)` to everyone.
+//! 2. On the sender's `Send(m)`, a node sends `Echo(m)` to everyone.
+//! 3. On `⌈(n + f + 1) / 2⌉` echoes of `m`, or `f + 1` readies for it, a node
+//!    sends `Ready(m)` to everyone.
+//! 4. On `2f + 1` readies for `m`, a node delivers `m`.
+//!
+//! Each step is taken at most once per broadcast. The echo quorum keeps two
+//! honest nodes from getting ready for different messages, and the `f + 1`
+//! readies rule carries the nodes that missed the echoes along.
+//!
+//! [`Broadcast`] only keeps the state of one broadcast and never does any IO,
+//! so it runs over whatever transport the caller has: it is handed the
+//! messages that arrive and returns the ones to send. The transport must
+//! authenticate who a message comes from, and eventually deliver the messages
+//! between honest nodes.
+//!
+//! The [`agreement`] module builds binary agreement on the same footing.
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod agreement;
+
+/// What nodes send each other in a broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Message<M> {
+    Send(M),
+    Echo(M),
+    Ready(M),
+}
+
+/// What a node has to do after taking a message in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<M> {
+    /// To be sent to every other node. They are already accounted for here.
+    pub messages: Vec<Message<M>>,
+    /// The broadcast message, the one time it is delivered.
+    pub delivered: Option<M>,
+}
+
+impl<M> Default for Step<M> {
+    fn default() -> Self {
+        Step {
+            messages: Vec::new(),
+            delivered: None,
+        }
+    }
+}
+
+/// One broadcast, as seen by one of the nodes.
+#[derive(Debug, Clone)]
+pub struct Broadcast<N, M> {
+    local: N,
+    sender: N,
+    nodes: Vec<N>,
+    /// The echo of each node, only the first one counts.
+    echoes: BTreeMap<N, M>,
+    /// The ready of each node, only the first one counts.
+    readies: BTreeMap<N, M>,
+    echoed: bool,
+    ready: bool,
+    delivered: Option<M>,
+}
+
+impl<N, M> Broadcast<N, M>
+where
+    N: Ord + Clone,
+    M: Eq + Clone,
+{
+    /// Joins the broadcast of `sender` to `nodes`, of which `local` is the
+    /// one running this.
+    pub fn new(local: N, sender: N, nodes: impl IntoIterator<Item = N>) -> Result<Self, Error> {
+        let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        if nodes.binary_search(&local).is_err() || nodes.binary_search(&sender).is_err() {
+            return Err(Error::UnknownNode);
+        }
+
+        Ok(Broadcast {
+            local,
+            sender,
+            nodes,
+            echoes: BTreeMap::new(),
+            readies: BTreeMap::new(),
+            echoed: false,
+            ready: false,
+            delivered: None,
+        })
+    }
+
+    /// How many byzantine nodes are tolerated, `⌊(n - 1) / 3⌋`.
+    pub fn faulty(&self) -> usize {
+        (self.nodes.len() - 1) / 3
+    }
+
+    /// The message, once it has been delivered.
+    pub fn delivered(&self) -> Option<&M> {
+        self.delivered.as_ref()
+    }
+
+    /// Starts the broadcast of `message`, only the sender may.
+    pub fn broadcast(&mut self, message: M) -> Result<Step<M>, Error> {
+        if self.local != self.sender {
+            return Err(Error::NotTheSender);
+        }
+        let mut step = Step::default();
+        step.messages.push(Message::Send(message.clone()));
+        self.echo(message, &mut step);
+        Ok(step)
+    }
+
+    /// Takes in a message from `from`. Fails if it can't come from an honest
+    /// node, the step is then empty.
+    pub fn handle(&mut self, from: &N, message: Message<M>) -> Result<Step<M>, Error> {
+        if self.nodes.binary_search(from).is_err() {
+            return Err(Error::UnknownNode);
+        }
+
+        let mut step = Step::default();
+        match message {
+            Message::Send(_) if *from != self.sender => return Err(Error::NotTheSender),
+            Message::Send(message) => self.echo(message, &mut step),
+            Message::Echo(message) => {
+                if let Some(echo) = self.echoes.get(from) {
+                    return conflict(echo, &message, step);
+                }
+                self.echoes.insert(from.clone(), message.clone());
+                self.check_echoes(&message, &mut step);
+            }
+            Message::Ready(message) => {
+                if let Some(ready) = self.readies.get(from) {
+                    return conflict(ready, &message, step);
+                }
+                self.readies.insert(from.clone(), message.clone());
+                self.check_readies(&message, &mut step);
+            }
+        }
+        Ok(step)
+    }
+
+    fn echo(&mut self, message: M, step: &mut Step<M>) {
+        if self.echoed {
+            return;
+        }
+        self.echoed = true;
+        step.messages.push(Message::Echo(message.clone()));
+        self.echoes.insert(self.local.clone(), message.clone());
+        self.check_echoes(&message, step);
+    }
+
+    fn check_echoes(&mut self, message: &M, step: &mut Step<M>) {
+        let n = self.nodes.len();
+        let quorum = (n + self.faulty() + 2) / 2;
+        if count(&self.echoes, message) >= quorum {
+            self.get_ready(message.clone(), step);
+        }
+    }
+
+    fn check_readies(&mut self, message: &M, step: &mut Step<M>) {
+        let f = self.faulty();
+        let readies = count(&self.readies, message);
+        if readies > f {
+            self.get_ready(message.clone(), step);
+        }
+        if readies > 2 * f && self.delivered.is_none() {
+            self.delivered = Some(message.clone());
+            step.delivered = Some(message.clone());
+        }
+    }
+
+    fn get_ready(&mut self, message: M, step: &mut Step<M>) {
+        if self.ready {
+            return;
+        }
+        self.ready = true;
+        step.messages.push(Message::Ready(message.clone()));
+        self.readies.insert(self.local.clone(), message.clone());
+        self.check_readies(&message, step);
+    }
+}
+
+fn count<N, M: Eq>(votes: &BTreeMap<N, M>, message: &M) -> usize {
+    votes.values().filter(|vote| *vote == message).count()
+}
+
+/// A node repeating itself is fine, one changing its mind isn't.
+fn conflict<M: Eq>(first: &M, again: &M, step: Step<M>) -> Result<Step<M>, Error> {
+    if first == again {
+        Ok(step)
+    } else {
+        Err(Error::Equivocation)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The node isn't one of the broadcast's nodes.
+    UnknownNode,
+    /// Only the sender may send the message to be broadcast.
+    NotTheSender,
+    /// The node already echoed, or got ready for, another message.
+    Equivocation,
+    /// No honest node sends the message, a coin share that doesn't verify
+    /// say.
+    Invalid,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::UnknownNode => "not a node of the broadcast",
+            Error::NotTheSender => "not the sender of the broadcast",
+            Error::Equivocation => "sent two different messages in the same phase",
+            Error::Invalid => "sent a message no honest node sends",
+        })
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,243 @@
+use rbc::agreement::{Agreement, Coin, Message, Step};
+use rbc::Error;
+
+/// A coin anyone can tell, splitmix64 of a seed and the round, each node's
+/// share being the coin itself. Agreement is safe with any coin.
+#[derive(Debug, Clone)]
+struct HashCoin {
+    seed: u64,
+    threshold: usize,
+}
+
+impl Coin<usize> for HashCoin {
+    type Share = bool;
+
+    fn share(&self, round: u32) -> bool {
+        let mut z = self
+            .seed
+            .wrapping_add((round as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31)) & 1 == 1
+    }
+
+    fn verify(&self, _: &usize, round: u32, share: &bool) -> bool {
+        *share == self.share(round)
+    }
+
+    fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    fn combine(&self, round: u32, shares: &[(usize, bool)]) -> bool {
+        assert!(shares.len() >= self.threshold);
+        self.share(round)
+    }
+}
+
+/// The coin of a committee of `n`, which takes `f + 1` shares.
+fn coin(n: usize) -> HashCoin {
+    HashCoin {
+        seed: 7,
+        threshold: (n - 1) / 3 + 1,
+    }
+}
+
+/// Nodes `0..n`, the messages between them delivered in an order picked by
+/// `seed`, except to and from the nodes in `silent`.
+struct Network {
+    nodes: Vec<Agreement<usize, HashCoin>>,
+    queue: Vec<(usize, usize, Message<bool>)>,
+    silent: Vec<usize>,
+    decided: Vec<Option<bool>>,
+    /// A xorshift state, enough for shuffling.
+    state: u64,
+}
+
+impl Network {
+    fn new(n: usize, silent: Vec<usize>, seed: u64) -> Self {
+        Network {
+            nodes: (0..n)
+                .map(|i| Agreement::new(i, 0..n, coin(n)).unwrap())
+                .collect(),
+            queue: Vec::new(),
+            silent,
+            decided: vec![None; n],
+            state: seed | 1,
+        }
+    }
+
+    fn push(&mut self, from: usize, step: Step<bool>) {
+        if let Some(bit) = step.decided {
+            assert_eq!(self.decided[from], None, "decided twice");
+            self.decided[from] = Some(bit);
+        }
+        for message in step.messages {
+            for to in 0..self.nodes.len() {
+                if to != from {
+                    self.queue.push((from, to, message));
+                }
+            }
+        }
+    }
+
+    fn input(&mut self, inputs: &[bool]) {
+        for (i, bit) in inputs.iter().enumerate() {
+            if !self.silent.contains(&i) {
+                let step = self.nodes[i].input(*bit);
+                self.push(i, step);
+            }
+        }
+    }
+
+    fn next(&mut self) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state as usize
+    }
+
+    fn run(&mut self) {
+        while !self.queue.is_empty() {
+            let i = self.next() % self.queue.len();
+            let (from, to, message) = self.queue.swap_remove(i);
+            if self.silent.contains(&from) || self.silent.contains(&to) {
+                continue;
+            }
+            let step = self.nodes[to].handle(&from, message).unwrap();
+            self.push(to, step);
+        }
+    }
+
+    fn honest(&self) -> Vec<Option<bool>> {
+        (0..self.nodes.len())
+            .filter(|i| !self.silent.contains(i))
+            .map(|i| self.decided[i])
+            .collect()
+    }
+}
+
+#[test]
+fn unanimous_input_is_decided() {
+    for bit in [false, true] {
+        let mut network = Network::new(4, vec![], 1);
+        network.input(&[bit; 4]);
+        network.run();
+        assert_eq!(network.decided, vec![Some(bit); 4]);
+    }
+}
+
+#[test]
+fn split_inputs_agree_in_any_order() {
+    for seed in 0..50 {
+        let mut network = Network::new(7, vec![], seed);
+        network.input(&[true, false, true, false, true, false, false]);
+        network.run();
+        let decided = network.decided[0];
+        assert!(decided.is_some());
+        assert_eq!(network.decided, vec![decided; 7], "seed {}", seed);
+    }
+}
+
+#[test]
+fn tolerates_silent_nodes() {
+    // 7 nodes tolerate 2 faults.
+    for seed in 0..20 {
+        let mut network = Network::new(7, vec![5, 6], seed);
+        network.input(&[true, true, false, true, false, false, false]);
+        network.run();
+        let honest = network.honest();
+        assert!(honest[0].is_some());
+        assert!(honest.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+}
+
+#[test]
+fn byzantine_votes_dont_overturn_a_unanimous_input() {
+    // Node 3 votes for the other bit in every round the others get to.
+    for seed in 0..20 {
+        let mut network = Network::new(4, vec![3], seed);
+        for round in 0..4 {
+            for to in 0..3 {
+                for message in [Message::BVal(round, false), Message::Aux(round, false)] {
+                    let step = network.nodes[to].handle(&3, message).unwrap();
+                    network.push(to, step);
+                }
+            }
+        }
+        network.input(&[true; 4]);
+        network.run();
+        assert_eq!(network.decided[..3], [Some(true); 3]);
+    }
+}
+
+#[test]
+fn rejects_misbehaving_nodes() {
+    let mut node = Agreement::new(1, 0..4, coin(4)).unwrap();
+    assert_eq!(
+        Agreement::new(7, 0..4, coin(4)).unwrap_err(),
+        Error::UnknownNode
+    );
+    assert_eq!(
+        node.handle(&7, Message::BVal(0, true)),
+        Err(Error::UnknownNode)
+    );
+
+    node.handle(&2, Message::Aux(0, true)).unwrap();
+    assert_eq!(node.handle(&2, Message::Aux(0, true)), Ok(Step::default()));
+    assert_eq!(
+        node.handle(&2, Message::Aux(0, false)),
+        Err(Error::Equivocation)
+    );
+
+    assert_eq!(
+        node.handle(&2, Message::Conf(0, [false, false])),
+        Err(Error::Invalid)
+    );
+    node.handle(&2, Message::Conf(0, [true, true])).unwrap();
+    assert_eq!(
+        node.handle(&2, Message::Conf(0, [false, true])),
+        Err(Error::Equivocation)
+    );
+
+    let share = coin(4).share(0);
+    assert_eq!(
+        node.handle(&2, Message::Coin(0, !share)),
+        Err(Error::Invalid)
+    );
+    assert_eq!(
+        node.handle(&2, Message::Coin(0, share)),
+        Ok(Step::default())
+    );
+}
+
+#[test]
+fn the_coin_is_revealed_after_the_confs() {
+    let mut node = Agreement::new(0, 0..4, coin(4)).unwrap();
+    let mut messages = node.input(true).messages;
+    for from in [1, 2] {
+        messages.extend(node.handle(&from, Message::BVal(0, true)).unwrap().messages);
+    }
+    for from in [1, 2] {
+        messages.extend(node.handle(&from, Message::Aux(0, true)).unwrap().messages);
+    }
+    assert_eq!(
+        messages,
+        [
+            Message::BVal(0, true),
+            Message::Aux(0, true),
+            Message::Conf(0, [false, true])
+        ]
+    );
+
+    // Two confs of the three it takes.
+    let step = node.handle(&1, Message::Conf(0, [false, true])).unwrap();
+    assert_eq!(step, Step::default());
+    let share = coin(4).share(0);
+    let step = node.handle(&2, Message::Conf(0, [false, true])).unwrap();
+    assert_eq!(step.messages, [Message::Coin(0, share)]);
+
+    // The bit is decided if the coin, which takes two shares, agrees.
+    let step = node.handle(&1, Message::Coin(0, share)).unwrap();
+    assert_eq!(step.decided, share.then_some(true));
+}
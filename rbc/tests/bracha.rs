@@ -0,0 +1,121 @@
+use rbc::{Broadcast, Error, Message, Step};
+use std::collections::VecDeque;
+
+/// Nodes `0..n`, the messages between them delivered in the order they were
+/// sent, except to and from the nodes in `silent`.
+struct Network {
+    nodes: Vec<Broadcast<usize, &'static str>>,
+    queue: VecDeque<(usize, usize, Message<&'static str>)>,
+    silent: Vec<usize>,
+    delivered: Vec<Option<&'static str>>,
+}
+
+impl Network {
+    fn new(n: usize, sender: usize, silent: Vec<usize>) -> Self {
+        Network {
+            nodes: (0..n)
+                .map(|i| Broadcast::new(i, sender, 0..n).unwrap())
+                .collect(),
+            queue: VecDeque::new(),
+            silent,
+            delivered: vec![None; n],
+        }
+    }
+
+    fn push(&mut self, from: usize, step: Step<&'static str>) {
+        if let Some(message) = step.delivered {
+            assert_eq!(self.delivered[from], None, "delivered twice");
+            self.delivered[from] = Some(message);
+        }
+        for message in step.messages {
+            for to in 0..self.nodes.len() {
+                if to != from {
+                    self.queue.push_back((from, to, message.clone()));
+                }
+            }
+        }
+    }
+
+    /// Sends `message` from `from` to the nodes in `to` only, as a byzantine
+    /// node would.
+    fn send(&mut self, from: usize, to: &[usize], message: Message<&'static str>) {
+        for to in to {
+            self.queue.push_back((from, *to, message.clone()));
+        }
+    }
+
+    fn run(&mut self) {
+        while let Some((from, to, message)) = self.queue.pop_front() {
+            if self.silent.contains(&from) || self.silent.contains(&to) {
+                continue;
+            }
+            let step = self.nodes[to].handle(&from, message).unwrap();
+            self.push(to, step);
+        }
+    }
+}
+
+#[test]
+fn honest_sender_reaches_everyone() {
+    let mut network = Network::new(4, 0, vec![]);
+    let step = network.nodes[0].broadcast("commitments").unwrap();
+    network.push(0, step);
+    network.run();
+
+    assert_eq!(network.delivered, vec![Some("commitments"); 4]);
+}
+
+#[test]
+fn tolerates_silent_nodes() {
+    // 7 nodes tolerate 2 faults.
+    let mut network = Network::new(7, 0, vec![5, 6]);
+    let step = network.nodes[0].broadcast("commitments").unwrap();
+    network.push(0, step);
+    network.run();
+
+    for i in 0..5 {
+        assert_eq!(network.delivered[i], Some("commitments"));
+    }
+}
+
+#[test]
+fn equivocating_sender_splits_no_one() {
+    let mut network = Network::new(4, 0, vec![]);
+    network.send(0, &[1, 2], Message::Send("a"));
+    network.send(0, &[3], Message::Send("b"));
+    network.run();
+
+    let delivered = network.delivered[1..].iter().flatten().collect::<Vec<_>>();
+    assert!(delivered.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[test]
+fn readies_carry_nodes_that_missed_the_echoes() {
+    // Node 3 sees neither the sender nor the echoes, only the readies.
+    let mut node = Broadcast::new(3, 0, 0..4).unwrap();
+    let step = node.handle(&1, Message::Ready("m")).unwrap();
+    assert!(step.messages.is_empty());
+
+    let step = node.handle(&2, Message::Ready("m")).unwrap();
+    assert_eq!(step.messages, vec![Message::Ready("m")]);
+    assert_eq!(step.delivered, Some("m"));
+    assert_eq!(node.delivered(), Some(&"m"));
+}
+
+#[test]
+fn rejects_misbehaving_nodes() {
+    let mut node = Broadcast::new(1, 0, 0..4).unwrap();
+    assert_eq!(node.broadcast("m"), Err(Error::NotTheSender));
+    assert_eq!(
+        node.handle(&2, Message::Send("m")),
+        Err(Error::NotTheSender)
+    );
+    assert_eq!(node.handle(&7, Message::Echo("m")), Err(Error::UnknownNode));
+
+    node.handle(&2, Message::Echo("a")).unwrap();
+    assert_eq!(node.handle(&2, Message::Echo("a")), Ok(Step::default()));
+    assert_eq!(
+        node.handle(&2, Message::Echo("b")),
+        Err(Error::Equivocation)
+    );
+}
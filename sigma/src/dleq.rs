@@ -0,0 +1,134 @@
+//! Chaum-Pedersen proofs that two points are multiples of their bases by
+//! the same scalar, `log_G(A) == log_M(B)`, without revealing it.
+//!
+//! The prover of `A = x * G` and `B = x * M` picks a random k and computes
+//!
+//! R1 = k * G
+//! R2 = k * M
+//! c  = H(context, G, A, M, B, R1, R2)
+//! z  = k - c * x
+//!
+//! and the verifier recomputes `R1 = z * G + c * A` and `R2 = z * M + c * B`.
+//!
+//! The two bases can be in different groups, both have the order of the
+//! scalar field. A partial signature `s_i * H(m)` in G2 is proven consistent
+//! with its public share `s_i * G` in G1 this way, for two multiplications
+//! in each group instead of a product of two pairings.
+use crate::protocol::SigmaProtocol;
+use crate::transcript::Transcript;
+use crate::Proof;
+use bls12_381::Scalar;
+use group::ff::Field;
+use group::{Group, GroupEncoding};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+const DOMAIN: &[u8] = b"zk-lab/sigma/dleq";
+
+/// The statement that `a = x * g` and `b = x * m` for an x the prover
+/// knows, for composing with other statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dleq<A, B> {
+    pub g: A,
+    pub a: A,
+    pub m: B,
+    pub b: B,
+}
+
+/// Proves that `secret * g` and `secret * m` share their discrete logarithm.
+pub fn prove<A, B, R>(secret: &Scalar, g: &A, m: &B, context: &[u8], rng: &mut R) -> Proof
+where
+    A: Group<Scalar = Scalar> + GroupEncoding,
+    B: Group<Scalar = Scalar> + GroupEncoding,
+    R: RngCore + CryptoRng,
+{
+    let a = *g * *secret;
+    let b = *m * *secret;
+    let k = Zeroizing::new(Scalar::random(&mut *rng));
+    let challenge = challenge(g, &a, m, &b, &(*g * *k), &(*m * *k), context);
+
+    Proof {
+        challenge,
+        response: *k - challenge * *secret,
+    }
+}
+
+/// Checks that `a` and `b` are multiples of `g` and `m` by the same scalar,
+/// with a proof made under `context`.
+pub fn verify<A, B>(g: &A, a: &A, m: &B, b: &B, context: &[u8], proof: &Proof) -> bool
+where
+    A: Group<Scalar = Scalar> + GroupEncoding,
+    B: Group<Scalar = Scalar> + GroupEncoding,
+{
+    let r1 = *g * proof.response + *a * proof.challenge;
+    let r2 = *m * proof.response + *b * proof.challenge;
+    challenge(g, a, m, b, &r1, &r2, context) == proof.challenge
+}
+
+fn challenge<A: GroupEncoding, B: GroupEncoding>(
+    g: &A,
+    a: &A,
+    m: &B,
+    b: &B,
+    r1: &A,
+    r2: &B,
+    context: &[u8],
+) -> Scalar {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_message(b"context", context);
+    transcript.append_point(b"G", g);
+    transcript.append_point(b"A", a);
+    transcript.append_point(b"M", m);
+    transcript.append_point(b"B", b);
+    transcript.append_point(b"R1", r1);
+    transcript.append_point(b"R2", r2);
+    transcript.challenge_scalar(b"c")
+}
+
+impl<A, B> SigmaProtocol for Dleq<A, B>
+where
+    A: Group<Scalar = Scalar> + GroupEncoding,
+    B: Group<Scalar = Scalar> + GroupEncoding,
+{
+    type Witness = Scalar;
+    type Commitment = (A, B);
+    type State = Zeroizing<Scalar>;
+    type Response = Scalar;
+
+    fn commit<R: RngCore + CryptoRng>(&self, _: &Scalar, rng: &mut R) -> ((A, B), Self::State) {
+        let k = Zeroizing::new(Scalar::random(&mut *rng));
+        ((self.g * *k, self.m * *k), k)
+    }
+
+    fn respond(&self, secret: &Scalar, k: Self::State, challenge: &Scalar) -> Scalar {
+        *k - challenge * secret
+    }
+
+    fn verify(&self, (r1, r2): &(A, B), challenge: &Scalar, response: &Scalar) -> bool {
+        self.g * response + self.a * challenge == *r1
+            && self.m * response + self.b * challenge == *r2
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        challenge: &Scalar,
+        rng: &mut R,
+    ) -> ((A, B), Scalar) {
+        let response = Scalar::random(&mut *rng);
+        let r1 = self.g * response + self.a * challenge;
+        let r2 = self.m * response + self.b * challenge;
+        ((r1, r2), response)
+    }
+
+    fn append_statement(&self, transcript: &mut Transcript) {
+        transcript.append_point(b"dleq/G", &self.g);
+        transcript.append_point(b"dleq/A", &self.a);
+        transcript.append_point(b"dleq/M", &self.m);
+        transcript.append_point(b"dleq/B", &self.b);
+    }
+
+    fn append_commitment(&self, (r1, r2): &(A, B), transcript: &mut Transcript) {
+        transcript.append_point(b"dleq/R1", r1);
+        transcript.append_point(b"dleq/R2", r2);
+    }
+}
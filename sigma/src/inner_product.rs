@@ -0,0 +1,277 @@
+//! The inner product argument of Bulletproofs (Bünz, Bootle, Boneh,
+//! Poelstra, Wuille and Maxwell): a proof that
+//!
+//! P = <a, G> + <b, H> + <a, b> * Q
+//!
+//! for vectors a and b the prover knows, in `2 * log2(n)` points and two
+//! scalars.
+//!
+//! Each round splits the vectors in halves, publishes
+//!
+//! L = <a_lo, G_hi> + <b_hi, H_lo> + <a_lo, b_hi> * Q
+//! R = <a_hi, G_lo> + <b_lo, H_hi> + <a_hi, b_lo> * Q
+//!
+//! and after the challenge u folds them into
+//!
+//! a' = u * a_lo + u⁻¹ * a_hi    G' = u⁻¹ * G_lo + u * G_hi
+//! b' = u⁻¹ * b_lo + u * b_hi    H' = u * H_lo + u⁻¹ * H_hi
+//!
+//! so that `P' = P + u² * L + u⁻² * R` has the same form at half the size.
+//! The verifier doesn't fold the generators round by round: the final G is
+//! `∑ s_i * G_i`, where `s_i` multiplies u or u⁻¹ of every round depending on
+//! the half index i fell in, and the final H is `∑ s_i⁻¹ * H_i`.
+//!
+//! When b is public, as the powers of an evaluation point are in a
+//! polynomial commitment, the relation is `P = <a, G> + <a, b> * Q` and
+//! there is no H: the rounds are the same without the H terms, and the
+//! verifier folds b itself into `<s, b>` instead of taking the prover's.
+use crate::transcript::Transcript;
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{G1Projective, Scalar};
+
+const DST: &[u8] = b"ZK_LAB_VECTOR_COMMITMENT_XMD:SHA-256_SSWU_RO_";
+
+/// Bases for committing to pairs of vectors, hashed to the curve so that
+/// nobody knows a relation between any of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorGenerators {
+    pub g: Vec<G1Projective>,
+    pub h: Vec<G1Projective>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerProductProof {
+    pub l: Vec<G1Projective>,
+    pub r: Vec<G1Projective>,
+    pub a: Scalar,
+    pub b: Scalar,
+}
+
+impl VectorGenerators {
+    /// The generators of `label` for vectors of up to `capacity` entries.
+    pub fn new(label: &[u8], capacity: usize) -> Self {
+        let hash = |base: &[u8], i: usize| {
+            let msg = [label, b"/", base, &(i as u64).to_be_bytes()].concat();
+            <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, DST)
+        };
+        VectorGenerators {
+            g: (0..capacity).map(|i| hash(b"G", i)).collect(),
+            h: (0..capacity).map(|i| hash(b"H", i)).collect(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.g.len()
+    }
+
+    /// `<a, G> + <b, H>`, over the first generators.
+    pub fn commit(&self, a: &[Scalar], b: &[Scalar]) -> G1Projective {
+        assert!(
+            a.len() <= self.capacity() && b.len() <= self.capacity(),
+            "The vectors fit in the generators"
+        );
+        msm(a, &self.g) + msm(b, &self.h)
+    }
+}
+
+impl InnerProductProof {
+    /// Proves the relation for `a` and `b`, whose length has to be a power
+    /// of two and the same as the generators'.
+    pub fn prove(
+        transcript: &mut Transcript,
+        q: &G1Projective,
+        g: Vec<G1Projective>,
+        h: Vec<G1Projective>,
+        a: Vec<Scalar>,
+        b: Vec<Scalar>,
+    ) -> Self {
+        assert_eq!(h.len(), g.len(), "There are as many H as G");
+        Self::fold(transcript, q, g, Some(h), a, b)
+    }
+
+    /// Proves the relation for `a` and the public `b`, without H.
+    pub fn prove_public(
+        transcript: &mut Transcript,
+        q: &G1Projective,
+        g: Vec<G1Projective>,
+        a: Vec<Scalar>,
+        b: Vec<Scalar>,
+    ) -> Self {
+        Self::fold(transcript, q, g, None, a, b)
+    }
+
+    fn fold(
+        transcript: &mut Transcript,
+        q: &G1Projective,
+        mut g: Vec<G1Projective>,
+        mut h: Option<Vec<G1Projective>>,
+        mut a: Vec<Scalar>,
+        mut b: Vec<Scalar>,
+    ) -> Self {
+        let n = a.len();
+        assert!(n.is_power_of_two(), "The length is a power of two");
+        assert!(
+            b.len() == n && g.len() == n,
+            "The vectors and generators have the same length"
+        );
+
+        let (mut ls, mut rs) = (Vec::new(), Vec::new());
+        while a.len() > 1 {
+            let half = a.len() / 2;
+            let (a_lo, a_hi) = a.split_at(half);
+            let (b_lo, b_hi) = b.split_at(half);
+            let (g_lo, g_hi) = g.split_at(half);
+
+            let mut l = msm(a_lo, g_hi) + q * inner(a_lo, b_hi);
+            let mut r = msm(a_hi, g_lo) + q * inner(a_hi, b_lo);
+            if let Some(h) = &h {
+                let (h_lo, h_hi) = h.split_at(half);
+                l += msm(b_hi, h_lo);
+                r += msm(b_lo, h_hi);
+            }
+            transcript.append_point(b"L", &l);
+            transcript.append_point(b"R", &r);
+            let u = transcript.challenge_scalar(b"u");
+            let u_inv = u.invert().unwrap_or_else(Scalar::zero);
+
+            a = fold_scalars(a_lo, a_hi, u, u_inv);
+            b = fold_scalars(b_lo, b_hi, u_inv, u);
+            g = fold_points(g_lo, g_hi, u_inv, u);
+            h = h.map(|h| {
+                let (h_lo, h_hi) = h.split_at(half);
+                fold_points(h_lo, h_hi, u, u_inv)
+            });
+            ls.push(l);
+            rs.push(r);
+        }
+
+        InnerProductProof {
+            l: ls,
+            r: rs,
+            a: a[0],
+            b: b[0],
+        }
+    }
+
+    /// Checks the proof of the relation for `p`.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        q: &G1Projective,
+        p: &G1Projective,
+        g: &[G1Projective],
+        h: &[G1Projective],
+    ) -> bool {
+        if h.len() != g.len() {
+            return false;
+        }
+        let (folded, s) = match self.unfold(transcript, p, g.len()) {
+            Some(unfolded) => unfolded,
+            None => return false,
+        };
+
+        let s_inv = s
+            .iter()
+            .map(|s| s.invert().unwrap_or_else(Scalar::zero))
+            .collect::<Vec<_>>();
+        let a_s = s.iter().map(|s| self.a * s).collect::<Vec<_>>();
+        let b_s = s_inv.iter().map(|s| self.b * s).collect::<Vec<_>>();
+        folded == msm(&a_s, g) + msm(&b_s, h) + q * (self.a * self.b)
+    }
+
+    /// Checks the proof of the relation for `p` and the public `b`.
+    pub fn verify_public(
+        &self,
+        transcript: &mut Transcript,
+        q: &G1Projective,
+        p: &G1Projective,
+        g: &[G1Projective],
+        b: &[Scalar],
+    ) -> bool {
+        if b.len() != g.len() {
+            return false;
+        }
+        let (folded, s) = match self.unfold(transcript, p, g.len()) {
+            Some(unfolded) => unfolded,
+            None => return false,
+        };
+
+        if inner(&s, b) != self.b {
+            return false;
+        }
+        let a_s = s.iter().map(|s| self.a * s).collect::<Vec<_>>();
+        folded == msm(&a_s, g) + q * (self.a * self.b)
+    }
+
+    /// Replays the rounds, returning `P + ∑ u² * L + u⁻² * R` and the
+    /// `s_i`, if the proof has the rounds of `n` entries.
+    fn unfold(
+        &self,
+        transcript: &mut Transcript,
+        p: &G1Projective,
+        n: usize,
+    ) -> Option<(G1Projective, Vec<Scalar>)> {
+        let rounds = self.l.len();
+        if self.r.len() != rounds || rounds >= usize::BITS as usize || n != 1 << rounds {
+            return None;
+        }
+
+        let mut challenges = Vec::with_capacity(rounds);
+        for (l, r) in self.l.iter().zip(&self.r) {
+            transcript.append_point(b"L", l);
+            transcript.append_point(b"R", r);
+            let u = transcript.challenge_scalar(b"u");
+            let u_inv = Option::<Scalar>::from(u.invert())?;
+            challenges.push((u, u_inv));
+        }
+
+        let folded = self
+            .l
+            .iter()
+            .zip(&self.r)
+            .zip(&challenges)
+            .fold(*p, |acc, ((l, r), (u, u_inv))| {
+                acc + l * u.square() + r * u_inv.square()
+            });
+
+        // The first round splits on the highest bit of the index.
+        let s = (0..n)
+            .map(|i| {
+                challenges
+                    .iter()
+                    .enumerate()
+                    .fold(Scalar::one(), |acc, (j, (u, u_inv))| {
+                        let hi = (i >> (rounds - 1 - j)) & 1 == 1;
+                        acc * if hi { u } else { u_inv }
+                    })
+            })
+            .collect();
+        Some((folded, s))
+    }
+}
+
+pub(crate) fn inner(a: &[Scalar], b: &[Scalar]) -> Scalar {
+    a.iter()
+        .zip(b)
+        .fold(Scalar::zero(), |acc, (a, b)| acc + a * b)
+}
+
+pub(crate) fn msm(scalars: &[Scalar], points: &[G1Projective]) -> G1Projective {
+    scalars
+        .iter()
+        .zip(points)
+        .fold(G1Projective::identity(), |acc, (s, p)| acc + p * s)
+}
+
+fn fold_scalars(lo: &[Scalar], hi: &[Scalar], x: Scalar, y: Scalar) -> Vec<Scalar> {
+    lo.iter().zip(hi).map(|(lo, hi)| lo * x + hi * y).collect()
+}
+
+fn fold_points(
+    lo: &[G1Projective],
+    hi: &[G1Projective],
+    x: Scalar,
+    y: Scalar,
+) -> Vec<G1Projective> {
+    lo.iter().zip(hi).map(|(lo, hi)| lo * x + hi * y).collect()
+}
@@ -0,0 +1,51 @@
+//! Sigma protocols over BLS12-381, made non-interactive with Fiat-Shamir:
+//! the verifier's random challenge is replaced by a hash of the statement
+//! and the prover's commitment, so a proof is a single message anyone can
+//! check.
+//!
+//! The challenges are drawn from a [`transcript::Transcript`] that also
+//! holds a context chosen by the caller, such as a session and the prover's
+//! index, binding the proof to where it is used.
+use bls12_381::Scalar;
+
+pub mod dleq;
+pub mod inner_product;
+pub mod pedersen;
+pub mod protocol;
+pub mod range;
+pub mod schnorr;
+pub mod transcript;
+
+/// The challenge and response of a proof, the commitment being recomputed
+/// from them by the verifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proof {
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+impl Proof {
+    pub const SIZE: usize = 64;
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..32].copy_from_slice(&self.challenge.to_bytes());
+        bytes[32..].copy_from_slice(&self.response.to_bytes());
+        bytes
+    }
+
+    /// `None` if `bytes` aren't two canonical scalars.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::SIZE {
+            return None;
+        }
+        let scalar = |bytes: &[u8]| {
+            let bytes: [u8; 32] = bytes.try_into().ok()?;
+            Option::<Scalar>::from(Scalar::from_bytes(&bytes))
+        };
+        Some(Proof {
+            challenge: scalar(&bytes[..32])?,
+            response: scalar(&bytes[32..])?,
+        })
+    }
+}
@@ -0,0 +1,333 @@
+//! Pedersen commitments over G1: the commitment to a value v with the
+//! blinding r is
+//!
+//! C = v * G + r * H
+//!
+//! where G and H are hashed to the curve, so that nobody knows `log_G(H)`.
+//! A random r hides v perfectly, and opening C to another value would give
+//! away `log_G(H)`.
+//!
+//! Commitments add up: `C1 + C2` commits to `v1 + v2` with `r1 + r2`, so
+//! sums of committed values can be checked without opening them.
+//!
+//! Two proofs come with them:
+//!
+//! - An opening proof that the committer knows a `(v, r)` behind C, the
+//!   two-base variant of Schnorr's proof: `R = k1 * G + k2 * H`, then
+//!   `z1 = k1 - c * v` and `z2 = k2 - c * r`.
+//! - An equality proof that C1 and C2 commit to the same value: then
+//!   `C1 - C2 = (r1 - r2) * H`, and knowing its logarithm in base H is
+//!   Schnorr's proof with H as the base.
+use crate::protocol::SigmaProtocol;
+use crate::transcript::Transcript;
+use crate::Proof;
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+use std::ops::{Add, Sub};
+use zeroize::{Zeroize, Zeroizing};
+
+const DST: &[u8] = b"ZK_LAB_PEDERSEN_XMD:SHA-256_SSWU_RO_";
+const OPENING_DOMAIN: &[u8] = b"zk-lab/sigma/pedersen-opening";
+const EQUALITY_DOMAIN: &[u8] = b"zk-lab/sigma/pedersen-equality";
+
+/// The two bases of the commitments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generators {
+    pub g: G1Projective,
+    pub h: G1Projective,
+}
+
+/// `v * G + r * H`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commitment(pub G1Projective);
+
+/// What a commitment opens to.
+#[derive(Clone, PartialEq)]
+pub struct Opening {
+    pub value: Scalar,
+    pub blinding: Scalar,
+}
+
+/// A proof of knowledge of the opening of a commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpeningProof {
+    pub challenge: Scalar,
+    pub value_response: Scalar,
+    pub blinding_response: Scalar,
+}
+
+/// The statement that the prover knows the opening of `commitment`, for
+/// composing with other statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Knowledge {
+    pub generators: Generators,
+    pub commitment: Commitment,
+}
+
+impl Generators {
+    /// The generators of `label`, independent of the ones of any other
+    /// label.
+    pub fn new(label: &[u8]) -> Self {
+        let hash = |base: &[u8]| {
+            let msg = [label, b"/", base].concat();
+            <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, DST)
+        };
+        Generators {
+            g: hash(b"G"),
+            h: hash(b"H"),
+        }
+    }
+
+    pub fn commit(&self, opening: &Opening) -> Commitment {
+        Commitment(self.g * opening.value + self.h * opening.blinding)
+    }
+
+    /// Commits to `value` with a random blinding.
+    pub fn commit_random<R: RngCore + CryptoRng>(
+        &self,
+        value: Scalar,
+        rng: &mut R,
+    ) -> (Commitment, Opening) {
+        let opening = Opening {
+            value,
+            blinding: Scalar::random(&mut *rng),
+        };
+        (self.commit(&opening), opening)
+    }
+
+    /// Whether `commitment` opens to `opening`.
+    pub fn open(&self, commitment: &Commitment, opening: &Opening) -> bool {
+        self.commit(opening) == *commitment
+    }
+
+    /// Proves knowledge of the opening of `commitment` without revealing it.
+    pub fn prove_opening<R: RngCore + CryptoRng>(
+        &self,
+        commitment: &Commitment,
+        opening: &Opening,
+        context: &[u8],
+        rng: &mut R,
+    ) -> OpeningProof {
+        let k1 = Zeroizing::new(Scalar::random(&mut *rng));
+        let k2 = Zeroizing::new(Scalar::random(&mut *rng));
+        let r = self.g * *k1 + self.h * *k2;
+        let challenge = self.opening_challenge(commitment, &r, context);
+
+        OpeningProof {
+            challenge,
+            value_response: *k1 - challenge * opening.value,
+            blinding_response: *k2 - challenge * opening.blinding,
+        }
+    }
+
+    pub fn verify_opening(
+        &self,
+        commitment: &Commitment,
+        context: &[u8],
+        proof: &OpeningProof,
+    ) -> bool {
+        // R = z1 * G + z2 * H + c * C
+        let r = self.g * proof.value_response
+            + self.h * proof.blinding_response
+            + commitment.0 * proof.challenge;
+        self.opening_challenge(commitment, &r, context) == proof.challenge
+    }
+
+    /// Proves that `a` and `b` commit to the same value, given both openings.
+    pub fn prove_equal<R: RngCore + CryptoRng>(
+        &self,
+        (a, a_opening): (&Commitment, &Opening),
+        (b, b_opening): (&Commitment, &Opening),
+        context: &[u8],
+        rng: &mut R,
+    ) -> Proof {
+        let blinding = Zeroizing::new(a_opening.blinding - b_opening.blinding);
+        let k = Zeroizing::new(Scalar::random(&mut *rng));
+        let challenge = self.equality_challenge(a, b, &(self.h * *k), context);
+
+        Proof {
+            challenge,
+            response: *k - challenge * *blinding,
+        }
+    }
+
+    pub fn verify_equal(
+        &self,
+        a: &Commitment,
+        b: &Commitment,
+        context: &[u8],
+        proof: &Proof,
+    ) -> bool {
+        // R = z * H + c * (A - B)
+        let r = self.h * proof.response + (a.0 - b.0) * proof.challenge;
+        self.equality_challenge(a, b, &r, context) == proof.challenge
+    }
+
+    fn opening_challenge(
+        &self,
+        commitment: &Commitment,
+        r: &G1Projective,
+        context: &[u8],
+    ) -> Scalar {
+        let mut transcript = self.transcript(OPENING_DOMAIN, context);
+        transcript.append_point(b"C", &commitment.0);
+        transcript.append_point(b"R", r);
+        transcript.challenge_scalar(b"c")
+    }
+
+    fn equality_challenge(
+        &self,
+        a: &Commitment,
+        b: &Commitment,
+        r: &G1Projective,
+        context: &[u8],
+    ) -> Scalar {
+        let mut transcript = self.transcript(EQUALITY_DOMAIN, context);
+        transcript.append_point(b"A", &a.0);
+        transcript.append_point(b"B", &b.0);
+        transcript.append_point(b"R", r);
+        transcript.challenge_scalar(b"c")
+    }
+
+    fn transcript(&self, domain: &'static [u8], context: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new(domain);
+        transcript.append_message(b"context", context);
+        transcript.append_point(b"G", &self.g);
+        transcript.append_point(b"H", &self.h);
+        transcript
+    }
+}
+
+impl SigmaProtocol for Knowledge {
+    type Witness = Opening;
+    type Commitment = G1Projective;
+    type State = (Zeroizing<Scalar>, Zeroizing<Scalar>);
+    type Response = (Scalar, Scalar);
+
+    fn commit<R: RngCore + CryptoRng>(
+        &self,
+        _: &Opening,
+        rng: &mut R,
+    ) -> (G1Projective, Self::State) {
+        let k1 = Zeroizing::new(Scalar::random(&mut *rng));
+        let k2 = Zeroizing::new(Scalar::random(&mut *rng));
+        let Generators { g, h } = self.generators;
+        (g * *k1 + h * *k2, (k1, k2))
+    }
+
+    fn respond(
+        &self,
+        opening: &Opening,
+        (k1, k2): Self::State,
+        challenge: &Scalar,
+    ) -> (Scalar, Scalar) {
+        (
+            *k1 - challenge * opening.value,
+            *k2 - challenge * opening.blinding,
+        )
+    }
+
+    fn verify(&self, r: &G1Projective, challenge: &Scalar, (z1, z2): &(Scalar, Scalar)) -> bool {
+        let Generators { g, h } = self.generators;
+        g * z1 + h * z2 + self.commitment.0 * challenge == *r
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        challenge: &Scalar,
+        rng: &mut R,
+    ) -> (G1Projective, (Scalar, Scalar)) {
+        let (z1, z2) = (Scalar::random(&mut *rng), Scalar::random(&mut *rng));
+        let Generators { g, h } = self.generators;
+        (g * z1 + h * z2 + self.commitment.0 * challenge, (z1, z2))
+    }
+
+    fn append_statement(&self, transcript: &mut Transcript) {
+        transcript.append_point(b"pedersen/G", &self.generators.g);
+        transcript.append_point(b"pedersen/H", &self.generators.h);
+        transcript.append_point(b"pedersen/C", &self.commitment.0);
+    }
+
+    fn append_commitment(&self, r: &G1Projective, transcript: &mut Transcript) {
+        transcript.append_point(b"pedersen/R", r);
+    }
+}
+
+impl Default for Generators {
+    fn default() -> Self {
+        Generators::new(b"zk-lab")
+    }
+}
+
+impl Commitment {
+    pub fn to_bytes(&self) -> [u8; 48] {
+        self.0.to_affine().to_compressed()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; 48] = bytes.try_into().ok()?;
+        Option::<G1Affine>::from(G1Affine::from_compressed(&bytes))
+            .map(|point| Commitment(point.into()))
+    }
+}
+
+impl Add for Commitment {
+    type Output = Commitment;
+
+    fn add(self, other: Commitment) -> Commitment {
+        Commitment(self.0 + other.0)
+    }
+}
+
+impl Sub for Commitment {
+    type Output = Commitment;
+
+    fn sub(self, other: Commitment) -> Commitment {
+        Commitment(self.0 - other.0)
+    }
+}
+
+impl<'a> Add for &'a Opening {
+    type Output = Opening;
+
+    fn add(self, other: &'a Opening) -> Opening {
+        Opening {
+            value: self.value + other.value,
+            blinding: self.blinding + other.blinding,
+        }
+    }
+}
+
+impl<'a> Sub for &'a Opening {
+    type Output = Opening;
+
+    fn sub(self, other: &'a Opening) -> Opening {
+        Opening {
+            value: self.value - other.value,
+            blinding: self.blinding - other.blinding,
+        }
+    }
+}
+
+impl Zeroize for Opening {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.blinding.zeroize();
+    }
+}
+
+impl Drop for Opening {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Opening(..)")
+    }
+}
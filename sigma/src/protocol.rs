@@ -0,0 +1,282 @@
+//! Sigma protocols as a trait, and their conjunction and disjunction.
+//!
+//! A sigma protocol is three moves: the prover commits to fresh randomness,
+//! the verifier answers with a random challenge and the prover responds.
+//! Every protocol here can also be simulated: given the challenge up front,
+//! a commitment and response that verify are made up without the witness.
+//!
+//! - [`And`] proves both statements, answering the same challenge in each.
+//! - [`Or`] proves either statement without telling which (Cramer, Damgård
+//!   and Schoenmakers). The prover simulates the branch it has no witness
+//!   for with a challenge of its choosing, and answers the real one with
+//!   whatever is left of the verifier's challenge: the two challenges have
+//!   to add up to it, so at most one of them was picked by the prover.
+//!
+//! [`prove`] and [`verify`] make any of them non-interactive, drawing the
+//! challenge from a transcript of the context, the statement and the
+//! commitment. Unlike the proofs of the other modules, the proof carries the
+//! commitment rather than the challenge, since a combination can't in
+//! general recompute the first from the second.
+use crate::transcript::Transcript;
+use bls12_381::Scalar;
+use group::ff::Field;
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+
+const DOMAIN: &[u8] = b"zk-lab/sigma/protocol";
+
+/// A statement proven with a sigma protocol. The challenges are scalars, so
+/// that the challenges of the branches of an [`Or`] can add up.
+pub trait SigmaProtocol {
+    /// What the statement is proven with.
+    type Witness;
+    /// The prover's first message.
+    type Commitment: Clone + fmt::Debug;
+    /// What the prover keeps between the commitment and the response.
+    type State;
+    type Response: Clone + fmt::Debug;
+
+    fn commit<R: RngCore + CryptoRng>(
+        &self,
+        witness: &Self::Witness,
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::State);
+
+    fn respond(
+        &self,
+        witness: &Self::Witness,
+        state: Self::State,
+        challenge: &Scalar,
+    ) -> Self::Response;
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        challenge: &Scalar,
+        response: &Self::Response,
+    ) -> bool;
+
+    /// A commitment and response that verify under `challenge`, made
+    /// without the witness.
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        challenge: &Scalar,
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::Response);
+
+    fn append_statement(&self, transcript: &mut Transcript);
+
+    fn append_commitment(&self, commitment: &Self::Commitment, transcript: &mut Transcript);
+}
+
+/// Both statements hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct And<A, B>(pub A, pub B);
+
+/// At least one of the statements holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Or<A, B>(pub A, pub B);
+
+/// The witness of one of the branches of an [`Or`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrWitness<A, B> {
+    Left(A),
+    Right(B),
+}
+
+/// The real branch's state, and the challenge and response of the
+/// simulated one.
+pub enum OrState<A: SigmaProtocol, B: SigmaProtocol> {
+    Left(A::State, Scalar, B::Response),
+    Right(B::State, Scalar, A::Response),
+}
+
+/// A non-interactive proof of the statement `P`.
+#[derive(Debug, Clone)]
+pub struct NizkProof<P: SigmaProtocol> {
+    pub commitment: P::Commitment,
+    pub response: P::Response,
+}
+
+impl<A: SigmaProtocol, B: SigmaProtocol> SigmaProtocol for And<A, B> {
+    type Witness = (A::Witness, B::Witness);
+    type Commitment = (A::Commitment, B::Commitment);
+    type State = (A::State, B::State);
+    type Response = (A::Response, B::Response);
+
+    fn commit<R: RngCore + CryptoRng>(
+        &self,
+        (a, b): &Self::Witness,
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::State) {
+        let (a_commitment, a_state) = self.0.commit(a, rng);
+        let (b_commitment, b_state) = self.1.commit(b, rng);
+        ((a_commitment, b_commitment), (a_state, b_state))
+    }
+
+    fn respond(
+        &self,
+        (a, b): &Self::Witness,
+        (a_state, b_state): Self::State,
+        challenge: &Scalar,
+    ) -> Self::Response {
+        (
+            self.0.respond(a, a_state, challenge),
+            self.1.respond(b, b_state, challenge),
+        )
+    }
+
+    fn verify(
+        &self,
+        (a_commitment, b_commitment): &Self::Commitment,
+        challenge: &Scalar,
+        (a_response, b_response): &Self::Response,
+    ) -> bool {
+        self.0.verify(a_commitment, challenge, a_response)
+            && self.1.verify(b_commitment, challenge, b_response)
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        challenge: &Scalar,
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::Response) {
+        let (a_commitment, a_response) = self.0.simulate(challenge, rng);
+        let (b_commitment, b_response) = self.1.simulate(challenge, rng);
+        ((a_commitment, b_commitment), (a_response, b_response))
+    }
+
+    fn append_statement(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"and", b"");
+        self.0.append_statement(transcript);
+        self.1.append_statement(transcript);
+    }
+
+    fn append_commitment(&self, (a, b): &Self::Commitment, transcript: &mut Transcript) {
+        self.0.append_commitment(a, transcript);
+        self.1.append_commitment(b, transcript);
+    }
+}
+
+impl<A: SigmaProtocol, B: SigmaProtocol> SigmaProtocol for Or<A, B> {
+    type Witness = OrWitness<A::Witness, B::Witness>;
+    type Commitment = (A::Commitment, B::Commitment);
+    type State = OrState<A, B>;
+    /// The challenge of the left branch, the right one's being what is left
+    /// of the verifier's, and the responses of both.
+    type Response = (Scalar, A::Response, B::Response);
+
+    fn commit<R: RngCore + CryptoRng>(
+        &self,
+        witness: &Self::Witness,
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::State) {
+        let simulated = Scalar::random(&mut *rng);
+        match witness {
+            OrWitness::Left(a) => {
+                let (a_commitment, a_state) = self.0.commit(a, rng);
+                let (b_commitment, b_response) = self.1.simulate(&simulated, rng);
+                (
+                    (a_commitment, b_commitment),
+                    OrState::Left(a_state, simulated, b_response),
+                )
+            }
+            OrWitness::Right(b) => {
+                let (b_commitment, b_state) = self.1.commit(b, rng);
+                let (a_commitment, a_response) = self.0.simulate(&simulated, rng);
+                (
+                    (a_commitment, b_commitment),
+                    OrState::Right(b_state, simulated, a_response),
+                )
+            }
+        }
+    }
+
+    fn respond(
+        &self,
+        witness: &Self::Witness,
+        state: Self::State,
+        challenge: &Scalar,
+    ) -> Self::Response {
+        match (witness, state) {
+            (OrWitness::Left(a), OrState::Left(a_state, b_challenge, b_response)) => {
+                let a_challenge = challenge - b_challenge;
+                let a_response = self.0.respond(a, a_state, &a_challenge);
+                (a_challenge, a_response, b_response)
+            }
+            (OrWitness::Right(b), OrState::Right(b_state, a_challenge, a_response)) => {
+                let b_response = self.1.respond(b, b_state, &(challenge - a_challenge));
+                (a_challenge, a_response, b_response)
+            }
+            _ => panic!("The state comes from committing with the same witness"),
+        }
+    }
+
+    fn verify(
+        &self,
+        (a_commitment, b_commitment): &Self::Commitment,
+        challenge: &Scalar,
+        (a_challenge, a_response, b_response): &Self::Response,
+    ) -> bool {
+        self.0.verify(a_commitment, a_challenge, a_response)
+            && self
+                .1
+                .verify(b_commitment, &(challenge - a_challenge), b_response)
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        challenge: &Scalar,
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::Response) {
+        let a_challenge = Scalar::random(&mut *rng);
+        let (a_commitment, a_response) = self.0.simulate(&a_challenge, rng);
+        let (b_commitment, b_response) = self.1.simulate(&(challenge - a_challenge), rng);
+        (
+            (a_commitment, b_commitment),
+            (a_challenge, a_response, b_response),
+        )
+    }
+
+    fn append_statement(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"or", b"");
+        self.0.append_statement(transcript);
+        self.1.append_statement(transcript);
+    }
+
+    fn append_commitment(&self, (a, b): &Self::Commitment, transcript: &mut Transcript) {
+        self.0.append_commitment(a, transcript);
+        self.1.append_commitment(b, transcript);
+    }
+}
+
+/// Proves `statement` with `witness`, under `context`.
+pub fn prove<P, R>(statement: &P, witness: &P::Witness, context: &[u8], rng: &mut R) -> NizkProof<P>
+where
+    P: SigmaProtocol,
+    R: RngCore + CryptoRng,
+{
+    let (commitment, state) = statement.commit(witness, rng);
+    let challenge = challenge(statement, &commitment, context);
+    NizkProof {
+        response: statement.respond(witness, state, &challenge),
+        commitment,
+    }
+}
+
+pub fn verify<P: SigmaProtocol>(statement: &P, context: &[u8], proof: &NizkProof<P>) -> bool {
+    let challenge = challenge(statement, &proof.commitment, context);
+    statement.verify(&proof.commitment, &challenge, &proof.response)
+}
+
+fn challenge<P: SigmaProtocol>(
+    statement: &P,
+    commitment: &P::Commitment,
+    context: &[u8],
+) -> Scalar {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_message(b"context", context);
+    statement.append_statement(&mut transcript);
+    statement.append_commitment(commitment, &mut transcript);
+    transcript.challenge_scalar(b"c")
+}
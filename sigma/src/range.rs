@@ -0,0 +1,341 @@
+//! Bulletproofs range proofs: a proof that the Pedersen commitments
+//! `V_j = v_j * G + γ_j * H` commit to values in `[0, 2^n)`, of size
+//! logarithmic in the number of bits, without a trusted setup.
+//!
+//! The bits `a_L` of the values, concatenated, and `a_R = a_L - 1` are
+//! committed to in A along with blinding vectors in S. The prover shows that
+//! `a_L ∘ a_R = 0`, that the bits of each value add up to it, and so on, by
+//! folding all of it with the challenges y and z into one inner product
+//! `t(x) = <l(x), r(x)>`, committing to its coefficients in T1 and T2 and,
+//! after the challenge x, proving `t(x) = <l(x), r(x)>` with an
+//! [`InnerProductProof`] instead of sending the vectors. The notation is the
+//! paper's, with the values of m commitments stacked in vectors of `n * m`
+//! entries for an aggregated proof.
+//!
+//! The values are committed to with the [`Generators`] of the `pedersen`
+//! module, and the vectors with [`VectorGenerators`].
+use crate::inner_product::{inner, msm, InnerProductProof, VectorGenerators};
+use crate::pedersen::{Commitment, Generators};
+use crate::transcript::Transcript;
+use bls12_381::{G1Projective, Scalar};
+use group::ff::Field;
+use rand_core::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroizing;
+
+const DOMAIN: &[u8] = b"zk-lab/sigma/range";
+
+/// The bases of the commitments to the values and to the vectors, enough
+/// for proofs of up to `capacity` bits in total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeGenerators {
+    pub pedersen: Generators,
+    pub vectors: VectorGenerators,
+}
+
+/// A proof that every value of a batch of commitments is in range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeProof {
+    pub a: G1Projective,
+    pub s: G1Projective,
+    pub t1: G1Projective,
+    pub t2: G1Projective,
+    pub t_hat: Scalar,
+    pub tau_x: Scalar,
+    pub mu: Scalar,
+    pub inner_product: InnerProductProof,
+}
+
+impl RangeGenerators {
+    pub fn new(pedersen: Generators, capacity: usize) -> Self {
+        RangeGenerators {
+            pedersen,
+            vectors: VectorGenerators::new(b"zk-lab/range", capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.vectors.capacity()
+    }
+
+    fn check(&self, bits: usize, count: usize) -> Result<usize, RangeError> {
+        if !bits.is_power_of_two() || bits > 64 {
+            return Err(RangeError::Bits(bits));
+        }
+        if !count.is_power_of_two() {
+            return Err(RangeError::Count(count));
+        }
+        let needed = bits * count;
+        if needed > self.capacity() {
+            return Err(RangeError::Capacity {
+                needed,
+                available: self.capacity(),
+            });
+        }
+        Ok(needed)
+    }
+}
+
+impl RangeProof {
+    /// Proves that every value is below `2^bits`, returning the commitments
+    /// to the values with their blindings along with the proof. There have
+    /// to be a power of two of them, `bits` being a power of two up to 64.
+    pub fn prove<R: RngCore + CryptoRng>(
+        generators: &RangeGenerators,
+        openings: &[(u64, Scalar)],
+        bits: usize,
+        context: &[u8],
+        rng: &mut R,
+    ) -> Result<(RangeProof, Vec<Commitment>), RangeError> {
+        let nm = generators.check(bits, openings.len())?;
+        if let Some(index) = openings
+            .iter()
+            .position(|(value, _)| bits < 64 && *value >> bits != 0)
+        {
+            return Err(RangeError::OutOfRange { index });
+        }
+        let Generators {
+            g: b,
+            h: b_blinding,
+        } = generators.pedersen;
+        let (g, h) = (&generators.vectors.g[..nm], &generators.vectors.h[..nm]);
+        let commitments = openings
+            .iter()
+            .map(|(value, blinding)| Commitment(b * Scalar::from(*value) + b_blinding * blinding))
+            .collect::<Vec<_>>();
+        let mut transcript = transcript(context, bits, &commitments);
+
+        let a_l = Zeroizing::new(
+            openings
+                .iter()
+                .flat_map(|(value, _)| (0..bits).map(move |i| Scalar::from((value >> i) & 1)))
+                .collect::<Vec<_>>(),
+        );
+        let a_r = Zeroizing::new(a_l.iter().map(|a| a - Scalar::one()).collect::<Vec<_>>());
+        let alpha = Zeroizing::new(Scalar::random(&mut *rng));
+        let a = b_blinding * *alpha + generators.vectors.commit(&a_l, &a_r);
+
+        let s_l = Zeroizing::new(random_vector(nm, rng));
+        let s_r = Zeroizing::new(random_vector(nm, rng));
+        let rho = Zeroizing::new(Scalar::random(&mut *rng));
+        let s = b_blinding * *rho + generators.vectors.commit(&s_l, &s_r);
+
+        transcript.append_point(b"A", &a);
+        transcript.append_point(b"S", &s);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        let y_powers = powers(y, nm);
+        let z_two = z_two(z, bits, openings.len());
+
+        // l(x) = (a_L - z) + s_L * x
+        // r(x) = y^nm ∘ (a_R + z + s_R * x) + z_two
+        let l0 = a_l.iter().map(|a| a - z).collect::<Vec<_>>();
+        let r0 = a_r
+            .iter()
+            .zip(&y_powers)
+            .zip(&z_two)
+            .map(|((a, y), zt)| y * (a + z) + zt)
+            .collect::<Vec<_>>();
+        let r1 = s_r
+            .iter()
+            .zip(&y_powers)
+            .map(|(s, y)| y * s)
+            .collect::<Vec<_>>();
+        let t1 = inner(&l0, &r1) + inner(&s_l, &r0);
+        let t2 = inner(&s_l, &r1);
+
+        let tau1 = Zeroizing::new(Scalar::random(&mut *rng));
+        let tau2 = Zeroizing::new(Scalar::random(&mut *rng));
+        let t1_point = b * t1 + b_blinding * *tau1;
+        let t2_point = b * t2 + b_blinding * *tau2;
+        transcript.append_point(b"T1", &t1_point);
+        transcript.append_point(b"T2", &t2_point);
+        let x = transcript.challenge_scalar(b"x");
+
+        let l = l0
+            .iter()
+            .zip(s_l.iter())
+            .map(|(l0, s)| l0 + s * x)
+            .collect::<Vec<_>>();
+        let r = r0
+            .iter()
+            .zip(&r1)
+            .map(|(r0, r1)| r0 + r1 * x)
+            .collect::<Vec<_>>();
+        let t_hat = inner(&l, &r);
+        let blindings = openings
+            .iter()
+            .zip(powers(z, openings.len() + 2).into_iter().skip(2))
+            .fold(Scalar::zero(), |acc, ((_, blinding), z)| acc + z * blinding);
+        let tau_x = *tau2 * x.square() + *tau1 * x + blindings;
+        let mu = *alpha + *rho * x;
+
+        transcript.append_scalar(b"t_hat", &t_hat);
+        transcript.append_scalar(b"tau_x", &tau_x);
+        transcript.append_scalar(b"mu", &mu);
+        let q = b * transcript.challenge_scalar(b"w");
+        let inner_product =
+            InnerProductProof::prove(&mut transcript, &q, g.to_vec(), h_prime(h, y), l, r);
+
+        let proof = RangeProof {
+            a,
+            s,
+            t1: t1_point,
+            t2: t2_point,
+            t_hat,
+            tau_x,
+            mu,
+            inner_product,
+        };
+        Ok((proof, commitments))
+    }
+
+    /// Checks that every commitment is to a value below `2^bits`.
+    pub fn verify(
+        &self,
+        generators: &RangeGenerators,
+        commitments: &[Commitment],
+        bits: usize,
+        context: &[u8],
+    ) -> Result<(), RangeError> {
+        let nm = generators.check(bits, commitments.len())?;
+        let Generators {
+            g: b,
+            h: b_blinding,
+        } = generators.pedersen;
+        let (g, h) = (&generators.vectors.g[..nm], &generators.vectors.h[..nm]);
+        let mut transcript = transcript(context, bits, commitments);
+
+        transcript.append_point(b"A", &self.a);
+        transcript.append_point(b"S", &self.s);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        transcript.append_point(b"T1", &self.t1);
+        transcript.append_point(b"T2", &self.t2);
+        let x = transcript.challenge_scalar(b"x");
+        transcript.append_scalar(b"t_hat", &self.t_hat);
+        transcript.append_scalar(b"tau_x", &self.tau_x);
+        transcript.append_scalar(b"mu", &self.mu);
+        let q = b * transcript.challenge_scalar(b"w");
+
+        // t_hat * G + tau_x * H == ∑ z^(j+2) * V_j + δ(y, z) * G + x * T1 + x² * T2
+        let y_powers = powers(y, nm);
+        let z_powers = powers(z, commitments.len() + 3);
+        let two_sum = powers(Scalar::from(2), bits)
+            .iter()
+            .fold(Scalar::zero(), |acc, p| acc + p);
+        let y_sum = y_powers.iter().fold(Scalar::zero(), |acc, p| acc + p);
+        let delta = (z - z.square()) * y_sum
+            - z_powers[3..]
+                .iter()
+                .fold(Scalar::zero(), |acc, z| acc + z * two_sum);
+        let values = commitments
+            .iter()
+            .zip(&z_powers[2..])
+            .fold(G1Projective::identity(), |acc, (v, z)| acc + v.0 * z);
+        if b * self.t_hat + b_blinding * self.tau_x
+            != values + b * delta + self.t1 * x + self.t2 * x.square()
+        {
+            return Err(RangeError::Invalid);
+        }
+
+        // P = A + x * S - z * <1, G> + <z * y^nm + z_two, H'> - mu * H + t_hat * Q,
+        // with H'_i = y^-i * H_i, so the H term is <z + z_two ∘ y^-nm, H>.
+        let y_inv = y.invert().unwrap_or_else(Scalar::zero);
+        let h_scalars = z_two(z, bits, commitments.len())
+            .iter()
+            .zip(powers(y_inv, nm))
+            .map(|(zt, y_inv)| z + zt * y_inv)
+            .collect::<Vec<_>>();
+        let p = self.a + self.s * x - g.iter().fold(G1Projective::identity(), |acc, g| acc + g) * z
+            + msm(&h_scalars, h)
+            - b_blinding * self.mu
+            + q * self.t_hat;
+        if !self
+            .inner_product
+            .verify(&mut transcript, &q, &p, g, &h_prime(h, y))
+        {
+            return Err(RangeError::Invalid);
+        }
+        Ok(())
+    }
+}
+
+fn transcript(context: &[u8], bits: usize, commitments: &[Commitment]) -> Transcript {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_message(b"context", context);
+    transcript.append_u64(b"bits", bits as u64);
+    transcript.append_u64(b"count", commitments.len() as u64);
+    for commitment in commitments {
+        transcript.append_point(b"V", &commitment.0);
+    }
+    transcript
+}
+
+/// `[1, x, x², ...]`
+fn powers(x: Scalar, n: usize) -> Vec<Scalar> {
+    std::iter::successors(Some(Scalar::one()), |p| Some(p * x))
+        .take(n)
+        .collect()
+}
+
+fn random_vector<R: RngCore + CryptoRng>(n: usize, rng: &mut R) -> Vec<Scalar> {
+    (0..n).map(|_| Scalar::random(&mut *rng)).collect()
+}
+
+/// `z^(j+2) * 2^i` at entry `j * bits + i`, what ties the bits of value j
+/// to its commitment.
+fn z_two(z: Scalar, bits: usize, count: usize) -> Vec<Scalar> {
+    let twos = powers(Scalar::from(2), bits);
+    powers(z, count + 2)[2..]
+        .iter()
+        .flat_map(|z| twos.iter().map(move |two| z * two))
+        .collect()
+}
+
+/// `y^-i * H_i`
+fn h_prime(h: &[G1Projective], y: Scalar) -> Vec<G1Projective> {
+    let y_inv = y.invert().unwrap_or_else(Scalar::zero);
+    h.iter()
+        .zip(powers(y_inv, h.len()))
+        .map(|(h, y)| h * y)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// The number of bits isn't a power of two up to 64.
+    Bits(usize),
+    /// The number of values isn't a power of two.
+    Count(usize),
+    /// There are fewer generators than bits in total.
+    Capacity {
+        needed: usize,
+        available: usize,
+    },
+    /// The value at `index` doesn't fit in the bits.
+    OutOfRange {
+        index: usize,
+    },
+    Invalid,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Bits(bits) => write!(f, "can't prove a range of {} bits", bits),
+            RangeError::Count(count) => {
+                write!(f, "can't prove the range of {} values at once", count)
+            }
+            RangeError::Capacity { needed, available } => write!(
+                f,
+                "the proof needs {} generators, there are {}",
+                needed, available
+            ),
+            RangeError::OutOfRange { index } => write!(f, "value {} is out of range", index),
+            RangeError::Invalid => write!(f, "invalid range proof"),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
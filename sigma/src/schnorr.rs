@@ -0,0 +1,107 @@
+//! Schnorr's proof of knowledge of a discrete logarithm, in G1 or G2.
+//!
+//! To show it knows x such that `P = x * G` without revealing it, the
+//! prover picks a random k and computes
+//!
+//! R = k * G
+//! c = H(context, G, P, R)
+//! z = k - c * x
+//!
+//! and publishes `(c, z)`. The verifier recomputes `R = z * G + c * P`,
+//! which only matches if z was computed with x, and checks that it hashes
+//! to c.
+//!
+//! A proof made under one context doesn't verify under another, so a
+//! dealer can't pass off another dealer's proof, and its commitment, as its
+//! own.
+use crate::protocol::SigmaProtocol;
+use crate::transcript::Transcript;
+use crate::Proof;
+use bls12_381::Scalar;
+use group::ff::Field;
+use group::{Group, GroupEncoding};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+const DOMAIN: &[u8] = b"zk-lab/sigma/schnorr";
+
+/// The statement that the prover knows the discrete logarithm of
+/// `public`, for composing with other statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schnorr<G> {
+    pub public: G,
+}
+
+/// Proves knowledge of `secret` for the point `secret * G` of the group `G`.
+pub fn prove<G, R>(secret: &Scalar, context: &[u8], rng: &mut R) -> Proof
+where
+    G: Group<Scalar = Scalar> + GroupEncoding,
+    R: RngCore + CryptoRng,
+{
+    let public = G::generator() * *secret;
+    let k = Zeroizing::new(Scalar::random(&mut *rng));
+    let commitment = G::generator() * *k;
+    let challenge = challenge(&public, &commitment, context);
+
+    Proof {
+        challenge,
+        response: *k - challenge * *secret,
+    }
+}
+
+/// Checks a proof of knowledge of the discrete logarithm of `public`, made
+/// under `context`.
+pub fn verify<G>(public: &G, context: &[u8], proof: &Proof) -> bool
+where
+    G: Group<Scalar = Scalar> + GroupEncoding,
+{
+    // R = k * G = z * G + c * x * G
+    let commitment = G::generator() * proof.response + *public * proof.challenge;
+    challenge(public, &commitment, context) == proof.challenge
+}
+
+fn challenge<G: Group + GroupEncoding>(public: &G, commitment: &G, context: &[u8]) -> Scalar {
+    let mut transcript = Transcript::new(DOMAIN);
+    transcript.append_message(b"context", context);
+    transcript.append_point(b"G", &G::generator());
+    transcript.append_point(b"P", public);
+    transcript.append_point(b"R", commitment);
+    transcript.challenge_scalar(b"c")
+}
+
+impl<G: Group<Scalar = Scalar> + GroupEncoding> SigmaProtocol for Schnorr<G> {
+    type Witness = Scalar;
+    type Commitment = G;
+    type State = Zeroizing<Scalar>;
+    type Response = Scalar;
+
+    fn commit<R: RngCore + CryptoRng>(&self, _: &Scalar, rng: &mut R) -> (G, Self::State) {
+        let k = Zeroizing::new(Scalar::random(&mut *rng));
+        (G::generator() * *k, k)
+    }
+
+    fn respond(&self, secret: &Scalar, k: Self::State, challenge: &Scalar) -> Scalar {
+        *k - challenge * secret
+    }
+
+    fn verify(&self, commitment: &G, challenge: &Scalar, response: &Scalar) -> bool {
+        G::generator() * response + self.public * challenge == *commitment
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(&self, challenge: &Scalar, rng: &mut R) -> (G, Scalar) {
+        let response = Scalar::random(&mut *rng);
+        (
+            G::generator() * response + self.public * challenge,
+            response,
+        )
+    }
+
+    fn append_statement(&self, transcript: &mut Transcript) {
+        transcript.append_point(b"schnorr/G", &G::generator());
+        transcript.append_point(b"schnorr/P", &self.public);
+    }
+
+    fn append_commitment(&self, commitment: &G, transcript: &mut Transcript) {
+        transcript.append_point(b"schnorr/R", commitment);
+    }
+}
@@ -0,0 +1,81 @@
+//! A Fiat-Shamir transcript in the style of merlin's: the prover and the
+//! verifier append the same labelled messages in the same order, and draw
+//! the same challenges from them.
+//!
+//! The transcript starts from a domain separator naming the protocol, and
+//! every message is framed by its label and length, so two different
+//! sequences of messages never hash the same. A challenge is absorbed back
+//! once drawn, so each one depends on everything before it, including the
+//! challenges drawn earlier.
+//!
+//! Where merlin runs STROBE, this is a running SHA-256 like the rest of the
+//! lab's hashing. Challenges are squeezed from copies of the state in
+//! counter mode.
+use bls12_381::Scalar;
+use group::GroupEncoding;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+#[derive(Clone)]
+pub struct Transcript {
+    hasher: Sha256,
+}
+
+impl Transcript {
+    /// Starts the transcript of the protocol `domain`.
+    pub fn new(domain: &'static [u8]) -> Self {
+        let mut transcript = Transcript {
+            hasher: Sha256::new(),
+        };
+        transcript.append_message(b"domain", domain);
+        transcript
+    }
+
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.hasher.update((label.len() as u64).to_be_bytes());
+        self.hasher.update(label);
+        self.hasher.update((message.len() as u64).to_be_bytes());
+        self.hasher.update(message);
+    }
+
+    pub fn append_u64(&mut self, label: &'static [u8], value: u64) {
+        self.append_message(label, &value.to_be_bytes());
+    }
+
+    pub fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
+        self.append_message(label, &scalar.to_bytes());
+    }
+
+    /// Appends the point in its compressed encoding.
+    pub fn append_point<G: GroupEncoding>(&mut self, label: &'static [u8], point: &G) {
+        self.append_message(label, point.to_bytes().as_ref());
+    }
+
+    /// Fills `dest` with a challenge bound to everything appended so far.
+    pub fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.append_u64(label, dest.len() as u64);
+        for (counter, chunk) in dest.chunks_mut(32).enumerate() {
+            let block = self
+                .hasher
+                .clone()
+                .chain(b"challenge")
+                .chain((counter as u64).to_be_bytes())
+                .finalize();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.append_message(b"challenge", dest);
+    }
+
+    /// A challenge scalar, from 64 bytes so that it is uniform.
+    pub fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        let mut wide = [0u8; 64];
+        self.challenge_bytes(label, &mut wide);
+        Scalar::from_bytes_wide(&wide)
+    }
+}
+
+impl fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transcript").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,71 @@
+use bls12_381::{G1Projective, G2Projective, Scalar};
+use bls_shamir::secret::SecretKey;
+use bls_shamir::signature::hash_to_g2;
+use bls_shamir::threshold;
+use group::ff::Field;
+use group::Curve;
+use rand::rngs::OsRng;
+use sigma::dleq::{prove, verify};
+
+#[test]
+fn proves_equal_logarithms_in_one_group() {
+    let secret = Scalar::random(&mut OsRng);
+    let g = G1Projective::generator();
+    let m = G1Projective::generator() * Scalar::random(&mut OsRng);
+
+    let proof = prove(&secret, &g, &m, b"context", &mut OsRng);
+    assert!(verify(
+        &g,
+        &(g * secret),
+        &m,
+        &(m * secret),
+        b"context",
+        &proof
+    ));
+}
+
+#[test]
+fn proves_a_partial_signature_consistent_with_its_public_share() {
+    let share = SecretKey::random(&mut OsRng);
+    let hm = hash_to_g2(b"message", b"ZK_LAB_TEST_DST").to_affine();
+    let partial = threshold::sign_share(3, &share, &hm, &mut OsRng);
+
+    let g = G1Projective::generator();
+    let m = G2Projective::from(hm);
+    let public_share = G1Projective::from(share.public_key());
+    let proof = prove(share.as_scalar(), &g, &m, b"share 3", &mut OsRng);
+    let point = G2Projective::from(partial.point);
+    assert!(verify(&g, &public_share, &m, &point, b"share 3", &proof));
+
+    // A forged partial signature doesn't pass with the honest proof.
+    let forged = point + G2Projective::generator();
+    assert!(!verify(&g, &public_share, &m, &forged, b"share 3", &proof));
+}
+
+#[test]
+fn rejects_different_logarithms() {
+    let secret = Scalar::random(&mut OsRng);
+    let g = G1Projective::generator();
+    let m = G2Projective::generator();
+
+    let proof = prove(&secret, &g, &m, b"context", &mut OsRng);
+    let b = m * (secret + Scalar::one());
+    assert!(!verify(&g, &(g * secret), &m, &b, b"context", &proof));
+}
+
+#[test]
+fn is_bound_to_its_context() {
+    let secret = Scalar::random(&mut OsRng);
+    let g = G1Projective::generator();
+    let m = G2Projective::generator();
+
+    let proof = prove(&secret, &g, &m, b"round 1", &mut OsRng);
+    assert!(!verify(
+        &g,
+        &(g * secret),
+        &m,
+        &(m * secret),
+        b"round 2",
+        &proof
+    ));
+}
@@ -0,0 +1,72 @@
+use bls12_381::{G1Projective, Scalar};
+use group::ff::Field;
+use rand::rngs::OsRng;
+use sigma::inner_product::{InnerProductProof, VectorGenerators};
+use sigma::transcript::Transcript;
+
+fn random(n: usize) -> Vec<Scalar> {
+    (0..n).map(|_| Scalar::random(&mut OsRng)).collect()
+}
+
+fn inner(a: &[Scalar], b: &[Scalar]) -> Scalar {
+    a.iter()
+        .zip(b)
+        .fold(Scalar::zero(), |acc, (a, b)| acc + a * b)
+}
+
+#[test]
+fn proves_the_inner_product_of_committed_vectors() {
+    let generators = VectorGenerators::new(b"test", 16);
+    let q = G1Projective::generator();
+    let (a, b) = (random(16), random(16));
+    let p = generators.commit(&a, &b) + q * inner(&a, &b);
+
+    let proof = InnerProductProof::prove(
+        &mut Transcript::new(b"test"),
+        &q,
+        generators.g.clone(),
+        generators.h.clone(),
+        a,
+        b,
+    );
+    assert_eq!(proof.l.len(), 4);
+    let (g, h) = (&generators.g, &generators.h);
+    assert!(proof.verify(&mut Transcript::new(b"test"), &q, &p, g, h));
+
+    let wrong = p + q;
+    assert!(!proof.verify(&mut Transcript::new(b"test"), &q, &wrong, g, h));
+    assert!(!proof.verify(&mut Transcript::new(b"other"), &q, &p, g, h));
+    assert!(!proof.verify(&mut Transcript::new(b"test"), &q, &p, &g[..8], &h[..8]));
+}
+
+#[test]
+fn opens_a_committed_polynomial_with_public_powers() {
+    // The coefficients are committed to, the evaluation at z is their inner
+    // product with the powers of z.
+    let generators = VectorGenerators::new(b"test", 8);
+    let q = G1Projective::generator();
+    let coefficients = random(8);
+    let z = Scalar::from(5);
+    let powers = (0..8u64)
+        .map(|i| z.pow_vartime(&[i, 0, 0, 0]))
+        .collect::<Vec<_>>();
+    let value = inner(&coefficients, &powers);
+    let commitment = generators.commit(&coefficients, &[]);
+    let p = commitment + q * value;
+
+    let proof = InnerProductProof::prove_public(
+        &mut Transcript::new(b"test"),
+        &q,
+        generators.g.clone(),
+        coefficients,
+        powers.clone(),
+    );
+    let g = &generators.g;
+    assert!(proof.verify_public(&mut Transcript::new(b"test"), &q, &p, g, &powers));
+
+    let wrong = commitment + q * (value + Scalar::one());
+    assert!(!proof.verify_public(&mut Transcript::new(b"test"), &q, &wrong, g, &powers));
+    let mut other_point = powers;
+    other_point[1] += Scalar::one();
+    assert!(!proof.verify_public(&mut Transcript::new(b"test"), &q, &p, g, &other_point));
+}
@@ -0,0 +1,82 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use rand::rngs::OsRng;
+use sigma::pedersen::{Commitment, Generators, Opening};
+
+#[test]
+fn opens_to_the_committed_value() {
+    let generators = Generators::default();
+    let (commitment, opening) = generators.commit_random(Scalar::from(42), &mut OsRng);
+    assert!(generators.open(&commitment, &opening));
+
+    let wrong = Opening {
+        value: Scalar::from(43),
+        blinding: opening.blinding,
+    };
+    assert!(!generators.open(&commitment, &wrong));
+}
+
+#[test]
+fn generators_are_independent() {
+    let generators = Generators::default();
+    assert_ne!(generators.g, generators.h);
+    assert_ne!(generators, Generators::new(b"other"));
+}
+
+#[test]
+fn hides_the_value() {
+    let generators = Generators::default();
+    let (a, _) = generators.commit_random(Scalar::from(1), &mut OsRng);
+    let (b, _) = generators.commit_random(Scalar::from(1), &mut OsRng);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn adds_up() {
+    let generators = Generators::default();
+    let (a, a_opening) = generators.commit_random(Scalar::from(20), &mut OsRng);
+    let (b, b_opening) = generators.commit_random(Scalar::from(22), &mut OsRng);
+
+    let sum = &a_opening + &b_opening;
+    assert_eq!(sum.value, Scalar::from(42));
+    assert!(generators.open(&(a + b), &sum));
+    assert!(generators.open(&(a - b), &(&a_opening - &b_opening)));
+}
+
+#[test]
+fn proves_knowledge_of_the_opening() {
+    let generators = Generators::default();
+    let (commitment, opening) = generators.commit_random(Scalar::random(&mut OsRng), &mut OsRng);
+    let proof = generators.prove_opening(&commitment, &opening, b"context", &mut OsRng);
+    assert!(generators.verify_opening(&commitment, b"context", &proof));
+
+    assert!(!generators.verify_opening(&commitment, b"other", &proof));
+    let (other, _) = generators.commit_random(Scalar::one(), &mut OsRng);
+    assert!(!generators.verify_opening(&other, b"context", &proof));
+}
+
+#[test]
+fn proves_equal_values() {
+    let generators = Generators::default();
+    let (a, a_opening) = generators.commit_random(Scalar::from(7), &mut OsRng);
+    let (b, b_opening) = generators.commit_random(Scalar::from(7), &mut OsRng);
+    let proof = generators.prove_equal((&a, &a_opening), (&b, &b_opening), b"context", &mut OsRng);
+    assert!(generators.verify_equal(&a, &b, b"context", &proof));
+    assert!(!generators.verify_equal(&b, &a, b"context", &proof));
+
+    // An honest-looking proof for different values doesn't verify.
+    let (c, c_opening) = generators.commit_random(Scalar::from(8), &mut OsRng);
+    let proof = generators.prove_equal((&a, &a_opening), (&c, &c_opening), b"context", &mut OsRng);
+    assert!(!generators.verify_equal(&a, &c, b"context", &proof));
+}
+
+#[test]
+fn roundtrips_through_bytes() {
+    let generators = Generators::default();
+    let (commitment, _) = generators.commit_random(Scalar::from(5), &mut OsRng);
+    assert_eq!(
+        Commitment::from_bytes(&commitment.to_bytes()),
+        Some(commitment)
+    );
+    assert_eq!(Commitment::from_bytes(&[0; 47]), None);
+}
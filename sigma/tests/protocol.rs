@@ -0,0 +1,92 @@
+use bls12_381::{G1Projective, G2Projective, Scalar};
+use group::ff::Field;
+use rand::rngs::OsRng;
+use sigma::dleq::Dleq;
+use sigma::pedersen::{Generators, Knowledge};
+use sigma::protocol::{prove, verify, And, Or, OrWitness, SigmaProtocol};
+use sigma::schnorr::Schnorr;
+
+fn schnorr(secret: &Scalar) -> Schnorr<G1Projective> {
+    Schnorr {
+        public: G1Projective::generator() * secret,
+    }
+}
+
+#[test]
+fn proves_the_share_or_the_trapdoor() {
+    let (share, trapdoor) = (Scalar::random(&mut OsRng), Scalar::random(&mut OsRng));
+    let statement = Or(schnorr(&share), schnorr(&trapdoor));
+
+    let proof = prove(&statement, &OrWitness::Left(share), b"context", &mut OsRng);
+    assert!(verify(&statement, b"context", &proof));
+    let proof = prove(
+        &statement,
+        &OrWitness::Right(trapdoor),
+        b"context",
+        &mut OsRng,
+    );
+    assert!(verify(&statement, b"context", &proof));
+    assert!(!verify(&statement, b"other", &proof));
+}
+
+#[test]
+fn or_needs_one_witness() {
+    let statement = Or(
+        schnorr(&Scalar::random(&mut OsRng)),
+        schnorr(&Scalar::random(&mut OsRng)),
+    );
+    let unrelated = Scalar::random(&mut OsRng);
+    let proof = prove(
+        &statement,
+        &OrWitness::Left(unrelated),
+        b"context",
+        &mut OsRng,
+    );
+    assert!(!verify(&statement, b"context", &proof));
+}
+
+#[test]
+fn and_needs_both_witnesses() {
+    let secret = Scalar::random(&mut OsRng);
+    let m = G2Projective::generator() * Scalar::random(&mut OsRng);
+    let dleq = Dleq {
+        g: G1Projective::generator(),
+        a: G1Projective::generator() * secret,
+        m,
+        b: m * secret,
+    };
+    let generators = Generators::default();
+    let (commitment, opening) = generators.commit_random(Scalar::from(9), &mut OsRng);
+    let knowledge = Knowledge {
+        generators,
+        commitment,
+    };
+
+    let statement = And(dleq, knowledge);
+    let proof = prove(
+        &statement,
+        &(secret, opening.clone()),
+        b"context",
+        &mut OsRng,
+    );
+    assert!(verify(&statement, b"context", &proof));
+
+    let wrong = (secret + Scalar::one(), opening);
+    let proof = prove(&statement, &wrong, b"context", &mut OsRng);
+    assert!(!verify(&statement, b"context", &proof));
+}
+
+#[test]
+fn simulations_verify_under_their_challenge() {
+    let statement = Or(
+        schnorr(&Scalar::random(&mut OsRng)),
+        And(
+            schnorr(&Scalar::random(&mut OsRng)),
+            schnorr(&Scalar::random(&mut OsRng)),
+        ),
+    );
+    let challenge = Scalar::random(&mut OsRng);
+    let (commitment, response) = statement.simulate(&challenge, &mut OsRng);
+    assert!(statement.verify(&commitment, &challenge, &response));
+    assert!(!statement.verify(&commitment, &(challenge + Scalar::one()), &response));
+}
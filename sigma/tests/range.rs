@@ -0,0 +1,122 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use rand::rngs::OsRng;
+use sigma::pedersen::{Commitment, Generators, Opening};
+use sigma::range::{RangeError, RangeGenerators, RangeProof};
+
+fn generators() -> RangeGenerators {
+    RangeGenerators::new(Generators::default(), 64)
+}
+
+fn openings(values: &[u64]) -> Vec<(u64, Scalar)> {
+    values
+        .iter()
+        .map(|value| (*value, Scalar::random(&mut OsRng)))
+        .collect()
+}
+
+#[test]
+fn proves_a_value_in_range() {
+    let generators = generators();
+    for value in [0, 1, 200, 255] {
+        let openings = openings(&[value]);
+        let (proof, commitments) =
+            RangeProof::prove(&generators, &openings, 8, b"context", &mut OsRng).unwrap();
+        proof
+            .verify(&generators, &commitments, 8, b"context")
+            .unwrap();
+
+        // The commitments are the usual Pedersen ones.
+        let opening = Opening {
+            value: Scalar::from(value),
+            blinding: openings[0].1,
+        };
+        assert!(generators.pedersen.open(&commitments[0], &opening));
+    }
+}
+
+#[test]
+fn proves_full_width_values() {
+    let generators = generators();
+    let (proof, commitments) =
+        RangeProof::prove(&generators, &openings(&[u64::MAX]), 64, b"", &mut OsRng).unwrap();
+    proof.verify(&generators, &commitments, 64, b"").unwrap();
+}
+
+#[test]
+fn aggregates_several_values() {
+    let generators = generators();
+    let openings = openings(&[3, 65535, 0, 4096]);
+    let (proof, commitments) =
+        RangeProof::prove(&generators, &openings, 16, b"context", &mut OsRng).unwrap();
+    assert_eq!(proof.inner_product.l.len(), 6);
+    proof
+        .verify(&generators, &commitments, 16, b"context")
+        .unwrap();
+
+    let mut swapped = commitments.clone();
+    swapped.swap(0, 1);
+    assert_eq!(
+        proof.verify(&generators, &swapped, 16, b"context"),
+        Err(RangeError::Invalid)
+    );
+}
+
+#[test]
+fn rejects_values_out_of_range() {
+    let generators = generators();
+    assert_eq!(
+        RangeProof::prove(&generators, &openings(&[1, 256]), 8, b"", &mut OsRng).unwrap_err(),
+        RangeError::OutOfRange { index: 1 }
+    );
+
+    // A proof of a commitment to something else doesn't carry over.
+    let (proof, _) = RangeProof::prove(&generators, &openings(&[7]), 8, b"", &mut OsRng).unwrap();
+    let (outside, _) = generators
+        .pedersen
+        .commit_random(Scalar::from(256), &mut OsRng);
+    assert_eq!(
+        proof.verify(&generators, &[outside], 8, b""),
+        Err(RangeError::Invalid)
+    );
+}
+
+#[test]
+fn is_bound_to_its_context_and_width() {
+    let generators = generators();
+    let (proof, commitments) =
+        RangeProof::prove(&generators, &openings(&[9]), 8, b"context", &mut OsRng).unwrap();
+    assert_eq!(
+        proof.verify(&generators, &commitments, 8, b"other"),
+        Err(RangeError::Invalid)
+    );
+    assert!(proof
+        .verify(&generators, &commitments, 16, b"context")
+        .is_err());
+}
+
+#[test]
+fn checks_the_shape() {
+    let generators = generators();
+    let commitments = vec![Commitment(generators.pedersen.g); 3];
+    assert!(matches!(
+        RangeProof::prove(&generators, &openings(&[1, 2, 3]), 8, b"", &mut OsRng),
+        Err(RangeError::Count(3))
+    ));
+    assert!(matches!(
+        RangeProof::prove(&generators, &openings(&[1]), 12, b"", &mut OsRng),
+        Err(RangeError::Bits(12))
+    ));
+    assert!(matches!(
+        RangeProof::prove(&generators, &openings(&[1, 2]), 64, b"", &mut OsRng),
+        Err(RangeError::Capacity {
+            needed: 128,
+            available: 64
+        })
+    ));
+    let (proof, _) = RangeProof::prove(&generators, &openings(&[1]), 8, b"", &mut OsRng).unwrap();
+    assert_eq!(
+        proof.verify(&generators, &commitments, 8, b""),
+        Err(RangeError::Count(3))
+    );
+}
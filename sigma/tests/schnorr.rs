@@ -0,0 +1,67 @@
+use bls12_381::{G1Projective, G2Projective, Scalar};
+use group::ff::Field;
+use rand::rngs::OsRng;
+use sigma::schnorr::{prove, verify};
+use sigma::Proof;
+
+#[test]
+fn proves_knowledge_in_both_groups() {
+    let secret = Scalar::random(&mut OsRng);
+
+    let proof = prove::<G1Projective, _>(&secret, b"context", &mut OsRng);
+    assert!(verify(
+        &(G1Projective::generator() * secret),
+        b"context",
+        &proof
+    ));
+
+    let proof = prove::<G2Projective, _>(&secret, b"context", &mut OsRng);
+    assert!(verify(
+        &(G2Projective::generator() * secret),
+        b"context",
+        &proof
+    ));
+}
+
+#[test]
+fn rejects_another_statement() {
+    let secret = Scalar::random(&mut OsRng);
+    let proof = prove::<G1Projective, _>(&secret, b"context", &mut OsRng);
+
+    let other = G1Projective::generator() * (secret + Scalar::one());
+    assert!(!verify(&other, b"context", &proof));
+}
+
+#[test]
+fn is_bound_to_its_context() {
+    let secret = Scalar::random(&mut OsRng);
+    let public = G1Projective::generator() * secret;
+    let proof = prove::<G1Projective, _>(&secret, b"session/1", &mut OsRng);
+
+    assert!(!verify(&public, b"session/2", &proof));
+    assert!(!verify(&public, b"session/", &proof));
+}
+
+#[test]
+fn rejects_tampered_proofs() {
+    let secret = Scalar::random(&mut OsRng);
+    let public = G2Projective::generator() * secret;
+    let proof = prove::<G2Projective, _>(&secret, b"context", &mut OsRng);
+
+    let mut tampered = proof;
+    tampered.response += Scalar::one();
+    assert!(!verify(&public, b"context", &tampered));
+    let mut tampered = proof;
+    tampered.challenge += Scalar::one();
+    assert!(!verify(&public, b"context", &tampered));
+}
+
+#[test]
+fn roundtrips_through_bytes() {
+    let secret = Scalar::random(&mut OsRng);
+    let proof = prove::<G1Projective, _>(&secret, b"context", &mut OsRng);
+    assert_eq!(Proof::from_bytes(&proof.to_bytes()), Some(proof));
+
+    assert_eq!(Proof::from_bytes(&[0xff; Proof::SIZE]), None);
+    assert_eq!(Proof::from_bytes(&proof.to_bytes()[1..]), None);
+}
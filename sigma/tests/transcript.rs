@@ -0,0 +1,55 @@
+use bls12_381::{G1Projective, Scalar};
+use sigma::transcript::Transcript;
+
+fn transcript() -> Transcript {
+    let mut transcript = Transcript::new(b"test");
+    transcript.append_message(b"message", b"hello");
+    transcript.append_u64(b"count", 3);
+    transcript.append_scalar(b"scalar", &Scalar::from(7));
+    transcript.append_point(b"point", &G1Projective::generator());
+    transcript
+}
+
+#[test]
+fn same_messages_give_the_same_challenges() {
+    let (mut prover, mut verifier) = (transcript(), transcript());
+    assert_eq!(
+        prover.challenge_scalar(b"c"),
+        verifier.challenge_scalar(b"c")
+    );
+
+    let (mut a, mut b) = ([0u8; 100], [0u8; 100]);
+    prover.challenge_bytes(b"bytes", &mut a);
+    verifier.challenge_bytes(b"bytes", &mut b);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn challenges_in_a_row_differ() {
+    let mut transcript = transcript();
+    let first = transcript.challenge_scalar(b"c");
+    let second = transcript.challenge_scalar(b"c");
+    assert_ne!(first, second);
+}
+
+#[test]
+fn is_separated_by_domain_and_labels() {
+    let challenge = |domain: &'static [u8], label: &'static [u8], message: &[u8]| {
+        let mut transcript = Transcript::new(domain);
+        transcript.append_message(label, message);
+        transcript.challenge_scalar(b"c")
+    };
+
+    let base = challenge(b"test", b"ab", b"c");
+    assert_ne!(base, challenge(b"other", b"ab", b"c"));
+    // The same bytes, framed differently.
+    assert_ne!(base, challenge(b"test", b"a", b"bc"));
+}
+
+#[test]
+fn depends_on_every_message() {
+    let mut base = transcript();
+    let mut longer = transcript();
+    longer.append_message(b"message", b"");
+    assert_ne!(base.challenge_scalar(b"c"), longer.challenge_scalar(b"c"));
+}
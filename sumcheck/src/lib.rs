@@ -0,0 +1,238 @@
+//! The sumcheck protocol, the building block of GKR and of the IOP-based
+//! proof systems after it.
+//!
+//! The prover claims that a polynomial g of v variables sums to H over the
+//! boolean hypercube. Here g is a product of multilinear extensions,
+//! `g = f̃_1 ⋯ f̃_d`, so of degree d in each variable, or a sum of such
+//! products. In round i, with the
+//! first variables already fixed to the challenges `r_1, ..., r_{i-1}`, the
+//! prover sends the univariate
+//!
+//! g_i(X) = ∑_{b ∈ {0, 1}^{v-i}} g(r_1, ..., r_{i-1}, X, b)
+//!
+//! as its values on `0, ..., d`. The verifier checks that
+//! `g_i(0) + g_i(1)` is the previous claim, H in the first round, and
+//! draws `r_i`, the new claim being `g_i(r_i)`. After the last round what
+//! is left is a claim on a single point: that `g(r) = g_v(r_v)`, which the
+//! verifier checks by evaluating g, or reduces to a claim for another
+//! protocol. A cheating prover survives a round with a probability of at
+//! most `d / |F|`.
+//!
+//! The challenges come from a [`Transcript`] the caller gives, bound to the
+//! statement, so that the protocol can be embedded in a larger one.
+use bls12_381::Scalar;
+use sigma::transcript::Transcript;
+use std::fmt;
+
+pub mod mle;
+
+pub use mle::MultilinearExtension;
+
+/// The round polynomials, each as its values on `0, ..., d`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub rounds: Vec<Vec<Scalar>>,
+}
+
+/// What the protocol reduces the sum to: that g takes `value` at `point`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubClaim {
+    pub point: Vec<Scalar>,
+    pub value: Scalar,
+}
+
+/// `∑_b ∏_j f̃_j(b)`, the sum a proof is made for.
+pub fn sum(factors: &[MultilinearExtension]) -> Scalar {
+    let size = factors.first().map_or(0, |f| f.evaluations().len());
+    (0..size)
+        .map(|b| {
+            factors
+                .iter()
+                .map(|f| f.evaluations()[b])
+                .fold(Scalar::one(), |acc, v| acc * v)
+        })
+        .sum()
+}
+
+/// Proves the sum of the product of the factors over the hypercube, returns
+/// the proof and the claim it reduces to, with which the prover can go on.
+pub fn prove(
+    factors: &[MultilinearExtension],
+    transcript: &mut Transcript,
+) -> Result<(Proof, SubClaim), SumcheckError> {
+    prove_sum(&[factors.to_vec()], transcript)
+}
+
+/// [`prove`] for a sum of products, `g = ∑_k ∏_j f̃_kj`, of the degree of
+/// the largest product.
+pub fn prove_sum(
+    terms: &[Vec<MultilinearExtension>],
+    transcript: &mut Transcript,
+) -> Result<(Proof, SubClaim), SumcheckError> {
+    let num_vars = shape(terms)?;
+    let degree = terms.iter().map(Vec::len).max().unwrap_or(0);
+    let total: Scalar = terms.iter().map(|factors| sum(factors)).sum();
+    statement(transcript, num_vars, degree, &total);
+
+    let mut terms = terms.to_vec();
+    let mut rounds = Vec::with_capacity(num_vars);
+    let mut point = Vec::with_capacity(num_vars);
+    for _ in 0..num_vars {
+        let round: Vec<Scalar> = (0..=degree as u64)
+            .map(Scalar::from)
+            .map(|t| terms.iter().map(|factors| partial_sum(factors, t)).sum())
+            .collect();
+        let r = challenge(transcript, &round);
+        for factors in &mut terms {
+            for f in factors.iter_mut() {
+                *f = f.fix_first(r);
+            }
+        }
+        rounds.push(round);
+        point.push(r);
+    }
+
+    let value = terms
+        .iter()
+        .map(|factors| {
+            factors
+                .iter()
+                .map(|f| f.evaluations()[0])
+                .fold(Scalar::one(), |acc, v| acc * v)
+        })
+        .sum();
+    Ok((Proof { rounds }, SubClaim { point, value }))
+}
+
+/// `∑_b ∏_j f̃_j(t, b)`
+fn partial_sum(factors: &[MultilinearExtension], t: Scalar) -> Scalar {
+    let half = factors[0].evaluations().len() / 2;
+    (0..half)
+        .map(|b| {
+            factors
+                .iter()
+                .map(|f| {
+                    let (low, high) = (f.evaluations()[b], f.evaluations()[b + half]);
+                    low + t * (high - low)
+                })
+                .fold(Scalar::one(), |acc, v| acc * v)
+        })
+        .sum()
+}
+
+/// Checks the rounds of a proof that a polynomial of `num_vars` variables
+/// and of degree `degree` in each sums to `sum`, returns the claim left for
+/// the caller to check.
+pub fn verify(
+    num_vars: usize,
+    degree: usize,
+    sum: Scalar,
+    proof: &Proof,
+    transcript: &mut Transcript,
+) -> Result<SubClaim, SumcheckError> {
+    if proof.rounds.len() != num_vars {
+        return Err(SumcheckError::Rounds {
+            expected: num_vars,
+            got: proof.rounds.len(),
+        });
+    }
+    statement(transcript, num_vars, degree, &sum);
+
+    let mut claim = sum;
+    let mut point = Vec::with_capacity(num_vars);
+    for (i, round) in proof.rounds.iter().enumerate() {
+        if round.len() != degree + 1 {
+            return Err(SumcheckError::Degree { round: i });
+        }
+        if round[0] + round[1] != claim {
+            return Err(SumcheckError::Sum { round: i });
+        }
+        let r = challenge(transcript, round);
+        claim = interpolate(round, r);
+        point.push(r);
+    }
+    Ok(SubClaim {
+        point,
+        value: claim,
+    })
+}
+
+/// The number of variables of the factors, which must all have the same.
+fn shape(terms: &[Vec<MultilinearExtension>]) -> Result<usize, SumcheckError> {
+    let num_vars = terms
+        .first()
+        .and_then(|factors| factors.first())
+        .ok_or(SumcheckError::Shape)?
+        .num_vars();
+    if terms.iter().any(|factors| factors.is_empty())
+        || terms.iter().flatten().any(|f| f.num_vars() != num_vars)
+    {
+        return Err(SumcheckError::Shape);
+    }
+    Ok(num_vars)
+}
+
+fn statement(transcript: &mut Transcript, num_vars: usize, degree: usize, sum: &Scalar) {
+    transcript.append_u64(b"sumcheck variables", num_vars as u64);
+    transcript.append_u64(b"sumcheck degree", degree as u64);
+    transcript.append_scalar(b"sumcheck sum", sum);
+}
+
+fn challenge(transcript: &mut Transcript, round: &[Scalar]) -> Scalar {
+    for value in round {
+        transcript.append_scalar(b"sumcheck round", value);
+    }
+    transcript.challenge_scalar(b"sumcheck challenge")
+}
+
+/// The value at `x` of the polynomial of degree below `values.len()` that
+/// takes `values[i]` at i, the form the round polynomials are sent in.
+pub fn interpolate(values: &[Scalar], x: Scalar) -> Scalar {
+    let points: Vec<Scalar> = (0..values.len() as u64).map(Scalar::from).collect();
+    values
+        .iter()
+        .zip(&points)
+        .map(|(value, xi)| {
+            let (numerator, denominator) = points.iter().filter(|xj| *xj != xi).fold(
+                (Scalar::one(), Scalar::one()),
+                |(numerator, denominator), xj| (numerator * (x - xj), denominator * (xi - xj)),
+            );
+            value * numerator * denominator.invert().unwrap()
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SumcheckError {
+    /// A product has no factors, or they don't all have the same variables.
+    Shape,
+    /// There isn't a round per variable.
+    Rounds { expected: usize, got: usize },
+    /// The polynomial of the round isn't of the degree of the statement.
+    Degree { round: usize },
+    /// The polynomial of the round doesn't sum to the claim.
+    Sum { round: usize },
+}
+
+impl fmt::Display for SumcheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SumcheckError::Shape => write!(f, "the factors don't have the same variables"),
+            SumcheckError::Rounds { expected, got } => {
+                write!(f, "expected {} rounds, got {}", expected, got)
+            }
+            SumcheckError::Degree { round } => {
+                write!(f, "the polynomial of round {} has the wrong degree", round)
+            }
+            SumcheckError::Sum { round } => {
+                write!(
+                    f,
+                    "the polynomial of round {} doesn't sum to the claim",
+                    round
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SumcheckError {}
@@ -0,0 +1,81 @@
+//! Multilinear extensions: a function on the boolean hypercube `{0, 1}^v`
+//! extends to a unique polynomial of degree at most one in each variable,
+//!
+//! f̃(x) = ∑_b f(b) ∏_i (b_i x_i + (1 - b_i)(1 - x_i))
+//!
+//! kept as its table of values on the hypercube. The first variable is the
+//! most significant bit of the index in the table.
+use bls12_381::Scalar;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultilinearExtension {
+    evaluations: Vec<Scalar>,
+    num_vars: usize,
+}
+
+impl MultilinearExtension {
+    /// The extension of the values, `None` unless there is a power of two
+    /// of them.
+    pub fn new(evaluations: Vec<Scalar>) -> Option<Self> {
+        if !evaluations.len().is_power_of_two() {
+            return None;
+        }
+        let num_vars = evaluations.len().trailing_zeros() as usize;
+        Some(MultilinearExtension {
+            evaluations,
+            num_vars,
+        })
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// The values on the hypercube.
+    pub fn evaluations(&self) -> &[Scalar] {
+        &self.evaluations
+    }
+
+    /// The sum of the values on the hypercube.
+    pub fn sum(&self) -> Scalar {
+        self.evaluations.iter().sum()
+    }
+
+    /// `f̃(r, x_2, ..., x_v)`, an extension of one variable less.
+    ///
+    /// # Panics
+    ///
+    /// If there are no variables left.
+    pub fn fix_first(&self, r: Scalar) -> Self {
+        assert!(self.num_vars > 0, "no variables left to fix");
+        let (low, high) = self.evaluations.split_at(self.evaluations.len() / 2);
+        let evaluations = low
+            .iter()
+            .zip(high)
+            .map(|(low, high)| low + r * (high - low))
+            .collect();
+        MultilinearExtension {
+            evaluations,
+            num_vars: self.num_vars - 1,
+        }
+    }
+
+    /// `f̃(point)`, in linear time by fixing the variables one by one.
+    ///
+    /// # Panics
+    ///
+    /// If the point doesn't have a coordinate for each variable.
+    pub fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        assert_eq!(
+            point.len(),
+            self.num_vars,
+            "a point of {} coordinates for {} variables",
+            point.len(),
+            self.num_vars
+        );
+        point
+            .iter()
+            .fold(self.clone(), |f, r| f.fix_first(*r))
+            .evaluations[0]
+    }
+}
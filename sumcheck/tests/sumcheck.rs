@@ -0,0 +1,115 @@
+use bls12_381::Scalar;
+use group::ff::Field;
+use rand::thread_rng;
+use sigma::transcript::Transcript;
+use sumcheck::{prove, sum, verify, MultilinearExtension, SumcheckError};
+
+const DOMAIN: &[u8] = b"sumcheck test";
+
+fn random(num_vars: usize) -> MultilinearExtension {
+    let mut rng = thread_rng();
+    MultilinearExtension::new(
+        (0..1 << num_vars)
+            .map(|_| Scalar::random(&mut rng))
+            .collect(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn extends_the_table() {
+    let f = random(3);
+    for b in 0..8 {
+        let point: Vec<Scalar> = (0..3)
+            .map(|i| Scalar::from(((b >> (2 - i)) & 1) as u64))
+            .collect();
+        assert_eq!(f.evaluate(&point), f.evaluations()[b]);
+    }
+    assert_eq!(MultilinearExtension::new(vec![Scalar::one(); 3]), None);
+
+    // Linear in each variable.
+    let (x, y, z) = (Scalar::from(3), Scalar::from(5), Scalar::from(9));
+    let at = |t: Scalar| f.evaluate(&[x, t, z]);
+    assert_eq!(
+        at(y),
+        at(Scalar::zero()) + y * (at(Scalar::one()) - at(Scalar::zero()))
+    );
+}
+
+#[test]
+fn proves_a_sum_of_products() {
+    let factors = [random(4), random(4), random(4)];
+    let (proof, claim) = prove(&factors, &mut Transcript::new(DOMAIN)).unwrap();
+    assert_eq!(proof.rounds.len(), 4);
+
+    let verified = verify(4, 3, sum(&factors), &proof, &mut Transcript::new(DOMAIN)).unwrap();
+    assert_eq!(verified, claim);
+    let product: Scalar = factors
+        .iter()
+        .map(|f| f.evaluate(&verified.point))
+        .fold(Scalar::one(), |acc, v| acc * v);
+    assert_eq!(verified.value, product);
+}
+
+#[test]
+fn proves_a_single_extension() {
+    let f = random(5);
+    let (proof, _) = prove(std::slice::from_ref(&f), &mut Transcript::new(DOMAIN)).unwrap();
+    let claim = verify(5, 1, f.sum(), &proof, &mut Transcript::new(DOMAIN)).unwrap();
+    assert_eq!(f.evaluate(&claim.point), claim.value);
+}
+
+#[test]
+fn rejects_a_wrong_sum() {
+    let factors = [random(3), random(3)];
+    let (proof, _) = prove(&factors, &mut Transcript::new(DOMAIN)).unwrap();
+    let wrong = sum(&factors) + Scalar::one();
+    assert_eq!(
+        verify(3, 2, wrong, &proof, &mut Transcript::new(DOMAIN)),
+        Err(SumcheckError::Sum { round: 0 })
+    );
+
+    let mut tampered = proof.clone();
+    tampered.rounds[1][0] += Scalar::one();
+    assert_eq!(
+        verify(3, 2, sum(&factors), &tampered, &mut Transcript::new(DOMAIN)),
+        Err(SumcheckError::Sum { round: 1 })
+    );
+
+    // A consistent round that isn't the polynomial only moves the error to
+    // the final claim.
+    let mut tampered = proof;
+    tampered.rounds[2][0] += Scalar::one();
+    tampered.rounds[2][1] -= Scalar::one();
+    let claim = verify(3, 2, sum(&factors), &tampered, &mut Transcript::new(DOMAIN)).unwrap();
+    let product = factors
+        .iter()
+        .map(|f| f.evaluate(&claim.point))
+        .fold(Scalar::one(), |acc, v| acc * v);
+    assert_ne!(claim.value, product);
+}
+
+#[test]
+fn rejects_the_wrong_shape() {
+    let factors = [random(3), random(3)];
+    let (proof, _) = prove(&factors, &mut Transcript::new(DOMAIN)).unwrap();
+    assert_eq!(
+        verify(4, 2, sum(&factors), &proof, &mut Transcript::new(DOMAIN)),
+        Err(SumcheckError::Rounds {
+            expected: 4,
+            got: 3
+        })
+    );
+    assert_eq!(
+        verify(3, 3, sum(&factors), &proof, &mut Transcript::new(DOMAIN)),
+        Err(SumcheckError::Degree { round: 0 })
+    );
+    assert_eq!(
+        prove(&[random(3), random(2)], &mut Transcript::new(DOMAIN)).map(|(proof, _)| proof),
+        Err(SumcheckError::Shape)
+    );
+    assert_eq!(
+        prove(&[], &mut Transcript::new(DOMAIN)).map(|(proof, _)| proof),
+        Err(SumcheckError::Shape)
+    );
+}